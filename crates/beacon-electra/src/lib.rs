@@ -5,7 +5,7 @@ use alloc::vec::Vec;
 #[cfg(feature = "no-zkvm")]
 use consensus_types::{BeaconBlockHeader, SignedBeaconBlock};
 #[cfg(feature = "no-zkvm")]
-use consensus_types::{MainnetEthSpec, SignedBeaconBlockElectra};
+use consensus_types::{EthSpec, MainnetEthSpec, SignedBeaconBlockElectra};
 use helpers::merkleize_container;
 use itertools::Itertools;
 #[cfg(feature = "no-zkvm")]
@@ -98,7 +98,7 @@ pub async fn get_beacon_block_header(slot: u64, url: &str) -> BeaconBlockHeader
 ///
 /// # Errors
 /// Returns an error if the request fails or the response cannot be parsed
-pub async fn get_electra_block(slot: u64, url: &str) -> SignedBeaconBlockElectra<MainnetEthSpec> {
+pub async fn get_electra_block<E: EthSpec>(slot: u64, url: &str) -> SignedBeaconBlockElectra<E> {
     use alloc::format;
 
     let endpoint = format!("{}/eth/v2/beacon/blocks/{}", url, slot);
@@ -113,12 +113,23 @@ pub async fn get_electra_block(slot: u64, url: &str) -> SignedBeaconBlockElectra
 
     let json: serde_json::Value = resp.json().await.expect("Invalid JSON");
     let block_data = json["data"].clone();
-    let block: SignedBeaconBlock<MainnetEthSpec> =
+    let block: SignedBeaconBlock<E> =
         serde_json::from_value(block_data).expect("Deserialization failed");
     let electra_block = block.as_electra().unwrap();
     electra_block.clone()
 }
 
+#[cfg(feature = "no-zkvm")]
+/// Fetches an Electra block of a mainnet preset beacon chain (Ethereum mainnet and testnets)
+/// and computes the merkle roots of its body
+///
+/// # Arguments
+/// * `slot` - The slot number of the block to fetch
+/// * `url` - The URL of the beacon node to query
+pub async fn get_electra_block_body_roots(slot: u64, url: &str) -> ElectraBlockBodyRoots {
+    extract_electra_block_body(get_electra_block::<MainnetEthSpec>(slot, url).await)
+}
+
 #[cfg(feature = "no-zkvm")]
 /// Extracts and computes the merkle roots of an Electra block body
 ///
@@ -130,8 +141,8 @@ pub async fn get_electra_block(slot: u64, url: &str) -> SignedBeaconBlockElectra
 ///
 /// # Returns
 /// A struct containing all the computed merkle roots for the block body
-pub fn extract_electra_block_body(
-    electra_block: SignedBeaconBlockElectra<MainnetEthSpec>,
+pub fn extract_electra_block_body<E: EthSpec>(
+    electra_block: SignedBeaconBlockElectra<E>,
 ) -> ElectraBlockBodyRoots {
     let electra_block_body = electra_block.message.body;
     let execution_payload = electra_block_body
@@ -0,0 +1,148 @@
+// ABI encoding of the wrapper outputs, the public values of the wrapper circuits generated with
+// `WRAPPER_ENCODING=abi`, which EVM contracts decode with `abi.decode` instead of a borsh decoder.
+// The outputs are encoded as `abi.encode(uint8 version, <static fields>, string chainId)`.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::WrapperError;
+
+/// Size of an ABI word
+pub const WORD: usize = 32;
+
+/// Encodes `abi.encode(uint8 version, <fields>, string chainId)`, `fields` being the words of
+/// the static fields of the outputs
+pub fn encode(version: u8, fields: &[[u8; WORD]], chain_id: &str) -> Vec<u8> {
+    let head_words = fields.len() + 2;
+    let chain_id = chain_id.as_bytes();
+    let mut encoded =
+        Vec::with_capacity((head_words + 1) * WORD + chain_id.len().next_multiple_of(WORD));
+    encoded.extend_from_slice(&uint(version as u64));
+    for field in fields {
+        encoded.extend_from_slice(field);
+    }
+    // offset and length of the dynamic `string` chain id
+    encoded.extend_from_slice(&uint((head_words * WORD) as u64));
+    encoded.extend_from_slice(&uint(chain_id.len() as u64));
+    encoded.extend_from_slice(chain_id);
+    encoded.resize(encoded.len().next_multiple_of(WORD), 0);
+    encoded
+}
+
+/// ABI encoded outputs of `fields` static fields, decoded by [`decode`]
+pub struct Decoded<'a> {
+    fields: &'a [u8],
+    pub chain_id: String,
+}
+
+impl Decoded<'_> {
+    /// The word of the `i`-th static field
+    pub fn word(&self, i: usize) -> Result<[u8; WORD], WrapperError> {
+        self.fields
+            .get(i * WORD..(i + 1) * WORD)
+            .map(|word| word.try_into().unwrap())
+            .ok_or(WrapperError::Malformed)
+    }
+
+    /// The `i`-th static field, a `uint` of at most 64 bits
+    pub fn uint(&self, i: usize) -> Result<u64, WrapperError> {
+        to_uint(&self.word(i)?)
+    }
+
+    /// The `i`-th static field, a `bool`
+    pub fn bool(&self, i: usize) -> Result<bool, WrapperError> {
+        match self.uint(i)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(WrapperError::Malformed),
+        }
+    }
+}
+
+/// Decodes outputs of format `version` with `fields` static fields, see [`encode`]. Any other
+/// encoding of the same outputs, e.g. with dirty padding, is refused
+pub fn decode(
+    version: u8,
+    fields: usize,
+    public_values: &[u8],
+) -> Result<Decoded<'_>, WrapperError> {
+    let head_words = fields + 2;
+    let word = |i: usize| {
+        public_values
+            .get(i * WORD..(i + 1) * WORD)
+            .ok_or(WrapperError::Malformed)
+    };
+    let found = to_uint(word(0)?)?;
+    if found != version as u64 {
+        return Err(WrapperError::UnsupportedVersion(
+            found.min(u8::MAX as u64) as u8
+        ));
+    }
+    if to_uint(word(head_words - 1)?)? != (head_words * WORD) as u64 {
+        return Err(WrapperError::Malformed);
+    }
+    let start = (head_words + 1) * WORD;
+    let end = start
+        .checked_add(to_uint(word(head_words)?)? as usize)
+        .ok_or(WrapperError::Malformed)?;
+    let chain_id = public_values
+        .get(start..end)
+        .ok_or(WrapperError::Malformed)?;
+    let padding = &public_values[end..];
+    if padding.len() != end.next_multiple_of(WORD) - end || padding.iter().any(|byte| *byte != 0) {
+        return Err(WrapperError::Malformed);
+    }
+    Ok(Decoded {
+        fields: &public_values[WORD..(head_words - 1) * WORD],
+        chain_id: String::from_utf8(chain_id.to_vec()).map_err(|_| WrapperError::Malformed)?,
+    })
+}
+
+/// A `uint` ABI word
+pub fn uint(value: u64) -> [u8; WORD] {
+    let mut word = [0; WORD];
+    word[WORD - 8..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// The value of a `uint` ABI word of at most 64 bits
+fn to_uint(word: &[u8]) -> Result<u64, WrapperError> {
+    if word[..WORD - 8].iter().any(|byte| *byte != 0) {
+        return Err(WrapperError::Malformed);
+    }
+    Ok(u64::from_be_bytes(word[WORD - 8..].try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abi_outputs() {
+        let fields = [uint(900001), [1; 32], uint(900000), [2; 32]];
+        let public_values = encode(1, &fields, "eip155:1");
+        // Six head words, the length of the chain id and its padded word
+        assert_eq!(public_values.len(), 8 * WORD);
+        assert_eq!(public_values[WORD - 1], 1);
+        assert_eq!(public_values[6 * WORD - 1], 0xc0);
+
+        let decoded = decode(1, fields.len(), &public_values).unwrap();
+        assert_eq!(decoded.uint(0), Ok(900001));
+        assert_eq!(decoded.word(1), Ok([1; 32]));
+        assert_eq!(decoded.uint(2), Ok(900000));
+        assert_eq!(decoded.word(3), Ok([2; 32]));
+        assert_eq!(decoded.word(4), Err(WrapperError::Malformed));
+        assert_eq!(decoded.chain_id, "eip155:1");
+        assert_eq!(decoded.bool(1), Err(WrapperError::Malformed));
+        assert!(matches!(
+            decode(2, fields.len(), &public_values),
+            Err(WrapperError::UnsupportedVersion(1))
+        ));
+
+        // The offset of the chain id tells the number of static fields
+        assert!(decode(1, 5, &public_values).is_err());
+        let mut dirty = public_values.clone();
+        *dirty.last_mut().unwrap() = 1;
+        assert!(decode(1, fields.len(), &dirty).is_err());
+        assert!(decode(1, fields.len(), &public_values[..7 * WORD]).is_err());
+    }
+}
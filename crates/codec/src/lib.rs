@@ -0,0 +1,237 @@
+// The encodings of the public values committed by the recursion and wrapper circuits, borsh
+// after a format version byte, and ABI for the wrapper outputs, shared by the recursion-types
+// crates of every backend, which define them with the format version of their outputs through
+// [`outputs_codec!`].
+
+#![no_std]
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub mod abi;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec::Vec;
+    pub use borsh::{BorshDeserialize, BorshSerialize};
+}
+
+/// Defines the `OUTPUTS_VERSION` of a recursion-types crate, the `encode_outputs` and
+/// `decode_outputs` of the public values of its circuits, and re-exports the codec errors
+#[macro_export]
+macro_rules! outputs_codec {
+    ($version:expr) => {
+        /// Format version of the public values committed by the recursion and wrapper circuits,
+        /// their first byte. Bumped whenever the wrapper output structs change, so that verifiers
+        /// can tell the encodings apart and evolve their decoding. The recursion outputs are only
+        /// read by the circuits generated together with them
+        pub const OUTPUTS_VERSION: u8 = $version;
+
+        pub use $crate::{OutputsError, WrapperError};
+
+        /// Encodes circuit outputs as the public values the circuits commit: [`OUTPUTS_VERSION`]
+        /// followed by the borsh encoded outputs
+        pub fn encode_outputs<T: $crate::__private::BorshSerialize>(
+            outputs: &T,
+        ) -> $crate::__private::Vec<u8> {
+            $crate::encode_outputs(OUTPUTS_VERSION, outputs)
+        }
+
+        /// Decodes the public values committed by the recursion or wrapper circuit, failing
+        /// unless they are of [`OUTPUTS_VERSION`]
+        pub fn decode_outputs<T: $crate::__private::BorshDeserialize>(
+            public_values: &[u8],
+        ) -> Result<T, OutputsError> {
+            $crate::decode_outputs(OUTPUTS_VERSION, public_values)
+        }
+    };
+}
+
+/// Why committed public values could not be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputsError {
+    /// The outputs following the version are not borsh encoded outputs
+    Malformed,
+    /// The public values are of another format version than the one expected
+    UnsupportedVersion(u8),
+}
+
+/// Encodes circuit outputs as the public values the circuits commit: the format `version`
+/// followed by the borsh encoded outputs
+pub fn encode_outputs<T: BorshSerialize>(version: u8, outputs: &T) -> Vec<u8> {
+    let mut public_values = alloc::vec![version];
+    public_values.extend(borsh::to_vec(outputs).expect("Failed to encode the outputs"));
+    public_values
+}
+
+/// Decodes the public values committed by a recursion or wrapper circuit, failing unless they
+/// are of format `version`
+pub fn decode_outputs<T: BorshDeserialize>(
+    version: u8,
+    public_values: &[u8],
+) -> Result<T, OutputsError> {
+    match public_values.split_first() {
+        Some((&found, outputs)) if found == version => {
+            borsh::from_slice(outputs).map_err(|_| OutputsError::Malformed)
+        }
+        Some((&found, _)) => Err(OutputsError::UnsupportedVersion(found)),
+        None => Err(OutputsError::Malformed),
+    }
+}
+
+/// Why wrapper outputs could not be accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapperError {
+    /// The public values are not encoded wrapper outputs
+    Malformed,
+    /// The public values are of another format version than the one expected
+    UnsupportedVersion(u8),
+    /// The Groth16 proof doesn't verify against the wrapper circuit vk
+    InvalidProof,
+}
+
+impl From<OutputsError> for WrapperError {
+    fn from(error: OutputsError) -> Self {
+        match error {
+            OutputsError::Malformed => Self::Malformed,
+            OutputsError::UnsupportedVersion(version) => Self::UnsupportedVersion(version),
+        }
+    }
+}
+
+/// The wrapper outputs committed by the wrapper circuits of format `VERSION`, the height and
+/// root the proof chain reached
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct WrapperOutputs<const VERSION: u8> {
+    pub height: u64,
+    pub root: [u8; 32],
+    /// The height and root the proof chain was at before this update, so the chain of state
+    /// transitions can be audited from the wrapper proofs alone
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    /// The CAIP-2 chain id of the proven chain, committed by the recursion circuit
+    pub chain_id: String,
+}
+
+impl<const VERSION: u8> WrapperOutputs<VERSION> {
+    /// Decodes the public values committed by the wrapper circuit, borsh or ABI encoded. ABI
+    /// encoded public values start with the zero high byte of their version word
+    pub fn decode(public_values: &[u8]) -> Result<Self, WrapperError> {
+        match public_values.first() {
+            Some(0) => Self::abi_decode(public_values),
+            _ => Ok(decode_outputs(VERSION, public_values)?),
+        }
+    }
+
+    /// Encodes the outputs as `abi.encode(uint8 version, uint64 height, bytes32 root,
+    /// uint64 previousHeight, bytes32 previousRoot, string chainId)`, the public values of the
+    /// wrapper circuits generated with `WRAPPER_ENCODING=abi`
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let fields = [
+            abi::uint(self.height),
+            self.root,
+            abi::uint(self.previous_height),
+            self.previous_root,
+        ];
+        abi::encode(VERSION, &fields, &self.chain_id)
+    }
+
+    /// Decodes ABI encoded public values, see [`Self::abi_encode`]. Any other encoding of the
+    /// same outputs, e.g. with dirty padding, is refused
+    pub fn abi_decode(public_values: &[u8]) -> Result<Self, WrapperError> {
+        let decoded = abi::decode(VERSION, 4, public_values)?;
+        Ok(Self {
+            height: decoded.uint(0)?,
+            root: decoded.word(1)?,
+            previous_height: decoded.uint(2)?,
+            previous_root: decoded.word(3)?,
+            chain_id: decoded.chain_id,
+        })
+    }
+
+    /// Verifies a wrapper proof against the wrapper circuit vk (`0x`-prefixed bytes32 hash)
+    /// and returns the outputs it commits to
+    #[cfg(feature = "verify")]
+    pub fn verify(
+        proof: &[u8],
+        public_values: &[u8],
+        wrapper_vk: &str,
+    ) -> Result<Self, WrapperError> {
+        verify_groth16(proof, public_values, wrapper_vk)?;
+        Self::decode(public_values)
+    }
+}
+
+/// Checks a Groth16 wrapper proof against the wrapper circuit vk (`0x`-prefixed bytes32 hash)
+#[cfg(feature = "verify")]
+pub fn verify_groth16(
+    proof: &[u8],
+    public_values: &[u8],
+    wrapper_vk: &str,
+) -> Result<(), WrapperError> {
+    sp1_verifier::Groth16Verifier::verify(
+        proof,
+        public_values,
+        wrapper_vk,
+        *sp1_verifier::GROTH16_VK_BYTES,
+    )
+    .map_err(|_| WrapperError::InvalidProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versioned_outputs() {
+        let public_values = encode_outputs(3, &(7u64, [1u8; 32]));
+        assert_eq!(public_values[0], 3);
+        assert_eq!(
+            decode_outputs::<(u64, [u8; 32])>(3, &public_values),
+            Ok((7, [1; 32]))
+        );
+
+        // Outputs of another format version are refused rather than misread
+        assert_eq!(
+            decode_outputs::<(u64, [u8; 32])>(4, &public_values),
+            Err(OutputsError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            decode_outputs::<(u64, [u8; 32])>(3, &public_values[..9]),
+            Err(OutputsError::Malformed)
+        );
+        assert_eq!(decode_outputs::<u64>(3, &[]), Err(OutputsError::Malformed));
+    }
+
+    #[test]
+    fn test_wrapper_outputs() {
+        let outputs = WrapperOutputs::<1> {
+            height: 900001,
+            root: [1; 32],
+            previous_height: 900000,
+            previous_root: [2; 32],
+            chain_id: "bip122:000000000019d6689c085ae165831e93".into(),
+        };
+        // Both encodings decode to the same outputs
+        let borsh = encode_outputs(1, &outputs);
+        assert_eq!(WrapperOutputs::<1>::decode(&borsh), Ok(outputs.clone()));
+        let abi = outputs.abi_encode();
+        assert_eq!(abi.len(), 9 * abi::WORD);
+        assert_eq!(WrapperOutputs::<1>::decode(&abi), Ok(outputs.clone()));
+
+        // Outputs of another format version are refused in both encodings
+        assert_eq!(
+            WrapperOutputs::<2>::decode(&borsh),
+            Err(WrapperError::UnsupportedVersion(1))
+        );
+        assert_eq!(
+            WrapperOutputs::<2>::decode(&abi),
+            Err(WrapperError::UnsupportedVersion(1))
+        );
+        assert_eq!(
+            WrapperOutputs::<1>::decode(&abi[..8 * abi::WORD]),
+            Err(WrapperError::Malformed)
+        );
+    }
+}
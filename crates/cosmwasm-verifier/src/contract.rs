@@ -0,0 +1,204 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    Binary, Deps, DepsMut, Env, HexBinary, MessageInfo, Response, StdResult, to_json_binary,
+};
+use tendermint_recursion_types::WrapperCircuitOutputs;
+
+use crate::{
+    error::ContractError,
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RootResponse, StateResponse},
+    state::{CONFIG, Config, ROOTS, TRUSTED_HEIGHT},
+};
+
+/// Checks that a wrapper vk is a 0x-prefixed bytes32 hash, as the Groth16 verifier expects
+fn validate_vk(wrapper_vk: &str) -> Result<(), ContractError> {
+    let valid = wrapper_vk
+        .strip_prefix("0x")
+        .and_then(|vk| hex::decode(vk).ok())
+        .is_some_and(|vk| vk.len() == 32);
+    if !valid {
+        return Err(ContractError::InvalidVk(wrapper_vk.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    validate_vk(&msg.wrapper_vk)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            wrapper_vk: msg.wrapper_vk.clone(),
+            chain_id: msg.chain_id.clone(),
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("wrapper_vk", msg.wrapper_vk)
+        .add_attribute("chain_id", msg.chain_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::VerifyProof {
+            height,
+            root,
+            proof,
+            public_values,
+        } => verify_proof(deps, height, &root, &proof, &public_values),
+    }
+}
+
+fn verify_proof(
+    deps: DepsMut,
+    height: u64,
+    root: &str,
+    proof: &[u8],
+    public_values: &[u8],
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let expected_root = root
+        .strip_prefix("0x")
+        .and_then(|root| hex::decode(root).ok())
+        .filter(|root| root.len() == 32)
+        .ok_or_else(|| ContractError::InvalidRoot(root.to_string()))?;
+
+    let outputs = WrapperCircuitOutputs::verify(proof, public_values, &config.wrapper_vk)?;
+    if outputs.chain_id != config.chain_id {
+        return Err(ContractError::WrongChain(outputs.chain_id));
+    }
+    if (outputs.height, outputs.root.as_slice()) != (height, expected_root.as_slice()) {
+        return Err(ContractError::OutputsMismatch {
+            height,
+            root: root.to_string(),
+        });
+    }
+    if let Some(trusted_height) = TRUSTED_HEIGHT.may_load(deps.storage)? {
+        if height <= trusted_height {
+            return Err(ContractError::NotNewer {
+                height,
+                trusted_height,
+            });
+        }
+    }
+
+    TRUSTED_HEIGHT.save(deps.storage, &height)?;
+    ROOTS.save(deps.storage, height, &HexBinary::from(outputs.root))?;
+    Ok(Response::new()
+        .add_attribute("action", "verify_proof")
+        .add_attribute("height", height.to_string())
+        .add_attribute("root", root)
+        .add_attribute("previous_height", outputs.previous_height.to_string())
+        .add_attribute("previous_root", hex::encode(outputs.previous_root)))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::State {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let height = TRUSTED_HEIGHT.may_load(deps.storage)?;
+            let root = match height {
+                Some(height) => ROOTS.may_load(deps.storage, height)?,
+                None => None,
+            };
+            to_json_binary(&StateResponse {
+                wrapper_vk: config.wrapper_vk,
+                chain_id: config.chain_id,
+                height,
+                root,
+            })
+        }
+        QueryMsg::Root { height } => to_json_binary(&RootResponse {
+            root: ROOTS.may_load(deps.storage, height)?,
+        }),
+    }
+}
+
+/// Sets the wrapper vk of upgraded circuits. The proofs of the new circuits continue the
+/// trusted height, the chain id is kept
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let mut response = Response::new().add_attribute("action", "migrate");
+    if let Some(wrapper_vk) = msg.wrapper_vk {
+        validate_vk(&wrapper_vk)?;
+        CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+            config.wrapper_vk = wrapper_vk.clone();
+            Ok(config)
+        })?;
+        response = response.add_attribute("wrapper_vk", wrapper_vk);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{
+        from_json,
+        testing::{message_info, mock_dependencies, mock_env},
+    };
+
+    #[test]
+    fn test_verify_proof() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make("relayer"), &[]);
+        let wrapper_vk = format!("0x{}", "ab".repeat(32));
+        let instantiate_msg = |wrapper_vk: &str| InstantiateMsg {
+            wrapper_vk: wrapper_vk.to_string(),
+            chain_id: "eip155:1".to_string(),
+        };
+        assert_eq!(
+            instantiate(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                instantiate_msg("0xab")
+            ),
+            Err(ContractError::InvalidVk("0xab".to_string()))
+        );
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(&wrapper_vk),
+        )
+        .unwrap();
+
+        let outputs = WrapperCircuitOutputs {
+            height: 42,
+            root: [1; 32],
+            previous_height: 41,
+            previous_root: [2; 32],
+            transition: false,
+            chain_id: "eip155:1".to_string(),
+        };
+        let msg = ExecuteMsg::VerifyProof {
+            height: 42,
+            root: format!("0x{}", hex::encode([1; 32])),
+            proof: Binary::from(vec![0; 260]),
+            public_values: Binary::from(outputs.abi_encode()),
+        };
+        assert_eq!(
+            execute(deps.as_mut(), mock_env(), info, msg),
+            Err(ContractError::InvalidProof)
+        );
+
+        let state: StateResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+        assert_eq!(state.wrapper_vk, wrapper_vk);
+        assert_eq!((state.height, state.root), (None, None));
+    }
+}
@@ -0,0 +1,43 @@
+use cosmwasm_std::StdError;
+use tendermint_recursion_types::WrapperError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("invalid wrapper vk {0}, expected a 0x-prefixed bytes32 hash")]
+    InvalidVk(String),
+
+    #[error("invalid root {0}, expected 32 bytes of hex")]
+    InvalidRoot(String),
+
+    #[error("the Groth16 proof doesn't verify against the wrapper vk")]
+    InvalidProof,
+
+    #[error("the public values are no wrapper outputs of version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("the public values are no wrapper outputs")]
+    MalformedOutputs,
+
+    #[error("the public values don't commit to height {height} and root {root}")]
+    OutputsMismatch { height: u64, root: String },
+
+    #[error("the proof is of chain {0}, not of the chain of the contract")]
+    WrongChain(String),
+
+    #[error("height {height} is not past the trusted height {trusted_height}")]
+    NotNewer { height: u64, trusted_height: u64 },
+}
+
+impl From<WrapperError> for ContractError {
+    fn from(error: WrapperError) -> Self {
+        match error {
+            WrapperError::InvalidProof => Self::InvalidProof,
+            WrapperError::UnsupportedVersion(version) => Self::UnsupportedVersion(version),
+            WrapperError::Malformed => Self::MalformedOutputs,
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// A CosmWasm light client of a Lightwave proof chain.
+//
+// The contract pins the wrapper circuit vk and the CAIP-2 chain id of a proof chain, verifies
+// the Groth16 wrapper proofs relayed to it and stores the (height, root) they commit. The
+// `verify_proof` execute message is served by `GET /?format=cosmwasm` and written by
+// `export-proof --format cosmwasm-msg`. Circuit upgrades change the wrapper vk, which the
+// admin of the contract sets with a migration.
+
+pub mod contract;
+pub mod error;
+pub mod msg;
+pub mod state;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,57 @@
+use cosmwasm_schema::{QueryResponses, cw_serde};
+use cosmwasm_std::{Binary, HexBinary};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The wrapper circuit vk the proofs are verified against, the `wrapper_vk` of `GET /vk`
+    pub wrapper_vk: String,
+    /// The CAIP-2 chain id the wrapper proofs commit, e.g. `eip155:1`
+    pub chain_id: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Verifies a wrapper proof and stores the height and root it commits, if past the trusted
+    /// height
+    VerifyProof {
+        height: u64,
+        /// The committed root, 0x-prefixed hex
+        root: String,
+        /// The Groth16 wrapper proof
+        proof: Binary,
+        /// The public values committed by the wrapper circuit
+        public_values: Binary,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// The configuration and the trusted height and root
+    #[returns(StateResponse)]
+    State {},
+    /// The root proven at a height
+    #[returns(RootResponse)]
+    Root { height: u64 },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// The wrapper vk of the upgraded circuits, unchanged if unset
+    pub wrapper_vk: Option<String>,
+}
+
+#[cw_serde]
+pub struct StateResponse {
+    pub wrapper_vk: String,
+    pub chain_id: String,
+    /// Absent until the first proof is verified
+    pub height: Option<u64>,
+    pub root: Option<HexBinary>,
+}
+
+#[cw_serde]
+pub struct RootResponse {
+    /// Absent for heights no verified proof committed
+    pub root: Option<HexBinary>,
+}
@@ -0,0 +1,17 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::HexBinary;
+use cw_storage_plus::{Item, Map};
+
+#[cw_serde]
+pub struct Config {
+    pub wrapper_vk: String,
+    pub chain_id: String,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// The latest verified height
+pub const TRUSTED_HEIGHT: Item<u64> = Item::new("trusted_height");
+
+/// The roots of the verified heights
+pub const ROOTS: Map<u64, HexBinary> = Map::new("roots");
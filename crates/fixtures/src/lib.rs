@@ -0,0 +1,30 @@
+// Chain data shared by the tests of the workspace.
+//
+// The captures of version 1 are SYNTHETIC: they keep the layout of mainnet slot 11715424 and
+// of an SP1 Tendermint proof from height 31134400 to 31234400, but their roots and hashes are
+// placeholder sequences, so they only exercise the shapes of the types, not real chain data.
+// They are read by the mock round tests of the service. The captures live in
+// `data/v<VERSION>/` and are never edited: a real capture, written by
+// `make capture-fixtures`, is added as the next version and the constants below move to it
+// in a single change, dropping the `SYNTHETIC_` prefix, together with the tests whose
+// expectations it changes.
+//
+// Until then the crate is incomplete: the circuit unit tests of the recursion-types crates, the
+// golden tests of `lightwave-codec` and the bench harness don't read it, as golden values of
+// placeholder data would pin nothing. They are wired to it together with the first real capture.
+//
+// The crate has no dependencies and is `no_std`, so the zkVM programs can use it as well.
+// Decode the JSON with the types of the crate under test.
+
+#![no_std]
+
+/// The version of the captures the constants point to
+pub const VERSION: u32 = 1;
+
+/// A synthetic Electra beacon block header and the roots of its body, as read by the Helios
+/// recursion circuit:
+/// `{"electra_header": ElectraBlockHeader, "electra_body_roots": ElectraBlockBodyRoots}`
+pub const SYNTHETIC_HELIOS_ROUND: &str = include_str!("../data/v1/helios_round.json");
+
+/// Synthetic public values of an SP1 Tendermint proof, a JSON encoded `TendermintOutput`
+pub const SYNTHETIC_TENDERMINT_OUTPUT: &str = include_str!("../data/v1/tendermint_output.json");
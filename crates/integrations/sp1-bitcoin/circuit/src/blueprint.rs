@@ -0,0 +1,85 @@
+// This is the main recursion circuit that extends a chain of Bitcoin block headers.
+// It validates the proof of work, difficulty and timestamps of every new header,
+// accumulates the chain work and maintains a chain of proofs for the best block.
+
+#![no_main]
+
+use bitcoin_recursion_types::{
+    ChainState, RecursionCircuitInputs, RecursionCircuitOutputs, decode_outputs, encode_outputs,
+    pow::BlockHeader,
+};
+use sp1_verifier::Groth16Verifier;
+sp1_zkvm::entrypoint!(main);
+
+// The trusted block from which we start our light client chain and the
+// context needed to validate its successors.
+const TRUSTED_HEIGHT: u64 = { trusted_height };
+const TRUSTED_HASH: [u8; 32] = { trusted_hash };
+const TRUSTED_BITS: u32 = { trusted_bits };
+const TRUSTED_PERIOD_START_TIME: u32 = { period_start_time };
+const TRUSTED_RECENT_TIMES: &[u32] = &{ recent_times };
+// The CAIP-2 chain id committed by every proof, telling the proofs of different chains apart
+const CHAIN_ID: &str = "{ chain_id }";
+
+pub fn main() {
+    // Deserialize the circuit inputs which contain the new headers and previous recursive proof
+    let inputs: RecursionCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // The tip proven by the previous proof, or the trusted block in the first round
+    let mut state = if inputs.trusted_height == TRUSTED_HEIGHT {
+        ChainState {
+            height: TRUSTED_HEIGHT,
+            hash: TRUSTED_HASH,
+            bits: TRUSTED_BITS,
+            period_start_time: TRUSTED_PERIOD_START_TIME,
+            recent_times: TRUSTED_RECENT_TIMES.to_vec(),
+            chainwork: 0,
+        }
+    } else {
+        let recursive_proof_outputs: RecursionCircuitOutputs = decode_outputs(
+            &inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Failed to unwrap recursive public values"),
+        )
+        .expect("Failed to deserialize Recursive Outputs");
+        let state = recursive_proof_outputs
+            .into_tip(&inputs.recursive_vk)
+            .expect("Previous proof committed another recursive vk");
+        Groth16Verifier::verify(
+            &inputs
+                .recursive_proof
+                .as_ref()
+                .expect("Previous proof is not provided"),
+            &inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Previous public values is not provided"),
+            &inputs.recursive_vk,
+            groth16_vk,
+        )
+        .expect("Failed to verify previous proof");
+        state
+    };
+
+    // Every round must extend the chain by at least one block
+    assert!(!inputs.headers.is_empty(), "No headers provided");
+    let (previous_height, previous_hash) = (state.height, state.hash);
+    for header in &inputs.headers {
+        let header = BlockHeader::decode(header).expect("Failed to decode header");
+        state.extend(&header).expect("Invalid header");
+    }
+
+    let outputs = RecursionCircuitOutputs {
+        state,
+        vk: inputs.recursive_vk,
+        previous_height,
+        previous_hash,
+        chain_id: CHAIN_ID.to_string(),
+    };
+    sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+}
@@ -0,0 +1,175 @@
+#![no_std]
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub mod pow;
+
+use pow::{BlockHeader, Error, MEDIAN_TIME_SPAN, RETARGET_INTERVAL, block_work, retarget};
+
+lightwave_codec::outputs_codec!(1);
+
+/// The tip of the proven headers chain and the context needed to validate its successor
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ChainState {
+    pub height: u64,
+    /// Hash of the tip, in internal byte order
+    pub hash: [u8; 32],
+    pub bits: u32,
+    /// Time of the first block of the current retarget period
+    pub period_start_time: u32,
+    /// Times of the last (up to 11) blocks, oldest first
+    pub recent_times: Vec<u32>,
+    /// Work accumulated since the trusted block
+    pub chainwork: u128,
+}
+
+impl ChainState {
+    /// Validates `header` as the next block and makes it the new tip
+    pub fn extend(&mut self, header: &BlockHeader) -> Result<(), Error> {
+        if header.prev_blockhash != self.hash {
+            return Err(Error::UnexpectedParent);
+        }
+
+        let height = self.height + 1;
+        let expected_bits = if height % RETARGET_INTERVAL == 0 {
+            let last_time = *self.recent_times.last().unwrap_or(&self.period_start_time);
+            retarget(
+                self.bits,
+                last_time.saturating_sub(self.period_start_time) as u64,
+            )
+        } else {
+            self.bits
+        };
+        if header.bits != expected_bits {
+            return Err(Error::UnexpectedBits);
+        }
+        header.check_pow()?;
+
+        if header.time <= self.median_time_past() {
+            return Err(Error::TimeTooOld);
+        }
+
+        if height % RETARGET_INTERVAL == 0 {
+            self.period_start_time = header.time;
+        }
+        self.recent_times.push(header.time);
+        if self.recent_times.len() > MEDIAN_TIME_SPAN {
+            self.recent_times.remove(0);
+        }
+        self.height = height;
+        self.hash = header.hash;
+        self.bits = header.bits;
+        self.chainwork += block_work(header.bits);
+        Ok(())
+    }
+
+    /// Median of the recent block times
+    pub fn median_time_past(&self) -> u32 {
+        let mut times = self.recent_times.clone();
+        times.sort_unstable();
+        times.get(times.len() / 2).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct RecursionCircuitInputs {
+    /// Serialized 80-byte headers extending the previously proven tip
+    pub headers: Vec<Vec<u8>>,
+    pub recursive_proof: Option<Vec<u8>>,
+    pub recursive_public_values: Option<Vec<u8>>,
+    pub recursive_vk: String,
+    pub trusted_height: u64,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct RecursionCircuitOutputs {
+    pub state: ChainState,
+    pub vk: String,
+    /// The tip the headers extend: the tip of the previous proof, or the trusted block
+    pub previous_height: u64,
+    pub previous_hash: [u8; 32],
+    /// The CAIP-2 chain id of the followed chain, `bip122:<genesis hash prefix>`, fixed when the
+    /// circuit is generated so that proofs of different chains can't be mistaken for each other
+    pub chain_id: String,
+}
+
+impl RecursionCircuitOutputs {
+    /// The tip a link verified with `recursive_vk` extends, none if this link committed
+    /// another vk. The vk is bound by induction: every link must be verified with the vk
+    /// committed by the previous one, and the wrapper circuit pins the vk of the last link
+    pub fn into_tip(self, recursive_vk: &str) -> Option<ChainState> {
+        (self.vk == recursive_vk).then_some(self.state)
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct WrapperCircuitInputs {
+    pub recursive_proof: Vec<u8>,
+    pub recursive_public_values: Vec<u8>,
+}
+
+/// The outputs committed by the wrapper circuit, whose roots are the hashes of the best proven
+/// block and of the tip before the update, in internal byte order
+pub type WrapperCircuitOutputs = lightwave_codec::WrapperOutputs<OUTPUTS_VERSION>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use pow::POW_LIMIT_BITS;
+
+    const GENESIS_HASH: &str = "6fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000";
+    const BLOCK_1_HEADER: &str = "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299";
+
+    fn genesis_state() -> ChainState {
+        ChainState {
+            height: 0,
+            hash: hex::decode(GENESIS_HASH).unwrap().try_into().unwrap(),
+            bits: POW_LIMIT_BITS,
+            period_start_time: 1231006505,
+            recent_times: vec![1231006505],
+            chainwork: 0,
+        }
+    }
+
+    #[test]
+    fn test_extend_chain() {
+        let header = BlockHeader::decode(&hex::decode(BLOCK_1_HEADER).unwrap()).unwrap();
+        let mut state = genesis_state();
+        state.extend(&header).unwrap();
+        assert_eq!(state.height, 1);
+        assert_eq!(state.hash, header.hash);
+        assert_eq!(state.chainwork, 0x100010001);
+        assert_eq!(state.recent_times, vec![1231006505, 1231469665]);
+
+        // The same header doesn't extend the new tip
+        assert_eq!(state.extend(&header), Err(Error::UnexpectedParent));
+    }
+
+    #[test]
+    fn test_reject_foreign_vk() {
+        let outputs = RecursionCircuitOutputs {
+            state: genesis_state(),
+            vk: "0x01".into(),
+            previous_height: 0,
+            previous_hash: [0; 32],
+            chain_id: "bip122:000000000019d6689c085ae165831e93".into(),
+        };
+        assert_eq!(outputs.clone().into_tip("0x01"), Some(genesis_state()));
+        assert_eq!(outputs.into_tip("0x02"), None);
+    }
+
+    #[test]
+    fn test_reject_invalid_header() {
+        let mut bytes = hex::decode(BLOCK_1_HEADER).unwrap();
+        // Changing the nonce invalidates the proof of work
+        bytes[79] ^= 1;
+        let header = BlockHeader::decode(&bytes).unwrap();
+        assert_eq!(
+            genesis_state().extend(&header),
+            Err(Error::InsufficientWork)
+        );
+    }
+}
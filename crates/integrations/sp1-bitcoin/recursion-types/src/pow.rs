@@ -0,0 +1,345 @@
+// Bitcoin proof-of-work consensus rules needed to validate a chain of block headers:
+// header decoding and hashing, compact targets, block work and difficulty retargeting.
+// Only the mainnet rules are implemented, the testnet minimum difficulty exception is not.
+
+use core::{
+    cmp::Ordering,
+    ops::{Div, Not, Shl, Shr},
+};
+use sha2::{Digest, Sha256};
+
+/// Number of blocks between difficulty adjustments
+pub const RETARGET_INTERVAL: u64 = 2016;
+/// Expected duration of a retarget period, in seconds
+pub const TARGET_TIMESPAN: u64 = 14 * 24 * 60 * 60;
+/// The easiest target allowed on mainnet
+pub const POW_LIMIT_BITS: u32 = 0x1d00ffff;
+/// Number of blocks in the median time past window
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The header isn't 80 bytes long
+    InvalidLength,
+    /// The header doesn't extend the current tip
+    UnexpectedParent,
+    /// The encoded target is negative, zero, overflows or is above the pow limit
+    InvalidTarget,
+    /// The header bits don't match the difficulty required at its height
+    UnexpectedBits,
+    /// The header hash is above its target
+    InsufficientWork,
+    /// The header time isn't after the median time of the previous blocks
+    TimeTooOld,
+}
+
+/// Unsigned 256-bit integer, little-endian 64-bit limbs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct U256(pub [u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+
+    pub fn from_u64(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+
+    /// Interprets 32 bytes as a little-endian integer, as Bitcoin does for hashes
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Number of significant bits
+    pub fn bits(&self) -> u32 {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return 64 * i as u32 + 64 - self.0[i].leading_zeros();
+            }
+        }
+        0
+    }
+
+    fn bit(&self, index: u32) -> bool {
+        (self.0[(index / 64) as usize] >> (index % 64)) & 1 == 1
+    }
+
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// The value as a u128, saturating if it doesn't fit
+    pub fn saturating_u128(&self) -> u128 {
+        if self.0[2] != 0 || self.0[3] != 0 {
+            return u128::MAX;
+        }
+        (self.0[1] as u128) << 64 | self.0[0] as u128
+    }
+
+    pub fn wrapping_add(&self, other: &Self) -> Self {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        U256(result)
+    }
+
+    pub fn wrapping_sub(&self, other: &Self) -> Self {
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (diff, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        U256(result)
+    }
+
+    /// Multiplies by a u64, returns None on overflow
+    pub fn checked_mul_u64(&self, factor: u64) -> Option<Self> {
+        let mut result = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let product = self.0[i] as u128 * factor as u128 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+        (carry == 0).then_some(U256(result))
+    }
+
+    /// Decodes a compact target, returns None if it is negative, zero or overflows
+    pub fn from_compact(bits: u32) -> Option<Self> {
+        let size = bits >> 24;
+        let word = bits & 0x007fffff;
+        let negative = word != 0 && bits & 0x00800000 != 0;
+        let overflow =
+            word != 0 && (size > 34 || (word > 0xff && size > 33) || (word > 0xffff && size > 32));
+        if negative || overflow {
+            return None;
+        }
+        let target = if size <= 3 {
+            U256::from_u64((word >> (8 * (3 - size))) as u64)
+        } else {
+            U256::from_u64(word as u64) << (8 * (size - 3))
+        };
+        (!target.is_zero()).then_some(target)
+    }
+
+    /// Encodes the value as a compact target
+    pub fn to_compact(&self) -> u32 {
+        let mut size = self.bits().div_ceil(8);
+        let mut compact = if size <= 3 {
+            (self.low_u64() << (8 * (3 - size))) as u32
+        } else {
+            (*self >> (8 * (size - 3))).low_u64() as u32
+        };
+        // The sign bit must stay clear
+        if compact & 0x00800000 != 0 {
+            compact >>= 8;
+            size += 1;
+        }
+        compact | size << 24
+    }
+}
+
+impl Shl<u32> for U256 {
+    type Output = U256;
+
+    fn shl(self, shift: u32) -> U256 {
+        let mut result = [0u64; 4];
+        let (limbs, bits) = ((shift / 64) as usize, shift % 64);
+        for i in (limbs..4).rev() {
+            result[i] = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                result[i] |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = U256;
+
+    fn shr(self, shift: u32) -> U256 {
+        let mut result = [0u64; 4];
+        let (limbs, bits) = ((shift / 64) as usize, shift % 64);
+        for i in 0..4usize.saturating_sub(limbs) {
+            result[i] = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                result[i] |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Not for U256 {
+    type Output = U256;
+
+    fn not(self) -> U256 {
+        U256(self.0.map(|limb| !limb))
+    }
+}
+
+/// Binary long division, panics if the divisor is zero
+impl Div for U256 {
+    type Output = U256;
+
+    fn div(self, divisor: U256) -> U256 {
+        assert!(!divisor.is_zero(), "Division by zero");
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for i in (0..self.bits()).rev() {
+            let overflow = remainder.bit(255);
+            remainder = remainder << 1;
+            remainder.0[0] |= self.bit(i) as u64;
+            if overflow || remainder >= divisor {
+                remainder = remainder.wrapping_sub(&divisor);
+                quotient.0[(i / 64) as usize] |= 1 << (i % 64);
+            }
+        }
+        quotient
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+/// A decoded 80-byte Bitcoin block header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub version: i32,
+    pub prev_blockhash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+    /// Double sha256 of the serialized header, in internal byte order
+    pub hash: [u8; 32],
+}
+
+impl BlockHeader {
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes: &[u8; 80] = bytes.try_into().map_err(|_| Error::InvalidLength)?;
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        Ok(Self {
+            version: u32_at(0) as i32,
+            prev_blockhash: bytes[4..36].try_into().unwrap(),
+            merkle_root: bytes[36..68].try_into().unwrap(),
+            time: u32_at(68),
+            bits: u32_at(72),
+            nonce: u32_at(76),
+            hash: Sha256::digest(Sha256::digest(bytes)).into(),
+        })
+    }
+
+    /// Checks that the header hash meets its own target
+    pub fn check_pow(&self) -> Result<(), Error> {
+        let target = U256::from_compact(self.bits).ok_or(Error::InvalidTarget)?;
+        if target > pow_limit() {
+            return Err(Error::InvalidTarget);
+        }
+        if U256::from_le_bytes(self.hash) > target {
+            return Err(Error::InsufficientWork);
+        }
+        Ok(())
+    }
+}
+
+pub fn pow_limit() -> U256 {
+    U256::from_compact(POW_LIMIT_BITS).unwrap()
+}
+
+/// Expected number of hashes to mine a block at the target encoded by `bits`
+pub fn block_work(bits: u32) -> u128 {
+    let Some(target) = U256::from_compact(bits) else {
+        return 0;
+    };
+    // 2**256 / (target + 1), computed as ~target / (target + 1) + 1 to stay within 256 bits
+    (!target / target.wrapping_add(&U256::from_u64(1)))
+        .wrapping_add(&U256::from_u64(1))
+        .saturating_u128()
+}
+
+/// Bits required for the first block of a retarget period, given the bits of the
+/// previous period and the time elapsed between its first and last blocks
+pub fn retarget(bits: u32, timespan: u64) -> u32 {
+    let timespan = timespan.clamp(TARGET_TIMESPAN / 4, TARGET_TIMESPAN * 4);
+    let target = U256::from_compact(bits).unwrap_or(U256::ZERO);
+    let target = match target.checked_mul_u64(timespan) {
+        Some(scaled) => scaled / U256::from_u64(TARGET_TIMESPAN),
+        None => pow_limit(),
+    };
+    target.min(pow_limit()).to_compact()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+    #[test]
+    fn test_genesis_header() {
+        let header = BlockHeader::decode(&hex::decode(GENESIS_HEADER).unwrap()).unwrap();
+        let mut hash = header.hash;
+        hash.reverse();
+        assert_eq!(
+            hex::encode(hash),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(header.bits, POW_LIMIT_BITS);
+        assert_eq!(header.time, 1231006505);
+        header.check_pow().unwrap();
+    }
+
+    #[test]
+    fn test_compact_roundtrip() {
+        for bits in [POW_LIMIT_BITS, 0x1b0404cb, 0x17034219, 0x03123456] {
+            assert_eq!(U256::from_compact(bits).unwrap().to_compact(), bits);
+        }
+        // Negative and zero targets
+        assert_eq!(U256::from_compact(0x04923456), None);
+        assert_eq!(U256::from_compact(0x00000000), None);
+    }
+
+    #[test]
+    fn test_block_work() {
+        assert_eq!(block_work(POW_LIMIT_BITS), 0x100010001);
+    }
+
+    #[test]
+    fn test_retarget() {
+        // A period that took exactly two weeks keeps the difficulty
+        assert_eq!(retarget(0x1b0404cb, TARGET_TIMESPAN), 0x1b0404cb);
+        // The target can at most quadruple and never exceeds the pow limit
+        assert_eq!(retarget(0x1b0404cb, TARGET_TIMESPAN * 10), 0x1b10132c);
+        assert_eq!(
+            retarget(POW_LIMIT_BITS, TARGET_TIMESPAN * 2),
+            POW_LIMIT_BITS
+        );
+    }
+}
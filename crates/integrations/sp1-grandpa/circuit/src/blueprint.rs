@@ -0,0 +1,174 @@
+// This is the main recursion circuit that verifies GRANDPA justifications and maintains
+// a chain of proofs for finalized blocks. It verifies the precommits of the authority set
+// tracked by the previous proof and hands over to the next set on scheduled changes.
+
+#![no_main]
+
+use blake2::{Blake2b, Digest, digest::consts::U32};
+use ed25519_consensus::{Signature, VerificationKey};
+use grandpa_recursion_types::{
+    RecursionCircuitInputs, RecursionCircuitOutputs, authority_set_hash, decode_outputs,
+    encode_outputs,
+    scale::{Header, Justification, precommit_message},
+};
+use sp1_verifier::Groth16Verifier;
+use std::collections::{BTreeMap, BTreeSet};
+sp1_zkvm::entrypoint!(main);
+
+// The trusted block from which we start our light client chain and the
+// authority set finalizing its descendants.
+const TRUSTED_HEIGHT: u64 = { trusted_height };
+const TRUSTED_AUTHORITY_SET_ID: u64 = { authority_set_id };
+const TRUSTED_AUTHORITY_SET_HASH: [u8; 32] = { authority_set_hash };
+// The CAIP-2 chain id committed by every proof, telling the proofs of different chains apart
+const CHAIN_ID: &str = "{ chain_id }";
+
+fn blake2_256(bytes: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::digest(bytes).into()
+}
+
+pub fn main() {
+    // Deserialize the circuit inputs which contain the justification and previous recursive proof
+    let inputs: RecursionCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // The authority set finalizing the blocks after the last proven height. The state root of
+    // the trusted block is not known to the circuit, the first link commits a zero root
+    let (previous_height, previous_root, set_id, set_hash) = if inputs.trusted_height
+        == TRUSTED_HEIGHT
+    {
+        (
+            TRUSTED_HEIGHT,
+            [0; 32],
+            TRUSTED_AUTHORITY_SET_ID,
+            TRUSTED_AUTHORITY_SET_HASH,
+        )
+    } else {
+        let recursive_proof_outputs: RecursionCircuitOutputs = decode_outputs(
+            &inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Failed to unwrap recursive public values"),
+        )
+        .expect("Failed to deserialize Recursive Outputs");
+        // The vk is bound by induction, every link must be verified with the vk committed by
+        // the previous one and the wrapper circuit pins the vk of the last link
+        assert_eq!(
+            recursive_proof_outputs.vk, inputs.recursive_vk,
+            "Previous proof committed another recursive vk"
+        );
+        Groth16Verifier::verify(
+            &inputs
+                .recursive_proof
+                .as_ref()
+                .expect("Previous proof is not provided"),
+            &inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Previous public values is not provided"),
+            &inputs.recursive_vk,
+            groth16_vk,
+        )
+        .expect("Failed to verify previous proof");
+        (
+            recursive_proof_outputs.height,
+            recursive_proof_outputs.root,
+            recursive_proof_outputs.authority_set_id,
+            recursive_proof_outputs.authority_set_hash,
+        )
+    };
+    assert_eq!(inputs.authority_set_id, set_id);
+    assert_eq!(authority_set_hash(&inputs.authorities), set_hash);
+
+    // The justification must finalize the provided header
+    let justification =
+        Justification::decode(&inputs.justification).expect("Failed to decode justification");
+    let header = Header::decode_all(&inputs.header).expect("Failed to decode header");
+    assert_eq!(blake2_256(&inputs.header), justification.target_hash);
+    assert_eq!(header.number, justification.target_number);
+    assert!(header.number as u64 > previous_height);
+
+    // Precommits may target descendants of the finalized block, linked by the ancestry headers
+    let ancestry: BTreeMap<[u8; 32], [u8; 32]> = justification
+        .votes_ancestries
+        .iter()
+        .map(|(header, encoded)| (blake2_256(encoded), header.parent_hash))
+        .collect();
+    let is_descendant = |mut hash: [u8; 32], mut number: u32| loop {
+        if hash == justification.target_hash {
+            return true;
+        }
+        if number <= justification.target_number {
+            return false;
+        }
+        match ancestry.get(&hash) {
+            Some(parent) => {
+                hash = *parent;
+                number -= 1;
+            }
+            None => return false,
+        }
+    };
+
+    // Count the weight of valid precommits, every authority counts at most once
+    let total_weight: u128 = inputs.authorities.iter().map(|a| a.weight as u128).sum();
+    let mut signed_weight: u128 = 0;
+    let mut signers = BTreeSet::new();
+    for precommit in &justification.precommits {
+        let Some(authority) = inputs
+            .authorities
+            .iter()
+            .find(|authority| authority.public_key == precommit.authority)
+        else {
+            continue;
+        };
+        if !signers.insert(precommit.authority) {
+            continue;
+        }
+        assert!(
+            is_descendant(precommit.target_hash, precommit.target_number),
+            "Precommit does not target a descendant of the finalized block"
+        );
+        let message = precommit_message(
+            &precommit.target_hash,
+            precommit.target_number,
+            justification.round,
+            set_id,
+        );
+        VerificationKey::try_from(precommit.authority)
+            .and_then(|key| key.verify(&Signature::from(precommit.signature), &message))
+            .expect("Invalid precommit signature");
+        signed_weight += authority.weight as u128;
+    }
+    assert!(
+        signed_weight * 3 > total_weight * 2,
+        "Precommits don't reach a supermajority"
+    );
+
+    // A change scheduled in the finalized block hands over to the next authority set
+    let (next_set_id, next_set_hash) = match header
+        .scheduled_change()
+        .expect("Failed to decode scheduled change")
+    {
+        Some((next_authorities, delay)) => {
+            assert_eq!(delay, 0, "Delayed authority set changes are not supported");
+            (set_id + 1, authority_set_hash(&next_authorities))
+        }
+        None => (set_id, set_hash),
+    };
+
+    let outputs = RecursionCircuitOutputs {
+        root: header.state_root,
+        height: header.number as u64,
+        authority_set_id: next_set_id,
+        authority_set_hash: next_set_hash,
+        vk: inputs.recursive_vk,
+        previous_height,
+        previous_root,
+        chain_id: CHAIN_ID.to_string(),
+    };
+    sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+}
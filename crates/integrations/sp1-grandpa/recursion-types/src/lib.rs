@@ -0,0 +1,64 @@
+#![no_std]
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+pub mod scale;
+
+lightwave_codec::outputs_codec!(1);
+
+/// A GRANDPA voter and its voting weight
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Authority {
+    /// Ed25519 public key
+    pub public_key: [u8; 32],
+    pub weight: u64,
+}
+
+/// Commitment to an authority set, as tracked by the recursion circuit
+pub fn authority_set_hash(authorities: &[Authority]) -> [u8; 32] {
+    Sha256::digest(borsh::to_vec(authorities).unwrap()).into()
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct RecursionCircuitInputs {
+    /// SCALE-encoded GRANDPA justification of the target block
+    pub justification: Vec<u8>,
+    /// SCALE-encoded header of the target block
+    pub header: Vec<u8>,
+    /// The authority set that finalized the target block
+    pub authorities: Vec<Authority>,
+    pub authority_set_id: u64,
+    pub recursive_proof: Option<Vec<u8>>,
+    pub recursive_public_values: Option<Vec<u8>>,
+    pub recursive_vk: String,
+    pub trusted_height: u64,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct RecursionCircuitOutputs {
+    pub root: [u8; 32],
+    pub height: u64,
+    /// The authority set finalizing the blocks after `height`
+    pub authority_set_id: u64,
+    pub authority_set_hash: [u8; 32],
+    pub vk: String,
+    /// The height and state root committed by the previous proof, the trusted height and a
+    /// zero root for the first
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    /// The CAIP-2 chain id of the followed chain, `polkadot:<genesis hash prefix>`, fixed when the
+    /// circuit is generated so that proofs of different chains can't be mistaken for each other
+    pub chain_id: String,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct WrapperCircuitInputs {
+    pub recursive_proof: Vec<u8>,
+    pub recursive_public_values: Vec<u8>,
+}
+
+/// The outputs committed by the wrapper circuit
+pub type WrapperCircuitOutputs = lightwave_codec::WrapperOutputs<OUTPUTS_VERSION>;
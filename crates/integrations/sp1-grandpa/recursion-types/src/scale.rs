@@ -0,0 +1,402 @@
+// Minimal SCALE decoding of the Substrate structures needed to verify GRANDPA finality:
+// block headers, justifications and finality proofs. Only the fields read by the
+// recursion circuit are kept, everything else is skipped while decoding.
+
+use alloc::vec::Vec;
+
+use crate::Authority;
+
+/// Engine id of GRANDPA consensus digests
+pub const GRANDPA_ENGINE_ID: [u8; 4] = *b"FRNK";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The input ended before the value was fully decoded
+    UnexpectedEnd,
+    /// An enum variant that isn't supported
+    UnknownVariant(u8),
+    /// A compact integer that doesn't fit in a u64
+    Overflow,
+    /// Bytes were left over after decoding
+    TrailingBytes,
+}
+
+/// Cursor over SCALE-encoded bytes
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(len).ok_or(Error::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(Error::UnexpectedEnd)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a compact-encoded integer
+    pub fn read_compact(&mut self) -> Result<u64, Error> {
+        let first = self.read_u8()?;
+        match first & 0b11 {
+            0 => Ok((first >> 2) as u64),
+            1 => {
+                let second = self.read_u8()?;
+                Ok((u16::from_le_bytes([first, second]) >> 2) as u64)
+            }
+            2 => {
+                let rest: [u8; 3] = self.read_array()?;
+                Ok((u32::from_le_bytes([first, rest[0], rest[1], rest[2]]) >> 2) as u64)
+            }
+            _ => {
+                let len = (first >> 2) as usize + 4;
+                if len > 8 {
+                    return Err(Error::Overflow);
+                }
+                let mut bytes = [0u8; 8];
+                bytes[..len].copy_from_slice(self.read_bytes(len)?);
+                Ok(u64::from_le_bytes(bytes))
+            }
+        }
+    }
+
+    /// Reads a length-prefixed byte vector
+    pub fn read_vec(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.read_compact()? as usize;
+        self.read_bytes(len)
+    }
+
+    /// Fails if not all bytes were consumed
+    pub fn finish(&self) -> Result<(), Error> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(Error::TrailingBytes)
+        }
+    }
+}
+
+/// Appends the compact encoding of `value` to `out`
+pub fn encode_compact(value: u64, out: &mut Vec<u8>) {
+    match value {
+        0..=0x3f => out.push((value as u8) << 2),
+        0x40..=0x3fff => out.extend_from_slice(&(((value as u16) << 2) | 1).to_le_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&(((value as u32) << 2) | 2).to_le_bytes()),
+        _ => {
+            let bytes = value.to_le_bytes();
+            let len = 8 - (value.leading_zeros() / 8) as usize;
+            out.push((((len - 4) as u8) << 2) | 3);
+            out.extend_from_slice(&bytes[..len]);
+        }
+    }
+}
+
+/// A digest item of a block header, only consensus messages are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestItem {
+    Consensus([u8; 4], Vec<u8>),
+    Other,
+}
+
+/// A Substrate block header with a `u32` block number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub parent_hash: [u8; 32],
+    pub number: u32,
+    pub state_root: [u8; 32],
+    pub extrinsics_root: [u8; 32],
+    pub digest: Vec<DigestItem>,
+}
+
+impl Header {
+    /// Decodes a header, returns it together with its encoding (hashed to get the block hash)
+    pub fn decode<'a>(reader: &mut Reader<'a>) -> Result<(Self, &'a [u8]), Error> {
+        let start = reader.position();
+        let parent_hash = reader.read_array()?;
+        let number = u32::try_from(reader.read_compact()?).map_err(|_| Error::Overflow)?;
+        let state_root = reader.read_array()?;
+        let extrinsics_root = reader.read_array()?;
+        let items = reader.read_compact()?;
+        let mut digest = Vec::new();
+        for _ in 0..items {
+            let item = match reader.read_u8()? {
+                // Other
+                0 => {
+                    reader.read_vec()?;
+                    DigestItem::Other
+                }
+                // Consensus
+                4 => {
+                    let engine_id = reader.read_array()?;
+                    DigestItem::Consensus(engine_id, reader.read_vec()?.to_vec())
+                }
+                // Seal and PreRuntime
+                5 | 6 => {
+                    reader.read_array::<4>()?;
+                    reader.read_vec()?;
+                    DigestItem::Other
+                }
+                // RuntimeEnvironmentUpdated
+                8 => DigestItem::Other,
+                variant => return Err(Error::UnknownVariant(variant)),
+            };
+            digest.push(item);
+        }
+        let encoded = &reader.bytes[start..reader.position()];
+        Ok((
+            Self {
+                parent_hash,
+                number,
+                state_root,
+                extrinsics_root,
+                digest,
+            },
+            encoded,
+        ))
+    }
+
+    /// Decodes a header that spans all of `bytes`
+    pub fn decode_all(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes);
+        let (header, _) = Self::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(header)
+    }
+
+    /// The authority set change scheduled by this header, as `(next authorities, delay)`
+    pub fn scheduled_change(&self) -> Result<Option<(Vec<Authority>, u32)>, Error> {
+        for item in &self.digest {
+            let DigestItem::Consensus(engine_id, data) = item else {
+                continue;
+            };
+            // ConsensusLog::ScheduledChange
+            if *engine_id != GRANDPA_ENGINE_ID || data.first() != Some(&1) {
+                continue;
+            }
+            let mut reader = Reader::new(&data[1..]);
+            let authorities = read_authorities(&mut reader)?;
+            let delay = reader.read_u32()?;
+            reader.finish()?;
+            return Ok(Some((authorities, delay)));
+        }
+        Ok(None)
+    }
+}
+
+/// Encodes a header from its parts, `digest_items` are the already encoded digest logs
+/// as returned by the `chain_getHeader` RPC
+pub fn encode_header(
+    parent_hash: &[u8; 32],
+    number: u32,
+    state_root: &[u8; 32],
+    extrinsics_root: &[u8; 32],
+    digest_items: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(parent_hash);
+    encode_compact(number as u64, &mut out);
+    out.extend_from_slice(state_root);
+    out.extend_from_slice(extrinsics_root);
+    encode_compact(digest_items.len() as u64, &mut out);
+    for item in digest_items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+/// A precommit vote signed by an authority
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPrecommit {
+    pub target_hash: [u8; 32],
+    pub target_number: u32,
+    pub signature: [u8; 64],
+    pub authority: [u8; 32],
+}
+
+/// A GRANDPA justification: the precommits of a round finalizing the target block,
+/// together with the headers linking precommits for descendants back to the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Justification {
+    pub round: u64,
+    pub target_hash: [u8; 32],
+    pub target_number: u32,
+    pub precommits: Vec<SignedPrecommit>,
+    /// The ancestry headers and their encodings
+    pub votes_ancestries: Vec<(Header, Vec<u8>)>,
+}
+
+impl Justification {
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes);
+        let round = reader.read_u64()?;
+        let target_hash = reader.read_array()?;
+        let target_number = reader.read_u32()?;
+        let count = reader.read_compact()?;
+        let mut precommits = Vec::new();
+        for _ in 0..count {
+            precommits.push(SignedPrecommit {
+                target_hash: reader.read_array()?,
+                target_number: reader.read_u32()?,
+                signature: reader.read_array()?,
+                authority: reader.read_array()?,
+            });
+        }
+        let count = reader.read_compact()?;
+        let mut votes_ancestries = Vec::new();
+        for _ in 0..count {
+            let (header, encoded) = Header::decode(&mut reader)?;
+            votes_ancestries.push((header, encoded.to_vec()));
+        }
+        reader.finish()?;
+        Ok(Self {
+            round,
+            target_hash,
+            target_number,
+            precommits,
+            votes_ancestries,
+        })
+    }
+}
+
+/// The message an authority signs when precommitting to a block
+pub fn precommit_message(
+    target_hash: &[u8; 32],
+    target_number: u32,
+    round: u64,
+    set_id: u64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(53);
+    // Message::Precommit
+    message.push(1);
+    message.extend_from_slice(target_hash);
+    message.extend_from_slice(&target_number.to_le_bytes());
+    message.extend_from_slice(&round.to_le_bytes());
+    message.extend_from_slice(&set_id.to_le_bytes());
+    message
+}
+
+/// A finality proof as returned by the `grandpa_proveFinality` RPC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalityProof {
+    /// Hash of the justified block
+    pub block: [u8; 32],
+    /// SCALE-encoded justification of the block
+    pub justification: Vec<u8>,
+}
+
+impl FinalityProof {
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes);
+        let block = reader.read_array()?;
+        let justification = reader.read_vec()?.to_vec();
+        // The unknown headers are not needed, the target header is fetched separately
+        Ok(Self {
+            block,
+            justification,
+        })
+    }
+}
+
+/// Decodes the SCALE-encoded `Vec<(AuthorityId, AuthorityWeight)>` returned by the
+/// `GrandpaApi_grandpa_authorities` runtime call
+pub fn decode_authorities(bytes: &[u8]) -> Result<Vec<Authority>, Error> {
+    let mut reader = Reader::new(bytes);
+    let authorities = read_authorities(&mut reader)?;
+    reader.finish()?;
+    Ok(authorities)
+}
+
+fn read_authorities(reader: &mut Reader) -> Result<Vec<Authority>, Error> {
+    let count = reader.read_compact()?;
+    let mut authorities = Vec::new();
+    for _ in 0..count {
+        authorities.push(Authority {
+            public_key: reader.read_array()?,
+            weight: reader.read_u64()?,
+        });
+    }
+    Ok(authorities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_compact_roundtrip() {
+        for value in [
+            0,
+            1,
+            63,
+            64,
+            16383,
+            16384,
+            1 << 30,
+            u32::MAX as u64,
+            u64::MAX,
+        ] {
+            let mut encoded = Vec::new();
+            encode_compact(value, &mut encoded);
+            let mut reader = Reader::new(&encoded);
+            assert_eq!(reader.read_compact().unwrap(), value);
+            reader.finish().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_header_scheduled_change() {
+        // ConsensusLog::ScheduledChange with one authority of weight 1 and no delay
+        let mut change = vec![1];
+        encode_compact(1, &mut change);
+        change.extend_from_slice(&[7; 32]);
+        change.extend_from_slice(&1u64.to_le_bytes());
+        change.extend_from_slice(&0u32.to_le_bytes());
+        let mut consensus = vec![4];
+        consensus.extend_from_slice(&GRANDPA_ENGINE_ID);
+        encode_compact(change.len() as u64, &mut consensus);
+        consensus.extend_from_slice(&change);
+        let seal = [vec![5], b"aura".to_vec(), vec![8], vec![9; 2]].concat();
+
+        let encoded = encode_header(&[1; 32], 26_000_000, &[2; 32], &[3; 32], &[consensus, seal]);
+        let header = Header::decode_all(&encoded).unwrap();
+        assert_eq!(header.number, 26_000_000);
+        assert_eq!(header.state_root, [2; 32]);
+        let (authorities, delay) = header.scheduled_change().unwrap().unwrap();
+        assert_eq!(
+            authorities,
+            vec![Authority {
+                public_key: [7; 32],
+                weight: 1
+            }]
+        );
+        assert_eq!(delay, 0);
+    }
+}
@@ -0,0 +1,183 @@
+// This is the recursion circuit with SP1 proof aggregation, generated instead of the Groth16
+// blueprint with RECURSION_AGGREGATION=compressed. The previous recursive proof is a compressed
+// SP1 proof the prover writes to the stdin, verified with `verify_sp1_proof` against the vk
+// digest of this circuit. Only the wrapper proof is Groth16-wrapped.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use alloy_primitives::U256;
+use alloy_sol_types::SolValue;
+use beacon_electra::merkleize_header;
+use helios_recursion_types::{
+    RecursionCircuitConstants, RecursionCircuitInputs, RecursionCircuitOutputs, decode_outputs,
+    encode_outputs,
+};
+use sha2::{Digest, Sha256};
+use sp1_helios_primitives::types::ProofOutputs as HeliosOutputs;
+use sp1_verifier::Groth16Verifier;
+
+// The trusted constants, generated for the network selected by SOURCE_CHAIN_ID, which binds
+// the proof chain to that network. The sync committee hash that was active at the trusted
+// slot verifies the initial state when starting from the trusted slot.
+static CONSTANTS: &[u8] = include_bytes!("../constants.bin");
+
+pub fn main() {
+    let constants: RecursionCircuitConstants =
+        borsh::from_slice(CONSTANTS).expect("Failed to deserialize the trusted constants");
+
+    // Deserialize the circuit inputs which contain the Helios proof and the public values of
+    // the previous recursive proof
+    let inputs: RecursionCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // Compute the Merkle root of the Electra block header
+    let electra_block_header_root = merkleize_header(inputs.electra_header.clone());
+    let electra_body_root = inputs.electra_body_roots.merkelize();
+
+    // Decode the Helios proof outputs which contain the new header information
+    let helios_output: HeliosOutputs =
+        HeliosOutputs::abi_decode(&inputs.helios_public_values, false).unwrap();
+
+    // Verify that the body root in the header matches our computed body root
+    assert_eq!(inputs.electra_header.body_root, electra_body_root);
+
+    // Verify that the header root matches the one from the Helios light client
+    assert_eq!(electra_block_header_root, helios_output.newHeader.0);
+
+    // Verify the Helios proof using Groth16 verification
+    Groth16Verifier::verify(
+        &inputs.helios_proof,
+        &inputs.helios_public_values,
+        &constants.helios_vk,
+        groth16_vk,
+    )
+    .expect("Failed to verify helios zk light client update");
+
+    if inputs.previous_head == constants.trusted_head {
+        // If this is the first proof after the trusted slot, verify the sync committee hash
+        assert_eq!(
+            helios_output.prevSyncCommitteeHash.to_vec(),
+            constants.trusted_sync_committee_hash
+        );
+
+        let outputs = get_helios_outputs(helios_output, None, &inputs, &constants.chain_id);
+
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+    } else {
+        // For subsequent proofs, verify the previous compressed proof to ensure continuity
+        let public_values_digest = Sha256::digest(
+            inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Previous public values is not provided"),
+        );
+        sp1_zkvm::lib::verify::verify_sp1_proof(
+            &vk_digest(&inputs.recursive_vk),
+            &public_values_digest.into(),
+        );
+
+        // deserialize the inputs required for the recursive verification
+        let recursive_proof_outputs: RecursionCircuitOutputs = decode_outputs(
+            &inputs
+                .recursive_public_values
+                .as_ref()
+                .expect("Previous public values is not provided"),
+        )
+        .unwrap();
+        // The circuit can't embed its own vk, so it is bound by induction: every link must be
+        // verified with the vk committed by the previous one, and the wrapper circuit pins the
+        // vk of the last link
+        assert_eq!(
+            recursive_proof_outputs.vk, inputs.recursive_vk,
+            "Previous proof committed another recursive vk"
+        );
+
+        let outputs = get_helios_outputs(
+            helios_output,
+            Some(recursive_proof_outputs),
+            &inputs,
+            &constants.chain_id,
+        );
+
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+    }
+}
+
+fn get_helios_outputs(
+    helios_output: HeliosOutputs,
+    recursive_proof_outputs: Option<RecursionCircuitOutputs>,
+    recursive_proof_inputs: &RecursionCircuitInputs,
+    chain_id: &str,
+) -> RecursionCircuitOutputs {
+    // The execution height and state root the proof chain was at before this link, none
+    // before the first link
+    let previous = recursive_proof_outputs
+        .as_ref()
+        .map_or((0, [0; 32]), |outputs| (outputs.height, outputs.root));
+
+    // Assert that the previous committee of the new proof matches the expected active committee
+    if recursive_proof_outputs.is_some() {
+        let recursive_proof_outputs =
+            recursive_proof_outputs.expect("Failed to unwrap recursive proof outputs");
+
+        // the new head must be greater than the previous head
+        assert!(helios_output.prevHead < helios_output.newHead);
+
+        if helios_output.prevSyncCommitteeHash != recursive_proof_outputs.active_committee {
+            panic!("Sync committee mismatch!");
+        }
+    }
+
+    // The execution payload fields are leaves of the payload root, which is part of the body
+    // root verified against the header proven by Helios
+    let payload_roots = &recursive_proof_inputs.electra_body_roots.payload_roots;
+
+    // Commit the outputs required by the wrapper circuit
+    RecursionCircuitOutputs {
+        active_committee: helios_output
+            .syncCommitteeHash
+            .to_vec()
+            .try_into()
+            .expect("Failed to fit committeeHash into slice"),
+        previous_committee: helios_output
+            .prevSyncCommitteeHash
+            .to_vec()
+            .try_into()
+            .expect("Failed to unwrap recursive proof outputs"),
+        // A zero hash means the proven update didn't carry the next sync committee
+        next_committee: Some(helios_output.nextSyncCommitteeHash.0)
+            .filter(|hash| *hash != [0; 32]),
+        root: payload_roots.state_root,
+        height: unpad_u64(&payload_roots.block_number),
+        block_hash: payload_roots.block_hash,
+        timestamp: unpad_u64(&payload_roots.timestamp),
+        receipts_root: payload_roots.receipts_root,
+        vk: recursive_proof_inputs.recursive_vk.clone(),
+        previous_height: previous.0,
+        previous_root: previous.1,
+        chain_id: chain_id.to_string(),
+    }
+}
+
+// the block height and timestamp leaves in the merkle tree were padded to 32 bytes,
+// so we need to unpad them
+fn unpad_u64(padded: &[u8; 32]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&padded[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+// the recursive vk is the digest of the vk as 8 big-endian words, 0x-prefixed hex
+fn vk_digest(vk: &str) -> [u32; 8] {
+    let hex = vk.strip_prefix("0x").unwrap_or(vk);
+    assert_eq!(hex.len(), 64, "Invalid recursive vk digest");
+    let mut digest = [0u32; 8];
+    for (i, word) in digest.iter_mut().enumerate() {
+        *word = u32::from_str_radix(&hex[i * 8..(i + 1) * 8], 16)
+            .expect("Invalid recursive vk digest");
+    }
+    digest
+}
@@ -7,20 +7,22 @@ sp1_zkvm::entrypoint!(main);
 use alloy_primitives::U256;
 use alloy_sol_types::SolValue;
 use beacon_electra::merkleize_header;
-use helios_recursion_types::{RecursionCircuitInputs, RecursionCircuitOutputs};
+use helios_recursion_types::{
+    RecursionCircuitConstants, RecursionCircuitInputs, RecursionCircuitOutputs, decode_outputs,
+    encode_outputs,
+};
 use sp1_helios_primitives::types::ProofOutputs as HeliosOutputs;
 use sp1_verifier::Groth16Verifier;
 
-// The trusted sync committee hash that was active at the trusted slot.
-// This is used to verify the initial state when starting from the trusted slot.
-const TRUSTED_SYNC_COMMITTEE_HASH: [u8; 32] = [42, 127, 126, 117, 72, 179, 28, 141, 55, 33, 177, 213, 151, 94, 45, 208, 226, 255, 98, 136, 212, 174, 252, 91, 254, 248, 107, 95, 40, 53, 223, 67];
-
-// The trusted slot number from which we start our light client chain.
-// This must be a slot where we have verified the sync committee hash.
-const TRUSTED_HEAD: u64 = 11715392;
-const HELIOS_VK: &str = "0x00cd47e188eeeab95c3c666088b928ff8243f8dd8d6e94f49795013bcd6231f0";
+// The trusted constants, generated for the network selected by SOURCE_CHAIN_ID, which binds
+// the proof chain to that network. The sync committee hash that was active at the trusted
+// slot verifies the initial state when starting from the trusted slot.
+static CONSTANTS: &[u8] = include_bytes!("../constants.bin");
 
 pub fn main() {
+    let constants: RecursionCircuitConstants =
+        borsh::from_slice(CONSTANTS).expect("Failed to deserialize the trusted constants");
+
     // Deserialize the circuit inputs which contain the Helios proof and previous wrapper proof
     let inputs: RecursionCircuitInputs =
         borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
@@ -31,8 +33,6 @@ pub fn main() {
     // Compute the Merkle root of the Electra block header
     let electra_block_header_root = merkleize_header(inputs.electra_header.clone());
     let electra_body_root = inputs.electra_body_roots.merkelize();
-    let state_root = inputs.electra_body_roots.payload_roots.state_root;
-    let height = inputs.electra_body_roots.payload_roots.block_number;
 
     // Decode the Helios proof outputs which contain the new header information
     let helios_output: HeliosOutputs =
@@ -42,31 +42,27 @@ pub fn main() {
     assert_eq!(inputs.electra_header.body_root, electra_body_root);
 
     // Verify that the header root matches the one from the Helios light client
-    assert_eq!(
-        electra_block_header_root.to_vec(),
-        helios_output.newHeader.to_vec()
-    );
+    assert_eq!(electra_block_header_root, helios_output.newHeader.0);
 
     // Verify the Helios proof using Groth16 verification
     Groth16Verifier::verify(
         &inputs.helios_proof,
         &inputs.helios_public_values,
-        // todo: hardcode this verifying key (must be the Helios VK)
-        HELIOS_VK,
+        &constants.helios_vk,
         groth16_vk,
     )
     .expect("Failed to verify helios zk light client update");
 
-    if inputs.previous_head == TRUSTED_HEAD {
+    if inputs.previous_head == constants.trusted_head {
         // If this is the first proof after the trusted slot, verify the sync committee hash
         assert_eq!(
             helios_output.prevSyncCommitteeHash.to_vec(),
-            TRUSTED_SYNC_COMMITTEE_HASH
+            constants.trusted_sync_committee_hash
         );
 
-        let outputs = get_helios_outputs(helios_output, None, &inputs, &state_root, &height);
+        let outputs = get_helios_outputs(helios_output, None, &inputs, &constants.chain_id);
 
-        sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
     } else {
         // For subsequent proofs, verify the previous wrapper proof to ensure continuity
         Groth16Verifier::verify(
@@ -84,23 +80,29 @@ pub fn main() {
         .expect("Failed to verify previous proof");
 
         // deserialize the inputs required for the recursive verification
-        let recursive_proof_outputs: RecursionCircuitOutputs = borsh::from_slice(
+        let recursive_proof_outputs: RecursionCircuitOutputs = decode_outputs(
             &inputs
                 .recursive_public_values
                 .as_ref()
                 .expect("Previous public values is not provided"),
         )
         .unwrap();
+        // The circuit can't embed its own vk, so it is bound by induction: every link must be
+        // verified with the vk committed by the previous one, and the wrapper circuit pins the
+        // vk of the last link
+        assert_eq!(
+            recursive_proof_outputs.vk, inputs.recursive_vk,
+            "Previous proof committed another recursive vk"
+        );
 
         let outputs = get_helios_outputs(
             helios_output,
             Some(recursive_proof_outputs),
             &inputs,
-            &state_root,
-            &height,
+            &constants.chain_id,
         );
 
-        sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
     }
 }
 
@@ -108,9 +110,14 @@ fn get_helios_outputs(
     helios_output: HeliosOutputs,
     recursive_proof_outputs: Option<RecursionCircuitOutputs>,
     recursive_proof_inputs: &RecursionCircuitInputs,
-    state_root: &[u8; 32],
-    height: &[u8; 32],
+    chain_id: &str,
 ) -> RecursionCircuitOutputs {
+    // The execution height and state root the proof chain was at before this link, none
+    // before the first link
+    let previous = recursive_proof_outputs
+        .as_ref()
+        .map_or((0, [0; 32]), |outputs| (outputs.height, outputs.root));
+
     // Assert that the previous committee of the new proof matches the expected active committee
     if recursive_proof_outputs.is_some() {
         let recursive_proof_outputs =
@@ -124,6 +131,10 @@ fn get_helios_outputs(
         }
     }
 
+    // The execution payload fields are leaves of the payload root, which is part of the body
+    // root verified against the header proven by Helios
+    let payload_roots = &recursive_proof_inputs.electra_body_roots.payload_roots;
+
     // Commit the outputs required by the wrapper circuit
     RecursionCircuitOutputs {
         active_committee: helios_output
@@ -136,14 +147,24 @@ fn get_helios_outputs(
             .to_vec()
             .try_into()
             .expect("Failed to unwrap recursive proof outputs"),
-        root: state_root.to_vec().try_into().unwrap(),
-        height: unpad_block_number(height),
+        // A zero hash means the proven update didn't carry the next sync committee
+        next_committee: Some(helios_output.nextSyncCommitteeHash.0)
+            .filter(|hash| *hash != [0; 32]),
+        root: payload_roots.state_root,
+        height: unpad_u64(&payload_roots.block_number),
+        block_hash: payload_roots.block_hash,
+        timestamp: unpad_u64(&payload_roots.timestamp),
+        receipts_root: payload_roots.receipts_root,
         vk: recursive_proof_inputs.recursive_vk.clone(),
+        previous_height: previous.0,
+        previous_root: previous.1,
+        chain_id: chain_id.to_string(),
     }
 }
 
-// the block height leaf in the merkle tree was padded to 32 bytes, so we need to unpad it
-fn unpad_block_number(padded: &[u8; 32]) -> u64 {
+// the block height and timestamp leaves in the merkle tree were padded to 32 bytes,
+// so we need to unpad them
+fn unpad_u64(padded: &[u8; 32]) -> u64 {
     let mut bytes = [0u8; 8];
     bytes.copy_from_slice(&padded[..8]);
     u64::from_le_bytes(bytes)
@@ -5,6 +5,24 @@ use alloc::vec::Vec;
 
 use beacon_electra::types::electra::{ElectraBlockBodyRoots, ElectraBlockHeader};
 use borsh::{BorshDeserialize, BorshSerialize};
+
+lightwave_codec::outputs_codec!(1);
+
+/// The trusted constants of the recursion circuit. The service writes them borsh encoded to
+/// `circuit/constants.bin` when generating the circuit, and the circuit embeds the file with
+/// `include_bytes!`, so the constants are part of the ELF and bound to its vk.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct RecursionCircuitConstants {
+    // the sync committee hash that was active at the trusted slot
+    pub trusted_sync_committee_hash: [u8; 32],
+    // the trusted slot the light client chain starts from
+    pub trusted_head: u64,
+    // the vk of the Helios program
+    pub helios_vk: String,
+    // the CAIP-2 chain id of the execution chain, committed by every proof
+    pub chain_id: String,
+}
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct RecursionCircuitInputs {
     pub electra_body_roots: ElectraBlockBodyRoots,
@@ -23,12 +41,26 @@ pub struct RecursionCircuitOutputs {
     pub active_committee: [u8; 32],
     // previous committee
     pub previous_committee: [u8; 32],
+    // the committee of the period after the active one, once the light client knows it
+    pub next_committee: Option<[u8; 32]>,
     // the execution state root
     pub root: [u8; 32],
     // the height of the execution block
     pub height: u64,
+    // the hash of the execution block
+    pub block_hash: [u8; 32],
+    // the timestamp of the execution block
+    pub timestamp: u64,
+    // the receipts root of the execution block, to prove logs against
+    pub receipts_root: [u8; 32],
     // the vk that was used to verify the previous recursive proof
     pub vk: String,
+    // the execution height and state root committed by the previous proof, zero for the first
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    // the CAIP-2 chain id of the execution chain (e.g. `eip155:1`), fixed when the circuit is
+    // generated so that proofs of different chains can't be mistaken for each other
+    pub chain_id: String,
 }
 
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
@@ -37,8 +69,5 @@ pub struct WrapperCircuitInputs {
     pub recursive_public_values: Vec<u8>,
 }
 
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
-pub struct WrapperCircuitOutputs {
-    pub height: u64,
-    pub root: [u8; 32],
-}
+/// The outputs committed by the wrapper circuit
+pub type WrapperCircuitOutputs = lightwave_codec::WrapperOutputs<OUTPUTS_VERSION>;
@@ -0,0 +1,54 @@
+// This is the wrapper circuit of the recursion circuit with SP1 proof aggregation, generated
+// instead of the Groth16 blueprint with RECURSION_AGGREGATION=compressed. It verifies the
+// compressed recursive proof the prover writes to the stdin and is the only Groth16 proof of a
+// round besides the Helios proof.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use helios_recursion_types::{
+    RecursionCircuitOutputs, WrapperCircuitInputs, WrapperCircuitOutputs, decode_outputs,
+    encode_outputs,
+};
+use sha2::{Digest, Sha256};
+
+const RECURSIVE_VK: &str = { recursive_vk };
+const RECURSIVE_VK_DIGEST: [u32; 8] = { recursive_vk_digest };
+// Whether the outputs are committed ABI encoded for EVM contracts instead of borsh encoded
+const ABI_OUTPUTS: bool = { abi_outputs };
+
+fn main() {
+    // Deserialize the wrapper circuit inputs which contain the recursive public values
+    let inputs: WrapperCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    let recursive_outputs: RecursionCircuitOutputs =
+        decode_outputs(&inputs.recursive_public_values)
+            .expect("Failed to deserialize recursive Outputs");
+
+    // Assert that the VK used for the verification of the recursive proof (if any) matches
+    // exactly the VK of the recursive circuit.
+    // This is required for every proof except the first one.
+    assert_eq!(recursive_outputs.vk, RECURSIVE_VK);
+    // Get the public outputs from the recursive proof
+    let public_outputs = inputs.recursive_public_values;
+
+    // Verify the compressed recursive proof against the vk digest of the recursive circuit
+    let public_values_digest = Sha256::digest(&public_outputs);
+    sp1_zkvm::lib::verify::verify_sp1_proof(&RECURSIVE_VK_DIGEST, &public_values_digest.into());
+
+    // Re-commit the public outputs after recursive proof verification
+    // This ensures the outputs are available for the next proof in the chain
+    let outputs = WrapperCircuitOutputs {
+        height: recursive_outputs.height,
+        root: recursive_outputs.root,
+        previous_height: recursive_outputs.previous_height,
+        previous_root: recursive_outputs.previous_root,
+        chain_id: recursive_outputs.chain_id,
+    };
+    let public_values = if ABI_OUTPUTS {
+        outputs.abi_encode()
+    } else {
+        encode_outputs(&outputs)
+    };
+    sp1_zkvm::io::commit_slice(&public_values);
+}
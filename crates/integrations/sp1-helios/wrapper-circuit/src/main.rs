@@ -5,11 +5,14 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 use helios_recursion_types::{
-    RecursionCircuitOutputs, WrapperCircuitInputs, WrapperCircuitOutputs,
+    RecursionCircuitOutputs, WrapperCircuitInputs, WrapperCircuitOutputs, decode_outputs,
+    encode_outputs,
 };
 use sp1_verifier::Groth16Verifier;
 
 const RECURSIVE_VK: &str = "0x0034e4a559df3be8975c94d57857e1e6fbfc4d26177b8f60ccd2dd86e75fd8c7";
+// Whether the outputs are committed ABI encoded for EVM contracts instead of borsh encoded
+const ABI_OUTPUTS: bool = false;
 
 fn main() {
     // Get the Groth16 verification key for proof verification
@@ -20,7 +23,7 @@ fn main() {
         borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
 
     let recursive_outputs: RecursionCircuitOutputs =
-        borsh::from_slice(&inputs.recursive_public_values)
+        decode_outputs(&inputs.recursive_public_values)
             .expect("Failed to deserialize recursive Outputs");
 
     // Assert that the VK used for the verification of the recursive proof (if any) matches
@@ -45,6 +48,14 @@ fn main() {
     let outputs = WrapperCircuitOutputs {
         height: recursive_outputs.height,
         root: recursive_outputs.root,
+        previous_height: recursive_outputs.previous_height,
+        previous_root: recursive_outputs.previous_root,
+        chain_id: recursive_outputs.chain_id,
     };
-    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+    let public_values = if ABI_OUTPUTS {
+        outputs.abi_encode()
+    } else {
+        encode_outputs(&outputs)
+    };
+    sp1_zkvm::io::commit_slice(&public_values);
 }
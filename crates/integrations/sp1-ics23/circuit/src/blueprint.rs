@@ -0,0 +1,57 @@
+// This is the ICS23 proof circuit. It verifies the ICS23 existence proof of a value in a
+// Cosmos SDK store against the app hash of a header proven by the Tendermint wrapper circuit,
+// so that Cosmos state is anchored to the Tendermint proof chain.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use ics23_proof_types::{Ics23CircuitInputs, Ics23CircuitOutputs, verify_store_query};
+use sp1_verifier::Groth16Verifier;
+use tendermint::block::Header;
+use tendermint_recursion_types::WrapperCircuitOutputs as TendermintWrapperOutputs;
+
+// The Tendermint wrapper circuit that proves the header hash
+const TENDERMINT_WRAPPER_VK: &str = "{ tendermint_wrapper_vk }";
+
+pub fn main() {
+    // Deserialize the circuit inputs which contain the Tendermint proof and the store proofs
+    let inputs: Ics23CircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // Verify the Tendermint wrapper proof of the header hash
+    Groth16Verifier::verify(
+        &inputs.tendermint_proof,
+        &inputs.tendermint_public_values,
+        TENDERMINT_WRAPPER_VK,
+        groth16_vk,
+    )
+    .expect("Failed to verify Tendermint proof");
+    let tendermint_outputs = TendermintWrapperOutputs::decode(&inputs.tendermint_public_values)
+        .expect("Failed to deserialize Tendermint Outputs");
+
+    // The header must hash to the proven header hash, it then commits the app hash
+    let header: Header =
+        serde_json::from_slice(&inputs.header).expect("Failed to deserialize Header");
+    assert_eq!(header.hash().as_bytes(), tendermint_outputs.root);
+    assert_eq!(header.height.value(), tendermint_outputs.height);
+    let app_hash: [u8; 32] = header
+        .app_hash
+        .as_bytes()
+        .try_into()
+        .expect("App hash must be 32 bytes");
+
+    // Verify the IAVL and multistore proofs of the value against the app hash
+    let value_hash =
+        verify_store_query(&app_hash, &inputs.query).expect("Failed to verify store proofs");
+
+    let outputs = Ics23CircuitOutputs {
+        height: tendermint_outputs.height,
+        app_hash,
+        store_key: inputs.query.store_key,
+        key: inputs.query.key,
+        value_hash,
+    };
+    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+}
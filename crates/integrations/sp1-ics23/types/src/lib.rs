@@ -0,0 +1,130 @@
+// Types shared by the ICS23 proof circuit and the service, and the verification of the ICS23
+// existence proofs of a Cosmos SDK store against the app hash of a Tendermint header.
+
+#![no_std]
+extern crate alloc;
+use alloc::vec::Vec;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use ics23::{
+    CommitmentProof, HostFunctionsManager, ProofSpec, calculate_existence_root,
+    commitment_proof::Proof, iavl_spec, tendermint_spec, verify_membership,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+/// A value of a Cosmos SDK store together with the proofs returned by an `abci_query`
+/// with `prove=true`
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StoreQuery {
+    /// The name of the module store, e.g. `bank`
+    pub store_key: Vec<u8>,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// Protobuf `CommitmentProof` of the key in the IAVL tree of the store
+    pub iavl_proof: Vec<u8>,
+    /// Protobuf `CommitmentProof` of the store root in the multistore
+    pub store_proof: Vec<u8>,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct Ics23CircuitInputs {
+    /// The Tendermint wrapper proof of the header hash
+    pub tendermint_proof: Vec<u8>,
+    pub tendermint_public_values: Vec<u8>,
+    /// The JSON encoded header of the proven block, which commits the app hash
+    pub header: Vec<u8>,
+    pub query: StoreQuery,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct Ics23CircuitOutputs {
+    // the height of the header the app hash was read from
+    pub height: u64,
+    // the app hash the value was proven against
+    pub app_hash: [u8; 32],
+    // the store the value belongs to
+    pub store_key: Vec<u8>,
+    // the key of the value in the store
+    pub key: Vec<u8>,
+    // sha256 of the value
+    pub value_hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidProofEncoding,
+    NotAnExistenceProof,
+    InvalidIavlProof,
+    InvalidStoreProof,
+}
+
+/// Verifies that `proof` proves `key` holds `value` under the returned root
+fn verify_existence(
+    proof: &[u8],
+    spec: &ProofSpec,
+    key: &[u8],
+    value: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let proof = CommitmentProof::decode(proof).map_err(|_| Error::InvalidProofEncoding)?;
+    let Some(Proof::Exist(existence)) = &proof.proof else {
+        return Err(Error::NotAnExistenceProof);
+    };
+    let root = calculate_existence_root::<HostFunctionsManager>(existence)
+        .map_err(|_| Error::InvalidProofEncoding)?;
+    if !verify_membership::<HostFunctionsManager>(&proof, spec, &root, key, value) {
+        return Err(Error::NotAnExistenceProof);
+    }
+    Ok(root)
+}
+
+/// Verifies the proofs of a store query against an app hash and returns the hash of the value.
+///
+/// The IAVL proof proves the value under the root of the module store, which the multistore
+/// proof proves under the app hash, like IBC verifies the membership of a Cosmos SDK value.
+pub fn verify_store_query(app_hash: &[u8], query: &StoreQuery) -> Result<[u8; 32], Error> {
+    let store_root = verify_existence(&query.iavl_proof, &iavl_spec(), &query.key, &query.value)
+        .map_err(|_| Error::InvalidIavlProof)?;
+    let root = verify_existence(
+        &query.store_proof,
+        &tendermint_spec(),
+        &query.store_key,
+        &store_root,
+    )
+    .map_err(|_| Error::InvalidStoreProof)?;
+    if root != app_hash {
+        return Err(Error::InvalidStoreProof);
+    }
+    Ok(Sha256::digest(&query.value).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_rejects_invalid_proofs() {
+        let query = StoreQuery {
+            store_key: b"bank".to_vec(),
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            iavl_proof: vec![0xff, 0xff],
+            store_proof: Vec::new(),
+        };
+        assert_eq!(
+            verify_store_query(&[0; 32], &query),
+            Err(Error::InvalidIavlProof)
+        );
+
+        // An empty commitment proof decodes, but is not an existence proof
+        let empty = StoreQuery {
+            iavl_proof: Vec::new(),
+            ..query
+        };
+        assert_eq!(
+            verify_store_query(&[0; 32], &empty),
+            Err(Error::InvalidIavlProof)
+        );
+    }
+}
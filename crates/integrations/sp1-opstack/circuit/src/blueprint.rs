@@ -0,0 +1,55 @@
+// This is the OP Stack output circuit. It reads the output root of a rollup from the
+// storage of its L1 contracts, at an execution state root proven by the Helios wrapper
+// circuit, so that the rollup state is anchored to the Helios proof chain.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use helios_recursion_types::WrapperCircuitOutputs as HeliosWrapperOutputs;
+use opstack_types::{OutputCircuitInputs, OutputCircuitOutputs, OutputSource, verify_claim};
+use sp1_verifier::Groth16Verifier;
+
+// The Helios wrapper circuit that proves the L1 execution state root
+const HELIOS_WRAPPER_VK: &str = "{ helios_wrapper_vk }";
+
+// The L1 contract of the rollup the outputs are read from and the way it stores them.
+// Both are generated from the OP_STACK_* environment variables.
+const CONTRACT: [u8; 20] = { contract };
+const OUTPUT_SOURCE: OutputSource = OutputSource::{ output_source };
+
+pub fn main() {
+    // Deserialize the circuit inputs which contain the Helios proof and the storage proofs
+    let inputs: OutputCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // Verify the Helios wrapper proof of the L1 execution state root
+    Groth16Verifier::verify(
+        &inputs.helios_proof,
+        &inputs.helios_public_values,
+        HELIOS_WRAPPER_VK,
+        groth16_vk,
+    )
+    .expect("Failed to verify Helios proof");
+    let helios_outputs = HeliosWrapperOutputs::decode(&inputs.helios_public_values)
+        .expect("Failed to deserialize Helios Outputs");
+
+    // Verify the account and storage proofs of the output proposal against the state root
+    let (l2_block_number, output_root) = verify_claim(
+        &helios_outputs.root,
+        OUTPUT_SOURCE,
+        &CONTRACT,
+        &inputs.claim,
+    )
+    .expect("Failed to verify output proposal");
+
+    let outputs = OutputCircuitOutputs {
+        l1_height: helios_outputs.height,
+        l1_state_root: helios_outputs.root,
+        contract: CONTRACT,
+        l2_block_number,
+        output_root,
+    };
+    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+}
@@ -0,0 +1,274 @@
+// Types shared by the OP Stack output circuit and the service, and the verification of the
+// L2 output roots read from the storage of the L1 contracts of a rollup.
+
+#![no_std]
+extern crate alloc;
+use alloc::vec::Vec;
+
+use alloy_primitives::{U256, keccak256};
+use borsh::{BorshDeserialize, BorshSerialize};
+pub use storage_proof_types::{AccountProof, StorageProof};
+use storage_proof_types::{verify_account, verify_storage};
+
+/// Storage slot of the `l2Outputs` array of the L2OutputOracle
+pub const L2_OUTPUTS_SLOT: u64 = 3;
+/// Storage slot of the `_disputeGames` mapping of the DisputeGameFactory
+pub const DISPUTE_GAMES_SLOT: u64 = 103;
+/// `GameStatus.DEFENDER_WINS`, the root claim of the game was not successfully challenged
+pub const DEFENDER_WINS: u8 = 2;
+
+/// The L1 contract the output roots of a rollup are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum OutputSource {
+    /// Outputs proposed to the L2OutputOracle, used by chains without fault proofs
+    L2OutputOracle,
+    /// Root claims of the dispute games of `game_type` that resolved in favour of the proposer
+    DisputeGameFactory { game_type: u32 },
+}
+
+/// An output proposal together with the proofs of the L1 storage it is read from
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum OutputClaim {
+    L2OutputOracle {
+        oracle: AccountProof,
+        index: u64,
+        /// `l2Outputs[index].outputRoot`
+        output_root: StorageProof,
+        /// `l2Outputs[index].timestamp` and `l2Outputs[index].l2BlockNumber`, packed in one slot
+        block: StorageProof,
+    },
+    DisputeGame {
+        factory: AccountProof,
+        root_claim: [u8; 32],
+        l2_block_number: u64,
+        /// `_disputeGames[uuid]`, the packed id of the game created for the claim
+        game_id: StorageProof,
+        game: AccountProof,
+        /// Slot 0 of the game, which holds its status
+        status: StorageProof,
+    },
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct OutputCircuitInputs {
+    /// The Helios wrapper proof of the L1 execution state root
+    pub helios_proof: Vec<u8>,
+    pub helios_public_values: Vec<u8>,
+    pub claim: OutputClaim,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct OutputCircuitOutputs {
+    // the L1 execution height the output was read at
+    pub l1_height: u64,
+    // the L1 execution state root the output was read from
+    pub l1_state_root: [u8; 32],
+    // the contract the output was read from
+    pub contract: [u8; 20],
+    // the L2 block the output root commits to
+    pub l2_block_number: u64,
+    // the L2 output root
+    pub output_root: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidAccountProof,
+    InvalidStorageProof,
+    UnexpectedContract,
+    UnexpectedSlot,
+    UnexpectedSource,
+    OutputNotFound,
+    GameNotFound,
+    GameTypeMismatch,
+    GameNotResolved,
+}
+
+impl From<storage_proof_types::Error> for Error {
+    fn from(error: storage_proof_types::Error) -> Self {
+        match error {
+            storage_proof_types::Error::InvalidAccountProof => Error::InvalidAccountProof,
+            storage_proof_types::Error::InvalidStorageProof => Error::InvalidStorageProof,
+        }
+    }
+}
+
+/// Slots of the output root and of the packed timestamp and block number of `l2Outputs[index]`
+pub fn l2_output_slots(index: u64) -> ([u8; 32], [u8; 32]) {
+    let data = U256::from_be_bytes(keccak256(U256::from(L2_OUTPUTS_SLOT).to_be_bytes::<32>()).0);
+    let output_root = data.wrapping_add(U256::from(index) * U256::from(2));
+    (
+        output_root.to_be_bytes(),
+        output_root.wrapping_add(U256::from(1)).to_be_bytes(),
+    )
+}
+
+/// The uuid of a fault dispute game, `keccak256(abi.encode(gameType, rootClaim, extraData))`
+/// where the extra data is the L2 block number of the claim
+pub fn game_uuid(game_type: u32, root_claim: &[u8; 32], l2_block_number: u64) -> [u8; 32] {
+    let mut encoded = Vec::with_capacity(160);
+    encoded.extend_from_slice(&U256::from(game_type).to_be_bytes::<32>());
+    encoded.extend_from_slice(root_claim);
+    // offset and length of the dynamic `bytes` extra data
+    encoded.extend_from_slice(&U256::from(0x60).to_be_bytes::<32>());
+    encoded.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    encoded.extend_from_slice(&U256::from(l2_block_number).to_be_bytes::<32>());
+    keccak256(encoded).0
+}
+
+/// Slot of `_disputeGames[uuid]`
+pub fn dispute_game_slot(uuid: &[u8; 32]) -> [u8; 32] {
+    let mut key = [0u8; 64];
+    key[..32].copy_from_slice(uuid);
+    key[32..].copy_from_slice(&U256::from(DISPUTE_GAMES_SLOT).to_be_bytes::<32>());
+    keccak256(key).0
+}
+
+/// Verifies the proofs of an output proposal against an L1 execution state root and
+/// returns the L2 block number and output root it commits to
+pub fn verify_claim(
+    state_root: &[u8; 32],
+    source: OutputSource,
+    contract: &[u8; 20],
+    claim: &OutputClaim,
+) -> Result<(u64, [u8; 32]), Error> {
+    match (source, claim) {
+        (
+            OutputSource::L2OutputOracle,
+            OutputClaim::L2OutputOracle {
+                oracle,
+                index,
+                output_root,
+                block,
+            },
+        ) => {
+            if oracle.address != *contract {
+                return Err(Error::UnexpectedContract);
+            }
+            verify_account(state_root, oracle)?;
+
+            let (output_root_slot, block_slot) = l2_output_slots(*index);
+            if output_root.slot != output_root_slot || block.slot != block_slot {
+                return Err(Error::UnexpectedSlot);
+            }
+            verify_storage(&oracle.storage_root, output_root)?;
+            verify_storage(&oracle.storage_root, block)?;
+            if output_root.value == [0; 32] {
+                return Err(Error::OutputNotFound);
+            }
+
+            // `timestamp` fills the low and `l2BlockNumber` the high 16 bytes of the slot
+            let l2_block_number = u128::from_be_bytes(block.value[..16].try_into().unwrap());
+            Ok((
+                l2_block_number
+                    .try_into()
+                    .map_err(|_| Error::OutputNotFound)?,
+                output_root.value,
+            ))
+        }
+        (
+            OutputSource::DisputeGameFactory { game_type },
+            OutputClaim::DisputeGame {
+                factory,
+                root_claim,
+                l2_block_number,
+                game_id,
+                game,
+                status,
+            },
+        ) => {
+            if factory.address != *contract {
+                return Err(Error::UnexpectedContract);
+            }
+            verify_account(state_root, factory)?;
+
+            // The factory indexes games by their type, root claim and extra data,
+            // so the game proves the claim was made for this L2 block
+            let uuid = game_uuid(game_type, root_claim, *l2_block_number);
+            if game_id.slot != dispute_game_slot(&uuid) {
+                return Err(Error::UnexpectedSlot);
+            }
+            verify_storage(&factory.storage_root, game_id)?;
+            if game_id.value == [0; 32] {
+                return Err(Error::GameNotFound);
+            }
+
+            // GameId packs the game type (4 bytes), creation timestamp (8 bytes) and proxy address
+            if u32::from_be_bytes(game_id.value[..4].try_into().unwrap()) != game_type {
+                return Err(Error::GameTypeMismatch);
+            }
+            if game.address[..] != game_id.value[12..] {
+                return Err(Error::UnexpectedContract);
+            }
+            verify_account(state_root, game)?;
+
+            // Slot 0 packs `createdAt` (8 bytes), `resolvedAt` (8 bytes) and `status`
+            if status.slot != [0; 32] {
+                return Err(Error::UnexpectedSlot);
+            }
+            verify_storage(&game.storage_root, status)?;
+            if status.value[15] != DEFENDER_WINS {
+                return Err(Error::GameNotResolved);
+            }
+            Ok((*l2_block_number, *root_claim))
+        }
+        _ => Err(Error::UnexpectedSource),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l2_output_slots() {
+        // keccak256(uint256(3)), the first element of `l2Outputs`
+        let first = [
+            0xc2, 0x57, 0x5a, 0x0e, 0x9e, 0x59, 0x3c, 0x00, 0xf9, 0x59, 0xf8, 0xc9, 0x2f, 0x12,
+            0xdb, 0x28, 0x69, 0xc3, 0x39, 0x5a, 0x3b, 0x05, 0x02, 0xd0, 0x5e, 0x25, 0x16, 0x44,
+            0x6f, 0x71, 0xf8, 0x5b,
+        ];
+        let (output_root, block) = l2_output_slots(0);
+        assert_eq!(output_root, first);
+        assert_eq!(block[31], 0x5c);
+        let (output_root, block) = l2_output_slots(1);
+        assert_eq!(output_root[31], 0x5d);
+        assert_eq!(block[31], 0x5e);
+    }
+
+    #[test]
+    fn test_source_mismatch() {
+        let account = AccountProof {
+            address: [1; 20],
+            nonce: 0,
+            balance: [0; 32],
+            storage_root: [0; 32],
+            code_hash: [0; 32],
+            proof: Vec::new(),
+        };
+        let storage = StorageProof {
+            slot: [0; 32],
+            value: [0; 32],
+            proof: Vec::new(),
+        };
+        let claim = OutputClaim::L2OutputOracle {
+            oracle: account,
+            index: 0,
+            output_root: storage.clone(),
+            block: storage,
+        };
+        assert_eq!(
+            verify_claim(
+                &[0; 32],
+                OutputSource::DisputeGameFactory { game_type: 0 },
+                &[1; 20],
+                &claim
+            ),
+            Err(Error::UnexpectedSource)
+        );
+        assert_eq!(
+            verify_claim(&[0; 32], OutputSource::L2OutputOracle, &[2; 20], &claim),
+            Err(Error::UnexpectedContract)
+        );
+    }
+}
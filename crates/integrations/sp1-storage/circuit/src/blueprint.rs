@@ -0,0 +1,44 @@
+// This is the storage proof circuit. It verifies account and storage proofs against an
+// execution state root proven by the Helios wrapper circuit, so that the values of storage
+// slots on Ethereum are anchored to the Helios proof chain.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use helios_recursion_types::WrapperCircuitOutputs as HeliosWrapperOutputs;
+use sp1_verifier::Groth16Verifier;
+use storage_proof_types::{StorageCircuitInputs, StorageCircuitOutputs, verify_storage_slots};
+
+// The Helios wrapper circuit that proves the execution state root
+const HELIOS_WRAPPER_VK: &str = "{ helios_wrapper_vk }";
+
+pub fn main() {
+    // Deserialize the circuit inputs which contain the Helios proof and the storage proofs
+    let inputs: StorageCircuitInputs =
+        borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
+
+    // Get the Groth16 verification key for proof verification
+    let groth16_vk: &[u8] = *sp1_verifier::GROTH16_VK_BYTES;
+
+    // Verify the Helios wrapper proof of the execution state root
+    Groth16Verifier::verify(
+        &inputs.helios_proof,
+        &inputs.helios_public_values,
+        HELIOS_WRAPPER_VK,
+        groth16_vk,
+    )
+    .expect("Failed to verify Helios proof");
+    let helios_outputs = HeliosWrapperOutputs::decode(&inputs.helios_public_values)
+        .expect("Failed to deserialize Helios Outputs");
+
+    // Verify the account proof and the storage proofs against the state root
+    let slots = verify_storage_slots(&helios_outputs.root, &inputs.account, &inputs.storage)
+        .expect("Failed to verify storage proofs");
+
+    let outputs = StorageCircuitOutputs {
+        height: helios_outputs.height,
+        state_root: helios_outputs.root,
+        address: inputs.account.address,
+        slots,
+    };
+    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+}
@@ -0,0 +1,159 @@
+// Types shared by the storage proof circuit and the service, and the verification of the
+// account and storage proofs returned by `eth_getProof` against an execution state root.
+
+#![no_std]
+extern crate alloc;
+use alloc::vec::Vec;
+
+use alloy_primitives::{B256, Bytes, U256, keccak256};
+use alloy_rlp::RlpEncodable;
+use alloy_trie::{Nibbles, proof::verify_proof};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// An account proof as returned by `eth_getProof`
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct AccountProof {
+    pub address: [u8; 20],
+    pub nonce: u64,
+    pub balance: [u8; 32],
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// A storage proof as returned by `eth_getProof`, the value is a big-endian word
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageProof {
+    pub slot: [u8; 32],
+    pub value: [u8; 32],
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// A storage slot and the big-endian word it holds
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct StorageSlot {
+    pub slot: [u8; 32],
+    pub value: [u8; 32],
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageCircuitInputs {
+    /// The Helios wrapper proof of the execution state root
+    pub helios_proof: Vec<u8>,
+    pub helios_public_values: Vec<u8>,
+    pub account: AccountProof,
+    pub storage: Vec<StorageProof>,
+}
+
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageCircuitOutputs {
+    // the execution height the storage was read at
+    pub height: u64,
+    // the execution state root the storage was read from
+    pub state_root: [u8; 32],
+    // the account the storage belongs to
+    pub address: [u8; 20],
+    // the proven slots and their values
+    pub slots: Vec<StorageSlot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidAccountProof,
+    InvalidStorageProof,
+}
+
+/// The account leaf of the state trie
+#[derive(RlpEncodable)]
+struct TrieAccount {
+    nonce: u64,
+    balance: U256,
+    storage_root: B256,
+    code_hash: B256,
+}
+
+fn to_bytes(proof: &[Vec<u8>]) -> Vec<Bytes> {
+    proof.iter().cloned().map(Bytes::from).collect()
+}
+
+/// Verifies an account proof against an execution state root
+pub fn verify_account(state_root: &[u8; 32], account: &AccountProof) -> Result<(), Error> {
+    let leaf = alloy_rlp::encode(TrieAccount {
+        nonce: account.nonce,
+        balance: U256::from_be_bytes(account.balance),
+        storage_root: B256::from(account.storage_root),
+        code_hash: B256::from(account.code_hash),
+    });
+    verify_proof(
+        B256::from(*state_root),
+        Nibbles::unpack(keccak256(account.address)),
+        Some(leaf),
+        &to_bytes(&account.proof),
+    )
+    .map_err(|_| Error::InvalidAccountProof)
+}
+
+/// Verifies a storage proof against the storage root of an account, a zero value
+/// is proven by the absence of the slot
+pub fn verify_storage(storage_root: &[u8; 32], storage: &StorageProof) -> Result<(), Error> {
+    let value = U256::from_be_bytes(storage.value);
+    verify_proof(
+        B256::from(*storage_root),
+        Nibbles::unpack(keccak256(storage.slot)),
+        (!value.is_zero()).then(|| alloy_rlp::encode(value)),
+        &to_bytes(&storage.proof),
+    )
+    .map_err(|_| Error::InvalidStorageProof)
+}
+
+/// Verifies the storage proofs of an account against an execution state root and
+/// returns the proven slots
+pub fn verify_storage_slots(
+    state_root: &[u8; 32],
+    account: &AccountProof,
+    storage: &[StorageProof],
+) -> Result<Vec<StorageSlot>, Error> {
+    verify_account(state_root, account)?;
+    storage
+        .iter()
+        .map(|storage| {
+            verify_storage(&account.storage_root, storage)?;
+            Ok(StorageSlot {
+                slot: storage.slot,
+                value: storage.value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_verify_storage_single_leaf() {
+        // A storage trie holding a single slot is a single leaf node
+        let slot = [7u8; 32];
+        let mut value = [0u8; 32];
+        value[31] = 42;
+        let mut leaf = vec![0xe3, 0xa1, 0x20];
+        leaf.extend_from_slice(&keccak256(slot).0);
+        leaf.push(42);
+        let storage_root = keccak256(&leaf).0;
+
+        let storage = StorageProof {
+            slot,
+            value,
+            proof: vec![leaf],
+        };
+        assert_eq!(verify_storage(&storage_root, &storage), Ok(()));
+
+        let mut forged = storage.clone();
+        forged.value[31] = 43;
+        assert_eq!(
+            verify_storage(&storage_root, &forged),
+            Err(Error::InvalidStorageProof)
+        );
+    }
+}
@@ -6,7 +6,10 @@
 
 use sp1_tendermint_primitives::TendermintOutput;
 use sp1_verifier::Groth16Verifier;
-use tendermint_recursion_types::{RecursionCircuitInputs, RecursionCircuitOutputs};
+use tendermint::block::Header;
+use tendermint_recursion_types::{
+    RecursionCircuitInputs, RecursionCircuitOutputs, decode_outputs, encode_outputs,
+};
 sp1_zkvm::entrypoint!(main);
 
 // The trusted slot number from which we start our light client chain.
@@ -14,12 +17,96 @@ sp1_zkvm::entrypoint!(main);
 const TRUSTED_HEIGHT: u64 = { trusted_height };
 const TRUSTED_ROOT: [u8; 32] = { trusted_root };
 const TENDERMINT_VK: &str = "{ tendermint_vk }";
+// The CAIP-2 chain id committed by every proof, telling the proofs of different chains apart
+const CHAIN_ID: &str = "{ chain_id }";
+// The longest time between the trusted and the target header of a link, past which the
+// validators of the trusted header may have unbonded and the chain can no longer be extended
+const TRUSTING_PERIOD_SECS: u64 = { trusting_period_secs };
+// The most blocks a link advances, the bisection of a round keeps its hops below it
+const MAX_HEIGHT_ADVANCE: u64 = { max_height_advance };
+// The upgrade the chain halted at, approved by the operator: the last height of the halted
+// chain, then the first trusted header and the CAIP-2 chain id of the restarted chain. An
+// upgrade height of 0 configures none
+const UPGRADE_HEIGHT: u64 = { upgrade_height };
+const UPGRADE_TRUSTED_HEIGHT: u64 = { upgrade_trusted_height };
+const UPGRADE_TRUSTED_ROOT: [u8; 32] = { upgrade_trusted_root };
+const UPGRADE_CHAIN_ID: &str = "{ upgrade_chain_id }";
+
+/// The time of a header in unix nanoseconds
+fn unix_nanos(header: &Header) -> u64 {
+    u64::try_from(header.time.unix_timestamp_nanos())
+        .expect("Header time must be after the unix epoch")
+}
+
+/// Re-anchors the proof chain at the approved first header of the chain restarted after the
+/// upgrade, extending the previous proof of the last height before the halt
+fn reanchor(inputs: RecursionCircuitInputs, groth16_vk: &[u8]) -> RecursionCircuitOutputs {
+    assert!(UPGRADE_HEIGHT != 0, "No chain upgrade to re-anchor at");
+    let recursive_public_values = inputs
+        .recursive_public_values
+        .as_ref()
+        .expect("Previous public values is not provided");
+    let previous: RecursionCircuitOutputs =
+        decode_outputs(recursive_public_values).expect("Failed to deserialize Recursive Outputs");
+    assert_eq!(
+        previous.height, UPGRADE_HEIGHT,
+        "Previous proof is not at the upgrade height"
+    );
+    assert_eq!(
+        previous.vk, inputs.recursive_vk,
+        "Previous proof committed another recursive vk"
+    );
+    Groth16Verifier::verify(
+        inputs
+            .recursive_proof
+            .as_ref()
+            .expect("Previous proof is not provided"),
+        recursive_public_values,
+        &inputs.recursive_vk,
+        groth16_vk,
+    )
+    .expect("Failed to verify previous proof");
+
+    // The approved header of the restarted chain provides the header fields of the link
+    let header: Header =
+        serde_json::from_slice(&inputs.target_header).expect("Failed to deserialize Header");
+    assert_eq!(header.hash().as_bytes(), UPGRADE_TRUSTED_ROOT);
+    assert_eq!(header.height.value(), UPGRADE_TRUSTED_HEIGHT);
+    RecursionCircuitOutputs {
+        root: UPGRADE_TRUSTED_ROOT,
+        height: UPGRADE_TRUSTED_HEIGHT,
+        vk: inputs.recursive_vk,
+        previous_height: previous.height,
+        previous_root: previous.root,
+        chain_id: UPGRADE_CHAIN_ID.to_string(),
+        app_hash: header
+            .app_hash
+            .as_bytes()
+            .try_into()
+            .expect("App hash must be 32 bytes"),
+        next_validators_hash: header
+            .next_validators_hash
+            .as_bytes()
+            .try_into()
+            .expect("Next validators hash must be 32 bytes"),
+        time: unix_nanos(&header),
+        transition: true,
+    }
+}
 
 pub fn main() {
     // Deserialize the circuit inputs which contain the Tendermint proof and previous wrapper proof
     let inputs: RecursionCircuitInputs =
         borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
 
+    // A transition link re-anchors the proof chain on the chain restarted after an upgrade,
+    // it has no Tendermint proof
+    if inputs.transition {
+        let outputs = reanchor(inputs, *sp1_verifier::GROTH16_VK_BYTES);
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+        return;
+    }
+
     let tendermintx_output: TendermintOutput =
         serde_json::from_slice(&inputs.tendermint_public_values)
             .expect("Failed to deserialize Tendermint Output");
@@ -35,17 +122,93 @@ pub fn main() {
         groth16_vk,
     )
     .expect("Failed to verify Tendermint proof");
-    if inputs.trusted_height == TRUSTED_HEIGHT {
+
+    // The trusted header must hash to the trusted header hash of the Tendermint proof, it
+    // provides the time the trusting period runs from
+    let trusted_header: Header =
+        serde_json::from_slice(&inputs.trusted_header).expect("Failed to deserialize Header");
+    assert_eq!(
+        trusted_header.hash().as_bytes(),
+        tendermintx_output.trusted_header_hash
+    );
+    assert_eq!(
+        trusted_header.height.value(),
+        tendermintx_output.trusted_height
+    );
+
+    // The target header must hash to the proven header hash, it then commits the app hash,
+    // next validators hash and time of the target block
+    let target_header: Header =
+        serde_json::from_slice(&inputs.target_header).expect("Failed to deserialize Header");
+    assert_eq!(
+        target_header.hash().as_bytes(),
+        tendermintx_output.target_header_hash
+    );
+    assert_eq!(
+        target_header.height.value(),
+        tendermintx_output.target_height
+    );
+    let app_hash: [u8; 32] = target_header
+        .app_hash
+        .as_bytes()
+        .try_into()
+        .expect("App hash must be 32 bytes");
+    let next_validators_hash: [u8; 32] = target_header
+        .next_validators_hash
+        .as_bytes()
+        .try_into()
+        .expect("Next validators hash must be 32 bytes");
+    let time = unix_nanos(&target_header);
+
+    // A stale chain can't be extended: the target header must be within the trusting period
+    // of the trusted header
+    let trusted_time = unix_nanos(&trusted_header);
+    assert!(
+        time > trusted_time,
+        "Target header is older than the trusted header"
+    );
+    assert!(
+        time - trusted_time <= TRUSTING_PERIOD_SECS * 1_000_000_000,
+        "Target header is past the trusting period of the trusted header"
+    );
+    assert!(
+        tendermintx_output.target_height <= tendermintx_output.trusted_height + MAX_HEIGHT_ADVANCE,
+        "Target header is past the height advance of a link"
+    );
+
+    // The height and header hash the proof chain was at before this link
+    let (previous_height, previous_root) = if inputs.trusted_height == TRUSTED_HEIGHT {
         assert_eq!(tendermintx_output.trusted_header_hash, TRUSTED_ROOT);
+        assert_eq!(tendermintx_output.trusted_height, TRUSTED_HEIGHT);
+        (TRUSTED_HEIGHT, TRUSTED_ROOT)
     } else {
-        let recusive_proof_outputs: RecursionCircuitOutputs = borsh::from_slice(
+        let recusive_proof_outputs: RecursionCircuitOutputs = decode_outputs(
             &inputs
                 .recursive_public_values
                 .as_ref()
                 .expect("Failed to unwrap recursive public values"),
         )
         .expect("Failed to deserialize Recursive Outputs");
+        // The link extends the header the previous proof committed: the Tendermint proof must
+        // start from it, which also runs the trusting period from its time
+        assert_eq!(
+            tendermintx_output.trusted_header_hash, recusive_proof_outputs.root,
+            "Tendermint proof does not start from the previous root"
+        );
+        assert_eq!(
+            tendermintx_output.trusted_height, recusive_proof_outputs.height,
+            "Tendermint proof does not start from the previous height"
+        );
         assert!(tendermintx_output.target_height > recusive_proof_outputs.height);
+        // The circuit can't embed its own vk, so the vk committed at the trusted height is
+        // bound by induction instead: every link must be verified with the vk committed by
+        // the previous one, and the wrapper circuit pins the vk of the last link to the
+        // recursive circuit vk fixed when it is generated. A first link committing another
+        // vk can therefore never be extended nor wrapped.
+        assert_eq!(
+            recusive_proof_outputs.vk, inputs.recursive_vk,
+            "Previous proof committed another recursive vk"
+        );
         Groth16Verifier::verify(
             &inputs
                 .recursive_proof
@@ -59,11 +222,32 @@ pub fn main() {
             groth16_vk,
         )
         .expect("Failed to verify previous proof");
-    }
+        (recusive_proof_outputs.height, recusive_proof_outputs.root)
+    };
+    // The links of a chain that halted at an upgrade end at the upgrade height, the links of
+    // the restarted chain commit its chain id
+    let restarted =
+        UPGRADE_HEIGHT != 0 && tendermintx_output.trusted_height >= UPGRADE_TRUSTED_HEIGHT;
+    assert!(
+        restarted || UPGRADE_HEIGHT == 0 || tendermintx_output.target_height <= UPGRADE_HEIGHT,
+        "Target header is past the upgrade height"
+    );
+    let chain_id = if restarted {
+        UPGRADE_CHAIN_ID
+    } else {
+        CHAIN_ID
+    };
     let outputs = RecursionCircuitOutputs {
         root: tendermintx_output.target_header_hash,
         height: tendermintx_output.target_height,
         vk: inputs.recursive_vk,
+        previous_height,
+        previous_root,
+        chain_id: chain_id.to_string(),
+        app_hash,
+        next_validators_hash,
+        time,
+        transition: false,
     };
-    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+    sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
 }
@@ -3,6 +3,10 @@ extern crate alloc;
 use alloc::{string::String, vec::Vec};
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use lightwave_codec::abi;
+
+lightwave_codec::outputs_codec!(2);
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct RecursionCircuitInputs {
     pub tendermint_proof: Vec<u8>,
@@ -11,6 +15,15 @@ pub struct RecursionCircuitInputs {
     pub recursive_public_values: Option<Vec<u8>>,
     pub recursive_vk: String,
     pub trusted_height: u64,
+    /// The JSON encoded header of the trusted block of the Tendermint proof, the trusting
+    /// period runs from its time
+    pub trusted_header: Vec<u8>,
+    /// The JSON encoded header of the target block, it must hash to the target header hash of
+    /// the Tendermint proof and commits the app hash, next validators hash and time
+    pub target_header: Vec<u8>,
+    /// Whether this link re-anchors the proof chain at the approved first header of a chain
+    /// restarted after an upgrade, `target_header`, instead of verifying a Tendermint proof
+    pub transition: bool,
 }
 
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
@@ -18,6 +31,20 @@ pub struct RecursionCircuitOutputs {
     pub root: [u8; 32],
     pub height: u64,
     pub vk: String,
+    /// The height and header hash committed by the previous proof, the trusted ones for the
+    /// first
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    /// The CAIP-2 chain id of the followed chain, `cosmos:<chain id>`, fixed when the
+    /// circuit is generated so that proofs of different chains can't be mistaken for each other
+    pub chain_id: String,
+    /// The app hash, next validators hash and time (unix nanoseconds) of the target header
+    pub app_hash: [u8; 32],
+    pub next_validators_hash: [u8; 32],
+    pub time: u64,
+    /// Whether this link re-anchored the proof chain at the approved first header of a chain
+    /// restarted after an upgrade, the chain id is the one of the restarted chain from then on
+    pub transition: bool,
 }
 
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
@@ -26,8 +53,128 @@ pub struct WrapperCircuitInputs {
     pub recursive_public_values: Vec<u8>,
 }
 
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct WrapperCircuitOutputs {
     pub height: u64,
     pub root: [u8; 32],
+    /// The height and root the proof chain was at before this update, so the chain of state
+    /// transitions can be audited from the wrapper proofs alone
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    /// Whether this update re-anchored the proof chain at the approved first header of a chain
+    /// restarted after an upgrade instead of verifying a Tendermint proof, so consumers can
+    /// tell it from a regular update
+    pub transition: bool,
+    /// The CAIP-2 chain id of the proven chain, committed by the recursion circuit
+    pub chain_id: String,
+}
+
+/// The wrapper outputs extended with the header fields an IBC light client tracks, committed
+/// instead of [`WrapperCircuitOutputs`] by the wrapper circuits generated with
+/// `TENDERMINT_IBC_OUTPUTS=true`. They are always borsh encoded and start with the
+/// [`WrapperCircuitOutputs`] fields, which [`WrapperCircuitOutputs::decode`] reads from them
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct IbcWrapperCircuitOutputs {
+    pub height: u64,
+    pub root: [u8; 32],
+    pub previous_height: u64,
+    pub previous_root: [u8; 32],
+    pub transition: bool,
+    pub chain_id: String,
+    /// The app hash of the header, the root of the application state after the previous block
+    pub app_hash: [u8; 32],
+    /// The hash of the validator set that signs the next block
+    pub next_validators_hash: [u8; 32],
+    /// The time of the header in unix nanoseconds
+    pub time: u64,
+}
+
+impl From<IbcWrapperCircuitOutputs> for WrapperCircuitOutputs {
+    fn from(outputs: IbcWrapperCircuitOutputs) -> Self {
+        Self {
+            height: outputs.height,
+            root: outputs.root,
+            previous_height: outputs.previous_height,
+            previous_root: outputs.previous_root,
+            transition: outputs.transition,
+            chain_id: outputs.chain_id,
+        }
+    }
+}
+
+impl IbcWrapperCircuitOutputs {
+    /// Decodes the public values committed by a wrapper circuit generated with IBC outputs
+    pub fn decode(public_values: &[u8]) -> Result<Self, WrapperError> {
+        Ok(decode_outputs(public_values)?)
+    }
+
+    /// Verifies a wrapper proof with IBC outputs against the wrapper circuit vk (`0x`-prefixed
+    /// bytes32 hash) and returns the outputs it commits to
+    #[cfg(feature = "verify")]
+    pub fn verify(
+        proof: &[u8],
+        public_values: &[u8],
+        wrapper_vk: &str,
+    ) -> Result<Self, WrapperError> {
+        lightwave_codec::verify_groth16(proof, public_values, wrapper_vk)?;
+        Self::decode(public_values)
+    }
+}
+
+impl WrapperCircuitOutputs {
+    /// Decodes the public values committed by the wrapper circuit, borsh or ABI encoded. ABI
+    /// encoded public values start with the zero high byte of their version word, borsh encoded
+    /// ones may be [`IbcWrapperCircuitOutputs`]
+    pub fn decode(public_values: &[u8]) -> Result<Self, WrapperError> {
+        match public_values.first() {
+            Some(0) => Self::abi_decode(public_values),
+            _ => match decode_outputs(public_values) {
+                Ok(outputs) => Ok(outputs),
+                Err(error) => IbcWrapperCircuitOutputs::decode(public_values)
+                    .map(Into::into)
+                    .map_err(|_| error.into()),
+            },
+        }
+    }
+
+    /// Encodes the outputs as `abi.encode(uint8 version, uint64 height, bytes32 root,
+    /// uint64 previousHeight, bytes32 previousRoot, bool transition, string chainId)`, the
+    /// public values of the wrapper circuits generated with `WRAPPER_ENCODING=abi`, which EVM
+    /// contracts decode with `abi.decode` instead of a borsh decoder
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let fields = [
+            abi::uint(self.height),
+            self.root,
+            abi::uint(self.previous_height),
+            self.previous_root,
+            abi::uint(self.transition as u64),
+        ];
+        abi::encode(OUTPUTS_VERSION, &fields, &self.chain_id)
+    }
+
+    /// Decodes ABI encoded public values, see [`Self::abi_encode`]. Any other encoding of the
+    /// same outputs, e.g. with dirty padding, is refused
+    pub fn abi_decode(public_values: &[u8]) -> Result<Self, WrapperError> {
+        let decoded = abi::decode(OUTPUTS_VERSION, 5, public_values)?;
+        Ok(Self {
+            height: decoded.uint(0)?,
+            root: decoded.word(1)?,
+            previous_height: decoded.uint(2)?,
+            previous_root: decoded.word(3)?,
+            transition: decoded.bool(4)?,
+            chain_id: decoded.chain_id,
+        })
+    }
+
+    /// Verifies a wrapper proof against the wrapper circuit vk (`0x`-prefixed bytes32 hash)
+    /// and returns the outputs it commits to
+    #[cfg(feature = "verify")]
+    pub fn verify(
+        proof: &[u8],
+        public_values: &[u8],
+        wrapper_vk: &str,
+    ) -> Result<Self, WrapperError> {
+        lightwave_codec::verify_groth16(proof, public_values, wrapper_vk)?;
+        Self::decode(public_values)
+    }
 }
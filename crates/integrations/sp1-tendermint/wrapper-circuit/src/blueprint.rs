@@ -6,10 +6,15 @@
 sp1_zkvm::entrypoint!(main);
 use sp1_verifier::Groth16Verifier;
 use tendermint_recursion_types::{
-    RecursionCircuitOutputs, WrapperCircuitInputs, WrapperCircuitOutputs,
+    IbcWrapperCircuitOutputs, RecursionCircuitOutputs, WrapperCircuitInputs, WrapperCircuitOutputs,
+    decode_outputs, encode_outputs,
 };
 
 const RECURSIVE_VK: &str = { recursive_vk };
+// Whether the outputs are committed ABI encoded for EVM contracts instead of borsh encoded
+const ABI_OUTPUTS: bool = { abi_outputs };
+// Whether the outputs carry the app hash, next validators hash and time for IBC light clients
+const IBC_OUTPUTS: bool = { ibc_outputs };
 
 fn main() {
     // Get the Groth16 verification key for proof verification
@@ -20,7 +25,7 @@ fn main() {
         borsh::from_slice(&sp1_zkvm::io::read_vec()).expect("Failed to deserialize Inputs");
 
     let recursive_outputs: RecursionCircuitOutputs =
-        borsh::from_slice(&inputs.recursive_public_values)
+        decode_outputs(&inputs.recursive_public_values)
             .expect("Failed to deserialize recursive Outputs");
 
     // Assert that the VK used for the verification of the recursive proof (if any) matches
@@ -42,9 +47,33 @@ fn main() {
 
     // Re-commit the public outputs after recursive proof verification
     // This ensures the outputs are available for the next proof in the chain
+    if IBC_OUTPUTS {
+        let outputs = IbcWrapperCircuitOutputs {
+            height: recursive_outputs.height,
+            root: recursive_outputs.root,
+            previous_height: recursive_outputs.previous_height,
+            previous_root: recursive_outputs.previous_root,
+            transition: recursive_outputs.transition,
+            chain_id: recursive_outputs.chain_id,
+            app_hash: recursive_outputs.app_hash,
+            next_validators_hash: recursive_outputs.next_validators_hash,
+            time: recursive_outputs.time,
+        };
+        sp1_zkvm::io::commit_slice(&encode_outputs(&outputs));
+        return;
+    }
     let outputs = WrapperCircuitOutputs {
         height: recursive_outputs.height,
         root: recursive_outputs.root,
+        previous_height: recursive_outputs.previous_height,
+        previous_root: recursive_outputs.previous_root,
+        transition: recursive_outputs.transition,
+        chain_id: recursive_outputs.chain_id,
     };
-    sp1_zkvm::io::commit_slice(&borsh::to_vec(&outputs).unwrap());
+    let public_values = if ABI_OUTPUTS {
+        outputs.abi_encode()
+    } else {
+        encode_outputs(&outputs)
+    };
+    sp1_zkvm::io::commit_slice(&public_values);
 }
@@ -0,0 +1,12 @@
+// A trivial program proven by the startup self-test. It commits its input and the sum of the
+// integers up to it, so the self-test can check the proof carries the expected outputs.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let n: u64 = sp1_zkvm::io::read();
+    let sum: u64 = (1..=n).sum();
+    sp1_zkvm::io::commit(&n);
+    sp1_zkvm::io::commit(&sum);
+}
@@ -1,6 +1,17 @@
 use sp1_build::build_program_with_args;
+use std::process::Command;
 
 fn main() {
+    // Recorded in the manifest of the dumped ELFs
+    let git_commit = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--abbrev=40"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=CIRCUITS_GIT_COMMIT={}", git_commit);
+
     build_program_with_args("../integrations/sp1-helios/circuit", Default::default());
     build_program_with_args(
         "../integrations/sp1-helios/wrapper-circuit",
@@ -11,4 +22,18 @@ fn main() {
         "../integrations/sp1-tendermint/wrapper-circuit",
         Default::default(),
     );
+    build_program_with_args("../integrations/sp1-grandpa/circuit", Default::default());
+    build_program_with_args(
+        "../integrations/sp1-grandpa/wrapper-circuit",
+        Default::default(),
+    );
+    build_program_with_args("../integrations/sp1-bitcoin/circuit", Default::default());
+    build_program_with_args(
+        "../integrations/sp1-bitcoin/wrapper-circuit",
+        Default::default(),
+    );
+    build_program_with_args("../integrations/sp1-opstack/circuit", Default::default());
+    build_program_with_args("../integrations/sp1-storage/circuit", Default::default());
+    build_program_with_args("../integrations/sp1-ics23/circuit", Default::default());
+    build_program_with_args("../sanity-circuit", Default::default());
 }
@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// @title SP1 Verifier Interface
+/// @notice The interface of the SP1 verifier contracts and of the SP1 verifier gateway
+interface ISP1Verifier {
+    /// @notice Verifies a proof with given public values and vkey, reverts if it is invalid
+    /// @param programVKey The verification key of the RISC-V program
+    /// @param publicValues The public values encoded as bytes
+    /// @param proofBytes The proof of the program execution the SP1 zkVM encoded as bytes
+    function verifyProof(bytes32 programVKey, bytes calldata publicValues, bytes calldata proofBytes)
+        external
+        view;
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {ISP1Verifier} from "./ISP1Verifier.sol";
+
+/// @title LightwaveVerifier
+/// @notice The light client state of { chain_id }, advanced by Lightwave wrapper proofs
+/// @dev Generated by `service generate-verifier`. The wrapper vk pins the recursion circuit and
+/// with it the trusted checkpoint and the chain the proofs follow
+contract LightwaveVerifier {
+    /// @notice The vk of the wrapper circuit the proofs are verified against
+    bytes32 public constant WRAPPER_VKEY = { wrapper_vk };
+
+    /// @notice The format version of the public values this contract decodes
+    uint8 public constant OUTPUTS_VERSION = { outputs_version };
+
+    /// @notice Whether the wrapper circuit commits its outputs ABI encoded instead of borsh
+    /// encoded
+    bool public constant ABI_OUTPUTS = { abi_outputs };
+
+    /// @notice Whether the outputs commit the `transition` flag before the chain id, as the
+    /// Tendermint wrapper circuit does
+    bool public constant TRANSITION_OUTPUTS = { transition_outputs };
+
+    /// @notice The CAIP-2 chain id the wrapper circuit commits
+    string public constant CHAIN_ID = "{ chain_id }";
+
+    /// @notice The SP1 verifier gateway, which routes the proofs to the verifier of their version
+    ISP1Verifier public immutable verifier;
+
+    /// @notice The latest proven height
+    uint64 public height;
+
+    /// @notice The root at the latest proven height
+    bytes32 public root;
+
+    /// @notice The roots of every proven height
+    mapping(uint64 => bytes32) public roots;
+
+    struct Outputs {
+        uint8 version;
+        uint64 height;
+        bytes32 root;
+        uint64 previousHeight;
+        bytes32 previousRoot;
+        bool transition;
+        string chainId;
+    }
+
+    event StateUpdated(uint64 indexed height, bytes32 root, uint64 previousHeight, bytes32 previousRoot);
+
+    error MalformedOutputs();
+    error UnsupportedVersion(uint8 version);
+    error WrongChain(string chainId);
+    error NotNewer(uint64 height, uint64 trustedHeight);
+
+    constructor(address _verifier) {
+        verifier = ISP1Verifier(_verifier);
+    }
+
+    /// @notice Verifies a wrapper proof and stores the height and root it commits, if newer
+    /// than the latest proven height
+    function updateState(bytes calldata proof, bytes calldata publicValues) external {
+        verifier.verifyProof(WRAPPER_VKEY, publicValues, proof);
+        Outputs memory outputs = decodeOutputs(publicValues);
+        if (keccak256(bytes(outputs.chainId)) != keccak256(bytes(CHAIN_ID))) {
+            revert WrongChain(outputs.chainId);
+        }
+        if (outputs.height <= height) {
+            revert NotNewer(outputs.height, height);
+        }
+        height = outputs.height;
+        root = outputs.root;
+        roots[outputs.height] = outputs.root;
+        emit StateUpdated(outputs.height, outputs.root, outputs.previousHeight, outputs.previousRoot);
+    }
+
+    /// @notice Decodes the public values committed by the wrapper circuit
+    function decodeOutputs(bytes calldata publicValues) public pure returns (Outputs memory outputs) {
+        if (ABI_OUTPUTS && TRANSITION_OUTPUTS) {
+            (
+                outputs.version,
+                outputs.height,
+                outputs.root,
+                outputs.previousHeight,
+                outputs.previousRoot,
+                outputs.transition,
+                outputs.chainId
+            ) = abi.decode(publicValues, (uint8, uint64, bytes32, uint64, bytes32, bool, string));
+        } else if (ABI_OUTPUTS) {
+            (outputs.version, outputs.height, outputs.root, outputs.previousHeight, outputs.previousRoot, outputs.chainId)
+            = abi.decode(publicValues, (uint8, uint64, bytes32, uint64, bytes32, string));
+        } else {
+            outputs = decodeBorsh(publicValues);
+        }
+        if (outputs.version != OUTPUTS_VERSION) {
+            revert UnsupportedVersion(outputs.version);
+        }
+    }
+
+    /// @dev The version byte, then the borsh encoded outputs: little endian integers, the
+    /// roots as 32 bytes, the transition flag as a 0 or 1 byte and the chain id prefixed with its
+    /// u32 length
+    function decodeBorsh(bytes calldata publicValues) internal pure returns (Outputs memory outputs) {
+        uint256 offset = TRANSITION_OUTPUTS ? 82 : 81;
+        if (publicValues.length < offset + 4) {
+            revert MalformedOutputs();
+        }
+        outputs.version = uint8(publicValues[0]);
+        outputs.height = uint64(littleEndian(publicValues[1:9]));
+        outputs.root = bytes32(publicValues[9:41]);
+        outputs.previousHeight = uint64(littleEndian(publicValues[41:49]));
+        outputs.previousRoot = bytes32(publicValues[49:81]);
+        if (TRANSITION_OUTPUTS) {
+            uint8 transition = uint8(publicValues[81]);
+            if (transition > 1) {
+                revert MalformedOutputs();
+            }
+            outputs.transition = transition == 1;
+        }
+        uint256 length = littleEndian(publicValues[offset:offset + 4]);
+        if (publicValues.length != offset + 4 + length) {
+            revert MalformedOutputs();
+        }
+        outputs.chainId = string(publicValues[offset + 4:]);
+    }
+
+    function littleEndian(bytes calldata data) internal pure returns (uint256 value) {
+        for (uint256 i = data.length; i > 0; i--) {
+            value = (value << 8) | uint8(data[i - 1]);
+        }
+    }
+}
@@ -1,50 +1,1279 @@
-use crate::state::StateManager;
-use axum::{http::StatusCode, response::IntoResponse};
+use crate::archive::ProofArchive;
+use crate::cache::{self, CacheStats};
+use crate::control::{Control, ControlState, active_backend};
+use crate::encoding::{parse_hex_array, to_base64, to_hex};
+use crate::errors::{ApiError, Upstream};
+use crate::eta::EtaResponse;
+use crate::export;
+use crate::ics23::ICS23_CHAIN_ID;
+use crate::network::{self, NetworkStats};
+use crate::networks::EthereumNetwork;
+use crate::opstack::OPSTACK_CHAIN_ID;
+use crate::preprocessor::{self, metrics::PreprocessorStats};
+use crate::preprocessor::{opstack::ExecutionRpcClient, sync_committee_hash};
+use crate::prover::ProverMode;
+use crate::rounds::{self, RoundDebug};
+use crate::slo::{SloConfig, SloStatus};
+use crate::state::{
+    Annotation, CommitteeRecord, DEFAULT_CHAIN_ID, DbPool, HeldUpdate, LineageRecord, ServiceState,
+    ShadowRound, StateManager, UpdateRecord, update_id,
+};
+use crate::storage::STORAGE_CHAIN_ID;
+use crate::trust::TrustResponse;
+use crate::warm_start::{self, InvalidSnapshot, Snapshot};
+use alloy_primitives::U256;
+use anyhow::Context;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+};
 use hex;
+use serde::{Deserialize, Serialize};
 use serde_json;
-use tracing::{error, info};
+use sha2::{Digest, Sha256};
+use sp1_sdk::SP1_CIRCUIT_VERSION;
+use storage_proof_types::verify_storage_slots;
+use tendermint_recursion_types::IbcWrapperCircuitOutputs;
+use tracing::info;
+use vote_extension::LightClientVoteExtension;
 
-pub async fn get_proof() -> impl IntoResponse {
+/// State shared by the API handlers
+#[derive(Clone)]
+pub struct AppState {
+    /// Connections to the state database, shared with the prover loops
+    pub db: DbPool,
+}
+
+impl AppState {
+    /// The state manager of a chain on a pooled connection
+    fn state_manager(&self, chain_id: &str) -> anyhow::Result<StateManager> {
+        StateManager::from_pool(&self.db, chain_id)
+    }
+}
+
+/// Query parameters accepted by the proof endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct ProofQuery {
+    /// Set to `hex` to receive the legacy hex-encoded serde_json proof, to `vote_extension`
+    /// to receive the proof encoded as a CometBFT vote extension, or to `cosmwasm` to receive
+    /// the execute message of the CosmWasm light client
+    pub format: Option<String>,
+    /// The Tendermint chain to return the proof of, defaults to the single configured chain.
+    /// `opstack` returns the latest OP Stack output proof, `storage` the latest storage proof
+    /// and `ics23` the latest ICS23 proof
+    pub chain_id: Option<String>,
+    /// The height to return the proof of, defaults to the trusted head. Proofs of earlier
+    /// heights are redirected to their archived bundle
+    pub height: Option<u64>,
+}
+
+/// The latest wrapper proof together with the trusted state it commits to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProofResponse {
+    /// The light client backend that produced the proof (HELIOS, TENDERMINT, GRANDPA or BITCOIN),
+    /// or OPSTACK / STORAGE / ICS23 for the proofs anchored to the Helios and Tendermint
+    /// proof chains
+    pub mode: String,
+    /// The trusted execution height (Helios, storage), block height (Tendermint, GRANDPA,
+    /// Bitcoin, ICS23) or L2 block number (OP Stack)
+    pub height: u64,
+    /// The trusted state root / header hash / best block hash / output root / account storage
+    /// root / value hash, 0x-prefixed hex
+    pub root: String,
+    /// The recursive circuit vk the wrapper proof was checked against, empty for OP Stack,
+    /// storage and ICS23 proofs
+    pub vk: String,
+    /// The raw wrapper proof bytes, base64 encoded
+    pub proof_b64: String,
+    /// The public values committed by the wrapper circuit, base64 encoded
+    pub public_values_b64: String,
+    /// Unix timestamp (seconds) at which the proof was committed
+    pub generated_at: Option<u64>,
+    /// The id of the update the proof committed, `{backend}:{chain_id}:{update_counter}`
+    #[serde(default)]
+    pub update_id: String,
+}
+
+pub async fn get_proof(
+    State(app): State<AppState>,
+    Query(query): Query<ProofQuery>,
+) -> Result<Response, ApiError> {
     info!("Received request for latest proof");
-    let state_manager = match StateManager::from_env() {
-        Ok(manager) => manager,
-        Err(e) => {
-            error!("Failed to initialize state manager: {}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
-    };
-
-    let result: Result<(StatusCode, String), ()> = {
-        let service_state = match state_manager.load_state() {
-            Ok(Some(state)) => state,
-            Ok(None) => {
-                info!("No state found in database");
-                return StatusCode::NOT_FOUND.into_response();
-            }
-            Err(e) => {
-                error!("Failed to load state: {}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        };
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+
+    // Only the update counter is read while the cached response is current
+    let head = state_manager
+        .load_head()?
+        .ok_or_else(|| no_state(chain_id))?;
+    if let Some(height) = query.height.filter(|height| *height != head.trusted_height) {
+        return archived_proof(&state_manager, height, head.trusted_height);
+    }
+    let update_counter = head.update_counter;
+    let format = match query.format.as_deref() {
+        Some(format @ ("hex" | "vote_extension" | "cosmwasm")) => format,
+        _ => "json",
+    };
+    let endpoint = format!("proof:{}", format);
+    if let Some(response) = cache::get(chain_id, &endpoint, update_counter) {
+        return Ok(response);
+    }
+    let response = proof_response(&state_manager, format)?;
+    Ok(cache::store(chain_id, &endpoint, update_counter, response).await)
+}
+
+/// Redirects to the archived bundle of the update that committed `height`, the state database
+/// only holds the proofs of the latest update
+fn archived_proof(
+    state_manager: &StateManager,
+    height: u64,
+    trusted_height: u64,
+) -> Result<Response, ApiError> {
+    if height > trusted_height {
+        return Err(not_committed(
+            state_manager,
+            format!(
+                "Height {} is not committed yet, the trusted height is {}",
+                height, trusted_height
+            ),
+        ));
+    }
+    let record = state_manager
+        .load_record_at_height(height)?
+        .ok_or_else(|| ApiError::NotFound(format!("No update committed height {}", height)))?;
+    match ProofArchive::from_env()?.archived_url(state_manager.chain_id(), record.update_counter) {
+        Some(url) => Ok(Redirect::temporary(&url).into_response()),
+        None => Err(ApiError::NotFound(format!(
+            "The proof of height {} is pruned and not archived",
+            height
+        ))),
+    }
+}
+
+/// The error of a chain without state, most likely an unknown chain id
+fn no_state(chain_id: &str) -> ApiError {
+    ApiError::NotFound(format!("No state found for chain {}", chain_id))
+}
+
+/// The error of a proof the chain of `state_manager` has not committed: the chain is paused
+/// if an operator disabled it, the proof is `pending` otherwise
+fn not_committed(state_manager: &StateManager, pending: String) -> ApiError {
+    let chain_id = state_manager.chain_id();
+    match state_manager.load_control() {
+        Ok(Some(control)) if !control.is_enabled(chain_id) => {
+            ApiError::Paused(format!("Chain {} is disabled", chain_id))
+        }
+        Ok(_) => ApiError::Pending(pending),
+        Err(e) => e.into(),
+    }
+}
+
+/// Builds the latest proof response of a chain in `format`
+fn proof_response(state_manager: &StateManager, format: &str) -> Result<Response, ApiError> {
+    let chain_id = state_manager.chain_id();
+    let service_state = state_manager
+        .load_state()?
+        .ok_or_else(|| no_state(chain_id))?;
+
+    let Some(proof) = service_state.most_recent_wrapper_proof.as_ref() else {
+        return Err(not_committed(
+            state_manager,
+            format!("Chain {} has not committed its first proof yet", chain_id),
+        ));
+    };
+
+    info!("Returning latest proof");
+    if format == "hex" {
+        // Serialize the entire SP1ProofWithPublicValues using serde_json
+        let serialized = serde_json::to_vec(proof).map_err(anyhow::Error::from)?;
+        // Convert to hex for human readability
+        let hex_proof = hex::encode(&serialized);
+        return Ok((StatusCode::OK, hex_proof).into_response());
+    }
+
+    if format == "vote_extension" {
+        // Raw payload to be returned from ExtendVote by a validator sidecar
+        let extension = LightClientVoteExtension::new(
+            service_state.trusted_height,
+            service_state.trusted_root,
+            proof.bytes(),
+            proof.public_values.to_vec(),
+        );
+        let bytes = extension
+            .encode()
+            .map_err(|e| anyhow::anyhow!("Failed to encode vote extension: {}", e))?;
+        return Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            bytes,
+        )
+            .into_response());
+    }
+
+    if format == "cosmwasm" {
+        let msg = export::cosmwasm_msg(
+            service_state.trusted_height,
+            &to_hex(service_state.trusted_root),
+            &to_base64(proof.bytes()),
+            &to_base64(proof.public_values.as_slice()),
+        );
+        return Ok((StatusCode::OK, Json(msg)).into_response());
+    }
+
+    let mode = chain_mode(state_manager)?;
+    let response = ProofResponse {
+        vk: recursive_vk(&mode, &service_state).unwrap_or_default(),
+        update_id: update_id(&mode, chain_id, service_state.update_counter),
+        mode,
+        height: service_state.trusted_height,
+        root: to_hex(service_state.trusted_root),
+        proof_b64: to_base64(proof.bytes()),
+        public_values_b64: to_base64(proof.public_values.as_slice()),
+        generated_at: service_state.generated_at,
+    };
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+/// Query parameters accepted by the IBC endpoint
+#[derive(Debug, Deserialize)]
+pub struct IbcQuery {
+    /// The 08-wasm client on the counterparty chain to update, e.g. `08-wasm-0`
+    pub client_id: String,
+    /// The Tendermint chain to return the update of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// An IBC revision height
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IbcHeight {
+    pub revision_number: u64,
+    pub revision_height: u64,
+}
 
-        match service_state.most_recent_wrapper_proof {
-            Some(proof) => {
-                info!("Returning latest proof");
-                // Serialize the entire SP1ProofWithPublicValues using serde_json
-                let serialized = serde_json::to_vec(&proof).unwrap();
-                // Convert to hex for human readability
-                let hex_proof = hex::encode(&serialized);
-                Ok((StatusCode::OK, hex_proof))
-            }
-            None => {
-                info!("No proof available");
-                Ok((StatusCode::NOT_FOUND, String::new()))
-            }
+/// The latest wrapper proof of a Tendermint chain packaged as an IBC client update
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IbcUpdateResponse {
+    /// The proven height, the revision number is read from the chain id
+    pub height: IbcHeight,
+    /// The header fields committed by the wrapper proof, 0x-prefixed hex
+    pub root: String,
+    pub app_hash: String,
+    pub next_validators_hash: String,
+    /// The header time in unix nanoseconds
+    pub timestamp: u64,
+    /// The `MsgUpdateClient` carrying the proof, its `signer` is set by the relayer
+    pub msg: serde_json::Value,
+}
+
+/// Packages the latest wrapper proof of a Tendermint chain as an IBC `MsgUpdateClient`. The
+/// wrapper circuit must be generated with `TENDERMINT_IBC_OUTPUTS=true`, so that its outputs
+/// carry the header fields an IBC light client tracks
+pub async fn get_ibc_update(
+    State(app): State<AppState>,
+    Query(query): Query<IbcQuery>,
+) -> Result<Response, ApiError> {
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+    let head = state_manager
+        .load_head()?
+        .ok_or_else(|| no_state(chain_id))?;
+    // The update is cached by chain only, for no client, and the client id of the request is
+    // set on a copy, so that requests of arbitrary client ids can't grow the cache
+    let mut update: IbcUpdateResponse = match cache::get(chain_id, "ibc", head.update_counter) {
+        Some(response) => {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .context("Failed to read the cached IBC update")?;
+            serde_json::from_slice(&body).context("Failed to decode the cached IBC update")?
         }
+        None => {
+            let update = ibc_update(&state_manager, chain_id)?;
+            let response = (StatusCode::OK, Json(&update)).into_response();
+            cache::store(chain_id, "ibc", head.update_counter, response).await;
+            update
+        }
+    };
+    update.msg["client_id"] = query.client_id.into();
+    Ok((StatusCode::OK, Json(update)).into_response())
+}
+
+/// The IBC update of the latest wrapper proof of a Tendermint chain, its message addressed
+/// to no client
+fn ibc_update(state_manager: &StateManager, chain_id: &str) -> Result<IbcUpdateResponse, ApiError> {
+    let mode = chain_mode(state_manager)?;
+    if mode != "TENDERMINT" {
+        return Err(ApiError::BadRequest(format!(
+            "Chain {} is proven by {}, IBC updates are only served for TENDERMINT chains",
+            chain_id, mode
+        )));
+    }
+    let service_state = state_manager
+        .load_state()?
+        .ok_or_else(|| no_state(chain_id))?;
+    let Some(proof) = service_state.most_recent_wrapper_proof.as_ref() else {
+        return Err(not_committed(
+            state_manager,
+            format!("Chain {} has not committed its first proof yet", chain_id),
+        ));
     };
+    let outputs =
+        IbcWrapperCircuitOutputs::decode(proof.public_values.as_slice()).map_err(|_| {
+            ApiError::BadRequest(format!(
+                "The wrapper proofs of {} carry no IBC outputs, generate the wrapper circuit with \
+             TENDERMINT_IBC_OUTPUTS=true",
+                chain_id
+            ))
+        })?;
+
+    let proof_b64 = to_base64(proof.bytes());
+    let public_values_b64 = to_base64(proof.public_values.as_slice());
+    let tendermint_chain_id = outputs
+        .chain_id
+        .strip_prefix("cosmos:")
+        .unwrap_or(&outputs.chain_id);
+    Ok(IbcUpdateResponse {
+        height: IbcHeight {
+            revision_number: export::revision_number(tendermint_chain_id),
+            revision_height: outputs.height,
+        },
+        root: to_hex(outputs.root),
+        app_hash: to_hex(outputs.app_hash),
+        next_validators_hash: to_hex(outputs.next_validators_hash),
+        timestamp: outputs.time,
+        msg: export::ibc_update_msg("", &proof_b64, &public_values_b64),
+    })
+}
+
+/// The mode of the proofs of the chain of `state_manager`
+pub(crate) fn chain_mode(state_manager: &StateManager) -> anyhow::Result<String> {
+    Ok(match state_manager.chain_id() {
+        OPSTACK_CHAIN_ID => "OPSTACK".to_string(),
+        STORAGE_CHAIN_ID => "STORAGE".to_string(),
+        ICS23_CHAIN_ID => "ICS23".to_string(),
+        _ => active_backend(state_manager)?,
+    })
+}
+
+/// Reads the recursive circuit vk from the outputs of the most recent recursive proof
+pub(crate) fn recursive_vk(mode: &str, service_state: &ServiceState) -> Option<String> {
+    let public_values = service_state
+        .most_recent_recursive_proof
+        .as_ref()?
+        .public_values
+        .to_vec();
+    committed_vk(mode, &public_values)
+}
 
-    match result {
-        Ok(response) => response.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+/// Reads the recursive circuit vk from the public values of a recursive proof
+pub(crate) fn committed_vk(mode: &str, public_values: &[u8]) -> Option<String> {
+    match mode {
+        "HELIOS" => helios_recursion_types::decode_outputs(public_values)
+            .ok()
+            .map(|outputs: helios_recursion_types::RecursionCircuitOutputs| outputs.vk),
+        "GRANDPA" => grandpa_recursion_types::decode_outputs(public_values)
+            .ok()
+            .map(|outputs: grandpa_recursion_types::RecursionCircuitOutputs| outputs.vk),
+        "BITCOIN" => bitcoin_recursion_types::decode_outputs(public_values)
+            .ok()
+            .map(|outputs: bitcoin_recursion_types::RecursionCircuitOutputs| outputs.vk),
+        _ => tendermint_recursion_types::decode_outputs(public_values)
+            .ok()
+            .map(|outputs: tendermint_recursion_types::RecursionCircuitOutputs| outputs.vk),
     }
 }
+
+/// Query parameters accepted by the history endpoint
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// First update counter to return
+    #[serde(default)]
+    pub from: u64,
+    /// Maximum number of records to return
+    #[serde(default = "default_history_limit")]
+    pub limit: u64,
+    /// The Tendermint chain to return the history of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+fn default_history_limit() -> u64 {
+    100
+}
+
+/// A hash-chained update history record with hex-encoded hashes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub update_counter: u64,
+    /// `{backend}:{chain_id}:{update_counter}`, empty in snapshots of earlier releases
+    #[serde(default)]
+    pub update_id: String,
+    pub slot: u64,
+    pub height: u64,
+    pub root: String,
+    pub proof_hash: String,
+    pub prev_hash: String,
+    pub hash: String,
+    pub committed_at: Option<u64>,
+}
+
+impl From<UpdateRecord> for HistoryRecord {
+    fn from(record: UpdateRecord) -> Self {
+        Self {
+            update_counter: record.update_counter,
+            update_id: record.update_id,
+            slot: record.slot,
+            height: record.height,
+            root: to_hex(record.root),
+            proof_hash: to_hex(record.proof_hash),
+            prev_hash: to_hex(record.prev_hash),
+            hash: to_hex(record.hash),
+            committed_at: record.committed_at,
+        }
+    }
+}
+
+/// A history record with the annotations of its update, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotatedRecord {
+    #[serde(flatten)]
+    pub record: HistoryRecord,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+pub async fn get_history(
+    State(app): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<AnnotatedRecord>>, ApiError> {
+    info!("Received request for update history");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+    let records = load_annotated_history(&state_manager, query.from, query.limit.min(1000))?;
+    Ok(Json(records))
+}
+
+fn load_annotated_history(
+    state_manager: &StateManager,
+    from: u64,
+    limit: u64,
+) -> anyhow::Result<Vec<AnnotatedRecord>> {
+    let records = state_manager.load_history(from, limit)?;
+    let annotations = match records.last() {
+        Some(last) => state_manager.load_update_annotations(from, last.update_counter)?,
+        None => Vec::new(),
+    };
+    Ok(records
+        .into_iter()
+        .map(|record| AnnotatedRecord {
+            annotations: annotations
+                .iter()
+                .filter(|annotation| annotation.update_counter == Some(record.update_counter))
+                .cloned()
+                .collect(),
+            record: record.into(),
+        })
+        .collect())
+}
+
+/// Query parameters accepted by the lineage endpoint
+#[derive(Debug, Deserialize)]
+pub struct LineageQuery {
+    /// The chain to return the lineage of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// A restart of a proof chain from a wrapper proof with hex-encoded hashes. The proof chain
+/// of `previous_vk` ends at the anchor update, the proof chain of `vk` starts from its head
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LineageEntry {
+    pub anchor_update: u64,
+    pub anchor_height: u64,
+    pub anchor_root: String,
+    pub anchor_proof_hash: String,
+    pub previous_vk: String,
+    pub vk: String,
+    pub restarted_at: u64,
+}
+
+impl From<LineageRecord> for LineageEntry {
+    fn from(record: LineageRecord) -> Self {
+        Self {
+            anchor_update: record.anchor_update,
+            anchor_height: record.anchor_height,
+            anchor_root: to_hex(record.anchor_root),
+            anchor_proof_hash: to_hex(record.anchor_proof_hash),
+            previous_vk: record.previous_vk,
+            vk: record.vk,
+            restarted_at: record.restarted_at,
+        }
+    }
+}
+
+/// Returns the restarts of a proof chain from its wrapper proofs after its recursive circuit
+/// was regenerated, oldest first
+pub async fn get_lineage(
+    State(app): State<AppState>,
+    Query(query): Query<LineageQuery>,
+) -> Result<Json<Vec<LineageEntry>>, ApiError> {
+    info!("Received request for proof chain lineage");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let records = app.state_manager(chain_id)?.load_lineage()?;
+    Ok(Json(records.into_iter().map(Into::into).collect()))
+}
+
+/// Query parameters accepted by the committee index endpoint
+#[derive(Debug, Deserialize)]
+pub struct CommitteesQuery {
+    /// First beacon period to return
+    #[serde(default)]
+    pub from: u64,
+    /// Maximum number of periods to return
+    #[serde(default = "default_history_limit")]
+    pub limit: u64,
+    /// The chain to return the committee index of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// The sync committee a beacon period maps to, hex encoded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitteeEntry {
+    pub period: u64,
+    pub committee_hash: String,
+    /// The first update that committed the committee
+    pub update_counter: u64,
+}
+
+impl From<CommitteeRecord> for CommitteeEntry {
+    fn from(record: CommitteeRecord) -> Self {
+        Self {
+            period: record.period,
+            committee_hash: to_hex(record.committee_hash),
+            update_counter: record.update_counter,
+        }
+    }
+}
+
+/// Returns the committee index of a proof chain, the sync committee every period its rounds
+/// attested to maps to, by period
+pub async fn get_committees(
+    State(app): State<AppState>,
+    Query(query): Query<CommitteesQuery>,
+) -> Result<Json<Vec<CommitteeEntry>>, ApiError> {
+    info!("Received request for the committee index");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let records = app
+        .state_manager(chain_id)?
+        .load_committees(query.from, query.limit)?;
+    Ok(Json(records.into_iter().map(Into::into).collect()))
+}
+
+/// Query parameters accepted by the shadow endpoint
+#[derive(Debug, Deserialize)]
+pub struct ShadowQuery {
+    /// The name of the shadow chain
+    pub name: String,
+    /// First production update to return
+    #[serde(default)]
+    pub from: u64,
+    /// Maximum number of rounds to return
+    #[serde(default = "default_history_limit")]
+    pub limit: u64,
+    /// The production chain the shadow chain follows, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// The outputs of a shadow chain next to production for one update, hex encoded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShadowEntry {
+    pub update_counter: u64,
+    pub matches: bool,
+    pub production_public_values: String,
+    pub shadow_public_values: Option<String>,
+    pub cycles: Option<u64>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    pub recorded_at: u64,
+}
+
+impl From<ShadowRound> for ShadowEntry {
+    fn from(round: ShadowRound) -> Self {
+        Self {
+            update_counter: round.update_counter,
+            matches: round.matches(),
+            production_public_values: to_hex(&round.production_public_values),
+            shadow_public_values: round.shadow_public_values.as_ref().map(to_hex),
+            cycles: round.cycles,
+            error: round.error,
+            elapsed_ms: round.elapsed_ms,
+            recorded_at: round.recorded_at,
+        }
+    }
+}
+
+/// Returns the outputs a shadow chain recorded next to the production chain
+pub async fn get_shadow(
+    State(app): State<AppState>,
+    Query(query): Query<ShadowQuery>,
+) -> Result<Json<Vec<ShadowEntry>>, ApiError> {
+    info!("Received request for shadow chain {}", query.name);
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let rounds = app.state_manager(chain_id)?.load_shadow_rounds(
+        &query.name,
+        query.from,
+        query.limit.min(1000),
+    )?;
+    Ok(Json(rounds.into_iter().map(Into::into).collect()))
+}
+
+/// Query parameters accepted by the base inputs endpoint
+#[derive(Debug, Deserialize)]
+pub struct BaseInputsQuery {
+    /// The update to return the base proof inputs of, defaults to the latest update
+    pub update: Option<u64>,
+    /// The chain to return the base proof inputs of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Returns the serialized inputs the base light client proof of an update was generated
+/// from, so auditors can re-execute the base program against the proven chain data
+pub async fn get_base_inputs(
+    State(app): State<AppState>,
+    Query(query): Query<BaseInputsQuery>,
+) -> Result<Response, ApiError> {
+    info!("Received request for base proof inputs");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+
+    let update_counter = match query.update {
+        Some(update_counter) => update_counter,
+        None => {
+            state_manager
+                .load_head()?
+                .ok_or_else(|| no_state(chain_id))?
+                .update_counter
+        }
+    };
+
+    let inputs = state_manager
+        .load_base_inputs(update_counter)?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "No base proof inputs stored for update {}",
+                update_counter
+            ))
+        })?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/cbor")],
+        inputs,
+    )
+        .into_response())
+}
+
+/// Query parameters accepted by the round debug endpoint
+#[derive(Debug, Deserialize)]
+pub struct RoundDebugQuery {
+    /// The chain of the round, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Returns everything needed to reproduce the round that committed update `update_counter`:
+/// input hashes, RPC endpoints, ELF hashes, vks, the prover configuration, timings and the
+/// archived artifacts
+pub async fn get_round_debug(
+    State(app): State<AppState>,
+    Path(update_counter): Path<u64>,
+    Query(query): Query<RoundDebugQuery>,
+) -> Result<Json<RoundDebug>, ApiError> {
+    info!(
+        "Received request for the debug info of round {}",
+        update_counter
+    );
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let debug =
+        RoundDebug::load(&app.state_manager(chain_id)?, update_counter)?.ok_or_else(|| {
+            ApiError::NotFound(format!("Update {} was not committed", update_counter))
+        })?;
+    Ok(Json(debug))
+}
+
+/// Query parameters accepted by the sync committee endpoint
+#[derive(Debug, Deserialize)]
+pub struct CommitteeQuery {
+    /// The slot to derive the sync committee hash at, defaults to the trusted slot of the network
+    pub slot: Option<u64>,
+}
+
+/// The hash of the sync committee a Helios light client bootstrapped at `slot` trusts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitteeResponse {
+    pub slot: u64,
+    /// The sync committee hash, 0x-prefixed hex
+    pub committee_hash: String,
+    /// The sync committee hash as written to `TRUSTED_SYNC_COMMITTEE_HASH` of the recursion circuit
+    pub committee_hash_bytes: [u8; 32],
+}
+
+impl CommitteeResponse {
+    /// Fetches the sync committee at `slot` of the network configured by `SOURCE_CHAIN_ID`
+    pub async fn derive(slot: Option<u64>) -> anyhow::Result<Self> {
+        let network = EthereumNetwork::from_env()?;
+        let slot = match slot {
+            Some(slot) => slot,
+            None => network.trusted_slot()?,
+        };
+        let committee_hash =
+            sync_committee_hash(slot, network)
+                .await
+                .context(Upstream(format!(
+                    "Failed to fetch the sync committee at slot {}",
+                    slot
+                )))?;
+        Ok(Self {
+            slot,
+            committee_hash: to_hex(committee_hash),
+            committee_hash_bytes: committee_hash,
+        })
+    }
+}
+
+/// Derives the sync committee hash at a slot, so third parties can reproduce the committee
+/// hash baked into a deployed Helios recursion circuit
+pub async fn get_committee(
+    Query(query): Query<CommitteeQuery>,
+) -> Result<Json<CommitteeResponse>, ApiError> {
+    info!("Received request for the sync committee hash");
+    Ok(Json(CommitteeResponse::derive(query.slot).await?))
+}
+
+/// Query parameters accepted by the verification key endpoint
+#[derive(Debug, Deserialize)]
+pub struct VkQuery {
+    /// The chain to return the vks of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// The verification keys an onchain verifier of a proof chain is deployed with
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VkResponse {
+    pub chain_id: String,
+    pub mode: String,
+    /// vk of the base light client program (Helios, Tendermint) the recursion circuit verifies,
+    /// absent for backends without one
+    pub base_vk: Option<String>,
+    /// The recursive circuit vk the proof chain is bound to
+    pub recursive_vk: String,
+    /// The wrapper circuit vk, the program vkey onchain verifiers check wrapper proofs against
+    pub wrapper_vk: String,
+    /// sha256 of the SP1 Groth16 verifying key, 0x-prefixed hex. Its first 4 bytes prefix every
+    /// Groth16 proof and select the SP1 verifier contract
+    pub groth16_vk_hash: String,
+    pub sp1_circuit_version: String,
+}
+
+impl VkResponse {
+    /// The vks of the circuits `chain_id` set up in this process, `None` until it did
+    pub fn load(chain_id: &str) -> Option<Self> {
+        let (mode, circuits) = rounds::registered_circuits(chain_id)?;
+        let vk = |name: &str| {
+            circuits
+                .iter()
+                .find(|circuit| circuit.name == name)
+                .map(|circuit| circuit.vk.clone())
+        };
+        Some(Self {
+            chain_id: chain_id.to_string(),
+            mode,
+            base_vk: vk("base"),
+            recursive_vk: vk("recursive")?,
+            wrapper_vk: vk("wrapper")?,
+            groth16_vk_hash: to_hex(Sha256::digest(*sp1_verifier::GROTH16_VK_BYTES)),
+            sp1_circuit_version: SP1_CIRCUIT_VERSION.to_string(),
+        })
+    }
+}
+
+/// Returns the vks of the circuits of the current ELFs, as set up by the proof chain
+pub async fn get_vk(Query(query): Query<VkQuery>) -> Result<Json<VkResponse>, ApiError> {
+    info!("Received request for the verification keys");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let response = VkResponse::load(chain_id).ok_or_else(|| {
+        ApiError::NotFound(format!("The circuits of chain {} are not set up", chain_id))
+    })?;
+    Ok(Json(response))
+}
+
+/// Query parameters accepted by the stats endpoint
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// The chain to return the stats of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Freshness of a proof chain and its SLO error budget
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub chain_id: String,
+    pub update_counter: u64,
+    pub height: u64,
+    /// Unix timestamp (seconds) at which the latest proof was committed
+    pub last_proof_at: Option<u64>,
+    /// Seconds since the latest proof was committed
+    pub proof_age_secs: Option<u64>,
+    /// The error budget of the freshness SLO, if one is configured
+    pub slo: Option<SloStatus>,
+    /// Requests of this process to the prover network, when proving on it
+    #[serde(default)]
+    pub network: Option<NetworkStats>,
+    /// RPC calls and runs of the preprocessor of this process
+    #[serde(default)]
+    pub preprocessor: Option<PreprocessorStats>,
+    /// Hits and misses of the API response cache of this process
+    #[serde(default)]
+    pub cache: Option<CacheStats>,
+    /// The latest annotations of the chain and its updates, latest first
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl StatsResponse {
+    /// Loads the stats of the chain of `state_manager`, `None` if it has no state yet
+    pub fn load(state_manager: &StateManager) -> anyhow::Result<Option<Self>> {
+        let Some(state) = state_manager.load_state()? else {
+            return Ok(None);
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let slo = match SloConfig::from_env()? {
+            Some(config) => Some(SloStatus::load(state_manager, &config, now)?),
+            None => None,
+        };
+        Ok(Some(Self {
+            chain_id: state_manager.chain_id().to_string(),
+            update_counter: state.update_counter,
+            height: state.trusted_height,
+            last_proof_at: state.generated_at,
+            proof_age_secs: state
+                .generated_at
+                .map(|generated_at| now.saturating_sub(generated_at)),
+            slo,
+            network: network::stats(),
+            preprocessor: preprocessor::metrics::stats(),
+            cache: cache::stats(),
+            annotations: state_manager.load_recent_annotations(10)?,
+        }))
+    }
+}
+
+pub async fn get_stats(
+    State(app): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    info!("Received request for stats");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let stats =
+        StatsResponse::load(&app.state_manager(chain_id)?)?.ok_or_else(|| no_state(chain_id))?;
+    Ok(Json(stats))
+}
+
+/// Query parameters accepted by the eta endpoint
+#[derive(Debug, Deserialize)]
+pub struct EtaQuery {
+    /// The chain to estimate the next proof of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Get an estimate of when the next wrapper proof of a chain is committed
+pub async fn get_eta(
+    State(app): State<AppState>,
+    Query(query): Query<EtaQuery>,
+) -> Result<Json<EtaResponse>, ApiError> {
+    info!("Received request for eta");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+    let mode = chain_mode(&state_manager)?;
+    let eta = EtaResponse::load(&state_manager, &mode)?.ok_or_else(|| no_state(chain_id))?;
+    Ok(Json(eta))
+}
+
+/// Query parameters accepted by the trust endpoint
+#[derive(Debug, Deserialize)]
+pub struct TrustQuery {
+    /// The chain to return the trust assumptions of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Returns the trust assumptions of a chain, rendered from the configuration and state of the
+/// deployment
+pub async fn get_trust(
+    State(app): State<AppState>,
+    Query(query): Query<TrustQuery>,
+) -> Result<Json<TrustResponse>, ApiError> {
+    info!("Received request for the trust assumptions");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+    let mode = chain_mode(&state_manager)?;
+    Ok(Json(TrustResponse::load(&state_manager, &mode)?))
+}
+
+/// Query parameters accepted by the state endpoint, the trusted head of a chain is returned
+/// without `address` and `slot`
+#[derive(Debug, Deserialize)]
+pub struct StateQuery {
+    /// The chain to return the trusted head of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+    /// The account to read, 0x-prefixed hex
+    pub address: Option<String>,
+    /// The storage slot to read, decimal or 0x-prefixed hex
+    pub slot: Option<String>,
+}
+
+/// The trusted head of a proof chain, for monitors that don't need its proofs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedStateResponse {
+    pub chain_id: String,
+    pub mode: String,
+    pub trusted_slot: u64,
+    pub trusted_height: u64,
+    /// 0x-prefixed hex
+    pub trusted_root: String,
+    pub update_counter: u64,
+    /// `{backend}:{chain_id}:{update_counter}`
+    pub update_id: String,
+    /// Unix timestamp (seconds) at which the latest round was committed
+    pub generated_at: Option<u64>,
+}
+
+/// A storage value proven against the latest trusted execution state root
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateResponse {
+    /// The trusted execution height the value was read at
+    pub height: u64,
+    /// The trusted execution state root, 0x-prefixed hex
+    pub root: String,
+    pub address: String,
+    pub slot: String,
+    /// The big-endian word held by the slot, 0x-prefixed hex
+    pub value: String,
+    /// The storage root of the account, 0x-prefixed hex
+    pub storage_root: String,
+    /// The trie nodes of the account proof against `root`, 0x-prefixed hex
+    pub account_proof: Vec<String>,
+    /// The trie nodes of the storage proof against `storage_root`, 0x-prefixed hex
+    pub storage_proof: Vec<String>,
+    /// The wrapper proof of `root`, base64 encoded
+    pub proof_b64: String,
+    /// The public values committed by the wrapper circuit, base64 encoded
+    pub public_values_b64: String,
+}
+
+fn parse_state_query(query: &StateQuery) -> Option<([u8; 20], [u8; 32])> {
+    let address = parse_hex_array(query.address.as_deref()?.trim()).ok()?;
+    let slot = query
+        .slot
+        .as_deref()?
+        .trim()
+        .parse::<U256>()
+        .ok()?
+        .to_be_bytes();
+    Some((address, slot))
+}
+
+fn encode_nodes(nodes: &[Vec<u8>]) -> Vec<String> {
+    nodes.iter().map(|node| to_hex(node)).collect()
+}
+
+/// Returns the trusted head of a chain, straight from the state database without its proofs
+async fn get_trusted_state(app: &AppState, chain_id: &str) -> Result<Response, ApiError> {
+    info!("Received request for the trusted state");
+    let state_manager = app.state_manager(chain_id)?;
+    let head = state_manager
+        .load_head()?
+        .ok_or_else(|| no_state(chain_id))?;
+    if let Some(response) = cache::get(chain_id, "state", head.update_counter) {
+        return Ok(response);
+    }
+    let mode = chain_mode(&state_manager)?;
+    let response = TrustedStateResponse {
+        chain_id: chain_id.to_string(),
+        update_id: update_id(&mode, chain_id, head.update_counter),
+        mode,
+        trusted_slot: head.trusted_slot,
+        trusted_height: head.trusted_height,
+        trusted_root: to_hex(head.trusted_root),
+        update_counter: head.update_counter,
+        generated_at: head.generated_at,
+    };
+    let response = (StatusCode::OK, Json(response)).into_response();
+    Ok(cache::store(chain_id, "state", head.update_counter, response).await)
+}
+
+/// Without `address` and `slot`, returns the trusted head of a chain. Otherwise reads a
+/// storage slot from `ETHEREUM_URL` at the latest trusted execution height (Helios only), and
+/// returns it once its proofs verify against the trusted state root, together with the proofs
+/// and the wrapper proof of the root
+pub async fn get_state(
+    State(app): State<AppState>,
+    Query(query): Query<StateQuery>,
+) -> Result<Response, ApiError> {
+    if query.address.is_none() && query.slot.is_none() {
+        return get_trusted_state(&app, query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID))
+            .await;
+    }
+    info!("Received request for a storage value");
+    let (address, slot) = parse_state_query(&query)
+        .ok_or_else(|| ApiError::BadRequest("Invalid address or slot".to_string()))?;
+    let execution_rpc = std::env::var("ETHEREUM_URL").map_err(|_| {
+        ApiError::Upstream(anyhow::anyhow!(
+            "ETHEREUM_URL must be set to serve state queries"
+        ))
+    })?;
+
+    let state_manager = app.state_manager(DEFAULT_CHAIN_ID)?;
+    if active_backend(&state_manager)? != "HELIOS" {
+        return Err(ApiError::BadRequest(
+            "State queries require the Helios backend".to_string(),
+        ));
+    }
+    let service_state = state_manager
+        .load_state()?
+        .ok_or_else(|| no_state(DEFAULT_CHAIN_ID))?;
+    let Some(proof) = service_state.most_recent_wrapper_proof.as_ref() else {
+        return Err(not_committed(
+            &state_manager,
+            "No execution state root is proven yet".to_string(),
+        ));
+    };
+
+    let rpc = ExecutionRpcClient::new(execution_rpc);
+    let (account, storage) = rpc
+        .get_proof(&address, &[slot], service_state.trusted_height)
+        .await
+        .context(Upstream("Failed to fetch storage proofs".to_string()))?;
+
+    // Never serve a value that is not proven by the trusted root
+    let slots =
+        verify_storage_slots(&service_state.trusted_root, &account, &storage).map_err(|e| {
+            ApiError::Upstream(anyhow::anyhow!(
+                "Storage proofs do not verify against the trusted state root: {:?}",
+                e
+            ))
+        })?;
+    let (Some(value), Some(storage)) = (slots.first(), storage.first()) else {
+        return Err(ApiError::Upstream(anyhow::anyhow!(
+            "The execution RPC returned no storage proof"
+        )));
+    };
+
+    let response = StateResponse {
+        height: service_state.trusted_height,
+        root: to_hex(service_state.trusted_root),
+        address: to_hex(address),
+        slot: to_hex(slot),
+        value: to_hex(value.value),
+        storage_root: to_hex(account.storage_root),
+        account_proof: encode_nodes(&account.proof),
+        storage_proof: encode_nodes(&storage.proof),
+        proof_b64: to_base64(proof.bytes()),
+        public_values_b64: to_base64(proof.public_values.as_slice()),
+    };
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+/// An annotation of an operator, see [`post_annotation`]
+#[derive(Debug, Deserialize)]
+pub struct AnnotationRequest {
+    /// The annotated chain, defaults to the single configured chain
+    pub chain_id: Option<String>,
+    /// The annotated update, the chain itself if absent
+    pub update_counter: Option<u64>,
+    pub text: String,
+}
+
+/// Attaches a note of an operator, e.g. an RPC outage or a planned circuit upgrade, to an
+/// update or a proof chain. Annotations are served with the history and the stats
+pub async fn post_annotation(
+    State(app): State<AppState>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<Response, ApiError> {
+    info!("Received request to annotate {:?}", request);
+    let text = request.text.trim();
+    if text.is_empty() || text.len() > 1000 {
+        return Err(ApiError::BadRequest(
+            "The annotation must have between 1 and 1000 bytes".to_string(),
+        ));
+    }
+    let chain_id = request.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+
+    if let Some(update_counter) = request.update_counter {
+        let records = state_manager.load_history(update_counter, 1)?;
+        if records.first().map(|record| record.update_counter) != Some(update_counter) {
+            return Err(ApiError::NotFound(format!(
+                "No update {} to annotate",
+                update_counter
+            )));
+        }
+    }
+    let annotation = state_manager.annotate(request.update_counter, text)?;
+    Ok((StatusCode::CREATED, Json(annotation)).into_response())
+}
+
+/// Query parameters accepted by the publication hold endpoint
+#[derive(Debug, Deserialize)]
+pub struct HoldQuery {
+    /// The chain to return the held update of, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// A proven update held back from publication, with a hex-encoded root
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldResponse {
+    pub update_counter: u64,
+    pub height: u64,
+    pub root: String,
+    pub proven_at: u64,
+    /// Unix timestamp (seconds) at which the update is committed and published
+    pub publish_at: u64,
+    pub aborted: bool,
+}
+
+impl From<HeldUpdate> for HoldResponse {
+    fn from(held: HeldUpdate) -> Self {
+        Self {
+            update_counter: held.update_counter,
+            height: held.height,
+            root: to_hex(held.root),
+            proven_at: held.proven_at,
+            publish_at: held.publish_at,
+            aborted: held.aborted,
+        }
+    }
+}
+
+/// Returns the update of a chain held back from publication by `PUBLICATION_DELAY_SECS`
+pub async fn get_hold(
+    State(app): State<AppState>,
+    Query(query): Query<HoldQuery>,
+) -> Result<Json<HoldResponse>, ApiError> {
+    info!("Received request for the held update");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let held = app
+        .state_manager(chain_id)?
+        .load_hold()?
+        .ok_or_else(|| ApiError::NotFound(format!("Chain {} holds no update", chain_id)))?;
+    Ok(Json(held.into()))
+}
+
+/// The held update whose publication to abort, see [`post_abort_hold`]
+#[derive(Debug, Deserialize)]
+pub struct AbortRequest {
+    /// The chain of the held update, defaults to the single configured chain
+    pub chain_id: Option<String>,
+    /// The held update, so that a later update is never aborted by mistake
+    pub update_counter: u64,
+}
+
+/// Aborts the publication of a held update. The prover drops its round within seconds and
+/// proves it again
+pub async fn post_abort_hold(
+    State(app): State<AppState>,
+    Json(request): Json<AbortRequest>,
+) -> Result<StatusCode, ApiError> {
+    info!("Received request to abort {:?}", request);
+    let chain_id = request.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    if !app
+        .state_manager(chain_id)?
+        .abort_hold(request.update_counter)?
+    {
+        return Err(ApiError::NotFound(format!(
+            "Update {} is not held",
+            request.update_counter
+        )));
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Query parameters accepted by the snapshot endpoint
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQuery {
+    /// The chain to snapshot, defaults to the single configured chain
+    pub chain_id: Option<String>,
+}
+
+/// Returns the latest committed update of a chain with its proofs, to warm start another
+/// deployment from it, see [`crate::warm_start`]
+pub async fn get_snapshot(
+    State(app): State<AppState>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Json<Snapshot>, ApiError> {
+    info!("Received request for a snapshot");
+    let chain_id = query.chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+    let state_manager = app.state_manager(chain_id)?;
+    if state_manager.load_head()?.is_none() {
+        return Err(no_state(chain_id));
+    }
+    let mode = chain_mode(&state_manager)?;
+    match Snapshot::load(&state_manager, &mode)? {
+        Some(snapshot) => Ok(Json(snapshot)),
+        None => Err(not_committed(
+            &state_manager,
+            format!("Chain {} has not committed its first proof yet", chain_id),
+        )),
+    }
+}
+
+/// Initializes a chain that committed nothing yet from the snapshot of another deployment.
+/// The chain must be disabled, it resumes from the snapshot once enabled again
+pub async fn post_warm_start(
+    State(app): State<AppState>,
+    Json(snapshot): Json<Snapshot>,
+) -> Result<(StatusCode, Json<HistoryRecord>), ApiError> {
+    info!(
+        "Received request to warm start chain {} from update {}",
+        snapshot.chain_id, snapshot.record.update_counter
+    );
+    let state_manager = app.state_manager(&snapshot.chain_id)?;
+    let enabled = state_manager
+        .load_control()?
+        .is_none_or(|control| control.is_enabled(&snapshot.chain_id));
+    if enabled {
+        return Err(ApiError::BadRequest(format!(
+            "Disable chain {} with PUT /admin/control before warm starting it",
+            snapshot.chain_id
+        )));
+    }
+    let mode = active_backend(&state_manager)?;
+    let verify = !ProverMode::from_env()?.mocks_circuits();
+    // Verifying the wrapper proof sets up its circuit
+    let record = tokio::task::spawn_blocking(move || {
+        warm_start::warm_start(&state_manager, &mode, snapshot, verify)
+    })
+    .await
+    .map_err(anyhow::Error::from)?
+    .map_err(|e| match e.downcast::<InvalidSnapshot>() {
+        Ok(invalid) => ApiError::BadRequest(invalid.0),
+        Err(e) => e.into(),
+    })?;
+    Ok((StatusCode::CREATED, Json(record.into())))
+}
+
+/// Returns the configuration the prover loops were last asked to run with
+pub async fn get_control(State(control): State<Control>) -> Json<ControlState> {
+    Json(control.current())
+}
+
+/// Switches the backend or the disabled chains. The running loops finish their round before
+/// the new configuration starts, the request returns immediately
+pub async fn put_control(
+    State(control): State<Control>,
+    Json(state): Json<ControlState>,
+) -> Result<Response, ApiError> {
+    info!("Received request to run {:?}", state);
+    control
+        .request(state.clone())
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok((StatusCode::ACCEPTED, Json(state)).into_response())
+}
@@ -0,0 +1,330 @@
+// Export of every committed proof to cold storage.
+//
+// The state database only keeps the most recent proofs of a chain, every commit replaces the
+// previous ones. When `PROOF_ARCHIVE_DIR` is set (e.g. a mounted bucket), each update is
+// exported as a self-contained JSON bundle before it is replaced, together with an index, so
+// the full proof chain stays reconstructible for audits.
+//
+// Layout: `<dir>/<chain_id>/<update_counter>.json` and `<dir>/<chain_id>/index.jsonl`, one
+// line per bundle in commit order.
+//
+// When `PROOF_ARCHIVE_S3` is set to an `s3://bucket/prefix` location, every bundle is also
+// uploaded to S3-compatible storage in the background, as `<prefix>/<chain_id>/<update_counter>.json`
+// next to a `<update_counter>.manifest.json` with its index entry and round metadata. The API
+// redirects requests of earlier heights, whose proofs the state database no longer holds, to
+// the uploaded bundles.
+
+use anyhow::{Context, Result};
+use object_store::{ObjectStore, PutPayload, aws::AmazonS3Builder, path::Path as ObjectPath};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    api::HistoryRecord,
+    artifacts,
+    encoding::{to_base64, to_hex},
+    prover::onchain_bytes,
+    rounds::RoundMetadata,
+    state::{ServiceState, StateManager},
+};
+
+/// A committed update with its proofs, everything needed to verify it offline
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub chain_id: String,
+    pub mode: String,
+    pub record: HistoryRecord,
+    /// The wrapper proof bytes, base64 encoded
+    pub proof_b64: String,
+    /// The public values committed by the wrapper circuit, base64 encoded
+    pub public_values_b64: String,
+    /// The recursive proof the wrapper proof verified, base64 encoded, absent for the proofs
+    /// anchored to another proof chain and for compressed recursive proofs
+    pub recursive_proof_b64: Option<String>,
+    pub recursive_public_values_b64: Option<String>,
+}
+
+/// A line of the index of a chain
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub update_counter: u64,
+    /// Empty in the indexes of earlier releases
+    #[serde(default)]
+    pub update_id: String,
+    pub height: u64,
+    pub root: String,
+    pub hash: String,
+    /// The bundle file, relative to the chain directory
+    pub bundle: String,
+}
+
+/// What is uploaded next to a bundle, to list an archive without downloading the proofs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub chain_id: String,
+    pub mode: String,
+    #[serde(flatten)]
+    pub entry: IndexEntry,
+    /// How the round was proven, absent for the proof chains anchored to another chain
+    pub round: Option<RoundMetadata>,
+}
+
+/// The configured cold storage, disabled by default
+#[derive(Debug, Clone, Default)]
+pub struct ProofArchive {
+    dir: Option<PathBuf>,
+    remote: Option<RemoteArchive>,
+}
+
+impl ProofArchive {
+    /// Archives to the directory referenced by `PROOF_ARCHIVE_DIR` and to the bucket of
+    /// `PROOF_ARCHIVE_S3`, nothing if both are unset
+    pub fn from_env() -> Result<Self> {
+        let dir = match std::env::var("PROOF_ARCHIVE_DIR") {
+            Ok(dir) => {
+                let dir = PathBuf::from(dir);
+                std::fs::create_dir_all(&dir)
+                    .context(format!("Failed to create proof archive {}", dir.display()))?;
+                Some(dir)
+            }
+            Err(_) => None,
+        };
+        let remote = match std::env::var("PROOF_ARCHIVE_S3") {
+            Ok(location) => Some(RemoteArchive::new(&location)?),
+            Err(_) => None,
+        };
+        Ok(Self { dir, remote })
+    }
+
+    /// The public URL of the uploaded bundle of an update, if bundles are uploaded
+    pub fn archived_url(&self, chain_id: &str, update_counter: u64) -> Option<String> {
+        self.remote
+            .as_ref()?
+            .url(chain_id, &format!("{}.json", update_counter))
+            .ok()
+    }
+
+    /// The bundle of an update, if it was archived
+    pub fn bundle_path(&self, chain_id: &str, update_counter: u64) -> Option<PathBuf> {
+        let path = self
+            .dir
+            .as_ref()?
+            .join(chain_id)
+            .join(format!("{}.json", update_counter));
+        path.exists().then_some(path)
+    }
+
+    /// The index entry of the latest archived update of a chain, if any
+    pub fn latest_entry(&self, chain_id: &str) -> Result<Option<IndexEntry>> {
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        let path = dir.join(chain_id).join("index.jsonl");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let index = std::fs::read_to_string(&path).context(format!(
+            "Failed to read the archive index {}",
+            path.display()
+        ))?;
+        index
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .transpose()
+    }
+
+    /// Exports the latest committed update of a chain, if it is not archived yet. Failures
+    /// are logged, archiving never stops the prover
+    pub fn export_latest(&self, state_manager: &StateManager, mode: &str) {
+        if self.dir.is_none() && self.remote.is_none() {
+            return;
+        }
+        if let Err(e) = self.export(state_manager, mode) {
+            tracing::warn!(
+                "⚠️  Failed to archive the latest proof of {}: {}",
+                state_manager.chain_id(),
+                e
+            );
+        }
+    }
+
+    fn export(&self, state_manager: &StateManager, mode: &str) -> Result<()> {
+        let Some(state) = state_manager.load_state()? else {
+            return Ok(());
+        };
+        let Some(record) = state_manager
+            .load_history(state.update_counter, 1)?
+            .into_iter()
+            .find(|record| record.update_counter == state.update_counter)
+        else {
+            return Ok(());
+        };
+
+        let chain_id = state_manager.chain_id();
+        let entry = IndexEntry {
+            update_counter: record.update_counter,
+            update_id: record.update_id.clone(),
+            height: record.height,
+            root: to_hex(record.root),
+            hash: to_hex(record.hash),
+            bundle: format!("{}.json", record.update_counter),
+        };
+        let bundle = bundle(chain_id, mode, record.into(), &state)?;
+        if let Some(dir) = &self.dir {
+            export_bundle(&dir.join(chain_id), &entry, &bundle)?;
+        }
+        if let Some(remote) = &self.remote {
+            let manifest = ArchiveManifest {
+                chain_id: chain_id.to_string(),
+                mode: mode.to_string(),
+                round: state_manager.load_round_metadata(entry.update_counter)?,
+                entry,
+            };
+            remote.upload(&bundle, &manifest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a bundle to the directory of its chain and indexes it, unless it was exported
+fn export_bundle(chain_dir: &Path, entry: &IndexEntry, bundle: &ProofBundle) -> Result<()> {
+    std::fs::create_dir_all(chain_dir)?;
+    let bundle_path = chain_dir.join(&entry.bundle);
+    if bundle_path.exists() {
+        return Ok(());
+    }
+
+    // Write the bundle atomically before indexing it, a crash leaves no partial bundle
+    let tmp_path = chain_dir.join(format!("{}.tmp", entry.bundle));
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(bundle)?)?;
+    std::fs::rename(&tmp_path, &bundle_path)?;
+    let mut index = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(chain_dir.join("index.jsonl"))?;
+    writeln!(index, "{}", serde_json::to_string(entry)?)?;
+
+    tracing::info!(
+        "🗄️  Archived update {} of {} to {}",
+        entry.update_counter,
+        bundle.chain_id,
+        bundle_path.display()
+    );
+    Ok(())
+}
+
+/// An S3-compatible bucket bundles are uploaded to, with the credentials of the `AWS_*`
+/// variables and the endpoint of `S3_ENDPOINT`
+#[derive(Debug, Clone)]
+struct RemoteArchive {
+    store: Arc<dyn ObjectStore>,
+    /// The `s3://bucket/prefix` location of the archive
+    location: String,
+    prefix: String,
+}
+
+impl RemoteArchive {
+    fn new(location: &str) -> Result<Self> {
+        let path = location.strip_prefix("s3://").context(format!(
+            "Invalid PROOF_ARCHIVE_S3 {}, expected s3://bucket/prefix",
+            location
+        ))?;
+        let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+        let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+        if let Ok(endpoint) = std::env::var("S3_ENDPOINT") {
+            builder = builder
+                .with_allow_http(endpoint.starts_with("http://"))
+                .with_endpoint(endpoint);
+        }
+        Ok(Self {
+            store: Arc::new(builder.build()?),
+            location: format!("s3://{}", path.trim_end_matches('/')),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    /// The key of the object `name` of a chain
+    fn key(&self, chain_id: &str, name: &str) -> String {
+        match self.prefix.as_str() {
+            "" => format!("{}/{}", chain_id, name),
+            prefix => format!("{}/{}/{}", prefix, chain_id, name),
+        }
+    }
+
+    /// The URL the object `name` of a chain is downloaded from, under `PROOF_ARCHIVE_PUBLIC_URL`
+    /// if the bucket is served from elsewhere, e.g. a CDN
+    fn url(&self, chain_id: &str, name: &str) -> Result<String> {
+        match std::env::var("PROOF_ARCHIVE_PUBLIC_URL") {
+            Ok(base) => Ok(artifacts::join(&base, &format!("{}/{}", chain_id, name))),
+            Err(_) => artifacts::http_url(&artifacts::join(
+                &self.location,
+                &format!("{}/{}", chain_id, name),
+            )),
+        }
+    }
+
+    /// Uploads a bundle and then its manifest in the background, failures are logged
+    fn upload(&self, bundle: &ProofBundle, manifest: &ArchiveManifest) -> Result<()> {
+        let chain_id = manifest.chain_id.clone();
+        let update_counter = manifest.entry.update_counter;
+        let objects = [
+            (
+                self.key(&chain_id, &manifest.entry.bundle),
+                serde_json::to_vec_pretty(bundle)?,
+            ),
+            (
+                self.key(&chain_id, &format!("{}.manifest.json", update_counter)),
+                serde_json::to_vec_pretty(manifest)?,
+            ),
+        ];
+        let store = self.store.clone();
+        let location = self.location.clone();
+        tokio::spawn(async move {
+            for (key, bytes) in objects {
+                let path = ObjectPath::from(key.as_str());
+                if let Err(e) = store.put(&path, PutPayload::from(bytes)).await {
+                    tracing::warn!("⚠️  Failed to upload {} to {}: {}", key, location, e);
+                    return;
+                }
+            }
+            tracing::info!(
+                "🪣 Uploaded update {} of {} to {}",
+                update_counter,
+                chain_id,
+                location
+            );
+        });
+        Ok(())
+    }
+}
+
+/// Bundles the committed proofs of `state` with their history record
+pub fn bundle(
+    chain_id: &str,
+    mode: &str,
+    record: HistoryRecord,
+    state: &ServiceState,
+) -> Result<ProofBundle> {
+    let proof = state
+        .most_recent_wrapper_proof
+        .as_ref()
+        .context("The committed update has no wrapper proof")?;
+    let recursive_proof = state.most_recent_recursive_proof.as_ref();
+    Ok(ProofBundle {
+        chain_id: chain_id.to_string(),
+        mode: mode.to_string(),
+        record,
+        proof_b64: to_base64(proof.bytes()),
+        public_values_b64: to_base64(proof.public_values.as_slice()),
+        recursive_proof_b64: recursive_proof.and_then(onchain_bytes).map(to_base64),
+        recursive_public_values_b64: recursive_proof
+            .map(|proof| to_base64(proof.public_values.as_slice())),
+    })
+}
@@ -0,0 +1,291 @@
+// Loading of circuit artifacts (ELFs) from the local filesystem or a remote registry.
+//
+// `ELFS_OUT` may be a local directory, an HTTP(S) URL or an `s3://bucket/prefix` URL.
+// Remote artifacts must be pinned by checksum in the sha256sum-formatted file referenced
+// by `ELFS_SHA256SUMS`, local artifacts are checked against it when an entry exists.
+//
+// `dump-elfs` writes a manifest next to the ELFs, recording the SHA-256, vk, SP1 circuit
+// version and circuits git commit of every ELF it dumps. ELFs are checked against the manifest
+// of their directory when it has one, so a stale ELF copied next to the ELFs of another dump
+// is refused instead of failing in-circuit.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sp1_sdk::SP1_CIRCUIT_VERSION;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Name of the manifest `dump-elfs` writes next to the ELFs
+pub const MANIFEST_NAME: &str = "elfs-manifest.json";
+
+/// The git commit the circuits built into this binary were compiled at
+pub const CIRCUITS_GIT_COMMIT: &str = env!("CIRCUITS_GIT_COMMIT");
+
+/// What a dump recorded about one of its ELFs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub sha256: String,
+    /// bytes32 of the program vk
+    pub vk: String,
+    pub sp1_circuit_version: String,
+    pub git_commit: String,
+}
+
+impl ManifestEntry {
+    /// The entry of an ELF built into this binary
+    pub fn new(elf: &[u8], vk: String) -> Self {
+        Self {
+            sha256: hex::encode(Sha256::digest(elf)),
+            vk,
+            sp1_circuit_version: SP1_CIRCUIT_VERSION.to_string(),
+            git_commit: CIRCUITS_GIT_COMMIT.to_string(),
+        }
+    }
+}
+
+/// The ELFs of an `ELFS_OUT` directory, by name relative to it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElfManifest {
+    pub elfs: BTreeMap<String, ManifestEntry>,
+}
+
+impl ElfManifest {
+    /// The manifest of the local directory `elfs_path`, empty if it has none
+    pub fn read(elfs_path: &Path) -> Result<Self> {
+        let path = elfs_path.join(MANIFEST_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(&path)
+            .context(format!("Failed to read ELF manifest {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .context(format!("Failed to parse ELF manifest {}", path.display()))
+    }
+
+    pub fn write(&self, elfs_path: &Path) -> Result<()> {
+        let path = elfs_path.join(MANIFEST_NAME);
+        std::fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .context(format!("Failed to write ELF manifest {}", path.display()))
+    }
+
+    /// The manifest of `base`, a local directory or remote prefix, `None` if it has none
+    async fn load(base: &str) -> Result<Option<Self>> {
+        let location = join(base, MANIFEST_NAME);
+        let contents = if is_remote(base) {
+            match fetch_optional(&location).await? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            }
+        } else if Path::new(&location).exists() {
+            std::fs::read(&location).context(format!("Failed to read {}", location))?
+        } else {
+            return Ok(None);
+        };
+        let manifest = serde_json::from_slice(&contents)
+            .context(format!("Failed to parse ELF manifest {}", location))?;
+        Ok(Some(manifest))
+    }
+
+    /// Fails unless the ELF `name` is the one the manifest records, dumped by the SP1 circuit
+    /// version of this binary
+    fn check(&self, name: &str, elf: &[u8]) -> Result<()> {
+        let entry = self.elfs.get(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} is not in the ELF manifest, dump the ELFs again with `service dump-elfs`",
+                name
+            )
+        })?;
+        let sha256 = hex::encode(Sha256::digest(elf));
+        if sha256 != entry.sha256 {
+            return Err(anyhow::anyhow!(
+                "{} has sha256 {} but the ELF manifest records {} (circuits at {}), it was \
+                 replaced after the ELFs were dumped. Dump all ELFs again with `service \
+                 dump-elfs`",
+                name,
+                sha256,
+                entry.sha256,
+                entry.git_commit
+            ));
+        }
+        if entry.sp1_circuit_version != SP1_CIRCUIT_VERSION {
+            return Err(anyhow::anyhow!(
+                "{} was built with SP1 {} but the service runs SP1 {}, rebuild the circuits",
+                name,
+                entry.sp1_circuit_version,
+                SP1_CIRCUIT_VERSION
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the location points to a remote artifact registry
+pub fn is_remote(location: &str) -> bool {
+    location.starts_with("http://")
+        || location.starts_with("https://")
+        || location.starts_with("s3://")
+}
+
+/// Joins an artifact name to a local directory or remote prefix
+pub fn join(base: &str, name: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), name)
+}
+
+/// Reads the artifact `name` (relative to `base`) and verifies its pinned checksum
+pub async fn read_artifact(base: &str, name: &str) -> Result<Vec<u8>> {
+    let location = join(base, name);
+    let bytes = if is_remote(base) {
+        info!("Fetching {} from remote registry", location);
+        fetch(&location).await?
+    } else {
+        std::fs::read(&location).context(format!("Failed to read {}", location))?
+    };
+
+    let checksums = load_checksums()?;
+    match checksums.get(name) {
+        Some(expected) => {
+            let actual = hex::encode(Sha256::digest(&bytes));
+            if &actual != expected {
+                return Err(anyhow::anyhow!(
+                    "Checksum mismatch for {}: expected {}, got {}",
+                    location,
+                    expected,
+                    actual
+                ));
+            }
+        }
+        None if is_remote(base) => {
+            return Err(anyhow::anyhow!(
+                "No checksum pinned for remote artifact {} in ELFS_SHA256SUMS",
+                name
+            ));
+        }
+        None => {}
+    }
+
+    match ElfManifest::load(base).await? {
+        Some(manifest) => manifest
+            .check(name, &bytes)
+            .context(format!("Failed to load {}", location))?,
+        None => warn!(
+            "⚠️  No ELF manifest at {}, {} is not checked against the ELFs dumped with it",
+            join(base, MANIFEST_NAME),
+            name
+        ),
+    }
+
+    Ok(bytes)
+}
+
+/// Resolves an `s3://` URL against `S3_ENDPOINT` (path-style) or the public AWS endpoint of
+/// the bucket, other locations are returned as is
+pub fn http_url(location: &str) -> Result<String> {
+    let Some(path) = location.strip_prefix("s3://") else {
+        return Ok(location.to_string());
+    };
+    let (bucket, key) = path
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("Invalid S3 location {}", location))?;
+    Ok(match std::env::var("S3_ENDPOINT") {
+        Ok(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key),
+        Err(_) => format!("https://{}.s3.amazonaws.com/{}", bucket, key),
+    })
+}
+
+/// Downloads an artifact over HTTP(S), `s3://` URLs are resolved with [`http_url`]
+async fn fetch(location: &str) -> Result<Vec<u8>> {
+    fetch_optional(location)
+        .await?
+        .context(format!("Failed to fetch {}: not found", location))
+}
+
+/// Downloads an artifact like [`fetch`], `None` if the registry doesn't have it
+async fn fetch_optional(location: &str) -> Result<Option<Vec<u8>>> {
+    let url = http_url(location)?;
+
+    let response = crate::egress::client()
+        .get(&url)
+        .send()
+        .await
+        .context(format!("Failed to fetch {}", url))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = response
+        .error_for_status()
+        .context(format!("Failed to fetch {}", url))?;
+    Ok(Some(response.bytes().await?.to_vec()))
+}
+
+/// Parses the `sha256sum`-formatted file referenced by `ELFS_SHA256SUMS`
+fn load_checksums() -> Result<HashMap<String, String>> {
+    let Ok(path) = std::env::var("ELFS_SHA256SUMS") else {
+        return Ok(HashMap::new());
+    };
+    let contents =
+        std::fs::read_to_string(&path).context(format!("Failed to read checksums {}", path))?;
+    Ok(parse_checksums(&contents))
+}
+
+fn parse_checksums(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (checksum, name) = line.trim().split_once(char::is_whitespace)?;
+            // sha256sum marks binary mode with a leading `*`
+            let name = name.trim().trim_start_matches('*').trim_start_matches("./");
+            Some((name.to_string(), checksum.to_lowercase()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_checksums() {
+        let checksums = parse_checksums(
+            "ab12  helios-recursive-elf.bin\nCD34 *./pion-1/tendermint-wrapper-elf.bin\n\n",
+        );
+        assert_eq!(checksums["helios-recursive-elf.bin"], "ab12");
+        assert_eq!(checksums["pion-1/tendermint-wrapper-elf.bin"], "cd34");
+        assert_eq!(checksums.len(), 2);
+    }
+
+    #[test]
+    fn test_manifest_check() {
+        let recursive_elf = b"\x7fELF recursive".as_slice();
+        let mut manifest = ElfManifest::default();
+        manifest.elfs.insert(
+            "helios-recursive-elf.bin".to_string(),
+            ManifestEntry::new(recursive_elf, "0x00ab".to_string()),
+        );
+        assert!(
+            manifest
+                .check("helios-recursive-elf.bin", recursive_elf)
+                .is_ok()
+        );
+
+        // A stale ELF, an ELF the dump didn't write or one of another SP1 version are refused
+        assert!(
+            manifest
+                .check("helios-recursive-elf.bin", b"\x7fELF stale")
+                .is_err()
+        );
+        assert!(
+            manifest
+                .check("helios-wrapper-elf.bin", recursive_elf)
+                .is_err()
+        );
+        let entry = manifest.elfs.get_mut("helios-recursive-elf.bin").unwrap();
+        entry.sp1_circuit_version = "v0.0.0".to_string();
+        assert!(
+            manifest
+                .check("helios-recursive-elf.bin", recursive_elf)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use bitcoin_recursion_types::{
+    RecursionCircuitInputs as BitcoinRecursionCircuitInputs,
+    RecursionCircuitOutputs as BitcoinRecursionCircuitOutputs,
+    WrapperCircuitInputs as BitcoinWrapperCircuitInputs, decode_outputs as decode_bitcoin_outputs,
+    pow::BlockHeader,
+};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+
+use super::{AlreadyCommitted, LightClientBackend, RecursionOutputs};
+use crate::{preprocessor::bitcoin::EsploraClient, state::ServiceState};
+
+/// Maximum number of headers proven in a single round
+const MAX_HEADERS_PER_ROUND: u64 = 144;
+
+/// Proves a chain of Bitcoin block headers and commits the best block hash.
+///
+/// There is no separate base proof, the headers are validated directly in the
+/// recursion circuit. Only blocks with at least `confirmations` confirmations
+/// are proven.
+pub struct BitcoinBackend {
+    client: EsploraClient,
+    confirmations: u64,
+}
+
+impl BitcoinBackend {
+    pub fn new(esplora_url: String, confirmations: u64) -> Self {
+        Self {
+            client: EsploraClient::new(esplora_url),
+            confirmations,
+        }
+    }
+}
+
+impl LightClientBackend for BitcoinBackend {
+    /// The height of the new best block
+    type BaseOutputs = u64;
+    type RecursionInputs = BitcoinRecursionCircuitInputs;
+
+    fn name(&self) -> &'static str {
+        "BITCOIN"
+    }
+
+    fn rpc_endpoints(&self) -> Vec<String> {
+        vec![self.client.url().to_string()]
+    }
+
+    /// Fetches the next confirmed headers and prepares recursive circuit inputs
+    ///
+    /// This function:
+    /// 1. Checks that a block after the trusted height has enough confirmations
+    /// 2. Fetches the headers of the next confirmed blocks
+    /// 3. Checks that they extend the committed best block
+    /// 4. Prepares inputs for the recursive circuit
+    async fn prepare_inputs(
+        &self,
+        _base_pk: Option<&SP1ProvingKey>,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> Result<(u64, BitcoinRecursionCircuitInputs)> {
+        tracing::info!("🔗 Connecting to Esplora...");
+        let confirmed_height = self
+            .client
+            .tip_height()
+            .await?
+            .saturating_sub(self.confirmations.saturating_sub(1));
+        if confirmed_height <= service_state.trusted_height {
+            return Err(AlreadyCommitted {
+                checkpoint: confirmed_height,
+            }
+            .into());
+        }
+
+        tracing::info!("📦 Fetching block headers...");
+        let target_height =
+            confirmed_height.min(service_state.trusted_height + MAX_HEADERS_PER_ROUND);
+        let headers = self
+            .client
+            .headers(service_state.trusted_height + 1, target_height)
+            .await?;
+
+        // Catch reorgs below the committed block before proving
+        if service_state.update_counter > 0 {
+            let first = BlockHeader::decode(&headers[0])
+                .map_err(|e| anyhow::anyhow!("Invalid header: {:?}", e))?;
+            if first.prev_blockhash != service_state.trusted_root {
+                return Err(anyhow::anyhow!(
+                    "Block {} doesn't extend the committed block {}, the chain was reorganized",
+                    service_state.trusted_height + 1,
+                    service_state.trusted_height
+                ));
+            }
+        }
+        tracing::info!(
+            "✅ Headers {}..={} retrieved",
+            service_state.trusted_height + 1,
+            target_height
+        );
+
+        let previous_proof = service_state.most_recent_recursive_proof.clone();
+
+        // Prepare recursive circuit inputs
+        tracing::info!("📝 Preparing recursive circuit inputs...");
+        let recursion_inputs = BitcoinRecursionCircuitInputs {
+            headers,
+            recursive_proof: previous_proof.as_ref().map(|p| p.bytes()),
+            recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
+            recursive_vk,
+            trusted_height: service_state.trusted_height,
+        };
+
+        Ok((target_height, recursion_inputs))
+    }
+
+    fn encode_base_outputs(&self, target_height: &u64) -> Option<Vec<u8>> {
+        Some(target_height.to_be_bytes().to_vec())
+    }
+
+    fn decode_base_outputs(&self, bytes: &[u8]) -> Result<u64> {
+        Ok(u64::from_be_bytes(
+            bytes.try_into().context("Invalid Bitcoin base outputs")?,
+        ))
+    }
+
+    fn decode_outputs(&self, public_values: &[u8]) -> Result<RecursionOutputs> {
+        let outputs: BitcoinRecursionCircuitOutputs = decode_bitcoin_outputs(public_values)
+            .map_err(|e| anyhow::anyhow!("Failed to decode Bitcoin outputs: {:?}", e))?;
+        Ok(RecursionOutputs {
+            height: outputs.state.height,
+            root: outputs.state.hash,
+            vk: outputs.vk,
+        })
+    }
+
+    fn wrapper_inputs(&self, recursive_proof: &SP1ProofWithPublicValues) -> SP1Stdin {
+        let wrapper_inputs = BitcoinWrapperCircuitInputs {
+            recursive_proof: recursive_proof.bytes(),
+            recursive_public_values: recursive_proof.public_values.to_vec(),
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
+        stdin
+    }
+
+    fn update_state(
+        &self,
+        service_state: &mut ServiceState,
+        target_height: &u64,
+        outputs: &RecursionOutputs,
+    ) {
+        // As for Tendermint, the trusted slot is the block height
+        service_state.trusted_slot = *target_height;
+        service_state.trusted_height = outputs.height;
+        service_state.trusted_root = outputs.root;
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use grandpa_recursion_types::{
+    RecursionCircuitInputs as GrandpaRecursionCircuitInputs,
+    RecursionCircuitOutputs as GrandpaRecursionCircuitOutputs,
+    WrapperCircuitInputs as GrandpaWrapperCircuitInputs, decode_outputs as decode_grandpa_outputs,
+};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+
+use super::{AlreadyCommitted, LightClientBackend, RecursionOutputs};
+use crate::{preprocessor::grandpa::SubstrateRpcClient, state::ServiceState};
+
+/// Proves a Substrate chain finalized by GRANDPA and commits its state root.
+///
+/// There is no separate base proof, the justifications are verified directly
+/// in the recursion circuit.
+pub struct GrandpaBackend {
+    client: SubstrateRpcClient,
+}
+
+impl GrandpaBackend {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            client: SubstrateRpcClient::new(rpc_url),
+        }
+    }
+}
+
+impl LightClientBackend for GrandpaBackend {
+    /// The number of the finalized block
+    type BaseOutputs = u64;
+    type RecursionInputs = GrandpaRecursionCircuitInputs;
+
+    fn name(&self) -> &'static str {
+        "GRANDPA"
+    }
+
+    fn rpc_endpoints(&self) -> Vec<String> {
+        vec![self.client.url().to_string()]
+    }
+
+    /// Fetches the next justification and prepares recursive circuit inputs
+    ///
+    /// This function:
+    /// 1. Checks that a block after the trusted height was finalized
+    /// 2. Fetches the justification of the next justified block
+    /// 3. Fetches the authority set finalizing the blocks after the trusted height
+    /// 4. Prepares inputs for the recursive circuit
+    async fn prepare_inputs(
+        &self,
+        _base_pk: Option<&SP1ProvingKey>,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> Result<(u64, GrandpaRecursionCircuitInputs)> {
+        tracing::info!("🔗 Connecting to Substrate RPC...");
+        let finalized_number = self.client.finalized_number().await?;
+        if finalized_number <= service_state.trusted_height {
+            return Err(AlreadyCommitted {
+                checkpoint: finalized_number,
+            }
+            .into());
+        }
+
+        tracing::info!("📦 Fetching GRANDPA justification...");
+        let round = match self
+            .client
+            .next_round(service_state.trusted_height + 1)
+            .await?
+        {
+            Some(round) if round.target_number > service_state.trusted_height => round,
+            Some(round) => {
+                return Err(AlreadyCommitted {
+                    checkpoint: round.target_number,
+                }
+                .into());
+            }
+            None => {
+                return Err(anyhow::anyhow!(
+                    "No justification available yet for block {}",
+                    service_state.trusted_height + 1
+                ));
+            }
+        };
+
+        // The set active after the trusted block, including a change it enacted
+        let trusted_hash = self.client.block_hash(service_state.trusted_height).await?;
+        let (authorities, authority_set_id) = self.client.authorities(&trusted_hash).await?;
+        tracing::info!(
+            "✅ Justification for block {} by authority set {} retrieved",
+            round.target_number,
+            authority_set_id
+        );
+
+        let previous_proof = service_state.most_recent_recursive_proof.clone();
+
+        // Prepare recursive circuit inputs
+        tracing::info!("📝 Preparing recursive circuit inputs...");
+        let recursion_inputs = GrandpaRecursionCircuitInputs {
+            justification: round.justification,
+            header: round.header,
+            authorities,
+            authority_set_id,
+            recursive_proof: previous_proof.as_ref().map(|p| p.bytes()),
+            recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
+            recursive_vk,
+            trusted_height: service_state.trusted_height,
+        };
+
+        Ok((round.target_number, recursion_inputs))
+    }
+
+    fn encode_base_outputs(&self, target_number: &u64) -> Option<Vec<u8>> {
+        Some(target_number.to_be_bytes().to_vec())
+    }
+
+    fn decode_base_outputs(&self, bytes: &[u8]) -> Result<u64> {
+        Ok(u64::from_be_bytes(
+            bytes.try_into().context("Invalid GRANDPA base outputs")?,
+        ))
+    }
+
+    fn decode_outputs(&self, public_values: &[u8]) -> Result<RecursionOutputs> {
+        let outputs: GrandpaRecursionCircuitOutputs = decode_grandpa_outputs(public_values)
+            .map_err(|e| anyhow::anyhow!("Failed to decode GRANDPA outputs: {:?}", e))?;
+        Ok(RecursionOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            vk: outputs.vk,
+        })
+    }
+
+    fn wrapper_inputs(&self, recursive_proof: &SP1ProofWithPublicValues) -> SP1Stdin {
+        let wrapper_inputs = GrandpaWrapperCircuitInputs {
+            recursive_proof: recursive_proof.bytes(),
+            recursive_public_values: recursive_proof.public_values.to_vec(),
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
+        stdin
+    }
+
+    fn update_state(
+        &self,
+        service_state: &mut ServiceState,
+        target_number: &u64,
+        outputs: &RecursionOutputs,
+    ) {
+        // As for Tendermint, the trusted slot is the finalized block number
+        service_state.trusted_slot = *target_number;
+        service_state.trusted_height = outputs.height;
+        service_state.trusted_root = outputs.root;
+    }
+}
@@ -0,0 +1,415 @@
+use alloy_primitives::U256;
+use alloy_sol_types::SolType;
+use anyhow::{Context, Result};
+use beacon_electra::{
+    get_beacon_block_header, get_electra_block_body_roots, types::electra::ElectraBlockHeader,
+};
+use helios_recursion_types::{
+    RecursionCircuitInputs as HeliosRecursionCircuitInputs,
+    RecursionCircuitOutputs as HeliosRecursionCircuitOutputs,
+    WrapperCircuitInputs as HeliosWrapperCircuitInputs,
+    WrapperCircuitOutputs as HeliosWrapperCircuitOutputs, decode_outputs as decode_helios_outputs,
+    encode_outputs as encode_helios_outputs,
+};
+use sp1_helios_primitives::types::{ProofInputs as HeliosInputs, ProofOutputs as HeliosOutputs};
+use sp1_sdk::{ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use std::sync::Mutex;
+use tree_hash::TreeHash;
+
+use super::{
+    AlreadyCommitted, LightClientBackend, NoProgress, PeriodBoundary, RecursionOutputs, Unaligned,
+    UpdatesBehind,
+};
+use crate::{
+    HELIOS_ELF,
+    gpu::{LocalProver, cleanup_gpu_containers},
+    network::NetworkConfig,
+    networks::{EthereumNetwork, UpdateAlignment, boundary_epochs},
+    preprocessor::{HeliosInputSlice, Preprocessor},
+    prover::{Aggregation, ProverMode, WrapperEncoding, onchain_bytes},
+    state::{CommitteePeriod, ServiceState},
+    workers::WorkerPool,
+};
+
+/// Outputs of a Helios proof together with the exact program inputs it was generated from
+#[derive(Debug, Clone)]
+pub struct HeliosBaseOutputs {
+    pub outputs: HeliosOutputs,
+    /// The CBOR serialized `ProofInputs` written to the Helios program
+    pub inputs: HeliosInputSlice,
+    /// Root of the attested header of the proven finality update
+    pub attested_root: [u8; 32],
+}
+
+/// Proves the Ethereum beacon chain with Helios and commits the execution state root
+pub struct HeliosBackend {
+    consensus_url: String,
+    network: EthereumNetwork,
+    /// Root of the attested header of the last committed finality update
+    last_attested_root: Mutex<Option<[u8; 32]>>,
+}
+
+impl HeliosBackend {
+    pub fn new(consensus_url: String, network: EthereumNetwork) -> Self {
+        Self {
+            consensus_url,
+            network,
+            last_attested_root: Mutex::new(None),
+        }
+    }
+}
+
+/// Proves the Helios program on `stdin` with the configured prover, a mock proof of the
+/// executed program with `PROVER_MODE=execute`
+pub(crate) async fn prove_helios(
+    helios_pk: SP1ProvingKey,
+    stdin: SP1Stdin,
+) -> Result<SP1ProofWithPublicValues> {
+    tracing::info!("⚡ Generating Helios proof...");
+    let prover_mode = ProverMode::from_env()?;
+    let network = NetworkConfig::from_env()?.filter(|_| prover_mode != ProverMode::Execute);
+    if let Some(network) = network {
+        return network.prove("Helios", &helios_pk, &stdin).await;
+    }
+    if let Some(workers) = WorkerPool::global()?.filter(|_| prover_mode != ProverMode::Execute) {
+        return workers.prove("Helios", &helios_pk, &stdin).await;
+    }
+
+    cleanup_gpu_containers().await?;
+    let handle = if prover_mode == ProverMode::Execute {
+        // The mock prover executes the program and returns a mock proof of its outputs
+        let client = ProverClient::builder().mock().build();
+        tokio::spawn(async move { client.prove(&helios_pk, &stdin).groth16().run() })
+    } else {
+        // On the GPU of the slot the base proof holds
+        let prover = LocalProver::current();
+        tokio::spawn(async move { prover.prove_groth16(&helios_pk, &stdin) })
+    };
+
+    match handle.await {
+        Ok(Ok(proof)) => {
+            tracing::info!("✅ Helios proof generated successfully");
+            Ok(proof)
+        }
+        Ok(Err(e)) => Err(anyhow::anyhow!(
+            "❌ Helios proof generation failed: {:?}",
+            e
+        )),
+        Err(join_error) => Err(anyhow::anyhow!(
+            "❌ Helios proof task failed: {:?}",
+            join_error
+        )),
+    }
+}
+
+impl LightClientBackend for HeliosBackend {
+    type BaseOutputs = HeliosBaseOutputs;
+    type RecursionInputs = HeliosRecursionCircuitInputs;
+
+    fn name(&self) -> &'static str {
+        "HELIOS"
+    }
+
+    fn base_elf(&self) -> Option<&'static [u8]> {
+        Some(HELIOS_ELF)
+    }
+
+    fn rpc_endpoints(&self) -> Vec<String> {
+        vec![self.consensus_url.clone()]
+    }
+
+    /// Generates a Helios proof and prepares recursive circuit inputs
+    ///
+    /// This function:
+    /// 1. Runs the Helios preprocessor to get block data
+    /// 2. Skips the round if its finality checkpoint is already committed or unaligned, and
+    ///    delays it while the checkpoint is close to a sync committee period boundary
+    /// 3. Generates a Helios proof for the target slot
+    /// 4. Fetches Electra block information from consensus layer
+    /// 5. Prepares inputs for the recursive circuit
+    async fn prepare_inputs(
+        &self,
+        base_pk: Option<&SP1ProvingKey>,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> Result<(HeliosBaseOutputs, HeliosRecursionCircuitInputs)> {
+        let helios_pk = base_pk
+            .context("Helios circuit has not been set up")?
+            .clone();
+
+        // Run Helios preprocessor to get block inputs
+        tracing::info!("🌞 Running Helios preprocessor...");
+        let preprocessor = Preprocessor::new(service_state.trusted_slot, self.network);
+        let inputs = match preprocessor.run().await {
+            Ok(inputs) => {
+                tracing::info!("✅ Helios preprocessor completed successfully");
+                inputs
+            }
+            Err(e) if e.downcast_ref::<UpdatesBehind>().is_some() => return Err(e),
+            Err(e) => {
+                return Err(anyhow::anyhow!("❌ Helios preprocessor failed: {:?}", e));
+            }
+        };
+
+        // The proven head is the finalized header of the finality update, skip the round
+        // if the light client update endpoint still serves the update or the checkpoint we
+        // committed
+        let helios_inputs: HeliosInputs =
+            serde_cbor::from_slice(&inputs).context("Failed to decode Helios inputs")?;
+        let attested_root = helios_inputs
+            .finality_update
+            .attested_header()
+            .beacon()
+            .tree_hash_root()
+            .0;
+        if *self.last_attested_root.lock().unwrap() == Some(attested_root) {
+            return Err(NoProgress { attested_root }.into());
+        }
+        let finalized_slot = helios_inputs
+            .finality_update
+            .finalized_header()
+            .beacon()
+            .slot;
+        if service_state.update_counter > 0 && finalized_slot <= service_state.trusted_slot {
+            return Err(AlreadyCommitted {
+                checkpoint: finalized_slot,
+            }
+            .into());
+        }
+        let alignment = UpdateAlignment::from_env()?;
+        if !alignment.admits(self.network, service_state.trusted_slot, finalized_slot) {
+            return Err(Unaligned {
+                checkpoint: finalized_slot,
+                alignment,
+            }
+            .into());
+        }
+
+        // An update too close to a sync committee period boundary may be signed by a committee
+        // the proof chain doesn't trust yet, wait for finality to pass the boundary instead of
+        // proving it. A round catching up proves the last light client update, whose committee
+        // is verified with the updates, and the node won't serve it again further from the
+        // boundary
+        let attested_slot = helios_inputs
+            .finality_update
+            .attested_header()
+            .beacon()
+            .slot;
+        let catching_up = helios_inputs.updates.last().is_some_and(|update| {
+            update.attested_header().beacon().tree_hash_root().0 == attested_root
+        });
+        if !catching_up
+            && self
+                .network
+                .near_period_boundary(finalized_slot, attested_slot, boundary_epochs()?)
+        {
+            return Err(PeriodBoundary {
+                finalized_slot,
+                epochs_until_next_period: self.network.epochs_until_next_period(finalized_slot),
+            }
+            .into());
+        }
+
+        // Prepare inputs for Helios proof generation
+        tracing::info!("📝 Preparing inputs for Helios proof generation...");
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(&inputs);
+
+        let helios_proof = prove_helios(helios_pk, stdin).await?;
+
+        // Decode proof outputs
+        tracing::info!("🔍 Decoding Helios proof outputs...");
+        let helios_outputs: HeliosOutputs =
+            HeliosOutputs::abi_decode(&helios_proof.public_values.to_vec(), false).unwrap();
+
+        let head_slot: u64 = helios_outputs.newHead.try_into()?;
+
+        // Fetch Electra block information from consensus layer
+        tracing::info!("🔗 Fetching Electra block from consensus layer...");
+        let electra_body_roots = get_electra_block_body_roots(head_slot, &self.consensus_url).await;
+        let beacon_header = get_beacon_block_header(head_slot, &self.consensus_url).await;
+        tracing::info!("✅ Electra block retrieved successfully");
+
+        // Create Electra block header
+        tracing::info!("📋 Creating Electra block header...");
+        let electra_header = ElectraBlockHeader {
+            slot: beacon_header.slot.as_u64(),
+            proposer_index: beacon_header.proposer_index,
+            parent_root: beacon_header.parent_root.to_vec().try_into().unwrap(),
+            state_root: beacon_header.state_root.to_vec().try_into().unwrap(),
+            body_root: beacon_header.body_root.to_vec().try_into().unwrap(),
+        };
+
+        let previous_proof = service_state.most_recent_recursive_proof.clone();
+
+        // Prepare recursive circuit inputs
+        tracing::info!("📝 Preparing recursive circuit inputs...");
+        let recursion_inputs = HeliosRecursionCircuitInputs {
+            electra_body_roots,
+            electra_header,
+            helios_proof: helios_proof.bytes(),
+            helios_public_values: helios_proof.public_values.to_vec(),
+            // A compressed previous proof is written to the stdin by the prover
+            recursive_proof: previous_proof.as_ref().and_then(onchain_bytes),
+            recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
+            recursive_vk,
+            previous_head: service_state.trusted_slot,
+        };
+
+        tracing::info!("✅ Helios prover completed successfully");
+        Ok((
+            HeliosBaseOutputs {
+                outputs: helios_outputs,
+                inputs,
+                attested_root,
+            },
+            recursion_inputs,
+        ))
+    }
+
+    fn base_inputs(&self, base_outputs: &HeliosBaseOutputs) -> Option<Vec<u8>> {
+        Some(base_outputs.inputs.clone())
+    }
+
+    fn encode_base_outputs(&self, base_outputs: &HeliosBaseOutputs) -> Option<Vec<u8>> {
+        borsh::to_vec(&(
+            HeliosOutputs::abi_encode(&base_outputs.outputs),
+            base_outputs.inputs.clone(),
+            base_outputs.attested_root,
+        ))
+        .ok()
+    }
+
+    fn decode_base_outputs(&self, bytes: &[u8]) -> Result<HeliosBaseOutputs> {
+        let (outputs, inputs, attested_root): (Vec<u8>, HeliosInputSlice, [u8; 32]) =
+            borsh::from_slice(bytes).context("Invalid Helios base outputs")?;
+        Ok(HeliosBaseOutputs {
+            outputs: HeliosOutputs::abi_decode(&outputs, false)
+                .context("Invalid Helios base outputs")?,
+            inputs,
+            attested_root,
+        })
+    }
+
+    /// The committee trusted at the previous head, the one trusted at the new head and the
+    /// next one if the light client knows it
+    fn committees(&self, base_outputs: &HeliosBaseOutputs) -> Vec<CommitteePeriod> {
+        let outputs = &base_outputs.outputs;
+        let period = |slot: U256| u64::try_from(slot).unwrap() / self.network.slots_per_period();
+        let mut committees = vec![
+            CommitteePeriod {
+                period: period(outputs.prevHead),
+                committee_hash: outputs.prevSyncCommitteeHash.into(),
+            },
+            CommitteePeriod {
+                period: period(outputs.newHead),
+                committee_hash: outputs.syncCommitteeHash.into(),
+            },
+        ];
+        if outputs.nextSyncCommitteeHash.0 != [0; 32] {
+            committees.push(CommitteePeriod {
+                period: period(outputs.newHead) + 1,
+                committee_hash: outputs.nextSyncCommitteeHash.into(),
+            });
+        }
+        committees
+    }
+
+    fn decode_outputs(&self, public_values: &[u8]) -> Result<RecursionOutputs> {
+        let outputs: HeliosRecursionCircuitOutputs = decode_helios_outputs(public_values)
+            .map_err(|e| anyhow::anyhow!("Failed to decode Helios outputs: {:?}", e))?;
+        Ok(RecursionOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            vk: outputs.vk,
+        })
+    }
+
+    fn mock_recursion_outputs(&self, inputs: &[u8]) -> Result<Vec<u8>> {
+        let inputs: HeliosRecursionCircuitInputs =
+            borsh::from_slice(inputs).context("Failed to decode Helios recursion inputs")?;
+        let helios_outputs = HeliosOutputs::abi_decode(&inputs.helios_public_values, false)
+            .context("Failed to decode Helios proof outputs")?;
+        // The execution payload fields are committed as little-endian padded leaves
+        let unpad_u64 = |padded: &[u8; 32]| u64::from_le_bytes(padded[..8].try_into().unwrap());
+        let payload_roots = &inputs.electra_body_roots.payload_roots;
+        // As the circuit, refuse to extend a link that committed another vk
+        let previous = match &inputs.recursive_public_values {
+            Some(previous) => {
+                let previous = self.decode_outputs(previous)?;
+                if previous.vk != inputs.recursive_vk {
+                    return Err(anyhow::anyhow!(
+                        "Previous proof committed recursive vk {}, expected {}",
+                        previous.vk,
+                        inputs.recursive_vk
+                    ));
+                }
+                (previous.height, previous.root)
+            }
+            None => (0, [0; 32]),
+        };
+        let outputs = HeliosRecursionCircuitOutputs {
+            active_committee: helios_outputs.syncCommitteeHash.into(),
+            previous_committee: helios_outputs.prevSyncCommitteeHash.into(),
+            next_committee: Some(helios_outputs.nextSyncCommitteeHash.0)
+                .filter(|hash| *hash != [0; 32]),
+            root: payload_roots.state_root,
+            height: unpad_u64(&payload_roots.block_number),
+            block_hash: payload_roots.block_hash,
+            timestamp: unpad_u64(&payload_roots.timestamp),
+            receipts_root: payload_roots.receipts_root,
+            vk: inputs.recursive_vk,
+            previous_height: previous.0,
+            previous_root: previous.1,
+            chain_id: self.network.caip2(),
+        };
+        Ok(encode_helios_outputs(&outputs))
+    }
+
+    fn mock_wrapper_outputs(&self, inputs: &[u8]) -> Result<Vec<u8>> {
+        let inputs: HeliosWrapperCircuitInputs =
+            borsh::from_slice(inputs).context("Failed to decode Helios wrapper inputs")?;
+        // Checks the finality source of the recursion circuit
+        self.decode_outputs(&inputs.recursive_public_values)?;
+        let outputs: HeliosRecursionCircuitOutputs =
+            decode_helios_outputs(&inputs.recursive_public_values)
+                .map_err(|e| anyhow::anyhow!("Failed to decode Helios outputs: {:?}", e))?;
+        let outputs = HeliosWrapperCircuitOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            previous_height: outputs.previous_height,
+            previous_root: outputs.previous_root,
+            chain_id: outputs.chain_id,
+        };
+        Ok(match WrapperEncoding::from_env()? {
+            WrapperEncoding::Borsh => encode_helios_outputs(&outputs),
+            WrapperEncoding::Abi => outputs.abi_encode(),
+        })
+    }
+
+    fn aggregation(&self) -> Result<Aggregation> {
+        Aggregation::from_env()
+    }
+
+    fn wrapper_inputs(&self, recursive_proof: &SP1ProofWithPublicValues) -> SP1Stdin {
+        let wrapper_inputs = HeliosWrapperCircuitInputs {
+            recursive_proof: onchain_bytes(recursive_proof).unwrap_or_default(),
+            recursive_public_values: recursive_proof.public_values.to_vec(),
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
+        stdin
+    }
+
+    fn update_state(
+        &self,
+        service_state: &mut ServiceState,
+        base_outputs: &HeliosBaseOutputs,
+        outputs: &RecursionOutputs,
+    ) {
+        service_state.trusted_slot = base_outputs.outputs.newHead.try_into().unwrap();
+        *self.last_attested_root.lock().unwrap() = Some(base_outputs.attested_root);
+        service_state.trusted_height = outputs.height;
+        service_state.trusted_root = outputs.root;
+    }
+}
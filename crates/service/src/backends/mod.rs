@@ -0,0 +1,303 @@
+// Light client backends proven by the service.
+//
+// A backend knows how to produce the base light client proof of its chain, how to read
+// the trusted state committed by its recursion circuit and how to advance the service
+// state. The prover loop only talks to the [`LightClientBackend`] trait, so integrating
+// a new chain from `crates/integrations/` means adding a backend here.
+
+use anyhow::{Result, anyhow};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use std::future::Future;
+
+use crate::{
+    encoding::to_hex,
+    networks::UpdateAlignment,
+    prover::Aggregation,
+    state::{CommitteePeriod, ServiceState},
+};
+
+mod bitcoin;
+mod grandpa;
+mod helios;
+mod tendermint;
+
+pub use bitcoin::BitcoinBackend;
+pub use grandpa::GrandpaBackend;
+pub(crate) use helios::prove_helios;
+pub use helios::{HeliosBackend, HeliosBaseOutputs};
+pub use tendermint::{TendermintBackend, ibc_outputs as tendermint_ibc_outputs};
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the round would prove a
+/// checkpoint the service state already commits to, e.g. on a fast retry before the
+/// source chain finalized anything new. The round is skipped without proving.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlreadyCommitted {
+    /// The finality checkpoint (slot or height) targeted by the round
+    pub checkpoint: u64,
+}
+
+impl std::fmt::Display for AlreadyCommitted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checkpoint {} is already committed", self.checkpoint)
+    }
+}
+
+impl std::error::Error for AlreadyCommitted {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the finalized head of the round
+/// isn't on a boundary of the configured [`UpdateAlignment`]. The round is skipped without
+/// proving until a finality update reaches the next boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unaligned {
+    /// The finalized slot of the skipped round
+    pub checkpoint: u64,
+    pub alignment: UpdateAlignment,
+}
+
+impl std::fmt::Display for Unaligned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "skipped slot {}, it doesn't reach the next {} boundary",
+            self.checkpoint, self.alignment
+        )
+    }
+}
+
+impl std::error::Error for Unaligned {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the source RPC serves the same
+/// finality update as the last committed round, the chain made no progress. The round is
+/// skipped instead of generating an identical proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoProgress {
+    /// Root of the attested header of the repeated update
+    pub attested_root: [u8; 32],
+}
+
+impl std::fmt::Display for NoProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "made no progress, the finality update attested by {} was already proven",
+            to_hex(self.attested_root)
+        )
+    }
+}
+
+impl std::error::Error for NoProgress {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the finality update is too close
+/// to a sync committee period boundary for its proof to chain to the previous one. The round
+/// waits for finality to pass the boundary instead of generating a proof the recursion
+/// circuit refuses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodBoundary {
+    /// The finalized slot of the update
+    pub finalized_slot: u64,
+    /// Epochs from the finalized slot to the next sync committee period
+    pub epochs_until_next_period: u64,
+}
+
+impl std::fmt::Display for PeriodBoundary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "finalized slot {} is {} epochs before the next sync committee period, waiting \
+             for finality to pass the boundary",
+            self.finalized_slot, self.epochs_until_next_period
+        )
+    }
+}
+
+impl std::error::Error for PeriodBoundary {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the consensus node serves fewer
+/// light client updates than the periods between the trusted and the finalized slot, and none
+/// of them advances past the trusted slot. Rounds catch up on the updates a node serves, this
+/// one waits for the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdatesBehind {
+    /// The light client updates the node served
+    pub served: u64,
+    /// The light client updates the round needs
+    pub needed: u64,
+}
+
+impl std::fmt::Display for UpdatesBehind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "consensus node serves {} of {} light client updates, waiting for it to catch up",
+            self.served, self.needed
+        )
+    }
+}
+
+impl std::error::Error for UpdatesBehind {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the source chain is fewer blocks
+/// past the trusted height than a round advances at least. The round waits for the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BelowMinAdvance {
+    /// The trusted height
+    pub trusted_height: u64,
+    /// The latest height of the source chain
+    pub latest_height: u64,
+    /// The fewest blocks a round advances
+    pub min_advance: u64,
+}
+
+impl std::fmt::Display for BelowMinAdvance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "is at height {}, less than {} blocks past the trusted height {}",
+            self.latest_height, self.min_advance, self.trusted_height
+        )
+    }
+}
+
+impl std::error::Error for BelowMinAdvance {}
+
+/// Returned by [`LightClientBackend::prepare_inputs`] when the source chain restarted with
+/// another chain id after an upgrade. The round waits until the operator approves the upgrade
+/// and generates the circuits re-anchoring the proof chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainUpgraded {
+    /// The chain id of the headers of the restarted chain
+    pub network: String,
+    /// The height its headers were found at
+    pub height: u64,
+}
+
+impl std::fmt::Display for ChainUpgraded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "restarted as {} (header of height {}), approve the upgrade in the chain \
+             configuration and regenerate the circuits to re-anchor the proof chain",
+            self.network, self.height
+        )
+    }
+}
+
+impl std::error::Error for ChainUpgraded {}
+
+/// The trusted state committed by a recursion circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecursionOutputs {
+    /// The trusted execution height (Helios) or block height (Tendermint, GRANDPA, Bitcoin)
+    pub height: u64,
+    /// The trusted state root / app hash / best block hash
+    pub root: [u8; 32],
+    /// The recursive circuit vk the proof chain is bound to
+    pub vk: String,
+}
+
+/// A light client whose proofs are chained through a recursion and a wrapper circuit
+pub trait LightClientBackend: Send + Sync {
+    /// Outputs of the base light client proof needed to update the service state
+    type BaseOutputs: Clone + Send + Sync;
+    /// Inputs of the recursion circuit
+    type RecursionInputs: borsh::BorshSerialize + Clone + Send + Sync;
+
+    /// Name of the backend, as configured with `CLIENT_BACKEND`
+    fn name(&self) -> &'static str;
+
+    /// ELF of the base light client program if the service proves it with its own
+    /// proving key, which is then set up once together with the other circuits
+    fn base_elf(&self) -> Option<&'static [u8]> {
+        None
+    }
+
+    /// ELF of the base light client program the recursion circuit verifies, its vk is
+    /// recorded with the circuits even if the program is proven by an external prover
+    fn base_program_elf(&self) -> Option<&'static [u8]> {
+        self.base_elf()
+    }
+
+    /// The RPC endpoints the base proof inputs are fetched from
+    fn rpc_endpoints(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Generates the base light client proof and prepares the recursion circuit inputs.
+    /// Fails with [`AlreadyCommitted`] if there is nothing new to prove.
+    fn prepare_inputs(
+        &self,
+        base_pk: Option<&SP1ProvingKey>,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> impl Future<Output = Result<(Self::BaseOutputs, Self::RecursionInputs)>> + Send;
+
+    /// Serialized inputs of the base light client program, stored with every committed
+    /// update so auditors can re-execute the base program against the proven chain data
+    fn base_inputs(&self, _base_outputs: &Self::BaseOutputs) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Serializes the base outputs of a round, persisted with its recursive proof so a round
+    /// interrupted before its wrapper proof is resumed after a restart. Rounds of backends
+    /// returning `None` are proven again
+    fn encode_base_outputs(&self, _base_outputs: &Self::BaseOutputs) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Decodes base outputs serialized by [`Self::encode_base_outputs`]
+    fn decode_base_outputs(&self, _bytes: &[u8]) -> Result<Self::BaseOutputs> {
+        Err(anyhow!("The {} backend can't resume rounds", self.name()))
+    }
+
+    /// The committees the base proof of a round attested to by period. A round is refused if
+    /// a period maps to another committee in the committee index of the chain
+    fn committees(&self, _base_outputs: &Self::BaseOutputs) -> Vec<CommitteePeriod> {
+        Vec::new()
+    }
+
+    /// Decodes the public values committed by the recursion circuit
+    fn decode_outputs(&self, public_values: &[u8]) -> Result<RecursionOutputs>;
+
+    /// The public values the recursion circuit commits for its serialized inputs, computed on
+    /// the host without verifying the proofs they carry. Used by `PROVER_MODE=mock|execute`
+    fn mock_recursion_outputs(&self, _inputs: &[u8]) -> Result<Vec<u8>> {
+        Err(anyhow!(
+            "The {} backend has no mock prover mode",
+            self.name()
+        ))
+    }
+
+    /// The public values the wrapper circuit commits for its serialized inputs, computed on
+    /// the host without verifying the recursive proof
+    fn mock_wrapper_outputs(&self, _inputs: &[u8]) -> Result<Vec<u8>> {
+        Err(anyhow!(
+            "The {} backend has no mock prover mode",
+            self.name()
+        ))
+    }
+
+    /// How the recursion circuit verifies the previous proof of the chain. Only backends
+    /// whose circuits have a compressed blueprint support [`Aggregation::Compressed`]
+    fn aggregation(&self) -> Result<Aggregation> {
+        Ok(Aggregation::Groth16)
+    }
+
+    /// Whether the base proof only reached an intermediate height on the way to the target
+    /// of the round, e.g. a bisection step of a validator set that changed too much for a
+    /// single skip. The round then proves the next hop on top of the recursive proof of this
+    /// one, only the last hop is wrapped and committed
+    fn is_intermediate(&self, _base_outputs: &Self::BaseOutputs) -> bool {
+        false
+    }
+
+    /// Prepares the wrapper circuit inputs verifying the recursive proof
+    fn wrapper_inputs(&self, recursive_proof: &SP1ProofWithPublicValues) -> SP1Stdin;
+
+    /// Advances the trusted slot / height / root of the service state to the new round
+    fn update_state(
+        &self,
+        service_state: &mut ServiceState,
+        base_outputs: &Self::BaseOutputs,
+        outputs: &RecursionOutputs,
+    );
+}
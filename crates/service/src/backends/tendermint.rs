@@ -0,0 +1,588 @@
+use anyhow::{Context, Result, anyhow};
+use cosmrs::tendermint::{
+    block::{Commit, CommitSig, Header},
+    validator,
+};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use sp1_tendermint_primitives::TendermintOutput;
+use std::{cmp::min, sync::Mutex};
+use tendermint_prover::TendermintProver;
+use tendermint_prover::util::TendermintRPCClient;
+use tendermint_recursion_types::{
+    IbcWrapperCircuitOutputs as TendermintIbcWrapperCircuitOutputs,
+    RecursionCircuitInputs as TendermintRecursionCircuitInputs,
+    RecursionCircuitOutputs as TendermintRecursionCircuitOutputs,
+    WrapperCircuitInputs as TendermintWrapperCircuitInputs,
+    WrapperCircuitOutputs as TendermintWrapperCircuitOutputs,
+    decode_outputs as decode_tendermint_outputs, encode_outputs as encode_tendermint_outputs,
+};
+
+use super::{BelowMinAdvance, ChainUpgraded, LightClientBackend, RecursionOutputs};
+use crate::{
+    TENDERMINT_ELF,
+    chains::{TendermintChainConfig, TendermintUpgrade, TrustThreshold, tendermint_chain},
+    gpu::cleanup_gpu_containers,
+    preprocessor::tendermint::LightBlockCache,
+    prover::WrapperEncoding,
+    state::{DEFAULT_CHAIN_ID, ServiceState},
+};
+
+/// Whether `TENDERMINT_IBC_OUTPUTS` generates the Tendermint wrapper circuit committing
+/// [`TendermintIbcWrapperCircuitOutputs`], which are always borsh encoded
+pub fn ibc_outputs() -> Result<bool> {
+    match std::env::var("TENDERMINT_IBC_OUTPUTS").as_deref() {
+        Err(_) | Ok("false") => Ok(false),
+        Ok("true") if WrapperEncoding::from_env()?.is_abi() => Err(anyhow!(
+            "TENDERMINT_IBC_OUTPUTS are borsh encoded, unset WRAPPER_ENCODING=abi"
+        )),
+        Ok("true") => Ok(true),
+        Ok(value) => Err(anyhow!(
+            "Invalid TENDERMINT_IBC_OUTPUTS {}, expected true or false",
+            value
+        )),
+    }
+}
+
+/// Proves a Tendermint chain and commits its app hash
+pub struct TendermintBackend {
+    /// The Tendermint chain to prove, the environment configures it if unset
+    chain: Option<TendermintChainConfig>,
+    /// The target height of a round bisecting through intermediate heights, until it is
+    /// reached
+    round_target: Mutex<Option<u64>>,
+}
+
+impl TendermintBackend {
+    pub fn new(chain: Option<TendermintChainConfig>) -> Self {
+        Self {
+            chain,
+            round_target: Mutex::new(None),
+        }
+    }
+
+    /// The configuration of the chain, the environment's if unset
+    fn chain_config(&self) -> Result<TendermintChainConfig> {
+        match &self.chain {
+            Some(chain) => Ok(chain.clone()),
+            None => tendermint_chain(DEFAULT_CHAIN_ID),
+        }
+    }
+
+    /// The outputs the recursion circuit commits for a transition link, re-anchoring the
+    /// proof chain at the approved first header of the chain restarted after its upgrade
+    fn mock_transition_outputs(
+        &self,
+        chain: &TendermintChainConfig,
+        inputs: TendermintRecursionCircuitInputs,
+    ) -> Result<Vec<u8>> {
+        let upgrade = chain
+            .upgrade
+            .as_ref()
+            .context("No chain upgrade to re-anchor at")?;
+        let previous = self.decode_outputs(
+            inputs
+                .recursive_public_values
+                .as_ref()
+                .context("The transition has no previous proof")?,
+        )?;
+        if previous.height != upgrade.halt_height || previous.vk != inputs.recursive_vk {
+            return Err(anyhow!(
+                "The previous proof is not the proof of the halt height {} of recursive vk {}",
+                upgrade.halt_height,
+                inputs.recursive_vk
+            ));
+        }
+        let header = proven_header(
+            &inputs.target_header,
+            upgrade.trusted_root,
+            upgrade.trusted_height,
+        )?;
+        let (app_hash, next_validators_hash) = header_hashes(&header)?;
+        let outputs = TendermintRecursionCircuitOutputs {
+            root: upgrade.trusted_root,
+            height: upgrade.trusted_height,
+            vk: inputs.recursive_vk,
+            previous_height: previous.height,
+            previous_root: previous.root,
+            chain_id: chain.caip2_at(upgrade.trusted_height)?,
+            app_hash,
+            next_validators_hash,
+            time: unix_nanos(&header)?,
+            transition: true,
+        };
+        Ok(encode_tendermint_outputs(&outputs))
+    }
+}
+
+impl LightClientBackend for TendermintBackend {
+    type BaseOutputs = TendermintOutput;
+    type RecursionInputs = TendermintRecursionCircuitInputs;
+
+    fn name(&self) -> &'static str {
+        "TENDERMINT"
+    }
+
+    fn base_program_elf(&self) -> Option<&'static [u8]> {
+        Some(TENDERMINT_ELF)
+    }
+
+    fn rpc_endpoints(&self) -> Vec<String> {
+        match &self.chain {
+            Some(chain) => vec![chain.rpc_url.clone()],
+            None => std::env::var("TENDERMINT_RPC_URL").into_iter().collect(),
+        }
+    }
+
+    /// Generates a Tendermint proof and prepares recursive circuit inputs
+    ///
+    /// This function:
+    /// 1. Connects to Tendermint RPC to get latest block information
+    /// 2. Generates a Tendermint proof for the target block range
+    /// 3. Prepares inputs for the recursive circuit
+    async fn prepare_inputs(
+        &self,
+        _base_pk: Option<&SP1ProvingKey>,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> Result<(TendermintOutput, TendermintRecursionCircuitInputs)> {
+        dotenvy::dotenv().ok();
+
+        tracing::info!("🌿 Starting Tendermint proof generation...");
+        let (tendermint_proof, trusted_header, target_header) = {
+            cleanup_gpu_containers().await?;
+            let chain = self.chain_config()?;
+            chain.validate()?;
+            let trust_threshold = chain.trust_threshold()?;
+
+            tracing::info!("🔗 Connecting to Tendermint RPC...");
+            let tendermint_rpc_client = match &self.chain {
+                Some(chain) => TendermintRPCClient::new(chain.rpc_url.clone()),
+                None => TendermintRPCClient::default(),
+            };
+            let tendermint_height = tendermint_rpc_client.get_latest_block_height().await;
+            let tendermint_prover = TendermintProver::new();
+
+            // Get light blocks for proof generation, only the ones missing from the cache
+            // are fetched from the RPC
+            let chain_id = self
+                .chain
+                .as_ref()
+                .map_or(DEFAULT_CHAIN_ID, |chain| chain.chain_id.as_str());
+            let cache = LightBlockCache::from_env(chain_id)?;
+            let fetch_light_block = |height: u64| {
+                let client = &tendermint_rpc_client;
+                cache.get_or_fetch(height, move || async move {
+                    let peer_id = client
+                        .fetch_peer_id()
+                        .await
+                        .map_err(|e| anyhow!("Failed to fetch the Tendermint peer id: {}", e))?;
+                    client
+                        .fetch_light_block(height, peer_id)
+                        .await
+                        .map_err(|e| anyhow!("Failed to fetch light block {}: {}", height, e))
+                })
+            };
+            // The round reaching the halt height of an upgrade re-anchors the proof chain at the
+            // approved first header of the restarted chain
+            let trusted_height = service_state.trusted_height;
+            if let Some(upgrade) = &chain.upgrade {
+                if trusted_height == upgrade.halt_height {
+                    let anchor = fetch_light_block(upgrade.trusted_height).await?;
+                    return transition_inputs(
+                        upgrade,
+                        service_state,
+                        &anchor.signed_header.header,
+                        recursive_vk,
+                    );
+                }
+            }
+
+            // Calculate target height within the height advance of a round, a round bisecting
+            // towards a target keeps it
+            let target_height = match *self.round_target.lock().unwrap() {
+                Some(target) if target > trusted_height => target,
+                _ => {
+                    // A chain halting at an upgrade is proven up to the halt height
+                    let limit = match &chain.upgrade {
+                        Some(upgrade) if trusted_height < upgrade.halt_height => {
+                            upgrade.halt_height
+                        }
+                        _ => u64::MAX,
+                    };
+                    let min_advance = chain.min_height_advance()?;
+                    if tendermint_height < min(trusted_height + min_advance, limit) {
+                        return Err(BelowMinAdvance {
+                            trusted_height,
+                            latest_height: tendermint_height,
+                            min_advance,
+                        }
+                        .into());
+                    }
+                    tendermint_height
+                        .min(trusted_height + chain.max_height_advance()?)
+                        .min(limit)
+                }
+            };
+
+            tracing::info!("📦 Fetching light blocks for proof generation...");
+            let trusted_light_block = fetch_light_block(trusted_height).await?;
+            let mut hop_height = target_height;
+            let mut target_light_block = fetch_light_block(hop_height).await?;
+            // A chain restarted with another chain id waits for the operator to approve the
+            // upgrade
+            let network = target_light_block.signed_header.header.chain_id.to_string();
+            if chain
+                .network_at(trusted_height)
+                .is_some_and(|expected| expected != network)
+            {
+                return Err(ChainUpgraded {
+                    network,
+                    height: hop_height,
+                }
+                .into());
+            }
+            // Bisect until the trusted validators signed the hop, the Tendermint program
+            // can't skip to a header they don't carry enough voting power of
+            while hop_height > trusted_height + 1
+                && !skip_trusted(
+                    trust_threshold,
+                    &trusted_light_block.next_validators,
+                    &target_light_block.signed_header.commit,
+                )
+            {
+                hop_height = trusted_height + (hop_height - trusted_height) / 2;
+                tracing::info!(
+                    "🪜 Validator set changed too much to skip to {}, bisecting to {}",
+                    target_height,
+                    hop_height
+                );
+                target_light_block = fetch_light_block(hop_height).await?;
+            }
+            *self.round_target.lock().unwrap() =
+                (hop_height < target_height).then_some(target_height);
+            // The recursion circuit checks the trusting period between the trusted and the target
+            // header, and reads the app hash, next validators hash and time of the target one
+            let trusted_header = serde_json::to_vec(&trusted_light_block.signed_header.header)?;
+            let target_header = serde_json::to_vec(&target_light_block.signed_header.header)?;
+
+            tracing::info!("⚡ Generating Tendermint proof in isolated task...");
+            let handle = tokio::spawn(async move {
+                tendermint_prover
+                    .generate_tendermint_proof(&trusted_light_block, &target_light_block)
+            });
+
+            match handle.await {
+                Ok(proof) => {
+                    tracing::info!("✅ Tendermint proof generated successfully");
+                    (proof, trusted_header, target_header)
+                }
+                Err(join_error) => {
+                    return Err(anyhow::anyhow!(
+                        "❌ Tendermint proof task panicked: {:?}",
+                        join_error
+                    ));
+                }
+            }
+        };
+
+        // Decode proof outputs
+        tracing::info!("🔍 Decoding Tendermint proof outputs...");
+        let tendermint_outputs: TendermintOutput =
+            serde_json::from_slice(&tendermint_proof.public_values.to_vec()).unwrap();
+
+        let previous_proof = service_state.most_recent_recursive_proof.clone();
+
+        // Prepare recursive circuit inputs
+        tracing::info!("📝 Preparing recursive circuit inputs...");
+        let recursion_inputs = TendermintRecursionCircuitInputs {
+            tendermint_proof: tendermint_proof.bytes(),
+            tendermint_public_values: tendermint_proof.public_values.to_vec(),
+            recursive_proof: previous_proof.as_ref().map(|p| p.bytes()),
+            recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
+            recursive_vk,
+            trusted_height: service_state.trusted_height,
+            trusted_header,
+            target_header,
+            transition: false,
+        };
+
+        tracing::info!("✅ Tendermint prover completed successfully");
+        Ok((tendermint_outputs, recursion_inputs))
+    }
+
+    fn is_intermediate(&self, base_outputs: &TendermintOutput) -> bool {
+        self.round_target
+            .lock()
+            .unwrap()
+            .is_some_and(|target| base_outputs.target_height < target)
+    }
+
+    fn encode_base_outputs(&self, base_outputs: &TendermintOutput) -> Option<Vec<u8>> {
+        serde_json::to_vec(base_outputs).ok()
+    }
+
+    fn decode_base_outputs(&self, bytes: &[u8]) -> Result<TendermintOutput> {
+        serde_json::from_slice(bytes).context("Invalid Tendermint base outputs")
+    }
+
+    fn decode_outputs(&self, public_values: &[u8]) -> Result<RecursionOutputs> {
+        let outputs: TendermintRecursionCircuitOutputs =
+            decode_tendermint_outputs(public_values)
+                .map_err(|e| anyhow!("Failed to decode Tendermint outputs: {:?}", e))?;
+        Ok(RecursionOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            vk: outputs.vk,
+        })
+    }
+
+    fn mock_recursion_outputs(&self, inputs: &[u8]) -> Result<Vec<u8>> {
+        let inputs: TendermintRecursionCircuitInputs =
+            borsh::from_slice(inputs).context("Failed to decode Tendermint recursion inputs")?;
+        let chain = self.chain_config()?;
+        if inputs.transition {
+            return self.mock_transition_outputs(&chain, inputs);
+        }
+        let tendermint_outputs: TendermintOutput =
+            serde_json::from_slice(&inputs.tendermint_public_values)
+                .context("Failed to decode Tendermint proof outputs")?;
+        // As the circuit, refuse to extend a link that committed another vk, or from another
+        // header than the one the previous link committed
+        let (previous_height, previous_root) = match &inputs.recursive_public_values {
+            Some(previous) => {
+                let previous = self.decode_outputs(previous)?;
+                if previous.vk != inputs.recursive_vk {
+                    return Err(anyhow!(
+                        "Previous proof committed recursive vk {}, expected {}",
+                        previous.vk,
+                        inputs.recursive_vk
+                    ));
+                }
+                if tendermint_outputs.trusted_header_hash != previous.root
+                    || tendermint_outputs.trusted_height != previous.height
+                {
+                    return Err(anyhow!(
+                        "Tendermint proof starts from height {}, the previous proof committed \
+                         height {}",
+                        tendermint_outputs.trusted_height,
+                        previous.height
+                    ));
+                }
+                (previous.height, previous.root)
+            }
+            None => (
+                inputs.trusted_height,
+                tendermint_outputs.trusted_header_hash,
+            ),
+        };
+        // As the circuit, read the times and header fields from the headers of the proven hashes
+        // and heights, and refuse a target header past the trusting period
+        let trusted_header = proven_header(
+            &inputs.trusted_header,
+            tendermint_outputs.trusted_header_hash,
+            tendermint_outputs.trusted_height,
+        )?;
+        let target_header = proven_header(
+            &inputs.target_header,
+            tendermint_outputs.target_header_hash,
+            tendermint_outputs.target_height,
+        )?;
+        let (trusted_time, time) = (unix_nanos(&trusted_header)?, unix_nanos(&target_header)?);
+        let trusting_period_secs = chain.trusting_period_secs()?;
+        if time <= trusted_time || time - trusted_time > trusting_period_secs * 1_000_000_000 {
+            return Err(anyhow!(
+                "The target header of height {} is not within the trusting period of {}s of \
+                 the trusted header",
+                tendermint_outputs.target_height,
+                trusting_period_secs
+            ));
+        }
+        let max_height_advance = chain.max_height_advance()?;
+        if tendermint_outputs.target_height > tendermint_outputs.trusted_height + max_height_advance
+        {
+            return Err(anyhow!(
+                "The target header of height {} is more than {} blocks past the trusted header",
+                tendermint_outputs.target_height,
+                max_height_advance
+            ));
+        }
+        // As the circuit, end the links of a chain halting at an upgrade at the halt height
+        let restarted = chain
+            .upgrade
+            .as_ref()
+            .is_some_and(|upgrade| tendermint_outputs.trusted_height >= upgrade.trusted_height);
+        if let Some(upgrade) = &chain.upgrade {
+            if !restarted && tendermint_outputs.target_height > upgrade.halt_height {
+                return Err(anyhow!(
+                    "The target header of height {} is past the halt height {}",
+                    tendermint_outputs.target_height,
+                    upgrade.halt_height
+                ));
+            }
+        }
+        let (app_hash, next_validators_hash) = header_hashes(&target_header)?;
+        let outputs = TendermintRecursionCircuitOutputs {
+            root: tendermint_outputs.target_header_hash,
+            height: tendermint_outputs.target_height,
+            vk: inputs.recursive_vk,
+            previous_height,
+            previous_root,
+            chain_id: chain.caip2_at(tendermint_outputs.trusted_height)?,
+            app_hash,
+            next_validators_hash,
+            time,
+            transition: false,
+        };
+        Ok(encode_tendermint_outputs(&outputs))
+    }
+
+    fn mock_wrapper_outputs(&self, inputs: &[u8]) -> Result<Vec<u8>> {
+        let inputs: TendermintWrapperCircuitInputs =
+            borsh::from_slice(inputs).context("Failed to decode Tendermint wrapper inputs")?;
+        let outputs: TendermintRecursionCircuitOutputs =
+            decode_tendermint_outputs(&inputs.recursive_public_values)
+                .map_err(|e| anyhow!("Failed to decode Tendermint outputs: {:?}", e))?;
+        if ibc_outputs()? {
+            return Ok(encode_tendermint_outputs(
+                &TendermintIbcWrapperCircuitOutputs {
+                    height: outputs.height,
+                    root: outputs.root,
+                    previous_height: outputs.previous_height,
+                    previous_root: outputs.previous_root,
+                    transition: outputs.transition,
+                    chain_id: outputs.chain_id,
+                    app_hash: outputs.app_hash,
+                    next_validators_hash: outputs.next_validators_hash,
+                    time: outputs.time,
+                },
+            ));
+        }
+        let outputs = TendermintWrapperCircuitOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            previous_height: outputs.previous_height,
+            previous_root: outputs.previous_root,
+            transition: outputs.transition,
+            chain_id: outputs.chain_id,
+        };
+        Ok(match WrapperEncoding::from_env()? {
+            WrapperEncoding::Borsh => encode_tendermint_outputs(&outputs),
+            WrapperEncoding::Abi => outputs.abi_encode(),
+        })
+    }
+
+    fn wrapper_inputs(&self, recursive_proof: &SP1ProofWithPublicValues) -> SP1Stdin {
+        let wrapper_inputs = TendermintWrapperCircuitInputs {
+            recursive_proof: recursive_proof.bytes(),
+            recursive_public_values: recursive_proof.public_values.to_vec(),
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
+        stdin
+    }
+
+    fn update_state(
+        &self,
+        service_state: &mut ServiceState,
+        tendermint_outputs: &TendermintOutput,
+        outputs: &RecursionOutputs,
+    ) {
+        // In the case of Tendermint, the trusted slot is the target height
+        service_state.trusted_slot = tendermint_outputs.target_height;
+        service_state.trusted_height = outputs.height;
+        service_state.trusted_root = outputs.root;
+    }
+}
+
+/// The inputs of the transition link re-anchoring the proof chain at `anchor`, the approved
+/// first header of the chain restarted after `upgrade`
+fn transition_inputs(
+    upgrade: &TendermintUpgrade,
+    service_state: &ServiceState,
+    anchor: &Header,
+    recursive_vk: String,
+) -> Result<(TendermintOutput, TendermintRecursionCircuitInputs)> {
+    if anchor.hash().as_bytes() != upgrade.trusted_root {
+        return Err(anyhow!(
+            "The header of height {} of {} is not the approved one",
+            upgrade.trusted_height,
+            upgrade.network
+        ));
+    }
+    tracing::info!(
+        "🔀 Re-anchoring the proof chain at height {} of {}",
+        upgrade.trusted_height,
+        upgrade.network
+    );
+    let outputs = TendermintOutput {
+        trusted_height: service_state.trusted_height,
+        target_height: upgrade.trusted_height,
+        trusted_header_hash: service_state.trusted_root,
+        target_header_hash: upgrade.trusted_root,
+    };
+    let previous_proof = service_state.most_recent_recursive_proof.as_ref();
+    let inputs = TendermintRecursionCircuitInputs {
+        tendermint_proof: Vec::new(),
+        tendermint_public_values: Vec::new(),
+        recursive_proof: previous_proof.map(|p| p.bytes()),
+        recursive_public_values: previous_proof.map(|p| p.public_values.to_vec()),
+        recursive_vk,
+        trusted_height: service_state.trusted_height,
+        trusted_header: Vec::new(),
+        target_header: serde_json::to_vec(anchor)?,
+        transition: true,
+    };
+    Ok((outputs, inputs))
+}
+
+/// Whether the trusted next validators carry more than `threshold` of the voting power
+/// signing `commit`, the Tendermint program verifies skips with at least 1/3
+fn skip_trusted(
+    threshold: TrustThreshold,
+    trusted_next_validators: &validator::Set,
+    commit: &Commit,
+) -> bool {
+    let signed = commit
+        .signatures
+        .iter()
+        .filter_map(|signature| match signature {
+            CommitSig::BlockIdFlagCommit {
+                validator_address, ..
+            } => trusted_next_validators.validator(*validator_address),
+            _ => None,
+        })
+        .map(|validator| validator.power.value())
+        .sum();
+    threshold.exceeded(signed, trusted_next_validators.total_voting_power().value())
+}
+
+/// Decodes a JSON encoded header, failing unless it is the header of `hash` at `height`
+fn proven_header(header: &[u8], hash: [u8; 32], height: u64) -> Result<Header> {
+    let header: Header = serde_json::from_slice(header).context("Failed to decode the header")?;
+    if header.hash().as_bytes() != hash || header.height.value() != height {
+        return Err(anyhow!("The header is not the header of height {}", height));
+    }
+    Ok(header)
+}
+
+/// The app hash and next validators hash of a header, committed by the recursion circuit
+fn header_hashes(header: &Header) -> Result<([u8; 32], [u8; 32])> {
+    Ok((
+        header
+            .app_hash
+            .as_bytes()
+            .try_into()
+            .context("The app hash is not 32 bytes")?,
+        header
+            .next_validators_hash
+            .as_bytes()
+            .try_into()
+            .context("The next validators hash is not 32 bytes")?,
+    ))
+}
+
+/// The time of a header in unix nanoseconds
+fn unix_nanos(header: &Header) -> Result<u64> {
+    u64::try_from(header.time.unix_timestamp_nanos())
+        .context("The header time is before the unix epoch")
+}
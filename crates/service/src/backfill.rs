@@ -0,0 +1,125 @@
+// Proofs of historical heights, behind `backfill`.
+//
+// The state database only keeps the latest proofs of a chain, so a consumer that needs the root
+// of an earlier height (e.g. for a dispute) can't get a proof of it from the API. The inputs of
+// the base proof of every committed update are stored with it, which lets the Helios proof of a
+// historical update be replayed on demand: the replayed proof shows the sync committee signed
+// the finalized header whose execution state root is the historical root, and the history
+// records from that update to the tip hash-chain it to the latest state, which consumers
+// already trust through its wrapper proof.
+
+use alloy_sol_types::SolType;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sp1_helios_primitives::types::ProofOutputs as HeliosOutputs;
+use sp1_sdk::{HashableKey, ProverClient, SP1Stdin};
+use std::path::Path;
+
+use crate::{
+    HELIOS_ELF,
+    api::HistoryRecord,
+    backends::prove_helios,
+    encoding::{to_base64, to_hex},
+    state::StateManager,
+};
+
+/// A replayed base proof of a historical update, linked to the latest committed update
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackfillProof {
+    pub chain_id: String,
+    /// The update that committed the requested height
+    pub record: HistoryRecord,
+    /// The records following it up to the latest update, each chaining to its predecessor
+    pub chain_to_tip: Vec<HistoryRecord>,
+    /// The vk of the Helios program the base proof verifies against
+    pub base_vk: String,
+    /// The base proof bytes, base64 encoded
+    pub base_proof_b64: String,
+    /// The ABI encoded Helios outputs, base64 encoded
+    pub base_public_values_b64: String,
+}
+
+/// Replays the base proof of the update that committed execution height `height` of the
+/// Helios proof chain
+pub async fn backfill(db_path: &Path, height: u64) -> Result<BackfillProof> {
+    let state_manager = StateManager::new(db_path)?;
+    let state = state_manager
+        .load_state()?
+        .context("No state found, nothing was proven yet")?;
+    if height > state.trusted_height {
+        return Err(anyhow!(
+            "Height {} is beyond the trusted height {}",
+            height,
+            state.trusted_height
+        ));
+    }
+    let record = state_manager
+        .load_record_at_height(height)?
+        .context(format!(
+            "Height {} was never committed, GET /history lists the committed heights",
+            height
+        ))?;
+    let inputs = state_manager
+        .load_base_inputs(record.update_counter)?
+        .context(format!(
+            "No base proof inputs stored for update {}",
+            record.update_counter
+        ))?;
+
+    // The records after it up to the tip link the update to the trusted state
+    let mut chain_to_tip = Vec::new();
+    let mut prev_hash = record.hash;
+    let mut from = record.update_counter + 1;
+    loop {
+        let records = state_manager.load_history(from, 1000)?;
+        let Some(last) = records.last() else {
+            break;
+        };
+        from = last.update_counter + 1;
+        for next in records {
+            if !next.verify(&prev_hash) {
+                return Err(anyhow!(
+                    "Update {} does not chain to its predecessor",
+                    next.update_counter
+                ));
+            }
+            prev_hash = next.hash;
+            chain_to_tip.push(next.into());
+        }
+    }
+
+    tracing::info!(
+        "⏪ Replaying the Helios proof of update {} at height {}",
+        record.update_counter,
+        height
+    );
+    let client = ProverClient::from_env();
+    let (helios_pk, helios_vk) = client.setup(HELIOS_ELF);
+    let mut stdin = SP1Stdin::new();
+    stdin.write_slice(&inputs);
+    let proof = prove_helios(helios_pk, stdin).await?;
+
+    // The stored inputs must reproduce the committed update
+    let outputs = HeliosOutputs::abi_decode(proof.public_values.as_slice(), false)
+        .context("Failed to decode Helios outputs")?;
+    let new_head: u64 = outputs.newHead.try_into()?;
+    if new_head != record.slot || outputs.executionStateRoot.0 != record.root {
+        return Err(anyhow!(
+            "The replayed proof commits slot {} and root {}, update {} committed slot {} and root {}",
+            new_head,
+            to_hex(outputs.executionStateRoot),
+            record.update_counter,
+            record.slot,
+            to_hex(record.root)
+        ));
+    }
+
+    Ok(BackfillProof {
+        chain_id: state_manager.chain_id().to_string(),
+        record: record.into(),
+        chain_to_tip,
+        base_vk: helios_vk.bytes32(),
+        base_proof_b64: to_base64(proof.bytes()),
+        base_public_values_b64: to_base64(proof.public_values.as_slice()),
+    })
+}
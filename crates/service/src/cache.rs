@@ -0,0 +1,138 @@
+// In-process cache of the responses of the hot API endpoints.
+//
+// Relayers poll `GET /` and `GET /state` far more often than proofs are committed, and every
+// request of the latest proof reads and re-encodes a multi-megabyte proof. Their responses are
+// cached by chain and endpoint together with the update counter they were built at: a request
+// only reads the update counter of its chain and is served the cached body while it is
+// unchanged, the first request after a commit rebuilds it. Hits and misses are served with
+// the chain stats.
+
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// Metrics of the response cache of this process
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Cached responses
+    pub entries: u64,
+    /// Size of the cached bodies, in bytes
+    pub bytes: u64,
+}
+
+/// A successful response built at `update_counter`
+struct Entry {
+    update_counter: u64,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+struct ResponseCache {
+    /// Entries by chain id and endpoint
+    entries: BTreeMap<(String, String), Entry>,
+    hits: u64,
+    misses: u64,
+}
+
+static CACHE: Mutex<ResponseCache> = Mutex::new(ResponseCache {
+    entries: BTreeMap::new(),
+    hits: 0,
+    misses: 0,
+});
+
+/// The cached response of `endpoint` of a chain, if it was built at `update_counter`
+pub fn get(chain_id: &str, endpoint: &str, update_counter: u64) -> Option<Response> {
+    let mut cache = CACHE.lock().unwrap();
+    let key = (chain_id.to_string(), endpoint.to_string());
+    let response = cache
+        .entries
+        .get(&key)
+        .filter(|entry| entry.update_counter == update_counter)
+        .map(|entry| (entry.headers.clone(), entry.body.clone()).into_response());
+    match response {
+        Some(_) => cache.hits += 1,
+        None => cache.misses += 1,
+    }
+    response
+}
+
+/// Caches a response of `endpoint` built at `update_counter` and returns it. Only successful
+/// responses are cached
+pub async fn store(
+    chain_id: &str,
+    endpoint: &str,
+    update_counter: u64,
+    response: Response,
+) -> Response {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer the {} response: {}", endpoint, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    CACHE.lock().unwrap().entries.insert(
+        (chain_id.to_string(), endpoint.to_string()),
+        Entry {
+            update_counter,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+        },
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Drops the cached responses of a chain whose state was replaced without a commit, e.g. by
+/// a backend switch
+pub fn invalidate(chain_id: &str) {
+    CACHE
+        .lock()
+        .unwrap()
+        .entries
+        .retain(|(cached_chain_id, _), _| cached_chain_id != chain_id);
+}
+
+/// The metrics of the response cache, `None` until it served a request
+pub fn stats() -> Option<CacheStats> {
+    let cache = CACHE.lock().unwrap();
+    (cache.hits + cache.misses > 0).then(|| CacheStats {
+        hits: cache.hits,
+        misses: cache.misses,
+        entries: cache.entries.len() as u64,
+        bytes: cache
+            .entries
+            .values()
+            .map(|entry| entry.body.len() as u64)
+            .sum(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_responses_are_invalidated_by_update_counter() {
+        assert!(get("test", "proof:json", 1).is_none());
+        store("test", "proof:json", 1, "proof 1".into_response()).await;
+
+        let cached = get("test", "proof:json", 1).unwrap();
+        let body = to_bytes(cached.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"proof 1");
+        assert!(get("test", "proof:json", 2).is_none());
+
+        // Failed responses are never cached
+        store("test", "state", 1, StatusCode::NOT_FOUND.into_response()).await;
+        assert!(get("test", "state", 1).is_none());
+    }
+}
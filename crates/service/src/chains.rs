@@ -0,0 +1,329 @@
+// Configuration of the Tendermint chains proven by this service.
+//
+// A single chain is configured through `TENDERMINT_RPC_URL` and the checkpoints manifest.
+// Multiple chains are configured with a JSON file referenced by `TENDERMINT_CHAINS_CONFIG`,
+// every entry gets its own proof chain, state row and recursion circuit.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::{checkpoints::CheckpointManifest, encoding::parse_hex_array, state::DEFAULT_CHAIN_ID};
+
+/// A Tendermint chain and the checkpoint its proof chain starts from
+#[derive(Debug, Clone, Deserialize)]
+pub struct TendermintChainConfig {
+    /// Identifier used to namespace state, ELFs and API requests
+    pub chain_id: String,
+    /// Tendermint RPC endpoint of the chain
+    pub rpc_url: String,
+    /// Height of the trusted checkpoint
+    pub trusted_height: u64,
+    /// Header hash of the trusted checkpoint, 0x-prefixed hex
+    #[serde(deserialize_with = "deserialize_root")]
+    pub trusted_root: [u8; 32],
+    /// Chain id of the headers of the chain (e.g. `pion-1`), which the recursion circuit
+    /// commits. Distinct from `chain_id`, which only names the proof chain in this service
+    #[serde(default)]
+    pub network: Option<String>,
+    /// Longest time in seconds between the trusted and the target header of a link, enforced
+    /// by the recursion circuit. `TENDERMINT_TRUSTING_PERIOD` if unset
+    #[serde(default)]
+    pub trusting_period_secs: Option<u64>,
+    /// Fraction of the voting power signing a target header the trusted validators must carry
+    /// to skip to it (e.g. `1/3`), bisecting otherwise. `TENDERMINT_TRUST_THRESHOLD` if unset.
+    /// A policy of the service choosing the skips, not proven: the Tendermint program and the
+    /// recursion circuit verify every skip with 1/3
+    #[serde(default)]
+    pub trust_threshold: Option<String>,
+    /// Fewest blocks a round advances, rounds wait for the chain otherwise.
+    /// `TENDERMINT_MIN_HEIGHT_ADVANCE` if unset
+    #[serde(default)]
+    pub min_height_advance: Option<u64>,
+    /// Most blocks a round advances. `TENDERMINT_EXPIRATION_LIMIT` if unset
+    #[serde(default)]
+    pub max_height_advance: Option<u64>,
+    /// Upgrade the chain halted at and restarted from with another chain id, approved by the
+    /// operator. `TENDERMINT_UPGRADE` (JSON) if unset
+    #[serde(default)]
+    pub upgrade: Option<TendermintUpgrade>,
+}
+
+/// An upgrade a Tendermint chain halted at, restarting with another chain id. The proof chain
+/// is proven up to the halt height, then re-anchored at the approved first header of the
+/// restarted chain by a transition link
+#[derive(Debug, Clone, Deserialize)]
+pub struct TendermintUpgrade {
+    /// Last height of the chain before it halted
+    pub halt_height: u64,
+    /// Chain id of the headers of the restarted chain
+    pub network: String,
+    /// Height of the approved first header of the restarted chain
+    pub trusted_height: u64,
+    /// Header hash of the approved first header of the restarted chain, 0x-prefixed hex
+    #[serde(deserialize_with = "deserialize_root")]
+    pub trusted_root: [u8; 32],
+}
+
+/// Default trusting period of the Tendermint light clients, two weeks
+pub const DEFAULT_TRUSTING_PERIOD_SECS: u64 = 14 * 24 * 3600;
+
+/// Default trust threshold, the one the Tendermint program verifies skips with
+pub const DEFAULT_TRUST_THRESHOLD: TrustThreshold = TrustThreshold {
+    numerator: 1,
+    denominator: 3,
+};
+
+/// Default most blocks a round advances
+pub const DEFAULT_MAX_HEIGHT_ADVANCE: u64 = 100_000;
+
+/// A fraction of voting power
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustThreshold {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl TrustThreshold {
+    /// Parses a `numerator/denominator` fraction
+    pub fn parse(threshold: &str) -> Result<Self> {
+        let (numerator, denominator) = threshold.split_once('/').context(format!(
+            "Invalid trust threshold {}, expected n/d",
+            threshold
+        ))?;
+        Ok(Self {
+            numerator: numerator
+                .trim()
+                .parse()
+                .context(format!("Invalid trust threshold {}", threshold))?,
+            denominator: denominator
+                .trim()
+                .parse()
+                .context(format!("Invalid trust threshold {}", threshold))?,
+        })
+    }
+
+    /// Whether `power` is more than this fraction of `total`
+    pub fn exceeded(&self, power: u64, total: u64) -> bool {
+        power as u128 * self.denominator as u128 > total as u128 * self.numerator as u128
+    }
+}
+
+impl std::fmt::Display for TrustThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// A number of the chain configuration, else of the environment variable `name`
+fn configured_u64(configured: Option<u64>, name: &str, default: u64) -> Result<u64> {
+    if let Some(value) = configured {
+        return Ok(value);
+    }
+    match std::env::var(name) {
+        Ok(value) => value.parse().context(format!("Invalid {} {}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+impl TendermintChainConfig {
+    /// The CAIP-2 chain id committed by the circuits of this chain
+    pub fn caip2(&self) -> Result<String> {
+        let network = self.network.as_ref().context(format!(
+            "Set the network (chain id of its headers) of Tendermint chain {}",
+            self.chain_id
+        ))?;
+        Ok(format!("cosmos:{}", network))
+    }
+
+    /// The chain id of the headers of the chain at `height`, the one of the restarted chain
+    /// from the approved first header of an upgrade on
+    pub fn network_at(&self, height: u64) -> Option<&str> {
+        match &self.upgrade {
+            Some(upgrade) if height >= upgrade.trusted_height => Some(&upgrade.network),
+            _ => self.network.as_deref(),
+        }
+    }
+
+    /// The CAIP-2 chain id committed by the links of this chain extending `height`
+    pub fn caip2_at(&self, height: u64) -> Result<String> {
+        match &self.upgrade {
+            Some(upgrade) if height >= upgrade.trusted_height => {
+                Ok(format!("cosmos:{}", upgrade.network))
+            }
+            _ => self.caip2(),
+        }
+    }
+
+    /// The trusting period of the chain in seconds, from its configuration, else from
+    /// `TENDERMINT_TRUSTING_PERIOD` (two weeks by default)
+    pub fn trusting_period_secs(&self) -> Result<u64> {
+        configured_u64(
+            self.trusting_period_secs,
+            "TENDERMINT_TRUSTING_PERIOD",
+            DEFAULT_TRUSTING_PERIOD_SECS,
+        )
+    }
+
+    /// The trust threshold of the skips of the chain, from its configuration, else from
+    /// `TENDERMINT_TRUST_THRESHOLD` (1/3 by default)
+    pub fn trust_threshold(&self) -> Result<TrustThreshold> {
+        match &self.trust_threshold {
+            Some(threshold) => TrustThreshold::parse(threshold),
+            None => match std::env::var("TENDERMINT_TRUST_THRESHOLD") {
+                Ok(threshold) => TrustThreshold::parse(&threshold),
+                Err(_) => Ok(DEFAULT_TRUST_THRESHOLD),
+            },
+        }
+    }
+
+    /// The fewest blocks a round of the chain advances, from its configuration, else from
+    /// `TENDERMINT_MIN_HEIGHT_ADVANCE` (1 by default)
+    pub fn min_height_advance(&self) -> Result<u64> {
+        configured_u64(self.min_height_advance, "TENDERMINT_MIN_HEIGHT_ADVANCE", 1)
+    }
+
+    /// The most blocks a round of the chain advances, from its configuration, else from
+    /// `TENDERMINT_EXPIRATION_LIMIT` (100k by default)
+    pub fn max_height_advance(&self) -> Result<u64> {
+        configured_u64(
+            self.max_height_advance,
+            "TENDERMINT_EXPIRATION_LIMIT",
+            DEFAULT_MAX_HEIGHT_ADVANCE,
+        )
+    }
+
+    /// Fails unless the trust and cadence settings of the chain can prove it: the trust
+    /// threshold is between the one of the Tendermint program and 1, the rounds advance at
+    /// least one block and no more than the maximum, and an upgrade continues the heights
+    pub fn validate(&self) -> Result<()> {
+        let threshold = self.trust_threshold()?;
+        if threshold.denominator == 0
+            || threshold.numerator > threshold.denominator
+            || (threshold.numerator as u128) * (DEFAULT_TRUST_THRESHOLD.denominator as u128)
+                < (DEFAULT_TRUST_THRESHOLD.numerator as u128) * (threshold.denominator as u128)
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid trust threshold {} of Tendermint chain {}, expected between {} and 1",
+                threshold,
+                self.chain_id,
+                DEFAULT_TRUST_THRESHOLD
+            ));
+        }
+        if self.trusting_period_secs()? == 0 {
+            return Err(anyhow::anyhow!(
+                "The trusting period of Tendermint chain {} is 0",
+                self.chain_id
+            ));
+        }
+        let (min, max) = (self.min_height_advance()?, self.max_height_advance()?);
+        if min == 0 || min > max {
+            return Err(anyhow::anyhow!(
+                "Invalid height advance of Tendermint chain {}, expected 1 <= {} <= {}",
+                self.chain_id,
+                min,
+                max
+            ));
+        }
+        if let Some(upgrade) = &self.upgrade {
+            if upgrade.halt_height <= self.trusted_height
+                || upgrade.trusted_height <= upgrade.halt_height
+            {
+                return Err(anyhow::anyhow!(
+                    "Invalid upgrade of Tendermint chain {}, expected its trusted height {} < \
+                     halt height {} < restarted trusted height {}",
+                    self.chain_id,
+                    self.trusted_height,
+                    upgrade.halt_height,
+                    upgrade.trusted_height
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Directory holding the recursive and wrapper ELFs generated for this chain.
+    ///
+    /// The default chain uses `elfs_path` itself, every other chain a subdirectory
+    /// named after its chain id since its recursion circuit has its own constants.
+    pub fn elfs_dir(&self, elfs_path: &Path) -> PathBuf {
+        if self.chain_id == DEFAULT_CHAIN_ID {
+            elfs_path.to_path_buf()
+        } else {
+            elfs_path.join(&self.chain_id)
+        }
+    }
+
+    /// Path of one of this chain's ELFs relative to `ELFS_OUT`, as used for
+    /// remote registries and checksum pinning.
+    pub fn elf_name(&self, file_name: &str) -> String {
+        if self.chain_id == DEFAULT_CHAIN_ID {
+            file_name.to_string()
+        } else {
+            format!("{}/{}", self.chain_id, file_name)
+        }
+    }
+}
+
+/// Loads the configured Tendermint chains.
+///
+/// Falls back to a single chain named [`DEFAULT_CHAIN_ID`] built from
+/// `TENDERMINT_RPC_URL` and the checkpoint of the manifest in `checkpoints.json`.
+pub fn tendermint_chains() -> Result<Vec<TendermintChainConfig>> {
+    match std::env::var("TENDERMINT_CHAINS_CONFIG") {
+        Ok(path) => {
+            let contents = std::fs::read_to_string(&path)
+                .context(format!("Failed to read Tendermint chains config {}", path))?;
+            let chains: Vec<TendermintChainConfig> = serde_json::from_str(&contents)
+                .context(format!("Failed to parse Tendermint chains config {}", path))?;
+            if chains.is_empty() {
+                return Err(anyhow::anyhow!("No chains configured in {}", path));
+            }
+            for (i, chain) in chains.iter().enumerate() {
+                if chains[..i]
+                    .iter()
+                    .any(|other| other.chain_id == chain.chain_id)
+                {
+                    return Err(anyhow::anyhow!("Duplicate chain id {}", chain.chain_id));
+                }
+            }
+            Ok(chains)
+        }
+        Err(_) => {
+            let checkpoint = &CheckpointManifest::built_in().tendermint;
+            Ok(vec![TendermintChainConfig {
+                chain_id: DEFAULT_CHAIN_ID.to_string(),
+                rpc_url: std::env::var("TENDERMINT_RPC_URL").unwrap_or_default(),
+                trusted_height: checkpoint.trusted_height,
+                trusted_root: checkpoint.root()?,
+                network: checkpoint.network.clone(),
+                trusting_period_secs: None,
+                trust_threshold: None,
+                min_height_advance: None,
+                max_height_advance: None,
+                upgrade: match std::env::var("TENDERMINT_UPGRADE") {
+                    Ok(upgrade) => {
+                        Some(serde_json::from_str(&upgrade).context("Invalid TENDERMINT_UPGRADE")?)
+                    }
+                    Err(_) => None,
+                },
+            }])
+        }
+    }
+}
+
+/// Looks up a configured chain by id
+pub fn tendermint_chain(chain_id: &str) -> Result<TendermintChainConfig> {
+    tendermint_chains()?
+        .into_iter()
+        .find(|chain| chain.chain_id == chain_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown Tendermint chain {}", chain_id))
+}
+
+fn deserialize_root<'de, D>(deserializer: D) -> Result<[u8; 32], D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let root = String::deserialize(deserializer)?;
+    parse_hex_array(&root).map_err(serde::de::Error::custom)
+}
@@ -1,14 +1,106 @@
-// Trusted State for Helios
-pub const HELIOS_TRUSTED_SLOT: u64 = 11715392;
-// Derived from slot
-/*pub const HELIOS_TRUSTED_SYNC_COMMITTEE_HASH: [u8; 32] = [
-    42, 127, 126, 117, 72, 179, 28, 141, 55, 33, 177, 213, 151, 94, 45, 208, 226, 255, 98, 136,
-    212, 174, 252, 91, 254, 248, 107, 95, 40, 53, 223, 67,
-];*/
-
-// Trusted State for Tendermint
-pub const TENDERMINT_TRUSTED_HEIGHT: u64 = 31134400;
-pub const TENDERMINT_TRUSTED_ROOT: [u8; 32] = [
-    133, 197, 217, 208, 182, 161, 40, 102, 214, 74, 216, 44, 87, 164, 134, 95, 150, 222, 115, 170,
-    222, 9, 183, 138, 57, 107, 86, 21, 40, 96, 131, 113,
-];
+// Trusted checkpoints the proof chains start from.
+//
+// The Helios and Tendermint checkpoints are read from the `checkpoints.json` manifest, which
+// is built into the binary and written by the `bootstrap` subcommand from the chain data at a
+// slot or height. The recursion circuits are generated from the manifest, so a new checkpoint
+// takes effect once they are regenerated, see `make bootstrap`.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::parse_hex_array;
+
+/// Path of the manifest relative to the repository root, where `bootstrap` writes it
+pub const MANIFEST_PATH: &str = "crates/service/checkpoints.json";
+
+static MANIFEST: Lazy<CheckpointManifest> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../checkpoints.json"))
+        .expect("Invalid checkpoints.json manifest")
+});
+
+/// The trusted checkpoints of the Helios and Tendermint proof chains
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointManifest {
+    pub helios: HeliosCheckpoint,
+    pub tendermint: TendermintCheckpoint,
+}
+
+/// The slot the Helios light client starts from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosCheckpoint {
+    /// `SOURCE_CHAIN_ID` of the network of the slot
+    pub source_chain_id: u64,
+    pub trusted_slot: u64,
+    /// Hash of the sync committee trusted at the slot, 0x-prefixed hex
+    pub trusted_sync_committee_hash: String,
+}
+
+/// The block the default Tendermint chain starts from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TendermintCheckpoint {
+    pub trusted_height: u64,
+    /// Header hash of the block, 0x-prefixed hex
+    pub trusted_root: String,
+    /// Chain id of the headers of the chain, e.g. `pion-1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+}
+
+impl CheckpointManifest {
+    /// The manifest built into this binary
+    pub fn built_in() -> &'static Self {
+        &MANIFEST
+    }
+
+    /// The manifest at `MANIFEST_PATH`, which `bootstrap` may have changed since the build
+    pub fn read() -> Result<Self> {
+        let manifest = std::fs::read_to_string(MANIFEST_PATH)
+            .context(format!("Failed to read {}", MANIFEST_PATH))?;
+        serde_json::from_str(&manifest).context(format!("Invalid manifest {}", MANIFEST_PATH))
+    }
+
+    pub fn write(&self) -> Result<()> {
+        let mut manifest = serde_json::to_string_pretty(self)?;
+        manifest.push('\n');
+        std::fs::write(MANIFEST_PATH, manifest)
+            .context(format!("Failed to write {}", MANIFEST_PATH))
+    }
+}
+
+impl HeliosCheckpoint {
+    pub fn committee_hash(&self) -> Result<[u8; 32]> {
+        parse_hex_array(&self.trusted_sync_committee_hash)
+            .context("Invalid trusted_sync_committee_hash in the checkpoints manifest")
+    }
+}
+
+impl TendermintCheckpoint {
+    pub fn root(&self) -> Result<[u8; 32]> {
+        parse_hex_array(&self.trusted_root)
+            .context("Invalid trusted_root in the checkpoints manifest")
+    }
+}
+
+// Trusted State for GRANDPA
+// The authority set finalizing the descendants of this block is read from the chain
+// when the recursion circuit is generated
+pub const GRANDPA_TRUSTED_HEIGHT: u64 = 26000000;
+
+// Trusted State for Bitcoin
+// The hash, difficulty and recent block times of this block are read from the chain
+// when the recursion circuit is generated
+pub const BITCOIN_TRUSTED_HEIGHT: u64 = 900000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_manifest() {
+        let manifest = CheckpointManifest::built_in();
+        assert_eq!(manifest.helios.source_chain_id, 1);
+        assert!(manifest.helios.committee_hash().is_ok());
+        assert!(manifest.tendermint.root().is_ok());
+    }
+}
@@ -0,0 +1,808 @@
+// Implementations of the service subcommands other than `run`: circuit generation, the
+// bootstrap of trusted checkpoints, ELF dumps, single proving rounds, the inspection of the
+// committed state and the capture of test fixtures.
+
+use anyhow::{Context, Result};
+use bitcoin_recursion_types::pow::BlockHeader;
+use serde::Serialize;
+use sp1_sdk::{HashableKey, ProverClient};
+use sp1_tendermint_primitives::TendermintOutput;
+use std::{fs::write, path::Path};
+use tendermint_prover::util::TendermintRPCClient;
+
+use crate::{
+    HELIOS_ELF, ICS23_PROOF_ELF, OPSTACK_OUTPUT_ELF, RECURSIVE_ELF_BITCOIN, RECURSIVE_ELF_GRANDPA,
+    RECURSIVE_ELF_HELIOS, RECURSIVE_ELF_TENDERMINT, STORAGE_PROOF_ELF, TENDERMINT_ELF,
+    WRAPPER_ELF_BITCOIN, WRAPPER_ELF_GRANDPA, WRAPPER_ELF_HELIOS, WRAPPER_ELF_TENDERMINT,
+    api::StatsResponse,
+    archive,
+    artifacts::{self, ElfManifest, ManifestEntry},
+    backends::{
+        BitcoinBackend, GrandpaBackend, HeliosBackend, TendermintBackend, tendermint_ibc_outputs,
+    },
+    chains::tendermint_chain,
+    checkpoints::{
+        BITCOIN_TRUSTED_HEIGHT, CheckpointManifest, GRANDPA_TRUSTED_HEIGHT, HeliosCheckpoint,
+        TendermintCheckpoint,
+    },
+    encoding::to_hex,
+    ics23::ICS23_CHAIN_ID,
+    networks::EthereumNetwork,
+    opstack::{OPSTACK_CHAIN_ID, OpStackConfig},
+    preprocessor,
+    prover::{self, Aggregation, Sp1RoundProver, WrapperEncoding},
+    recover,
+    state::{DEFAULT_CHAIN_ID, ServiceState, StateManager},
+    storage::STORAGE_CHAIN_ID,
+};
+
+/// Generates the recursive circuits from the trusted checkpoints, the Tendermint circuit for
+/// `tendermint_chain_id`
+pub async fn generate_recursion_circuits(tendermint_chain_id: &str) -> Result<()> {
+    let client = ProverClient::from_env();
+
+    // Derive the sync committee trusted at the trusted slot of the source network
+    let network = EthereumNetwork::from_env()?;
+    let helios_trusted_slot = network.trusted_slot()?;
+    let trusted_committee_hash =
+        preprocessor::sync_committee_hash(helios_trusted_slot, network).await?;
+    let checkpoint = &CheckpointManifest::built_in().helios;
+    if checkpoint.source_chain_id == network.chain_id()
+        && checkpoint.trusted_slot == helios_trusted_slot
+        && checkpoint.committee_hash()? != trusted_committee_hash
+    {
+        return Err(anyhow::anyhow!(
+            "The sync committee hash at slot {} is {}, the checkpoints manifest pins {}",
+            helios_trusted_slot,
+            to_hex(trusted_committee_hash),
+            checkpoint.trusted_sync_committee_hash
+        ));
+    }
+
+    // Write the trusted constants embedded by the Helios recursive circuit
+    let (_, helios_vk) = client.setup(HELIOS_ELF);
+    let constants = helios_recursion_types::RecursionCircuitConstants {
+        trusted_sync_committee_hash: trusted_committee_hash,
+        trusted_head: helios_trusted_slot,
+        helios_vk: helios_vk.bytes32(),
+        chain_id: network.caip2(),
+    };
+    write(
+        "crates/integrations/sp1-helios/circuit/constants.bin",
+        borsh::to_vec(&constants)?,
+    )
+    .context("Failed to write the constants of the recursive circuit")?;
+    tracing::info!("Helios recursive circuit constants: {:?}", constants);
+
+    // The Helios recursive circuit verifies the previous recursive proof with the Groth16
+    // verifier or with SP1 proof aggregation
+    let template = match Aggregation::from_env()? {
+        Aggregation::Groth16 => {
+            include_str!("../../integrations/sp1-helios/circuit/src/blueprint.rs")
+        }
+        Aggregation::Compressed => {
+            include_str!("../../integrations/sp1-helios/circuit/src/blueprint_compressed.rs")
+        }
+    };
+    write(
+        "crates/integrations/sp1-helios/circuit/src/main.rs",
+        template,
+    )
+    .context("Failed to generate recursive circuit from blueprint")?;
+
+    // Generate the Tendermint recursive circuit for the selected chain
+    let chain = tendermint_chain(tendermint_chain_id)?;
+    chain.validate()?;
+    let template = include_str!("../../integrations/sp1-tendermint/circuit/src/blueprint.rs");
+    let (_, tendermint_vk) = client.setup(TENDERMINT_ELF);
+    // Chains without an approved upgrade have an upgrade height of 0
+    let (upgrade_height, upgrade_trusted_height, upgrade_trusted_root, upgrade_chain_id) =
+        match &chain.upgrade {
+            Some(upgrade) => (
+                upgrade.halt_height,
+                upgrade.trusted_height,
+                upgrade.trusted_root,
+                chain.caip2_at(upgrade.trusted_height)?,
+            ),
+            None => (0, 0, [0; 32], String::new()),
+        };
+    let generated_code = template
+        .replace("{ trusted_height }", &chain.trusted_height.to_string())
+        .replace("{ trusted_root }", &format!("{:?}", chain.trusted_root))
+        .replace("{ tendermint_vk }", &tendermint_vk.bytes32())
+        .replace("{ chain_id }", &chain.caip2()?)
+        .replace(
+            "{ trusting_period_secs }",
+            &chain.trusting_period_secs()?.to_string(),
+        )
+        .replace(
+            "{ max_height_advance }",
+            &chain.max_height_advance()?.to_string(),
+        )
+        .replace("{ upgrade_height }", &upgrade_height.to_string())
+        .replace(
+            "{ upgrade_trusted_height }",
+            &upgrade_trusted_height.to_string(),
+        )
+        .replace(
+            "{ upgrade_trusted_root }",
+            &format!("{:?}", upgrade_trusted_root),
+        )
+        .replace("{ upgrade_chain_id }", &upgrade_chain_id);
+    write(
+        "crates/integrations/sp1-tendermint/circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate recursive circuit from blueprint")?;
+
+    // Generate the GRANDPA recursive circuit if a Substrate chain is configured
+    if let Ok(grandpa_rpc_url) = std::env::var("GRANDPA_RPC_URL") {
+        let substrate_client = preprocessor::grandpa::SubstrateRpcClient::new(grandpa_rpc_url);
+        let trusted_hash = substrate_client.block_hash(GRANDPA_TRUSTED_HEIGHT).await?;
+        let (authorities, authority_set_id) = substrate_client.authorities(&trusted_hash).await?;
+        // CAIP-2 identifies Substrate chains by the prefix of their genesis hash
+        let genesis_hash = substrate_client.block_hash(0).await?;
+        let template = include_str!("../../integrations/sp1-grandpa/circuit/src/blueprint.rs");
+        let generated_code = template
+            .replace("{ trusted_height }", &GRANDPA_TRUSTED_HEIGHT.to_string())
+            .replace("{ authority_set_id }", &authority_set_id.to_string())
+            .replace(
+                "{ authority_set_hash }",
+                &format!(
+                    "{:?}",
+                    grandpa_recursion_types::authority_set_hash(&authorities)
+                ),
+            )
+            .replace(
+                "{ chain_id }",
+                &format!("polkadot:{}", hex::encode(&genesis_hash[..16])),
+            );
+        write(
+            "crates/integrations/sp1-grandpa/circuit/src/main.rs",
+            generated_code,
+        )
+        .context("Failed to generate recursive circuit from blueprint")?;
+    }
+
+    // Generate the Bitcoin recursive circuit if an Esplora instance is configured
+    if let Ok(esplora_url) = std::env::var("BITCOIN_ESPLORA_URL") {
+        let esplora_client = preprocessor::bitcoin::EsploraClient::new(esplora_url);
+        let trusted_state = esplora_client.chain_state(BITCOIN_TRUSTED_HEIGHT).await?;
+        // CAIP-2 identifies Bitcoin chains by the prefix of their genesis hash, as displayed
+        let genesis = BlockHeader::decode(&esplora_client.header(0).await?)
+            .map_err(|e| anyhow::anyhow!("Invalid genesis header: {:?}", e))?;
+        let mut genesis_hash = genesis.hash;
+        genesis_hash.reverse();
+        let template = include_str!("../../integrations/sp1-bitcoin/circuit/src/blueprint.rs");
+        let generated_code = template
+            .replace("{ trusted_height }", &BITCOIN_TRUSTED_HEIGHT.to_string())
+            .replace("{ trusted_hash }", &format!("{:?}", trusted_state.hash))
+            .replace("{ trusted_bits }", &format!("{:#010x}", trusted_state.bits))
+            .replace(
+                "{ period_start_time }",
+                &trusted_state.period_start_time.to_string(),
+            )
+            .replace(
+                "{ recent_times }",
+                &format!("{:?}", trusted_state.recent_times),
+            )
+            .replace(
+                "{ chain_id }",
+                &format!("bip122:{}", hex::encode(&genesis_hash[..16])),
+            );
+        write(
+            "crates/integrations/sp1-bitcoin/circuit/src/main.rs",
+            generated_code,
+        )
+        .context("Failed to generate recursive circuit from blueprint")?;
+    }
+
+    tracing::info!("Recursive circuit generated successfully");
+    Ok(())
+}
+
+/// Generates the wrapper circuits from the recursive circuits built into this binary
+pub fn generate_wrapper_circuits() -> Result<()> {
+    let client = ProverClient::from_env();
+    let (_, helios_vk) = client.setup(RECURSIVE_ELF_HELIOS);
+    let aggregation = Aggregation::from_env()?;
+    let helios_vk_bytes = aggregation.recursive_vk(&helios_vk);
+    let abi_outputs = WrapperEncoding::from_env()?.is_abi().to_string();
+
+    let (_, tendermint_vk) = client.setup(RECURSIVE_ELF_TENDERMINT);
+    let tendermint_vk_bytes = tendermint_vk.bytes32();
+
+    let template = match aggregation {
+        Aggregation::Groth16 => {
+            include_str!("../../integrations/sp1-helios/wrapper-circuit/src/blueprint.rs")
+        }
+        Aggregation::Compressed => include_str!(
+            "../../integrations/sp1-helios/wrapper-circuit/src/blueprint_compressed.rs"
+        ),
+    };
+    let generated_code = template
+        .replace("{ recursive_vk }", &format!("{:?}", helios_vk_bytes))
+        .replace(
+            "{ recursive_vk_digest }",
+            &format!("{:?}", helios_vk.hash_u32()),
+        )
+        .replace("{ abi_outputs }", &abi_outputs);
+
+    // Generate the Helios wrapper circuit
+    write(
+        "crates/integrations/sp1-helios/wrapper-circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate wrapper circuit from blueprint")?;
+
+    let template =
+        include_str!("../../integrations/sp1-tendermint/wrapper-circuit/src/blueprint.rs");
+
+    // Generate the Tendermint wrapper circuit
+    let generated_code = template
+        .replace("{ recursive_vk }", &format!("{:?}", tendermint_vk_bytes))
+        .replace("{ abi_outputs }", &abi_outputs)
+        .replace("{ ibc_outputs }", &tendermint_ibc_outputs()?.to_string());
+    write(
+        "crates/integrations/sp1-tendermint/wrapper-circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate wrapper circuit from blueprint")?;
+
+    // Generate the GRANDPA wrapper circuit
+    let (_, grandpa_vk) = client.setup(RECURSIVE_ELF_GRANDPA);
+    let template = include_str!("../../integrations/sp1-grandpa/wrapper-circuit/src/blueprint.rs");
+    let generated_code = template
+        .replace("{ recursive_vk }", &format!("{:?}", grandpa_vk.bytes32()))
+        .replace("{ abi_outputs }", &abi_outputs);
+    write(
+        "crates/integrations/sp1-grandpa/wrapper-circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate wrapper circuit from blueprint")?;
+
+    // Generate the Bitcoin wrapper circuit
+    let (_, bitcoin_vk) = client.setup(RECURSIVE_ELF_BITCOIN);
+    let template = include_str!("../../integrations/sp1-bitcoin/wrapper-circuit/src/blueprint.rs");
+    let generated_code = template
+        .replace("{ recursive_vk }", &format!("{:?}", bitcoin_vk.bytes32()))
+        .replace("{ abi_outputs }", &abi_outputs);
+    write(
+        "crates/integrations/sp1-bitcoin/wrapper-circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate wrapper circuit from blueprint")?;
+
+    tracing::info!("Wrapper circuit generated successfully");
+    Ok(())
+}
+
+/// Generates the OP Stack output circuit for the rollup configured by `OP_STACK_*`, anchored
+/// to the Helios wrapper circuit built into this binary
+pub fn generate_opstack_circuit() -> Result<()> {
+    let client = ProverClient::from_env();
+    let config = OpStackConfig::from_env()?.context(
+        "Set OP_STACK_L2_OUTPUT_ORACLE or OP_STACK_DISPUTE_GAME_FACTORY to generate the OP Stack circuit",
+    )?;
+    let (_, helios_wrapper_vk) = client.setup(WRAPPER_ELF_HELIOS);
+    let template = include_str!("../../integrations/sp1-opstack/circuit/src/blueprint.rs");
+    let generated_code = template
+        .replace("{ helios_wrapper_vk }", &helios_wrapper_vk.bytes32())
+        .replace("{ contract }", &format!("{:?}", config.contract))
+        .replace("{ output_source }", &format!("{:?}", config.source));
+    write(
+        "crates/integrations/sp1-opstack/circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate OP Stack circuit from blueprint")?;
+
+    tracing::info!("OP Stack circuit generated successfully");
+    Ok(())
+}
+
+/// Generates the storage proof circuit, anchored to the Helios wrapper circuit built into
+/// this binary
+pub fn generate_storage_circuit() -> Result<()> {
+    let client = ProverClient::from_env();
+    let (_, helios_wrapper_vk) = client.setup(WRAPPER_ELF_HELIOS);
+    let template = include_str!("../../integrations/sp1-storage/circuit/src/blueprint.rs");
+    let generated_code = template.replace("{ helios_wrapper_vk }", &helios_wrapper_vk.bytes32());
+    write(
+        "crates/integrations/sp1-storage/circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate storage proof circuit from blueprint")?;
+
+    tracing::info!("Storage proof circuit generated successfully");
+    Ok(())
+}
+
+/// Generates the ICS23 proof circuit, anchored to the Tendermint wrapper circuit built into
+/// this binary
+pub fn generate_ics23_circuit() -> Result<()> {
+    let client = ProverClient::from_env();
+    let (_, tendermint_wrapper_vk) = client.setup(WRAPPER_ELF_TENDERMINT);
+    let template = include_str!("../../integrations/sp1-ics23/circuit/src/blueprint.rs");
+    let generated_code = template.replace(
+        "{ tendermint_wrapper_vk }",
+        &tendermint_wrapper_vk.bytes32(),
+    );
+    write(
+        "crates/integrations/sp1-ics23/circuit/src/main.rs",
+        generated_code,
+    )
+    .context("Failed to generate ICS23 proof circuit from blueprint")?;
+
+    tracing::info!("ICS23 proof circuit generated successfully");
+    Ok(())
+}
+
+/// Writes the ELFs built into this binary to `elfs_path`, the Tendermint ELFs to the
+/// directory of `tendermint_chain_id`
+pub fn dump_elfs(elfs_path: &str, tendermint_chain_id: &str) -> Result<()> {
+    let helios_recursive_elf_path = Path::new(elfs_path).join("helios-recursive-elf.bin");
+    let helios_wrapper_elf_path = Path::new(elfs_path).join("helios-wrapper-elf.bin");
+    if artifacts::is_remote(elfs_path) {
+        return Err(anyhow::anyhow!(
+            "Cannot dump ELFs to remote registry {}, dump them locally and upload them",
+            elfs_path
+        ));
+    }
+    std::fs::create_dir_all(elfs_path)?;
+
+    // Create parent directory if it doesn't exist
+    if let Some(parent) = Path::new(elfs_path).parent() {
+        std::fs::create_dir_all(parent).context("Failed to create ELF directory")?;
+    }
+
+    // Write Helios ELFs
+    std::fs::write(&helios_recursive_elf_path, RECURSIVE_ELF_HELIOS).context(format!(
+        "Failed to dump recursive ELF to {}",
+        helios_recursive_elf_path.display()
+    ))?;
+    std::fs::write(&helios_wrapper_elf_path, WRAPPER_ELF_HELIOS).context(format!(
+        "Failed to dump wrapper ELF to {}",
+        helios_wrapper_elf_path.display()
+    ))?;
+
+    // Write Tendermint ELFs, every chain has its own recursion circuit
+    let chain = tendermint_chain(tendermint_chain_id)?;
+    let tendermint_elfs_dir = chain.elfs_dir(Path::new(elfs_path));
+    std::fs::create_dir_all(&tendermint_elfs_dir)?;
+    let tendermint_recursive_elf_path = tendermint_elfs_dir.join("tendermint-recursive-elf.bin");
+    let tendermint_wrapper_elf_path = tendermint_elfs_dir.join("tendermint-wrapper-elf.bin");
+    std::fs::write(&tendermint_recursive_elf_path, RECURSIVE_ELF_TENDERMINT).context(format!(
+        "Failed to dump recursive ELF to {}",
+        tendermint_recursive_elf_path.display()
+    ))?;
+    std::fs::write(&tendermint_wrapper_elf_path, WRAPPER_ELF_TENDERMINT).context(format!(
+        "Failed to dump wrapper ELF to {}",
+        tendermint_wrapper_elf_path.display()
+    ))?;
+
+    // Write GRANDPA ELFs
+    let grandpa_recursive_elf_path = Path::new(elfs_path).join("grandpa-recursive-elf.bin");
+    let grandpa_wrapper_elf_path = Path::new(elfs_path).join("grandpa-wrapper-elf.bin");
+    std::fs::write(&grandpa_recursive_elf_path, RECURSIVE_ELF_GRANDPA).context(format!(
+        "Failed to dump recursive ELF to {}",
+        grandpa_recursive_elf_path.display()
+    ))?;
+    std::fs::write(&grandpa_wrapper_elf_path, WRAPPER_ELF_GRANDPA).context(format!(
+        "Failed to dump wrapper ELF to {}",
+        grandpa_wrapper_elf_path.display()
+    ))?;
+
+    // Write Bitcoin ELFs
+    let bitcoin_recursive_elf_path = Path::new(elfs_path).join("bitcoin-recursive-elf.bin");
+    let bitcoin_wrapper_elf_path = Path::new(elfs_path).join("bitcoin-wrapper-elf.bin");
+    std::fs::write(&bitcoin_recursive_elf_path, RECURSIVE_ELF_BITCOIN).context(format!(
+        "Failed to dump recursive ELF to {}",
+        bitcoin_recursive_elf_path.display()
+    ))?;
+    std::fs::write(&bitcoin_wrapper_elf_path, WRAPPER_ELF_BITCOIN).context(format!(
+        "Failed to dump wrapper ELF to {}",
+        bitcoin_wrapper_elf_path.display()
+    ))?;
+
+    // Write the OP Stack ELF
+    let opstack_elf_path = Path::new(elfs_path).join("opstack-output-elf.bin");
+    std::fs::write(&opstack_elf_path, OPSTACK_OUTPUT_ELF).context(format!(
+        "Failed to dump OP Stack ELF to {}",
+        opstack_elf_path.display()
+    ))?;
+
+    // Write the storage proof ELF
+    let storage_elf_path = Path::new(elfs_path).join("storage-proof-elf.bin");
+    std::fs::write(&storage_elf_path, STORAGE_PROOF_ELF).context(format!(
+        "Failed to dump storage proof ELF to {}",
+        storage_elf_path.display()
+    ))?;
+
+    // Write the ICS23 proof ELF
+    let ics23_elf_path = Path::new(elfs_path).join("ics23-proof-elf.bin");
+    std::fs::write(&ics23_elf_path, ICS23_PROOF_ELF).context(format!(
+        "Failed to dump ICS23 proof ELF to {}",
+        ics23_elf_path.display()
+    ))?;
+
+    // Record the dumped ELFs in the manifest of the directory, the entries of other Tendermint
+    // chains are kept
+    let client = ProverClient::builder().cpu().build();
+    let mut manifest = ElfManifest::read(Path::new(elfs_path))?;
+    for (name, elf) in [
+        ("helios-recursive-elf.bin".to_string(), RECURSIVE_ELF_HELIOS),
+        ("helios-wrapper-elf.bin".to_string(), WRAPPER_ELF_HELIOS),
+        (
+            chain.elf_name("tendermint-recursive-elf.bin"),
+            RECURSIVE_ELF_TENDERMINT,
+        ),
+        (
+            chain.elf_name("tendermint-wrapper-elf.bin"),
+            WRAPPER_ELF_TENDERMINT,
+        ),
+        (
+            "grandpa-recursive-elf.bin".to_string(),
+            RECURSIVE_ELF_GRANDPA,
+        ),
+        ("grandpa-wrapper-elf.bin".to_string(), WRAPPER_ELF_GRANDPA),
+        (
+            "bitcoin-recursive-elf.bin".to_string(),
+            RECURSIVE_ELF_BITCOIN,
+        ),
+        ("bitcoin-wrapper-elf.bin".to_string(), WRAPPER_ELF_BITCOIN),
+        ("opstack-output-elf.bin".to_string(), OPSTACK_OUTPUT_ELF),
+        ("storage-proof-elf.bin".to_string(), STORAGE_PROOF_ELF),
+        ("ics23-proof-elf.bin".to_string(), ICS23_PROOF_ELF),
+    ] {
+        let (_, vk) = client.setup(elf);
+        manifest
+            .elfs
+            .insert(name, ManifestEntry::new(elf, vk.bytes32()));
+    }
+    manifest.write(Path::new(elfs_path))?;
+    tracing::info!(
+        "ELF manifest written to {}, circuits at {}",
+        artifacts::join(elfs_path, artifacts::MANIFEST_NAME),
+        artifacts::CIRCUITS_GIT_COMMIT
+    );
+
+    tracing::info!("ELFs dumped successfully");
+    Ok(())
+}
+
+/// The circuit whose vk the wrapper proofs of a chain verify against
+pub(crate) fn wrapper_elf(chain_id: &str, mode: &str) -> &'static [u8] {
+    match (chain_id, mode) {
+        (OPSTACK_CHAIN_ID, _) => OPSTACK_OUTPUT_ELF,
+        (STORAGE_CHAIN_ID, _) => STORAGE_PROOF_ELF,
+        (ICS23_CHAIN_ID, _) => ICS23_PROOF_ELF,
+        (_, "HELIOS") => WRAPPER_ELF_HELIOS,
+        (_, "GRANDPA") => WRAPPER_ELF_GRANDPA,
+        (_, "BITCOIN") => WRAPPER_ELF_BITCOIN,
+        _ => WRAPPER_ELF_TENDERMINT,
+    }
+}
+
+/// Verifies the latest proof of a chain against the wrapper circuit built into this binary,
+/// and the hash chain of its update history
+pub fn verify(db_path: &Path, chain_id: &str, mode: &str) -> Result<()> {
+    let state_manager = StateManager::for_chain(db_path, chain_id)?;
+    let state = state_manager
+        .load_state()?
+        .context(format!("No state found for chain {}", chain_id))?;
+
+    // Every record must chain to its predecessor and the tip must match the state
+    let tip = state_manager.verify_history()?;
+    if let Some(tip) = &tip {
+        if tip.update_counter != state.update_counter || tip.root != state.trusted_root {
+            return Err(anyhow::anyhow!(
+                "The state of update {} does not match the history tip {}",
+                state.update_counter,
+                tip.update_counter
+            ));
+        }
+    }
+    tracing::info!(
+        "✅ Update history of {} verified up to update {}",
+        chain_id,
+        state.update_counter
+    );
+
+    let Some(proof) = state.most_recent_wrapper_proof.as_ref() else {
+        tracing::info!("No proof committed yet");
+        return Ok(());
+    };
+    let client = ProverClient::builder().cpu().build();
+    let (_, vk) = client.setup(wrapper_elf(chain_id, mode));
+    client.verify(proof, &vk).context(format!(
+        "The latest proof does not verify against {}",
+        vk.bytes32()
+    ))?;
+    tracing::info!(
+        "✅ Proof of update {} at height {} verified against {}",
+        state.update_counter,
+        state.trusted_height,
+        vk.bytes32()
+    );
+    Ok(())
+}
+
+/// The committed state of the chains in the database
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub mode: String,
+    pub chains: Vec<StatsResponse>,
+}
+
+/// Loads the committed state of `chain_id`, or of every chain in the database
+pub fn status(db_path: &Path, chain_id: Option<&str>, mode: &str) -> Result<Status> {
+    let chain_ids = match chain_id {
+        Some(chain_id) => vec![chain_id.to_string()],
+        None => StateManager::new(db_path)?.chain_ids()?,
+    };
+    let mut chains = Vec::new();
+    for chain_id in chain_ids {
+        let state_manager = StateManager::for_chain(db_path, &chain_id)?;
+        if let Some(stats) = StatsResponse::load(&state_manager)? {
+            chains.push(stats);
+        }
+    }
+    Ok(Status {
+        mode: mode.to_string(),
+        chains,
+    })
+}
+
+/// Proves and commits a single round of the chain `chain_id` of the `mode` backend, writes
+/// the committed proofs to `out` and prints its path. Exits with an error if the round fails
+pub async fn prove_once(
+    db_path: &Path,
+    mode: &str,
+    elfs_path: &str,
+    chain_id: &str,
+    out: &Path,
+) -> Result<()> {
+    if mode != "TENDERMINT" && chain_id != DEFAULT_CHAIN_ID {
+        return Err(anyhow::anyhow!(
+            "The {} backend only proves the {} chain",
+            mode,
+            DEFAULT_CHAIN_ID
+        ));
+    }
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create database directory")?;
+    }
+    let state_manager = StateManager::for_chain(db_path, chain_id)?;
+    let record = match mode {
+        "HELIOS" => {
+            let network = EthereumNetwork::from_env()?;
+            let state = load_or_initialize(&state_manager, network.trusted_slot()?, 0)?;
+            let consensus_url = std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap_or_default();
+            let prover = Sp1RoundProver::new(
+                artifacts::read_artifact(elfs_path, "helios-recursive-elf.bin").await?,
+                artifacts::read_artifact(elfs_path, "helios-wrapper-elf.bin").await?,
+            );
+            let backend = HeliosBackend::new(consensus_url, network);
+            prover::prove_once(&state_manager, state, backend, prover).await?
+        }
+        "TENDERMINT" => {
+            let chain = tendermint_chain(chain_id)?;
+            let state =
+                load_or_initialize(&state_manager, chain.trusted_height, chain.trusted_height)?;
+            let prover = Sp1RoundProver::new(
+                artifacts::read_artifact(
+                    elfs_path,
+                    &chain.elf_name("tendermint-recursive-elf.bin"),
+                )
+                .await?,
+                artifacts::read_artifact(elfs_path, &chain.elf_name("tendermint-wrapper-elf.bin"))
+                    .await?,
+            );
+            let backend = TendermintBackend::new(Some(chain));
+            prover::prove_once(&state_manager, state, backend, prover).await?
+        }
+        "GRANDPA" => {
+            let state = load_or_initialize(
+                &state_manager,
+                GRANDPA_TRUSTED_HEIGHT,
+                GRANDPA_TRUSTED_HEIGHT,
+            )?;
+            let prover = Sp1RoundProver::new(
+                artifacts::read_artifact(elfs_path, "grandpa-recursive-elf.bin").await?,
+                artifacts::read_artifact(elfs_path, "grandpa-wrapper-elf.bin").await?,
+            );
+            let backend = GrandpaBackend::new(std::env::var("GRANDPA_RPC_URL").unwrap_or_default());
+            prover::prove_once(&state_manager, state, backend, prover).await?
+        }
+        "BITCOIN" => {
+            let state = load_or_initialize(
+                &state_manager,
+                BITCOIN_TRUSTED_HEIGHT,
+                BITCOIN_TRUSTED_HEIGHT,
+            )?;
+            let prover = Sp1RoundProver::new(
+                artifacts::read_artifact(elfs_path, "bitcoin-recursive-elf.bin").await?,
+                artifacts::read_artifact(elfs_path, "bitcoin-wrapper-elf.bin").await?,
+            );
+            let confirmations = std::env::var("BITCOIN_CONFIRMATIONS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(6);
+            let backend = BitcoinBackend::new(
+                std::env::var("BITCOIN_ESPLORA_URL").unwrap_or_default(),
+                confirmations,
+            );
+            prover::prove_once(&state_manager, state, backend, prover).await?
+        }
+        _ => return Err(anyhow::anyhow!("Invalid mode: {:?}", mode)),
+    };
+
+    let Some(record) = record else {
+        tracing::info!("🏁 No new update to prove");
+        return Ok(());
+    };
+    let state = state_manager
+        .load_state()?
+        .context("The committed state is missing")?;
+    let bundle = archive::bundle(chain_id, mode, record.into(), &state)?;
+    write(out, serde_json::to_vec_pretty(&bundle)?)
+        .context(format!("Failed to write the proof to {}", out.display()))?;
+    println!("{}", out.display());
+    Ok(())
+}
+
+fn load_or_initialize(
+    state_manager: &StateManager,
+    initial_slot: u64,
+    initial_height: u64,
+) -> Result<ServiceState> {
+    match state_manager.load_state()? {
+        Some(state) => Ok(state),
+        None => state_manager.initialize_state(initial_slot, initial_height),
+    }
+}
+
+/// Writes the Helios checkpoint at `slot` of the `SOURCE_CHAIN_ID` network into the manifest,
+/// with the hash of the sync committee trusted at the slot
+pub async fn bootstrap_helios(slot: u64) -> Result<HeliosCheckpoint> {
+    let network = EthereumNetwork::from_env()?;
+    let committee_hash = preprocessor::sync_committee_hash(slot, network).await?;
+    let checkpoint = HeliosCheckpoint {
+        source_chain_id: network.chain_id(),
+        trusted_slot: slot,
+        trusted_sync_committee_hash: to_hex(committee_hash),
+    };
+    let mut manifest = CheckpointManifest::read()?;
+    manifest.helios = checkpoint.clone();
+    manifest.write()?;
+    tracing::info!(
+        "🥾 Bootstrapped the Helios checkpoint at slot {} (committee {})",
+        slot,
+        checkpoint.trusted_sync_committee_hash
+    );
+    if std::env::var("HELIOS_TRUSTED_SLOT").is_ok_and(|trusted| trusted != slot.to_string()) {
+        tracing::warn!("⚠️  HELIOS_TRUSTED_SLOT overrides the bootstrapped slot, unset it");
+    }
+    Ok(checkpoint)
+}
+
+/// Writes the checkpoint of the Tendermint chain `chain_id` at `height` with the hash of its
+/// header: into the manifest for the chain configured by `TENDERMINT_RPC_URL`, into its entry
+/// of `TENDERMINT_CHAINS_CONFIG` for the chains configured there
+pub async fn bootstrap_tendermint(chain_id: &str, height: u64) -> Result<TendermintCheckpoint> {
+    let chain = tendermint_chain(chain_id)?;
+    let (root, _) = recover::light_block(&chain, height).await?;
+    let checkpoint = TendermintCheckpoint {
+        trusted_height: height,
+        trusted_root: to_hex(root),
+        network: chain.network.clone(),
+    };
+    match std::env::var("TENDERMINT_CHAINS_CONFIG") {
+        Ok(path) => {
+            let config = std::fs::read_to_string(&path)
+                .context(format!("Failed to read Tendermint chains config {}", path))?;
+            let mut chains: Vec<serde_json::Value> = serde_json::from_str(&config)
+                .context(format!("Failed to parse Tendermint chains config {}", path))?;
+            let entry = chains
+                .iter_mut()
+                .find(|entry| entry["chain_id"] == chain_id)
+                .context(format!("Unknown Tendermint chain {}", chain_id))?;
+            entry["trusted_height"] = height.into();
+            entry["trusted_root"] = checkpoint.trusted_root.clone().into();
+            let mut config = serde_json::to_string_pretty(&chains)?;
+            config.push('\n');
+            write(&path, config).context(format!("Failed to write {}", path))?;
+        }
+        Err(_) => {
+            let mut manifest = CheckpointManifest::read()?;
+            manifest.tendermint = checkpoint.clone();
+            manifest.write()?;
+        }
+    }
+    tracing::info!(
+        "🥾 Bootstrapped the checkpoint of Tendermint chain {} at height {} (root {})",
+        chain_id,
+        height,
+        checkpoint.trusted_root
+    );
+    Ok(checkpoint)
+}
+
+/// Captures the fixtures of the `lightwave-fixtures` crate into `out`: the Electra header and
+/// body roots at `slot` from `SOURCE_CONSENSUS_RPC_URL`, and the outputs an SP1 Tendermint
+/// proof from `trusted_height` to `target_height` commits, from the header hashes served by
+/// `TENDERMINT_RPC_URL`
+pub async fn capture_fixtures(
+    slot: u64,
+    trusted_height: u64,
+    target_height: u64,
+    out: &Path,
+) -> Result<()> {
+    std::fs::create_dir_all(out).context(format!(
+        "Failed to create fixtures directory {}",
+        out.display()
+    ))?;
+
+    let consensus_url = std::env::var("SOURCE_CONSENSUS_RPC_URL")
+        .context("SOURCE_CONSENSUS_RPC_URL must be set to capture the Helios fixtures")?;
+    let electra_body_roots =
+        beacon_electra::get_electra_block_body_roots(slot, &consensus_url).await;
+    let beacon_header = beacon_electra::get_beacon_block_header(slot, &consensus_url).await;
+    let electra_header = beacon_electra::types::electra::ElectraBlockHeader {
+        slot: beacon_header.slot.as_u64(),
+        proposer_index: beacon_header.proposer_index,
+        parent_root: beacon_header.parent_root.to_vec().try_into().unwrap(),
+        state_root: beacon_header.state_root.to_vec().try_into().unwrap(),
+        body_root: beacon_header.body_root.to_vec().try_into().unwrap(),
+    };
+    let helios_round = serde_json::json!({
+        "electra_header": electra_header,
+        "electra_body_roots": electra_body_roots,
+    });
+    write(
+        out.join("helios_round.json"),
+        serde_json::to_vec_pretty(&helios_round)?,
+    )?;
+    tracing::info!("📸 Captured the Electra block of slot {}", slot);
+
+    let client = TendermintRPCClient::default();
+    let peer_id = client
+        .fetch_peer_id()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch the Tendermint peer id: {}", e))?;
+    let mut header_hashes = Vec::new();
+    for height in [trusted_height, target_height] {
+        let light_block = client
+            .fetch_light_block(height, peer_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch light block {}: {}", height, e))?;
+        let hash: [u8; 32] = light_block
+            .signed_header
+            .header
+            .hash()
+            .as_bytes()
+            .try_into()
+            .context(format!(
+                "The header hash of height {} is not 32 bytes",
+                height
+            ))?;
+        header_hashes.push(hash);
+    }
+    let tendermint_output = TendermintOutput {
+        trusted_height,
+        target_height,
+        trusted_header_hash: header_hashes[0],
+        target_header_hash: header_hashes[1],
+    };
+    write(
+        out.join("tendermint_output.json"),
+        serde_json::to_vec_pretty(&tendermint_output)?,
+    )?;
+    tracing::info!(
+        "📸 Captured the Tendermint headers of heights {} and {}",
+        trusted_height,
+        target_height
+    );
+    println!("{}", out.display());
+    Ok(())
+}
@@ -0,0 +1,207 @@
+// Runtime control of the prover loops: the admin API can switch the active backend or
+// disable chains without an env change and a restart.
+//
+// A change stops the running loops after their current round and starts the loops of the new
+// configuration. Helios, GRANDPA, Bitcoin and single-chain Tendermint deployments all prove
+// into the `default` state row, so switching the backend parks the rows of the previous
+// backend and restores the parked rows of the new one, each keeps its own proof chain.
+// The active configuration is persisted and survives restarts.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+use crate::state::StateManager;
+
+/// The backends the service can run, selected by `CLIENT_BACKEND`
+pub const BACKENDS: [&str; 4] = ["HELIOS", "TENDERMINT", "GRANDPA", "BITCOIN"];
+
+/// The configuration the prover loops run with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlState {
+    /// The light client backend proving the `default` chain
+    pub backend: String,
+    /// Chains whose loops are not started: `default`, a Tendermint chain id, `opstack`,
+    /// `storage` or `ics23`
+    #[serde(default)]
+    pub disabled_chains: BTreeSet<String>,
+}
+
+impl ControlState {
+    /// Whether the loop of a chain should run
+    pub fn is_enabled(&self, chain_id: &str) -> bool {
+        !self.disabled_chains.contains(chain_id)
+    }
+}
+
+/// The backend whose proofs the `default` chain holds, `CLIENT_BACKEND` until the backend is
+/// switched at runtime
+pub fn active_backend(state_manager: &StateManager) -> Result<String> {
+    Ok(match state_manager.load_control()? {
+        Some(control) => control.backend,
+        None => std::env::var("CLIENT_BACKEND").unwrap_or_else(|_| "TENDERMINT".to_string()),
+    })
+}
+
+/// Shared handle on the requested configuration, watched by the prover supervisor
+#[derive(Clone)]
+pub struct Control {
+    tx: Arc<watch::Sender<ControlState>>,
+}
+
+impl Control {
+    /// Loads the persisted configuration, or records `CLIENT_BACKEND` as the owner of the
+    /// `default` chain on the first start
+    pub fn load(state_manager: &StateManager, mode: &str) -> Result<Self> {
+        let state = match state_manager.load_control()? {
+            Some(state) => {
+                if state.backend != mode {
+                    tracing::warn!(
+                        "⚠️  CLIENT_BACKEND is {} but the backend was switched to {} at runtime, running {}",
+                        mode,
+                        state.backend,
+                        state.backend
+                    );
+                }
+                state
+            }
+            None => {
+                let state = ControlState {
+                    backend: mode.to_string(),
+                    disabled_chains: BTreeSet::new(),
+                };
+                state_manager.save_control(&state)?;
+                state
+            }
+        };
+        Ok(Self {
+            tx: Arc::new(watch::channel(state).0),
+        })
+    }
+
+    /// The most recently requested configuration
+    pub fn current(&self) -> ControlState {
+        self.tx.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ControlState> {
+        self.tx.subscribe()
+    }
+
+    /// Requests a new configuration, applied once the running loops finished their round
+    pub fn request(&self, state: ControlState) -> Result<()> {
+        if !BACKENDS.contains(&state.backend.as_str()) {
+            return Err(anyhow!(
+                "Unsupported backend {}, expected one of {}",
+                state.backend,
+                BACKENDS.join(", ")
+            ));
+        }
+        self.tx.send_replace(state);
+        Ok(())
+    }
+
+    /// Replaces the requested configuration without notifying the supervisor, used to roll
+    /// back a configuration that failed to start
+    pub fn restore(&self, state: ControlState, changes: &mut watch::Receiver<ControlState>) {
+        self.tx.send_replace(state);
+        changes.borrow_and_update();
+    }
+}
+
+/// Asks the loops of a configuration to stop after their current round
+#[derive(Clone)]
+pub struct Stop(watch::Receiver<bool>);
+
+impl Stop {
+    pub fn new() -> (watch::Sender<bool>, Self) {
+        let (tx, rx) = watch::channel(false);
+        (tx, Self(rx))
+    }
+
+    /// A signal that is never raised
+    pub fn never() -> Self {
+        Self(watch::channel(false).1)
+    }
+
+    pub fn requested(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Sleeps for `secs` seconds, or until a stop is requested
+    pub async fn sleep(&mut self, secs: u64) {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(secs)) => {}
+            _ = self.raised() => {}
+        }
+    }
+
+    /// Waits until a stop is requested
+    pub async fn raised(&mut self) {
+        // The sender of `never` is gone, it can't be raised anymore
+        if self.0.wait_for(|stop| *stop).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stop_interrupts_sleep() {
+        let (tx, mut stop) = Stop::new();
+        assert!(!stop.requested());
+        tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), stop.sleep(3600))
+            .await
+            .unwrap();
+        assert!(stop.requested());
+
+        // A signal that can't be raised sleeps the full duration
+        let mut never = Stop::never();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), never.sleep(3600))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_switch_backend_keeps_each_proof_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_manager = StateManager::new(&dir.path().join("service_state.db")).unwrap();
+        let control = |backend: &str| ControlState {
+            backend: backend.to_string(),
+            disabled_chains: BTreeSet::new(),
+        };
+        state_manager.save_control(&control("TENDERMINT")).unwrap();
+        state_manager.initialize_state(100, 100).unwrap();
+
+        // Helios starts from an empty default chain, Tendermint's is parked
+        state_manager
+            .switch_backend("TENDERMINT", &control("HELIOS"))
+            .unwrap();
+        assert!(state_manager.load_state().unwrap().is_none());
+        assert_eq!(active_backend(&state_manager).unwrap(), "HELIOS");
+        assert_eq!(state_manager.chain_ids().unwrap(), Vec::<String>::new());
+        state_manager.initialize_state(7, 0).unwrap();
+
+        // Switching back restores the Tendermint chain and parks Helios'
+        state_manager
+            .switch_backend("HELIOS", &control("TENDERMINT"))
+            .unwrap();
+        assert_eq!(
+            state_manager.load_state().unwrap().unwrap().trusted_height,
+            100
+        );
+        let parked = StateManager::for_chain(
+            &dir.path().join("service_state.db"),
+            &crate::state::parked_chain_id("HELIOS"),
+        )
+        .unwrap();
+        assert_eq!(parked.load_state().unwrap().unwrap().trusted_slot, 7);
+    }
+}
@@ -0,0 +1,760 @@
+// Troubleshooting checks run by `service doctor`.
+//
+// Validates the configuration and environment the prover loop depends on and prints
+// a pass/fail report with remediation hints, so misconfiguration is caught before
+// the first round instead of hours into it.
+
+use anyhow::Result;
+use sp1_sdk::{HashableKey, ProverClient};
+use std::{path::Path, process::Command, time::Duration};
+
+use crate::{
+    ICS23_PROOF_ELF, OPSTACK_OUTPUT_ELF, RECURSIVE_ELF_BITCOIN, RECURSIVE_ELF_GRANDPA,
+    RECURSIVE_ELF_HELIOS, RECURSIVE_ELF_TENDERMINT, STORAGE_PROOF_ELF,
+    api::recursive_vk,
+    artifacts,
+    chains::tendermint_chains,
+    egress,
+    gpu::{ContainerHealth, GpuManager},
+    ics23::{ICS23_CHAIN_ID, Ics23Config},
+    network::NetworkConfig,
+    networks::EthereumNetwork,
+    opstack::{OPSTACK_CHAIN_ID, OpStackConfig},
+    preprocessor::opstack::ExecutionRpcClient,
+    prover::ProverMode,
+    slo::SloConfig,
+    slots::ProvingSlots,
+    state::{DEFAULT_CHAIN_ID, ServiceState, StateManager},
+    state_diff::StateDiffConfig,
+    storage::{STORAGE_CHAIN_ID, StorageConfig},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The outcome of a single check, also reported by `service recover`
+pub(crate) struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    pub(crate) fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub(crate) fn warn(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub(crate) fn fail(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Runs all checks, prints the report and returns the number of failed checks
+pub async fn run() -> Result<usize> {
+    let mode = std::env::var("CLIENT_BACKEND").unwrap_or_else(|_| "TENDERMINT".to_string());
+    let elfs_path = std::env::var("ELFS_OUT").unwrap_or_else(|_| "elfs/variable".to_string());
+    let db_path =
+        std::env::var("SERVICE_STATE_DB_PATH").unwrap_or_else(|_| "service_state.db".to_string());
+
+    let mut checks = check_env(&mode);
+    checks.push(check_egress());
+    checks.extend(check_gpu().await);
+    if let Err(e) = SloConfig::from_env() {
+        checks.push(Check::fail(
+            "SLO",
+            e.to_string(),
+            "Fix the SLO_* variables or unset them",
+        ));
+    }
+
+    // (chain id, recursive ELF name, recursive ELF built into this binary)
+    let mut chains = Vec::new();
+    match mode.as_str() {
+        "HELIOS" => {
+            checks.push(check_ethereum_network());
+            let url = std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap_or_default();
+            checks.push(check_beacon_rpc(&url).await);
+            chains.push((
+                DEFAULT_CHAIN_ID.to_string(),
+                "helios-recursive-elf.bin".to_string(),
+                RECURSIVE_ELF_HELIOS,
+            ));
+            match OpStackConfig::from_env() {
+                Ok(Some(config)) => {
+                    checks.push(check_execution_rpc(&config.execution_rpc).await);
+                    chains.push((
+                        OPSTACK_CHAIN_ID.to_string(),
+                        "opstack-output-elf.bin".to_string(),
+                        OPSTACK_OUTPUT_ELF,
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => checks.push(Check::fail(
+                    "OP Stack",
+                    e.to_string(),
+                    "Fix the OP_STACK_* variables or unset them",
+                )),
+            }
+            match StorageConfig::from_env() {
+                Ok(Some(config)) => {
+                    checks.push(check_execution_rpc(&config.execution_rpc).await);
+                    chains.push((
+                        STORAGE_CHAIN_ID.to_string(),
+                        "storage-proof-elf.bin".to_string(),
+                        STORAGE_PROOF_ELF,
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => checks.push(Check::fail(
+                    "Storage proofs",
+                    e.to_string(),
+                    "Fix the STORAGE_PROOF_* variables or unset them",
+                )),
+            }
+            match StateDiffConfig::from_env() {
+                Ok(Some(config)) => {
+                    checks.push(check_execution_rpc(&config.execution_rpc).await);
+                    checks.push(check_orphaned_roots(&db_path));
+                }
+                Ok(None) => {}
+                Err(e) => checks.push(Check::fail(
+                    "State-diff verification",
+                    e.to_string(),
+                    "Fix the STATE_DIFF_* variables and ETHEREUM_URL or unset STATE_DIFF_VERIFICATION",
+                )),
+            }
+        }
+        "TENDERMINT" => {
+            match tendermint_chains() {
+                Ok(configs) => {
+                    for chain in configs {
+                        checks.push(check_tendermint_rpc(&chain.chain_id, &chain.rpc_url).await);
+                        chains.push((
+                            chain.chain_id.clone(),
+                            chain.elf_name("tendermint-recursive-elf.bin"),
+                            RECURSIVE_ELF_TENDERMINT,
+                        ));
+                    }
+                }
+                Err(e) => checks.push(Check::fail(
+                    "Tendermint chains",
+                    e.to_string(),
+                    "Fix the file referenced by TENDERMINT_CHAINS_CONFIG",
+                )),
+            }
+            match Ics23Config::from_env() {
+                Ok(Some(_)) => chains.push((
+                    ICS23_CHAIN_ID.to_string(),
+                    "ics23-proof-elf.bin".to_string(),
+                    ICS23_PROOF_ELF,
+                )),
+                Ok(None) => {}
+                Err(e) => checks.push(Check::fail(
+                    "ICS23 proofs",
+                    e.to_string(),
+                    "Fix the ICS23_* variables or unset them",
+                )),
+            }
+        }
+        "GRANDPA" => {
+            let url = std::env::var("GRANDPA_RPC_URL").unwrap_or_default();
+            checks.push(check_substrate_rpc(&url).await);
+            chains.push((
+                DEFAULT_CHAIN_ID.to_string(),
+                "grandpa-recursive-elf.bin".to_string(),
+                RECURSIVE_ELF_GRANDPA,
+            ));
+        }
+        "BITCOIN" => {
+            let url = std::env::var("BITCOIN_ESPLORA_URL").unwrap_or_default();
+            checks.push(check_esplora(&url).await);
+            chains.push((
+                DEFAULT_CHAIN_ID.to_string(),
+                "bitcoin-recursive-elf.bin".to_string(),
+                RECURSIVE_ELF_BITCOIN,
+            ));
+        }
+        _ => {}
+    }
+
+    for (chain_id, elf_name, embedded_elf) in chains {
+        let state = match check_db(&db_path, &chain_id) {
+            Ok((check, state)) => {
+                checks.push(check);
+                state
+            }
+            Err(check) => {
+                checks.push(check);
+                None
+            }
+        };
+        checks.extend(check_elf(&mode, &elfs_path, &elf_name, embedded_elf, state.as_ref()).await);
+    }
+
+    print_report("Lightwave doctor report", &checks);
+    Ok(failed(&checks))
+}
+
+/// Checks that the required environment variables are set for the configured backend
+fn check_env(mode: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let required: &[&str] = match mode {
+        "HELIOS" => &["SOURCE_CONSENSUS_RPC_URL"],
+        "TENDERMINT" if std::env::var("TENDERMINT_CHAINS_CONFIG").is_ok() => &[],
+        "TENDERMINT" => &["TENDERMINT_RPC_URL"],
+        "GRANDPA" => &["GRANDPA_RPC_URL"],
+        "BITCOIN" => &["BITCOIN_ESPLORA_URL"],
+        _ => {
+            checks.push(Check::fail(
+                "CLIENT_BACKEND",
+                format!("invalid mode {}", mode),
+                "Set CLIENT_BACKEND to HELIOS, TENDERMINT, GRANDPA or BITCOIN",
+            ));
+            return checks;
+        }
+    };
+    checks.push(Check::pass("CLIENT_BACKEND", mode));
+
+    for var in required {
+        checks.push(match std::env::var(var) {
+            Ok(value) if !value.is_empty() => Check::pass(*var, "set"),
+            _ => Check::fail(*var, "not set", format!("Set {} in .env", var)),
+        });
+    }
+
+    for var in ["SERVICE_STATE_DB_PATH", "ELFS_OUT"] {
+        if std::env::var(var).is_err() {
+            checks.push(Check::warn(
+                var,
+                "not set, using the default",
+                format!("Set {} explicitly to avoid surprises across restarts", var),
+            ));
+        }
+    }
+
+    if artifacts::is_remote(&std::env::var("ELFS_OUT").unwrap_or_default())
+        && std::env::var("ELFS_SHA256SUMS").is_err()
+    {
+        checks.push(Check::fail(
+            "ELFS_SHA256SUMS",
+            "not set but ELFS_OUT is a remote registry",
+            "Pin the checksums of the remote ELFs in a sha256sum file",
+        ));
+    }
+
+    match std::env::var("SP1_PROVER").as_deref() {
+        Ok("network") => match NetworkConfig::from_env() {
+            Ok(_) => checks.push(Check::pass("SP1_PROVER", "network")),
+            Err(e) => checks.push(Check::fail(
+                "Prover network",
+                e.to_string(),
+                "Set NETWORK_PRIVATE_KEY to a funded prover network key and check the NETWORK_* settings",
+            )),
+        },
+        Ok(prover) => checks.push(Check::pass("SP1_PROVER", prover)),
+        Err(_) => checks.push(Check::warn(
+            "SP1_PROVER",
+            "not set, SP1 defaults to the CPU prover",
+            "Set SP1_PROVER to cpu, cuda or network",
+        )),
+    }
+    if let Err(e) = ProvingSlots::from_env() {
+        checks.push(Check::fail(
+            "PROVING_SLOTS",
+            e.to_string(),
+            "Set PROVING_SLOTS to the number of proofs the prover can generate concurrently",
+        ));
+    }
+    match ProverMode::from_env() {
+        Ok(ProverMode::Groth16) => checks.push(Check::pass("PROVER_MODE", "groth16")),
+        Ok(mode) => checks.push(Check::warn(
+            "PROVER_MODE",
+            format!("{}, the service publishes mock proofs", mode),
+            "Unset PROVER_MODE outside of integration tests",
+        )),
+        Err(e) => checks.push(Check::fail(
+            "PROVER_MODE",
+            e.to_string(),
+            "Set PROVER_MODE to groth16, execute or mock",
+        )),
+    }
+    checks
+}
+
+/// Checks the GPU and its prover containers when proving with CUDA
+async fn check_gpu() -> Vec<Check> {
+    let manager = match GpuManager::from_env() {
+        Ok(manager) => manager,
+        Err(e) => {
+            return vec![Check::fail(
+                "GPU containers",
+                e.to_string(),
+                "Fix GPU_CONTAINER_MANAGER or DOCKER_HOST",
+            )];
+        }
+    };
+    let mut checks = match manager.health().await {
+        Ok(health) if health.is_empty() => {
+            vec![Check::pass("GPU containers", "not managed by the service")]
+        }
+        Ok(health) => health
+            .into_iter()
+            .map(|(name, health)| {
+                let check_name = format!("GPU container {}", name);
+                match health {
+                    ContainerHealth::Absent => {
+                        Check::pass(check_name, "not running, started with the next proof")
+                    }
+                    ContainerHealth::Running => Check::pass(check_name, "running"),
+                    ContainerHealth::Unhealthy => Check::warn(
+                        check_name,
+                        "running but unhealthy",
+                        "It is removed before the next proof, check the GPU if this persists",
+                    ),
+                    ContainerHealth::Stopped(status) => {
+                        Check::warn(check_name, status, "It is removed before the next proof")
+                    }
+                }
+            })
+            .collect(),
+        Err(e) => vec![Check::fail(
+            "GPU containers",
+            e.to_string(),
+            "Start the docker daemon (or podman socket) and make sure DOCKER_HOST points to it",
+        )],
+    };
+    if std::env::var("SP1_PROVER").as_deref() != Ok("cuda") {
+        return checks;
+    }
+
+    checks.push(match Command::new("nvidia-smi").arg("-L").output() {
+        Ok(output) if output.status.success() => Check::pass(
+            "GPU",
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        Ok(_) | Err(_) => Check::fail(
+            "GPU",
+            "nvidia-smi failed",
+            "Install the NVIDIA driver and container toolkit",
+        ),
+    });
+    checks
+}
+
+/// Checks that `EGRESS_ALLOW_LIST` allows every configured endpoint
+fn check_egress() -> Check {
+    let name = "EGRESS_ALLOW_LIST";
+    let proxy = ["HTTPS_PROXY", "HTTP_PROXY", "ALL_PROXY"]
+        .into_iter()
+        .find(|var| std::env::var(var).is_ok())
+        .map(|var| format!(", proxied by {}", var))
+        .unwrap_or_default();
+    match egress::denied_endpoints() {
+        Ok(denied) if denied.is_empty() => match egress::EgressAllowList::global() {
+            Ok(Some(_)) => Check::pass(name, format!("every endpoint is allowed{}", proxy)),
+            _ => Check::pass(name, format!("unset, every host is allowed{}", proxy)),
+        },
+        Ok(denied) => Check::fail(
+            name,
+            denied
+                .iter()
+                .map(|(var, url)| format!("{} ({})", url, var))
+                .collect::<Vec<_>>()
+                .join(", "),
+            "Allow the hosts of these endpoints or change the endpoints",
+        ),
+        Err(e) => Check::fail(name, e.to_string(), "Fix EGRESS_ALLOW_LIST"),
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    egress::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Checks that the beacon node is reachable and synced
+async fn check_beacon_rpc(url: &str) -> Check {
+    let name = "Consensus RPC";
+    let response = http_client()
+        .get(format!("{}/eth/v1/node/syncing", url.trim_end_matches('/')))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    let body: serde_json::Value = match response {
+        Ok(response) => match response.json().await {
+            Ok(body) => body,
+            Err(e) => {
+                return Check::fail(
+                    name,
+                    e.to_string(),
+                    "Point SOURCE_CONSENSUS_RPC_URL to a beacon API",
+                );
+            }
+        },
+        Err(e) => {
+            return Check::fail(
+                name,
+                e.to_string(),
+                "Check SOURCE_CONSENSUS_RPC_URL and network access",
+            );
+        }
+    };
+
+    if body["data"]["is_syncing"].as_bool() == Some(false) {
+        Check::pass(
+            name,
+            format!(
+                "synced at slot {}",
+                body["data"]["head_slot"].as_str().unwrap_or("?")
+            ),
+        )
+    } else {
+        Check::warn(
+            name,
+            "node is syncing",
+            "Wait for the beacon node to sync or use another endpoint",
+        )
+    }
+}
+
+/// Checks that a Tendermint RPC is reachable and caught up
+async fn check_tendermint_rpc(chain_id: &str, url: &str) -> Check {
+    let name = format!("Tendermint RPC ({})", chain_id);
+    let response = http_client()
+        .get(format!("{}/status", url.trim_end_matches('/')))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    let body: serde_json::Value = match response {
+        Ok(response) => match response.json().await {
+            Ok(body) => body,
+            Err(e) => {
+                return Check::fail(name, e.to_string(), "Point the RPC url to a Tendermint RPC");
+            }
+        },
+        Err(e) => return Check::fail(name, e.to_string(), "Check the RPC url and network access"),
+    };
+
+    let sync_info = &body["result"]["sync_info"];
+    if sync_info["catching_up"].as_bool() == Some(false) {
+        Check::pass(
+            name,
+            format!(
+                "synced at height {}",
+                sync_info["latest_block_height"].as_str().unwrap_or("?")
+            ),
+        )
+    } else {
+        Check::warn(
+            name,
+            "node is catching up",
+            "Wait for the node to sync or use another endpoint",
+        )
+    }
+}
+
+/// Checks that a Substrate RPC is reachable and synced
+async fn check_substrate_rpc(url: &str) -> Check {
+    let name = "Substrate RPC";
+    let response = http_client()
+        .post(url)
+        .json(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "system_health", "params": [] }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    let body: serde_json::Value = match response {
+        Ok(response) => match response.json().await {
+            Ok(body) => body,
+            Err(e) => {
+                return Check::fail(
+                    name,
+                    e.to_string(),
+                    "Point GRANDPA_RPC_URL to a Substrate RPC",
+                );
+            }
+        },
+        Err(e) => {
+            return Check::fail(
+                name,
+                e.to_string(),
+                "Check GRANDPA_RPC_URL and network access",
+            );
+        }
+    };
+
+    if body["result"]["isSyncing"].as_bool() == Some(false) {
+        Check::pass(
+            name,
+            format!("synced with {} peers", body["result"]["peers"]),
+        )
+    } else {
+        Check::warn(
+            name,
+            "node is syncing",
+            "Wait for the node to sync or use another endpoint",
+        )
+    }
+}
+
+/// Checks that an Esplora instance is reachable
+async fn check_esplora(url: &str) -> Check {
+    let name = "Esplora";
+    let response = http_client()
+        .get(format!("{}/blocks/tip/height", url.trim_end_matches('/')))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    match response {
+        Ok(response) => match response.text().await {
+            Ok(height) => Check::pass(name, format!("tip at height {}", height.trim())),
+            Err(e) => Check::fail(
+                name,
+                e.to_string(),
+                "Point BITCOIN_ESPLORA_URL to an Esplora API",
+            ),
+        },
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "Check BITCOIN_ESPLORA_URL and network access",
+        ),
+    }
+}
+
+/// Checks that an Ethereum execution RPC is reachable
+async fn check_execution_rpc(url: &str) -> Check {
+    let name = "Execution RPC";
+    match ExecutionRpcClient::new(url.to_string())
+        .block_number()
+        .await
+    {
+        Ok(number) => Check::pass(name, format!("at block {}", number)),
+        Err(e) => Check::fail(
+            name,
+            format!("{:#}", e),
+            "Point ETHEREUM_URL to an Ethereum execution RPC",
+        ),
+    }
+}
+
+/// Checks that `SOURCE_CHAIN_ID` selects a supported network with a trusted slot
+fn check_ethereum_network() -> Check {
+    let name = "SOURCE_CHAIN_ID";
+    let network = match EthereumNetwork::from_env() {
+        Ok(network) => network,
+        Err(e) => {
+            return Check::fail(
+                name,
+                e.to_string(),
+                "Set SOURCE_CHAIN_ID to 1, 11155111 or 17000",
+            );
+        }
+    };
+    if let Err(e) = network.helios_config("") {
+        return Check::fail(name, e.to_string(), "Use an Ethereum mainnet or testnet");
+    }
+    match network.trusted_slot() {
+        Ok(slot) => Check::pass(name, format!("{:?}, trusted slot {}", network, slot)),
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "Set HELIOS_TRUSTED_SLOT to a finalized slot",
+        ),
+    }
+}
+
+/// Checks that the state of a chain can be read, returns it if present
+fn check_db(
+    db_path: &str,
+    chain_id: &str,
+) -> std::result::Result<(Check, Option<ServiceState>), Check> {
+    let name = format!("Database ({})", chain_id);
+    if !Path::new(db_path).exists() {
+        return Ok((
+            Check::warn(
+                name,
+                format!("{} does not exist", db_path),
+                "It will be created when the service starts",
+            ),
+            None,
+        ));
+    }
+
+    let state = StateManager::for_chain(Path::new(db_path), chain_id)
+        .and_then(|manager| Ok((manager.load_state()?, manager.schema_version()?)))
+        .map_err(|e| {
+            Check::fail(
+                name.clone(),
+                e.to_string(),
+                "Check the file permissions or restore the database from a backup",
+            )
+        })?;
+    let (state, schema_version) = state;
+    let detail = match &state {
+        Some(state) => format!(
+            "update {} at height {}, schema version {}",
+            state.update_counter, state.trusted_height, schema_version
+        ),
+        None => format!(
+            "no state yet, the service will start from the trusted checkpoint, schema version {}",
+            schema_version
+        ),
+    };
+    Ok((Check::pass(name, detail), state))
+}
+
+/// Checks that the state-diff verification found no committed root orphaned
+fn check_orphaned_roots(db_path: &str) -> Check {
+    let name = "Orphaned roots";
+    if !Path::new(db_path).exists() {
+        return Check::pass(name, "no committed roots yet");
+    }
+    let orphaned = StateManager::for_chain(Path::new(db_path), DEFAULT_CHAIN_ID)
+        .and_then(|manager| manager.load_orphaned_roots(5));
+    match orphaned {
+        Ok(orphaned) if orphaned.is_empty() => {
+            Check::pass(name, "every checked root is on the canonical chain")
+        }
+        Ok(orphaned) => {
+            let updates: Vec<String> = orphaned
+                .iter()
+                .map(|check| format!("{} (height {})", check.update_id, check.height))
+                .collect();
+            Check::fail(
+                name,
+                format!("orphaned: {}", updates.join(", ")),
+                "Compare the execution RPC with the beacon RPC, and stop relaying the orphaned \
+                 roots until the cause is understood",
+            )
+        }
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "Check the file permissions or restore the database from a backup",
+        ),
+    }
+}
+
+/// Checks that the recursive ELF is present, matches this build and the committed vk
+async fn check_elf(
+    mode: &str,
+    elfs_path: &str,
+    elf_name: &str,
+    embedded_elf: &'static [u8],
+    state: Option<&ServiceState>,
+) -> Vec<Check> {
+    let elf = match artifacts::read_artifact(elfs_path, elf_name).await {
+        Ok(elf) => elf,
+        Err(e) => {
+            return vec![Check::fail(
+                format!("ELF {}", elf_name),
+                format!("{:#}", e),
+                "Run `make build-circuits` or fix ELFS_OUT",
+            )];
+        }
+    };
+
+    let mut checks = vec![if elf == embedded_elf {
+        Check::pass(
+            format!("ELF {}", elf_name),
+            "present and matches this build",
+        )
+    } else {
+        Check::warn(
+            format!("ELF {}", elf_name),
+            "differs from the circuit built into this binary",
+            "Rebuild the service after regenerating the circuits",
+        )
+    }];
+
+    // Only compare vks if there is a committed proof to compare against
+    let Some(committed_vk) = state.and_then(|state| recursive_vk(mode, state)) else {
+        return checks;
+    };
+    let vk = tokio::task::spawn_blocking(move || {
+        let (_, vk) = ProverClient::builder().cpu().build().setup(&elf);
+        vk.bytes32()
+    })
+    .await;
+    checks.push(match vk {
+        Ok(vk) if vk == committed_vk => Check::pass(format!("VK {}", elf_name), vk),
+        Ok(vk) => Check::fail(
+            format!("VK {}", elf_name),
+            format!("ELF vk {} but committed proofs use {}", vk, committed_vk),
+            "Restore the ELFs the proof chain was started with, or restart it with `make run`",
+        ),
+        Err(e) => Check::fail(format!("VK {}", elf_name), e.to_string(), "Check the ELF"),
+    });
+    checks
+}
+
+/// The number of failed checks
+pub(crate) fn failed(checks: &[Check]) -> usize {
+    checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count()
+}
+
+pub(crate) fn print_report(title: &str, checks: &[Check]) {
+    println!("\n{}\n", title);
+    for check in checks {
+        let symbol = match check.status {
+            Status::Pass => "✅",
+            Status::Warn => "⚠️ ",
+            Status::Fail => "❌",
+        };
+        println!("{} {}: {}", symbol, check.name, check.detail);
+        if let Some(hint) = &check.hint {
+            println!("   ↳ {}", hint);
+        }
+    }
+    let warned = checks
+        .iter()
+        .filter(|check| check.status == Status::Warn)
+        .count();
+    println!(
+        "\n{} checks, {} failed, {} warnings",
+        checks.len(),
+        failed(checks),
+        warned
+    );
+}
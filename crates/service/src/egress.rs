@@ -0,0 +1,235 @@
+// Outbound connections through proxies, restricted to an egress allow-list.
+//
+// The HTTP clients of the service and of the RPC libraries it uses (beacon, Tendermint,
+// Substrate and Esplora RPCs, webhooks, workers, artifacts) honor the standard proxy variables
+// `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY`, with `http://`, `https://`,
+// `socks5://` and `socks5h://` proxy URLs.
+//
+// `EGRESS_ALLOW_LIST` restricts the hosts the service talks to, comma separated: a host name,
+// a `*.`-prefixed domain matching its subdomains, or either with a `:port`. The service
+// refuses to start if a configured endpoint is not allowed, and the HTTP clients of the
+// service refuse to follow redirects to other hosts.
+
+use anyhow::{Context, Result, anyhow};
+use once_cell::sync::OnceCell;
+use reqwest::{Url, redirect::Policy};
+
+use crate::chains::tendermint_chains;
+
+/// Redirects followed by the HTTP clients, as reqwest does by default
+const MAX_REDIRECTS: usize = 10;
+
+static ALLOW_LIST: OnceCell<Option<EgressAllowList>> = OnceCell::new();
+
+/// A host outbound requests may go to
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AllowedHost {
+    /// The host, or the domain of its subdomains
+    host: String,
+    subdomains: bool,
+    port: Option<u16>,
+}
+
+/// The hosts of `EGRESS_ALLOW_LIST`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EgressAllowList {
+    hosts: Vec<AllowedHost>,
+}
+
+impl EgressAllowList {
+    /// The allow-list of `EGRESS_ALLOW_LIST`, `None` to allow every host
+    pub fn global() -> Result<Option<&'static Self>> {
+        Ok(ALLOW_LIST.get_or_try_init(Self::from_env)?.as_ref())
+    }
+
+    fn from_env() -> Result<Option<Self>> {
+        match std::env::var("EGRESS_ALLOW_LIST") {
+            Ok(allow_list) => allow_list.parse().map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Whether requests to `url` may leave the host
+    pub fn allows(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        self.hosts.iter().any(|allowed| {
+            let host_matches = host == allowed.host
+                || (allowed.subdomains && host.ends_with(&format!(".{}", allowed.host)));
+            host_matches
+                && allowed
+                    .port
+                    .is_none_or(|port| url.port_or_known_default() == Some(port))
+        })
+    }
+}
+
+impl std::str::FromStr for EgressAllowList {
+    type Err = anyhow::Error;
+
+    fn from_str(allow_list: &str) -> Result<Self> {
+        let hosts = allow_list
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (host, port) = match entry.rsplit_once(':') {
+                    Some((host, port)) => (
+                        host,
+                        Some(port.parse().with_context(|| {
+                            format!("Invalid port in EGRESS_ALLOW_LIST entry {}", entry)
+                        })?),
+                    ),
+                    None => (entry, None),
+                };
+                let (host, subdomains) = match host.strip_prefix("*.") {
+                    Some(domain) => (domain, true),
+                    None => (host, false),
+                };
+                if host.is_empty() || host.contains(['/', '*']) {
+                    return Err(anyhow!("Invalid EGRESS_ALLOW_LIST entry {}", entry));
+                }
+                Ok(AllowedHost {
+                    host: host.to_ascii_lowercase(),
+                    subdomains,
+                    port,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if hosts.is_empty() {
+            return Err(anyhow!("EGRESS_ALLOW_LIST allows no hosts"));
+        }
+        Ok(Self { hosts })
+    }
+}
+
+/// A builder of the HTTP clients of the service, which only follow redirects to allowed hosts
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let allow_list = EgressAllowList::global().ok().flatten();
+    reqwest::Client::builder().redirect(Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if allow_list.is_some_and(|allow_list| !allow_list.allows(attempt.url())) {
+            let error = format!("redirect to {} is not in EGRESS_ALLOW_LIST", attempt.url());
+            attempt.error(error)
+        } else {
+            attempt.follow()
+        }
+    }))
+}
+
+/// An HTTP client of the service
+pub fn client() -> reqwest::Client {
+    client_builder()
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// The outbound endpoints of the configuration, with the variable configuring them
+pub fn configured_endpoints() -> Result<Vec<(String, String)>> {
+    let mut endpoints = Vec::new();
+    for var in [
+        "SOURCE_CONSENSUS_RPC_URL",
+        "ETHEREUM_URL",
+        "GRANDPA_RPC_URL",
+        "BITCOIN_ESPLORA_URL",
+        "NETWORK_RPC_URL",
+        "S3_ENDPOINT",
+        "SLO_ALERT_URL",
+        "EVM_RELAYER_RPC_URL",
+        "COSMOS_RELAYER_REST_URL",
+    ] {
+        if let Ok(url) = std::env::var(var) {
+            endpoints.push((var.to_string(), url));
+        }
+    }
+    if let Ok(workers) = std::env::var("PROVER_WORKERS") {
+        endpoints.extend(
+            workers
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(|url| ("PROVER_WORKERS".to_string(), url.to_string())),
+        );
+    }
+    if let Ok(elfs_path) = std::env::var("ELFS_OUT") {
+        if elfs_path.starts_with("http://") || elfs_path.starts_with("https://") {
+            endpoints.push(("ELFS_OUT".to_string(), elfs_path));
+        }
+    }
+    if std::env::var("TENDERMINT_CHAINS_CONFIG").is_ok()
+        || std::env::var("TENDERMINT_RPC_URL").is_ok()
+    {
+        for chain in tendermint_chains()? {
+            endpoints.push((
+                format!("rpc_url of Tendermint chain {}", chain.chain_id),
+                chain.rpc_url,
+            ));
+        }
+    }
+    if let Ok(path) = std::env::var("WEBHOOKS_CONFIG") {
+        let contents = std::fs::read_to_string(&path)
+            .context(format!("Failed to read webhooks config {}", path))?;
+        let webhooks: Vec<serde_json::Value> = serde_json::from_str(&contents)
+            .context(format!("Failed to parse webhooks config {}", path))?;
+        endpoints.extend(webhooks.iter().filter_map(|webhook| {
+            let url = webhook["url"].as_str()?;
+            Some(("WEBHOOKS_CONFIG".to_string(), url.to_string()))
+        }));
+    }
+    Ok(endpoints)
+}
+
+/// The configured endpoints `EGRESS_ALLOW_LIST` doesn't allow, none without an allow-list
+pub fn denied_endpoints() -> Result<Vec<(String, String)>> {
+    let Some(allow_list) = EgressAllowList::global()? else {
+        return Ok(Vec::new());
+    };
+    Ok(configured_endpoints()?
+        .into_iter()
+        .filter(|(_, url)| {
+            Url::parse(url)
+                .map(|url| !allow_list.allows(&url))
+                .unwrap_or(true)
+        })
+        .collect())
+}
+
+/// Fails if a configured endpoint is not in `EGRESS_ALLOW_LIST`
+pub fn check_endpoints() -> Result<()> {
+    let denied = denied_endpoints()?;
+    if denied.is_empty() {
+        return Ok(());
+    }
+    let denied: Vec<String> = denied
+        .into_iter()
+        .map(|(var, url)| format!("{} ({})", url, var))
+        .collect();
+    Err(anyhow!(
+        "Endpoints not in EGRESS_ALLOW_LIST: {}",
+        denied.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow_list() {
+        let allow_list: EgressAllowList = "beacon.internal, *.ntrn.tech, proxy.corp:8443"
+            .parse()
+            .unwrap();
+        let allows = |url: &str| allow_list.allows(&Url::parse(url).unwrap());
+        assert!(allows("http://beacon.internal:5052/eth/v1/node/syncing"));
+        assert!(allows("https://rpc-falcron.pion-1.ntrn.tech"));
+        assert!(allows("https://proxy.corp:8443/rpc"));
+        assert!(!allows("https://proxy.corp/rpc"));
+        assert!(!allows("https://ntrn.tech.evil.com"));
+        assert!(!allows("https://beacon.internal.evil.com"));
+        assert!("".parse::<EgressAllowList>().is_err());
+        assert!("host:port".parse::<EgressAllowList>().is_err());
+    }
+}
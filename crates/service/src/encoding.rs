@@ -0,0 +1,67 @@
+// Canonical encodings of the bytes the service exchanges with the API, the CLI and relayers.
+//
+// Roots, hashes, addresses and keys are written as 0x-prefixed lowercase hex, proofs and
+// public values as standard base64. Inputs are parsed strictly: odd lengths, non-hex digits
+// and unexpected lengths are rejected, and mixed-case hex is accepted with a warning.
+
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+/// Encodes bytes as 0x-prefixed lowercase hex
+pub fn to_hex(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Parses hex with an optional 0x prefix
+pub fn parse_hex(value: &str) -> Result<Vec<u8>> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.len() % 2 != 0 {
+        return Err(anyhow!("Odd number of hex digits in {}", value));
+    }
+    if let Some(invalid) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(anyhow!("Invalid hex digit {:?} in {}", invalid, value));
+    }
+    let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+    if has_upper && has_lower {
+        tracing::warn!("⚠️  Mixed-case hex {}, expected lowercase", value);
+    }
+    Ok(hex::decode(digits)?)
+}
+
+/// Parses hex of exactly `N` bytes with an optional 0x prefix
+pub fn parse_hex_array<const N: usize>(value: &str) -> Result<[u8; N]> {
+    let bytes = parse_hex(value)?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Expected {} bytes of hex, got {} in {}", N, len, value))
+}
+
+/// Encodes bytes as standard base64
+pub fn to_base64(bytes: impl AsRef<[u8]>) -> String {
+    BASE64.encode(bytes)
+}
+
+/// Parses standard base64
+pub fn parse_base64(value: &str) -> Result<Vec<u8>> {
+    BASE64.decode(value).context("Invalid base64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_hex() {
+        assert_eq!(to_hex([0xab, 0x01]), "0xab01");
+        assert_eq!(parse_hex("0xab01").unwrap(), vec![0xab, 0x01]);
+        assert_eq!(parse_hex("AB01").unwrap(), vec![0xab, 0x01]);
+        assert!(parse_hex("0xabc").is_err());
+        assert!(parse_hex("0xzz").is_err());
+        assert!(parse_hex("0x0x00").is_err());
+        assert_eq!(parse_hex_array::<2>("0xAb01").unwrap(), [0xab, 0x01]);
+        assert!(parse_hex_array::<32>("0xab01").is_err());
+        assert_eq!(parse_base64(&to_base64([1, 2, 3])).unwrap(), vec![1, 2, 3]);
+    }
+}
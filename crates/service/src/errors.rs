@@ -0,0 +1,158 @@
+// The errors of the API and the HTTP statuses they map to.
+//
+// Handlers return `Result<_, ApiError>`, and every error is rendered here with its documented
+// status and a JSON body `{"error": <kind>, "message": <message>}`, so that clients can write
+// their retry logic against the status alone:
+//
+// - `400 bad_request`: the request is invalid, retrying it won't help
+// - `404 not_found`: the chain, update or resource doesn't exist
+// - `409 paused`: the chain is disabled, retry once an operator resumes it
+// - `425 pending`: the requested proof is not committed yet, retry later
+// - `503 upstream_unavailable`: an RPC the response depends on failed, retry with backoff
+// - `500 internal`: a bug of the service
+//
+// Errors raised below the API map to `500` unless they are typed: an [`Upstream`] error, or
+// a `reqwest` error anywhere in their chain, maps to `503`.
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+/// An RPC the service depends on failed or returned invalid data
+#[derive(Debug)]
+pub struct Upstream(pub String);
+
+impl std::fmt::Display for Upstream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Upstream {}
+
+/// An error of an API request
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+    Paused(String),
+    Pending(String),
+    Upstream(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+/// The body of an error response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Paused(_) => StatusCode::CONFLICT,
+            Self::Pending(_) => StatusCode::from_u16(425).expect("425 Too Early is a status"),
+            Self::Upstream(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::NotFound(_) => "not_found",
+            Self::Paused(_) => "paused",
+            Self::Pending(_) => "pending",
+            Self::Upstream(_) => "upstream_unavailable",
+            Self::Internal(_) => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadRequest(message)
+            | Self::NotFound(message)
+            | Self::Paused(message)
+            | Self::Pending(message) => write!(f, "{}", message),
+            Self::Upstream(e) | Self::Internal(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        // `downcast_ref` also finds an `Upstream` attached as context, which `chain` doesn't
+        let upstream = e.downcast_ref::<Upstream>().is_some()
+            || e.chain()
+                .any(|cause| cause.is::<Upstream>() || cause.is::<reqwest::Error>());
+        if upstream {
+            Self::Upstream(e)
+        } else {
+            Self::Internal(e)
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Internal(e.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        match &self {
+            Self::Internal(_) => error!("❌ {}", self),
+            Self::Upstream(_) => warn!("⚠️  {}", self),
+            _ => info!("{}", self),
+        }
+        // The causes of a bug are logged, not served
+        let message = match &self {
+            Self::Internal(_) => "Internal error, see the service logs".to_string(),
+            _ => self.to_string(),
+        };
+        let body = ErrorBody {
+            error: self.kind().to_string(),
+            message,
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_errors_map_to_their_status() {
+        let upstream = anyhow::Error::new(Upstream("RPC down".to_string())).context("Fetching");
+        assert_eq!(
+            ApiError::from(upstream).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            ApiError::from(anyhow::anyhow!("bug")).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        let context = anyhow::anyhow!("timeout").context(Upstream("RPC down".to_string()));
+        assert!(matches!(ApiError::from(context), ApiError::Upstream(_)));
+        assert_eq!(
+            ApiError::Pending("proving".to_string()).status().as_u16(),
+            425
+        );
+        assert_eq!(
+            ApiError::Paused("disabled".to_string()).status(),
+            StatusCode::CONFLICT
+        );
+    }
+}
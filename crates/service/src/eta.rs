@@ -0,0 +1,297 @@
+// Estimates of when the next wrapper proof of a proof chain is committed, served by `GET /eta`.
+//
+// The prover loops record the phase their rounds are in: waiting for a new head to prove,
+// proving the base proof, the recursive proof or the wrapper proof. The time left in the
+// current phase is the median duration of the phase over the recent rounds minus the time
+// already spent in it, and the phases after it are added. A chain waiting for a head waits
+// for the finality interval of its backend. When no prover loop of this process proves the
+// chain, as when the API is served by another process, the estimate falls back to the median
+// interval between the recent commits.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{rounds::RoundTimings, state::StateManager};
+
+/// Rounds the per-phase durations are taken from
+const TIMING_SAMPLES: u64 = 20;
+
+/// Seconds of commit history the fallback interval is taken from
+const COMMIT_WINDOW: u64 = 24 * 60 * 60;
+
+/// The phase of the round a prover loop is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// No new head to prove yet
+    Waiting,
+    Base,
+    Recursive,
+    Wrapper,
+}
+
+/// The phases of the rounds in flight of a chain, with the unix time they were entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundProgress {
+    /// The round the front stage of the pipeline is proving
+    pub front: (Phase, u64),
+    /// When the wrapper proof in flight started, if any
+    pub wrapper: Option<u64>,
+}
+
+/// The rounds in flight of the prover loops of this process, by chain id
+static PROGRESS: Mutex<BTreeMap<String, RoundProgress>> = Mutex::new(BTreeMap::new());
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+/// Records that the proof chain `chain_id` entered `phase`. The wrapper proof runs next to the
+/// front stage, it is tracked apart until [`wrapper_done`]
+pub fn enter_phase(chain_id: &str, phase: Phase) {
+    let now = now();
+    let mut progress = PROGRESS.lock().unwrap();
+    let progress = progress
+        .entry(chain_id.to_string())
+        .or_insert(RoundProgress {
+            front: (Phase::Waiting, now),
+            wrapper: None,
+        });
+    match phase {
+        Phase::Wrapper => progress.wrapper = Some(now),
+        _ if progress.front.0 != phase => progress.front = (phase, now),
+        _ => {}
+    }
+}
+
+/// Records that the wrapper proof in flight of the proof chain `chain_id` was committed or
+/// dropped
+pub fn wrapper_done(chain_id: &str) {
+    if let Some(progress) = PROGRESS.lock().unwrap().get_mut(chain_id) {
+        progress.wrapper = None;
+    }
+}
+
+/// Forgets the rounds of a proof chain whose prover loop stopped
+pub fn clear(chain_id: &str) {
+    PROGRESS.lock().unwrap().remove(chain_id);
+}
+
+/// The rounds in flight of the proof chain `chain_id`, `None` unless a prover loop of this
+/// process proves it
+pub fn progress(chain_id: &str) -> Option<RoundProgress> {
+    PROGRESS.lock().unwrap().get(chain_id).copied()
+}
+
+/// Typical seconds between two heads a backend can prove
+pub fn finality_interval(mode: &str) -> u64 {
+    match mode {
+        // A finalized checkpoint per epoch of 32 slots of 12 seconds
+        "HELIOS" => 384,
+        // A block every 10 minutes on average
+        "BITCOIN" => 600,
+        // Blocks of a few seconds, final once committed or shortly after
+        _ => 6,
+    }
+}
+
+/// Median seconds of each phase over the recent rounds of a chain
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseDurations {
+    pub finality_secs: u64,
+    pub base_secs: u64,
+    pub recursive_secs: u64,
+    pub wrapper_secs: u64,
+    /// Rounds the durations are the median of, none before the first committed round
+    pub samples: usize,
+}
+
+impl PhaseDurations {
+    pub fn from_timings(finality_secs: u64, timings: &[RoundTimings]) -> Self {
+        let median_secs = |ms: fn(&RoundTimings) -> u64| {
+            median(timings.iter().map(ms).collect()).map_or(0, |ms| ms.div_ceil(1000))
+        };
+        Self {
+            finality_secs,
+            base_secs: median_secs(|timings| timings.base_ms),
+            recursive_secs: median_secs(|timings| timings.recursive_ms),
+            wrapper_secs: median_secs(|timings| timings.wrapper_ms),
+            samples: timings.len(),
+        }
+    }
+
+    /// Seconds left until the wrapper proof following the rounds in flight is done, at `now`
+    pub fn remaining(&self, progress: &RoundProgress, now: u64) -> u64 {
+        let left = |secs: u64, since: u64| secs.saturating_sub(now.saturating_sub(since));
+        if let Some(since) = progress.wrapper {
+            return left(self.wrapper_secs, since);
+        }
+        let (phase, since) = progress.front;
+        match phase {
+            Phase::Waiting => {
+                left(self.finality_secs, since)
+                    + self.base_secs
+                    + self.recursive_secs
+                    + self.wrapper_secs
+            }
+            Phase::Base => left(self.base_secs, since) + self.recursive_secs + self.wrapper_secs,
+            Phase::Recursive => left(self.recursive_secs, since) + self.wrapper_secs,
+            Phase::Wrapper => left(self.wrapper_secs, since),
+        }
+    }
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied()
+}
+
+/// What an estimate is based on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EtaBasis {
+    /// The phase of the rounds in flight and the per-phase durations
+    Phase,
+    /// The median interval between the recent commits
+    History,
+}
+
+/// When the next wrapper proof of a proof chain is expected
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EtaResponse {
+    pub chain_id: String,
+    /// The update the next wrapper proof commits
+    pub next_update: u64,
+    /// The phase of the round ahead, `None` unless a prover loop of the serving process
+    /// proves the chain
+    pub phase: Option<Phase>,
+    /// Seconds the round ahead spent in its phase
+    pub phase_elapsed_secs: Option<u64>,
+    pub durations: PhaseDurations,
+    /// Median seconds between the commits of the last day
+    pub commit_interval_secs: Option<u64>,
+    /// Seconds until the next wrapper proof is expected, `None` without history to estimate
+    /// from
+    pub eta_secs: Option<u64>,
+    /// Unix timestamp (seconds) at which the next wrapper proof is expected
+    pub expected_at: Option<u64>,
+    pub basis: Option<EtaBasis>,
+}
+
+impl EtaResponse {
+    /// Estimates the next wrapper proof of the chain of `state_manager` proven by a `mode`
+    /// backend, `None` if it has no state yet
+    pub fn load(state_manager: &StateManager, mode: &str) -> Result<Option<Self>> {
+        let Some(state) = state_manager.load_state()? else {
+            return Ok(None);
+        };
+        let now = now();
+        let durations = PhaseDurations::from_timings(
+            finality_interval(mode),
+            &state_manager.load_recent_round_timings(TIMING_SAMPLES)?,
+        );
+        let commits = state_manager.load_commit_times(now.saturating_sub(COMMIT_WINDOW))?;
+        let commit_interval = median(
+            commits
+                .iter()
+                .zip(commits.iter().skip(1))
+                .map(|(previous, next)| next - previous)
+                .collect(),
+        );
+        let progress = progress(state_manager.chain_id());
+
+        let (eta_secs, basis) = match (&progress, commit_interval) {
+            (Some(progress), _) if durations.samples > 0 => (
+                Some(durations.remaining(progress, now)),
+                Some(EtaBasis::Phase),
+            ),
+            (_, Some(interval)) => {
+                let last_commit = commits.last().copied().unwrap_or(now);
+                (
+                    Some((last_commit + interval).saturating_sub(now)),
+                    Some(EtaBasis::History),
+                )
+            }
+            _ => (None, None),
+        };
+        // The phase of the round committed next
+        let phase = progress.map(|progress| match progress.wrapper {
+            Some(since) => (Phase::Wrapper, since),
+            None => progress.front,
+        });
+        Ok(Some(Self {
+            chain_id: state_manager.chain_id().to_string(),
+            next_update: state.update_counter + 1,
+            phase: phase.map(|(phase, _)| phase),
+            phase_elapsed_secs: phase.map(|(_, since)| now.saturating_sub(since)),
+            durations,
+            commit_interval_secs: commit_interval,
+            eta_secs,
+            expected_at: eta_secs.map(|eta| now + eta),
+            basis,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining() {
+        let timings = [
+            RoundTimings {
+                base_ms: 30_000,
+                recursive_ms: 20_000,
+                wrapper_ms: 100_000,
+            },
+            RoundTimings {
+                base_ms: 50_000,
+                recursive_ms: 40_000,
+                wrapper_ms: 120_000,
+            },
+            RoundTimings {
+                base_ms: 40_000,
+                recursive_ms: 30_000,
+                wrapper_ms: 110_000,
+            },
+        ];
+        let durations = PhaseDurations::from_timings(384, &timings);
+        assert_eq!(
+            (
+                durations.base_secs,
+                durations.recursive_secs,
+                durations.wrapper_secs
+            ),
+            (40, 30, 110)
+        );
+
+        let waiting = RoundProgress {
+            front: (Phase::Waiting, 1000),
+            wrapper: None,
+        };
+        assert_eq!(durations.remaining(&waiting, 1084), 300 + 40 + 30 + 110);
+        let recursive = RoundProgress {
+            front: (Phase::Recursive, 1000),
+            wrapper: None,
+        };
+        assert_eq!(durations.remaining(&recursive, 1010), 20 + 110);
+        // An overdue phase is expected to end any moment
+        assert_eq!(durations.remaining(&recursive, 2000), 110);
+
+        // The wrapper proof in flight is committed before the round of the front stage
+        let pipelined = RoundProgress {
+            front: (Phase::Base, 1000),
+            wrapper: Some(990),
+        };
+        assert_eq!(durations.remaining(&pipelined, 1050), 50);
+    }
+}
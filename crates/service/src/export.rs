@@ -0,0 +1,231 @@
+// Export of committed wrapper proofs for manual relays, behind `export-proof`.
+//
+// Reads the latest wrapper proof of a chain from the state database, or a historical one
+// from the proof archive, and writes it in the format the destination expects:
+// - `json`: the `GET /` response
+// - `bundle`: the self-contained archive bundle
+// - `evm-calldata`: `verifyProof(programVKey, publicValues, proofBytes)` calldata for the SP1
+//   verifier gateway
+// - `cosmwasm-msg`: the `verify_proof` execute message of the CosmWasm light client
+// - `base64`: the proof bytes and the public values, one per line
+
+use alloy_sol_types::{SolCall, sol};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+use sp1_sdk::{HashableKey, ProverClient};
+use std::path::Path;
+
+use crate::{
+    api::{ProofResponse, committed_vk},
+    archive::{self, ProofArchive, ProofBundle},
+    commands::wrapper_elf,
+    encoding::{parse_base64, to_base64, to_hex},
+    state::StateManager,
+};
+
+sol! {
+    function verifyProof(bytes32 programVKey, bytes publicValues, bytes proofBytes) external view;
+}
+
+/// The formats `export-proof` writes wrapper proofs in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    Json,
+    Bundle,
+    EvmCalldata,
+    CosmwasmMsg,
+    Base64,
+}
+
+/// Loads the bundle of update `update` of a chain, the latest committed update by default
+pub fn load_bundle(
+    db_path: &Path,
+    chain_id: &str,
+    mode: &str,
+    update: Option<u64>,
+) -> Result<ProofBundle> {
+    let state_manager = StateManager::for_chain(db_path, chain_id)?;
+    let state = state_manager
+        .load_state()?
+        .context(format!("No state found for chain {}", chain_id))?;
+
+    // Only the latest proofs are kept in the database, older ones live in the archive
+    if let Some(update) = update.filter(|update| *update != state.update_counter) {
+        let path = ProofArchive::from_env()?
+            .bundle_path(chain_id, update)
+            .context(format!(
+                "Update {} of {} is not in the proof archive, set PROOF_ARCHIVE_DIR",
+                update, chain_id
+            ))?;
+        let bundle = std::fs::read(&path)
+            .context(format!("Failed to read proof bundle {}", path.display()))?;
+        return Ok(serde_json::from_slice(&bundle)?);
+    }
+
+    let record = state_manager
+        .load_history(state.update_counter, 1)?
+        .into_iter()
+        .find(|record| record.update_counter == state.update_counter)
+        .context(format!(
+            "No history record for update {} of {}",
+            state.update_counter, chain_id
+        ))?;
+    archive::bundle(chain_id, mode, record.into(), &state)
+}
+
+/// Encodes a bundle in `format`
+pub fn encode(bundle: &ProofBundle, format: ProofFormat) -> Result<String> {
+    let proof = parse_base64(&bundle.proof_b64)?;
+    let public_values = parse_base64(&bundle.public_values_b64)?;
+    Ok(match format {
+        ProofFormat::Json => {
+            let recursive_public_values = bundle
+                .recursive_public_values_b64
+                .as_deref()
+                .map(parse_base64)
+                .transpose()?;
+            let response = ProofResponse {
+                vk: recursive_public_values
+                    .and_then(|public_values| committed_vk(&bundle.mode, &public_values))
+                    .unwrap_or_default(),
+                mode: bundle.mode.clone(),
+                height: bundle.record.height,
+                root: bundle.record.root.clone(),
+                proof_b64: bundle.proof_b64.clone(),
+                public_values_b64: bundle.public_values_b64.clone(),
+                generated_at: bundle.record.committed_at,
+                update_id: bundle.record.update_id.clone(),
+            };
+            serde_json::to_string_pretty(&response)?
+        }
+        ProofFormat::Bundle => serde_json::to_string_pretty(bundle)?,
+        ProofFormat::EvmCalldata => {
+            let call = verifyProofCall {
+                programVKey: wrapper_vk(&bundle.chain_id, &bundle.mode).into(),
+                publicValues: public_values.into(),
+                proofBytes: proof.into(),
+            };
+            to_hex(call.abi_encode())
+        }
+        ProofFormat::CosmwasmMsg => serde_json::to_string_pretty(&cosmwasm_msg(
+            bundle.record.height,
+            &bundle.record.root,
+            &bundle.proof_b64,
+            &bundle.public_values_b64,
+        ))?,
+        ProofFormat::Base64 => format!("{}\n{}", bundle.proof_b64, bundle.public_values_b64),
+    })
+}
+
+/// The `verify_proof` execute message of the `lightwave-cosmwasm` contract, the proof and the
+/// public values base64 encoded
+pub fn cosmwasm_msg(
+    height: u64,
+    root: &str,
+    proof_b64: &str,
+    public_values_b64: &str,
+) -> serde_json::Value {
+    json!({
+        "verify_proof": {
+            "height": height,
+            "root": root,
+            "proof": proof_b64,
+            "public_values": public_values_b64,
+        }
+    })
+}
+
+/// An IBC `MsgUpdateClient` of an 08-wasm light client, whose `ClientMessage` data is the
+/// JSON encoded wrapper proof and public values, base64 encoded. The relayer signs it
+pub fn ibc_update_msg(
+    client_id: &str,
+    proof_b64: &str,
+    public_values_b64: &str,
+) -> serde_json::Value {
+    let data = json!({
+        "proof": proof_b64,
+        "public_values": public_values_b64,
+    });
+    json!({
+        "@type": "/ibc.core.client.v1.MsgUpdateClient",
+        "client_id": client_id,
+        "client_message": {
+            "@type": "/ibc.lightclients.wasm.v1.ClientMessage",
+            "data": to_base64(data.to_string()),
+        },
+        "signer": "",
+    })
+}
+
+/// The IBC revision number of a Tendermint chain id, the number following its last dash
+/// (`pion-1`), zero for chain ids not in the revision format
+pub fn revision_number(chain_id: &str) -> u64 {
+    chain_id
+        .rsplit_once('-')
+        .and_then(|(_, revision)| revision.parse().ok())
+        .unwrap_or(0)
+}
+
+/// The vk of the circuit the wrapper proofs of a chain are verified against on chain
+fn wrapper_vk(chain_id: &str, mode: &str) -> [u8; 32] {
+    let client = ProverClient::builder().cpu().build();
+    let (_, vk) = client.setup(wrapper_elf(chain_id, mode));
+    vk.bytes32_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::HistoryRecord;
+
+    #[test]
+    fn test_encode_relay_formats() {
+        let bundle = ProofBundle {
+            chain_id: "test-chain".to_string(),
+            mode: "TENDERMINT".to_string(),
+            record: HistoryRecord {
+                update_counter: 3,
+                update_id: "tendermint:test-chain:3".to_string(),
+                slot: 0,
+                height: 42,
+                root: to_hex([1; 32]),
+                proof_hash: to_hex([2; 32]),
+                prev_hash: to_hex([3; 32]),
+                hash: to_hex([4; 32]),
+                committed_at: Some(1_700_000_000),
+            },
+            proof_b64: to_base64([5, 6]),
+            public_values_b64: to_base64([7]),
+            recursive_proof_b64: None,
+            recursive_public_values_b64: None,
+        };
+
+        let response: ProofResponse =
+            serde_json::from_str(&encode(&bundle, ProofFormat::Json).unwrap()).unwrap();
+        assert_eq!(response.height, 42);
+        assert_eq!(response.proof_b64, bundle.proof_b64);
+        assert_eq!(response.vk, "");
+
+        let msg: serde_json::Value =
+            serde_json::from_str(&encode(&bundle, ProofFormat::CosmwasmMsg).unwrap()).unwrap();
+        assert_eq!(
+            msg["verify_proof"]["public_values"],
+            bundle.public_values_b64
+        );
+
+        assert_eq!(
+            encode(&bundle, ProofFormat::Base64).unwrap(),
+            format!("{}\n{}", bundle.proof_b64, bundle.public_values_b64)
+        );
+
+        let msg = ibc_update_msg("08-wasm-0", &bundle.proof_b64, &bundle.public_values_b64);
+        let data = parse_base64(msg["client_message"]["data"].as_str().unwrap()).unwrap();
+        let data: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(data["proof"], bundle.proof_b64);
+        assert_eq!(
+            (revision_number("pion-1"), revision_number("osmosis")),
+            (1, 0)
+        );
+    }
+}
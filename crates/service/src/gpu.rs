@@ -0,0 +1,397 @@
+// Management of the local GPU prover containers.
+//
+// With `SP1_PROVER=cuda` the SDK proves in a container it starts on first use. A container
+// left over by a crashed or concurrent prover holds the GPU and fails the next proof, so the
+// managed containers are removed before every local proof. `GPU_CONTAINER_MANAGER` selects how:
+// - `docker`: through the Docker Engine API at `DOCKER_HOST`, which rootless docker and podman
+//   (`unix:///run/user/<uid>/podman/podman.sock`) serve as well. The default with CUDA
+// - `none`: containers are never touched, e.g. on Kubernetes where the pod owns the GPU. The
+//   default for the other provers
+//
+// `GPU_CONTAINER_NAMES` lists the managed containers, comma separated, `sp1-gpu` by default.
+//
+// Several GPUs prove concurrently when each runs its own prover container, started by the
+// operator (e.g. `docker run --gpus device=1 -p 3001:3000 ...`) and listed in `GPU_DEVICES` as
+// comma separated `name=endpoint` pairs. Every GPU is a proving slot, so the base, recursive and
+// wrapper proofs of the pipeline run on different GPUs. The containers of configured GPUs are
+// long-lived and never removed by default.
+//
+// The proofs of every configured GPU are tracked: a GPU failing `GPU_MAX_FAILURES` proofs in a
+// row (3 by default) is excluded from the proving slots for `GPU_EXCLUSION_SECS` (10 minutes by
+// default), then proves again. A GPU that fails its first proof after its exclusion is
+// excluded again right away.
+
+use anyhow::{Result, anyhow};
+use bollard::{
+    Docker,
+    container::{InspectContainerOptions, RemoveContainerOptions},
+    errors::Error as DockerError,
+    models::HealthStatusEnum,
+};
+use once_cell::sync::OnceCell;
+use sp1_sdk::{
+    CudaProver, EnvProver, ProverClient, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+    SP1Stdin,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The container the SP1 SDK runs the CUDA prover in
+const DEFAULT_CONTAINER: &str = "sp1-gpu";
+
+/// Default consecutive failed proofs excluding a GPU
+const DEFAULT_MAX_FAILURES: u32 = 3;
+
+/// Default exclusion of a failing GPU, in seconds
+const DEFAULT_EXCLUSION_SECS: u64 = 600;
+
+static MANAGER: OnceCell<GpuManager> = OnceCell::new();
+
+static HEALTH: OnceCell<GpuHealth> = OnceCell::new();
+
+tokio::task_local! {
+    /// The GPU of the slot held by the running proving step
+    static DEVICE: Option<GpuDevice>;
+}
+
+/// A GPU proving through its own prover container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuDevice {
+    pub name: String,
+    /// The endpoint of the prover server of the container
+    pub endpoint: String,
+}
+
+impl GpuDevice {
+    /// The GPUs configured with `GPU_DEVICES`, e.g.
+    /// `gpu0=http://127.0.0.1:3000,gpu1=http://127.0.0.1:3001`
+    pub fn from_env() -> Result<Vec<Self>> {
+        let Ok(devices) = std::env::var("GPU_DEVICES") else {
+            return Ok(Vec::new());
+        };
+        devices
+            .split(',')
+            .map(str::trim)
+            .filter(|device| !device.is_empty())
+            .map(|device| {
+                let (name, endpoint) = device.split_once('=').ok_or_else(|| {
+                    anyhow!(
+                        "Invalid GPU_DEVICES entry {}, expected name=endpoint",
+                        device
+                    )
+                })?;
+                Ok(Self {
+                    name: name.trim().to_string(),
+                    endpoint: endpoint.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Runs a proving step holding the slot of `device`, the provers it creates with
+/// [`LocalProver::current`] prove on that GPU
+pub async fn with_device<F: Future>(device: Option<GpuDevice>, step: F) -> F::Output {
+    DEVICE.scope(device, step).await
+}
+
+/// The proofs of a GPU since its last successful one
+#[derive(Debug, Clone, Default)]
+struct DeviceHealth {
+    consecutive_failures: u32,
+    last_failure: Option<Instant>,
+}
+
+/// Tracks the failed proofs of the configured GPUs
+pub struct GpuHealth {
+    max_failures: u32,
+    exclusion: Duration,
+    devices: Mutex<HashMap<String, DeviceHealth>>,
+}
+
+impl GpuHealth {
+    pub fn new(max_failures: u32, exclusion: Duration) -> Self {
+        Self {
+            max_failures,
+            exclusion,
+            devices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The health of the GPUs of the process, configured by `GPU_MAX_FAILURES` and
+    /// `GPU_EXCLUSION_SECS`
+    pub fn global() -> Result<&'static Self> {
+        HEALTH.get_or_try_init(Self::from_env)
+    }
+
+    fn from_env() -> Result<Self> {
+        let max_failures = match std::env::var("GPU_MAX_FAILURES") {
+            Ok(max_failures) => max_failures
+                .parse()
+                .ok()
+                .filter(|max_failures| *max_failures > 0)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Invalid GPU_MAX_FAILURES {}, expected at least 1",
+                        max_failures
+                    )
+                })?,
+            Err(_) => DEFAULT_MAX_FAILURES,
+        };
+        let exclusion_secs = match std::env::var("GPU_EXCLUSION_SECS") {
+            Ok(secs) => secs
+                .parse()
+                .map_err(|_| anyhow!("Invalid GPU_EXCLUSION_SECS {}", secs))?,
+            Err(_) => DEFAULT_EXCLUSION_SECS,
+        };
+        Ok(Self::new(max_failures, Duration::from_secs(exclusion_secs)))
+    }
+
+    /// Records the outcome of a proof on `device`
+    pub fn record(&self, device: &GpuDevice, success: bool) {
+        let mut devices = self.devices.lock().unwrap();
+        let health = devices.entry(device.name.clone()).or_default();
+        if success {
+            *health = DeviceHealth::default();
+            return;
+        }
+        health.consecutive_failures += 1;
+        health.last_failure = Some(Instant::now());
+        tracing::warn!(
+            "⚠️  GPU {} failed {} proof(s) in a row",
+            device.name,
+            health.consecutive_failures
+        );
+    }
+
+    /// How much longer `device` is excluded from proving, `None` if it may prove
+    pub fn exclusion(&self, device: &GpuDevice) -> Option<Duration> {
+        let devices = self.devices.lock().unwrap();
+        let health = devices.get(&device.name)?;
+        if health.consecutive_failures < self.max_failures {
+            return None;
+        }
+        let excluded_until = health.last_failure? + self.exclusion;
+        excluded_until
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// A local SP1 prover, on a configured GPU or the one selected by `SP1_PROVER`
+pub enum LocalProver {
+    Env(EnvProver),
+    Device(GpuDevice, CudaProver),
+}
+
+impl LocalProver {
+    pub fn new(device: Option<&GpuDevice>) -> Self {
+        match device {
+            Some(device) => Self::Device(
+                device.clone(),
+                ProverClient::builder()
+                    .cuda()
+                    .server(&device.endpoint)
+                    .build(),
+            ),
+            None => Self::Env(ProverClient::from_env()),
+        }
+    }
+
+    /// The prover of the GPU held by the running proving step, see [`with_device`]
+    pub fn current() -> Self {
+        DEVICE
+            .try_with(|device| Self::new(device.as_ref()))
+            .unwrap_or_else(|_| Self::new(None))
+    }
+
+    /// Generates a Groth16 proof, blocking until it is done
+    pub fn prove_groth16(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+    ) -> Result<SP1ProofWithPublicValues> {
+        self.prove(pk, stdin, SP1ProofMode::Groth16)
+    }
+
+    /// Generates a proof of `mode`, blocking until it is done
+    pub fn prove(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<SP1ProofWithPublicValues> {
+        match self {
+            Self::Env(client) => client.prove(pk, stdin).mode(mode).run(),
+            Self::Device(device, client) => {
+                // The SDK panics on some failures of the prover server
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    client.prove(pk, stdin).mode(mode).run()
+                }));
+                if let Ok(health) = GpuHealth::global() {
+                    health.record(device, matches!(result, Ok(Ok(_))));
+                }
+                result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            }
+        }
+    }
+}
+
+/// Removes the managed GPU containers before a local proof. Failures are logged, a container
+/// that can't be removed fails the proof with a clearer error than this cleanup could give
+pub async fn cleanup_gpu_containers() -> Result<()> {
+    MANAGER
+        .get_or_try_init(GpuManager::from_env)?
+        .cleanup()
+        .await;
+    Ok(())
+}
+
+/// How the local GPU prover containers are managed
+pub enum GpuManager {
+    Docker {
+        docker: Docker,
+        containers: Vec<String>,
+    },
+    /// Leaves the containers alone
+    None,
+}
+
+/// The state of a managed container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerHealth {
+    /// Not created, the SDK starts it with the next proof
+    Absent,
+    Running,
+    /// Running with a failing health check
+    Unhealthy,
+    /// Created but not running, e.g. exited or paused
+    Stopped(String),
+}
+
+impl GpuManager {
+    pub fn from_env() -> Result<Self> {
+        // The containers of configured GPUs are left running
+        let cuda = std::env::var("SP1_PROVER").as_deref() == Ok("cuda")
+            && std::env::var("GPU_DEVICES").is_err();
+        match std::env::var("GPU_CONTAINER_MANAGER").as_deref() {
+            Ok("docker") => {}
+            Err(_) if cuda => {}
+            Err(_) | Ok("none") => return Ok(Self::None),
+            Ok(manager) => {
+                return Err(anyhow!(
+                    "Invalid GPU_CONTAINER_MANAGER {}, expected docker or none",
+                    manager
+                ));
+            }
+        }
+
+        let containers = match std::env::var("GPU_CONTAINER_NAMES") {
+            Ok(names) => names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => vec![DEFAULT_CONTAINER.to_string()],
+        };
+        let docker = Docker::connect_with_defaults()
+            .map_err(|e| anyhow!("Failed to connect to the docker daemon: {}", e))?;
+        Ok(Self::Docker { docker, containers })
+    }
+
+    /// Force-removes the managed containers, those that don't exist are skipped
+    pub async fn cleanup(&self) {
+        let Self::Docker { docker, containers } = self else {
+            return;
+        };
+        tracing::debug!("🧹 Removing GPU containers {:?}", containers);
+        for name in containers {
+            let options = RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            };
+            match docker.remove_container(name, Some(options)).await {
+                Ok(()) => tracing::info!("🧹 Removed GPU container {}", name),
+                Err(DockerError::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {}
+                Err(e) => tracing::warn!("⚠️  Failed to remove container {}: {}", name, e),
+            }
+        }
+    }
+
+    /// Checks the daemon is reachable and returns the state of every managed container
+    pub async fn health(&self) -> Result<Vec<(String, ContainerHealth)>> {
+        let Self::Docker { docker, containers } = self else {
+            return Ok(Vec::new());
+        };
+        docker
+            .ping()
+            .await
+            .map_err(|e| anyhow!("Docker daemon unreachable: {}", e))?;
+
+        let mut health = Vec::new();
+        for name in containers {
+            let container = match docker
+                .inspect_container(name, None::<InspectContainerOptions>)
+                .await
+            {
+                Ok(container) => container,
+                Err(DockerError::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {
+                    health.push((name.clone(), ContainerHealth::Absent));
+                    continue;
+                }
+                Err(e) => return Err(anyhow!("Failed to inspect container {}: {}", name, e)),
+            };
+            let state = container.state.unwrap_or_default();
+            let status = if state.running != Some(true) {
+                ContainerHealth::Stopped(
+                    state
+                        .status
+                        .map_or_else(|| "unknown".to_string(), |status| status.to_string()),
+                )
+            } else if state.health.and_then(|health| health.status)
+                == Some(HealthStatusEnum::UNHEALTHY)
+            {
+                ContainerHealth::Unhealthy
+            } else {
+                ContainerHealth::Running
+            };
+            health.push((name.clone(), status));
+        }
+        Ok(health)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_gpu_is_excluded() {
+        let health = GpuHealth::new(2, Duration::from_secs(60));
+        let device = GpuDevice {
+            name: "gpu0".to_string(),
+            endpoint: "http://127.0.0.1:3000".to_string(),
+        };
+        health.record(&device, false);
+        assert_eq!(health.exclusion(&device), None);
+        health.record(&device, false);
+        assert!(health.exclusion(&device).is_some());
+
+        // A successful proof, e.g. after the exclusion, clears the failures
+        health.record(&device, true);
+        assert_eq!(health.exclusion(&device), None);
+
+        // The exclusion ends
+        let health = GpuHealth::new(1, Duration::ZERO);
+        health.record(&device, false);
+        assert_eq!(health.exclusion(&device), None);
+    }
+}
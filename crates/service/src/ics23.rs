@@ -0,0 +1,185 @@
+// Values of a Cosmos SDK store anchored to the Tendermint proof chain.
+//
+// After every Tendermint update, the ICS23 stage fetches the header of the proven block and the
+// ICS23 proofs of the configured key against its app hash, and proves them with the ICS23 proof
+// circuit. The proofs are committed to their own state row, `ICS23_CHAIN_ID`.
+
+use anyhow::{Context, Result};
+use ics23_proof_types::{Ics23CircuitInputs, verify_store_query};
+use sp1_sdk::SP1ProvingKey;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    archive::ProofArchive,
+    chains::tendermint_chain,
+    control::Stop,
+    encoding::{parse_hex, to_hex},
+    preprocessor::ics23::CometRpcClient,
+    prover::prove_anchored,
+    relayers::Relayers,
+    slots::ChainSlots,
+    state::{DEFAULT_CHAIN_ID, DbPool, StateManager},
+    webhooks::Webhooks,
+};
+
+/// The namespace of the state row holding the ICS23 proofs
+pub const ICS23_CHAIN_ID: &str = "ics23";
+
+/// Seconds between two checks for a new Tendermint update
+const POLL_INTERVAL: u64 = 60;
+
+/// The store key that is proven, and the Tendermint chain it is anchored to
+#[derive(Debug, Clone)]
+pub struct Ics23Config {
+    /// The Tendermint chain whose proof chain the values are anchored to
+    pub chain_id: String,
+    /// CometBFT RPC of the chain the proofs are fetched from
+    pub rpc_url: String,
+    pub store_key: String,
+    pub key: Vec<u8>,
+}
+
+impl Ics23Config {
+    /// Reads `ICS23_STORE_KEY` and the hex encoded `ICS23_KEY`, proven on the Tendermint chain
+    /// `ICS23_CHAIN_ID` (defaults to the single configured chain). Returns `None` if no store
+    /// key is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(store_key) = std::env::var("ICS23_STORE_KEY") else {
+            return Ok(None);
+        };
+        let key =
+            std::env::var("ICS23_KEY").context("ICS23_KEY must be set to prove a store value")?;
+        let key = parse_hex(&key).context(format!("Invalid ICS23_KEY {}", key))?;
+        let chain_id =
+            std::env::var("ICS23_CHAIN_ID").unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string());
+        let chain = tendermint_chain(&chain_id)?;
+        Ok(Some(Self {
+            chain_id,
+            rpc_url: chain.rpc_url,
+            store_key,
+            key,
+        }))
+    }
+}
+
+/// Proves the configured store value at every new Tendermint update, until `stop` is requested
+pub async fn run_ics23_loop(
+    db: DbPool,
+    config: Ics23Config,
+    elf: Vec<u8>,
+    slots: ChainSlots,
+    mut stop: Stop,
+) -> Result<()> {
+    let rpc = CometRpcClient::new(config.rpc_url.clone());
+    let mut pk: Option<SP1ProvingKey> = None;
+    let webhooks = Webhooks::from_env()?;
+    let relayers = Relayers::from_env()?;
+    let archive = ProofArchive::from_env()?;
+    tracing::info!("🚀 Starting ICS23 proof service loop...");
+
+    loop {
+        if stop.requested() {
+            tracing::info!("⏸️  Stopping the ICS23 proof loop");
+            return Ok(());
+        }
+        let tendermint_state = StateManager::from_pool(&db, &config.chain_id)?.load_state()?;
+        let Some((tendermint_proof, height)) = tendermint_state.and_then(|state| {
+            let proof = state.most_recent_wrapper_proof?;
+            Some((proof, state.trusted_height))
+        }) else {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        };
+
+        let state_manager = StateManager::from_pool(&db, ICS23_CHAIN_ID)?;
+        let mut service_state = match state_manager.load_state()? {
+            Some(state) => state,
+            None => state_manager.initialize_state(0, 0)?,
+        };
+        if height <= service_state.trusted_height {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        // The app hash of the proven header commits the state after the previous block
+        tracing::info!(
+            "🌌 Fetching ICS23 proofs of {}/{} at height {}...",
+            config.store_key,
+            to_hex(&config.key),
+            height
+        );
+        let fetched = async {
+            let header = rpc.header(height).await?;
+            let app_hash = header["app_hash"]
+                .as_str()
+                .and_then(|app_hash| hex::decode(app_hash).ok())
+                .context("Missing app hash in header")?;
+            let query = rpc
+                .store_query(&config.store_key, &config.key, height - 1)
+                .await?;
+            anyhow::Ok((header, app_hash, query))
+        };
+        let (header, app_hash, query) = match fetched.await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                tracing::warn!("⚠️  Failed to fetch ICS23 proofs: {}", e);
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        // Check the proofs before proving, a mismatch means the RPC serves another chain
+        let value_hash = match verify_store_query(&app_hash, &query) {
+            Ok(value_hash) => value_hash,
+            Err(e) => {
+                tracing::warn!(
+                    "⚠️  ICS23 proofs do not verify against the app hash: {:?}",
+                    e
+                );
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        let inputs = Ics23CircuitInputs {
+            tendermint_proof: tendermint_proof.bytes(),
+            tendermint_public_values: tendermint_proof.public_values.to_vec(),
+            header: serde_json::to_vec(&header)?,
+            query,
+        };
+        tracing::info!("⚡ Generating ICS23 proof at height {}...", height);
+        let proof = match prove_anchored("ICS23", &elf, &mut pk, &slots, &inputs).await {
+            Ok(proof) => proof,
+            Err(e) => {
+                tracing::error!("❌ {}", e);
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        service_state.trusted_slot = height;
+        service_state.trusted_height = height;
+        service_state.trusted_root = value_hash;
+        service_state.most_recent_wrapper_proof = Some(proof);
+        service_state.update_counter += 1;
+        service_state.generated_at = Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("System clock is before the unix epoch")?
+                .as_secs(),
+        );
+        let record = state_manager.commit_update("ICS23", &service_state, None, None, &[])?;
+        tracing::info!(
+            "✅ Update {}: Value of {}/{} at height {} committed - Value hash: {}",
+            record.update_id,
+            config.store_key,
+            to_hex(&config.key),
+            height,
+            to_hex(value_hash)
+        );
+        tracing::info!("🔗 Update history hash: {}", to_hex(record.hash));
+        webhooks.notify(ICS23_CHAIN_ID, "ICS23", &record);
+        relayers.relay(ICS23_CHAIN_ID, "ICS23", &record, &service_state);
+        archive.export_latest(&state_manager, "ICS23");
+    }
+}
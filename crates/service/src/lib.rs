@@ -0,0 +1,67 @@
+// The light client service as a library: the prover loops, the proof chain state and the API.
+//
+// The `service` binary runs it from the environment, `LightwaveService::builder()` embeds it in
+// another process, such as an orchestrator running other services next to the prover loops.
+
+use sp1_sdk::include_elf;
+
+pub mod api;
+pub mod archive;
+pub mod artifacts;
+pub mod backends;
+pub mod backfill;
+pub mod cache;
+pub mod chains;
+pub mod checkpoints;
+pub mod commands;
+pub mod control;
+pub mod doctor;
+pub mod egress;
+pub mod encoding;
+pub mod errors;
+pub mod eta;
+pub mod export;
+pub mod gpu;
+pub mod ics23;
+pub mod middleware;
+pub mod network;
+pub mod networks;
+pub mod opstack;
+pub mod preprocessor;
+pub mod prover;
+pub mod publication;
+pub mod recover;
+pub mod relayers;
+pub mod rounds;
+pub mod selftest;
+pub mod server;
+pub mod service;
+pub mod shadow;
+pub mod slo;
+pub mod slots;
+pub mod solidity;
+pub mod state;
+pub mod state_diff;
+pub mod storage;
+pub mod trust;
+pub mod warm_start;
+pub mod webhooks;
+pub mod workers;
+
+pub use service::{LightwaveService, LightwaveServiceBuilder, ServiceHandle, Stopper};
+
+// Binary artifacts for the various circuits used in the light client
+pub const HELIOS_ELF: &[u8] = include_bytes!("../../../elfs/constant/sp1-helios-elf");
+pub const TENDERMINT_ELF: &[u8] = include_bytes!("../../../elfs/constant/sp1-tendermint-elf");
+pub const RECURSIVE_ELF_HELIOS: &[u8] = include_elf!("helios-recursion-circuit");
+pub const WRAPPER_ELF_HELIOS: &[u8] = include_elf!("helios-wrapper-circuit");
+pub const RECURSIVE_ELF_TENDERMINT: &[u8] = include_elf!("tendermint-recursion-circuit");
+pub const WRAPPER_ELF_TENDERMINT: &[u8] = include_elf!("tendermint-wrapper-circuit");
+pub const RECURSIVE_ELF_GRANDPA: &[u8] = include_elf!("grandpa-recursion-circuit");
+pub const WRAPPER_ELF_GRANDPA: &[u8] = include_elf!("grandpa-wrapper-circuit");
+pub const RECURSIVE_ELF_BITCOIN: &[u8] = include_elf!("bitcoin-recursion-circuit");
+pub const WRAPPER_ELF_BITCOIN: &[u8] = include_elf!("bitcoin-wrapper-circuit");
+pub const OPSTACK_OUTPUT_ELF: &[u8] = include_elf!("opstack-output-circuit");
+pub const STORAGE_PROOF_ELF: &[u8] = include_elf!("storage-proof-circuit");
+pub const ICS23_PROOF_ELF: &[u8] = include_elf!("ics23-proof-circuit");
+pub const SANITY_ELF: &[u8] = include_elf!("sanity-circuit");
@@ -3,65 +3,227 @@
 // and maintains a chain of trusted state transitions.
 
 use anyhow::{Context, Result};
-use axum::{Router, routing::get};
-use std::{fs::write, path::Path};
-mod api;
-use api::get_proof;
-use clap::Parser;
-use preprocessor::Preprocessor;
-use sp1_helios_primitives::types::ProofInputs as HeliosInputs;
-use sp1_sdk::{HashableKey, ProverClient, include_elf};
+use clap::{Parser, Subcommand, ValueEnum};
+use lightwave_service::{
+    LightwaveService,
+    api::CommitteeResponse,
+    backfill, commands, control, doctor, export,
+    ics23::ICS23_CHAIN_ID,
+    opstack::OPSTACK_CHAIN_ID,
+    prover::ProverMode,
+    recover, solidity,
+    state::{DEFAULT_CHAIN_ID, StateManager},
+    storage::STORAGE_CHAIN_ID,
+    warm_start, workers,
+};
+use std::path::{Path, PathBuf};
 use tokio::signal;
 use tracing::{error, info};
-mod preprocessor;
-mod state;
-use state::StateManager;
-use tree_hash::TreeHash;
-mod prover;
-use prover::run_prover_loop;
-
-use crate::checkpoints::{HELIOS_TRUSTED_SLOT, TENDERMINT_TRUSTED_HEIGHT, TENDERMINT_TRUSTED_ROOT};
-pub mod checkpoints;
 
 /// Command line arguments for the service
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Delete the state file before starting
-    #[arg(long)]
-    delete: bool,
-
-    /// Initial slot number to start from (only used when initializing new state)
-    #[arg(long)]
-    generate_recursion_circuit: bool,
-
-    /// Generate the wrapper circuit
-    #[arg(long)]
-    generate_wrapper_circuit: bool,
+    /// Runs the service if no command is given
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    /// Dump the ELFs as bytes
-    #[arg(long)]
-    dump_elfs: bool,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the prover loops and the API
+    Run,
+
+    /// Generate the circuits of a stage from the trusted checkpoints and the ELFs built into
+    /// this binary. The wrapper circuits are generated after rebuilding with the recursive
+    /// circuits, see `make build-circuits`
+    GenerateCircuits {
+        #[arg(value_enum)]
+        stage: CircuitStage,
+
+        /// Tendermint chain to generate the recursive circuit for, as configured in
+        /// `TENDERMINT_CHAINS_CONFIG`
+        #[arg(long)]
+        chain_id: Option<String>,
+    },
+
+    /// Write the trusted checkpoint at a Helios slot or a Tendermint height into the
+    /// checkpoints manifest, from the chain data. The circuits are generated from it once
+    /// regenerated, see `make bootstrap`
+    Bootstrap {
+        /// Slot of the Helios checkpoint, on the `SOURCE_CHAIN_ID` network
+        #[arg(long, conflicts_with = "height", required_unless_present = "height")]
+        slot: Option<u64>,
+
+        /// Height of the Tendermint checkpoint
+        #[arg(long)]
+        height: Option<u64>,
+
+        /// Tendermint chain of the checkpoint, as configured in `TENDERMINT_CHAINS_CONFIG`
+        #[arg(long, requires = "height")]
+        chain_id: Option<String>,
+    },
+
+    /// Write the ELFs built into this binary to `ELFS_OUT`
+    DumpElfs {
+        /// Tendermint chain to dump the ELFs of, as configured in `TENDERMINT_CHAINS_CONFIG`
+        #[arg(long)]
+        chain_id: Option<String>,
+    },
+
+    /// Delete the state database
+    DeleteState,
+
+    /// Verify the latest proof of a chain against the circuits built into this binary and
+    /// the hash chain of its update history
+    Verify {
+        /// The chain to verify, defaults to the single configured chain
+        #[arg(long)]
+        chain_id: Option<String>,
+    },
+
+    /// Print the committed state of the chains as JSON
+    Status {
+        /// The chain to print, defaults to all chains in the database
+        #[arg(long)]
+        chain_id: Option<String>,
+    },
+
+    /// Prove and commit a single round, write the proof to a file and print its path. For
+    /// cron-driven deployments and smoke tests, exits with an error if the round fails
+    ProveOnce {
+        /// Tendermint chain to prove, defaults to the single configured chain
+        #[arg(long)]
+        chain_id: Option<String>,
+
+        /// File to write the committed proofs to
+        #[arg(long, default_value = "proof.json")]
+        out: PathBuf,
+    },
+
+    /// Write the wrapper proof of an update in a relay format, for manual relays
+    ExportProof {
+        /// The chain to export, defaults to the single configured chain
+        #[arg(long)]
+        chain_id: Option<String>,
+
+        /// Update to export, defaults to the latest. Older updates are read from the proof
+        /// archive
+        #[arg(long)]
+        update: Option<u64>,
+
+        #[arg(long, value_enum, default_value = "json")]
+        format: export::ProofFormat,
+
+        /// File to write the proof to, defaults to stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Write a foundry project with a Solidity contract verifying the wrapper proofs of a chain
+    /// through the SP1 verifier gateway and storing the heights and roots they commit
+    GenerateVerifier {
+        /// The chain to verify, defaults to the single configured chain
+        #[arg(long)]
+        chain_id: Option<String>,
+
+        /// Directory to write the foundry project to
+        #[arg(long, default_value = "verifier")]
+        out: PathBuf,
+    },
+
+    /// Replay the Helios proof of a historical execution height from its stored inputs and
+    /// write it with the history records linking it to the latest update
+    Backfill {
+        /// The execution height to prove, committed by an earlier update
+        #[arg(long)]
+        height: u64,
+
+        /// File to write the proof to
+        #[arg(long, default_value = "backfill.json")]
+        out: PathBuf,
+    },
+
+    /// Serve proofs to coordinators listing this worker in `PROVER_WORKERS`
+    Worker,
+
+    /// Check the configuration and environment and print a troubleshooting report
+    Doctor,
+
+    /// Check and recover a proof chain from a supported failure. Prints the recovery checks
+    /// and the steps left to the operator, changes are only made with `--apply`
+    Recover {
+        #[arg(value_enum)]
+        scenario: recover::Scenario,
+
+        /// The chain to recover, defaults to the single configured chain
+        #[arg(long)]
+        chain_id: Option<String>,
+
+        /// The database backup to restore, for `restore-db`
+        #[arg(long)]
+        backup: Option<PathBuf>,
+
+        /// Apply the recovery once every check passes, with the service stopped
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Initialize a chain that committed nothing yet from the snapshot of another deployment
+    /// (`GET /snapshot`), to join its proof chain instead of proving from the trusted
+    /// checkpoint. Run with the service stopped
+    WarmStart {
+        /// The snapshot file
+        #[arg(long)]
+        snapshot: PathBuf,
+    },
+
+    /// Capture the test fixtures of the `lightwave-fixtures` crate from the configured RPCs
+    CaptureFixtures {
+        /// Slot of the Electra block to capture
+        #[arg(long)]
+        slot: u64,
+
+        /// Trusted height of the captured Tendermint outputs
+        #[arg(long)]
+        trusted_height: u64,
+
+        /// Target height of the captured Tendermint outputs
+        #[arg(long)]
+        target_height: u64,
+
+        /// Directory to write the fixtures to, the next version of the crate
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Print the hash of the sync committee trusted at a slot, to reproduce the committee
+    /// hash of a deployed Helios recursion circuit
+    DeriveCommittee {
+        /// Slot to derive the sync committee hash at, defaults to the trusted slot of the
+        /// network
+        #[arg(long)]
+        slot: Option<u64>,
+    },
 }
 
-// Binary artifacts for the various circuits used in the light client
-pub const HELIOS_ELF: &[u8] = include_bytes!("../../../elfs/constant/sp1-helios-elf");
-pub const TENDERMINT_ELF: &[u8] = include_bytes!("../../../elfs/constant/sp1-tendermint-elf");
-pub const RECURSIVE_ELF_HELIOS: &[u8] = include_elf!("helios-recursion-circuit");
-pub const WRAPPER_ELF_HELIOS: &[u8] = include_elf!("helios-wrapper-circuit");
-pub const RECURSIVE_ELF_TENDERMINT: &[u8] = include_elf!("tendermint-recursion-circuit");
-pub const WRAPPER_ELF_TENDERMINT: &[u8] = include_elf!("tendermint-wrapper-circuit");
+/// The circuits generated by `generate-circuits`
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CircuitStage {
+    /// The recursive circuits of all backends
+    Recursion,
+    /// The wrapper circuits of all backends
+    Wrapper,
+    /// The OP Stack output circuit of the rollup configured by `OP_STACK_*`
+    Opstack,
+    /// The storage proof circuit
+    Storage,
+    /// The ICS23 proof circuit
+    Ics23,
+}
 
-/// Main entry point for the light client service.
-///
-/// This function:
-/// 1. Initializes the service state with a trusted slot
-/// 2. Sets up the prover client and circuit artifacts
-/// 3. Enters a loop that:
-///    - Generates proofs for new blocks (Helios or Tendermint depending on mode)
-///    - Verifies proofs recursively
-///    - Updates the service state with new trusted information
-///    - Commits execution block height and state root instead of beacon header
+/// Main entry point for the light client service, runs the command given on the command line
+/// (`run` by default).
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing with INFO level and clean formatting
@@ -75,288 +237,215 @@ async fn main() -> Result<()> {
 
     // Parse command line arguments
     let args = Args::parse();
-    let client = ProverClient::from_env();
 
     // Load environment variables
     dotenvy::dotenv().ok();
 
-    // Get server port from environment or use default
-    let port = std::env::var("API_PORT").unwrap_or_else(|_| "7778".to_string());
-    let addr = format!("0.0.0.0:{}", port);
-
-    // Create router for API endpoints
-    let app = Router::new().route("/", get(get_proof));
-
-    // Create a shutdown signal handler for graceful shutdown
-    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
-    let app = app.into_make_service();
-
-    // Get consensus URL from environment
-    let consensus_url = std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap_or_default();
-
-    // Get database path from environment or use default
+    // Get database path and client backend mode from environment
     let db_path =
         std::env::var("SERVICE_STATE_DB_PATH").unwrap_or_else(|_| "service_state.db".to_string());
-
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = Path::new(&db_path).parent() {
-        std::fs::create_dir_all(parent).context("Failed to create database directory")?;
-    }
-
-    // Initialize the state manager with a database file
-    let state_manager = StateManager::new(Path::new(&db_path))?;
-
-    // Delete state if --delete flag is set
-    if args.delete {
-        state_manager.delete_state()?;
-        tracing::info!("State file deleted successfully");
-        return Ok(());
-    }
-
-    // Get client backend mode from environment
     let mode = std::env::var("CLIENT_BACKEND").unwrap_or_else(|_| "TENDERMINT".to_string());
-
-    // Set up ELF file paths
     let elfs_path = std::env::var("ELFS_OUT").unwrap_or_else(|_| "elfs/variable".to_string());
-    let helios_recursive_elf_path = Path::new(&elfs_path).join("helios-recursive-elf.bin");
-    let helios_wrapper_elf_path = Path::new(&elfs_path).join("helios-wrapper-elf.bin");
-    let tendermint_recursive_elf_path = Path::new(&elfs_path).join("tendermint-recursive-elf.bin");
-    let tendermint_wrapper_elf_path = Path::new(&elfs_path).join("tendermint-wrapper-elf.bin");
-
-    // Generate the Recursion Circuit if requested
-    if args.generate_recursion_circuit {
-        // Initialize the preprocessor with the current trusted slot
-        let preprocessor = Preprocessor::new(HELIOS_TRUSTED_SLOT);
-        // Get the next block's inputs for proof generation
-        let inputs = preprocessor.run().await?;
-
-        let helios_inputs: HeliosInputs = serde_cbor::from_slice(&inputs)?;
-        let trusted_committee_hash = helios_inputs
-            .store
-            .current_sync_committee
-            .clone()
-            .tree_hash_root()
-            .to_vec();
-
-        let committee_hash_formatted = format!("{:?}", trusted_committee_hash);
-        let template = include_str!("../../integrations/sp1-helios/circuit/src/blueprint.rs");
-
-        // Generate the Helios recursive circuit
-        let (_, helios_vk) = client.setup(HELIOS_ELF);
-        let generated_code = template
-            .replace("{ committee_hash }", &committee_hash_formatted)
-            .replace("{ trusted_head }", &HELIOS_TRUSTED_SLOT.to_string())
-            .replace("{ helios_vk }", &helios_vk.bytes32());
-        write(
-            "crates/integrations/sp1-helios/circuit/src/main.rs",
-            generated_code,
-        )
-        .context("Failed to generate recursive circuit from blueprint")?;
-
-        // Generate the Tendermint recursive circuit
-        let template = include_str!("../../integrations/sp1-tendermint/circuit/src/blueprint.rs");
-        let (_, tendermint_vk) = client.setup(TENDERMINT_ELF);
-        let generated_code = template
-            .replace("{ trusted_height }", &TENDERMINT_TRUSTED_HEIGHT.to_string())
-            .replace(
-                "{ trusted_root }",
-                &format!("{:?}", TENDERMINT_TRUSTED_ROOT),
-            )
-            .replace("{ tendermint_vk }", &tendermint_vk.bytes32());
-        write(
-            "crates/integrations/sp1-tendermint/circuit/src/main.rs",
-            generated_code,
-        )
-        .context("Failed to generate recursive circuit from blueprint")?;
-
-        tracing::info!("Recursive circuit generated successfully");
-        return Ok(());
-    }
-
-    // Generate the Wrapper Circuit if requested
-    if args.generate_wrapper_circuit {
-        let client = ProverClient::from_env();
-        let (_, helios_vk) = client.setup(RECURSIVE_ELF_HELIOS);
-        let helios_vk_bytes = helios_vk.bytes32();
-
-        let (_, tendermint_vk) = client.setup(RECURSIVE_ELF_TENDERMINT);
-        let tendermint_vk_bytes = tendermint_vk.bytes32();
-
-        let template =
-            include_str!("../../integrations/sp1-helios/wrapper-circuit/src/blueprint.rs");
-        let generated_code =
-            template.replace("{ recursive_vk }", &format!("{:?}", helios_vk_bytes));
-
-        // Generate the Helios wrapper circuit
-        write(
-            "crates/integrations/sp1-helios/wrapper-circuit/src/main.rs",
-            generated_code,
-        )
-        .context("Failed to generate wrapper circuit from blueprint")?;
-
-        let template =
-            include_str!("../../integrations/sp1-tendermint/wrapper-circuit/src/blueprint.rs");
-
-        // Generate the Tendermint wrapper circuit
-        let generated_code =
-            template.replace("{ recursive_vk }", &format!("{:?}", tendermint_vk_bytes));
-        write(
-            "crates/integrations/sp1-tendermint/wrapper-circuit/src/main.rs",
-            generated_code,
-        )
-        .context("Failed to generate wrapper circuit from blueprint")?;
-
-        tracing::info!("Wrapper circuit generated successfully");
-        return Ok(());
-    }
 
-    // Dump the ELFs as bytes if requested
-    if args.dump_elfs {
-        std::fs::create_dir_all(&elfs_path)?;
-
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = Path::new(&elfs_path).parent() {
-            std::fs::create_dir_all(parent).context("Failed to create ELF directory")?;
-        }
-
-        // Write Helios ELFs
-        std::fs::write(&helios_recursive_elf_path, RECURSIVE_ELF_HELIOS).context(format!(
-            "Failed to dump recursive ELF to {}",
-            helios_recursive_elf_path.display()
-        ))?;
-        std::fs::write(&helios_wrapper_elf_path, WRAPPER_ELF_HELIOS).context(format!(
-            "Failed to dump wrapper ELF to {}",
-            helios_wrapper_elf_path.display()
-        ))?;
-
-        // Write Tendermint ELFs
-        std::fs::write(&tendermint_recursive_elf_path, RECURSIVE_ELF_TENDERMINT).context(
-            format!(
-                "Failed to dump recursive ELF to {}",
-                tendermint_recursive_elf_path.display()
-            ),
-        )?;
-        std::fs::write(&tendermint_wrapper_elf_path, WRAPPER_ELF_TENDERMINT).context(format!(
-            "Failed to dump wrapper ELF to {}",
-            tendermint_wrapper_elf_path.display()
-        ))?;
-
-        tracing::info!("ELFs dumped successfully");
-        return Ok(());
-    }
-
-    // Load or initialize the service state
-    let state_manager = StateManager::new(Path::new(&db_path))?;
-    let service_state = match state_manager.load_state()? {
-        Some(state) => state,
-        None => match mode.as_str() {
-            "TENDERMINT" => state_manager
-                .initialize_state(TENDERMINT_TRUSTED_HEIGHT, TENDERMINT_TRUSTED_HEIGHT)?,
-            "HELIOS" => state_manager.initialize_state(HELIOS_TRUSTED_SLOT, 0)?,
-            _ => state_manager.initialize_state(HELIOS_TRUSTED_SLOT, 0)?,
+    match args.command.unwrap_or(Command::Run) {
+        Command::Run => run(db_path, mode, elfs_path).await,
+        Command::GenerateCircuits { stage, chain_id } => match stage {
+            CircuitStage::Recursion => {
+                commands::generate_recursion_circuits(
+                    chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                )
+                .await
+            }
+            CircuitStage::Wrapper => commands::generate_wrapper_circuits(),
+            CircuitStage::Opstack => commands::generate_opstack_circuit(),
+            CircuitStage::Storage => commands::generate_storage_circuit(),
+            CircuitStage::Ics23 => commands::generate_ics23_circuit(),
         },
-    };
-
-    // Start the API server in a separate task
-    let server_handle = tokio::spawn(async move {
-        let listener = match tokio::net::TcpListener::bind(&addr).await {
-            Ok(listener) => {
-                info!("API server listening on {}", addr);
-                listener
+        Command::Bootstrap {
+            slot,
+            height,
+            chain_id,
+        } => {
+            let checkpoint = match (slot, height) {
+                (Some(slot), _) => serde_json::to_value(commands::bootstrap_helios(slot).await?)?,
+                (None, Some(height)) => serde_json::to_value(
+                    commands::bootstrap_tendermint(
+                        chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                        height,
+                    )
+                    .await?,
+                )?,
+                (None, None) => unreachable!("clap requires a slot or a height"),
+            };
+            println!("{}", serde_json::to_string_pretty(&checkpoint)?);
+            Ok(())
+        }
+        Command::DumpElfs { chain_id } => {
+            commands::dump_elfs(&elfs_path, chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID))
+        }
+        Command::DeleteState => {
+            StateManager::new(Path::new(&db_path))?.delete_state()?;
+            info!("State file deleted successfully");
+            Ok(())
+        }
+        Command::Verify { chain_id } => {
+            // The backend may have been switched at runtime
+            let mode = control::active_backend(&StateManager::new(Path::new(&db_path))?)?;
+            commands::verify(
+                Path::new(&db_path),
+                chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                &mode,
+            )
+        }
+        Command::Status { chain_id } => {
+            let mode = control::active_backend(&StateManager::new(Path::new(&db_path))?)?;
+            let status = commands::status(Path::new(&db_path), chain_id.as_deref(), &mode)?;
+            println!("{}", serde_json::to_string_pretty(&status)?);
+            Ok(())
+        }
+        Command::ProveOnce { chain_id, out } => {
+            let mode = control::active_backend(&StateManager::new(Path::new(&db_path))?)?;
+            commands::prove_once(
+                Path::new(&db_path),
+                &mode,
+                &elfs_path,
+                chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                &out,
+            )
+            .await
+        }
+        Command::ExportProof {
+            chain_id,
+            update,
+            format,
+            out,
+        } => {
+            let chain_id = chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID);
+            let mode = match chain_id {
+                OPSTACK_CHAIN_ID => "OPSTACK".to_string(),
+                STORAGE_CHAIN_ID => "STORAGE".to_string(),
+                ICS23_CHAIN_ID => "ICS23".to_string(),
+                _ => control::active_backend(&StateManager::new(Path::new(&db_path))?)?,
+            };
+            let bundle = export::load_bundle(Path::new(&db_path), chain_id, &mode, update)?;
+            let encoded = export::encode(&bundle, format)?;
+            match out {
+                Some(out) => {
+                    std::fs::write(&out, encoded)
+                        .context(format!("Failed to write {}", out.display()))?;
+                    info!(
+                        "📤 Exported update {} of {} to {}",
+                        bundle.record.update_counter,
+                        chain_id,
+                        out.display()
+                    );
+                }
+                None => println!("{}", encoded),
             }
-            Err(e) => {
-                error!("Failed to bind to {}: {}", addr, e);
-                return Err(e);
+            Ok(())
+        }
+        Command::GenerateVerifier { chain_id, out } => {
+            let mode = control::active_backend(&StateManager::new(Path::new(&db_path))?)?;
+            solidity::generate_verifier(
+                Path::new(&db_path),
+                chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                &mode,
+                &out,
+            )
+        }
+        Command::Backfill { height, out } => {
+            let mode = control::active_backend(&StateManager::new(Path::new(&db_path))?)?;
+            if mode != "HELIOS" {
+                return Err(anyhow::anyhow!(
+                    "Only the Helios backend stores the inputs back-filled proofs are replayed from"
+                ));
             }
-        };
-
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async {
-                shutdown_rx.await.ok();
-                info!("API server shutting down gracefully");
-            })
+            let proof = backfill::backfill(Path::new(&db_path), height).await?;
+            std::fs::write(&out, serde_json::to_vec_pretty(&proof)?)
+                .context(format!("Failed to write {}", out.display()))?;
+            info!(
+                "⏪ Back-filled height {} (update {}) to {}",
+                height,
+                proof.record.update_counter,
+                out.display()
+            );
+            Ok(())
+        }
+        Command::Worker => workers::run_worker().await,
+        Command::Doctor => {
+            let failed = doctor::run().await?;
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} doctor checks failed", failed));
+            }
+            Ok(())
+        }
+        Command::Recover {
+            scenario,
+            chain_id,
+            backup,
+            apply,
+        } => {
+            recover::run(
+                scenario,
+                Path::new(&db_path),
+                &elfs_path,
+                chain_id.as_deref().unwrap_or(DEFAULT_CHAIN_ID),
+                backup.as_deref(),
+                apply,
+            )
             .await
-            .map_err(|e| {
-                error!("API server error: {}", e);
-                e
-            })
-    });
+        }
+        Command::WarmStart { snapshot } => {
+            let verify = !ProverMode::from_env()?.mocks_circuits();
+            let record = warm_start::import_file(Path::new(&db_path), &snapshot, verify)?;
+            info!(
+                "Warm started from update {} at height {}, start the service to continue",
+                record.update_counter, record.height
+            );
+            Ok(())
+        }
+        Command::CaptureFixtures {
+            slot,
+            trusted_height,
+            target_height,
+            out,
+        } => commands::capture_fixtures(slot, trusted_height, target_height, &out).await,
+        Command::DeriveCommittee { slot } => {
+            let committee = CommitteeResponse::derive(slot).await?;
+            println!("{}", serde_json::to_string_pretty(&committee)?);
+            Ok(())
+        }
+    }
+}
 
-    // Handle shutdown signals (Ctrl+C)
+/// Runs the prover loops of the configured backend and serves the API until shutdown.
+///
+/// This function:
+/// 1. Initializes the service state with a trusted slot
+/// 2. Sets up the prover client and circuit artifacts
+/// 3. Enters a loop that:
+///    - Generates proofs for new blocks (Helios or Tendermint depending on mode)
+///    - Verifies proofs recursively
+///    - Updates the service state with new trusted information
+///    - Commits execution block height and state root instead of beacon header
+async fn run(db_path: String, mode: String, elfs_path: String) -> Result<()> {
+    let service = LightwaveService::builder()
+        .backend(mode)
+        .database(db_path)
+        .elfs(elfs_path)
+        .start()
+        .await?;
+
+    // Handle shutdown signals (Ctrl+C), the prover loops finish their round first
+    let stopper = service.stopper();
     tokio::spawn(async move {
         if let Err(e) = signal::ctrl_c().await {
             error!("Failed to listen for ctrl+c: {}", e);
+            return;
         }
         info!("Received shutdown signal");
-        let _ = shutdown_tx.send(());
+        stopper.stop();
     });
 
-    // Verify that required ELF files exist
-    if !helios_recursive_elf_path.exists() {
-        println!(
-            "Recursive ELF not found at {}, please run with --dump-elfs",
-            helios_recursive_elf_path.display()
-        );
-        return Err(anyhow::anyhow!("Recursive ELF not found"));
-    }
-
-    // Load the appropriate ELF files based on the selected mode
-    let (recursive_elf, wrapper_elf) = match mode.as_str() {
-        "TENDERMINT" => {
-            // Read bytes of recursive-elf and wrapper-elf for Tendermint
-            let recursive_elf = std::fs::read(&tendermint_recursive_elf_path).context(format!(
-                "Failed to read recursive elf from {}",
-                tendermint_recursive_elf_path.display()
-            ))?;
-
-            let wrapper_elf = std::fs::read(&tendermint_wrapper_elf_path).context(format!(
-                "Failed to read wrapper elf from {}",
-                tendermint_wrapper_elf_path.display()
-            ))?;
-
-            (recursive_elf, wrapper_elf)
-        }
-        "HELIOS" => {
-            // Read bytes of recursive-elf and wrapper-elf for Helios
-            let recursive_elf = std::fs::read(&helios_recursive_elf_path).context(format!(
-                "Failed to read recursive elf from {}",
-                helios_recursive_elf_path.display()
-            ))?;
-
-            let wrapper_elf = std::fs::read(&helios_wrapper_elf_path).context(format!(
-                "Failed to read wrapper elf from {}",
-                helios_wrapper_elf_path.display()
-            ))?;
-
-            (recursive_elf, wrapper_elf)
-        }
-        _ => {
-            panic!("Invalid mode: {:?}", mode);
-        }
-    };
-
-    // Start the prover service loop in a separate task
-    let service_handle = tokio::spawn(run_prover_loop(
-        state_manager,
-        service_state,
-        recursive_elf,
-        wrapper_elf,
-        consensus_url,
-    ));
-
-    // Wait for both tasks to conclude
-    let (server_result, service_result) = tokio::join!(server_handle, service_handle);
-
-    // Handle any errors from the tasks
-    if let Err(e) = server_result {
-        error!("API server crashed: {}", e);
-        return Err(anyhow::anyhow!("{}", e));
-    }
-
-    if let Err(e) = service_result {
-        error!("Prover service crashed: {}", e);
-        return Err(anyhow::anyhow!("{}", e));
-    }
-
-    Ok(())
+    service.join().await
 }
@@ -0,0 +1,171 @@
+// Optional access control for the public API: bearer-token authentication and
+// per-client rate limiting. Both are disabled unless configured in the environment.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tracing::warn;
+
+/// Rejects requests that don't carry `Authorization: Bearer <token>`
+pub async fn require_bearer_token(
+    State(token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Compares two byte strings without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// A token bucket per client IP address
+pub struct RateLimiter {
+    /// Maximum number of requests a client can burst
+    capacity: f64,
+    /// Number of tokens added back per second
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_minute` requests per client, with bursts up to the same amount
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self {
+            capacity: requests_per_minute as f64,
+            refill_per_sec: requests_per_minute as f64 / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reads `API_RATE_LIMIT_PER_MINUTE`, rate limiting is disabled if unset
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        match std::env::var("API_RATE_LIMIT_PER_MINUTE") {
+            Ok(limit) => Ok(Some(Self::new(parse_rate_limit(&limit)?))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Takes a token from the client's bucket, returns false if the bucket is empty
+    pub fn try_acquire(&self, client: IpAddr) -> bool {
+        self.try_acquire_at(client, Instant::now())
+    }
+
+    fn try_acquire_at(&self, client: IpAddr, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Drop clients whose bucket has fully refilled to keep the map bounded
+        if buckets.len() > 10_000 {
+            let capacity = self.capacity;
+            let refill_per_sec = self.refill_per_sec;
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens + elapsed * refill_per_sec < capacity
+            });
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Rejects requests from clients that exceeded their rate limit
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if limiter.try_acquire(addr.ip()) {
+        next.run(request).await
+    } else {
+        warn!("Rate limit exceeded for {}", addr.ip());
+        StatusCode::TOO_MANY_REQUESTS.into_response()
+    }
+}
+
+/// Parses `API_RATE_LIMIT_PER_MINUTE`, a limit of 0 would refuse every request
+fn parse_rate_limit(limit: &str) -> anyhow::Result<u32> {
+    match limit.parse() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(anyhow::anyhow!(
+            "Invalid API_RATE_LIMIT_PER_MINUTE {}, expected a positive number of requests",
+            limit
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limiter_refills_per_client() {
+        let limiter = RateLimiter::new(2);
+        let alice: IpAddr = "10.0.0.1".parse().unwrap();
+        let bob: IpAddr = "10.0.0.2".parse().unwrap();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire_at(alice, now));
+        assert!(limiter.try_acquire_at(alice, now));
+        assert!(!limiter.try_acquire_at(alice, now));
+        // Other clients have their own bucket
+        assert!(limiter.try_acquire_at(bob, now));
+        // Two requests per minute refill one token every 30 seconds
+        assert!(limiter.try_acquire_at(alice, now + Duration::from_secs(30)));
+        assert!(!limiter.try_acquire_at(alice, now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_rate_limit_must_be_positive() {
+        assert_eq!(parse_rate_limit("60").unwrap(), 60);
+        assert!(parse_rate_limit("0").is_err());
+        assert!(parse_rate_limit("-1").is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+}
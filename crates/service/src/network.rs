@@ -0,0 +1,209 @@
+// Proving on the Succinct prover network.
+//
+// With `SP1_PROVER=network` the service outsources its proofs instead of proving on local
+// hardware. The network is configured explicitly rather than through the SDK defaults:
+// - `NETWORK_PRIVATE_KEY` or `NETWORK_PRIVATE_KEY_FILE`: the requester key paying for proofs
+// - `NETWORK_RPC_URL`: the network endpoint, the SDK default if unset
+// - `NETWORK_STRATEGY`: `hosted` (default), `reserved` or `auction`
+// - `NETWORK_TIMEOUT_SECS`: how long a request may take to be fulfilled
+// - `NETWORK_CYCLE_LIMIT`: the maximum cycles a request may be charged for
+// - `NETWORK_MAX_PRICE_PER_PGU`: the maximum price per prover gas unit of auctioned requests
+//
+// Every request is logged with its id so operators can follow it in the network explorer,
+// and fulfillment counts are served with the chain stats.
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{
+    ProverClient, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin,
+    network::FulfillmentStrategy,
+};
+use std::{sync::Mutex, time::Duration};
+
+use crate::encoding::{parse_hex_array, to_hex};
+
+/// Requests of this process to the prover network
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkStats {
+    pub requested: u64,
+    pub fulfilled: u64,
+    pub failed: u64,
+    /// Id of the most recent request
+    pub last_request_id: Option<String>,
+}
+
+static STATS: Mutex<NetworkStats> = Mutex::new(NetworkStats {
+    requested: 0,
+    fulfilled: 0,
+    failed: 0,
+    last_request_id: None,
+});
+
+/// The prover network requests of this process, `None` unless proving on the network
+pub fn stats() -> Option<NetworkStats> {
+    let proving = std::env::var("SP1_PROVER").as_deref() == Ok("network");
+    proving.then(|| STATS.lock().unwrap().clone())
+}
+
+/// The prover network configuration
+#[derive(Clone)]
+pub struct NetworkConfig {
+    /// 0x-prefixed hex of the requester key
+    private_key: String,
+    rpc_url: Option<String>,
+    strategy: FulfillmentStrategy,
+    timeout: Option<Duration>,
+    cycle_limit: Option<u64>,
+    max_price_per_pgu: Option<u64>,
+}
+
+impl NetworkConfig {
+    /// Loads the configuration if `SP1_PROVER=network`, `None` for local provers
+    pub fn from_env() -> Result<Option<Self>> {
+        if std::env::var("SP1_PROVER").as_deref() != Ok("network") {
+            return Ok(None);
+        }
+
+        let private_key = match (
+            std::env::var("NETWORK_PRIVATE_KEY"),
+            std::env::var("NETWORK_PRIVATE_KEY_FILE"),
+        ) {
+            (Ok(key), _) => key,
+            (Err(_), Ok(path)) => std::fs::read_to_string(&path)
+                .context(format!("Failed to read NETWORK_PRIVATE_KEY_FILE {}", path))?
+                .trim()
+                .to_string(),
+            (Err(_), Err(_)) => {
+                return Err(anyhow!(
+                    "SP1_PROVER=network requires NETWORK_PRIVATE_KEY or NETWORK_PRIVATE_KEY_FILE"
+                ));
+            }
+        };
+        // Never echo the key in errors
+        let private_key = parse_hex_array::<32>(&private_key)
+            .map(to_hex)
+            .map_err(|_| anyhow!("The prover network key must be 32 bytes of hex"))?;
+
+        let strategy = match std::env::var("NETWORK_STRATEGY").as_deref() {
+            Err(_) | Ok("hosted") => FulfillmentStrategy::Hosted,
+            Ok("reserved") => FulfillmentStrategy::Reserved,
+            Ok("auction") => FulfillmentStrategy::Auction,
+            Ok(strategy) => {
+                return Err(anyhow!(
+                    "Invalid NETWORK_STRATEGY {}, expected hosted, reserved or auction",
+                    strategy
+                ));
+            }
+        };
+
+        Ok(Some(Self {
+            private_key,
+            rpc_url: std::env::var("NETWORK_RPC_URL").ok(),
+            strategy,
+            timeout: parse_env_u64("NETWORK_TIMEOUT_SECS")?.map(Duration::from_secs),
+            cycle_limit: parse_env_u64("NETWORK_CYCLE_LIMIT")?,
+            max_price_per_pgu: parse_env_u64("NETWORK_MAX_PRICE_PER_PGU")?,
+        }))
+    }
+
+    /// Requests a Groth16 proof of `name` from the network and waits for its fulfillment
+    pub async fn prove(
+        &self,
+        name: &str,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+    ) -> Result<SP1ProofWithPublicValues> {
+        self.prove_with_mode(name, pk, stdin, SP1ProofMode::Groth16)
+            .await
+    }
+
+    /// Requests a proof of `name` in `mode` from the network and waits for its fulfillment
+    pub async fn prove_with_mode(
+        &self,
+        name: &str,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<SP1ProofWithPublicValues> {
+        let mut builder = ProverClient::builder()
+            .network()
+            .private_key(&self.private_key);
+        if let Some(rpc_url) = &self.rpc_url {
+            builder = builder.rpc_url(rpc_url);
+        }
+        let client = builder.build();
+
+        let mut request = client.prove(pk, stdin).mode(mode).strategy(self.strategy);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(cycle_limit) = self.cycle_limit {
+            request = request.cycle_limit(cycle_limit);
+        }
+        if let Some(max_price_per_pgu) = self.max_price_per_pgu {
+            request = request.max_price_per_pgu(max_price_per_pgu);
+        }
+
+        let id = request
+            .request_async()
+            .await
+            .map_err(|e| anyhow!("{} proof request failed: {}", name, e))?;
+        let request_id = to_hex(id);
+        {
+            let mut stats = STATS.lock().unwrap();
+            stats.requested += 1;
+            stats.last_request_id = Some(request_id.clone());
+        }
+        tracing::info!(
+            "📡 Requested {} proof from the prover network ({:?}): {}",
+            name,
+            self.strategy,
+            request_id
+        );
+
+        match client.wait_proof(id, self.timeout).await {
+            Ok(proof) => {
+                STATS.lock().unwrap().fulfilled += 1;
+                tracing::info!(
+                    "✅ Prover network fulfilled {} request {}",
+                    name,
+                    request_id
+                );
+                Ok(proof)
+            }
+            Err(e) => {
+                STATS.lock().unwrap().failed += 1;
+                Err(anyhow!(
+                    "Prover network request {} for the {} proof failed: {}",
+                    request_id,
+                    name,
+                    e
+                ))
+            }
+        }
+    }
+}
+
+// The key is left out so the configuration can be logged
+impl std::fmt::Debug for NetworkConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkConfig")
+            .field("rpc_url", &self.rpc_url)
+            .field("strategy", &self.strategy)
+            .field("timeout", &self.timeout)
+            .field("cycle_limit", &self.cycle_limit)
+            .field("max_price_per_pgu", &self.max_price_per_pgu)
+            .finish_non_exhaustive()
+    }
+}
+
+fn parse_env_u64(name: &str) -> Result<Option<u64>> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| anyhow!("Invalid {} {}, expected an integer", name, value))
+        })
+        .transpose()
+}
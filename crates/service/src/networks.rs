@@ -0,0 +1,247 @@
+// Ethereum networks followed by the Helios backend, selected by `SOURCE_CHAIN_ID`.
+
+use anyhow::{Context, Result};
+use helios_ethereum::config::{Config, networks::Network};
+
+use crate::checkpoints::CheckpointManifest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthereumNetwork {
+    Mainnet,
+    Sepolia,
+    Holesky,
+}
+
+impl EthereumNetwork {
+    pub fn from_chain_id(chain_id: u64) -> Result<Self> {
+        match chain_id {
+            1 => Ok(Self::Mainnet),
+            11155111 => Ok(Self::Sepolia),
+            17000 => Ok(Self::Holesky),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported SOURCE_CHAIN_ID {}, expected 1 (mainnet), 11155111 (Sepolia) or 17000 (Holesky)",
+                chain_id
+            )),
+        }
+    }
+
+    /// Reads `SOURCE_CHAIN_ID`, defaults to mainnet
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("SOURCE_CHAIN_ID") {
+            Ok(chain_id) => Self::from_chain_id(
+                chain_id
+                    .parse()
+                    .context(format!("Invalid SOURCE_CHAIN_ID {}", chain_id))?,
+            ),
+            Err(_) => Ok(Self::Mainnet),
+        }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Self::Mainnet => 1,
+            Self::Sepolia => 11155111,
+            Self::Holesky => 17000,
+        }
+    }
+
+    /// The CAIP-2 chain id committed by the Helios circuits
+    pub fn caip2(&self) -> String {
+        format!("eip155:{}", self.chain_id())
+    }
+
+    /// Slots per epoch, a finality update is produced at most once per epoch
+    pub fn slots_per_epoch(&self) -> u64 {
+        32
+    }
+
+    /// Slots per sync committee period, 32 slots times 256 epochs
+    pub fn slots_per_period(&self) -> u64 {
+        8192
+    }
+
+    /// Epochs from `slot` to the first slot of the next sync committee period
+    pub fn epochs_until_next_period(&self, slot: u64) -> u64 {
+        let slots_per_period = self.slots_per_period();
+        (slots_per_period - slot % slots_per_period).div_ceil(self.slots_per_epoch())
+    }
+
+    /// Whether the finality update of `finalized_slot` attested at `attested_slot` is too close
+    /// to a sync committee period boundary to be proven: it is attested in the next period,
+    /// signed by a committee the proof chain doesn't trust yet, or finalized in the last
+    /// `boundary_epochs` epochs of its period, where the next update may be
+    pub fn near_period_boundary(
+        &self,
+        finalized_slot: u64,
+        attested_slot: u64,
+        boundary_epochs: u64,
+    ) -> bool {
+        let slots_per_period = self.slots_per_period();
+        attested_slot / slots_per_period > finalized_slot / slots_per_period
+            || self.epochs_until_next_period(finalized_slot) <= boundary_epochs
+    }
+
+    /// The slot the light client starts from. `HELIOS_TRUSTED_SLOT` overrides the checkpoint
+    /// of the manifest and is required on the other networks
+    pub fn trusted_slot(&self) -> Result<u64> {
+        match std::env::var("HELIOS_TRUSTED_SLOT") {
+            Ok(slot) => slot
+                .parse()
+                .context(format!("Invalid HELIOS_TRUSTED_SLOT {}", slot)),
+            Err(_) if self.chain_id() == CheckpointManifest::built_in().helios.source_chain_id => {
+                Ok(CheckpointManifest::built_in().helios.trusted_slot)
+            }
+            Err(_) => Err(anyhow::anyhow!(
+                "HELIOS_TRUSTED_SLOT must be set to a checkpoint slot of {:?}",
+                self
+            )),
+        }
+    }
+
+    /// Helios client configuration with the genesis and fork schedule of the network
+    pub fn helios_config(&self, consensus_rpc: &str) -> Result<Config> {
+        let network = Network::from_chain_id(self.chain_id())
+            .map_err(|e| anyhow::anyhow!("Unsupported network {:?}: {}", self, e))?;
+        let base_config = network.to_base_config();
+        Ok(Config {
+            consensus_rpc: consensus_rpc.to_string(),
+            execution_rpc: None,
+            chain: base_config.chain,
+            forks: base_config.forks,
+            strict_checkpoint_age: false,
+            ..Default::default()
+        })
+    }
+}
+
+/// The finalized heads the Helios backend proves and publishes, selected by
+/// `UPDATE_ALIGNMENT`. Finality updates whose finalized head doesn't reach the next boundary
+/// after the trusted slot are skipped, consumers then only see updates at epoch or period
+/// boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateAlignment {
+    /// Every finality update (default)
+    #[default]
+    Finalized,
+    /// The first finalized head of every `n`-th epoch
+    Epochs(u64),
+    /// The first finalized head of every sync committee period
+    Period,
+}
+
+impl UpdateAlignment {
+    /// Reads `UPDATE_ALIGNMENT`: `finalized`, `epoch`, `epochs:<n>` or `period`
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("UPDATE_ALIGNMENT") {
+            Ok(alignment) => alignment.parse(),
+            Err(_) => Ok(Self::Finalized),
+        }
+    }
+
+    /// Whether the finalized head at `finalized_slot` is proven after the trusted slot
+    pub fn admits(&self, network: EthereumNetwork, trusted_slot: u64, finalized_slot: u64) -> bool {
+        let boundary = match self {
+            Self::Finalized => return true,
+            Self::Epochs(epochs) => network.slots_per_epoch() * epochs,
+            Self::Period => network.slots_per_period(),
+        };
+        finalized_slot / boundary > trusted_slot / boundary
+    }
+}
+
+impl std::str::FromStr for UpdateAlignment {
+    type Err = anyhow::Error;
+
+    fn from_str(alignment: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "Invalid UPDATE_ALIGNMENT {}, expected finalized, epoch, epochs:<n> or period",
+                alignment
+            )
+        };
+        match alignment {
+            "finalized" => Ok(Self::Finalized),
+            "epoch" => Ok(Self::Epochs(1)),
+            "period" => Ok(Self::Period),
+            _ => match alignment.strip_prefix("epochs:").map(str::parse) {
+                Some(Ok(epochs)) if epochs > 0 => Ok(Self::Epochs(epochs)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for UpdateAlignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Finalized => f.write_str("finalized"),
+            Self::Epochs(1) => f.write_str("epoch"),
+            Self::Epochs(epochs) => write!(f, "epochs:{}", epochs),
+            Self::Period => f.write_str("period"),
+        }
+    }
+}
+
+/// Epochs before a sync committee period boundary whose finality updates the Helios backend
+/// waits out, `HELIOS_BOUNDARY_EPOCHS` (default 3, 0 only waits out updates attested in the
+/// next period)
+pub fn boundary_epochs() -> Result<u64> {
+    match std::env::var("HELIOS_BOUNDARY_EPOCHS") {
+        Ok(epochs) => epochs
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid HELIOS_BOUNDARY_EPOCHS {}", epochs)),
+        Err(_) => Ok(DEFAULT_BOUNDARY_EPOCHS),
+    }
+}
+
+/// Default epochs waited out before a sync committee period boundary, the finality lag of
+/// two epochs and one more for the signature of the update
+pub const DEFAULT_BOUNDARY_EPOCHS: u64 = 3;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_networks() {
+        for chain_id in [1, 11155111, 17000] {
+            let network = EthereumNetwork::from_chain_id(chain_id).unwrap();
+            assert_eq!(network.chain_id(), chain_id);
+            assert_eq!(network.slots_per_period() % network.slots_per_epoch(), 0);
+        }
+        assert!(EthereumNetwork::from_chain_id(10).is_err());
+        assert!(EthereumNetwork::from_chain_id(100).is_err());
+    }
+
+    #[test]
+    fn test_update_alignment() {
+        let mainnet = EthereumNetwork::Mainnet;
+        assert_eq!(
+            "epochs:4".parse::<UpdateAlignment>().unwrap(),
+            UpdateAlignment::Epochs(4)
+        );
+        assert!("epochs:0".parse::<UpdateAlignment>().is_err());
+
+        // Any newer head, the first head of the next epoch, of the next 4-epoch window
+        assert!(UpdateAlignment::Finalized.admits(mainnet, 64, 65));
+        assert!(!UpdateAlignment::Epochs(1).admits(mainnet, 64, 95));
+        assert!(UpdateAlignment::Epochs(1).admits(mainnet, 64, 96));
+        assert!(!UpdateAlignment::Epochs(4).admits(mainnet, 128, 224));
+        assert!(UpdateAlignment::Epochs(4).admits(mainnet, 128, 256));
+        assert!(!UpdateAlignment::Period.admits(mainnet, 8192, 16000));
+        assert!(UpdateAlignment::Period.admits(mainnet, 8192, 16384));
+    }
+
+    #[test]
+    fn test_period_boundary() {
+        let mainnet = EthereumNetwork::Mainnet;
+        assert_eq!(mainnet.epochs_until_next_period(8192), 256);
+        assert_eq!(mainnet.epochs_until_next_period(16384 - 32), 1);
+
+        // Mid-period updates are proven, the last epochs and the ones signed across are not
+        assert!(!mainnet.near_period_boundary(8192 + 4096, 8192 + 4160, 3));
+        assert!(mainnet.near_period_boundary(16384 - 96, 16384 - 32, 3));
+        assert!(!mainnet.near_period_boundary(16384 - 128, 16384 - 64, 3));
+        assert!(mainnet.near_period_boundary(16384 - 64, 16384, 0));
+    }
+}
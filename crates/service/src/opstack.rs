@@ -0,0 +1,200 @@
+// OP Stack output roots anchored to the Helios proof chain.
+//
+// After every Helios update, the output stage reads the latest output proposal of a rollup
+// from its L1 contracts at the proven execution state root and proves it with the OP Stack
+// output circuit. The proofs are committed to their own state row, `OPSTACK_CHAIN_ID`.
+
+use anyhow::{Context, Result};
+use opstack_types::{OutputCircuitInputs, OutputSource, verify_claim};
+use sp1_sdk::SP1ProvingKey;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    archive::ProofArchive,
+    control::Stop,
+    encoding::{parse_hex_array, to_hex},
+    preprocessor::opstack::ExecutionRpcClient,
+    prover::prove_anchored,
+    relayers::Relayers,
+    slots::ChainSlots,
+    state::{DEFAULT_CHAIN_ID, DbPool, StateManager},
+    webhooks::Webhooks,
+};
+
+/// The namespace of the state row holding the OP Stack output proofs
+pub const OPSTACK_CHAIN_ID: &str = "opstack";
+
+/// Seconds between two checks for a new Helios update
+const POLL_INTERVAL: u64 = 60;
+
+/// The rollup whose output roots are proven
+#[derive(Debug, Clone)]
+pub struct OpStackConfig {
+    pub source: OutputSource,
+    /// The L2OutputOracle or DisputeGameFactory of the rollup on L1
+    pub contract: [u8; 20],
+    /// Execution RPC of the L1 the storage proofs are fetched from
+    pub execution_rpc: String,
+}
+
+impl OpStackConfig {
+    /// Reads `OP_STACK_L2_OUTPUT_ORACLE` or `OP_STACK_DISPUTE_GAME_FACTORY` together with
+    /// `OP_STACK_GAME_TYPE` (default 0) and `ETHEREUM_URL`. Returns `None` if no rollup is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let oracle = std::env::var("OP_STACK_L2_OUTPUT_ORACLE").ok();
+        let factory = std::env::var("OP_STACK_DISPUTE_GAME_FACTORY").ok();
+        let (source, contract) = match (oracle, factory) {
+            (None, None) => return Ok(None),
+            (Some(oracle), None) => (OutputSource::L2OutputOracle, oracle),
+            (None, Some(factory)) => {
+                let game_type = match std::env::var("OP_STACK_GAME_TYPE") {
+                    Ok(game_type) => game_type
+                        .parse()
+                        .context(format!("Invalid OP_STACK_GAME_TYPE {}", game_type))?,
+                    Err(_) => 0,
+                };
+                (OutputSource::DisputeGameFactory { game_type }, factory)
+            }
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "Set either OP_STACK_L2_OUTPUT_ORACLE or OP_STACK_DISPUTE_GAME_FACTORY, not both"
+                ));
+            }
+        };
+        let contract = parse_hex_array(&contract)
+            .context(format!("Invalid OP Stack contract address {}", contract))?;
+        let execution_rpc = std::env::var("ETHEREUM_URL")
+            .context("ETHEREUM_URL must be set to prove OP Stack output roots")?;
+        Ok(Some(Self {
+            source,
+            contract,
+            execution_rpc,
+        }))
+    }
+}
+
+/// Proves the output root of the rollup at every new Helios update, until `stop` is requested
+pub async fn run_output_loop(
+    db: DbPool,
+    config: OpStackConfig,
+    elf: Vec<u8>,
+    slots: ChainSlots,
+    mut stop: Stop,
+) -> Result<()> {
+    let rpc = ExecutionRpcClient::new(config.execution_rpc.clone());
+    let mut pk: Option<SP1ProvingKey> = None;
+    // The L1 height the outputs were last read at, outputs are only read once per update
+    let mut checked_height = 0;
+    let webhooks = Webhooks::from_env()?;
+    let relayers = Relayers::from_env()?;
+    let archive = ProofArchive::from_env()?;
+    tracing::info!("🚀 Starting OP Stack output service loop...");
+
+    loop {
+        if stop.requested() {
+            tracing::info!("⏸️  Stopping the OP Stack output loop");
+            return Ok(());
+        }
+        let helios_state = StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?.load_state()?;
+        let Some((helios_proof, l1_height, l1_state_root)) = helios_state.and_then(|state| {
+            let proof = state.most_recent_wrapper_proof?;
+            Some((proof, state.trusted_height, state.trusted_root))
+        }) else {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        };
+
+        let state_manager = StateManager::from_pool(&db, OPSTACK_CHAIN_ID)?;
+        let mut service_state = match state_manager.load_state()? {
+            Some(state) => state,
+            None => state_manager.initialize_state(0, 0)?,
+        };
+        if l1_height <= checked_height.max(service_state.trusted_slot) {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        // Read the latest output proposal at the proven L1 block
+        tracing::info!(
+            "🔴 Fetching OP Stack output proposal at L1 height {}...",
+            l1_height
+        );
+        let claim = match rpc
+            .output_claim(config.source, &config.contract, l1_height)
+            .await
+        {
+            Ok(claim) => claim,
+            Err(e) => {
+                tracing::warn!("⚠️  Failed to fetch OP Stack output proposal: {}", e);
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        checked_height = l1_height;
+
+        // Check the proofs before proving, a mismatch means the RPC serves another chain
+        let (l2_block_number, output_root) =
+            match verify_claim(&l1_state_root, config.source, &config.contract, &claim) {
+                Ok(output) => output,
+                Err(e) => {
+                    tracing::warn!(
+                        "⚠️  Output proposal does not verify against the proven state root: {:?}",
+                        e
+                    );
+                    continue;
+                }
+            };
+        if service_state.update_counter > 0 && l2_block_number <= service_state.trusted_height {
+            tracing::info!(
+                "♻️  No output newer than L2 block {}, keeping update {}",
+                service_state.trusted_height,
+                service_state.update_counter
+            );
+            continue;
+        }
+
+        let inputs = OutputCircuitInputs {
+            helios_proof: helios_proof.bytes(),
+            helios_public_values: helios_proof.public_values.to_vec(),
+            claim,
+        };
+        tracing::info!(
+            "⚡ Generating OP Stack output proof for L2 block {}...",
+            l2_block_number
+        );
+        let proof = match prove_anchored("OP Stack output", &elf, &mut pk, &slots, &inputs).await {
+            Ok(proof) => proof,
+            Err(e) => {
+                tracing::error!("❌ {}", e);
+                // Retry the same L1 height in the next round
+                checked_height = 0;
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        service_state.trusted_slot = l1_height;
+        service_state.trusted_height = l2_block_number;
+        service_state.trusted_root = output_root;
+        service_state.most_recent_wrapper_proof = Some(proof);
+        service_state.update_counter += 1;
+        service_state.generated_at = Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("System clock is before the unix epoch")?
+                .as_secs(),
+        );
+        let record = state_manager.commit_update("OPSTACK", &service_state, None, None, &[])?;
+        tracing::info!(
+            "✅ Update {}: OP Stack output of L2 block {} committed - Root: {}, L1 height: {}",
+            record.update_id,
+            l2_block_number,
+            to_hex(output_root),
+            l1_height
+        );
+        tracing::info!("🔗 Update history hash: {}", to_hex(record.hash));
+        webhooks.notify(OPSTACK_CHAIN_ID, "OPSTACK", &record);
+        relayers.relay(OPSTACK_CHAIN_ID, "OPSTACK", &record, &service_state);
+        archive.export_latest(&state_manager, "OPSTACK");
+    }
+}
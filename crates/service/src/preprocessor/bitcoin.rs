@@ -0,0 +1,86 @@
+// Esplora client fetching the Bitcoin block headers proven by the recursion circuit.
+
+use anyhow::{Context, Result};
+use bitcoin_recursion_types::{
+    ChainState,
+    pow::{BlockHeader, MEDIAN_TIME_SPAN, RETARGET_INTERVAL},
+};
+
+/// REST client of an Esplora instance (e.g. https://blockstream.info/api)
+pub struct EsploraClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl EsploraClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: crate::egress::client(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn get(&self, path: &str) -> Result<String> {
+        let url = format!("{}{}", self.url, path);
+        self.client
+            .get(&url)
+            .send()
+            .await
+            .context(format!("Failed to fetch {}", url))?
+            .error_for_status()?
+            .text()
+            .await
+            .context(format!("Failed to read {}", url))
+    }
+
+    /// Height of the best block known to the node
+    pub async fn tip_height(&self) -> Result<u64> {
+        self.get("/blocks/tip/height")
+            .await?
+            .trim()
+            .parse()
+            .context("Invalid tip height")
+    }
+
+    /// Serialized 80-byte header of the block at `height` on the best chain
+    pub async fn header(&self, height: u64) -> Result<Vec<u8>> {
+        let hash = self.get(&format!("/block-height/{}", height)).await?;
+        let header = self.get(&format!("/block/{}/header", hash.trim())).await?;
+        hex::decode(header.trim()).context(format!("Invalid header of block {}", height))
+    }
+
+    /// Headers of the blocks `from..=to`
+    pub async fn headers(&self, from: u64, to: u64) -> Result<Vec<Vec<u8>>> {
+        let mut headers = Vec::new();
+        for height in from..=to {
+            headers.push(self.header(height).await?);
+        }
+        Ok(headers)
+    }
+
+    /// The chain state at `height`, used as the trusted state of the recursion circuit
+    pub async fn chain_state(&self, height: u64) -> Result<ChainState> {
+        let decode = |bytes: &[u8]| {
+            BlockHeader::decode(bytes).map_err(|e| anyhow::anyhow!("Invalid header: {:?}", e))
+        };
+        let tip = decode(&self.header(height).await?)?;
+        let period_start = decode(&self.header(height - height % RETARGET_INTERVAL).await?)?;
+        let first = height.saturating_sub(MEDIAN_TIME_SPAN as u64 - 1);
+        let mut recent_times = Vec::new();
+        for header in self.headers(first, height).await? {
+            recent_times.push(decode(&header)?.time);
+        }
+        Ok(ChainState {
+            height,
+            hash: tip.hash,
+            bits: tip.bits,
+            period_start_time: period_start.time,
+            recent_times,
+            chainwork: 0,
+        })
+    }
+}
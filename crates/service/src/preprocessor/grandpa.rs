@@ -0,0 +1,153 @@
+// Substrate RPC client fetching the GRANDPA finality data proven by the recursion circuit.
+
+use anyhow::{Context, Result};
+use grandpa_recursion_types::{
+    Authority,
+    scale::{FinalityProof, Justification, decode_authorities, encode_header},
+};
+use serde_json::{Value, json};
+
+/// JSON-RPC client of a Substrate node
+pub struct SubstrateRpcClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+/// Everything needed to prove the finality of the next justified block
+pub struct GrandpaRound {
+    /// SCALE-encoded justification of the target block
+    pub justification: Vec<u8>,
+    /// SCALE-encoded header of the target block
+    pub header: Vec<u8>,
+    pub target_number: u64,
+}
+
+impl SubstrateRpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: crate::egress::client(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .context(format!("Failed to call {}", method))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Hash of the block at `number` on the canonical chain
+    pub async fn block_hash(&self, number: u64) -> Result<[u8; 32]> {
+        let hash = self.call("chain_getBlockHash", json!([number])).await?;
+        decode_hex(&hash).and_then(|bytes| {
+            bytes
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid hash of block {}", number))
+        })
+    }
+
+    /// Number of the latest finalized block
+    pub async fn finalized_number(&self) -> Result<u64> {
+        let hash = self.call("chain_getFinalizedHead", json!([])).await?;
+        let header = self.call("chain_getHeader", json!([hash])).await?;
+        parse_number(&header["number"])
+    }
+
+    /// SCALE-encoded header of a block, rebuilt from the JSON header
+    pub async fn header(&self, hash: &[u8; 32]) -> Result<Vec<u8>> {
+        let header = self
+            .call(
+                "chain_getHeader",
+                json!([format!("0x{}", hex::encode(hash))]),
+            )
+            .await?;
+        let hash_field = |field: &str| -> Result<[u8; 32]> {
+            decode_hex(&header[field])?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid {} in header", field))
+        };
+        let number = u32::try_from(parse_number(&header["number"])?)?;
+        // Digest logs are already SCALE-encoded digest items
+        let logs = header["digest"]["logs"]
+            .as_array()
+            .context("Header without digest logs")?
+            .iter()
+            .map(decode_hex)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(encode_header(
+            &hash_field("parentHash")?,
+            number,
+            &hash_field("stateRoot")?,
+            &hash_field("extrinsicsRoot")?,
+            &logs,
+        ))
+    }
+
+    /// The GRANDPA authority set and set id in the state of the given block
+    pub async fn authorities(&self, at: &[u8; 32]) -> Result<(Vec<Authority>, u64)> {
+        let at = format!("0x{}", hex::encode(at));
+        let authorities = self
+            .call(
+                "state_call",
+                json!(["GrandpaApi_grandpa_authorities", "0x", at]),
+            )
+            .await?;
+        let authorities = decode_authorities(&decode_hex(&authorities)?)
+            .map_err(|e| anyhow::anyhow!("Failed to decode authorities: {:?}", e))?;
+        let set_id = self
+            .call("state_call", json!(["GrandpaApi_current_set_id", "0x", at]))
+            .await?;
+        let set_id = u64::from_le_bytes(
+            decode_hex(&set_id)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid authority set id"))?,
+        );
+        Ok((authorities, set_id))
+    }
+
+    /// Fetches the justification of the first justified block finalizing `number`.
+    ///
+    /// The node returns the justification of the last block of the authority set that
+    /// finalized `number`, or the latest justification if that set is still active.
+    pub async fn next_round(&self, number: u64) -> Result<Option<GrandpaRound>> {
+        let proof = self.call("grandpa_proveFinality", json!([number])).await?;
+        if proof.is_null() {
+            return Ok(None);
+        }
+        let proof = FinalityProof::decode(&decode_hex(&proof)?)
+            .map_err(|e| anyhow::anyhow!("Failed to decode finality proof: {:?}", e))?;
+        let justification = Justification::decode(&proof.justification)
+            .map_err(|e| anyhow::anyhow!("Failed to decode justification: {:?}", e))?;
+        let header = self.header(&proof.block).await?;
+        Ok(Some(GrandpaRound {
+            justification: proof.justification,
+            header,
+            target_number: justification.target_number as u64,
+        }))
+    }
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>> {
+    let value = value.as_str().context("Expected a hex string")?;
+    hex::decode(value.trim_start_matches("0x")).context("Invalid hex")
+}
+
+fn parse_number(value: &Value) -> Result<u64> {
+    let value = value.as_str().context("Expected a hex number")?;
+    u64::from_str_radix(value.trim_start_matches("0x"), 16).context("Invalid block number")
+}
@@ -1,53 +1,160 @@
 use alloy_primitives::B256;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use helios_consensus_core::{
     calc_sync_period,
     consensus_spec::MainnetConsensusSpec,
-    types::{BeaconBlock, Update},
+    types::{BeaconBlock, FinalityUpdate, Update},
 };
 use helios_ethereum::{
-    config::{Config, networks::Network},
     consensus::Inner,
     rpc::{ConsensusRpc, http_rpc::HttpRpc},
 };
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
 use tokio::sync::{mpsc::channel, watch};
 use tree_hash::TreeHash;
 
+use super::metrics::record_rpc;
+use crate::networks::EthereumNetwork;
+
 use anyhow::Result as AnyResult;
 
-/// Fetch updates for client
+/// Maximum number of light client updates a beacon node serves per request,
+/// `MAX_REQUEST_LIGHT_CLIENT_UPDATES` in the consensus specs
+const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u64 = 128;
+
+/// Most light client updates cached, the lowest periods are evicted first since the proof
+/// chain never goes back to them
+const MAX_CACHED_UPDATES: usize = 256;
+
+/// Light client updates by chain id and sync committee period, shared by the rounds of the
+/// process. Every update of a period carries the same next sync committee, the one Helios
+/// rotates to, so a cached update stays valid when a better one is published later
+static UPDATES: Mutex<BTreeMap<(u64, u64), Update<MainnetConsensusSpec>>> =
+    Mutex::new(BTreeMap::new());
+
+fn cached_update(chain_id: u64, period: u64) -> Option<Update<MainnetConsensusSpec>> {
+    UPDATES.lock().unwrap().get(&(chain_id, period)).cloned()
+}
+
+fn cache_updates(chain_id: u64, updates: &[Update<MainnetConsensusSpec>]) {
+    let mut cache = UPDATES.lock().unwrap();
+    for update in updates {
+        let period =
+            calc_sync_period::<MainnetConsensusSpec>(update.attested_header().beacon().slot);
+        cache.insert((chain_id, period), update.clone());
+    }
+    while cache.len() > MAX_CACHED_UPDATES {
+        cache.pop_first();
+    }
+}
+
+/// Fetches the update of `period` in the background unless it is cached, so that the round
+/// rotating to the committee it carries finds it in the cache
+pub fn prefetch_update(network: EthereumNetwork, period: u64) {
+    let chain_id = network.chain_id();
+    if cached_update(chain_id, period).is_some() {
+        return;
+    }
+    let Ok(consensus_rpc) = std::env::var("SOURCE_CONSENSUS_RPC_URL") else {
+        return;
+    };
+    tokio::spawn(async move {
+        let rpc = HttpRpc::new(&consensus_rpc);
+        match record_rpc("updates", rpc.get_updates(period, 1)).await {
+            Ok(updates) => {
+                tracing::info!("📥 Prefetched the light client update of period {}", period);
+                cache_updates(chain_id, &updates);
+            }
+            Err(e) => tracing::warn!(
+                "⚠️  Failed to prefetch the light client update of period {}: {}",
+                period,
+                e
+            ),
+        }
+    });
+}
+
+/// Fetch the updates of `period_distance` sync committee periods, starting at the period of
+/// the client's finalized header.
+///
+/// The updates of the first periods are reused from the cache of earlier rounds. Beacon nodes
+/// cap the number of updates per request, so the rest of the range is fetched in chunks.
+/// Every update must be for the period following the previous one, also across chunks. A node
+/// may not serve the updates of the latest periods yet, fetching stops at the first short chunk.
 pub async fn get_updates(
     client: &Inner<MainnetConsensusSpec, HttpRpc>,
-    update_count: u8,
+    network: EthereumNetwork,
+    period_distance: u64,
 ) -> AnyResult<Vec<Update<MainnetConsensusSpec>>> {
-    let period =
+    let start_period =
         calc_sync_period::<MainnetConsensusSpec>(client.store.finalized_header.beacon().slot);
+    let chain_id = network.chain_id();
 
-    let updates = client
-        .rpc
-        .get_updates(period, update_count)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to get updates: {}", e))?;
+    let mut updates: Vec<Update<MainnetConsensusSpec>> = Vec::new();
+    while (updates.len() as u64) < period_distance {
+        match cached_update(chain_id, start_period + updates.len() as u64) {
+            Some(update) => updates.push(update),
+            None => break,
+        }
+    }
+    let cached = updates.len();
+    if cached > 0 {
+        tracing::debug!("📦 {} light client updates served from the cache", cached);
+    }
+    while (updates.len() as u64) < period_distance {
+        let period = start_period + updates.len() as u64;
+        let count = (period_distance - updates.len() as u64).min(MAX_REQUEST_LIGHT_CLIENT_UPDATES);
+        let chunk = record_rpc("updates", client.rpc.get_updates(period, count as u8))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get updates: {}", e))?;
+        let received = chunk.len() as u64;
+
+        for update in chunk {
+            let expected_period = start_period + updates.len() as u64;
+            let update_period =
+                calc_sync_period::<MainnetConsensusSpec>(update.attested_header().beacon().slot);
+            if update_period != expected_period {
+                return Err(anyhow::anyhow!(
+                    "Light client updates are not continuous, expected period {} but got {}",
+                    expected_period,
+                    update_period
+                ));
+            }
+            updates.push(update);
+        }
+
+        if received < count {
+            break;
+        }
+    }
 
-    Ok(updates.clone())
+    cache_updates(chain_id, &updates[cached..]);
+    Ok(updates)
+}
+
+/// The finality update of a light client update: its attested and finalized headers and
+/// their signature, without the next sync committee it carries. A round catching up on a node
+/// that doesn't serve the updates of the latest periods proves the finalized header of the last
+/// served update with it.
+pub fn finality_update(
+    update: &Update<MainnetConsensusSpec>,
+) -> AnyResult<FinalityUpdate<MainnetConsensusSpec>> {
+    let mut update = serde_json::to_value(update)?;
+    let fields = update
+        .as_object_mut()
+        .context("Light client update is not an object")?;
+    fields.remove("next_sync_committee");
+    fields.remove("next_sync_committee_branch");
+    serde_json::from_value(update).context("Failed to convert the light client update")
 }
 
 /// Fetch checkpoint from a slot number.
-pub async fn get_checkpoint(slot: u64) -> Result<B256> {
+pub async fn get_checkpoint(slot: u64, network: EthereumNetwork) -> Result<B256> {
     let consensus_rpc = std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap();
-    let chain_id = std::env::var("SOURCE_CHAIN_ID").unwrap();
-    let network = Network::from_chain_id(chain_id.parse().unwrap()).unwrap();
-    let base_config = network.to_base_config();
-
-    let config = Config {
-        consensus_rpc: consensus_rpc.to_string(),
-        execution_rpc: None,
-        chain: base_config.chain,
-        forks: base_config.forks,
-        strict_checkpoint_age: false,
-        ..Default::default()
-    };
+    let config = network.helios_config(&consensus_rpc)?;
 
     let (block_send, _) = channel(256);
     let (finalized_block_send, _) = watch::channel(None);
@@ -60,30 +167,21 @@ pub async fn get_checkpoint(slot: u64) -> Result<B256> {
         Arc::new(config),
     );
 
-    let block: BeaconBlock<MainnetConsensusSpec> = client
-        .rpc
-        .get_block(slot)
-        .await
-        .map_err(|e| anyhow::anyhow!("error getting block: {}", e.to_string()))?;
+    let block: BeaconBlock<MainnetConsensusSpec> =
+        record_rpc("checkpoint", client.rpc.get_block(slot))
+            .await
+            .map_err(|e| anyhow::anyhow!("error getting block: {}", e.to_string()))?;
 
     Ok(B256::from_slice(block.tree_hash_root().as_ref()))
 }
 
 /// Setup a client from a checkpoint.
-pub async fn get_client(checkpoint: B256) -> Result<Inner<MainnetConsensusSpec, HttpRpc>> {
+pub async fn get_client(
+    checkpoint: B256,
+    network: EthereumNetwork,
+) -> Result<Inner<MainnetConsensusSpec, HttpRpc>> {
     let consensus_rpc = std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap();
-    let chain_id = std::env::var("SOURCE_CHAIN_ID").unwrap();
-    let network = Network::from_chain_id(chain_id.parse().unwrap()).unwrap();
-    let base_config = network.to_base_config();
-
-    let config = Config {
-        consensus_rpc: consensus_rpc.to_string(),
-        execution_rpc: None,
-        chain: base_config.chain,
-        forks: base_config.forks,
-        strict_checkpoint_age: false,
-        ..Default::default()
-    };
+    let config = network.helios_config(&consensus_rpc)?;
 
     let (block_send, _) = channel(256);
     let (finalized_block_send, _) = watch::channel(None);
@@ -97,8 +195,7 @@ pub async fn get_client(checkpoint: B256) -> Result<Inner<MainnetConsensusSpec,
         Arc::new(config),
     );
 
-    client
-        .bootstrap(checkpoint)
+    record_rpc("bootstrap", client.bootstrap(checkpoint))
         .await
         .map_err(|e| anyhow::anyhow!("error bootstrapping client: {}", e.to_string()))?;
     Ok(client)
@@ -7,7 +7,7 @@ pub async fn get_execution_block_height_from_slot(
     slot: u64,
 ) -> Result<u64, Box<dyn std::error::Error>> {
     let url = format!("{}/eth/v2/beacon/blocks/{}", beacon_node_url, slot);
-    let client = reqwest::Client::new();
+    let client = crate::egress::client();
     let res = client.get(&url).send().await?.error_for_status()?;
     let json: Value = res.json().await?;
     let block_number = json["data"]["message"]["body"]["execution_payload"]["block_number"]
@@ -0,0 +1,110 @@
+// CometBFT RPC client fetching the headers and the ICS23 store proofs verified by the ICS23
+// proof circuit.
+
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use ics23_proof_types::StoreQuery;
+use serde_json::{Value, json};
+
+/// JSON-RPC client of a CometBFT node
+pub struct CometRpcClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl CometRpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: crate::egress::client(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .context(format!("Failed to call {}", method))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// The JSON header of the block at `height`
+    pub async fn header(&self, height: u64) -> Result<Value> {
+        let commit = self
+            .call("commit", json!({ "height": height.to_string() }))
+            .await?;
+        let header = commit["signed_header"]["header"].clone();
+        if header.is_null() {
+            return Err(anyhow::anyhow!("Missing header of block {}", height));
+        }
+        Ok(header)
+    }
+
+    /// The value of `key` in the module store `store_key` after executing block `height`,
+    /// with the proofs against the app hash of the header at `height + 1`
+    pub async fn store_query(
+        &self,
+        store_key: &str,
+        key: &[u8],
+        height: u64,
+    ) -> Result<StoreQuery> {
+        let result = self
+            .call(
+                "abci_query",
+                json!({
+                    "path": format!("/store/{}/key", store_key),
+                    "data": hex::encode(key),
+                    "height": height.to_string(),
+                    "prove": true,
+                }),
+            )
+            .await?;
+        let response = &result["response"];
+        if response["code"].as_u64().unwrap_or(0) != 0 {
+            return Err(anyhow::anyhow!(
+                "abci_query of {} failed: {}",
+                store_key,
+                response["log"]
+            ));
+        }
+        let value = decode_base64(&response["value"])?;
+        if value.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Key 0x{} is not set in store {}",
+                hex::encode(key),
+                store_key
+            ));
+        }
+
+        // The IAVL proof of the key comes first, then the proof of the store in the multistore
+        let ops = response["proofOps"]["ops"]
+            .as_array()
+            .context("Missing proof ops, the node may not serve proofs at this height")?;
+        let [iavl, store] = ops.as_slice() else {
+            return Err(anyhow::anyhow!("Expected 2 proof ops, got {}", ops.len()));
+        };
+        Ok(StoreQuery {
+            store_key: store_key.as_bytes().to_vec(),
+            key: key.to_vec(),
+            value,
+            iavl_proof: decode_base64(&iavl["data"])?,
+            store_proof: decode_base64(&store["data"])?,
+        })
+    }
+}
+
+fn decode_base64(value: &Value) -> Result<Vec<u8>> {
+    match value.as_str() {
+        Some(encoded) => BASE64.decode(encoded).context("Invalid base64"),
+        None => Ok(Vec::new()),
+    }
+}
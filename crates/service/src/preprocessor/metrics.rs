@@ -0,0 +1,104 @@
+// Metrics of the preprocessor, served with the chain stats.
+//
+// Every consensus RPC call of the preprocessor is counted and timed by kind (the checkpoint
+// block, the bootstrap, the update ranges, the finality update and the finalized header), so
+// providers can be compared by latency and error rate. Each run of the Helios preprocessor also
+// records the number and size of the updates it fetched and how many slots the finalized header
+// of its finality update lags behind the head of the chain, and logs them as structured fields.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, future::Future, sync::Mutex, time::Instant};
+
+/// Calls of one kind to the consensus RPC
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcStats {
+    pub requests: u64,
+    pub errors: u64,
+    /// Duration of the latest call, in milliseconds
+    pub last_ms: u64,
+    /// Total duration of all calls, in milliseconds
+    pub total_ms: u64,
+}
+
+/// The latest run of the Helios preprocessor
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosRun {
+    /// Light client updates fetched
+    pub updates: u64,
+    /// Size of the fetched updates in the program inputs, in bytes
+    pub updates_bytes: u64,
+    /// Slots between the head of the chain and the finalized header of the finality update
+    pub finality_lag_slots: u64,
+}
+
+/// The preprocessor metrics of this process
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreprocessorStats {
+    /// Completed preprocessor runs
+    pub runs: u64,
+    /// RPC calls by kind
+    pub rpc: BTreeMap<String, RpcStats>,
+    pub last_helios_run: Option<HeliosRun>,
+}
+
+static STATS: Mutex<PreprocessorStats> = Mutex::new(PreprocessorStats {
+    runs: 0,
+    rpc: BTreeMap::new(),
+    last_helios_run: None,
+});
+
+/// The preprocessor metrics of this process, `None` until the preprocessor called its RPC
+pub fn stats() -> Option<PreprocessorStats> {
+    let stats = STATS.lock().unwrap();
+    (!stats.rpc.is_empty()).then(|| stats.clone())
+}
+
+/// Runs an RPC call of kind `call`, counting it and its duration
+pub async fn record_rpc<T, E>(
+    call: &str,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = request.await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    let mut stats = STATS.lock().unwrap();
+    let rpc = stats.rpc.entry(call.to_string()).or_default();
+    rpc.requests += 1;
+    rpc.errors += result.is_err() as u64;
+    rpc.last_ms = elapsed_ms;
+    rpc.total_ms += elapsed_ms;
+    result
+}
+
+/// Records a completed run of the Helios preprocessor
+pub fn record_helios_run(run: HeliosRun) {
+    tracing::info!(
+        updates = run.updates,
+        updates_bytes = run.updates_bytes,
+        finality_lag_slots = run.finality_lag_slots,
+        "📊 Helios preprocessor run"
+    );
+    let mut stats = STATS.lock().unwrap();
+    stats.runs += 1;
+    stats.last_helios_run = Some(run);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rpc_calls_are_counted_by_kind() {
+        record_rpc("test_call", async { Ok::<_, ()>(()) })
+            .await
+            .unwrap();
+        record_rpc("test_call", async { Err::<(), _>("unavailable") })
+            .await
+            .unwrap_err();
+
+        let rpc = stats().unwrap().rpc["test_call"].clone();
+        assert_eq!(rpc.requests, 2);
+        assert_eq!(rpc.errors, 1);
+    }
+}
@@ -4,10 +4,28 @@ use serde_json::Value;
 use sp1_helios_primitives::types::ProofInputs;
 use std::env;
 use tracing::info;
+use tree_hash::TreeHash;
 
-use crate::preprocessor::helios::{get_checkpoint, get_client, get_updates};
+use crate::{
+    backends::UpdatesBehind,
+    networks::EthereumNetwork,
+    preprocessor::{
+        helios::{finality_update, get_checkpoint, get_client, get_updates, prefetch_update},
+        metrics::{HeliosRun, record_helios_run, record_rpc},
+    },
+};
+pub mod bitcoin;
+pub mod grandpa;
 mod helios;
 mod helpers;
+pub mod ics23;
+pub mod metrics;
+pub mod opstack;
+pub mod tendermint;
+
+/// Epochs before a sync committee period boundary the update carrying the next committee is
+/// prefetched, about 50 minutes on mainnet
+const PREFETCH_EPOCHS: u64 = 8;
 
 /// Type alias for the serialized Helios program inputs
 pub type HeliosInputSlice = Vec<u8>;
@@ -23,12 +41,17 @@ pub type HeliosInputSlice = Vec<u8>;
 pub struct Preprocessor {
     /// The trusted slot to use as a reference point
     pub trusted_slot: u64,
+    /// The network followed by the light client
+    pub network: EthereumNetwork,
 }
 
 impl Preprocessor {
     /// Creates a new Preprocessor instance with the given trusted slot
-    pub fn new(trusted_slot: u64) -> Self {
-        Self { trusted_slot }
+    pub fn new(trusted_slot: u64, network: EthereumNetwork) -> Self {
+        Self {
+            trusted_slot,
+            network,
+        }
     }
 
     /// Runs the preprocessing pipeline to generate inputs for the Helios program.
@@ -40,39 +63,75 @@ impl Preprocessor {
     /// 4. Fetches updates and finality data
     /// 5. Serializes everything into the format expected by the Helios program
     pub async fn run(&self) -> Result<HeliosInputSlice> {
-        let checkpoint = get_checkpoint(self.trusted_slot).await?;
-        let client = get_client(checkpoint).await?;
-        let trusted_slot_period = &self.trusted_slot / 8192;
+        let checkpoint = get_checkpoint(self.trusted_slot, self.network).await?;
+        let client = get_client(checkpoint, self.network).await?;
+        let slots_per_epoch = self.network.slots_per_epoch();
+        let slots_per_period = self.network.slots_per_period();
+        let trusted_slot_period = &self.trusted_slot / slots_per_period;
         let latest_slot = gest_latest_slot().await?;
-        // we only get a finality update every 32 slots, so we need to wait for the
-        // latest finalized slot to be at least 32 slots ahead of the trusted slot
-        if latest_slot <= self.trusted_slot || latest_slot / 32 < self.trusted_slot / 32 {
+        // we only get a finality update once per epoch, so we need to wait for the
+        // latest finalized slot to be at least one epoch ahead of the trusted slot
+        if latest_slot <= self.trusted_slot
+            || latest_slot / slots_per_epoch < self.trusted_slot / slots_per_epoch
+        {
             return Err(anyhow::anyhow!(
                 "Waiting for new slot to be finalized, retry in 60 seconds!"
             ));
         }
 
-        let latest_finalized_slot = latest_slot - (latest_slot % 32);
+        let latest_finalized_slot = latest_slot - (latest_slot % slots_per_epoch);
         info!(
             "latest_finalized_slot: {}, trusted_slot: {}",
             latest_finalized_slot, self.trusted_slot
         );
-        let latest_finalized_slot_period = latest_finalized_slot / 8192;
+        let latest_finalized_slot_period = latest_finalized_slot / slots_per_period;
         let mut period_distance = latest_finalized_slot_period - trusted_slot_period;
         if period_distance == 0 {
             // minimum period distance is 1
             period_distance = 1;
         }
-        let updates = get_updates(&client, period_distance as u8)
+        // Shortly before a period boundary, fetch the update carrying the next committee ahead
+        // of the round rotating to it
+        if self.network.epochs_until_next_period(latest_slot) <= PREFETCH_EPOCHS {
+            prefetch_update(self.network, latest_slot / slots_per_period);
+        }
+        let updates = get_updates(&client, self.network, period_distance)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get updates: {}", e))?;
-        let finality_update = client
-            .rpc
-            .get_finality_update()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to get finality update: {}", e))?;
+        // A node that pruned or hasn't backfilled the updates of the latest periods ends the
+        // range early, and the light client can't verify a finality update past the periods it
+        // has updates for. The round then proves the finalized header of the last served update
+        // and the next rounds continue from it
+        let needed = latest_finalized_slot_period - trusted_slot_period;
+        let last_finalized_slot = updates
+            .last()
+            .map(|update| update.finalized_header().beacon().slot);
+        let finality_update = if catch_up(
+            updates.len() as u64,
+            needed,
+            last_finalized_slot,
+            self.trusted_slot,
+        )? {
+            info!(
+                "⏩ Node serves {} of {} light client updates, catching up to slot {}",
+                updates.len(),
+                needed,
+                last_finalized_slot.unwrap_or_default()
+            );
+            finality_update(updates.last().context("No light client update served")?)?
+        } else {
+            record_rpc("finality_update", client.rpc.get_finality_update())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get finality update: {}", e))?
+        };
         // Create program inputs
         let expected_current_slot = client.expected_current_slot();
+        record_helios_run(HeliosRun {
+            updates: updates.len() as u64,
+            updates_bytes: serde_cbor::to_vec(&updates)?.len() as u64,
+            finality_lag_slots: expected_current_slot
+                .saturating_sub(finality_update.finalized_header().beacon().slot),
+        });
         let inputs = ProofInputs {
             updates,
             finality_update,
@@ -85,16 +144,51 @@ impl Preprocessor {
     }
 }
 
+/// Whether a round proves the last of the `served` light client updates instead of the latest
+/// finality update, because the node served fewer than the `needed` updates of the periods up
+/// to it. A round only catches up if the last served update, finalizing
+/// `last_finalized_slot`, advances past the trusted slot, it waits for the node otherwise.
+fn catch_up(
+    served: u64,
+    needed: u64,
+    last_finalized_slot: Option<u64>,
+    trusted_slot: u64,
+) -> Result<bool, UpdatesBehind> {
+    if served >= needed {
+        return Ok(false);
+    }
+    match last_finalized_slot {
+        Some(slot) if slot > trusted_slot => Ok(true),
+        _ => Err(UpdatesBehind { served, needed }),
+    }
+}
+
+/// Derives the hash of the sync committee the light client trusts when bootstrapped at `slot`,
+/// the `TRUSTED_SYNC_COMMITTEE_HASH` baked into the Helios recursion circuit.
+///
+/// The hash is the `tree_hash` root of the current sync committee of the bootstrap at the
+/// block of `slot`, which anyone can reproduce from a consensus node.
+pub async fn sync_committee_hash(slot: u64, network: EthereumNetwork) -> Result<[u8; 32]> {
+    let checkpoint = get_checkpoint(slot, network).await?;
+    let client = get_client(checkpoint, network).await?;
+    Ok(client.store.current_sync_committee.tree_hash_root().0)
+}
+
 /// Fetches the latest finalized slot from the consensus layer.
 ///
 /// This function makes an RPC call to the consensus client to get
 /// the most recently finalized slot number.
 pub async fn gest_latest_slot() -> Result<u64> {
     let consensus_url = env::var("SOURCE_CONSENSUS_RPC_URL")?;
-    let resp: Value = reqwest::get(format!("{}/eth/v1/beacon/headers/finalized", consensus_url))
-        .await?
-        .json()
-        .await?;
+    let resp: Value = record_rpc("finalized_header", async {
+        crate::egress::client()
+            .get(format!("{}/eth/v1/beacon/headers/finalized", consensus_url))
+            .send()
+            .await?
+            .json()
+            .await
+    })
+    .await?;
 
     let slot_str = resp["data"]["header"]["message"]["slot"]
         .as_str()
@@ -103,3 +197,26 @@ pub async fn gest_latest_slot() -> Result<u64> {
     let slot = slot_str.parse::<u64>()?;
     Ok(slot)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_up_on_partial_updates() {
+        // The node serves every update up to the latest finality update
+        assert_eq!(catch_up(3, 3, Some(24_000), 8_000), Ok(false));
+        assert_eq!(catch_up(1, 0, Some(8_100), 8_000), Ok(false));
+        // It serves 2 of 300 updates, the round proves the last one and the next continue
+        assert_eq!(catch_up(2, 300, Some(16_300), 8_000), Ok(true));
+        // Nothing served past the trusted slot, the round waits for the node
+        assert_eq!(
+            catch_up(0, 300, None, 8_000),
+            Err(UpdatesBehind {
+                served: 0,
+                needed: 300
+            })
+        );
+        assert!(catch_up(1, 300, Some(7_900), 8_000).is_err());
+    }
+}
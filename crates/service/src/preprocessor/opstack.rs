@@ -0,0 +1,258 @@
+// Execution RPC client fetching the OP Stack output proposals and the L1 storage proofs
+// verified by the OP Stack output circuit.
+
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{SolCall, sol};
+use anyhow::{Context, Result};
+use opstack_types::{
+    AccountProof, DEFENDER_WINS, OutputClaim, OutputSource, StorageProof, dispute_game_slot,
+    game_uuid, l2_output_slots,
+};
+use serde_json::{Value, json};
+
+sol! {
+    function latestOutputIndex() external view returns (uint256);
+    function gameCount() external view returns (uint256);
+    function gameAtIndex(uint256 index) external view returns (uint32 gameType, uint64 timestamp, address proxy);
+    function status() external view returns (uint8);
+    function rootClaim() external view returns (bytes32);
+    function l2BlockNumber() external view returns (uint256);
+}
+
+/// Number of the most recent dispute games searched for one that resolved in favour of
+/// the proposer, games resolve days after their creation
+const MAX_GAMES_SEARCHED: u64 = 512;
+
+/// The fields of an execution block header linking it to its parent and its state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionHeader {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+    pub state_root: [u8; 32],
+}
+
+/// JSON-RPC client of an Ethereum execution node
+pub struct ExecutionRpcClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl ExecutionRpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: crate::egress::client(),
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .context(format!("Failed to call {}", method))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Calls a view function of `to` at `block`
+    async fn call<C: SolCall>(&self, to: &[u8; 20], call: C, block: u64) -> Result<C::Return> {
+        let data = self
+            .request(
+                "eth_call",
+                json!([
+                    {
+                        "to": Address::from(*to),
+                        "data": format!("0x{}", hex::encode(call.abi_encode())),
+                    },
+                    format!("{:#x}", block),
+                ]),
+            )
+            .await?;
+        C::abi_decode_returns(&decode_hex(&data)?, true)
+            .context(format!("Invalid return data of {}", C::SIGNATURE))
+    }
+
+    /// Number of the latest block
+    pub async fn block_number(&self) -> Result<u64> {
+        let number = self.request("eth_blockNumber", json!([])).await?;
+        Ok(parse_word(&number)?.try_into()?)
+    }
+
+    /// The header of the canonical block at `block`
+    pub async fn header(&self, block: u64) -> Result<ExecutionHeader> {
+        let header = self
+            .request(
+                "eth_getBlockByNumber",
+                json!([format!("{:#x}", block), false]),
+            )
+            .await?;
+        if header.is_null() {
+            return Err(anyhow::anyhow!("The execution RPC has no block {}", block));
+        }
+        Ok(ExecutionHeader {
+            number: parse_word(&header["number"])?.try_into()?,
+            hash: decode_word(&header["hash"])?,
+            parent_hash: decode_word(&header["parentHash"])?,
+            state_root: decode_word(&header["stateRoot"])?,
+        })
+    }
+
+    /// Account proof of `address` and storage proofs of `slots` at `block`
+    pub async fn get_proof(
+        &self,
+        address: &[u8; 20],
+        slots: &[[u8; 32]],
+        block: u64,
+    ) -> Result<(AccountProof, Vec<StorageProof>)> {
+        let slots: Vec<String> = slots
+            .iter()
+            .map(|slot| format!("0x{}", hex::encode(slot)))
+            .collect();
+        let response = self
+            .request(
+                "eth_getProof",
+                json!([Address::from(*address), slots, format!("{:#x}", block)]),
+            )
+            .await?;
+
+        let account = AccountProof {
+            address: *address,
+            nonce: parse_word(&response["nonce"])?.try_into()?,
+            balance: parse_word(&response["balance"])?.to_be_bytes(),
+            storage_root: decode_word(&response["storageHash"])?,
+            code_hash: decode_word(&response["codeHash"])?,
+            proof: decode_proof(&response["accountProof"])?,
+        };
+        let storage = response["storageProof"]
+            .as_array()
+            .context("Missing storage proofs")?
+            .iter()
+            .map(|storage| {
+                Ok(StorageProof {
+                    slot: parse_word(&storage["key"])?.to_be_bytes(),
+                    value: parse_word(&storage["value"])?.to_be_bytes(),
+                    proof: decode_proof(&storage["proof"])?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((account, storage))
+    }
+
+    /// The latest output proposal of the rollup at L1 block `block`, with its storage proofs
+    pub async fn output_claim(
+        &self,
+        source: OutputSource,
+        contract: &[u8; 20],
+        block: u64,
+    ) -> Result<OutputClaim> {
+        match source {
+            OutputSource::L2OutputOracle => {
+                let index: u64 = self
+                    .call(contract, latestOutputIndexCall {}, block)
+                    .await?
+                    ._0
+                    .try_into()?;
+                let (output_root_slot, block_slot) = l2_output_slots(index);
+                let (oracle, mut storage) = self
+                    .get_proof(contract, &[output_root_slot, block_slot], block)
+                    .await?;
+                let block = storage.pop().context("Missing storage proof")?;
+                let output_root = storage.pop().context("Missing storage proof")?;
+                Ok(OutputClaim::L2OutputOracle {
+                    oracle,
+                    index,
+                    output_root,
+                    block,
+                })
+            }
+            OutputSource::DisputeGameFactory { game_type } => {
+                let count: u64 = self
+                    .call(contract, gameCountCall {}, block)
+                    .await?
+                    ._0
+                    .try_into()?;
+                for index in (count.saturating_sub(MAX_GAMES_SEARCHED)..count).rev() {
+                    let game = self
+                        .call(
+                            contract,
+                            gameAtIndexCall {
+                                index: U256::from(index),
+                            },
+                            block,
+                        )
+                        .await?;
+                    if game.gameType != game_type {
+                        continue;
+                    }
+                    let proxy = game.proxy.into_array();
+                    if self.call(&proxy, statusCall {}, block).await?._0 != DEFENDER_WINS {
+                        continue;
+                    }
+
+                    let root_claim = self.call(&proxy, rootClaimCall {}, block).await?._0.0;
+                    let l2_block_number: u64 = self
+                        .call(&proxy, l2BlockNumberCall {}, block)
+                        .await?
+                        ._0
+                        .try_into()?;
+                    let game_id_slot =
+                        dispute_game_slot(&game_uuid(game_type, &root_claim, l2_block_number));
+                    let (factory, mut game_id) =
+                        self.get_proof(contract, &[game_id_slot], block).await?;
+                    let (game, mut status) = self.get_proof(&proxy, &[[0; 32]], block).await?;
+                    return Ok(OutputClaim::DisputeGame {
+                        factory,
+                        root_claim,
+                        l2_block_number,
+                        game_id: game_id.pop().context("Missing storage proof")?,
+                        game,
+                        status: status.pop().context("Missing storage proof")?,
+                    });
+                }
+                Err(anyhow::anyhow!(
+                    "No dispute game of type {} resolved in favour of the proposer among the last {} games",
+                    game_type,
+                    MAX_GAMES_SEARCHED
+                ))
+            }
+        }
+    }
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>> {
+    let hex_str = value.as_str().context("Expected a hex string")?;
+    hex::decode(hex_str.trim_start_matches("0x")).context("Invalid hex")
+}
+
+/// Parses a hex quantity, which may omit leading zeros
+fn parse_word(value: &Value) -> Result<U256> {
+    value
+        .as_str()
+        .context("Expected a hex quantity")?
+        .parse()
+        .context(format!("Invalid hex quantity {}", value))
+}
+
+fn decode_word(value: &Value) -> Result<[u8; 32]> {
+    decode_hex(value)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected a 32 byte word, got {}", value))
+}
+
+fn decode_proof(value: &Value) -> Result<Vec<Vec<u8>>> {
+    value
+        .as_array()
+        .context("Expected a list of proof nodes")?
+        .iter()
+        .map(decode_hex)
+        .collect()
+}
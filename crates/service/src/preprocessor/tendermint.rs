@@ -0,0 +1,162 @@
+// On-disk cache of the Tendermint light blocks fetched for base proofs.
+//
+// Every round fetches the light block at the trusted height and at the target height. The
+// trusted block is the target of the previous round, and retries and catch-up batches ask
+// for the same heights again, so light blocks are cached by height under
+// `LIGHT_BLOCK_CACHE_DIR/<chain id>/` and only the missing ones are fetched from the RPC.
+// The cache of a chain is capped at `LIGHT_BLOCK_CACHE_MAX_MB` (256 by default), the lowest
+// heights are evicted first since the proof chain never goes back to them.
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{future::Future, path::PathBuf};
+
+const DEFAULT_MAX_MB: u64 = 256;
+
+/// Light blocks of one chain, cached by height. Disabled unless `LIGHT_BLOCK_CACHE_DIR` is set
+#[derive(Debug, Clone, Default)]
+pub struct LightBlockCache {
+    dir: Option<PathBuf>,
+    max_bytes: u64,
+}
+
+impl LightBlockCache {
+    pub fn from_env(chain_id: &str) -> Result<Self> {
+        let Ok(dir) = std::env::var("LIGHT_BLOCK_CACHE_DIR") else {
+            return Ok(Self::default());
+        };
+        let max_mb = match std::env::var("LIGHT_BLOCK_CACHE_MAX_MB") {
+            Ok(max_mb) => max_mb.parse().map_err(|_| {
+                anyhow!(
+                    "Invalid LIGHT_BLOCK_CACHE_MAX_MB {}, expected an integer",
+                    max_mb
+                )
+            })?,
+            Err(_) => DEFAULT_MAX_MB,
+        };
+        Ok(Self::new(
+            PathBuf::from(dir).join(chain_id),
+            max_mb * 1024 * 1024,
+        ))
+    }
+
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            dir: Some(dir),
+            max_bytes,
+        }
+    }
+
+    /// The light block at `height`, from the cache or fetched with `fetch` and cached
+    pub async fn get_or_fetch<T, F, Fut>(&self, height: u64, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(dir) = &self.dir else {
+            return fetch().await;
+        };
+        let path = dir.join(format!("{}.json", height));
+        if let Ok(bytes) = std::fs::read(&path) {
+            match serde_json::from_slice(&bytes) {
+                Ok(light_block) => {
+                    tracing::debug!("📦 Light block {} served from the cache", height);
+                    return Ok(light_block);
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️  Dropping corrupt cached light block {}: {}", height, e)
+                }
+            }
+        }
+
+        let light_block = fetch().await?;
+        // A failing cache never fails the round
+        if let Err(e) = self.store(height, &serde_json::to_vec(&light_block)?) {
+            tracing::warn!("⚠️  Failed to cache light block {}: {}", height, e);
+        }
+        Ok(light_block)
+    }
+
+    fn store(&self, height: u64, bytes: &[u8]) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir).context(format!(
+            "Failed to create light block cache {}",
+            dir.display()
+        ))?;
+        // Write atomically, a crash leaves no partial light block
+        let tmp_path = dir.join(format!("{}.json.tmp", height));
+        std::fs::write(&tmp_path, bytes)?;
+        std::fs::rename(&tmp_path, dir.join(format!("{}.json", height)))?;
+        self.evict(height)
+    }
+
+    /// Deletes the lowest cached heights until the cache fits its size limit, never the
+    /// block at `keep` that was just stored
+    fn evict(&self, keep: u64) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(height) = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|height| height.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            entries.push((height, entry.metadata()?.len(), entry.path()));
+        }
+        entries.sort_unstable_by_key(|(height, _, _)| *height);
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (height, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if height == keep {
+                continue;
+            }
+            std::fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache_serves_and_evicts_lowest_heights() {
+        let dir = tempfile::tempdir().unwrap();
+        let block = vec![7u8; 100];
+        // Room for two blocks of ~200 bytes of JSON
+        let cache = LightBlockCache::new(dir.path().to_path_buf(), 500);
+
+        for height in [3, 1, 2] {
+            let fetched: Vec<u8> = cache
+                .get_or_fetch(height, || async { Ok(block.clone()) })
+                .await
+                .unwrap();
+            assert_eq!(fetched, block);
+        }
+
+        // Height 2 is served without fetching
+        let cached: Vec<u8> = cache
+            .get_or_fetch(2, || async { Err(anyhow!("fetched a cached block")) })
+            .await
+            .unwrap();
+        assert_eq!(cached, block);
+
+        // Height 1 was the lowest when the third block exceeded the limit
+        assert!(!dir.path().join("1.json").exists());
+        assert!(dir.path().join("3.json").exists());
+    }
+}
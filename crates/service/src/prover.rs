@@ -1,487 +1,2304 @@
-use alloy_sol_types::SolType;
 use anyhow::{Context, Result};
-use beacon_electra::{
-    extract_electra_block_body, get_beacon_block_header, get_electra_block,
-    types::electra::ElectraBlockHeader,
-};
-use helios_recursion_types::{
-    RecursionCircuitInputs as HeliosRecursionCircuitInputs,
-    RecursionCircuitOutputs as HeliosRecursionCircuitOutputs,
-    WrapperCircuitInputs as HeliosWrapperCircuitInputs,
-};
-use once_cell::sync::Lazy;
-use sp1_helios_primitives::types::ProofOutputs as HeliosOutputs;
-use sp1_sdk::{HashableKey, ProverClient, SP1Stdin};
-use sp1_tendermint_primitives::TendermintOutput;
-use std::cmp::min;
-use std::env;
-use std::process::Command;
-use std::time::{Duration, Instant};
-use tendermint_prover::TendermintProver;
-use tendermint_prover::util::TendermintRPCClient;
-use tendermint_recursion_types::{
-    RecursionCircuitInputs as TendermintRecursionCircuitInputs,
-    RecursionCircuitOutputs as TendermintRecursionCircuitOutputs,
-    WrapperCircuitInputs as TendermintWrapperCircuitInputs,
+use sp1_sdk::{
+    HashableKey, ProverClient, SP1_CIRCUIT_VERSION, SP1Proof, SP1ProofMode,
+    SP1ProofWithPublicValues, SP1ProvingKey, SP1PublicValues, SP1Stdin, SP1VerifyingKey,
 };
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
 
 use crate::{
-    HELIOS_ELF,
-    preprocessor::Preprocessor,
-    state::{ServiceState, StateManager},
+    archive::ProofArchive,
+    backends::{
+        AlreadyCommitted, BelowMinAdvance, ChainUpgraded, LightClientBackend, NoProgress,
+        PeriodBoundary, Unaligned, UpdatesBehind,
+    },
+    control::Stop,
+    encoding::to_hex,
+    eta::{self, Phase},
+    gpu::{LocalProver, cleanup_gpu_containers, with_device},
+    network::NetworkConfig,
+    publication::PublicationDelay,
+    relayers::Relayers,
+    rounds::{self, CircuitInfo, RoundMetadata, RoundTimings, sha256_hex},
+    shadow::ShadowChain,
+    slots::{ChainSlots, ProvingSlots},
+    state::{
+        DEFAULT_CHAIN_ID, LineageRecord, PendingRound, ServiceState, StateManager, UpdateRecord,
+        proof_hash,
+    },
+    webhooks::Webhooks,
+    workers::WorkerPool,
 };
 
 /// Default timeout in seconds for retry operations
 const DEFAULT_TIMEOUT: u64 = 60;
 
-/// Reads the MODE environment variable once at startup
-/// Determines whether to use HELIOS or TENDERMINT consensus
-pub static MODE: Lazy<String> =
-    Lazy::new(|| env::var("CLIENT_BACKEND").unwrap_or_else(|_| "HELIOS".to_string()));
-
-/// Cleans up any existing SP1 GPU containers to prevent conflicts
-fn cleanup_gpu_containers() -> Result<()> {
-    let output = Command::new("docker")
-        .args(["rm", "-f", "sp1-gpu"])
-        .output()
-        .context("Failed to execute docker command")?;
-
-    if !output.status.success() {
-        tracing::warn!(
-            "⚠️  Failed to remove container: {}",
-            String::from_utf8_lossy(&output.stderr)
+/// Rounds with a finished recursive proof that may wait for the wrapper stage
+const PIPELINE_DEPTH: usize = 1;
+
+/// Proves a circuit anchored to the wrapper proof of another proof chain, e.g. the OP Stack
+/// output circuit, setting up its proving key on first use
+pub(crate) async fn prove_anchored<T: borsh::BorshSerialize>(
+    name: &str,
+    elf: &[u8],
+    pk: &mut Option<SP1ProvingKey>,
+    slots: &ChainSlots,
+    inputs: &T,
+) -> Result<SP1ProofWithPublicValues> {
+    let slot = slots.acquire().await;
+    cleanup_gpu_containers().await?;
+    let client = ProverClient::from_env();
+    let pk = match pk {
+        Some(pk) => pk.clone(),
+        None => {
+            let (new_pk, vk) = client.setup(elf);
+            tracing::info!("✅ {} verification key: {}", name, vk.bytes32());
+            pk.insert(new_pk).clone()
+        }
+    };
+
+    let mut stdin = SP1Stdin::new();
+    stdin.write_slice(&borsh::to_vec(inputs)?);
+    if let Some(network) = NetworkConfig::from_env()? {
+        return network.prove(name, &pk, &stdin).await;
+    }
+    if let Some(workers) = WorkerPool::global()? {
+        return workers.prove(name, &pk, &stdin).await;
+    }
+    let prover = LocalProver::new(slot.device());
+    let handle = tokio::spawn(async move { prover.prove_groth16(&pk, &stdin) });
+    match handle.await {
+        Ok(Ok(proof)) => Ok(proof),
+        Ok(Err(e)) => Err(anyhow::anyhow!("{} proof generation failed: {}", name, e)),
+        Err(join_error) => Err(anyhow::anyhow!(
+            "{} proof task failed: {}",
+            name,
+            join_error
+        )),
+    }
+}
+
+/// How the recursion and wrapper circuits of a round are proven, selected by `PROVER_MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProverMode {
+    /// Groth16 proofs of every circuit, the only mode whose proofs relayers accept
+    #[default]
+    Groth16,
+    /// The base light client program runs in the SP1 executor, the recursion and wrapper
+    /// outputs are computed on the host and committed as mock proofs
+    Execute,
+    /// The base proof follows `SP1_PROVER`, the recursion and wrapper outputs are computed on
+    /// the host and committed as mock proofs
+    Mock,
+}
+
+impl ProverMode {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("PROVER_MODE").as_deref() {
+            Err(_) | Ok("groth16") => Ok(Self::Groth16),
+            Ok("execute") => Ok(Self::Execute),
+            Ok("mock") => Ok(Self::Mock),
+            Ok(mode) => Err(anyhow::anyhow!(
+                "Invalid PROVER_MODE {}, expected groth16, execute or mock",
+                mode
+            )),
+        }
+    }
+
+    /// Whether the recursion and wrapper proofs are mock proofs. The circuits verify the
+    /// Groth16 proofs they chain in-guest, which a mock proof can't satisfy, so they are
+    /// emulated on the host instead of executed
+    pub fn mocks_circuits(self) -> bool {
+        self != Self::Groth16
+    }
+}
+
+/// How the recursion circuit verifies the previous proof of the chain, selected by
+/// `RECURSION_AGGREGATION`. Must match the mode the circuits were generated with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregation {
+    /// Every recursive proof is Groth16-wrapped and verified with the Groth16 verifier
+    #[default]
+    Groth16,
+    /// Recursive proofs are compressed SP1 proofs verified with native proof aggregation,
+    /// only the wrapper proof is Groth16-wrapped
+    Compressed,
+}
+
+impl Aggregation {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("RECURSION_AGGREGATION").as_deref() {
+            Err(_) | Ok("groth16") => Ok(Self::Groth16),
+            Ok("compressed") => Ok(Self::Compressed),
+            Ok(mode) => Err(anyhow::anyhow!(
+                "Invalid RECURSION_AGGREGATION {}, expected groth16 or compressed",
+                mode
+            )),
+        }
+    }
+
+    /// The proof mode of recursive proofs
+    pub fn proof_mode(self) -> SP1ProofMode {
+        match self {
+            Self::Groth16 => SP1ProofMode::Groth16,
+            Self::Compressed => SP1ProofMode::Compressed,
+        }
+    }
+
+    /// The recursive vk the proof chain is bound to: the bytes32 hash verified by the Groth16
+    /// verifier, or the digest of the vk `verify_sp1_proof` takes, as big-endian words
+    pub fn recursive_vk(self, vk: &SP1VerifyingKey) -> String {
+        match self {
+            Self::Groth16 => vk.bytes32(),
+            Self::Compressed => to_hex(vk.hash_u32().map(u32::to_be_bytes).concat()),
+        }
+    }
+}
+
+/// How the wrapper circuits encode their outputs, selected by `WRAPPER_ENCODING`. Must match
+/// the encoding the wrapper circuits were generated with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapperEncoding {
+    /// The format version byte followed by the borsh encoded outputs
+    #[default]
+    Borsh,
+    /// An ABI encoded tuple, decoded by EVM contracts with `abi.decode`
+    Abi,
+}
+
+impl WrapperEncoding {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("WRAPPER_ENCODING").as_deref() {
+            Err(_) | Ok("borsh") => Ok(Self::Borsh),
+            Ok("abi") => Ok(Self::Abi),
+            Ok(encoding) => Err(anyhow::anyhow!(
+                "Invalid WRAPPER_ENCODING {}, expected borsh or abi",
+                encoding
+            )),
+        }
+    }
+
+    pub fn is_abi(self) -> bool {
+        self == Self::Abi
+    }
+}
+
+impl std::fmt::Display for WrapperEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Borsh => "borsh",
+            Self::Abi => "abi",
+        })
+    }
+}
+
+impl std::fmt::Display for ProverMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Groth16 => "groth16",
+            Self::Execute => "execute",
+            Self::Mock => "mock",
+        })
+    }
+}
+
+/// The proving steps of a single round.
+///
+/// The service loop drives an implementation of this trait through the
+/// base -> recursive -> wrapper pipeline. [`Sp1RoundProver`] is the
+/// implementation used in production, tests substitute one backed by fixtures.
+/// The proofs of consecutive rounds overlap, a wrapper proof may run concurrently
+/// with the base and recursive proofs of the next round.
+pub trait RoundProver<B: LightClientBackend>: Send + Sync {
+    /// Prepares the circuits for a new round and returns the recursive circuit vk.
+    /// Implementations may set up their keys on the first call and reuse them afterwards.
+    fn setup(&mut self, backend: &B) -> impl Future<Output = Result<String>> + Send;
+
+    /// Generates the base light client proof and prepares the recursion circuit inputs
+    fn base_proof(
+        &self,
+        backend: &B,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> impl Future<Output = Result<(B::BaseOutputs, B::RecursionInputs)>> + Send;
+
+    /// Proves the recursion circuit
+    fn recursive_proof(
+        &self,
+        backend: &B,
+        stdin: SP1Stdin,
+    ) -> impl Future<Output = Result<SP1ProofWithPublicValues>> + Send;
+
+    /// Proves the wrapper circuit
+    fn wrapper_proof(
+        &self,
+        backend: &B,
+        stdin: SP1Stdin,
+    ) -> impl Future<Output = Result<SP1ProofWithPublicValues>> + Send;
+
+    /// The circuits the rounds are proven with, recorded with every committed round
+    fn circuits(&self) -> Vec<CircuitInfo> {
+        Vec::new()
+    }
+
+    /// The shadow chain running a candidate recursive circuit on the inputs of every round
+    fn shadow(&self) -> Option<&ShadowChain> {
+        None
+    }
+
+    /// Writes a compressed recursive proof to the stdin of the circuit verifying it with
+    /// `verify_sp1_proof`. Groth16 proofs are part of the circuit inputs instead
+    fn write_recursive_proof(
+        &self,
+        _stdin: &mut SP1Stdin,
+        _proof: &SP1ProofWithPublicValues,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Proving keys of all circuits, set up once at startup
+#[derive(Clone)]
+struct CircuitKeys {
+    recursive_pk: SP1ProvingKey,
+    recursive_vk: String,
+    /// Verifies compressed recursive proofs in the next recursion and wrapper circuits
+    recursive_verifying_key: SP1VerifyingKey,
+    aggregation: Aggregation,
+    wrapper_pk: SP1ProvingKey,
+    /// Proving key of the base light client program, if the backend has one
+    base_pk: Option<SP1ProvingKey>,
+    circuits: Vec<CircuitInfo>,
+}
+
+/// Generates all proofs of a round with the SP1 prover selected by the environment
+pub struct Sp1RoundProver {
+    recursive_elf: Vec<u8>,
+    wrapper_elf: Vec<u8>,
+    /// The proving slots shared with the other proof chains of this process
+    slots: ChainSlots,
+    keys: Option<CircuitKeys>,
+    shadow: Option<ShadowChain>,
+}
+
+impl Sp1RoundProver {
+    pub fn new(recursive_elf: Vec<u8>, wrapper_elf: Vec<u8>) -> Self {
+        Self {
+            recursive_elf,
+            wrapper_elf,
+            slots: ProvingSlots::new(1).for_chain(DEFAULT_CHAIN_ID),
+            keys: None,
+            shadow: None,
+        }
+    }
+
+    /// Proves through slots shared with the proof chains of other provers
+    pub fn with_proving_slots(mut self, slots: ChainSlots) -> Self {
+        self.slots = slots;
+        self
+    }
+
+    /// Runs the candidate recursive circuit of a shadow chain on the inputs of every round
+    pub fn with_shadow(mut self, shadow: Option<ShadowChain>) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    fn keys(&self) -> Result<&CircuitKeys> {
+        self.keys.as_ref().context("Circuits have not been set up")
+    }
+}
+
+impl<B: LightClientBackend> RoundProver<B> for Sp1RoundProver {
+    async fn setup(&mut self, backend: &B) -> Result<String> {
+        let _slot = self.slots.acquire().await;
+
+        // Clean up any existing GPU containers
+        tracing::info!("🧹 Cleaning up GPU containers...");
+        cleanup_gpu_containers().await?;
+
+        if let Some(keys) = &self.keys {
+            return Ok(keys.recursive_vk.clone());
+        }
+
+        // Set up the keys of all circuits concurrently on the blocking pool,
+        // setup is CPU-bound and independent per ELF
+        tracing::info!("🔑 Setting up verification keys for all circuits...");
+        let client = Arc::new(ProverClient::from_env());
+        let setup = |elf: Vec<u8>| {
+            let client = client.clone();
+            tokio::task::spawn_blocking(move || client.setup(&elf))
+        };
+        let base_setup = backend.base_program_elf().map(|elf| setup(elf.to_vec()));
+        let (recursive, wrapper, base) = tokio::try_join!(
+            setup(self.recursive_elf.clone()),
+            setup(self.wrapper_elf.clone()),
+            async {
+                match base_setup {
+                    Some(handle) => handle.await.map(Some),
+                    None => Ok(None),
+                }
+            },
+        )
+        .context("Circuit setup task failed")?;
+        let (recursive_pk, recursive_verifying_key) = recursive;
+        let (wrapper_pk, wrapper_vk) = wrapper;
+        let aggregation = backend.aggregation()?;
+        let recursive_vk = aggregation.recursive_vk(&recursive_verifying_key);
+
+        tracing::info!(
+            "✅ Recursive verification key: {} ({:?} aggregation)",
+            recursive_vk,
+            aggregation
         );
+        tracing::info!("✅ Wrapper verification key: {}", wrapper_vk.bytes32());
+
+        // A circuit regenerated without the circuits verifying it would only fail their
+        // assertions once its first proof is verified, hours into the round
+        check_embedded_vk("wrapper", &self.wrapper_elf, "recursive", &recursive_vk)?;
+        if let Some((_, base_vk)) = &base {
+            check_embedded_vk("recursive", &self.recursive_elf, "base", &base_vk.bytes32())?;
+        }
+
+        let mut circuits = vec![
+            CircuitInfo::new("recursive", &self.recursive_elf, recursive_vk.clone()),
+            CircuitInfo::new("wrapper", &self.wrapper_elf, wrapper_vk.bytes32()),
+        ];
+        if let (Some(elf), Some((_, base_vk))) = (backend.base_program_elf(), &base) {
+            circuits.insert(0, CircuitInfo::new("base", elf, base_vk.bytes32()));
+        }
+        rounds::register_circuits(self.slots.chain_id(), backend.name(), circuits.clone());
+        let keys = CircuitKeys {
+            recursive_pk,
+            recursive_vk,
+            recursive_verifying_key,
+            aggregation,
+            wrapper_pk,
+            // The keys of a base program proven by an external prover are only set up for its vk
+            base_pk: base
+                .filter(|_| backend.base_elf().is_some())
+                .map(|(pk, _)| pk),
+            circuits,
+        };
+        let recursive_vk = keys.recursive_vk.clone();
+        self.keys = Some(keys);
+        Ok(recursive_vk)
+    }
+
+    async fn base_proof(
+        &self,
+        backend: &B,
+        service_state: &ServiceState,
+        recursive_vk: String,
+    ) -> Result<(B::BaseOutputs, B::RecursionInputs)> {
+        let base_pk = self.keys()?.base_pk.as_ref();
+        let slot = self.slots.acquire().await;
+        with_device(
+            slot.device().cloned(),
+            backend.prepare_inputs(base_pk, service_state, recursive_vk),
+        )
+        .await
+    }
+
+    async fn recursive_proof(
+        &self,
+        backend: &B,
+        stdin: SP1Stdin,
+    ) -> Result<SP1ProofWithPublicValues> {
+        let recursive_pk = self.keys()?.recursive_pk.clone();
+        let mode = self.keys()?.aggregation.proof_mode();
+        if ProverMode::from_env()?.mocks_circuits() {
+            let public_values = backend.mock_recursion_outputs(circuit_inputs(&stdin)?)?;
+            return Ok(mock_proof(&recursive_pk, &public_values));
+        }
+        let slot = self.slots.acquire().await;
+        if let Some(network) = NetworkConfig::from_env()? {
+            return network
+                .prove_with_mode("Recursive", &recursive_pk, &stdin, mode)
+                .await;
+        }
+        if let Some(workers) = WorkerPool::global()? {
+            return workers
+                .prove_with_mode("Recursive", &recursive_pk, &stdin, mode)
+                .await;
+        }
+        cleanup_gpu_containers().await?;
+        let prover = LocalProver::new(slot.device());
+
+        // Run recursive proof generation in isolated task
+        let handle = tokio::spawn(async move { prover.prove(&recursive_pk, &stdin, mode) });
+
+        match handle.await {
+            Ok(Ok(proof)) => Ok(proof),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Recursive proof generation failed: {}", e)),
+            Err(join_error) => Err(anyhow::anyhow!(
+                "Recursive proof task failed: {}",
+                join_error
+            )),
+        }
+    }
+
+    async fn wrapper_proof(
+        &self,
+        backend: &B,
+        stdin: SP1Stdin,
+    ) -> Result<SP1ProofWithPublicValues> {
+        let wrapper_pk = self.keys()?.wrapper_pk.clone();
+        if ProverMode::from_env()?.mocks_circuits() {
+            let public_values = backend.mock_wrapper_outputs(circuit_inputs(&stdin)?)?;
+            return Ok(mock_proof(&wrapper_pk, &public_values));
+        }
+        let slot = self.slots.acquire().await;
+        if let Some(network) = NetworkConfig::from_env()? {
+            return network.prove("Wrapper", &wrapper_pk, &stdin).await;
+        }
+        if let Some(workers) = WorkerPool::global()? {
+            return workers.prove("Wrapper", &wrapper_pk, &stdin).await;
+        }
+        cleanup_gpu_containers().await?;
+        let prover = LocalProver::new(slot.device());
+
+        // Run wrapper proof generation in isolated task
+        let handle = tokio::spawn(async move { prover.prove_groth16(&wrapper_pk, &stdin) });
+
+        match handle.await {
+            Ok(Ok(proof)) => Ok(proof),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Wrapper proof generation failed: {}", e)),
+            Err(join_error) => Err(anyhow::anyhow!("Wrapper proof task failed: {}", join_error)),
+        }
+    }
+
+    fn circuits(&self) -> Vec<CircuitInfo> {
+        self.keys
+            .as_ref()
+            .map(|keys| keys.circuits.clone())
+            .unwrap_or_default()
+    }
+
+    fn shadow(&self) -> Option<&ShadowChain> {
+        self.shadow.as_ref()
+    }
+
+    fn write_recursive_proof(
+        &self,
+        stdin: &mut SP1Stdin,
+        proof: &SP1ProofWithPublicValues,
+    ) -> Result<()> {
+        if let SP1Proof::Compressed(compressed) = &proof.proof {
+            let vk = self.keys()?.recursive_verifying_key.vk.clone();
+            stdin.write_proof(*compressed.clone(), vk);
+        }
+        Ok(())
     }
-    Ok(())
+}
+
+/// The serialized inputs a circuit reads from its stdin
+fn circuit_inputs(stdin: &SP1Stdin) -> Result<&[u8]> {
+    stdin
+        .buffer
+        .first()
+        .map(Vec::as_slice)
+        .context("Circuit inputs are empty")
+}
+
+/// The proof bytes accepted by onchain verifiers, `None` for compressed recursive proofs
+pub(crate) fn onchain_bytes(proof: &SP1ProofWithPublicValues) -> Option<Vec<u8>> {
+    matches!(proof.proof, SP1Proof::Groth16(_) | SP1Proof::Plonk(_)).then(|| proof.bytes())
+}
+
+/// A mock proof carrying `public_values`, it passes the service's own checks but no verifier
+pub(crate) fn mock_proof(pk: &SP1ProvingKey, public_values: &[u8]) -> SP1ProofWithPublicValues {
+    SP1ProofWithPublicValues::create_mock_proof(
+        pk,
+        SP1PublicValues::from(public_values),
+        SP1ProofMode::Groth16,
+        SP1_CIRCUIT_VERSION,
+    )
 }
 
 /// Runs the main service loop that generates and verifies proofs
 ///
 /// This function orchestrates the entire proof generation process:
 /// 1. Sets up prover clients and verification keys
-/// 2. Generates base proofs (Helios or Tendermint)
+/// 2. Generates base proofs with the light client backend
 /// 3. Generates recursive proofs
 /// 4. Generates wrapper proofs
 /// 5. Updates service state with new trusted information
 /// 6. Saves state and continues the loop
-pub async fn run_prover_loop(
+///
+/// Rounds are pipelined, see [`Pipeline::run`]. The loop runs until `stop` is requested, or
+/// until `max_rounds` rounds have been committed if it is set. A requested stop never
+/// interrupts a round in progress.
+pub async fn run_prover_loop<B: LightClientBackend, P: RoundProver<B>>(
     state_manager: StateManager,
     mut service_state: ServiceState,
-    recursive_elf: Vec<u8>,
-    wrapper_elf: Vec<u8>,
-    consensus_url: String,
+    backend: B,
+    mut prover: P,
+    max_rounds: Option<u64>,
+    mut stop: Stop,
 ) -> Result<()> {
     let start_time = Instant::now();
+    let mut completed_rounds = 0;
+    let restart_on_vk_change = restart_on_vk_change()?;
+    let webhooks = Webhooks::from_env()?;
+    let relayers = Relayers::from_env()?;
+    let publication = PublicationDelay::from_env()?;
+    let archive = ProofArchive::from_env()?;
     tracing::info!("🚀 Starting proof generation service loop...");
 
+    // Archive the latest update in case the service stopped before exporting it
+    archive.export_latest(&state_manager, backend.name());
+    // Both stages of the pipeline write to the database
+    let chain_id = state_manager.chain_id().to_string();
+    let state_manager = Mutex::new(state_manager);
+
     loop {
-        let round_start_time = Instant::now();
+        if max_rounds.is_some_and(|max_rounds| completed_rounds >= max_rounds) {
+            tracing::info!("🏁 Completed {} round(s), stopping", completed_rounds);
+            eta::clear(&chain_id);
+            return Ok(());
+        }
+        if stop.requested() {
+            tracing::info!("⏸️  Stopping the {} proof chain", backend.name());
+            eta::clear(&chain_id);
+            return Ok(());
+        }
 
-        // Clean up any existing GPU containers
-        tracing::info!("🧹 Cleaning up GPU containers...");
-        cleanup_gpu_containers()?;
+        let recursive_vk = prover.setup(&backend).await?;
+        restart_from_wrapper(
+            &state_manager.lock().unwrap(),
+            &backend,
+            &mut service_state,
+            &recursive_vk,
+            restart_on_vk_change,
+        )?;
+        state_manager
+            .lock()
+            .unwrap()
+            .bind(backend.name(), &recursive_vk)?;
+        let pipeline = Pipeline {
+            state_manager: &state_manager,
+            backend: &backend,
+            prover: &prover,
+            webhooks: &webhooks,
+            relayers: &relayers,
+            publication: &publication,
+            archive: &archive,
+            start_time,
+        };
+        let rounds = max_rounds.map(|max_rounds| max_rounds - completed_rounds);
+        let result = pipeline
+            .run(
+                &mut service_state,
+                recursive_vk,
+                rounds,
+                &mut stop,
+                &mut completed_rounds,
+            )
+            .await;
+        if let Err(e) = result {
+            // Restart the pipeline from the last committed update
+            tracing::warn!(
+                "⚠️  {} round failed: {}, retrying in {} seconds...",
+                backend.name(),
+                e,
+                DEFAULT_TIMEOUT
+            );
+            eta::wrapper_done(&chain_id);
+            eta::enter_phase(&chain_id, Phase::Waiting);
+            stop.sleep(DEFAULT_TIMEOUT).await;
+        }
+    }
+}
 
-        // Initialize prover client and load ELF files
-        tracing::info!("🔧 Initializing prover client and loading ELF files...");
-        let client = ProverClient::from_env();
-        let helios_elf = HELIOS_ELF.to_vec();
-        let recursive_elf_clone = recursive_elf.clone();
-        let wrapper_elf_clone = wrapper_elf.clone();
+/// What the stages of a pipeline share
+struct Pipeline<'a, B, P> {
+    state_manager: &'a Mutex<StateManager>,
+    backend: &'a B,
+    prover: &'a P,
+    webhooks: &'a Webhooks,
+    relayers: &'a Relayers,
+    publication: &'a PublicationDelay,
+    archive: &'a ProofArchive,
+    start_time: Instant,
+}
 
-        // Set up verification keys for all circuits
-        tracing::info!("🔑 Setting up verification keys for all circuits...");
-        let (recursive_pk, recursive_vk) = client.setup(&recursive_elf_clone);
-        let (wrapper_pk, wrapper_vk) = client.setup(&wrapper_elf_clone);
-        let _ = client.setup(&helios_elf);
+impl<B: LightClientBackend, P: RoundProver<B>> Pipeline<'_, B, P> {
+    /// Proves and commits up to `rounds` rounds in two stages connected by a bounded channel.
+    ///
+    /// The front stage proves the base and recursive proofs of a round, then advances a
+    /// speculative copy of `service_state` to the round and starts the next one. The back
+    /// stage proves the wrapper proof of each round and commits it in order, so the inputs
+    /// and base proof of the next round are prepared while the current wrapper proof runs.
+    /// Only committed rounds advance `service_state`. A failed round ends the pipeline once
+    /// the rounds ahead of it are committed, the rounds proven after it are dropped.
+    async fn run(
+        &self,
+        service_state: &mut ServiceState,
+        recursive_vk: String,
+        rounds: Option<u64>,
+        stop: &mut Stop,
+        completed_rounds: &mut u64,
+    ) -> Result<()> {
+        let (tx, mut rx) = mpsc::channel(PIPELINE_DEPTH);
+        let mut speculative_state = service_state.clone();
+        let mut hold_stop = stop.clone();
+        let chain_id = self.state_manager.lock().unwrap().chain_id().to_string();
+        let chain_id = chain_id.as_str();
 
-        tracing::info!("✅ Recursive verification key: {}", recursive_vk.bytes32());
-        tracing::info!("✅ Wrapper verification key: {}", wrapper_vk.bytes32());
+        let front = async move {
+            let mut proven_rounds = 0;
+            loop {
+                if rounds.is_some_and(|rounds| proven_rounds >= rounds) || stop.requested() {
+                    return Ok(());
+                }
+                // The wrapper stage failed, nothing proven from here on gets committed
+                if tx.is_closed() {
+                    return Ok(());
+                }
 
-        // Generate base proof based on selected mode
-        let recursive_prover = match MODE.as_str() {
-            "HELIOS" => {
-                tracing::info!("🌞 Generating Helios proof...");
-                match helios_prover(
-                    &helios_elf,
-                    recursive_vk.bytes32(),
-                    &service_state,
-                    &consensus_url,
-                )
-                .await
-                {
-                    Ok(prover) => {
-                        tracing::info!("✅ Helios proof generated successfully");
-                        prover
+                // A round interrupted by a restart is completed from its recursive proof
+                let resumed = resume_round(
+                    &self.state_manager.lock().unwrap(),
+                    self.backend,
+                    &speculative_state,
+                    &recursive_vk,
+                )?;
+                let proven = match resumed {
+                    Some(round) => Ok(round),
+                    None => {
+                        prove_recursion(
+                            chain_id,
+                            self.backend,
+                            self.prover,
+                            &speculative_state,
+                            recursive_vk.clone(),
+                        )
+                        .await
                     }
-                    Err(e) => {
-                        tracing::warn!(
-                            "⚠️  Helios prover failed: {}, retrying in {} seconds...",
+                };
+                let round = match proven {
+                    Ok(round) => round,
+                    Err(e) if e.downcast_ref::<AlreadyCommitted>().is_some() => {
+                        // Serve the committed update instead of proving it a second time
+                        tracing::info!(
+                            "♻️  {} {}, keeping update {} and retrying in {} seconds...",
+                            self.backend.name(),
                             e,
+                            speculative_state.update_counter,
                             DEFAULT_TIMEOUT
                         );
-                        tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
+                        eta::enter_phase(chain_id, Phase::Waiting);
+                        stop.sleep(DEFAULT_TIMEOUT).await;
                         continue;
                     }
-                }
-            }
-            "TENDERMINT" => {
-                tracing::info!("🌿 Generating Tendermint proof...");
-                match tendermint_prover(&service_state, recursive_vk.bytes32()).await {
-                    Ok(prover) => {
-                        tracing::info!("✅ Tendermint proof generated successfully");
-                        prover
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            "⚠️  Tendermint prover failed: {}, retrying in {} seconds...",
+                    Err(e)
+                        if e.downcast_ref::<NoProgress>().is_some()
+                            || e.downcast_ref::<Unaligned>().is_some()
+                            || e.downcast_ref::<BelowMinAdvance>().is_some()
+                            || e.downcast_ref::<ChainUpgraded>().is_some()
+                            || e.downcast_ref::<PeriodBoundary>().is_some()
+                            || e.downcast_ref::<UpdatesBehind>().is_some() =>
+                    {
+                        tracing::info!(
+                            "⏸️  {} {}, retrying in {} seconds...",
+                            self.backend.name(),
                             e,
                             DEFAULT_TIMEOUT
                         );
-                        tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
+                        eta::enter_phase(chain_id, Phase::Waiting);
+                        stop.sleep(DEFAULT_TIMEOUT).await;
                         continue;
                     }
+                    Err(e) => return Err(e),
+                };
+                // Drop a round contradicting the committee index before its wrapper proof
+                self.state_manager
+                    .lock()
+                    .unwrap()
+                    .check_committees(&self.backend.committees(&round.base_outputs))?;
+                persist_round(
+                    &self.state_manager.lock().unwrap(),
+                    self.backend,
+                    &speculative_state,
+                    &round,
+                )?;
+
+                // The next round chains from this one before it is committed
+                let outputs = self
+                    .backend
+                    .decode_outputs(round.recursive_proof.public_values.as_slice())?;
+                self.backend
+                    .update_state(&mut speculative_state, &round.base_outputs, &outputs);
+                speculative_state.most_recent_recursive_proof = Some(round.recursive_proof.clone());
+                speculative_state.update_counter += 1;
+                proven_rounds += 1;
+
+                if tx.send(round).await.is_err() {
+                    return Ok(());
                 }
             }
-            _ => panic!("❌ Invalid mode: {:?}", MODE.as_str()),
         };
 
+        let back = async {
+            let mut round_start_time = Instant::now();
+            while let Some(round) = rx.recv().await {
+                eta::enter_phase(chain_id, Phase::Wrapper);
+                let proofs = prove_wrapper(self.backend, self.prover, round).await?;
+                let db = self.state_manager;
+                hold_round(
+                    self.publication,
+                    move || db.lock().unwrap(),
+                    self.backend,
+                    service_state,
+                    &proofs,
+                    &mut hold_stop,
+                )
+                .await?;
+                {
+                    let state_manager = self.state_manager.lock().unwrap();
+                    let record = commit_round(&state_manager, service_state, self.backend, proofs)?;
+                    self.webhooks
+                        .notify(state_manager.chain_id(), self.backend.name(), &record);
+                    self.relayers.relay(
+                        state_manager.chain_id(),
+                        self.backend.name(),
+                        &record,
+                        service_state,
+                    );
+                    self.archive
+                        .export_latest(&state_manager, self.backend.name());
+                }
+                eta::wrapper_done(chain_id);
+
+                tracing::info!("⏱️  Round completed in: {:?}", round_start_time.elapsed());
+                tracing::info!("⏱️  Service uptime: {:?}", self.start_time.elapsed());
+                round_start_time = Instant::now();
+                *completed_rounds += 1;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+
+        let (front, back) = tokio::join!(front, back);
+        back.and(front)
+    }
+}
+
+/// Proves and commits exactly one update of a proof chain, for cron-driven deployments and
+/// smoke tests. Unlike `run_prover_loop` a failed round is returned instead of retried.
+/// Returns `None` if the chain has no new update to prove
+pub async fn prove_once<B: LightClientBackend, P: RoundProver<B>>(
+    state_manager: &StateManager,
+    mut service_state: ServiceState,
+    backend: B,
+    mut prover: P,
+) -> Result<Option<UpdateRecord>> {
+    let recursive_vk = prover.setup(&backend).await?;
+    restart_from_wrapper(
+        state_manager,
+        &backend,
+        &mut service_state,
+        &recursive_vk,
+        restart_on_vk_change()?,
+    )?;
+    state_manager.bind(backend.name(), &recursive_vk)?;
+    let round = match resume_round(state_manager, &backend, &service_state, &recursive_vk)? {
+        Some(round) => round,
+        None => match prove_recursion(
+            state_manager.chain_id(),
+            &backend,
+            &prover,
+            &service_state,
+            recursive_vk,
+        )
+        .await
+        {
+            Ok(round) => round,
+            Err(e)
+                if e.downcast_ref::<AlreadyCommitted>().is_some()
+                    || e.downcast_ref::<NoProgress>().is_some()
+                    || e.downcast_ref::<Unaligned>().is_some()
+                    || e.downcast_ref::<BelowMinAdvance>().is_some()
+                    || e.downcast_ref::<ChainUpgraded>().is_some()
+                    || e.downcast_ref::<PeriodBoundary>().is_some()
+                    || e.downcast_ref::<UpdatesBehind>().is_some() =>
+            {
+                tracing::info!("♻️  {} {}", backend.name(), e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        },
+    };
+    state_manager.check_committees(&backend.committees(&round.base_outputs))?;
+    persist_round(state_manager, &backend, &service_state, &round)?;
+    let proofs = prove_wrapper(&backend, &prover, round).await?;
+    hold_round(
+        &PublicationDelay::from_env()?,
+        || state_manager,
+        &backend,
+        &service_state,
+        &proofs,
+        &mut Stop::never(),
+    )
+    .await?;
+
+    let record = commit_round(state_manager, &mut service_state, &backend, proofs)?;
+    Webhooks::from_env()?.notify(state_manager.chain_id(), backend.name(), &record);
+    ProofArchive::from_env()?.export_latest(state_manager, backend.name());
+    Ok(Some(record))
+}
+
+/// Holds the proven round following `service_state` back from publication for the delay of
+/// `publication`, if one is configured
+async fn hold_round<B: LightClientBackend, S: Deref<Target = StateManager>>(
+    publication: &PublicationDelay,
+    state_manager: impl Fn() -> S,
+    backend: &B,
+    service_state: &ServiceState,
+    proofs: &RoundProofs<B::BaseOutputs>,
+    stop: &mut Stop,
+) -> Result<()> {
+    if !publication.is_enabled() {
+        return Ok(());
+    }
+    let outputs = backend.decode_outputs(proofs.recursive_proof.public_values.as_slice())?;
+    let mut next_state = service_state.clone();
+    backend.update_state(&mut next_state, &proofs.base_outputs, &outputs);
+    publication
+        .hold(
+            state_manager,
+            next_state.update_counter + 1,
+            next_state.trusted_height,
+            next_state.trusted_root,
+            stop,
+        )
+        .await
+}
+
+/// A round with its recursive proof, waiting for the wrapper proof
+struct RecursionRound<O> {
+    base_outputs: O,
+    recursive_proof: SP1ProofWithPublicValues,
+    recursion_inputs_sha256: String,
+    base_ms: u64,
+    recursive_ms: u64,
+}
+
+/// The proofs of a round, before they are committed
+struct RoundProofs<O> {
+    base_outputs: O,
+    recursive_proof: SP1ProofWithPublicValues,
+    wrapper_proof: SP1ProofWithPublicValues,
+    metadata: RoundMetadata,
+}
+
+/// Generates the base and recursive proofs of the update following `service_state`
+async fn prove_recursion<B: LightClientBackend, P: RoundProver<B>>(
+    chain_id: &str,
+    backend: &B,
+    prover: &P,
+    service_state: &ServiceState,
+    recursive_vk: String,
+) -> Result<RecursionRound<B::BaseOutputs>> {
+    // A backend may only reach an intermediate height, the round then proves the next hop on
+    // top of it until the backend reaches its target
+    let mut hop_state = service_state.clone();
+    let (mut base_ms, mut recursive_ms) = (0, 0);
+    loop {
+        // Generate base proof with the light client backend
+        tracing::info!("⚡ Generating {} proof...", backend.name());
+        eta::enter_phase(chain_id, Phase::Base);
+        let step_start = Instant::now();
+        let (base_outputs, recursion_inputs) = prover
+            .base_proof(backend, &hop_state, recursive_vk.clone())
+            .await?;
+        base_ms += elapsed_ms(step_start);
+        tracing::info!("✅ Base proof generated successfully");
+        let intermediate = backend.is_intermediate(&base_outputs);
+
         // Prepare inputs for recursive proof generation
         tracing::info!("📝 Preparing inputs for recursive proof generation...");
+        let recursion_inputs = borsh::to_vec(&recursion_inputs)?;
         let mut stdin = SP1Stdin::new();
-        match recursive_prover.clone() {
-            RecursiveProver::Helios((_, recursion_inputs)) => {
-                stdin.write_slice(&borsh::to_vec(&recursion_inputs).unwrap());
-            }
-            RecursiveProver::Tendermint((_, recursion_inputs)) => {
-                stdin.write_slice(&borsh::to_vec(&recursion_inputs).unwrap());
-            }
+        stdin.write_slice(&recursion_inputs);
+        if let Some(previous_proof) = &hop_state.most_recent_recursive_proof {
+            prover.write_recursive_proof(&mut stdin, previous_proof)?;
         }
 
+        // The shadow chain runs on the same stdin, with the compressed previous proof if any.
+        // Only the last hop of a round is shadowed, as only its proof is committed
+        let shadow_stdin = match intermediate {
+            true => None,
+            false => prover.shadow().map(|_| stdin.clone()),
+        };
+
         tracing::info!("🔄 Generating recursive proof...");
-        // Run recursive proof generation in isolated task
-        let recursive_proof = {
-            let recursive_pk_clone = recursive_pk.clone();
-            let stdin_clone = stdin.clone();
-            cleanup_gpu_containers()?;
-            let client = ProverClient::from_env();
-
-            let _ = client.setup(&recursive_elf);
-
-            let handle = tokio::spawn(async move {
-                client
-                    .prove(&recursive_pk_clone, &stdin_clone)
-                    .groth16()
-                    .run()
-            });
-
-            match handle.await {
-                Ok(Ok(proof)) => {
-                    tracing::info!("✅ Recursive proof generated successfully");
-                    proof
-                }
-                Ok(Err(e)) => {
-                    tracing::error!("❌ Recursive proof generation failed: {}", e);
-                    tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
-                    continue;
-                }
-                Err(join_error) => {
-                    tracing::error!("❌ Recursive proof task failed: {}", join_error);
-                    tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
-                    continue;
-                }
+        eta::enter_phase(chain_id, Phase::Recursive);
+        let step_start = Instant::now();
+        let recursive_proof = prover.recursive_proof(backend, stdin).await?;
+        recursive_ms += elapsed_ms(step_start);
+        tracing::info!("✅ Recursive proof generated successfully");
+
+        if intermediate {
+            let outputs = backend.decode_outputs(&recursive_proof.public_values.to_vec())?;
+            tracing::info!(
+                "🪜 Proved intermediate height {}, continuing the round",
+                outputs.height
+            );
+            backend.update_state(&mut hop_state, &base_outputs, &outputs);
+            hop_state.most_recent_recursive_proof = Some(recursive_proof);
+            continue;
+        }
+        if let (Some(shadow), Some(stdin)) = (prover.shadow(), shadow_stdin) {
+            shadow.spawn(
+                service_state.update_counter + 1,
+                stdin,
+                recursive_proof.public_values.to_vec(),
+            );
+        }
+
+        return Ok(RecursionRound {
+            base_outputs,
+            recursive_proof,
+            recursion_inputs_sha256: sha256_hex(&recursion_inputs),
+            base_ms,
+            recursive_ms,
+        });
+    }
+}
+
+/// Persists a round with its recursive proof until it is committed, unless the backend
+/// can't serialize its base outputs
+fn persist_round<B: LightClientBackend>(
+    state_manager: &StateManager,
+    backend: &B,
+    parent: &ServiceState,
+    round: &RecursionRound<B::BaseOutputs>,
+) -> Result<()> {
+    let Some(base_outputs) = backend.encode_base_outputs(&round.base_outputs) else {
+        return Ok(());
+    };
+    state_manager.save_pending_round(&PendingRound {
+        update_counter: parent.update_counter + 1,
+        parent_height: parent.trusted_height,
+        parent_root: parent.trusted_root,
+        base_outputs,
+        recursive_proof: round.recursive_proof.clone(),
+        recursion_inputs_sha256: round.recursion_inputs_sha256.clone(),
+        base_ms: round.base_ms,
+        recursive_ms: round.recursive_ms,
+    })
+}
+
+/// Whether `RESTART_ON_VK_CHANGE` allows restarting proof chains from their wrapper proofs
+fn restart_on_vk_change() -> Result<bool> {
+    match std::env::var("RESTART_ON_VK_CHANGE").as_deref() {
+        Err(_) | Ok("false") => Ok(false),
+        Ok("true") => Ok(true),
+        Ok(value) => Err(anyhow::anyhow!(
+            "Invalid RESTART_ON_VK_CHANGE {}, expected true or false",
+            value
+        )),
+    }
+}
+
+/// Fails unless the ELF of `circuit` embeds the vk of the `verified` circuit it verifies.
+/// The generated circuits embed the vks they verify as hex constants, an ELF generated for
+/// another circuit doesn't contain its vk
+fn check_embedded_vk(circuit: &str, elf: &[u8], verified: &str, vk: &str) -> Result<()> {
+    if elf.windows(vk.len()).any(|window| window == vk.as_bytes()) {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "The {} ELF was not generated for the {} circuit with vk {}, the circuits drifted \
+         apart. Regenerate the {} circuit with `make regenerate-circuits` and dump the ELFs \
+         again",
+        circuit,
+        verified,
+        vk,
+        circuit
+    ))
+}
+
+/// Restarts the proof chain from its latest wrapper proof if the recursive circuit was
+/// regenerated since its recursive proof was committed. That proof can't be extended by the
+/// new circuit, which instead starts a new proof chain at the committed head, the new trusted
+/// head of the regenerated circuit. Restarts must be allowed with `RESTART_ON_VK_CHANGE`, an
+/// unintended circuit change would otherwise silently cut the proof chain
+fn restart_from_wrapper<B: LightClientBackend>(
+    state_manager: &StateManager,
+    backend: &B,
+    service_state: &mut ServiceState,
+    recursive_vk: &str,
+    allowed: bool,
+) -> Result<()> {
+    // Without a recursive proof, e.g. right after a restart, the vk the state row is bound to
+    // tells which circuit proved its wrapper proof
+    let previous_vk = match &service_state.most_recent_recursive_proof {
+        Some(recursive_proof) => {
+            backend
+                .decode_outputs(recursive_proof.public_values.as_slice())?
+                .vk
+        }
+        None if service_state.most_recent_wrapper_proof.is_none() => return Ok(()),
+        None => match state_manager.bound_vk()? {
+            Some(vk) => vk,
+            None => return Ok(()),
+        },
+    };
+    if previous_vk == recursive_vk {
+        return Ok(());
+    }
+    if !allowed {
+        return Err(anyhow::anyhow!(
+            "The recursive circuit vk of the {} proof chain changed from {} to {}, restore \
+             the ELFs it was proven with or set RESTART_ON_VK_CHANGE=true to restart it from \
+             its latest wrapper proof",
+            backend.name(),
+            previous_vk,
+            recursive_vk
+        ));
+    }
+    let anchor = service_state.most_recent_wrapper_proof.as_ref().context(
+        "The proof chain has no wrapper proof to restart from, delete the state instead",
+    )?;
+
+    let record = LineageRecord {
+        anchor_update: service_state.update_counter,
+        anchor_height: service_state.trusted_height,
+        anchor_root: service_state.trusted_root,
+        anchor_proof_hash: proof_hash(anchor),
+        previous_vk,
+        vk: recursive_vk.to_string(),
+        restarted_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    state_manager.restart_proof_chain(&record)?;
+    service_state.most_recent_recursive_proof = None;
+    tracing::warn!(
+        "🔀 Restarted the {} proof chain from the wrapper proof of update {} at height {}, \
+         recursive vk {} -> {}",
+        backend.name(),
+        record.anchor_update,
+        record.anchor_height,
+        record.previous_vk,
+        record.vk
+    );
+    Ok(())
+}
+
+/// The persisted round following `parent`, if an interrupted service proved its recursive
+/// proof from the same state and with the same recursive circuit
+fn resume_round<B: LightClientBackend>(
+    state_manager: &StateManager,
+    backend: &B,
+    parent: &ServiceState,
+    recursive_vk: &str,
+) -> Result<Option<RecursionRound<B::BaseOutputs>>> {
+    let Some(pending) = state_manager.load_pending_round(parent.update_counter + 1)? else {
+        return Ok(None);
+    };
+    if pending.parent_height != parent.trusted_height || pending.parent_root != parent.trusted_root
+    {
+        return Ok(None);
+    }
+    let outputs = backend.decode_outputs(pending.recursive_proof.public_values.as_slice())?;
+    if outputs.vk != recursive_vk {
+        tracing::info!(
+            "♻️  Pending update {} was proven with another recursive circuit, proving it again",
+            pending.update_counter
+        );
+        return Ok(None);
+    }
+
+    tracing::info!(
+        "⏯️  Resuming update {} of the {} proof chain from its recursive proof",
+        pending.update_counter,
+        backend.name()
+    );
+    Ok(Some(RecursionRound {
+        base_outputs: backend.decode_base_outputs(&pending.base_outputs)?,
+        recursive_proof: pending.recursive_proof,
+        recursion_inputs_sha256: pending.recursion_inputs_sha256,
+        base_ms: pending.base_ms,
+        recursive_ms: pending.recursive_ms,
+    }))
+}
+
+/// Generates the wrapper proof of a round with a recursive proof
+async fn prove_wrapper<B: LightClientBackend, P: RoundProver<B>>(
+    backend: &B,
+    prover: &P,
+    round: RecursionRound<B::BaseOutputs>,
+) -> Result<RoundProofs<B::BaseOutputs>> {
+    // Prepare inputs for wrapper proof generation
+    tracing::info!("📦 Preparing inputs for wrapper proof generation...");
+    let mut stdin = backend.wrapper_inputs(&round.recursive_proof);
+    prover.write_recursive_proof(&mut stdin, &round.recursive_proof)?;
+
+    tracing::info!("🎁 Generating wrapper proof...");
+    let step_start = Instant::now();
+    let wrapper_proof = prover.wrapper_proof(backend, stdin).await?;
+    let wrapper_ms = elapsed_ms(step_start);
+    tracing::info!("✅ Wrapper proof generated successfully");
+
+    let metadata = RoundMetadata {
+        backend: backend.name().to_string(),
+        prover_mode: ProverMode::from_env()?.to_string(),
+        sp1_prover: std::env::var("SP1_PROVER").ok(),
+        rpc_endpoints: backend.rpc_endpoints(),
+        circuits: prover.circuits(),
+        recursion_inputs_sha256: round.recursion_inputs_sha256,
+        timings: RoundTimings {
+            base_ms: round.base_ms,
+            recursive_ms: round.recursive_ms,
+            wrapper_ms,
+        },
+    };
+    Ok(RoundProofs {
+        base_outputs: round.base_outputs,
+        recursive_proof: round.recursive_proof,
+        wrapper_proof,
+        metadata,
+    })
+}
+
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+/// Commits the proofs of a round as the next update of the chain
+fn commit_round<B: LightClientBackend>(
+    state_manager: &StateManager,
+    service_state: &mut ServiceState,
+    backend: &B,
+    proofs: RoundProofs<B::BaseOutputs>,
+) -> Result<UpdateRecord> {
+    // Update service state with new trusted information
+    tracing::info!("📊 Updating service state with new trusted information...");
+    let outputs = backend.decode_outputs(proofs.recursive_proof.public_values.as_slice())?;
+    let base_inputs = backend.base_inputs(&proofs.base_outputs);
+    let committees = backend.committees(&proofs.base_outputs);
+    backend.update_state(service_state, &proofs.base_outputs, &outputs);
+    service_state.most_recent_recursive_proof = Some(proofs.recursive_proof);
+    service_state.most_recent_wrapper_proof = Some(proofs.wrapper_proof);
+    service_state.update_counter += 1;
+    service_state.generated_at = Some(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before the unix epoch")?
+            .as_secs(),
+    );
+
+    // Save updated state to persistent storage
+    tracing::info!("💾 Saving service state to persistent storage...");
+    let record = state_manager.commit_update(
+        backend.name(),
+        service_state,
+        base_inputs.as_deref(),
+        Some(&proofs.metadata),
+        &committees,
+    )?;
+    tracing::info!(
+        "✅ Committed update {} - Root: {}, Slot: {}, Height: {}",
+        record.update_id,
+        to_hex(service_state.trusted_root),
+        service_state.trusted_slot,
+        service_state.trusted_height
+    );
+    tracing::info!("🔗 Update history hash: {}", to_hex(record.hash));
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{AppState, ProofQuery, ProofResponse, get_proof};
+    use crate::backends::{HeliosBackend, HeliosBaseOutputs, TendermintBackend};
+    use crate::chains::{DEFAULT_TRUSTING_PERIOD_SECS, TendermintChainConfig, TendermintUpgrade};
+    use crate::networks::EthereumNetwork;
+    use alloy_primitives::{B256, U256};
+    use axum::{
+        body::to_bytes,
+        extract::{Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+    };
+    use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+    use beacon_electra::types::electra::{ElectraBlockBodyRoots, ElectraBlockHeader};
+    use cosmrs::tendermint::{
+        Hash, Time, account,
+        block::{Header, header::Version},
+    };
+    use helios_recursion_types::{
+        RecursionCircuitInputs as HeliosRecursionCircuitInputs,
+        RecursionCircuitOutputs as HeliosRecursionCircuitOutputs,
+        WrapperCircuitOutputs as HeliosWrapperCircuitOutputs,
+        decode_outputs as decode_helios_outputs, encode_outputs as encode_helios_outputs,
+    };
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
+    use sp1_helios_primitives::types::ProofOutputs as HeliosOutputs;
+    use sp1_sdk::Prover;
+    use sp1_tendermint_primitives::TendermintOutput;
+    use std::time::Duration;
+    use tendermint_recursion_types::{
+        RecursionCircuitInputs as TendermintRecursionCircuitInputs,
+        RecursionCircuitOutputs as TendermintRecursionCircuitOutputs,
+        WrapperCircuitOutputs as TendermintWrapperCircuitOutputs,
+        decode_outputs as decode_tendermint_outputs, encode_outputs as encode_tendermint_outputs,
+    };
+    use tokio::sync::Mutex;
+
+    /// The API reads the database location and mode from the environment, so tests that
+    /// touch it must not run concurrently.
+    static ENV_LOCK: Mutex<()> = Mutex::const_new(());
+
+    const RECURSIVE_VK: &str = "0x0034e4a559df3be8975c94d57857e1e6fbfc4d26177b8f60ccd2dd86e75fd8c7";
+
+    /// The trusted checkpoints the test rounds start from
+    const HELIOS_TRUSTED_SLOT: u64 = 11715392;
+    const TENDERMINT_TRUSTED_HEIGHT: u64 = 31134400;
+
+    #[derive(Deserialize)]
+    struct HeliosRoundFixture {
+        electra_header: ElectraBlockHeader,
+        electra_body_roots: ElectraBlockBodyRoots,
+    }
+
+    /// Replays a recorded round: every proof is a mock proof carrying the
+    /// public values the real circuits would have committed.
+    struct FixtureProver<B: LightClientBackend> {
+        pk: SP1ProvingKey,
+        base: (B::BaseOutputs, B::RecursionInputs),
+        recursive_public_values: Vec<u8>,
+        wrapper_public_values: Vec<u8>,
+    }
+
+    impl<B: LightClientBackend> FixtureProver<B> {
+        fn new(
+            base: (B::BaseOutputs, B::RecursionInputs),
+            recursive_public_values: Vec<u8>,
+            wrapper_public_values: Vec<u8>,
+        ) -> Self {
+            let (pk, _) = ProverClient::builder()
+                .mock()
+                .build()
+                .setup(crate::WRAPPER_ELF_TENDERMINT);
+            Self {
+                pk,
+                base,
+                recursive_public_values,
+                wrapper_public_values,
             }
+        }
+
+        fn mock_proof(&self, public_values: &[u8]) -> SP1ProofWithPublicValues {
+            mock_proof(&self.pk, public_values)
+        }
+    }
+
+    impl<B: LightClientBackend> RoundProver<B> for FixtureProver<B> {
+        async fn setup(&mut self, _backend: &B) -> Result<String> {
+            Ok(RECURSIVE_VK.to_string())
+        }
+
+        async fn base_proof(
+            &self,
+            _backend: &B,
+            _service_state: &ServiceState,
+            _recursive_vk: String,
+        ) -> Result<(B::BaseOutputs, B::RecursionInputs)> {
+            Ok(self.base.clone())
+        }
+
+        async fn recursive_proof(
+            &self,
+            _backend: &B,
+            _stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            Ok(self.mock_proof(&self.recursive_public_values))
+        }
+
+        async fn wrapper_proof(
+            &self,
+            _backend: &B,
+            _stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            Ok(self.mock_proof(&self.wrapper_public_values))
+        }
+    }
+
+    /// Runs exactly one round against a fresh database and returns the
+    /// committed state together with the API response for the latest proof.
+    async fn run_one_round<B: LightClientBackend>(
+        backend: B,
+        prover: FixtureProver<B>,
+        initial_slot: u64,
+        initial_height: u64,
+    ) -> (ServiceState, StatusCode, Vec<u8>) {
+        let _guard = ENV_LOCK.lock().await;
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("service_state.db");
+        // SAFETY: guarded by ENV_LOCK, no other test thread reads the environment concurrently
+        unsafe {
+            std::env::set_var("SERVICE_STATE_DB_PATH", &db_path);
+            std::env::set_var("CLIENT_BACKEND", backend.name());
+        }
+
+        let state_manager = StateManager::new(&db_path).unwrap();
+        let service_state = state_manager
+            .initialize_state(initial_slot, initial_height)
+            .unwrap();
+        let base_inputs = backend.base_inputs(&prover.base.0);
+        let backend_name = backend.name();
+        run_prover_loop(
+            state_manager,
+            service_state,
+            backend,
+            prover,
+            Some(1),
+            Stop::never(),
+        )
+        .await
+        .unwrap();
+
+        let state_manager = StateManager::new(&db_path).unwrap();
+        let state = state_manager
+            .load_state()
+            .unwrap()
+            .expect("state must be committed after one round");
+        let history = state_manager.load_history(0, 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].verify(&[0; 32]));
+        assert_eq!(history[0].height, state.trusted_height);
+        assert_eq!(history[0].root, state.trusted_root);
+        assert_eq!(state_manager.load_base_inputs(1).unwrap(), base_inputs);
+        let debug = crate::rounds::RoundDebug::load(&state_manager, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(debug.record.hash, to_hex(history[0].hash));
+        assert_eq!(debug.base_inputs_url.is_some(), base_inputs.is_some());
+        assert_eq!(debug.round.unwrap().backend, backend_name);
+        let app = AppState {
+            db: crate::state::open_pool(&db_path).unwrap(),
         };
+        let response = get_proof(State(app), Query(ProofQuery::default()))
+            .await
+            .into_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec();
+        (state, status, body)
+    }
 
-        // Prepare inputs for wrapper proof generation
-        tracing::info!("📦 Preparing inputs for wrapper proof generation...");
-        let mut stdin = SP1Stdin::new();
-        match recursive_prover {
-            RecursiveProver::Helios(_) => {
-                let wrapper_inputs = HeliosWrapperCircuitInputs {
-                    recursive_proof: recursive_proof.bytes(),
-                    recursive_public_values: recursive_proof.public_values.to_vec(),
-                };
-                stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
-            }
-            RecursiveProver::Tendermint(_) => {
-                let wrapper_inputs = TendermintWrapperCircuitInputs {
-                    recursive_proof: recursive_proof.bytes(),
-                    recursive_public_values: recursive_proof.public_values.to_vec(),
-                };
-                stdin.write_slice(&borsh::to_vec(&wrapper_inputs).unwrap());
-            }
+    #[tokio::test]
+    async fn test_helios_mock_round() {
+        let fixture: HeliosRoundFixture =
+            serde_json::from_str(lightwave_fixtures::SYNTHETIC_HELIOS_ROUND).unwrap();
+        let payload_roots = &fixture.electra_body_roots.payload_roots;
+        let height = u64::from_le_bytes(payload_roots.block_number[..8].try_into().unwrap());
+        let root = payload_roots.state_root;
+        let new_head = fixture.electra_header.slot;
+
+        let helios_outputs = HeliosOutputs {
+            executionStateRoot: B256::from(root),
+            newHeader: B256::repeat_byte(1),
+            nextSyncCommitteeHash: B256::ZERO,
+            newHead: U256::from(new_head),
+            prevHeader: B256::repeat_byte(2),
+            prevHead: U256::from(HELIOS_TRUSTED_SLOT),
+            syncCommitteeHash: B256::repeat_byte(3),
+            prevSyncCommitteeHash: B256::repeat_byte(3),
+        };
+        let recursion_inputs = HeliosRecursionCircuitInputs {
+            electra_body_roots: fixture.electra_body_roots.clone(),
+            electra_header: fixture.electra_header.clone(),
+            helios_proof: Vec::new(),
+            helios_public_values: Vec::new(),
+            recursive_proof: None,
+            recursive_public_values: None,
+            recursive_vk: RECURSIVE_VK.to_string(),
+            previous_head: HELIOS_TRUSTED_SLOT,
+        };
+        let recursion_outputs = HeliosRecursionCircuitOutputs {
+            active_committee: [3; 32],
+            previous_committee: [3; 32],
+            next_committee: None,
+            root,
+            height,
+            block_hash: payload_roots.block_hash,
+            timestamp: u64::from_le_bytes(payload_roots.timestamp[..8].try_into().unwrap()),
+            receipts_root: payload_roots.receipts_root,
+            vk: RECURSIVE_VK.to_string(),
+            previous_height: 0,
+            previous_root: [0; 32],
+            chain_id: "eip155:1".to_string(),
+        };
+        let wrapper_outputs = HeliosWrapperCircuitOutputs {
+            height,
+            root,
+            previous_height: 0,
+            previous_root: [0; 32],
+            chain_id: "eip155:1".to_string(),
+        };
+        let prover = FixtureProver::<HeliosBackend>::new(
+            (
+                HeliosBaseOutputs {
+                    outputs: helios_outputs,
+                    inputs: b"helios inputs".to_vec(),
+                    attested_root: [4; 32],
+                },
+                recursion_inputs,
+            ),
+            encode_helios_outputs(&recursion_outputs),
+            encode_helios_outputs(&wrapper_outputs),
+        );
+
+        let mode = "HELIOS";
+        let backend = HeliosBackend::new(String::new(), EthereumNetwork::Mainnet);
+        let (state, status, body) = run_one_round(backend, prover, HELIOS_TRUSTED_SLOT, 0).await;
+
+        assert_eq!(state.update_counter, 1);
+        assert_eq!(state.trusted_slot, new_head);
+        assert_eq!(state.trusted_height, height);
+        assert_eq!(state.trusted_root, root);
+        let recursive_proof = state.most_recent_recursive_proof.unwrap();
+        let decoded: HeliosRecursionCircuitOutputs =
+            decode_helios_outputs(recursive_proof.public_values.as_slice()).unwrap();
+        assert_eq!(decoded.vk, RECURSIVE_VK);
+
+        assert_eq!(status, StatusCode::OK);
+        let served: ProofResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(served.mode, mode);
+        assert_eq!(served.vk, RECURSIVE_VK);
+        assert_eq!(served.root, to_hex(root));
+        assert!(served.generated_at.is_some());
+        let served_outputs: HeliosWrapperCircuitOutputs =
+            decode_helios_outputs(&BASE64.decode(served.public_values_b64).unwrap()).unwrap();
+        assert_eq!(served_outputs.height, height);
+        assert_eq!(served_outputs.root, root);
+    }
+
+    /// The times of the trusted and target headers of the Tendermint fixture
+    const TENDERMINT_TRUSTED_TIME: i64 = 1_699_900_000;
+    const TENDERMINT_TARGET_TIME: i64 = 1_700_000_000;
+
+    /// A header of a Tendermint fixture height. The capture has no headers, its proven
+    /// outputs are pointed at the hashes of these
+    fn tendermint_header(height: u64, time: i64) -> Header {
+        Header {
+            version: Version { block: 11, app: 0 },
+            chain_id: "pion-1".parse().unwrap(),
+            height: height.try_into().unwrap(),
+            time: Time::from_unix_timestamp(time, 0).unwrap(),
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::Sha256([1; 32]),
+            next_validators_hash: Hash::Sha256([2; 32]),
+            consensus_hash: Hash::Sha256([3; 32]),
+            app_hash: vec![4; 32].try_into().unwrap(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([5; 20]),
         }
+    }
 
-        tracing::info!("🎁 Generating wrapper proof...");
-        // Run wrapper proof generation in isolated task
-        let final_wrapped_proof = {
-            let wrapper_pk_clone = wrapper_pk.clone();
-            let stdin_clone = stdin.clone();
-            cleanup_gpu_containers()?;
-            let client = ProverClient::from_env();
-
-            let handle = tokio::spawn(async move {
-                let _ = client.setup(&wrapper_elf_clone);
-                client
-                    .prove(&wrapper_pk_clone, &stdin_clone)
-                    .groth16()
-                    .run()
-            });
-
-            match handle.await {
-                Ok(Ok(proof)) => {
-                    tracing::info!("✅ Wrapper proof generated successfully");
-                    proof
-                }
-                Ok(Err(e)) => {
-                    tracing::error!("❌ Wrapper proof generation failed: {}", e);
-                    tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
-                    continue;
-                }
-                Err(join_error) => {
-                    tracing::error!("❌ Wrapper proof task failed: {}", join_error);
-                    tokio::time::sleep(Duration::from_secs(DEFAULT_TIMEOUT)).await;
-                    continue;
-                }
-            }
+    /// The Tendermint fixture proving headers of [`tendermint_header`], the target one at
+    /// `target_time`, and the recursion inputs of the first link extending it
+    fn tendermint_fixture(
+        target_time: i64,
+    ) -> (TendermintOutput, TendermintRecursionCircuitInputs) {
+        let mut tendermint_outputs: TendermintOutput =
+            serde_json::from_str(lightwave_fixtures::SYNTHETIC_TENDERMINT_OUTPUT).unwrap();
+        let trusted_header =
+            tendermint_header(tendermint_outputs.trusted_height, TENDERMINT_TRUSTED_TIME);
+        let target_header = tendermint_header(tendermint_outputs.target_height, target_time);
+        tendermint_outputs.trusted_header_hash =
+            trusted_header.hash().as_bytes().try_into().unwrap();
+        tendermint_outputs.target_header_hash = target_header.hash().as_bytes().try_into().unwrap();
+        let inputs = TendermintRecursionCircuitInputs {
+            tendermint_proof: Vec::new(),
+            tendermint_public_values: serde_json::to_vec(&tendermint_outputs).unwrap(),
+            recursive_proof: None,
+            recursive_public_values: None,
+            recursive_vk: RECURSIVE_VK.to_string(),
+            trusted_height: TENDERMINT_TRUSTED_HEIGHT,
+            trusted_header: serde_json::to_vec(&trusted_header).unwrap(),
+            target_header: serde_json::to_vec(&target_header).unwrap(),
+            transition: false,
         };
+        (tendermint_outputs, inputs)
+    }
 
-        // Update service state with new trusted information
-        tracing::info!("📊 Updating service state with new trusted information...");
-        match recursive_prover {
-            RecursiveProver::Helios((helios_outputs, _)) => {
-                let wrapped_outputs: HeliosRecursionCircuitOutputs =
-                    borsh::from_slice(&recursive_proof.public_values.to_vec())
-                        .expect("Failed to decode Helios outputs");
-                service_state.most_recent_recursive_proof = Some(recursive_proof.clone());
-                service_state.most_recent_wrapper_proof = Some(final_wrapped_proof);
-                service_state.trusted_slot = helios_outputs.newHead.try_into().unwrap();
-                service_state.trusted_height = wrapped_outputs.height;
-                service_state.trusted_root = wrapped_outputs.root;
-                service_state.update_counter += 1;
-            }
-            RecursiveProver::Tendermint((tendermint_outputs, _)) => {
-                let wrapped_outputs: TendermintRecursionCircuitOutputs =
-                    borsh::from_slice(&recursive_proof.public_values.to_vec())
-                        .expect("Failed to decode Tendermint outputs");
-                service_state.most_recent_recursive_proof = Some(recursive_proof.clone());
-                service_state.most_recent_wrapper_proof = Some(final_wrapped_proof);
-                // In the case of Tendermint, the trusted slot is the target height
-                service_state.trusted_slot = tendermint_outputs.target_height;
-                service_state.trusted_height = wrapped_outputs.height;
-                service_state.trusted_root = wrapped_outputs.root;
-                service_state.update_counter += 1;
-            }
+    /// The recursion inputs of a link extending `previous` with a Tendermint proof from
+    /// `trusted_header` to `target_header`
+    fn tendermint_extension(
+        previous: &[u8],
+        trusted_header: &Header,
+        target_header: &Header,
+    ) -> TendermintRecursionCircuitInputs {
+        let mut tendermint_outputs: TendermintOutput =
+            serde_json::from_str(lightwave_fixtures::SYNTHETIC_TENDERMINT_OUTPUT).unwrap();
+        tendermint_outputs.trusted_height = trusted_header.height.value();
+        tendermint_outputs.trusted_header_hash =
+            trusted_header.hash().as_bytes().try_into().unwrap();
+        tendermint_outputs.target_height = target_header.height.value();
+        tendermint_outputs.target_header_hash = target_header.hash().as_bytes().try_into().unwrap();
+        TendermintRecursionCircuitInputs {
+            tendermint_proof: Vec::new(),
+            tendermint_public_values: serde_json::to_vec(&tendermint_outputs).unwrap(),
+            recursive_proof: None,
+            recursive_public_values: Some(previous.to_vec()),
+            recursive_vk: RECURSIVE_VK.to_string(),
+            trusted_height: trusted_header.height.value(),
+            trusted_header: serde_json::to_vec(trusted_header).unwrap(),
+            target_header: serde_json::to_vec(target_header).unwrap(),
+            transition: false,
         }
+    }
 
-        // Save updated state to persistent storage
-        tracing::info!("💾 Saving service state to persistent storage...");
-        state_manager.save_state(&service_state)?;
-        tracing::info!(
-            "✅ Service state updated - Root: {:?}, Slot: {}, Height: {}",
-            service_state.trusted_root,
-            service_state.trusted_slot,
-            service_state.trusted_height
+    #[tokio::test]
+    async fn test_tendermint_mock_round() {
+        let (tendermint_outputs, recursion_inputs) = tendermint_fixture(TENDERMINT_TARGET_TIME);
+        let height = tendermint_outputs.target_height;
+        let root = tendermint_outputs.target_header_hash;
+        let recursion_outputs = TendermintRecursionCircuitOutputs {
+            root,
+            height,
+            vk: RECURSIVE_VK.to_string(),
+            previous_height: TENDERMINT_TRUSTED_HEIGHT,
+            previous_root: tendermint_outputs.trusted_header_hash,
+            chain_id: "cosmos:pion-1".to_string(),
+            app_hash: [4; 32],
+            next_validators_hash: [2; 32],
+            time: 1_700_000_000_000_000_000,
+            transition: false,
+        };
+        let wrapper_outputs = TendermintWrapperCircuitOutputs {
+            height,
+            root,
+            previous_height: TENDERMINT_TRUSTED_HEIGHT,
+            previous_root: tendermint_outputs.trusted_header_hash,
+            transition: false,
+            chain_id: "cosmos:pion-1".to_string(),
+        };
+        let prover = FixtureProver::<TendermintBackend>::new(
+            (tendermint_outputs, recursion_inputs),
+            encode_tendermint_outputs(&recursion_outputs),
+            encode_tendermint_outputs(&wrapper_outputs),
         );
 
-        let round_duration = round_start_time.elapsed();
-        tracing::info!("⏱️  Round completed in: {:?}", round_duration);
-        tracing::info!("⏱️  Service uptime: {:?}", start_time.elapsed());
+        let mode = "TENDERMINT";
+        let (state, status, body) = run_one_round(
+            TendermintBackend::new(None),
+            prover,
+            TENDERMINT_TRUSTED_HEIGHT,
+            TENDERMINT_TRUSTED_HEIGHT,
+        )
+        .await;
+
+        assert_eq!(state.update_counter, 1);
+        assert_eq!(state.trusted_slot, height);
+        assert_eq!(state.trusted_height, height);
+        assert_eq!(state.trusted_root, root);
+        let recursive_proof = state.most_recent_recursive_proof.unwrap();
+        let decoded: TendermintRecursionCircuitOutputs =
+            decode_tendermint_outputs(recursive_proof.public_values.as_slice()).unwrap();
+        assert_eq!(decoded.vk, RECURSIVE_VK);
+
+        assert_eq!(status, StatusCode::OK);
+        let served: ProofResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(served.mode, mode);
+        assert_eq!(served.vk, RECURSIVE_VK);
+        assert_eq!(served.root, to_hex(root));
+        assert!(served.generated_at.is_some());
+        let served_outputs: TendermintWrapperCircuitOutputs =
+            decode_tendermint_outputs(&BASE64.decode(served.public_values_b64).unwrap()).unwrap();
+        assert_eq!(served_outputs.height, height);
+        assert_eq!(served_outputs.root, root);
     }
-}
 
-/// Generates a Tendermint proof and prepares recursive circuit inputs
-///
-/// This function:
-/// 1. Connects to Tendermint RPC to get latest block information
-/// 2. Generates a Tendermint proof for the target block range
-/// 3. Prepares inputs for the recursive circuit
-async fn tendermint_prover(
-    service_state: &ServiceState,
-    recursive_vk: String,
-) -> Result<RecursiveProver> {
-    dotenvy::dotenv().ok();
-
-    tracing::info!("🌿 Starting Tendermint proof generation...");
-    let tendermint_proof = {
-        cleanup_gpu_containers()?;
-
-        // Get expiration limit from environment
-        let tendermint_expiration_limit = std::env::var("TENDERMINT_EXPIRATION_LIMIT")
-            .unwrap_or_else(|_| "100000".to_string())
-            .parse::<u64>()
-            .unwrap_or(100_000);
-
-        tracing::info!("🔗 Connecting to Tendermint RPC...");
-        let tendermint_rpc_client = TendermintRPCClient::default();
-        let tendermint_height = tendermint_rpc_client.get_latest_block_height().await;
-        let tendermint_prover = TendermintProver::new();
-
-        // Calculate target height with expiration limit
-        let target_height = min(
-            tendermint_height,
-            service_state.trusted_height + tendermint_expiration_limit,
+    #[test]
+    fn test_check_embedded_vk() {
+        let vk = format!("0x{}", "ab".repeat(32));
+        let elf = [b"\x7fELF".as_slice(), vk.as_bytes(), b"\0"].concat();
+        assert!(check_embedded_vk("wrapper", &elf, "recursive", &vk).is_ok());
+        assert!(check_embedded_vk("wrapper", &elf, "recursive", RECURSIVE_VK).is_err());
+    }
+
+    #[test]
+    fn test_tendermint_mock_outputs() {
+        let (tendermint_outputs, recursion_inputs) = tendermint_fixture(TENDERMINT_TARGET_TIME);
+
+        // The host computes what the recursion and wrapper circuits would commit
+        let backend = TendermintBackend::new(None);
+        let recursive_public_values = backend
+            .mock_recursion_outputs(&borsh::to_vec(&recursion_inputs).unwrap())
+            .unwrap();
+        let outputs = backend.decode_outputs(&recursive_public_values).unwrap();
+        assert_eq!(outputs.height, tendermint_outputs.target_height);
+        assert_eq!(outputs.root, tendermint_outputs.target_header_hash);
+        assert_eq!(outputs.vk, RECURSIVE_VK);
+        let header_outputs: TendermintRecursionCircuitOutputs =
+            decode_tendermint_outputs(&recursive_public_values).unwrap();
+        assert_eq!(header_outputs.app_hash, [4; 32]);
+        assert_eq!(header_outputs.next_validators_hash, [2; 32]);
+        assert_eq!(header_outputs.time, 1_700_000_000_000_000_000);
+
+        // The header fields are only read from the header of the proven hash
+        let other_header = TendermintRecursionCircuitInputs {
+            target_header: serde_json::to_vec(&tendermint_header(
+                outputs.height + 1,
+                TENDERMINT_TARGET_TIME,
+            ))
+            .unwrap(),
+            ..recursion_inputs.clone()
+        };
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&other_header).unwrap())
+                .is_err()
         );
 
-        tracing::info!("📦 Fetching light blocks for proof generation...");
-        // Get light blocks for proof generation
-        let (trusted_light_block, target_light_block) = tendermint_rpc_client
-            .get_light_blocks(service_state.trusted_height, target_height)
-            .await;
+        // A target header past the trusting period of the trusted one can't be proven
+        let (_, stale) =
+            tendermint_fixture(TENDERMINT_TRUSTED_TIME + DEFAULT_TRUSTING_PERIOD_SECS as i64 + 1);
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&stale).unwrap())
+                .is_err()
+        );
 
-        tracing::info!("⚡ Generating Tendermint proof in isolated task...");
-        let handle = tokio::spawn(async move {
-            tendermint_prover.generate_tendermint_proof(&trusted_light_block, &target_light_block)
-        });
+        // The next link extends the header the first one committed
+        let target_header: Header =
+            serde_json::from_slice(&recursion_inputs.target_header).unwrap();
+        let next_header = tendermint_header(outputs.height + 1, TENDERMINT_TARGET_TIME + 60);
+        let extension =
+            tendermint_extension(&recursive_public_values, &target_header, &next_header);
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&extension).unwrap())
+                .is_ok()
+        );
 
-        match handle.await {
-            Ok(proof) => {
-                tracing::info!("✅ Tendermint proof generated successfully");
-                proof
-            }
-            Err(join_error) => {
-                return Err(anyhow::anyhow!(
-                    "❌ Tendermint proof task panicked: {:?}",
-                    join_error
-                ));
+        // A link that committed another vk can't be extended
+        let other_vk = TendermintRecursionCircuitInputs {
+            recursive_vk: format!("0x{}", "00".repeat(32)),
+            ..extension
+        };
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&other_vk).unwrap())
+                .is_err()
+        );
+
+        // Nor extended by a Tendermint proof starting from another header than its root
+        let other_trusted = tendermint_extension(
+            &recursive_public_values,
+            &tendermint_header(outputs.height, TENDERMINT_TARGET_TIME + 1),
+            &next_header,
+        );
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&other_trusted).unwrap())
+                .is_err()
+        );
+
+        // A link left stale past the trusting period can't be extended, neither from the header
+        // it committed nor from a recent header
+        let late = TENDERMINT_TARGET_TIME + DEFAULT_TRUSTING_PERIOD_SECS as i64;
+        let late_header = tendermint_header(outputs.height + 1, late + 60);
+        let stale_extension =
+            tendermint_extension(&recursive_public_values, &target_header, &late_header);
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&stale_extension).unwrap())
+                .is_err()
+        );
+        let recent_trusted = tendermint_extension(
+            &recursive_public_values,
+            &tendermint_header(outputs.height, late),
+            &late_header,
+        );
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&recent_trusted).unwrap())
+                .is_err()
+        );
+
+        let (pk, _) = ProverClient::builder()
+            .mock()
+            .build()
+            .setup(crate::WRAPPER_ELF_TENDERMINT);
+        let stdin = backend.wrapper_inputs(&mock_proof(&pk, &recursive_public_values));
+        let wrapper_outputs: TendermintWrapperCircuitOutputs = decode_tendermint_outputs(
+            &backend
+                .mock_wrapper_outputs(circuit_inputs(&stdin).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(wrapper_outputs.height, outputs.height);
+        assert_eq!(wrapper_outputs.root, outputs.root);
+    }
+
+    #[test]
+    fn test_tendermint_mock_transition() {
+        let (tendermint_outputs, recursion_inputs) = tendermint_fixture(TENDERMINT_TARGET_TIME);
+        let halt_height = tendermint_outputs.target_height;
+        let anchor = tendermint_header(halt_height + 10, TENDERMINT_TARGET_TIME + 3600);
+        let anchor_root: [u8; 32] = anchor.hash().as_bytes().try_into().unwrap();
+        let backend = TendermintBackend::new(Some(TendermintChainConfig {
+            chain_id: "pion".to_string(),
+            rpc_url: String::new(),
+            trusted_height: TENDERMINT_TRUSTED_HEIGHT,
+            trusted_root: tendermint_outputs.trusted_header_hash,
+            network: Some("pion-1".to_string()),
+            trusting_period_secs: None,
+            trust_threshold: None,
+            min_height_advance: None,
+            max_height_advance: None,
+            upgrade: Some(TendermintUpgrade {
+                halt_height,
+                network: "pion-2".to_string(),
+                trusted_height: halt_height + 10,
+                trusted_root: anchor_root,
+            }),
+        }));
+        let halted = backend
+            .mock_recursion_outputs(&borsh::to_vec(&recursion_inputs).unwrap())
+            .unwrap();
+
+        // The transition extends the proof of the halt height with the approved header
+        let transition = TendermintRecursionCircuitInputs {
+            tendermint_proof: Vec::new(),
+            tendermint_public_values: Vec::new(),
+            recursive_proof: None,
+            recursive_public_values: Some(halted.clone()),
+            recursive_vk: RECURSIVE_VK.to_string(),
+            trusted_height: halt_height,
+            trusted_header: Vec::new(),
+            target_header: serde_json::to_vec(&anchor).unwrap(),
+            transition: true,
+        };
+        let outputs: TendermintRecursionCircuitOutputs = decode_tendermint_outputs(
+            &backend
+                .mock_recursion_outputs(&borsh::to_vec(&transition).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(outputs.transition);
+        assert_eq!(
+            (outputs.height, outputs.root),
+            (halt_height + 10, anchor_root)
+        );
+        assert_eq!(outputs.previous_height, halt_height);
+        assert_eq!(outputs.chain_id, "cosmos:pion-2");
+
+        // The wrapper outputs tell the transition from a regular update
+        let (pk, _) = ProverClient::builder()
+            .mock()
+            .build()
+            .setup(crate::WRAPPER_ELF_TENDERMINT);
+        let recursive_public_values = encode_tendermint_outputs(&outputs);
+        let stdin = backend.wrapper_inputs(&mock_proof(&pk, &recursive_public_values));
+        let wrapper_outputs: TendermintWrapperCircuitOutputs = decode_tendermint_outputs(
+            &backend
+                .mock_wrapper_outputs(circuit_inputs(&stdin).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(wrapper_outputs.transition);
+        assert_eq!(wrapper_outputs.chain_id, "cosmos:pion-2");
+
+        // Only the proof of the halt height is re-anchored
+        let mut before_halt: TendermintRecursionCircuitOutputs =
+            decode_tendermint_outputs(&halted).unwrap();
+        before_halt.height -= 1;
+        let early = TendermintRecursionCircuitInputs {
+            recursive_public_values: Some(encode_tendermint_outputs(&before_halt)),
+            ..transition
+        };
+        assert!(
+            backend
+                .mock_recursion_outputs(&borsh::to_vec(&early).unwrap())
+                .is_err()
+        );
+    }
+
+    /// Proves a new Tendermint height every round, pausing at random points so the chaos
+    /// test kills the service in every step of a round
+    struct ChaosProver {
+        pk: SP1ProvingKey,
+        fixture: TendermintOutput,
+        /// The height proven by the current round
+        height: std::sync::Mutex<u64>,
+        rng: std::sync::Mutex<u64>,
+    }
+
+    impl ChaosProver {
+        fn new() -> Self {
+            let (pk, _) = ProverClient::builder()
+                .mock()
+                .build()
+                .setup(crate::WRAPPER_ELF_TENDERMINT);
+            Self {
+                pk,
+                fixture: serde_json::from_str(lightwave_fixtures::SYNTHETIC_TENDERMINT_OUTPUT)
+                    .unwrap(),
+                height: std::sync::Mutex::new(0),
+                rng: std::sync::Mutex::new(chaos_seed()),
             }
         }
-    };
 
-    // Decode proof outputs
-    tracing::info!("🔍 Decoding Tendermint proof outputs...");
-    let tendermint_outputs: TendermintOutput =
-        serde_json::from_slice(&tendermint_proof.public_values.to_vec()).unwrap();
-
-    let previous_proof = service_state.most_recent_recursive_proof.clone();
-
-    // Prepare recursive circuit inputs
-    tracing::info!("📝 Preparing recursive circuit inputs...");
-    let recursion_inputs = TendermintRecursionCircuitInputs {
-        tendermint_proof: tendermint_proof.bytes(),
-        tendermint_public_values: tendermint_proof.public_values.to_vec(),
-        recursive_proof: previous_proof.as_ref().map(|p| p.bytes()),
-        recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
-        recursive_vk,
-        trusted_height: service_state.trusted_height,
-    };
+        async fn pause(&self) {
+            let millis = next_random(&mut self.rng.lock().unwrap()) % 50;
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+        }
 
-    tracing::info!("✅ Tendermint prover completed successfully");
-    Ok(RecursiveProver::Tendermint((
-        tendermint_outputs,
-        recursion_inputs,
-    )))
-}
+        fn mock_proof(&self, public_values: &[u8]) -> SP1ProofWithPublicValues {
+            mock_proof(&self.pk, public_values)
+        }
+    }
 
-/// Generates a Helios proof and prepares recursive circuit inputs
-///
-/// This function:
-/// 1. Runs the Helios preprocessor to get block data
-/// 2. Generates a Helios proof for the target slot
-/// 3. Fetches Electra block information from consensus layer
-/// 4. Prepares inputs for the recursive circuit
-async fn helios_prover(
-    helios_elf: &[u8],
-    recursive_vk: String,
-    service_state: &ServiceState,
-    consensus_url: &str,
-) -> Result<RecursiveProver> {
-    // Run Helios preprocessor to get block inputs
-    tracing::info!("🌞 Running Helios preprocessor...");
-    let preprocessor = Preprocessor::new(service_state.trusted_slot);
-    let inputs = match preprocessor.run().await {
-        Ok(inputs) => {
-            tracing::info!("✅ Helios preprocessor completed successfully");
-            inputs
-        }
-        Err(e) => {
-            return Err(anyhow::anyhow!("❌ Helios preprocessor failed: {:?}", e));
+    fn chaos_root(height: u64) -> [u8; 32] {
+        Sha256::digest(height.to_be_bytes()).into()
+    }
+
+    fn chaos_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos() as u64
+            | 1
+    }
+
+    /// xorshift64, enough to scatter the kills
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    impl RoundProver<TendermintBackend> for ChaosProver {
+        async fn setup(&mut self, _backend: &TendermintBackend) -> Result<String> {
+            Ok(RECURSIVE_VK.to_string())
         }
-    };
 
-    // Prepare inputs for Helios proof generation
-    tracing::info!("📝 Preparing inputs for Helios proof generation...");
-    let mut stdin = SP1Stdin::new();
-    stdin.write_slice(&inputs);
+        async fn base_proof(
+            &self,
+            _backend: &TendermintBackend,
+            service_state: &ServiceState,
+            _recursive_vk: String,
+        ) -> Result<(TendermintOutput, TendermintRecursionCircuitInputs)> {
+            self.pause().await;
+            let height = service_state.trusted_height + 1;
+            *self.height.lock().unwrap() = height;
+            let outputs = TendermintOutput {
+                trusted_height: service_state.trusted_height,
+                target_height: height,
+                trusted_header_hash: service_state.trusted_root,
+                target_header_hash: chaos_root(height),
+            };
+            let inputs = TendermintRecursionCircuitInputs {
+                tendermint_proof: Vec::new(),
+                tendermint_public_values: serde_json::to_vec(&self.fixture)?,
+                recursive_proof: None,
+                recursive_public_values: None,
+                recursive_vk: RECURSIVE_VK.to_string(),
+                trusted_height: service_state.trusted_height,
+                trusted_header: Vec::new(),
+                target_header: Vec::new(),
+                transition: false,
+            };
+            Ok((outputs, inputs))
+        }
 
-    tracing::info!("⚡ Generating Helios proof...");
-    let helios_proof = {
-        let stdin_clone = stdin.clone();
-        cleanup_gpu_containers()?;
-        let client = ProverClient::from_env();
-        let (helios_pk, _) = client.setup(helios_elf);
+        async fn recursive_proof(
+            &self,
+            _backend: &TendermintBackend,
+            _stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            self.pause().await;
+            let height = *self.height.lock().unwrap();
+            let outputs = TendermintRecursionCircuitOutputs {
+                root: chaos_root(height),
+                height,
+                vk: RECURSIVE_VK.to_string(),
+                previous_height: 0,
+                previous_root: [0; 32],
+                chain_id: "cosmos:pion-1".to_string(),
+                app_hash: [0; 32],
+                next_validators_hash: [0; 32],
+                time: 0,
+                transition: false,
+            };
+            Ok(self.mock_proof(&encode_tendermint_outputs(&outputs)))
+        }
 
-        let handle =
-            tokio::spawn(async move { client.prove(&helios_pk, &stdin_clone).groth16().run() });
+        async fn wrapper_proof(
+            &self,
+            backend: &TendermintBackend,
+            stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            self.pause().await;
+            // The next round may have started, read the height from the recursive proof
+            let public_values = backend.mock_wrapper_outputs(circuit_inputs(&stdin)?)?;
+            Ok(self.mock_proof(&public_values))
+        }
+    }
 
-        match handle.await {
-            Ok(Ok(proof)) => {
-                tracing::info!("✅ Helios proof generated successfully");
-                proof
-            }
-            Ok(Err(e)) => {
-                return Err(anyhow::anyhow!(
-                    "❌ Helios proof generation failed: {:?}",
-                    e
-                ));
+    /// Records the base and wrapper proofs of each height
+    struct RecordingProver {
+        inner: ChaosProver,
+        events: Arc<std::sync::Mutex<Vec<String>>>,
+        fail_wrapper: bool,
+    }
+
+    impl RecordingProver {
+        fn new(events: &Arc<std::sync::Mutex<Vec<String>>>, fail_wrapper: bool) -> Self {
+            Self {
+                inner: ChaosProver::new(),
+                events: events.clone(),
+                fail_wrapper,
             }
-            Err(join_error) => {
-                return Err(anyhow::anyhow!(
-                    "❌ Helios proof task failed: {:?}",
-                    join_error
-                ));
+        }
+    }
+
+    impl RoundProver<TendermintBackend> for RecordingProver {
+        async fn setup(&mut self, backend: &TendermintBackend) -> Result<String> {
+            self.inner.setup(backend).await
+        }
+
+        async fn base_proof(
+            &self,
+            backend: &TendermintBackend,
+            service_state: &ServiceState,
+            recursive_vk: String,
+        ) -> Result<(TendermintOutput, TendermintRecursionCircuitInputs)> {
+            let base = self
+                .inner
+                .base_proof(backend, service_state, recursive_vk)
+                .await?;
+            let event = format!("base {}", base.0.target_height);
+            self.events.lock().unwrap().push(event);
+            Ok(base)
+        }
+
+        async fn recursive_proof(
+            &self,
+            backend: &TendermintBackend,
+            stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            self.inner.recursive_proof(backend, stdin).await
+        }
+
+        async fn wrapper_proof(
+            &self,
+            backend: &TendermintBackend,
+            stdin: SP1Stdin,
+        ) -> Result<SP1ProofWithPublicValues> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            if self.fail_wrapper {
+                return Err(anyhow::anyhow!("Wrapper prover crashed"));
             }
+            let proof = self.inner.wrapper_proof(backend, stdin).await?;
+            let outputs: TendermintWrapperCircuitOutputs =
+                decode_tendermint_outputs(proof.public_values.as_slice())
+                    .map_err(|e| anyhow::anyhow!("Invalid wrapper outputs: {:?}", e))?;
+            let event = format!("wrapper {}", outputs.height);
+            self.events.lock().unwrap().push(event);
+            Ok(proof)
         }
-    };
+    }
 
-    // Decode proof outputs
-    tracing::info!("🔍 Decoding Helios proof outputs...");
-    let helios_outputs: HeliosOutputs =
-        HeliosOutputs::abi_decode(&helios_proof.public_values.to_vec(), false).unwrap();
-
-    // Fetch Electra block information from consensus layer
-    tracing::info!("🔗 Fetching Electra block from consensus layer...");
-    let electra_block = get_electra_block(helios_outputs.newHead.try_into()?, consensus_url).await;
-    let electra_body_roots = extract_electra_block_body(electra_block);
-    let beacon_header =
-        get_beacon_block_header(helios_outputs.newHead.try_into()?, consensus_url).await;
-    tracing::info!("✅ Electra block retrieved successfully");
-
-    // Create Electra block header
-    tracing::info!("📋 Creating Electra block header...");
-    let electra_header = ElectraBlockHeader {
-        slot: beacon_header.slot.as_u64(),
-        proposer_index: beacon_header.proposer_index,
-        parent_root: beacon_header.parent_root.to_vec().try_into().unwrap(),
-        state_root: beacon_header.state_root.to_vec().try_into().unwrap(),
-        body_root: beacon_header.body_root.to_vec().try_into().unwrap(),
-    };
+    #[tokio::test]
+    async fn test_next_round_overlaps_wrapper_proof() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("service_state.db");
+        let state_manager = StateManager::new(&db_path).unwrap();
+        let service_state = state_manager.initialize_state(0, 0).unwrap();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let prover = RecordingProver::new(&events, false);
 
-    let previous_proof = service_state.most_recent_recursive_proof.clone();
-
-    // Prepare recursive circuit inputs
-    tracing::info!("📝 Preparing recursive circuit inputs...");
-    let recursion_inputs = HeliosRecursionCircuitInputs {
-        electra_body_roots,
-        electra_header,
-        helios_proof: helios_proof.bytes(),
-        helios_public_values: helios_proof.public_values.to_vec(),
-        recursive_proof: previous_proof.as_ref().map(|p| p.bytes()),
-        recursive_public_values: previous_proof.as_ref().map(|p| p.public_values.to_vec()),
-        recursive_vk,
-        previous_head: service_state.trusted_slot,
-    };
+        run_prover_loop(
+            StateManager::new(&db_path).unwrap(),
+            service_state,
+            TendermintBackend::new(None),
+            prover,
+            Some(2),
+            Stop::never(),
+        )
+        .await
+        .unwrap();
 
-    tracing::info!("✅ Helios prover completed successfully");
-    Ok(RecursiveProver::Helios((helios_outputs, recursion_inputs)))
-}
+        // The second base proof ran while the first wrapper proof was generated
+        let events = events.lock().unwrap().clone();
+        let position = |event: &str| events.iter().position(|e| e == event).unwrap();
+        assert!(position("base 2") < position("wrapper 1"));
+        assert!(position("wrapper 1") < position("wrapper 2"));
 
-/// Enum representing different types of recursive provers
-///
-/// This allows the main loop to handle both Helios and Tendermint
-/// consensus mechanisms with a unified interface.
-#[derive(Clone)]
-enum RecursiveProver {
-    Helios((HeliosOutputs, HeliosRecursionCircuitInputs)),
-    Tendermint((TendermintOutput, TendermintRecursionCircuitInputs)),
+        // The rounds were still committed in order, each chained to the previous one
+        let history = state_manager.load_history(0, 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[1].verify(&history[0].hash));
+        assert_eq!(
+            state_manager.load_state().unwrap().unwrap().trusted_height,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_round_resumes_from_recursive_proof() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_manager = StateManager::new(&dir.path().join("service_state.db")).unwrap();
+        let service_state = state_manager.initialize_state(10, 10).unwrap();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let failed = prove_once(
+            &state_manager,
+            service_state.clone(),
+            TendermintBackend::new(None),
+            RecordingProver::new(&events, true),
+        )
+        .await;
+        assert!(failed.is_err());
+        assert!(state_manager.load_pending_round(1).unwrap().is_some());
+
+        // The restarted round proves the wrapper proof only
+        let record = prove_once(
+            &state_manager,
+            service_state,
+            TendermintBackend::new(None),
+            RecordingProver::new(&events, false),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(record.height, 11);
+        assert_eq!(*events.lock().unwrap(), ["base 11", "wrapper 11"]);
+        assert!(state_manager.load_pending_round(1).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_regenerated_circuit_restarts_from_wrapper_proof() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_manager = StateManager::new(&dir.path().join("service_state.db")).unwrap();
+        let service_state = state_manager.initialize_state(10, 10).unwrap();
+        prove_once(
+            &state_manager,
+            service_state,
+            TendermintBackend::new(None),
+            ChaosProver::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let backend = TendermintBackend::new(None);
+        let new_vk = format!("0x{}", "11".repeat(32));
+        let mut service_state = state_manager.load_state().unwrap().unwrap();
+        // Unless allowed, a changed circuit stops the proof chain
+        assert!(
+            restart_from_wrapper(&state_manager, &backend, &mut service_state, &new_vk, false)
+                .is_err()
+        );
+        restart_from_wrapper(&state_manager, &backend, &mut service_state, &new_vk, true).unwrap();
+
+        let state = state_manager.load_state().unwrap().unwrap();
+        assert!(state.most_recent_recursive_proof.is_none());
+        assert!(state.most_recent_wrapper_proof.is_some());
+        assert_eq!(state.trusted_height, 11);
+        let lineage = state_manager.load_lineage().unwrap();
+        assert_eq!(lineage.len(), 1);
+        assert_eq!(lineage[0].anchor_update, 1);
+        assert_eq!(lineage[0].previous_vk, RECURSIVE_VK);
+        assert_eq!(lineage[0].vk, new_vk);
+        // The restarted chain stays bound to its backend
+        state_manager.bind("TENDERMINT", &new_vk).unwrap();
+        assert_eq!(state_manager.bound_vk().unwrap(), Some(new_vk));
+        assert!(state_manager.bind("HELIOS", RECURSIVE_VK).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_warm_start_continues_the_proof_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = StateManager::new(&dir.path().join("source.db")).unwrap();
+        let service_state = source.initialize_state(10, 10).unwrap();
+        prove_once(
+            &source,
+            service_state,
+            TendermintBackend::new(None),
+            ChaosProver::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let snapshot = crate::warm_start::Snapshot::load(&source, "TENDERMINT")
+            .unwrap()
+            .unwrap();
+        let snapshot = serde_json::to_vec(&snapshot).unwrap();
+
+        let target = StateManager::new(&dir.path().join("target.db")).unwrap();
+        target.initialize_state(10, 10).unwrap();
+        let snapshot = || serde_json::from_slice(&snapshot).unwrap();
+        assert!(crate::warm_start::warm_start(&target, "HELIOS", snapshot(), false).is_err());
+        crate::warm_start::warm_start(&target, "TENDERMINT", snapshot(), false).unwrap();
+        assert!(crate::warm_start::warm_start(&target, "TENDERMINT", snapshot(), false).is_err());
+
+        // The next round verifies the imported recursive proof and extends the history
+        let service_state = target.load_state().unwrap().unwrap();
+        assert_eq!(service_state.trusted_height, 11);
+        let record = prove_once(
+            &target,
+            service_state,
+            TendermintBackend::new(None),
+            ChaosProver::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(record.update_counter, 2);
+        assert_eq!(record.height, 12);
+        assert_eq!(
+            record.prev_hash,
+            source.verify_history().unwrap().unwrap().hash
+        );
+        assert_eq!(target.verify_history().unwrap().unwrap().update_counter, 2);
+        assert_eq!(target.load_lineage().unwrap()[0].vk, RECURSIVE_VK);
+    }
+
+    #[tokio::test]
+    async fn test_prove_once_commits_one_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_manager = StateManager::new(&dir.path().join("service_state.db")).unwrap();
+        let service_state = state_manager.initialize_state(10, 10).unwrap();
+
+        let record = prove_once(
+            &state_manager,
+            service_state,
+            TendermintBackend::new(None),
+            ChaosProver::new(),
+        )
+        .await
+        .unwrap()
+        .expect("a new update must be committed");
+        assert_eq!(record.update_counter, 1);
+        assert_eq!(record.height, 11);
+        assert_eq!(record.root, chaos_root(11));
+
+        let state = state_manager.load_state().unwrap().unwrap();
+        assert_eq!(state.update_counter, 1);
+        assert!(state.most_recent_wrapper_proof.is_some());
+    }
+
+    /// The service process killed by the chaos test: resumes the proof chain of the database
+    /// at `CHAOS_DB_PATH` and proves `CHAOS_ROUNDS` rounds, forever if unset
+    #[tokio::test]
+    #[ignore = "spawned by test_chaos_restarts"]
+    async fn test_chaos_child() {
+        let Ok(db_path) = std::env::var("CHAOS_DB_PATH") else {
+            return;
+        };
+        let max_rounds = std::env::var("CHAOS_ROUNDS")
+            .ok()
+            .map(|rounds| rounds.parse().unwrap());
+        let state_manager = StateManager::new(std::path::Path::new(&db_path)).unwrap();
+        let service_state = match state_manager.load_state().unwrap() {
+            Some(state) => state,
+            None => state_manager.initialize_state(0, 0).unwrap(),
+        };
+        run_prover_loop(
+            state_manager,
+            service_state,
+            TendermintBackend::new(None),
+            ChaosProver::new(),
+            max_rounds,
+            Stop::never(),
+        )
+        .await
+        .unwrap();
+    }
+
+    fn chaos_child(db_path: &std::path::Path, rounds: Option<u64>) -> std::process::Command {
+        let mut command = std::process::Command::new(std::env::current_exe().unwrap());
+        command
+            .args([
+                "prover::tests::test_chaos_child",
+                "--exact",
+                "--ignored",
+                "--test-threads=1",
+            ])
+            .env("CHAOS_DB_PATH", db_path)
+            .env_remove("WEBHOOKS_CONFIG")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        if let Some(rounds) = rounds {
+            command.env("CHAOS_ROUNDS", rounds.to_string());
+        }
+        command
+    }
+
+    /// Checks the state row matches the tip of a gapless, hash-chained history with one row
+    /// per round, and returns the number of committed updates
+    fn assert_consistent(db_path: &std::path::Path) -> u64 {
+        let state_manager = StateManager::new(db_path).unwrap();
+        let Some(state) = state_manager.load_state().unwrap() else {
+            return 0;
+        };
+        let history = state_manager.load_history(0, 1_000_000).unwrap();
+        assert_eq!(history.len() as u64, state.update_counter);
+
+        let mut prev_hash = [0; 32];
+        for (i, record) in history.iter().enumerate() {
+            assert_eq!(record.update_counter, i as u64 + 1);
+            assert_eq!(record.height, i as u64 + 1, "a round was committed twice");
+            assert_eq!(record.root, chaos_root(record.height));
+            assert!(record.verify(&prev_hash));
+            prev_hash = record.hash;
+        }
+        if let Some(tip) = history.last() {
+            assert_eq!(tip.height, state.trusted_height);
+            assert_eq!(tip.root, state.trusted_root);
+            let proof = state.most_recent_wrapper_proof.as_ref().unwrap();
+            assert_eq!(tip.proof_hash, crate::state::proof_hash(proof));
+        }
+        state.update_counter
+    }
+
+    /// Kills the service at random points across several rounds and checks every restart
+    /// resumes a consistent proof chain
+    #[test]
+    #[cfg(unix)]
+    fn test_chaos_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("service_state.db");
+        let mut rng = chaos_seed();
+        println!("chaos seed: {}", rng);
+
+        let mut committed = 0;
+        for _ in 0..8 {
+            let mut child = chaos_child(&db_path, None).spawn().unwrap();
+            let millis = 200 + next_random(&mut rng) % 2000;
+            std::thread::sleep(Duration::from_millis(millis));
+            // SIGKILL, the service gets no chance to clean up
+            child.kill().unwrap();
+            child.wait().unwrap();
+
+            let counter = assert_consistent(&db_path);
+            assert!(counter >= committed, "committed updates were lost");
+            committed = counter;
+        }
+
+        // The chain continues where the last killed process left it
+        let status = chaos_child(&db_path, Some(3)).status().unwrap();
+        assert!(status.success());
+        assert_eq!(assert_consistent(&db_path), committed + 3);
+    }
 }
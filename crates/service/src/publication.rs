@@ -0,0 +1,151 @@
+// Delayed publication of proven updates.
+//
+// An update is committed, and with it served by the API and relayed, as soon as its wrapper
+// proof is proven. With `PUBLICATION_DELAY_SECS` every proven update is held back for that
+// long first, e.g. 768 seconds (2 epochs), for deployments that prioritize safety over
+// latency. The held update is listed by the admin API, which can abort its publication, and
+// the checks of the commit against the committee index and the committed heights run when
+// the window ends, against whatever was committed meanwhile. An aborted round is dropped and
+// proven again from the committed state.
+
+use anyhow::{Context, Result, anyhow};
+use std::{
+    ops::Deref,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::Instant;
+
+use crate::{
+    control::Stop,
+    encoding::to_hex,
+    state::{HeldUpdate, StateManager},
+};
+
+/// How often a held update checks whether it was aborted, in seconds
+const POLL_INTERVAL: u64 = 5;
+
+/// The publication of a held update was aborted by an operator
+#[derive(Debug)]
+pub struct Aborted(pub u64);
+
+impl std::fmt::Display for Aborted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The publication of update {} was aborted", self.0)
+    }
+}
+
+impl std::error::Error for Aborted {}
+
+/// The delay between proving an update and publishing it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicationDelay {
+    delay_secs: u64,
+}
+
+impl PublicationDelay {
+    /// The delay of `PUBLICATION_DELAY_SECS`, none if it is unset
+    pub fn from_env() -> Result<Self> {
+        let delay_secs = match std::env::var("PUBLICATION_DELAY_SECS") {
+            Ok(secs) => secs
+                .parse()
+                .with_context(|| format!("Invalid PUBLICATION_DELAY_SECS {}", secs))?,
+            Err(_) => 0,
+        };
+        Ok(Self { delay_secs })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.delay_secs > 0
+    }
+
+    /// Holds the update `update_counter` proven at `height` and `root` back for the delay.
+    /// Fails with [`Aborted`] if an operator aborts its publication, and if a stop is
+    /// requested, in which case its round is resumed and held again by the next start.
+    /// `state_manager` gives access to the state manager of the chain between the checks
+    pub async fn hold<S: Deref<Target = StateManager>>(
+        &self,
+        state_manager: impl Fn() -> S,
+        update_counter: u64,
+        height: u64,
+        root: [u8; 32],
+        stop: &mut Stop,
+    ) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let proven_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let held = HeldUpdate {
+            update_counter,
+            height,
+            root,
+            proven_at,
+            publish_at: proven_at + self.delay_secs,
+            aborted: false,
+        };
+        state_manager().hold_update(&held)?;
+        tracing::info!(
+            "⏳ Holding update {} at height {} (root {}) for {} seconds before publishing it",
+            update_counter,
+            height,
+            to_hex(root),
+            self.delay_secs
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(self.delay_secs);
+        loop {
+            let remaining = {
+                let state_manager = state_manager();
+                if state_manager.load_hold()?.is_some_and(|held| held.aborted) {
+                    state_manager.release_hold(true)?;
+                    tracing::warn!("🛑 Publication of update {} aborted", update_counter);
+                    return Err(Aborted(update_counter).into());
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    state_manager.release_hold(false)?;
+                    return Ok(());
+                }
+                if stop.requested() {
+                    state_manager.release_hold(false)?;
+                    return Err(anyhow!(
+                        "Stopped while holding update {} back from publication",
+                        update_counter
+                    ));
+                }
+                remaining
+            };
+            stop.sleep(POLL_INTERVAL.min(remaining.as_secs().max(1)))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_held_update_can_be_aborted() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_manager = StateManager::new(&dir.path().join("service_state.db")).unwrap();
+        let delay = PublicationDelay { delay_secs: 1 };
+
+        // Without an abort the update is published once the delay passed
+        delay
+            .hold(|| &state_manager, 1, 10, [1; 32], &mut Stop::never())
+            .await
+            .unwrap();
+        assert!(state_manager.load_hold().unwrap().is_none());
+
+        let abort = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(state_manager.abort_hold(2).unwrap());
+        };
+        let (held, ()) = tokio::join!(
+            delay.hold(|| &state_manager, 2, 11, [2; 32], &mut Stop::never()),
+            abort
+        );
+        assert!(held.unwrap_err().downcast_ref::<Aborted>().is_some());
+        assert!(state_manager.load_hold().unwrap().is_none());
+    }
+}
@@ -0,0 +1,768 @@
+// Guided disaster recovery, `service recover <scenario>`.
+//
+// Every supported failure is a scenario whose checks diagnose the database, the RPCs and the
+// generated circuits and print what is left to do. Nothing is changed unless `--apply` is
+// given and every check passed, and the service must be stopped while recovering:
+// - `restore-db`: the database is corrupted and `--backup` is a valid backup. The backup is
+//   checked on a copy, then the corrupted database is kept as `<db>.corrupt-<timestamp>` and
+//   the copy takes its place
+// - `lost-proofs`: the state and history are valid but the latest proofs are missing or
+//   unreadable. The proof chain restarts at its committed head with a recursive circuit
+//   regenerated from that head
+// - `tendermint-trust-period`: the trusted light block of a Tendermint chain is older than
+//   `TENDERMINT_TRUSTING_PERIOD` (seconds, two weeks by default), so nothing can be verified
+//   from it anymore. The proof chain is re-anchored at a recent checkpoint
+// - `helios-checkpoint`: the consensus RPC no longer serves the bootstrap of the Helios trusted
+//   slot. The proof chain is re-anchored at a recent finalized checkpoint
+//
+// Restarted and re-anchored proof chains keep their update counters and history, and the
+// restart is recorded in the lineage.
+
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use sp1_sdk::ProverClient;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tendermint_prover::util::TendermintRPCClient;
+
+use crate::{
+    api,
+    archive::ProofArchive,
+    artifacts,
+    chains::{TendermintChainConfig, tendermint_chain},
+    checkpoints::{BITCOIN_TRUSTED_HEIGHT, GRANDPA_TRUSTED_HEIGHT},
+    control::active_backend,
+    doctor::{Check, failed, print_report},
+    encoding::to_hex,
+    networks::EthereumNetwork,
+    preprocessor::{gest_latest_slot, sync_committee_hash},
+    prover::Aggregation,
+    state::{
+        DEFAULT_CHAIN_ID, LineageRecord, ServiceState, StateManager, UpdateRecord, integrity_check,
+    },
+};
+
+const REGENERATE: &str = "regenerate the circuits without deleting the state \
+    (`make regenerate-circuits`, or `make build-chain-circuits` for a Tendermint chain)";
+
+/// A failure `service recover` brings a proof chain back from
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Scenario {
+    /// The database is corrupted, restore it from `--backup`
+    RestoreDb,
+    /// The latest proofs are missing or unreadable, restart the proof chain at its committed
+    /// head
+    LostProofs,
+    /// The trusted light block of a Tendermint chain is past its trusting period, re-anchor
+    /// the proof chain at a recent checkpoint
+    TendermintTrustPeriod,
+    /// The Helios trusted slot can't be bootstrapped anymore, re-anchor the proof chain at a
+    /// recent finalized checkpoint
+    HeliosCheckpoint,
+}
+
+/// Runs the checks of a scenario and, with `apply`, recovers the chain `chain_id`
+pub async fn run(
+    scenario: Scenario,
+    db_path: &Path,
+    elfs_path: &str,
+    chain_id: &str,
+    backup: Option<&Path>,
+    apply: bool,
+) -> Result<()> {
+    match scenario {
+        Scenario::RestoreDb => {
+            let backup = backup.context("restore-db requires --backup <path>")?;
+            restore_db(db_path, backup, apply)
+        }
+        Scenario::LostProofs => lost_proofs(db_path, elfs_path, chain_id, apply).await,
+        Scenario::TendermintTrustPeriod => {
+            tendermint_trust_period(db_path, elfs_path, chain_id, apply).await
+        }
+        Scenario::HeliosCheckpoint => helios_checkpoint(db_path, elfs_path, apply).await,
+    }
+}
+
+/// Prints the report of a scenario, returns whether its recovery is applied
+fn ready(scenario: &str, checks: &[Check], apply: bool) -> Result<bool> {
+    print_report(&format!("Lightwave recovery: {}", scenario), checks);
+    let failed = failed(checks);
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} recovery checks failed, nothing was changed",
+            failed
+        ));
+    }
+    if !apply {
+        println!("Dry run, nothing was changed. Stop the service and rerun with --apply");
+    }
+    Ok(apply)
+}
+
+fn restore_db(db_path: &Path, backup: &Path, apply: bool) -> Result<()> {
+    let mut checks = vec![if !db_path.exists() {
+        Check::pass("Database", format!("{} is missing", db_path.display()))
+    } else {
+        match integrity_check(db_path) {
+            Ok(()) => Check::warn(
+                "Database",
+                format!("{} passes the integrity check", db_path.display()),
+                "It is replaced by the backup anyway, `make verify` checks its chains",
+            ),
+            Err(e) => Check::pass(
+                "Database",
+                format!("{} is corrupted: {}", db_path.display(), e),
+            ),
+        }
+    }];
+
+    // Opening the backup as a state database migrates it, it is checked on a copy
+    let staged = with_suffix(db_path, ".restore");
+    std::fs::copy(backup, &staged).context(format!("Failed to copy {}", backup.display()))?;
+    checks.extend(check_backup(&staged));
+    let applied = ready("restore the database", &checks, apply);
+    if !matches!(applied, Ok(true)) {
+        std::fs::remove_file(&staged).ok();
+        return applied.map(|_| ());
+    }
+
+    // A WAL left by the corrupted database would be replayed into the restored one
+    let suffix = format!(".corrupt-{}", now()?);
+    for file in ["", "-wal", "-shm"] {
+        let path = with_suffix(db_path, file);
+        if path.exists() {
+            std::fs::rename(&path, with_suffix(&path, &suffix))?;
+        }
+    }
+    std::fs::rename(&staged, db_path)?;
+    println!(
+        "Restored {} from {}, the corrupted database was kept as {}",
+        db_path.display(),
+        backup.display(),
+        with_suffix(db_path, &suffix).display()
+    );
+    Ok(())
+}
+
+/// Checks the integrity of a backup and the committed head of each of its chains
+fn check_backup(path: &Path) -> Vec<Check> {
+    if let Err(e) = integrity_check(path) {
+        return vec![Check::fail("Backup", e.to_string(), "Pick another backup")];
+    }
+    let chain_ids = match StateManager::new(path).and_then(|manager| manager.chain_ids()) {
+        Ok(chain_ids) if !chain_ids.is_empty() => chain_ids,
+        Ok(_) => {
+            return vec![Check::fail(
+                "Backup",
+                "holds no chain",
+                "Pick another backup",
+            )];
+        }
+        Err(e) => return vec![Check::fail("Backup", e.to_string(), "Pick another backup")],
+    };
+
+    let archive = ProofArchive::from_env();
+    let mut checks = Vec::new();
+    for chain_id in chain_ids {
+        let name = format!("Backup ({})", chain_id);
+        let head = StateManager::for_chain(path, &chain_id)
+            .and_then(|state_manager| verified_state(&state_manager));
+        let (state, _) = match head {
+            Ok(head) => head,
+            Err(e) => {
+                checks.push(Check::fail(name, e.to_string(), "Pick another backup"));
+                continue;
+            }
+        };
+        checks.push(Check::pass(
+            name,
+            format!(
+                "update {} at height {}, history verified",
+                state.update_counter, state.trusted_height
+            ),
+        ));
+
+        // Updates committed after the backup are lost with the database
+        let latest = archive
+            .as_ref()
+            .ok()
+            .and_then(|archive| archive.latest_entry(&chain_id).ok().flatten());
+        if let Some(latest) = latest.filter(|entry| entry.update_counter > state.update_counter) {
+            checks.push(Check::warn(
+                format!("Archive ({})", chain_id),
+                format!(
+                    "updates {} to {} were archived after the backup",
+                    state.update_counter + 1,
+                    latest.update_counter
+                ),
+                "They are proven again with other proofs, consumers that kept their history \
+                 hashes will see them rewritten",
+            ));
+        }
+    }
+    checks
+}
+
+async fn lost_proofs(db_path: &Path, elfs_path: &str, chain_id: &str, apply: bool) -> Result<()> {
+    const SCENARIO: &str = "lost proofs";
+    let state_manager = StateManager::for_chain(db_path, chain_id)?;
+    let mode = chain_mode(&state_manager)?;
+    let mut checks = Vec::new();
+
+    let tip = match state_manager.verify_history() {
+        Ok(Some(tip)) => tip,
+        Ok(None) => {
+            checks.push(Check::fail(
+                "History",
+                "nothing was committed",
+                "Nothing to recover, delete the state to start over",
+            ));
+            return ready(SCENARIO, &checks, apply).map(|_| ());
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "History",
+                e.to_string(),
+                "Restore the database with `recover restore-db`",
+            ));
+            return ready(SCENARIO, &checks, apply).map(|_| ());
+        }
+    };
+    checks.push(Check::pass(
+        "History",
+        format!("verified up to update {}", tip.update_counter),
+    ));
+
+    // The state row is valid if it only lost its proofs
+    let state = state_manager.load_state();
+    let keeps_wrapper = match &state {
+        Ok(Some(state))
+            if state.update_counter != tip.update_counter || state.trusted_root != tip.root =>
+        {
+            checks.push(Check::fail(
+                "State",
+                format!(
+                    "update {} does not match the history tip {}",
+                    state.update_counter, tip.update_counter
+                ),
+                "Restore the database with `recover restore-db`",
+            ));
+            false
+        }
+        Ok(Some(state)) => match (
+            &state.most_recent_recursive_proof,
+            &state.most_recent_wrapper_proof,
+        ) {
+            (Some(_), Some(_)) => {
+                checks.push(Check::fail(
+                    "Proofs",
+                    "the latest proofs are readable",
+                    "Nothing to recover, `make verify` checks them",
+                ));
+                false
+            }
+            (None, Some(_)) => {
+                checks.push(Check::pass(
+                    "Proofs",
+                    "the recursive proof is missing, the wrapper proof is served until the \
+                     next proof chain commits",
+                ));
+                true
+            }
+            (_, None) => {
+                checks.push(Check::pass(
+                    "Proofs",
+                    "the wrapper proof is missing, no proof is served until the next proof \
+                     chain commits",
+                ));
+                false
+            }
+        },
+        Ok(None) => {
+            checks.push(Check::fail(
+                "State",
+                "missing",
+                "Restore the database with `recover restore-db`",
+            ));
+            false
+        }
+        Err(e) => {
+            checks.push(Check::pass(
+                "Proofs",
+                format!(
+                    "unreadable ({}), both are dropped and no proof is served until the next \
+                     proof chain commits",
+                    e
+                ),
+            ));
+            false
+        }
+    };
+
+    // The regenerated circuit trusts the committed head
+    checks.push(check_committed_checkpoint(&mode, chain_id, &tip).await);
+    let previous_vk = previous_vk(&state_manager, &mode, state.ok().flatten().as_ref(), &tip)?;
+    let (check, vk) = check_circuit(&mode, elfs_path, chain_id, &previous_vk).await;
+    checks.push(check);
+
+    if !ready(SCENARIO, &checks, apply)? {
+        return Ok(());
+    }
+    let record = lineage_record(&tip, previous_vk, vk.context("No recursive vk")?)?;
+    if keeps_wrapper {
+        state_manager.restart_proof_chain(&record)?;
+    } else {
+        state_manager.reanchor_proof_chain(&record, tip.slot, tip.height)?;
+    }
+    println!(
+        "Restarted the {} proof chain of {} at update {}, recursive vk {} -> {}",
+        mode, chain_id, record.anchor_update, record.previous_vk, record.vk
+    );
+    Ok(())
+}
+
+/// Checks the configured checkpoint of a backend is the committed head `tip`
+async fn check_committed_checkpoint(mode: &str, chain_id: &str, tip: &UpdateRecord) -> Check {
+    const NAME: &str = "Checkpoint";
+    match mode {
+        "HELIOS" => match EthereumNetwork::from_env().and_then(|network| network.trusted_slot()) {
+            Ok(slot) if slot == tip.slot => Check::pass(
+                NAME,
+                format!("HELIOS_TRUSTED_SLOT is the committed slot {}", slot),
+            ),
+            Ok(slot) => Check::fail(
+                NAME,
+                format!("HELIOS_TRUSTED_SLOT {} is not the committed slot", slot),
+                format!("Set HELIOS_TRUSTED_SLOT={}, then {}", tip.slot, REGENERATE),
+            ),
+            Err(e) => Check::fail(NAME, e.to_string(), "Check the Helios configuration"),
+        },
+        "TENDERMINT" => {
+            let chain = match tendermint_chain(chain_id) {
+                Ok(chain) => chain,
+                Err(e) => {
+                    return Check::fail(NAME, e.to_string(), "Check TENDERMINT_CHAINS_CONFIG");
+                }
+            };
+            let hash = match light_block(&chain, tip.height).await {
+                Ok((hash, _)) => hash,
+                Err(e) => return Check::fail(NAME, e.to_string(), "Check the Tendermint RPC"),
+            };
+            if chain.trusted_height == tip.height && chain.trusted_root == hash {
+                Check::pass(
+                    NAME,
+                    format!(
+                        "the trusted checkpoint is the committed height {}",
+                        tip.height
+                    ),
+                )
+            } else {
+                Check::fail(
+                    NAME,
+                    format!(
+                        "the trusted checkpoint {} is not the committed height",
+                        chain.trusted_height
+                    ),
+                    format!(
+                        "Set the trusted height {} and root {} of {}, then {}",
+                        tip.height,
+                        to_hex(hash),
+                        chain.chain_id,
+                        REGENERATE
+                    ),
+                )
+            }
+        }
+        "GRANDPA" | "BITCOIN" => {
+            let trusted_height = match mode {
+                "GRANDPA" => GRANDPA_TRUSTED_HEIGHT,
+                _ => BITCOIN_TRUSTED_HEIGHT,
+            };
+            if trusted_height == tip.height {
+                Check::pass(
+                    NAME,
+                    format!("the trusted height is the committed height {}", tip.height),
+                )
+            } else {
+                Check::fail(
+                    NAME,
+                    format!(
+                        "the trusted height {} is not the committed height",
+                        trusted_height
+                    ),
+                    format!(
+                        "Set {}_TRUSTED_HEIGHT to {} in checkpoints.rs, rebuild, then {}",
+                        mode, tip.height, REGENERATE
+                    ),
+                )
+            }
+        }
+        _ => Check::fail(
+            NAME,
+            format!("invalid mode {}", mode),
+            "Check CLIENT_BACKEND",
+        ),
+    }
+}
+
+async fn tendermint_trust_period(
+    db_path: &Path,
+    elfs_path: &str,
+    chain_id: &str,
+    apply: bool,
+) -> Result<()> {
+    const SCENARIO: &str = "expired Tendermint trust period";
+    let state_manager = StateManager::for_chain(db_path, chain_id)?;
+    let mode = chain_mode(&state_manager)?;
+    if mode != "TENDERMINT" {
+        return Err(anyhow!(
+            "Chain {} is proven by the {} backend",
+            chain_id,
+            mode
+        ));
+    }
+    let chain = tendermint_chain(chain_id)?;
+    let trusting_period = chain.trusting_period_secs()?;
+    let now = now()?;
+    let mut checks = Vec::new();
+
+    let (state, tip) = match committed_head(&state_manager) {
+        Ok(head) => head,
+        Err(e) => {
+            checks.push(Check::fail(
+                "State",
+                e.to_string(),
+                "Recover the state first, or delete it to start from the new checkpoint",
+            ));
+            return ready(SCENARIO, &checks, apply).map(|_| ());
+        }
+    };
+
+    checks.push(match light_block(&chain, state.trusted_height).await {
+        Ok((_, time)) if time + trusting_period > now => Check::fail(
+            "Trust period",
+            format!(
+                "the trusted light block {} is trusted for another {}h",
+                state.trusted_height,
+                (time + trusting_period - now) / 3600
+            ),
+            "Nothing to recover, `make doctor` checks the RPC the proof chain advances with",
+        ),
+        Ok((_, time)) => Check::pass(
+            "Trust period",
+            format!(
+                "the trusted light block {} expired {}h ago",
+                state.trusted_height,
+                (now - time - trusting_period) / 3600
+            ),
+        ),
+        Err(e) => Check::warn(
+            "Trust period",
+            e.to_string(),
+            "Check the RPC, a pruned trusted light block also requires a new checkpoint",
+        ),
+    });
+
+    // The new checkpoint is configured like the first one, and must be trusted itself
+    let configured = light_block(&chain, chain.trusted_height).await.ok();
+    checks.push(match configured {
+        Some((hash, time))
+            if chain.trusted_height > state.trusted_height
+                && hash == chain.trusted_root
+                && time + trusting_period > now =>
+        {
+            Check::pass(
+                "New checkpoint",
+                format!("height {} root {}", chain.trusted_height, to_hex(hash)),
+            )
+        }
+        _ => {
+            let rpc = rpc_client(&chain);
+            let height = rpc.get_latest_block_height().await;
+            let hint = match light_block(&chain, height).await {
+                Ok((hash, _)) => format!(
+                    "Set the trusted height {} and root {} of {}, then {}",
+                    height,
+                    to_hex(hash),
+                    chain.chain_id,
+                    REGENERATE
+                ),
+                Err(e) => format!("Check the Tendermint RPC: {}", e),
+            };
+            Check::fail(
+                "New checkpoint",
+                format!(
+                    "the trusted checkpoint {} is not a recent checkpoint of the chain",
+                    chain.trusted_height
+                ),
+                hint,
+            )
+        }
+    });
+
+    let previous_vk = previous_vk(&state_manager, &mode, Some(&state), &tip)?;
+    let (check, vk) = check_circuit(&mode, elfs_path, chain_id, &previous_vk).await;
+    checks.push(check);
+
+    if !ready(SCENARIO, &checks, apply)? {
+        return Ok(());
+    }
+    let record = lineage_record(&tip, previous_vk, vk.context("No recursive vk")?)?;
+    state_manager.reanchor_proof_chain(&record, chain.trusted_height, chain.trusted_height)?;
+    println!(
+        "Re-anchored the Tendermint proof chain of {} at height {}, recursive vk {} -> {}",
+        chain_id, chain.trusted_height, record.previous_vk, record.vk
+    );
+    Ok(())
+}
+
+async fn helios_checkpoint(db_path: &Path, elfs_path: &str, apply: bool) -> Result<()> {
+    const SCENARIO: &str = "stale Helios checkpoint";
+    let state_manager = StateManager::new(db_path)?;
+    let mode = chain_mode(&state_manager)?;
+    if mode != "HELIOS" {
+        return Err(anyhow!(
+            "The default chain is proven by the {} backend",
+            mode
+        ));
+    }
+    let network = EthereumNetwork::from_env()?;
+    let mut checks = Vec::new();
+
+    let (state, tip) = match committed_head(&state_manager) {
+        Ok(head) => head,
+        Err(e) => {
+            checks.push(Check::fail(
+                "State",
+                e.to_string(),
+                "Recover the state first, or delete it to start from the new checkpoint",
+            ));
+            return ready(SCENARIO, &checks, apply).map(|_| ());
+        }
+    };
+
+    // A missing bootstrap only means a stale checkpoint if the RPC answers
+    let finalized_slot = match gest_latest_slot().await {
+        Ok(slot) => slot,
+        Err(e) => {
+            checks.push(Check::fail(
+                "Consensus RPC",
+                e.to_string(),
+                "Check SOURCE_CONSENSUS_RPC_URL",
+            ));
+            return ready(SCENARIO, &checks, apply).map(|_| ());
+        }
+    };
+    checks.push(Check::pass(
+        "Consensus RPC",
+        format!("finalized slot {}", finalized_slot),
+    ));
+    checks.push(
+        match sync_committee_hash(state.trusted_slot, network).await {
+            Ok(_) => Check::fail(
+                "Trusted checkpoint",
+                format!("the bootstrap of slot {} is served", state.trusted_slot),
+                "Nothing to recover, `make doctor` checks the RPC the proof chain advances with",
+            ),
+            Err(e) => Check::pass(
+                "Trusted checkpoint",
+                format!("no bootstrap for slot {}: {}", state.trusted_slot, e),
+            ),
+        },
+    );
+
+    let trusted_slot = network.trusted_slot()?;
+    checks.push(if trusted_slot > state.trusted_slot {
+        match sync_committee_hash(trusted_slot, network).await {
+            Ok(hash) => Check::pass(
+                "New checkpoint",
+                format!("slot {} with sync committee {}", trusted_slot, to_hex(hash)),
+            ),
+            Err(e) => Check::fail(
+                "New checkpoint",
+                format!(
+                    "no bootstrap for HELIOS_TRUSTED_SLOT {}: {}",
+                    trusted_slot, e
+                ),
+                "Pick a finalized epoch boundary slot",
+            ),
+        }
+    } else {
+        Check::fail(
+            "New checkpoint",
+            format!(
+                "HELIOS_TRUSTED_SLOT {} is not past the trusted slot {}",
+                trusted_slot, state.trusted_slot
+            ),
+            format!(
+                "Set HELIOS_TRUSTED_SLOT={} (the latest finalized epoch), then {}",
+                finalized_slot - finalized_slot % network.slots_per_epoch(),
+                REGENERATE
+            ),
+        )
+    });
+
+    let previous_vk = previous_vk(&state_manager, &mode, Some(&state), &tip)?;
+    let (check, vk) = check_circuit(&mode, elfs_path, DEFAULT_CHAIN_ID, &previous_vk).await;
+    checks.push(check);
+
+    if !ready(SCENARIO, &checks, apply)? {
+        return Ok(());
+    }
+    let record = lineage_record(&tip, previous_vk, vk.context("No recursive vk")?)?;
+    state_manager.reanchor_proof_chain(&record, trusted_slot, state.trusted_height)?;
+    println!(
+        "Re-anchored the Helios proof chain at slot {}, recursive vk {} -> {}",
+        trusted_slot, record.previous_vk, record.vk
+    );
+    Ok(())
+}
+
+/// The backend proving a chain, recovery is supported for the light client proof chains
+fn chain_mode(state_manager: &StateManager) -> Result<String> {
+    if state_manager.chain_id() == DEFAULT_CHAIN_ID {
+        return active_backend(state_manager);
+    }
+    tendermint_chain(state_manager.chain_id())?;
+    Ok("TENDERMINT".to_string())
+}
+
+/// The state of a chain and the tip of its verified history, which must match
+fn verified_state(state_manager: &StateManager) -> Result<(ServiceState, Option<UpdateRecord>)> {
+    let state = state_manager.load_state()?.context("No state")?;
+    let tip = state_manager.verify_history()?;
+    if let Some(tip) = &tip {
+        if tip.update_counter != state.update_counter || tip.root != state.trusted_root {
+            return Err(anyhow!(
+                "The state of update {} does not match the history tip {}",
+                state.update_counter,
+                tip.update_counter
+            ));
+        }
+    }
+    Ok((state, tip))
+}
+
+/// The verified state of a chain that committed at least one update, and its history tip
+fn committed_head(state_manager: &StateManager) -> Result<(ServiceState, UpdateRecord)> {
+    let (state, tip) = verified_state(state_manager)?;
+    Ok((state, tip.context("Nothing was committed")?))
+}
+
+/// The recursive vk of the proof chain, committed by its recursive proof or recorded with its
+/// latest round
+fn previous_vk(
+    state_manager: &StateManager,
+    mode: &str,
+    state: Option<&ServiceState>,
+    tip: &UpdateRecord,
+) -> Result<String> {
+    if let Some(vk) = state.and_then(|state| api::recursive_vk(mode, state)) {
+        return Ok(vk);
+    }
+    let recorded = state_manager
+        .load_round_metadata(tip.update_counter)?
+        .and_then(|metadata| {
+            metadata
+                .circuits
+                .into_iter()
+                .find(|circuit| circuit.name == "recursive")
+        });
+    Ok(recorded.map_or_else(|| "unknown".to_string(), |circuit| circuit.vk))
+}
+
+/// Checks the recursive circuit in `ELFS_OUT` was regenerated since the proof chain committed,
+/// returns its vk
+async fn check_circuit(
+    mode: &str,
+    elfs_path: &str,
+    chain_id: &str,
+    previous_vk: &str,
+) -> (Check, Option<String>) {
+    const NAME: &str = "Recursive circuit";
+    match recursive_vk(mode, elfs_path, chain_id).await {
+        Ok(vk) if vk == previous_vk => (
+            Check::fail(
+                NAME,
+                format!("vk {} is the vk of the proof chain", vk),
+                format!("The circuit must trust the new checkpoint, {}", REGENERATE),
+            ),
+            None,
+        ),
+        Ok(vk) => (
+            Check::pass(NAME, format!("regenerated, vk {} -> {}", previous_vk, vk)),
+            Some(vk),
+        ),
+        Err(e) => (Check::fail(NAME, e.to_string(), "Check ELFS_OUT"), None),
+    }
+}
+
+/// The recursive vk of the circuit in `ELFS_OUT`, as committed by its proofs
+async fn recursive_vk(mode: &str, elfs_path: &str, chain_id: &str) -> Result<String> {
+    let elf_name = match mode {
+        "TENDERMINT" => tendermint_chain(chain_id)?.elf_name("tendermint-recursive-elf.bin"),
+        _ => format!("{}-recursive-elf.bin", mode.to_lowercase()),
+    };
+    let elf = artifacts::read_artifact(elfs_path, &elf_name).await?;
+    let aggregation = match mode {
+        "HELIOS" => Aggregation::from_env()?,
+        _ => Aggregation::Groth16,
+    };
+    let (_, vk) =
+        tokio::task::spawn_blocking(move || ProverClient::builder().cpu().build().setup(&elf))
+            .await?;
+    Ok(aggregation.recursive_vk(&vk))
+}
+
+/// The restart of a proof chain after its committed head `tip`
+fn lineage_record(tip: &UpdateRecord, previous_vk: String, vk: String) -> Result<LineageRecord> {
+    Ok(LineageRecord {
+        anchor_update: tip.update_counter,
+        anchor_height: tip.height,
+        anchor_root: tip.root,
+        anchor_proof_hash: tip.proof_hash,
+        previous_vk,
+        vk,
+        restarted_at: now()?,
+    })
+}
+
+fn rpc_client(chain: &TendermintChainConfig) -> TendermintRPCClient {
+    TendermintRPCClient::new(chain.rpc_url.clone())
+}
+
+/// The header hash and time (unix seconds) of a light block of a Tendermint chain
+pub(crate) async fn light_block(
+    chain: &TendermintChainConfig,
+    height: u64,
+) -> Result<([u8; 32], u64)> {
+    let client = rpc_client(chain);
+    let peer_id = client
+        .fetch_peer_id()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch the Tendermint peer id: {}", e))?;
+    let light_block = client
+        .fetch_light_block(height, peer_id)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch light block {}: {}", height, e))?;
+    let header = &light_block.signed_header.header;
+    let hash = header
+        .hash()
+        .as_bytes()
+        .try_into()
+        .context("Invalid header hash")?;
+    Ok((hash, header.time.unix_timestamp().max(0) as u64))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
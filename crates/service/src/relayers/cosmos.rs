@@ -0,0 +1,424 @@
+use anyhow::{Context, Result, anyhow};
+use cosmrs::{
+    AccountId, Coin,
+    cosmwasm::MsgExecuteContract,
+    crypto::secp256k1::SigningKey,
+    tendermint::{block::Height, chain},
+    tx::{Body, Fee, Msg, SignDoc, SignerInfo},
+};
+use serde_json::{Value, json};
+use std::{collections::HashMap, time::Duration};
+use tokio::sync::Mutex;
+
+use super::{Cost, RelayUpdate, Relayer};
+use crate::{
+    encoding::{parse_hex_array, to_base64, to_hex},
+    export::cosmwasm_msg,
+    state::DEFAULT_CHAIN_ID,
+};
+
+/// Gas limit over the simulated gas, in percent
+const GAS_ADJUSTMENT_PERCENT: u64 = 130;
+
+/// Interval between inclusion polls
+const TX_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The code of a transaction signed with another sequence than the account's
+const SEQUENCE_MISMATCH_CODE: u64 = 32;
+
+/// Configuration of the Cosmos relayer
+#[derive(Clone)]
+pub struct CosmosRelayerConfig {
+    /// `COSMOS_RELAYER_REST_URL`, the REST (gRPC gateway) endpoint of a node
+    pub rest_url: String,
+    /// `COSMOS_RELAYER_CONTRACT`, a `lightwave-cosmwasm` contract. Its address prefix is the
+    /// prefix of the sender
+    pub contract: AccountId,
+    /// `COSMOS_RELAYER_PRIVATE_KEY`, the secp256k1 key of the account paying for the
+    /// transactions
+    pub signing_key: [u8; 32],
+    /// `COSMOS_RELAYER_GAS_PRICE`, e.g. `0.0053untrn`
+    pub gas_price: (f64, String),
+    /// `COSMOS_RELAYER_CHAIN_ID`, the proof chain the contract verifies
+    pub chain_id: String,
+    /// `COSMOS_RELAYER_TIMEOUT_BLOCKS`, blocks after which an unincluded transaction expires
+    /// and is broadcast again
+    pub timeout_blocks: u64,
+}
+
+impl std::fmt::Debug for CosmosRelayerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The REST URL may carry an API key, the private key is never logged
+        f.debug_struct("CosmosRelayerConfig")
+            .field("contract", &self.contract)
+            .field("gas_price", &self.gas_price)
+            .field("chain_id", &self.chain_id)
+            .field("timeout_blocks", &self.timeout_blocks)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parses a gas price of the form `<amount><denom>`
+fn parse_gas_price(gas_price: &str) -> Result<(f64, String)> {
+    let split = gas_price
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .filter(|split| *split > 0)
+        .context(format!("Invalid gas price {}", gas_price))?;
+    let (amount, denom) = gas_price.split_at(split);
+    Ok((
+        amount
+            .parse()
+            .context(format!("Invalid gas price {}", gas_price))?,
+        denom.to_string(),
+    ))
+}
+
+impl CosmosRelayerConfig {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).context(format!("{} is not set", name));
+        Ok(Self {
+            rest_url: var("COSMOS_RELAYER_REST_URL")?
+                .trim_end_matches('/')
+                .to_string(),
+            contract: var("COSMOS_RELAYER_CONTRACT")?
+                .parse()
+                .map_err(|e| anyhow!("Invalid COSMOS_RELAYER_CONTRACT: {}", e))?,
+            signing_key: parse_hex_array(&var("COSMOS_RELAYER_PRIVATE_KEY")?)
+                .context("Invalid COSMOS_RELAYER_PRIVATE_KEY")?,
+            gas_price: parse_gas_price(&var("COSMOS_RELAYER_GAS_PRICE")?)?,
+            chain_id: std::env::var("COSMOS_RELAYER_CHAIN_ID")
+                .unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string()),
+            timeout_blocks: match std::env::var("COSMOS_RELAYER_TIMEOUT_BLOCKS") {
+                Ok(blocks) => blocks
+                    .parse()
+                    .context("Invalid COSMOS_RELAYER_TIMEOUT_BLOCKS")?,
+                Err(_) => 20,
+            },
+        })
+    }
+}
+
+/// Broadcasts wrapper proofs as the `verify_proof` execute message of a CosmWasm contract.
+/// Transactions are signed locally in direct mode, their sequences are assigned in order of
+/// submission, and they expire at a timeout height so an unincluded transaction can be
+/// broadcast again without being included twice
+#[derive(Debug)]
+pub struct CosmosRelayer {
+    config: CosmosRelayerConfig,
+    client: reqwest::Client,
+    /// The sequence of the next transaction, `None` to read it from the account
+    next_sequence: Mutex<Option<u64>>,
+    /// The timeout heights of the broadcast transactions, by hash
+    timeouts: Mutex<HashMap<String, u64>>,
+}
+
+/// An unsigned `verify_proof` execution, its sequence and timeout height are assigned when
+/// it is submitted
+#[derive(Debug, Clone)]
+pub struct CosmosTx {
+    /// The execute message, JSON encoded
+    pub msg: Vec<u8>,
+    pub gas_limit: u64,
+    pub fee: u128,
+}
+
+impl CosmosRelayer {
+    pub fn new(config: CosmosRelayerConfig) -> Self {
+        Self {
+            config,
+            client: crate::egress::client(),
+            next_sequence: Mutex::new(None),
+            timeouts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(CosmosRelayerConfig::from_env()?))
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.config.rest_url, path))
+            .send()
+            .await
+            .context(format!("Failed to query {}", path))?;
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<Value> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.config.rest_url, path))
+            .json(&body)
+            .send()
+            .await
+            .context(format!("Failed to call {}", path))?;
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    fn signing_key(&self) -> Result<SigningKey> {
+        SigningKey::from_slice(&self.config.signing_key)
+            .map_err(|e| anyhow!("Invalid COSMOS_RELAYER_PRIVATE_KEY: {}", e))
+    }
+
+    fn sender(&self) -> Result<AccountId> {
+        self.signing_key()?
+            .public_key()
+            .account_id(self.config.contract.prefix())
+            .map_err(|e| anyhow!("Invalid sender address: {}", e))
+    }
+
+    /// The account number and sequence of the sender
+    async fn account(&self) -> Result<(u64, u64)> {
+        let response = self
+            .get(&format!("/cosmos/auth/v1beta1/accounts/{}", self.sender()?))
+            .await?;
+        // Module and vesting accounts wrap their base account
+        let account = &response["account"];
+        let account = account
+            .pointer("/base_vesting_account/base_account")
+            .or_else(|| account.get("base_account"))
+            .unwrap_or(account);
+        Ok((
+            parse_number(&account["account_number"])?,
+            parse_number(&account["sequence"])?,
+        ))
+    }
+
+    /// The network id and the height of the latest block
+    async fn latest_block(&self) -> Result<(String, u64)> {
+        let block = self
+            .get("/cosmos/base/tendermint/v1beta1/blocks/latest")
+            .await?;
+        let header = &block["block"]["header"];
+        Ok((
+            header["chain_id"]
+                .as_str()
+                .context("Block without chain id")?
+                .to_string(),
+            parse_number(&header["height"])?,
+        ))
+    }
+
+    /// Signs the execution of `msg` with `sequence`, expiring after `timeout_height`
+    #[allow(clippy::too_many_arguments)]
+    fn sign(
+        &self,
+        msg: &[u8],
+        network: &str,
+        account_number: u64,
+        sequence: u64,
+        gas_limit: u64,
+        fee: u128,
+        timeout_height: u64,
+    ) -> Result<Vec<u8>> {
+        let signing_key = self.signing_key()?;
+        let execute = MsgExecuteContract {
+            sender: self.sender()?,
+            contract: self.config.contract.clone(),
+            msg: msg.to_vec(),
+            funds: Vec::new(),
+        }
+        .to_any()
+        .map_err(|e| anyhow!("Failed to encode the execute message: {}", e))?;
+        let timeout_height = Height::try_from(timeout_height)
+            .map_err(|e| anyhow!("Invalid timeout height {}: {}", timeout_height, e))?;
+        let body = Body::new(vec![execute], "", timeout_height);
+        let fee = Fee::from_amount_and_gas(
+            Coin {
+                denom: self
+                    .config
+                    .gas_price
+                    .1
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid gas price denom: {}", e))?,
+                amount: fee,
+            },
+            gas_limit,
+        );
+        let auth_info =
+            SignerInfo::single_direct(Some(signing_key.public_key()), sequence).auth_info(fee);
+        let network: chain::Id = network
+            .parse()
+            .map_err(|e| anyhow!("Invalid network id {}: {}", network, e))?;
+        let sign_doc = SignDoc::new(&body, &auth_info, &network, account_number)
+            .map_err(|e| anyhow!("Failed to build the sign doc: {}", e))?;
+        sign_doc
+            .sign(&signing_key)
+            .and_then(|tx| tx.to_bytes())
+            .map_err(|e| anyhow!("Failed to sign the transaction: {}", e))
+    }
+}
+
+impl Relayer for CosmosRelayer {
+    type Tx = CosmosTx;
+
+    fn name(&self) -> String {
+        format!("cosmos:{}", self.config.contract)
+    }
+
+    fn relays(&self, update: &RelayUpdate) -> bool {
+        update.chain_id == self.config.chain_id
+    }
+
+    async fn prepare(&self, update: &RelayUpdate) -> Result<CosmosTx> {
+        let msg = serde_json::to_vec(&cosmwasm_msg(
+            update.record.height,
+            &to_hex(update.record.root),
+            &to_base64(&update.proof),
+            &to_base64(&update.public_values),
+        ))?;
+        // Simulations don't check signatures or fees, the sequence must be the account's
+        let (network, height) = self.latest_block().await?;
+        let (account_number, sequence) = self.account().await?;
+        let tx = self.sign(&msg, &network, account_number, sequence, 0, 0, height + 1)?;
+        let simulation = self
+            .post(
+                "/cosmos/tx/v1beta1/simulate",
+                json!({ "tx_bytes": to_base64(tx) }),
+            )
+            .await
+            .context("The execution fails in simulation")?;
+        let gas_used = parse_number(&simulation["gas_info"]["gas_used"])?;
+        let gas_limit = gas_used * GAS_ADJUSTMENT_PERCENT / 100;
+        Ok(CosmosTx {
+            msg,
+            gas_limit,
+            fee: (gas_limit as f64 * self.config.gas_price.0).ceil() as u128,
+        })
+    }
+
+    async fn estimate(&self, tx: &CosmosTx) -> Result<Cost> {
+        Ok(Cost {
+            amount: tx.fee,
+            denom: self.config.gas_price.1.clone(),
+        })
+    }
+
+    async fn submit(&self, tx: &CosmosTx) -> Result<String> {
+        let mut next_sequence = self.next_sequence.lock().await;
+        let (network, height) = self.latest_block().await?;
+        let (account_number, account_sequence) = self.account().await?;
+        let sequence = next_sequence.map_or(account_sequence, |next| next.max(account_sequence));
+        let timeout_height = height + self.config.timeout_blocks;
+        let raw = self.sign(
+            &tx.msg,
+            &network,
+            account_number,
+            sequence,
+            tx.gas_limit,
+            tx.fee,
+            timeout_height,
+        )?;
+        let response = self
+            .post(
+                "/cosmos/tx/v1beta1/txs",
+                json!({ "tx_bytes": to_base64(raw), "mode": "BROADCAST_MODE_SYNC" }),
+            )
+            .await;
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                *next_sequence = None;
+                return Err(e);
+            }
+        };
+        let tx_response = &response["tx_response"];
+        let code = tx_response["code"].as_u64().unwrap_or_default();
+        if code != 0 {
+            // Read the sequence again, another transaction of the sender may have been
+            // included meanwhile
+            if code == SEQUENCE_MISMATCH_CODE {
+                *next_sequence = None;
+            }
+            return Err(anyhow!(
+                "Broadcast rejected with code {}: {}",
+                code,
+                tx_response["raw_log"]
+            ));
+        }
+        *next_sequence = Some(sequence + 1);
+        let hash = tx_response["txhash"]
+            .as_str()
+            .context("Invalid transaction hash")?
+            .to_string();
+        self.timeouts
+            .lock()
+            .await
+            .insert(hash.clone(), timeout_height);
+        Ok(hash)
+    }
+
+    async fn confirm(&self, hash: &str) -> Result<()> {
+        let timeout_height = self.timeouts.lock().await.get(hash).copied();
+        let result = loop {
+            let response = self
+                .client
+                .get(format!(
+                    "{}/cosmos/tx/v1beta1/txs/{}",
+                    self.config.rest_url, hash
+                ))
+                .send()
+                .await?;
+            // Transactions not included yet are not found
+            if response.status().is_success() {
+                let response: Value = response.json().await?;
+                let tx_response = &response["tx_response"];
+                let code = tx_response["code"].as_u64().unwrap_or_default();
+                break match code {
+                    0 => Ok(()),
+                    code => Err(anyhow!(
+                        "Transaction {} failed with code {}: {}",
+                        hash,
+                        code,
+                        tx_response["raw_log"]
+                    )),
+                };
+            }
+            let (_, height) = self.latest_block().await?;
+            if timeout_height.is_some_and(|timeout_height| height > timeout_height) {
+                // The expired transaction can't be included anymore, its sequence is free
+                *self.next_sequence.lock().await = None;
+                break Err(anyhow!(
+                    "Transaction {} expired at height {} without being included",
+                    hash,
+                    timeout_height.unwrap_or_default()
+                ));
+            }
+            tokio::time::sleep(TX_POLL_INTERVAL).await;
+        };
+        self.timeouts.lock().await.remove(hash);
+        result
+    }
+}
+
+/// Parses a number of a REST response, which encodes 64-bit integers as strings
+fn parse_number(value: &Value) -> Result<u64> {
+    match value {
+        Value::String(number) => number.parse().context(format!("Invalid number {}", number)),
+        value => value
+            .as_u64()
+            .context(format!("Expected a number, got {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gas_price() {
+        assert_eq!(
+            parse_gas_price("0.0053untrn").unwrap(),
+            (0.0053, "untrn".to_string())
+        );
+        assert_eq!(
+            parse_gas_price("25ibc/ABCD").unwrap(),
+            (25.0, "ibc/ABCD".to_string())
+        );
+        assert!(parse_gas_price("untrn").is_err());
+        assert!(parse_gas_price("0.1").is_err());
+        assert_eq!(parse_number(&json!("42")).unwrap(), 42);
+    }
+}
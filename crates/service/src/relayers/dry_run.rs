@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use super::{Cost, RelayUpdate, Relayer};
+use crate::encoding::to_hex;
+
+/// Relays to no destination: every step succeeds and the transaction that would be
+/// submitted is logged
+pub struct DryRunRelayer;
+
+/// What a relayer would submit for an update
+#[derive(Debug, Clone)]
+pub struct DryRunTx {
+    pub update_id: String,
+    pub height: u64,
+    pub root: [u8; 32],
+    pub proof: Vec<u8>,
+    pub public_values: Vec<u8>,
+}
+
+impl Relayer for DryRunRelayer {
+    type Tx = DryRunTx;
+
+    fn name(&self) -> String {
+        "dry-run".to_string()
+    }
+
+    async fn prepare(&self, update: &RelayUpdate) -> Result<DryRunTx> {
+        Ok(DryRunTx {
+            update_id: update.record.update_id.clone(),
+            height: update.record.height,
+            root: update.record.root,
+            proof: update.proof.clone(),
+            public_values: update.public_values.clone(),
+        })
+    }
+
+    async fn estimate(&self, _tx: &DryRunTx) -> Result<Cost> {
+        Ok(Cost {
+            amount: 0,
+            denom: "none".to_string(),
+        })
+    }
+
+    async fn submit(&self, tx: &DryRunTx) -> Result<String> {
+        tracing::info!(
+            "🧪 Would submit update {}: height {}, root {}, proof {}, public values {}",
+            tx.update_id,
+            tx.height,
+            to_hex(tx.root),
+            to_hex(&tx.proof),
+            to_hex(&tx.public_values)
+        );
+        Ok(format!("dry-run:{}", tx.update_id))
+    }
+
+    async fn confirm(&self, _hash: &str) -> Result<()> {
+        Ok(())
+    }
+}
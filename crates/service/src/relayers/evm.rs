@@ -0,0 +1,309 @@
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope},
+    eips::eip2718::Encodable2718,
+    network::TxSignerSync,
+    primitives::{Address, Bytes, TxKind, U256},
+    signers::local::PrivateKeySigner,
+};
+use alloy_sol_types::{SolCall, sol};
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::{Cost, RelayUpdate, Relayer};
+use crate::{
+    encoding::{parse_hex_array, to_hex},
+    state::DEFAULT_CHAIN_ID,
+};
+
+sol! {
+    function updateState(bytes proof, bytes publicValues) external;
+}
+
+/// Gas limit margin over the estimate, in percent
+const GAS_MARGIN_PERCENT: u64 = 20;
+
+/// Interval between receipt polls
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Configuration of the EVM relayer
+#[derive(Clone)]
+pub struct EvmRelayerConfig {
+    /// `EVM_RELAYER_RPC_URL`
+    pub rpc_url: String,
+    /// `EVM_RELAYER_CONTRACT`, a verifier of `generate-verifier`
+    pub contract: Address,
+    /// `EVM_RELAYER_PRIVATE_KEY`, the key of the account paying for the transactions
+    pub signer: PrivateKeySigner,
+    /// `EVM_RELAYER_CHAIN_ID`, the proof chain the contract verifies
+    pub chain_id: String,
+    /// `EVM_RELAYER_CONFIRMATIONS`, blocks on top of the inclusion block
+    pub confirmations: u64,
+    /// `EVM_RELAYER_CONFIRM_TIMEOUT_SECS`, how long a transaction may stay unconfirmed
+    pub confirm_timeout: Duration,
+}
+
+impl std::fmt::Debug for EvmRelayerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The RPC URL may carry an API key, the private key is never logged
+        f.debug_struct("EvmRelayerConfig")
+            .field("contract", &self.contract)
+            .field("sender", &self.signer.address())
+            .field("chain_id", &self.chain_id)
+            .field("confirmations", &self.confirmations)
+            .field("confirm_timeout", &self.confirm_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EvmRelayerConfig {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).context(format!("{} is not set", name));
+        let number = |name: &str, default: u64| -> Result<u64> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().context(format!("Invalid {}", name)),
+                Err(_) => Ok(default),
+            }
+        };
+        let private_key: [u8; 32] = parse_hex_array(&var("EVM_RELAYER_PRIVATE_KEY")?)
+            .context("Invalid EVM_RELAYER_PRIVATE_KEY")?;
+        Ok(Self {
+            rpc_url: var("EVM_RELAYER_RPC_URL")?,
+            contract: Address::from(
+                parse_hex_array::<20>(&var("EVM_RELAYER_CONTRACT")?)
+                    .context("Invalid EVM_RELAYER_CONTRACT")?,
+            ),
+            signer: PrivateKeySigner::from_bytes(&private_key.into())
+                .context("Invalid EVM_RELAYER_PRIVATE_KEY")?,
+            chain_id: std::env::var("EVM_RELAYER_CHAIN_ID")
+                .unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string()),
+            confirmations: number("EVM_RELAYER_CONFIRMATIONS", 1)?.max(1),
+            confirm_timeout: Duration::from_secs(number("EVM_RELAYER_CONFIRM_TIMEOUT_SECS", 300)?),
+        })
+    }
+}
+
+/// Submits wrapper proofs to `updateState(bytes proof, bytes publicValues)` of an EVM contract.
+/// Transactions are EIP-1559 transactions signed locally, their nonces are assigned in order
+/// of submission so concurrent relays don't race for the same nonce
+#[derive(Debug)]
+pub struct EvmRelayer {
+    config: EvmRelayerConfig,
+    client: reqwest::Client,
+    /// The nonce of the next transaction, `None` to read it from the RPC
+    next_nonce: Mutex<Option<u64>>,
+}
+
+/// An unsigned `updateState` transaction, its nonce is assigned when it is submitted
+#[derive(Debug, Clone)]
+pub struct EvmTx {
+    pub chain_id: u64,
+    pub input: Vec<u8>,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl EvmRelayer {
+    pub fn new(config: EvmRelayerConfig) -> Self {
+        Self {
+            config,
+            client: crate::egress::client(),
+            next_nonce: Mutex::new(None),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(EvmRelayerConfig::from_env()?))
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .client
+            .post(&self.config.rpc_url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .context(format!("Failed to call {}", method))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    async fn quantity(&self, method: &str, params: Value) -> Result<u128> {
+        parse_quantity(&self.request(method, params).await?)
+    }
+
+    /// The nonce of the next transaction of the sender, at least the count of its pending
+    /// transactions
+    async fn pending_nonce(&self) -> Result<u64> {
+        let count = self
+            .quantity(
+                "eth_getTransactionCount",
+                json!([self.config.signer.address(), "pending"]),
+            )
+            .await?;
+        Ok(count.try_into()?)
+    }
+}
+
+impl Relayer for EvmRelayer {
+    type Tx = EvmTx;
+
+    fn name(&self) -> String {
+        format!("evm:{}", self.config.contract)
+    }
+
+    fn relays(&self, update: &RelayUpdate) -> bool {
+        update.chain_id == self.config.chain_id
+    }
+
+    async fn prepare(&self, update: &RelayUpdate) -> Result<EvmTx> {
+        let input = updateStateCall {
+            proof: update.proof.clone().into(),
+            publicValues: update.public_values.clone().into(),
+        }
+        .abi_encode();
+        let chain_id = self.quantity("eth_chainId", json!([])).await?.try_into()?;
+        // Reverts, e.g. of a proof the contract already has a newer height than, fail the
+        // estimate before anything is paid
+        let gas = self
+            .quantity(
+                "eth_estimateGas",
+                json!([{
+                    "from": self.config.signer.address(),
+                    "to": self.config.contract,
+                    "data": to_hex(&input),
+                }]),
+            )
+            .await?;
+        let gas_limit = u64::try_from(gas)? * (100 + GAS_MARGIN_PERCENT) / 100;
+        let block = self
+            .request("eth_getBlockByNumber", json!(["latest", false]))
+            .await?;
+        let base_fee = parse_quantity(&block["baseFeePerGas"])
+            .context("The destination chain has no EIP-1559 base fee")?;
+        let max_priority_fee_per_gas = self.quantity("eth_maxPriorityFeePerGas", json!([])).await?;
+        Ok(EvmTx {
+            chain_id,
+            input,
+            gas_limit,
+            // Stays includable while the base fee doubles
+            max_fee_per_gas: 2 * base_fee + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    async fn estimate(&self, tx: &EvmTx) -> Result<Cost> {
+        Ok(Cost {
+            amount: tx.gas_limit as u128 * tx.max_fee_per_gas,
+            denom: "wei".to_string(),
+        })
+    }
+
+    async fn submit(&self, tx: &EvmTx) -> Result<String> {
+        let mut next_nonce = self.next_nonce.lock().await;
+        let pending = self.pending_nonce().await?;
+        let nonce = next_nonce.map_or(pending, |next| next.max(pending));
+        let mut unsigned = TxEip1559 {
+            chain_id: tx.chain_id,
+            nonce,
+            gas_limit: tx.gas_limit,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            to: TxKind::Call(self.config.contract),
+            value: U256::ZERO,
+            input: Bytes::from(tx.input.clone()),
+            ..Default::default()
+        };
+        let signature = self
+            .config
+            .signer
+            .sign_transaction_sync(&mut unsigned)
+            .context("Failed to sign the transaction")?;
+        let raw = TxEnvelope::from(unsigned.into_signed(signature)).encoded_2718();
+        match self
+            .request("eth_sendRawTransaction", json!([to_hex(raw)]))
+            .await
+        {
+            Ok(hash) => {
+                *next_nonce = Some(nonce + 1);
+                hash.as_str()
+                    .map(str::to_string)
+                    .context("Invalid transaction hash")
+            }
+            Err(e) => {
+                // Read the nonce again, a transaction of the sender may have been included
+                // or dropped meanwhile
+                *next_nonce = None;
+                Err(e)
+            }
+        }
+    }
+
+    async fn confirm(&self, hash: &str) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let receipt = self
+                .request("eth_getTransactionReceipt", json!([hash]))
+                .await?;
+            if !receipt.is_null() {
+                if parse_quantity(&receipt["status"])? != 1 {
+                    return Err(anyhow!("Transaction {} reverted", hash));
+                }
+                let included = parse_quantity(&receipt["blockNumber"])?;
+                let latest = self.quantity("eth_blockNumber", json!([])).await?;
+                if latest + 1 >= included + self.config.confirmations as u128 {
+                    return Ok(());
+                }
+            }
+            if started.elapsed() > self.config.confirm_timeout {
+                // A transaction stuck in the mempool keeps its nonce, the retry is queued
+                // behind it
+                *self.next_nonce.lock().await = None;
+                return Err(anyhow!(
+                    "Transaction {} not confirmed after {}s",
+                    hash,
+                    self.config.confirm_timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Parses a hex quantity of a JSON-RPC response
+fn parse_quantity(value: &Value) -> Result<u128> {
+    let value = value.as_str().context("Expected a hex quantity")?;
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.is_empty() {
+        return Err(anyhow!("Empty hex quantity"));
+    }
+    // Quantities may omit leading zeros, which `parse_hex` rejects as odd lengths
+    u128::from_str_radix(digits, 16).context(format!("Invalid hex quantity {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::parse_hex;
+
+    #[test]
+    fn test_update_state_calldata() {
+        let input = updateStateCall {
+            proof: vec![1, 2, 3].into(),
+            publicValues: vec![4].into(),
+        }
+        .abi_encode();
+        assert_eq!(input[..4], parse_hex("0x9ee0930f").unwrap()[..]);
+        let call = updateStateCall::abi_decode(&input, true).unwrap();
+        assert_eq!(call.proof.to_vec(), vec![1, 2, 3]);
+        assert_eq!(parse_quantity(&json!("0x1a")).unwrap(), 26);
+        assert!(parse_quantity(&json!("0x")).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+// Relayers submitting committed proofs to destination chains.
+//
+// A relayer turns every committed update into a transaction of its destination: it prepares
+// the transaction, estimates its cost, submits it and waits for its confirmation. The relay
+// of an update only talks to the [`Relayer`] trait, so integrating a new destination means
+// adding a relayer here and a variant to [`RelayerKind`].
+//
+// `RELAYERS` lists the enabled relayers, comma separated. `dry-run` runs the whole flow
+// without a destination and logs what would be submitted, to test integrations without
+// funded accounts. `evm` submits the wrapper proofs of one proof chain to a verifier contract
+// of `generate-verifier`, configured by `EVM_RELAYER_*`, and `cosmos` executes them on a
+// `lightwave-cosmwasm` contract, configured by `COSMOS_RELAYER_*`. Relays run in the
+// background and never hold up the prover.
+
+use anyhow::{Context, Result, anyhow};
+use std::{future::Future, sync::Arc, time::Duration};
+
+use crate::state::{ServiceState, UpdateRecord};
+
+mod cosmos;
+mod dry_run;
+mod evm;
+
+pub use cosmos::{CosmosRelayer, CosmosRelayerConfig, CosmosTx};
+pub use dry_run::DryRunRelayer;
+pub use evm::{EvmRelayer, EvmRelayerConfig, EvmTx};
+
+/// Submissions are attempted this many times before the update is dropped
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A committed update with the wrapper proof that delivers it
+#[derive(Debug, Clone)]
+pub struct RelayUpdate {
+    pub chain_id: String,
+    pub mode: String,
+    pub record: UpdateRecord,
+    /// The wrapper proof bytes, as verified on-chain
+    pub proof: Vec<u8>,
+    /// The public values committed by the wrapper circuit
+    pub public_values: Vec<u8>,
+}
+
+impl RelayUpdate {
+    /// The update `record` committed with the wrapper proof of `state`
+    pub fn new(
+        chain_id: &str,
+        mode: &str,
+        record: &UpdateRecord,
+        state: &ServiceState,
+    ) -> Result<Self> {
+        let proof = state
+            .most_recent_wrapper_proof
+            .as_ref()
+            .context("The committed update has no wrapper proof")?;
+        Ok(Self {
+            chain_id: chain_id.to_string(),
+            mode: mode.to_string(),
+            record: record.clone(),
+            proof: proof.bytes(),
+            public_values: proof.public_values.to_vec(),
+        })
+    }
+}
+
+/// The estimated cost of a transaction, in the smallest unit of the fee token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cost {
+    pub amount: u128,
+    pub denom: String,
+}
+
+/// A destination committed updates are relayed to
+pub trait Relayer: Send + Sync + 'static {
+    /// A transaction of the destination
+    type Tx: std::fmt::Debug + Send + Sync;
+
+    /// Name of the destination, for logs
+    fn name(&self) -> String;
+
+    /// Whether `update` is delivered to this destination, e.g. of the proof chain its contract
+    /// verifies
+    fn relays(&self, _update: &RelayUpdate) -> bool {
+        true
+    }
+
+    /// Builds the transaction delivering `update`
+    fn prepare(&self, update: &RelayUpdate) -> impl Future<Output = Result<Self::Tx>> + Send;
+
+    /// Estimates the cost of submitting `tx`
+    fn estimate(&self, tx: &Self::Tx) -> impl Future<Output = Result<Cost>> + Send;
+
+    /// Broadcasts `tx` and returns its hash
+    fn submit(&self, tx: &Self::Tx) -> impl Future<Output = Result<String>> + Send;
+
+    /// Waits until the transaction `hash` is included, fails if it reverted
+    fn confirm(&self, hash: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// The relayers `RELAYERS` can enable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayerKind {
+    DryRun,
+    Evm,
+    Cosmos,
+}
+
+impl std::str::FromStr for RelayerKind {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "dry-run" => Ok(Self::DryRun),
+            "evm" => Ok(Self::Evm),
+            "cosmos" => Ok(Self::Cosmos),
+            name => Err(anyhow!(
+                "Unknown relayer {}, expected dry-run, evm or cosmos",
+                name
+            )),
+        }
+    }
+}
+
+/// A configured relayer
+#[derive(Debug, Clone)]
+enum Destination {
+    DryRun,
+    /// Shared by the relays of every update, which take their nonces from it
+    Evm(Arc<EvmRelayer>),
+    Cosmos(Arc<CosmosRelayer>),
+}
+
+/// The configured relayers, cheap to clone into the prover loops
+#[derive(Debug, Clone, Default)]
+pub struct Relayers {
+    destinations: Arc<Vec<Destination>>,
+}
+
+impl Relayers {
+    /// The relayers of `RELAYERS`, none if it is unset
+    pub fn from_env() -> Result<Self> {
+        let Ok(relayers) = std::env::var("RELAYERS") else {
+            return Ok(Self::default());
+        };
+        let destinations = relayers
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| match name.parse()? {
+                RelayerKind::DryRun => Ok(Destination::DryRun),
+                RelayerKind::Evm => Ok(Destination::Evm(Arc::new(EvmRelayer::from_env()?))),
+                RelayerKind::Cosmos => {
+                    Ok(Destination::Cosmos(Arc::new(CosmosRelayer::from_env()?)))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            destinations: Arc::new(destinations),
+        })
+    }
+
+    /// Relays a committed update to every configured destination in the background
+    pub fn relay(&self, chain_id: &str, mode: &str, record: &UpdateRecord, state: &ServiceState) {
+        if self.destinations.is_empty() {
+            return;
+        }
+        let update = match RelayUpdate::new(chain_id, mode, record, state) {
+            Ok(update) => update,
+            Err(e) => {
+                tracing::warn!("⚠️  Not relaying update {}: {}", record.update_id, e);
+                return;
+            }
+        };
+        for destination in self.destinations.iter() {
+            let update = update.clone();
+            match destination {
+                Destination::DryRun => {
+                    tokio::spawn(async move { relay(&DryRunRelayer, &update).await });
+                }
+                Destination::Evm(relayer) => {
+                    let relayer = relayer.clone();
+                    tokio::spawn(async move { relay(relayer.as_ref(), &update).await });
+                }
+                Destination::Cosmos(relayer) => {
+                    let relayer = relayer.clone();
+                    tokio::spawn(async move { relay(relayer.as_ref(), &update).await });
+                }
+            }
+        }
+    }
+}
+
+/// Prepares, submits and confirms the transaction of `update`, retrying failed submissions
+pub async fn relay<R: Relayer>(relayer: &R, update: &RelayUpdate) -> Option<String> {
+    if !relayer.relays(update) {
+        return None;
+    }
+    for attempt in 1..=MAX_ATTEMPTS {
+        match relay_once(relayer, update).await {
+            Ok(hash) => {
+                tracing::info!(
+                    "📨 Relayed update {} to {} in {}",
+                    update.record.update_id,
+                    relayer.name(),
+                    hash
+                );
+                return Some(hash);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "⚠️  Relaying update {} to {} failed (attempt {}/{}): {}",
+                    update.record.update_id,
+                    relayer.name(),
+                    attempt,
+                    MAX_ATTEMPTS,
+                    e
+                );
+                if attempt < MAX_ATTEMPTS {
+                    tokio::time::sleep(Duration::from_secs(5 * attempt as u64)).await;
+                }
+            }
+        }
+    }
+    None
+}
+
+async fn relay_once<R: Relayer>(relayer: &R, update: &RelayUpdate) -> Result<String> {
+    let tx = relayer.prepare(update).await?;
+    let cost = relayer.estimate(&tx).await?;
+    tracing::info!(
+        "💸 Relaying update {} to {} costs {} {}",
+        update.record.update_id,
+        relayer.name(),
+        cost.amount,
+        cost.denom
+    );
+    let hash = relayer.submit(&tx).await?;
+    relayer.confirm(&hash).await?;
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dry_run_relays_without_destination() {
+        let update = RelayUpdate {
+            chain_id: "default".to_string(),
+            mode: "HELIOS".to_string(),
+            record: UpdateRecord {
+                update_counter: 3,
+                update_id: "helios:default:3".to_string(),
+                slot: 100,
+                height: 42,
+                root: [0xab; 32],
+                proof_hash: [0; 32],
+                prev_hash: [0; 32],
+                hash: [1; 32],
+                committed_at: None,
+            },
+            proof: vec![1, 2, 3],
+            public_values: vec![4, 5],
+        };
+        assert_eq!(
+            relay(&DryRunRelayer, &update).await.as_deref(),
+            Some("dry-run:helios:default:3")
+        );
+        assert_eq!(
+            "dry-run".parse::<RelayerKind>().unwrap(),
+            RelayerKind::DryRun
+        );
+        assert!("solana".parse::<RelayerKind>().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+// Reproduction metadata of committed rounds, served by `GET /rounds/{id}/debug`.
+//
+// When a prover loop commits a round it records what the round ran against: the backend and
+// prover configuration, the RPC endpoints, the circuits with their ELF hashes and vks, a hash
+// of the recursion inputs and the duration of every proving step. Together with the history
+// record, the stored base inputs and the archived bundle, that is everything needed to re-run
+// the round and compare its outputs.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{api::HistoryRecord, archive::ProofArchive, encoding::to_hex, state::StateManager};
+
+/// A circuit a round was proven with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitInfo {
+    /// `base`, `recursive` or `wrapper`
+    pub name: String,
+    pub elf_sha256: String,
+    pub vk: String,
+}
+
+impl CircuitInfo {
+    pub fn new(name: &str, elf: &[u8], vk: String) -> Self {
+        Self {
+            name: name.to_string(),
+            elf_sha256: sha256_hex(elf),
+            vk,
+        }
+    }
+}
+
+/// The circuits the proof chains of this process set up, by chain id, with their backend
+static CIRCUITS: Mutex<BTreeMap<String, (String, Vec<CircuitInfo>)>> = Mutex::new(BTreeMap::new());
+
+/// Records the circuits the proof chain `chain_id` proves with, served on `GET /vk`
+pub fn register_circuits(chain_id: &str, backend: &str, circuits: Vec<CircuitInfo>) {
+    CIRCUITS
+        .lock()
+        .unwrap()
+        .insert(chain_id.to_string(), (backend.to_string(), circuits));
+}
+
+/// The backend and circuits of the proof chain `chain_id`, `None` until it set them up
+pub fn registered_circuits(chain_id: &str) -> Option<(String, Vec<CircuitInfo>)> {
+    CIRCUITS.lock().unwrap().get(chain_id).cloned()
+}
+
+/// Wall-clock duration of each proving step of a round, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTimings {
+    pub base_ms: u64,
+    pub recursive_ms: u64,
+    pub wrapper_ms: u64,
+}
+
+/// What a round ran against, stored with its update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundMetadata {
+    pub backend: String,
+    /// `PROVER_MODE`
+    pub prover_mode: String,
+    /// `SP1_PROVER`, unset defaults to the CPU prover
+    pub sp1_prover: Option<String>,
+    /// The RPC endpoints the base proof inputs were fetched from
+    pub rpc_endpoints: Vec<String>,
+    pub circuits: Vec<CircuitInfo>,
+    /// sha256 over the borsh encoded recursion circuit inputs
+    pub recursion_inputs_sha256: String,
+    pub timings: RoundTimings,
+}
+
+/// Everything needed to reproduce a committed round
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoundDebug {
+    pub chain_id: String,
+    pub record: HistoryRecord,
+    /// sha256 over the stored base proof inputs
+    pub base_inputs_sha256: Option<String>,
+    /// API path serving the base proof inputs
+    pub base_inputs_url: Option<String>,
+    /// The archived proof bundle, if `PROOF_ARCHIVE_DIR` holds one, or its URL if bundles are
+    /// uploaded to `PROOF_ARCHIVE_S3`
+    pub archived_bundle: Option<String>,
+    /// Absent for rounds committed before the metadata was recorded and for proof chains
+    /// anchored to another chain
+    pub round: Option<RoundMetadata>,
+}
+
+impl RoundDebug {
+    /// Assembles the debug info of update `update_counter` of the chain of `state_manager`,
+    /// `None` if it was never committed
+    pub fn load(state_manager: &StateManager, update_counter: u64) -> Result<Option<Self>> {
+        let Some(record) = state_manager
+            .load_history(update_counter, 1)?
+            .into_iter()
+            .find(|record| record.update_counter == update_counter)
+        else {
+            return Ok(None);
+        };
+        let chain_id = state_manager.chain_id();
+        let base_inputs = state_manager.load_base_inputs(update_counter)?;
+        let archive = ProofArchive::from_env()?;
+        Ok(Some(Self {
+            chain_id: chain_id.to_string(),
+            record: record.into(),
+            base_inputs_sha256: base_inputs.as_ref().map(sha256_hex),
+            base_inputs_url: base_inputs
+                .map(|_| format!("/inputs?update={}&chain_id={}", update_counter, chain_id)),
+            archived_bundle: archive
+                .bundle_path(chain_id, update_counter)
+                .map(|path| path.display().to_string())
+                .or_else(|| archive.archived_url(chain_id, update_counter)),
+            round: state_manager.load_round_metadata(update_counter)?,
+        }))
+    }
+}
+
+pub fn sha256_hex(bytes: impl AsRef<[u8]>) -> String {
+    to_hex(Sha256::digest(bytes))
+}
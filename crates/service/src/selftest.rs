@@ -0,0 +1,130 @@
+// Startup self-test of the prover environment.
+//
+// A misconfigured prover (missing GPU drivers, an unreachable docker daemon or prover
+// container, a rejected prover network key) otherwise surfaces only when the first round
+// reaches its recursive proof, after the base proof was generated. With `SELF_TEST` set the
+// service proves a trivial program before starting its proof chains and refuses to start if
+// that fails:
+// - `prove`: a Groth16 proof with the configured prover, on every GPU of `GPU_DEVICES`, on the
+//   prover network or on the remote workers, verified on the host
+// - `mock`: a mock proof, which checks the SDK and the built-in ELFs without touching the
+//   proving hardware
+
+use anyhow::{Context, Result, anyhow};
+use sp1_sdk::{ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use std::time::Instant;
+use tokio::task::JoinSet;
+
+use crate::{
+    SANITY_ELF,
+    gpu::{GpuDevice, LocalProver, cleanup_gpu_containers},
+    network::NetworkConfig,
+    workers::WorkerPool,
+};
+
+/// The input of the sanity program, small enough to prove in seconds
+const SANITY_INPUT: u64 = 100;
+
+/// The self-test selected by `SELF_TEST`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTest {
+    Prove,
+    Mock,
+}
+
+impl SelfTest {
+    /// Reads `SELF_TEST`, `None` if the self-test is disabled
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var("SELF_TEST").as_deref() {
+            Err(_) | Ok("") | Ok("off") => Ok(None),
+            Ok("prove") => Ok(Some(Self::Prove)),
+            Ok("mock") => Ok(Some(Self::Mock)),
+            Ok(mode) => Err(anyhow!(
+                "Invalid SELF_TEST {}, expected prove, mock or off",
+                mode
+            )),
+        }
+    }
+
+    /// Proves the sanity program and checks its outputs
+    pub async fn run(self) -> Result<()> {
+        tracing::info!("🩺 Running the {:?} prover self-test...", self);
+        let start = Instant::now();
+        let client = ProverClient::builder().cpu().build();
+        let (pk, vk) = client.setup(SANITY_ELF);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&SANITY_INPUT);
+
+        let proofs = match self {
+            Self::Mock => {
+                let client = ProverClient::builder().mock().build();
+                vec![("mock prover", client.prove(&pk, &stdin).groth16().run()?)]
+            }
+            Self::Prove => {
+                if let Some(network) = NetworkConfig::from_env()? {
+                    vec![(
+                        "prover network",
+                        network.prove("Self-test", &pk, &stdin).await?,
+                    )]
+                } else if let Some(workers) = WorkerPool::global()? {
+                    // Concurrent requests go to different workers, every worker proves once
+                    let mut requests = JoinSet::new();
+                    for _ in 0..workers.len() {
+                        let (pk, stdin) = (pk.clone(), stdin.clone());
+                        requests
+                            .spawn(async move { workers.prove("Self-test", &pk, &stdin).await });
+                    }
+                    let mut proofs = Vec::new();
+                    while let Some(proof) = requests.join_next().await {
+                        proofs.push(("worker", proof??));
+                    }
+                    proofs
+                } else {
+                    cleanup_gpu_containers().await?;
+                    let devices = GpuDevice::from_env()?;
+                    let mut proofs = Vec::new();
+                    if devices.is_empty() {
+                        proofs.push(("local prover", prove_locally(None, &pk, &stdin).await?));
+                    }
+                    for device in &devices {
+                        let proof = prove_locally(Some(device), &pk, &stdin)
+                            .await
+                            .context(format!("Self-test proof on GPU {} failed", device.name))?;
+                        proofs.push(("GPU", proof));
+                    }
+                    proofs
+                }
+            }
+        };
+
+        for (prover, mut proof) in proofs {
+            if self == Self::Prove {
+                client
+                    .verify(&proof, &vk)
+                    .map_err(|e| anyhow!("Self-test proof of the {} is invalid: {}", prover, e))?;
+            }
+            let n: u64 = proof.public_values.read();
+            let sum: u64 = proof.public_values.read();
+            if n != SANITY_INPUT || sum != SANITY_INPUT * (SANITY_INPUT + 1) / 2 {
+                return Err(anyhow!(
+                    "Self-test proof of the {} committed unexpected outputs {} and {}",
+                    prover,
+                    n,
+                    sum
+                ));
+            }
+        }
+        tracing::info!("✅ Prover self-test passed in {:?}", start.elapsed());
+        Ok(())
+    }
+}
+
+async fn prove_locally(
+    device: Option<&GpuDevice>,
+    pk: &SP1ProvingKey,
+    stdin: &SP1Stdin,
+) -> Result<SP1ProofWithPublicValues> {
+    let prover = LocalProver::new(device);
+    let (pk, stdin) = (pk.clone(), stdin.clone());
+    tokio::task::spawn_blocking(move || prover.prove_groth16(&pk, &stdin)).await?
+}
@@ -0,0 +1,130 @@
+// Binding of the API server to a TCP address or to a unix domain socket, for relayers
+// running as sidecars on the same host.
+
+use anyhow::{Context, Result};
+use axum::Router;
+use std::{
+    fmt,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+};
+use tracing::{error, info};
+
+/// Where the API server listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiBind {
+    Tcp(SocketAddr),
+    Unix {
+        path: PathBuf,
+        /// Permissions of the socket file
+        mode: u32,
+    },
+}
+
+impl ApiBind {
+    /// Reads `API_BIND_ADDRESS` (an interface address, default `0.0.0.0`, or `unix:<path>`),
+    /// `API_PORT` (default `7778`) and `API_SOCKET_MODE` (octal, default `660`)
+    pub fn from_env() -> Result<Self> {
+        let address = std::env::var("API_BIND_ADDRESS").unwrap_or_else(|_| "0.0.0.0".to_string());
+        let port = std::env::var("API_PORT").unwrap_or_else(|_| "7778".to_string());
+        let mode = std::env::var("API_SOCKET_MODE").unwrap_or_else(|_| "660".to_string());
+        Self::parse(&address, &port, &mode)
+    }
+
+    fn parse(address: &str, port: &str, mode: &str) -> Result<Self> {
+        if let Some(path) = address.strip_prefix("unix:") {
+            let mode = u32::from_str_radix(mode, 8)
+                .ok()
+                .filter(|mode| *mode <= 0o777)
+                .context(format!("Invalid API_SOCKET_MODE {}", mode))?;
+            return Ok(Self::Unix {
+                path: PathBuf::from(path),
+                mode,
+            });
+        }
+        let ip: IpAddr = address
+            .parse()
+            .context(format!("Invalid API_BIND_ADDRESS {}", address))?;
+        let port: u16 = port.parse().context(format!("Invalid API_PORT {}", port))?;
+        Ok(Self::Tcp(SocketAddr::new(ip, port)))
+    }
+
+    pub fn is_unix(&self) -> bool {
+        matches!(self, Self::Unix { .. })
+    }
+}
+
+impl fmt::Display for ApiBind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix { path, .. } => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Serves the API until `shutdown` resolves
+pub async fn serve(
+    bind: ApiBind,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let result = match &bind {
+        ApiBind::Tcp(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .inspect_err(|e| error!("Failed to bind to {}: {}", bind, e))?;
+            info!("API server listening on {}", bind);
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+        }
+        ApiBind::Unix { path, mode } => {
+            // A socket left behind by a previous run would make the bind fail
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            let listener = tokio::net::UnixListener::bind(path)
+                .inspect_err(|e| error!("Failed to bind to {}: {}", bind, e))?;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(*mode))?;
+            info!("API server listening on {} (mode {:o})", bind, mode);
+            let result = axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown)
+                .await;
+            std::fs::remove_file(path).ok();
+            result
+        }
+    };
+    result.inspect_err(|e| error!("API server error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bind() {
+        assert_eq!(
+            ApiBind::parse("127.0.0.1", "8080", "660").unwrap(),
+            ApiBind::Tcp("127.0.0.1:8080".parse().unwrap())
+        );
+        assert_eq!(
+            ApiBind::parse("::1", "7778", "660").unwrap(),
+            ApiBind::Tcp("[::1]:7778".parse().unwrap())
+        );
+        assert_eq!(
+            ApiBind::parse("unix:/run/lightwave/api.sock", "7778", "600").unwrap(),
+            ApiBind::Unix {
+                path: PathBuf::from("/run/lightwave/api.sock"),
+                mode: 0o600,
+            }
+        );
+        assert!(ApiBind::parse("localhost", "7778", "660").is_err());
+        assert!(ApiBind::parse("unix:/tmp/api.sock", "7778", "999").is_err());
+    }
+}
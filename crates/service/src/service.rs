@@ -0,0 +1,792 @@
+// The prover loops and the API of the service, started by the `service` binary or embedded in
+// another process with `LightwaveService::builder()`.
+//
+// The builder defaults to the environment the binary runs with (`CLIENT_BACKEND`,
+// `SERVICE_STATE_DB_PATH`, `ELFS_OUT`, `SOURCE_CONSENSUS_RPC_URL`, `API_BIND_ADDRESS` and
+// `PROVING_SLOTS`), each can be overridden. The other settings of the backends and the prover
+// are read from the environment as for the binary. An embedding process can merge its own
+// routes into the API, or serve the API routes itself, and stops the service through the
+// returned handle: the prover loops finish their current round first.
+
+use anyhow::{Context, Result, anyhow};
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    middleware::from_fn_with_state,
+    routing::{get, post},
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    sync::watch,
+    task::{JoinError, JoinHandle, JoinSet},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    api::{
+        AppState, chain_mode, get_base_inputs, get_committee, get_committees, get_control, get_eta,
+        get_history, get_hold, get_ibc_update, get_lineage, get_proof, get_round_debug, get_shadow,
+        get_snapshot, get_state, get_stats, get_trust, get_vk, post_abort_hold, post_annotation,
+        post_warm_start, put_control,
+    },
+    artifacts,
+    backends::{BitcoinBackend, GrandpaBackend, HeliosBackend, TendermintBackend},
+    cache,
+    chains::tendermint_chains,
+    checkpoints::{BITCOIN_TRUSTED_HEIGHT, GRANDPA_TRUSTED_HEIGHT},
+    control::{Control, ControlState, Stop},
+    egress,
+    encoding::to_hex,
+    eta::EtaResponse,
+    ics23::{self, ICS23_CHAIN_ID, Ics23Config},
+    middleware::{RateLimiter, rate_limit, require_bearer_token},
+    network,
+    networks::EthereumNetwork,
+    opstack::{self, OPSTACK_CHAIN_ID, OpStackConfig},
+    prover::{ProverMode, Sp1RoundProver, run_prover_loop},
+    selftest,
+    server::{self, ApiBind},
+    shadow::ShadowChain,
+    slo::{self, SloConfig},
+    slots::ProvingSlots,
+    state::{self, DEFAULT_CHAIN_ID, DbPool, StateManager},
+    state_diff::{self, StateDiffConfig},
+    storage::{self, STORAGE_CHAIN_ID, StorageConfig},
+    trust::TrustResponse,
+};
+
+/// The largest snapshot `POST /admin/warm-start` accepts
+const WARM_START_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// The light client service: the prover loops of a backend and the API serving their proofs
+pub struct LightwaveService;
+
+impl LightwaveService {
+    /// A builder of the service, configured from the environment until overridden
+    pub fn builder() -> LightwaveServiceBuilder {
+        LightwaveServiceBuilder::from_env()
+    }
+}
+
+/// Configures the service to start
+pub struct LightwaveServiceBuilder {
+    backend: String,
+    db_path: PathBuf,
+    db: Option<DbPool>,
+    elfs_path: String,
+    consensus_url: String,
+    slots: Option<ProvingSlots>,
+    bind: Option<ApiBind>,
+    serve_api: bool,
+    router: Option<Router>,
+}
+
+impl LightwaveServiceBuilder {
+    fn from_env() -> Self {
+        Self {
+            backend: std::env::var("CLIENT_BACKEND").unwrap_or_else(|_| "TENDERMINT".to_string()),
+            db_path: std::env::var("SERVICE_STATE_DB_PATH")
+                .unwrap_or_else(|_| "service_state.db".to_string())
+                .into(),
+            db: None,
+            elfs_path: std::env::var("ELFS_OUT").unwrap_or_else(|_| "elfs/variable".to_string()),
+            consensus_url: std::env::var("SOURCE_CONSENSUS_RPC_URL").unwrap_or_default(),
+            slots: None,
+            bind: None,
+            serve_api: true,
+            router: None,
+        }
+    }
+
+    /// The backend proving the `default` chain on the first start, `CLIENT_BACKEND`. A backend
+    /// switched at runtime through the admin API or [`ServiceHandle::control`] is kept
+    pub fn backend(mut self, backend: impl Into<String>) -> Self {
+        self.backend = backend.into();
+        self
+    }
+
+    /// The state database, `SERVICE_STATE_DB_PATH`
+    pub fn database(mut self, db_path: impl Into<PathBuf>) -> Self {
+        self.db_path = db_path.into();
+        self
+    }
+
+    /// A pool of connections to a state database opened with [`state::open_pool`], shared
+    /// with the embedding process. Replaces the database path
+    pub fn pool(mut self, db: DbPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// The directory or registry URL of the ELFs, `ELFS_OUT`
+    pub fn elfs(mut self, elfs_path: impl Into<String>) -> Self {
+        self.elfs_path = elfs_path.into();
+        self
+    }
+
+    /// The beacon RPC of the Helios backend, `SOURCE_CONSENSUS_RPC_URL`
+    pub fn consensus_url(mut self, consensus_url: impl Into<String>) -> Self {
+        self.consensus_url = consensus_url.into();
+        self
+    }
+
+    /// The proving slots shared by the proof chains, by default configured by `PROVING_SLOTS`,
+    /// `GPU_DEVICES` and `PROVER_WORKERS`. Slots shared with other provers of the embedding
+    /// process schedule their proofs fairly
+    pub fn proving_slots(mut self, slots: ProvingSlots) -> Self {
+        self.slots = Some(slots);
+        self
+    }
+
+    /// Where the API is served, `API_BIND_ADDRESS` and `API_PORT`
+    pub fn bind(mut self, bind: ApiBind) -> Self {
+        self.bind = Some(bind);
+        self
+    }
+
+    /// Routes of the embedding process served next to the API, with their own state and
+    /// layers. The bearer token and the rate limit of the API apply to them as well
+    pub fn router(mut self, router: Router) -> Self {
+        self.router = Some(match self.router.take() {
+            Some(routes) => routes.merge(router),
+            None => router,
+        });
+        self
+    }
+
+    /// Doesn't serve the API, the embedding process serves [`ServiceHandle::router`] itself
+    pub fn without_api(mut self) -> Self {
+        self.serve_api = false;
+        self
+    }
+
+    /// Checks the configuration, starts the prover loops and serves the API
+    pub async fn start(self) -> Result<ServiceHandle> {
+        // In locked-down environments every configured endpoint must be reachable
+        egress::check_endpoints()?;
+        if let Some(network) = network::NetworkConfig::from_env()? {
+            info!("📡 Proving on the prover network: {:?}", network);
+        }
+        // Fail now rather than at the first recursive proof if the prover is misconfigured
+        if let Some(self_test) = selftest::SelfTest::from_env()? {
+            self_test
+                .run()
+                .await
+                .context("Prover self-test failed, the prover environment is misconfigured")?;
+        }
+        let prover_mode = ProverMode::from_env()?;
+        if prover_mode.mocks_circuits() {
+            warn!(
+                "⚠️  PROVER_MODE={}, recursion and wrapper proofs are mock proofs that no relayer accepts",
+                prover_mode
+            );
+        }
+
+        // Get the server address or unix socket from environment or use default
+        let bind = match self.bind {
+            Some(bind) => bind,
+            None => ApiBind::from_env()?,
+        };
+
+        // The API and the prover loops share a pool of connections to the state database
+        let db = match self.db {
+            Some(db) => db,
+            None => {
+                // Create parent directory if it doesn't exist
+                if let Some(parent) = self.db_path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .context("Failed to create database directory")?;
+                }
+                state::open_pool(&self.db_path)?
+            }
+        };
+
+        // Load the backend and chains to prove, which the admin API can change at runtime
+        let control = Control::load(
+            &StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?,
+            &self.backend,
+        )?;
+
+        // Verify that required ELF files exist, remote registries are checked when fetching
+        let helios_recursive_elf_path = Path::new(&self.elfs_path).join("helios-recursive-elf.bin");
+        if !artifacts::is_remote(&self.elfs_path) && !helios_recursive_elf_path.exists() {
+            return Err(anyhow!(
+                "Recursive ELF not found at {}, please run `service dump-elfs`",
+                helios_recursive_elf_path.display()
+            ));
+        }
+
+        let mut router = api_router(&db, &control);
+        if let Some(extra) = self.router {
+            router = router.merge(extra);
+        }
+        // Rate limit clients by IP address if a limit is configured
+        if let Some(limiter) = RateLimiter::from_env()? {
+            if bind.is_unix() || !self.serve_api {
+                warn!(
+                    "API rate limiting is per IP address and is ignored on unix sockets and APIs \
+                     served by the embedding process"
+                );
+            } else {
+                info!("API rate limiting enabled");
+                router = router.layer(from_fn_with_state(Arc::new(limiter), rate_limit));
+            }
+        }
+
+        let (shutdown, stopped) = Stop::new();
+
+        // Start the API server in a separate task
+        let server = self.serve_api.then(|| {
+            let mut stopped = stopped.clone();
+            tokio::spawn(server::serve(bind, router.clone(), async move {
+                stopped.raised().await;
+                info!("API server shutting down gracefully");
+            }))
+        });
+
+        // Start the prover loops, and restart them whenever the admin API changes the
+        // configuration
+        let (stop_tx, stop) = Stop::new();
+        let provers = spawn_provers(
+            &control.current(),
+            &db,
+            &self.elfs_path,
+            &self.consensus_url,
+            self.slots.as_ref(),
+            &stop,
+        )
+        .await?;
+        let mut service_tasks = JoinSet::new();
+        service_tasks.spawn(supervise_provers(
+            control.clone(),
+            provers,
+            stop_tx,
+            stopped,
+            ProverConfig {
+                db: db.clone(),
+                elfs_path: self.elfs_path,
+                consensus_url: self.consensus_url,
+                slots: self.slots,
+            },
+        ));
+
+        // Track the freshness SLO of every proof chain in the database
+        if let Some(config) = SloConfig::from_env()? {
+            service_tasks.spawn(slo::run_slo_monitor(db.clone(), config));
+        }
+
+        // Check the committed execution roots against the canonical chain
+        if let Some(config) = StateDiffConfig::from_env()? {
+            service_tasks.spawn(state_diff::run_state_diff_verifier(db.clone(), config));
+        }
+
+        // Surface the first service task that crashes
+        let services = tokio::spawn(async move {
+            while let Some(result) = service_tasks.join_next().await {
+                match result {
+                    // The supervisor only returns once the service is stopped
+                    Ok(Ok(())) => service_tasks.abort_all(),
+                    Ok(Err(e)) => return Err(e),
+                    Err(e) if e.is_cancelled() => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Ok(())
+        });
+
+        Ok(ServiceHandle {
+            control,
+            db,
+            router,
+            stopper: Stopper(Arc::new(shutdown)),
+            server,
+            services,
+        })
+    }
+}
+
+/// A running service
+pub struct ServiceHandle {
+    control: Control,
+    db: DbPool,
+    router: Router,
+    stopper: Stopper,
+    server: Option<JoinHandle<std::io::Result<()>>>,
+    services: JoinHandle<Result<()>>,
+}
+
+impl ServiceHandle {
+    /// The configuration of the prover loops, to switch the backend or disable chains as the
+    /// admin API does
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+
+    /// The routes of the API, with their authentication, for a service started
+    /// [`without_api`](LightwaveServiceBuilder::without_api)
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+
+    /// Estimates when the next wrapper proof of the chain `chain_id` is committed, as
+    /// `GET /eta` does. `None` if the chain has no state yet
+    pub fn eta(&self, chain_id: &str) -> Result<Option<EtaResponse>> {
+        let state_manager = StateManager::from_pool(&self.db, chain_id)?;
+        EtaResponse::load(&state_manager, &chain_mode(&state_manager)?)
+    }
+
+    /// The trust assumptions of the chain `chain_id`, as `GET /trust` renders them
+    pub fn trust(&self, chain_id: &str) -> Result<TrustResponse> {
+        let state_manager = StateManager::from_pool(&self.db, chain_id)?;
+        TrustResponse::load(&state_manager, &chain_mode(&state_manager)?)
+    }
+
+    /// Stops the service from another task than the one joining it
+    pub fn stopper(&self) -> Stopper {
+        self.stopper.clone()
+    }
+
+    /// Asks the prover loops to stop after their current round and the API server to shut down
+    pub fn stop(&self) {
+        self.stopper.stop();
+    }
+
+    /// Waits until the service stopped, fails if the API server or a service task crashed
+    pub async fn join(self) -> Result<()> {
+        if let Some(server) = self.server {
+            match server.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    error!("API server failed: {}", e);
+                    return Err(anyhow!("{}", e));
+                }
+                Err(e) => {
+                    error!("API server crashed: {}", e);
+                    return Err(anyhow!("{}", e));
+                }
+            }
+        }
+        if let Err(e) = self
+            .services
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result)
+        {
+            error!("Prover service crashed: {}", e);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Stops the service and waits until the prover loops finished their round
+    pub async fn shutdown(self) -> Result<()> {
+        self.stop();
+        self.join().await
+    }
+}
+
+/// Stops a running service
+#[derive(Clone)]
+pub struct Stopper(Arc<watch::Sender<bool>>);
+
+impl Stopper {
+    pub fn stop(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+/// The routes of the API, and of the admin API if `ADMIN_API_TOKEN` is set
+pub fn api_router(db: &DbPool, control: &Control) -> Router {
+    let mut app = Router::new()
+        .route("/", get(get_proof))
+        .route("/ibc", get(get_ibc_update))
+        .route("/history", get(get_history))
+        .route("/lineage", get(get_lineage))
+        .route("/shadow", get(get_shadow))
+        .route("/inputs", get(get_base_inputs))
+        .route("/rounds/{id}/debug", get(get_round_debug))
+        .route("/committee", get(get_committee))
+        .route("/committees", get(get_committees))
+        .route("/stats", get(get_stats))
+        .route("/eta", get(get_eta))
+        .route("/trust", get(get_trust))
+        .route("/vk", get(get_vk))
+        .route("/state", get(get_state))
+        .route("/snapshot", get(get_snapshot))
+        .with_state(AppState { db: db.clone() });
+
+    // Require a bearer token if one is configured
+    if let Ok(token) = std::env::var("API_AUTH_TOKEN") {
+        info!("API bearer token authentication enabled");
+        app = app.layer(from_fn_with_state(Arc::new(token), require_bearer_token));
+    }
+
+    if let Ok(token) = std::env::var("ADMIN_API_TOKEN") {
+        info!("Admin API enabled");
+        let db_admin = Router::new()
+            .route("/admin/annotations", post(post_annotation))
+            .route("/admin/hold", get(get_hold))
+            .route("/admin/hold/abort", post(post_abort_hold))
+            // Compressed recursive proofs exceed the default limit of request bodies
+            .route(
+                "/admin/warm-start",
+                post(post_warm_start).layer(DefaultBodyLimit::max(WARM_START_BODY_LIMIT)),
+            )
+            .with_state(AppState { db: db.clone() });
+        let admin = Router::new()
+            .route("/admin/control", get(get_control).put(put_control))
+            .with_state(control.clone())
+            .merge(db_admin)
+            .route_layer(from_fn_with_state(Arc::new(token), require_bearer_token));
+        app = app.merge(admin);
+    }
+    app
+}
+
+/// What the prover loops of every configuration are started with
+struct ProverConfig {
+    db: DbPool,
+    elfs_path: String,
+    consensus_url: String,
+    slots: Option<ProvingSlots>,
+}
+
+/// Starts one loop per enabled proof chain of a configuration, each with its own state row
+/// and ELFs
+async fn spawn_provers(
+    control: &ControlState,
+    db: &DbPool,
+    elfs_path: &str,
+    consensus_url: &str,
+    slots: Option<&ProvingSlots>,
+    stop: &Stop,
+) -> Result<JoinSet<Result<()>>> {
+    let mut service_tasks = JoinSet::new();
+    // All proof chains of the configuration share the proving slots
+    let slots = match slots {
+        Some(slots) => slots.clone(),
+        None => ProvingSlots::from_env()?,
+    };
+    match control.backend.as_str() {
+        "HELIOS" => {
+            // Read bytes of recursive-elf and wrapper-elf for Helios
+            let recursive_elf =
+                artifacts::read_artifact(elfs_path, "helios-recursive-elf.bin").await?;
+            let wrapper_elf = artifacts::read_artifact(elfs_path, "helios-wrapper-elf.bin").await?;
+
+            // Load or initialize the service state
+            let network = EthereumNetwork::from_env()?;
+            let state_manager = StateManager::from_pool(db, DEFAULT_CHAIN_ID)?;
+            let service_state = match state_manager.load_state()? {
+                Some(state) => state,
+                None => state_manager.initialize_state(network.trusted_slot()?, 0)?,
+            };
+
+            if let Some(config) =
+                OpStackConfig::from_env()?.filter(|_| control.is_enabled(OPSTACK_CHAIN_ID))
+            {
+                let opstack_elf =
+                    artifacts::read_artifact(elfs_path, "opstack-output-elf.bin").await?;
+                info!("Proving OP Stack output roots of {:?}", config.source);
+                service_tasks.spawn(opstack::run_output_loop(
+                    db.clone(),
+                    config,
+                    opstack_elf,
+                    slots.for_chain(OPSTACK_CHAIN_ID),
+                    stop.clone(),
+                ));
+            }
+            if let Some(config) =
+                StorageConfig::from_env()?.filter(|_| control.is_enabled(STORAGE_CHAIN_ID))
+            {
+                let storage_elf =
+                    artifacts::read_artifact(elfs_path, "storage-proof-elf.bin").await?;
+                info!(
+                    "Proving {} storage slot(s) of {}",
+                    config.slots.len(),
+                    to_hex(config.address)
+                );
+                service_tasks.spawn(storage::run_storage_loop(
+                    db.clone(),
+                    config,
+                    storage_elf,
+                    slots.for_chain(STORAGE_CHAIN_ID),
+                    stop.clone(),
+                ));
+            }
+
+            let backend = HeliosBackend::new(consensus_url.to_string(), network);
+            let prover = Sp1RoundProver::new(recursive_elf, wrapper_elf)
+                .with_proving_slots(slots.for_chain(DEFAULT_CHAIN_ID))
+                .with_shadow(ShadowChain::from_env(DEFAULT_CHAIN_ID, &slots)?);
+            if control.is_enabled(DEFAULT_CHAIN_ID) {
+                service_tasks.spawn(run_prover_loop(
+                    state_manager,
+                    service_state,
+                    backend,
+                    prover,
+                    None,
+                    stop.clone(),
+                ));
+            }
+        }
+        "TENDERMINT" => {
+            for chain in tendermint_chains()?
+                .into_iter()
+                .filter(|chain| control.is_enabled(&chain.chain_id))
+            {
+                // Read bytes of recursive-elf and wrapper-elf for this chain
+                let recursive_elf = artifacts::read_artifact(
+                    elfs_path,
+                    &chain.elf_name("tendermint-recursive-elf.bin"),
+                )
+                .await?;
+                let wrapper_elf = artifacts::read_artifact(
+                    elfs_path,
+                    &chain.elf_name("tendermint-wrapper-elf.bin"),
+                )
+                .await?;
+
+                // Load or initialize the state of this chain
+                let state_manager = StateManager::from_pool(db, &chain.chain_id)?;
+                let service_state = match state_manager.load_state()? {
+                    Some(state) => state,
+                    None => state_manager
+                        .initialize_state(chain.trusted_height, chain.trusted_height)?,
+                };
+
+                info!(
+                    "Starting proof chain for Tendermint chain {}",
+                    chain.chain_id
+                );
+                let prover = Sp1RoundProver::new(recursive_elf, wrapper_elf)
+                    .with_proving_slots(slots.for_chain(&chain.chain_id))
+                    .with_shadow(ShadowChain::from_env(&chain.chain_id, &slots)?);
+                let backend = TendermintBackend::new(Some(chain));
+                service_tasks.spawn(run_prover_loop(
+                    state_manager,
+                    service_state,
+                    backend,
+                    prover,
+                    None,
+                    stop.clone(),
+                ));
+            }
+
+            if let Some(config) =
+                Ics23Config::from_env()?.filter(|_| control.is_enabled(ICS23_CHAIN_ID))
+            {
+                let ics23_elf = artifacts::read_artifact(elfs_path, "ics23-proof-elf.bin").await?;
+                info!(
+                    "Proving {}/{} of Tendermint chain {}",
+                    config.store_key,
+                    to_hex(&config.key),
+                    config.chain_id
+                );
+                service_tasks.spawn(ics23::run_ics23_loop(
+                    db.clone(),
+                    config,
+                    ics23_elf,
+                    slots.for_chain(ICS23_CHAIN_ID),
+                    stop.clone(),
+                ));
+            }
+        }
+        "GRANDPA" => {
+            // Read bytes of recursive-elf and wrapper-elf for GRANDPA
+            let recursive_elf =
+                artifacts::read_artifact(elfs_path, "grandpa-recursive-elf.bin").await?;
+            let wrapper_elf =
+                artifacts::read_artifact(elfs_path, "grandpa-wrapper-elf.bin").await?;
+
+            // Load or initialize the service state
+            let state_manager = StateManager::from_pool(db, DEFAULT_CHAIN_ID)?;
+            let service_state = match state_manager.load_state()? {
+                Some(state) => state,
+                None => state_manager
+                    .initialize_state(GRANDPA_TRUSTED_HEIGHT, GRANDPA_TRUSTED_HEIGHT)?,
+            };
+
+            let grandpa_rpc_url = std::env::var("GRANDPA_RPC_URL").unwrap_or_default();
+            let backend = GrandpaBackend::new(grandpa_rpc_url);
+            let prover = Sp1RoundProver::new(recursive_elf, wrapper_elf)
+                .with_proving_slots(slots.for_chain(DEFAULT_CHAIN_ID))
+                .with_shadow(ShadowChain::from_env(DEFAULT_CHAIN_ID, &slots)?);
+            if control.is_enabled(DEFAULT_CHAIN_ID) {
+                service_tasks.spawn(run_prover_loop(
+                    state_manager,
+                    service_state,
+                    backend,
+                    prover,
+                    None,
+                    stop.clone(),
+                ));
+            }
+        }
+        "BITCOIN" => {
+            // Read bytes of recursive-elf and wrapper-elf for Bitcoin
+            let recursive_elf =
+                artifacts::read_artifact(elfs_path, "bitcoin-recursive-elf.bin").await?;
+            let wrapper_elf =
+                artifacts::read_artifact(elfs_path, "bitcoin-wrapper-elf.bin").await?;
+
+            // Load or initialize the service state
+            let state_manager = StateManager::from_pool(db, DEFAULT_CHAIN_ID)?;
+            let service_state = match state_manager.load_state()? {
+                Some(state) => state,
+                None => state_manager
+                    .initialize_state(BITCOIN_TRUSTED_HEIGHT, BITCOIN_TRUSTED_HEIGHT)?,
+            };
+
+            let esplora_url = std::env::var("BITCOIN_ESPLORA_URL").unwrap_or_default();
+            let confirmations = std::env::var("BITCOIN_CONFIRMATIONS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(6);
+            let backend = BitcoinBackend::new(esplora_url, confirmations);
+            let prover = Sp1RoundProver::new(recursive_elf, wrapper_elf)
+                .with_proving_slots(slots.for_chain(DEFAULT_CHAIN_ID))
+                .with_shadow(ShadowChain::from_env(DEFAULT_CHAIN_ID, &slots)?);
+            if control.is_enabled(DEFAULT_CHAIN_ID) {
+                service_tasks.spawn(run_prover_loop(
+                    state_manager,
+                    service_state,
+                    backend,
+                    prover,
+                    None,
+                    stop.clone(),
+                ));
+            }
+        }
+        mode => return Err(anyhow!("Invalid mode: {:?}", mode)),
+    }
+    Ok(service_tasks)
+}
+
+/// Keeps the prover loops of the requested configuration running, and restarts them whenever
+/// the admin API changes it. The running loops finish their round first, then the `default`
+/// chain is handed over to the new backend. A configuration that fails to start is rolled
+/// back to the running one. Returns once the service is stopped and the loops finished their
+/// round
+async fn supervise_provers(
+    control: Control,
+    mut tasks: JoinSet<Result<()>>,
+    mut stop_tx: watch::Sender<bool>,
+    mut stopped: Stop,
+    config: ProverConfig,
+) -> Result<()> {
+    let mut changes = control.subscribe();
+    let mut active = changes.borrow_and_update().clone();
+    let ProverConfig {
+        db,
+        elfs_path,
+        consensus_url,
+        slots,
+    } = config;
+
+    loop {
+        // Prove until the configuration changes or the service is stopped
+        let requested = loop {
+            tokio::select! {
+                Some(result) = tasks.join_next() => check_prover_loop(result)?,
+                Ok(()) = changes.changed() => {
+                    let requested = changes.borrow_and_update().clone();
+                    if requested != active {
+                        break Some(requested);
+                    }
+                }
+                () = stopped.raised() => break None,
+            }
+        };
+
+        let _ = stop_tx.send(true);
+        let Some(requested) = requested else {
+            info!("Finishing the current round before stopping");
+            while let Some(result) = tasks.join_next().await {
+                check_prover_loop(result)?;
+            }
+            return Ok(());
+        };
+        info!(
+            "Finishing the current round before applying {:?}",
+            requested
+        );
+        while let Some(result) = tasks.join_next().await {
+            check_prover_loop(result)?;
+        }
+
+        // Hand the default chain over to the new backend and persist the configuration
+        let state_manager = StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?;
+        apply_control(&state_manager, &active, &requested)?;
+
+        let (tx, stop) = Stop::new();
+        tasks = match spawn_provers(
+            &requested,
+            &db,
+            &elfs_path,
+            &consensus_url,
+            slots.as_ref(),
+            &stop,
+        )
+        .await
+        {
+            Ok(tasks) => {
+                active = requested;
+                tasks
+            }
+            Err(e) => {
+                error!(
+                    "Failed to start {:?}: {}, restoring {:?}",
+                    requested, e, active
+                );
+                apply_control(&state_manager, &requested, &active)?;
+                control.restore(active.clone(), &mut changes);
+                spawn_provers(
+                    &active,
+                    &db,
+                    &elfs_path,
+                    &consensus_url,
+                    slots.as_ref(),
+                    &stop,
+                )
+                .await?
+            }
+        };
+        stop_tx = tx;
+    }
+}
+
+/// Persists a new configuration, switching the backend of the `default` chain if it changed
+fn apply_control(
+    state_manager: &StateManager,
+    from: &ControlState,
+    to: &ControlState,
+) -> Result<()> {
+    if from.backend == to.backend {
+        return state_manager.save_control(to);
+    }
+    info!(
+        "Switching from the {} to the {} backend",
+        from.backend, to.backend
+    );
+    state_manager.switch_backend(&from.backend, to)?;
+    // The default chain now serves the proofs of the other backend
+    cache::invalidate(state_manager.chain_id());
+    Ok(())
+}
+
+/// Logs a prover loop that failed, the other loops keep running. Panics are propagated
+fn check_prover_loop(result: Result<Result<()>, JoinError>) -> Result<()> {
+    match result {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            error!("Prover loop stopped: {}", e);
+            Ok(())
+        }
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,181 @@
+// Shadow chains, for A/B testing candidate circuits before a cutover.
+//
+// A shadow chain follows a production proof chain with a candidate version of its recursive
+// circuit. Every round the production chain proves, the candidate circuit runs on the same
+// recursion inputs in the background, and its public values are recorded next to the
+// production ones (`GET /shadow`). A candidate that matches production round after round can
+// be cut over with `RESTART_ON_VK_CHANGE`. Configured with:
+// - `SHADOW_CHAIN`: the name of the shadow chain
+// - `SHADOW_RECURSIVE_ELF`: the path of the candidate recursive ELF
+// - `SHADOW_CHAIN_ID`: the production chain it follows, `default` by default
+// - `SHADOW_MODE`: `execute` (default) executes the candidate circuit, `prove` proves it on a
+//   proving slot no proof chain is waiting for, skipping the round if there is none
+//
+// The wrapper circuit is bound to the production recursive vk and isn't shadowed. A failing
+// candidate is recorded with its error and never affects the production chain.
+
+use anyhow::{Context, Result, anyhow};
+use once_cell::sync::OnceCell;
+use sp1_sdk::{ProverClient, SP1ProvingKey, SP1Stdin};
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    gpu::{LocalProver, cleanup_gpu_containers},
+    network::NetworkConfig,
+    slots::{ChainSlots, ProvingSlots},
+    state::{DEFAULT_CHAIN_ID, ShadowRound, StateManager},
+    workers::WorkerPool,
+};
+
+/// How the candidate circuit of a shadow chain runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowMode {
+    Execute,
+    Prove,
+}
+
+/// A candidate recursive circuit following a production proof chain
+#[derive(Clone)]
+pub struct ShadowChain {
+    name: String,
+    /// The production chain
+    chain_id: String,
+    elf: Arc<Vec<u8>>,
+    mode: ShadowMode,
+    slots: ChainSlots,
+    /// Set up on the first proof in `prove` mode
+    pk: Arc<OnceCell<SP1ProvingKey>>,
+}
+
+impl ShadowChain {
+    /// The shadow chain of `SHADOW_CHAIN` if it follows the production chain `chain_id`
+    pub fn from_env(chain_id: &str, slots: &ProvingSlots) -> Result<Option<Self>> {
+        let Ok(name) = std::env::var("SHADOW_CHAIN") else {
+            return Ok(None);
+        };
+        let followed =
+            std::env::var("SHADOW_CHAIN_ID").unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string());
+        if followed != chain_id {
+            return Ok(None);
+        }
+        let mode = match std::env::var("SHADOW_MODE").as_deref() {
+            Ok("execute") | Err(_) => ShadowMode::Execute,
+            Ok("prove") => ShadowMode::Prove,
+            Ok(mode) => {
+                return Err(anyhow!(
+                    "Invalid SHADOW_MODE {}, expected execute or prove",
+                    mode
+                ));
+            }
+        };
+        let path = std::env::var("SHADOW_RECURSIVE_ELF")
+            .context("SHADOW_CHAIN is set but SHADOW_RECURSIVE_ELF is not")?;
+        let elf = std::fs::read(&path).context(format!("Failed to read {}", path))?;
+        tracing::info!(
+            "🌗 Shadow chain {} follows {} with {} ({:?})",
+            name,
+            chain_id,
+            path,
+            mode
+        );
+        Ok(Some(Self {
+            slots: slots.for_chain(&format!("shadow:{}", name)),
+            name,
+            chain_id: chain_id.to_string(),
+            elf: Arc::new(elf),
+            mode,
+            pk: Arc::new(OnceCell::new()),
+        }))
+    }
+
+    /// Runs the candidate circuit on the recursion stdin of production update
+    /// `update_counter` in the background and records its outputs
+    pub fn spawn(&self, update_counter: u64, stdin: SP1Stdin, production_public_values: Vec<u8>) {
+        let shadow = self.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let (shadow_public_values, cycles, error) = match shadow.run(stdin).await {
+                Ok((public_values, cycles)) => (Some(public_values), cycles, None),
+                Err(e) => (None, None, Some(e.to_string())),
+            };
+            let round = ShadowRound {
+                name: shadow.name.clone(),
+                update_counter,
+                production_public_values,
+                shadow_public_values,
+                cycles,
+                error,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                recorded_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            };
+            if round.matches() {
+                tracing::info!(
+                    "🌗 Shadow chain {} matches update {}",
+                    shadow.name,
+                    update_counter
+                );
+            } else {
+                tracing::warn!(
+                    "⚠️  Shadow chain {} diverges at update {}: {}",
+                    shadow.name,
+                    update_counter,
+                    round.error.as_deref().unwrap_or("different public values")
+                );
+            }
+            let saved = StateManager::from_env_for_chain(&shadow.chain_id)
+                .and_then(|state_manager| state_manager.save_shadow_round(&round));
+            if let Err(e) = saved {
+                tracing::warn!("⚠️  Failed to record shadow chain {}: {}", shadow.name, e);
+            }
+        });
+    }
+
+    /// The public values of the candidate circuit, and its cycles when executed
+    async fn run(&self, stdin: SP1Stdin) -> Result<(Vec<u8>, Option<u64>)> {
+        match self.mode {
+            ShadowMode::Execute => {
+                let elf = self.elf.clone();
+                let (public_values, report) = tokio::task::spawn_blocking(move || {
+                    ProverClient::builder()
+                        .cpu()
+                        .build()
+                        .execute(&elf, &stdin)
+                        .run()
+                })
+                .await??;
+                Ok((
+                    public_values.to_vec(),
+                    Some(report.total_instruction_count()),
+                ))
+            }
+            ShadowMode::Prove => {
+                let Some(slot) = self.slots.try_acquire() else {
+                    return Err(anyhow!("No spare proving slot, the round was not proven"));
+                };
+                let elf = self.elf.clone();
+                let cell = self.pk.clone();
+                let pk = tokio::task::spawn_blocking(move || {
+                    cell.get_or_init(|| ProverClient::from_env().setup(&elf).0)
+                        .clone()
+                })
+                .await?;
+                let proof = if let Some(network) = NetworkConfig::from_env()? {
+                    network.prove("Shadow", &pk, &stdin).await?
+                } else if let Some(workers) = WorkerPool::global()? {
+                    workers.prove("Shadow", &pk, &stdin).await?
+                } else {
+                    cleanup_gpu_containers().await?;
+                    let prover = LocalProver::new(slot.device());
+                    tokio::task::spawn_blocking(move || prover.prove_groth16(&pk, &stdin)).await??
+                };
+                Ok((proof.public_values.to_vec(), None))
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// Freshness SLO of the proof chains: a new proof at most every `max_proof_interval`.
+//
+// Every second a chain goes without a proof for longer than the interval spends its error
+// budget, `(1 - target) * window` seconds per rolling window. The budget is derived from the
+// commit times of the update history and the time tracking started, both persisted, so
+// restarts keep the accounting and downtime spends the budget like any other stall.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    state::{DEFAULT_CHAIN_ID, DbPool, StateManager},
+    webhooks::{WebhookConfig, deliver},
+};
+
+/// Seconds between two evaluations of the SLO
+const CHECK_INTERVAL: u64 = 60;
+
+/// The freshness objective offered to consumers
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    /// Maximum seconds between two proofs
+    pub max_proof_interval: u64,
+    /// Fraction of the window the latest proof must be fresh, e.g. 0.99
+    pub target: f64,
+    /// Seconds of the rolling window the error budget is accounted over
+    pub window: u64,
+    /// Receives a POST when the error budget is exhausted and when it recovers
+    pub alert_url: Option<String>,
+}
+
+impl SloConfig {
+    /// Reads `SLO_MAX_PROOF_INTERVAL_HOURS`, `SLO_TARGET` (defaults to 0.99),
+    /// `SLO_WINDOW_DAYS` (defaults to 30) and `SLO_ALERT_URL`. Returns `None` if no
+    /// interval is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(hours) = std::env::var("SLO_MAX_PROOF_INTERVAL_HOURS") else {
+            return Ok(None);
+        };
+        let hours: f64 = hours
+            .parse()
+            .context(format!("Invalid SLO_MAX_PROOF_INTERVAL_HOURS {}", hours))?;
+        let target: f64 = match std::env::var("SLO_TARGET") {
+            Ok(target) => target
+                .parse()
+                .context(format!("Invalid SLO_TARGET {}", target))?,
+            Err(_) => 0.99,
+        };
+        if !(0.0..=1.0).contains(&target) {
+            return Err(anyhow::anyhow!("SLO_TARGET must be between 0 and 1"));
+        }
+        let days: f64 = match std::env::var("SLO_WINDOW_DAYS") {
+            Ok(days) => days
+                .parse()
+                .context(format!("Invalid SLO_WINDOW_DAYS {}", days))?,
+            Err(_) => 30.0,
+        };
+        if hours <= 0.0 || days <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "SLO_MAX_PROOF_INTERVAL_HOURS and SLO_WINDOW_DAYS must be positive"
+            ));
+        }
+        Ok(Some(Self {
+            max_proof_interval: (hours * 3600.0) as u64,
+            target,
+            window: (days * 86400.0) as u64,
+            alert_url: std::env::var("SLO_ALERT_URL").ok(),
+        }))
+    }
+
+    /// The seconds of staleness allowed per window
+    pub fn budget(&self) -> u64 {
+        ((1.0 - self.target) * self.window as f64).round() as u64
+    }
+}
+
+/// The error budget of a chain over the current window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloStatus {
+    pub max_proof_interval_secs: u64,
+    pub target: f64,
+    pub window_secs: u64,
+    /// Unix timestamp (seconds) since which the SLO is tracked
+    pub tracking_since: u64,
+    /// Unix timestamp (seconds) of the latest committed proof
+    pub last_proof_at: Option<u64>,
+    /// Whether the latest proof is older than the maximum proof interval
+    pub stale: bool,
+    pub budget_secs: u64,
+    /// Seconds spent without a fresh proof in the window
+    pub spent_secs: u64,
+    pub remaining_secs: u64,
+    pub exhausted: bool,
+}
+
+impl SloStatus {
+    /// Loads the persisted accounting of a chain and evaluates its budget at `now`
+    pub fn load(state_manager: &StateManager, config: &SloConfig, now: u64) -> Result<Self> {
+        let (tracking_since, _) = state_manager.load_slo_tracking(now)?;
+        let commits = state_manager.load_commit_times(now.saturating_sub(config.window))?;
+        Ok(evaluate(config, tracking_since, &commits, now))
+    }
+}
+
+/// Evaluates the error budget at `now` from the commit times of a chain, in commit order.
+///
+/// The latest proof is fresh for `max_proof_interval` after it was committed, and so is the
+/// start of tracking, which leaves a new chain the interval to produce its first proof.
+pub fn evaluate(config: &SloConfig, tracking_since: u64, commits: &[u64], now: u64) -> SloStatus {
+    let window_start = now.saturating_sub(config.window).max(tracking_since);
+    let mut anchors: Vec<u64> = commits.to_vec();
+    anchors.push(tracking_since);
+    anchors.sort_unstable();
+
+    // Sum the stale intervals [anchor + interval, next anchor] within the window
+    let spent: u64 = anchors
+        .iter()
+        .enumerate()
+        .map(|(i, anchor)| {
+            let next = anchors.get(i + 1).copied().unwrap_or(now).min(now);
+            let stale_from = (anchor + config.max_proof_interval).max(window_start);
+            next.saturating_sub(stale_from)
+        })
+        .sum();
+
+    let last_proof_at = commits.last().copied();
+    let budget = config.budget();
+    let remaining = budget.saturating_sub(spent);
+    SloStatus {
+        max_proof_interval_secs: config.max_proof_interval,
+        target: config.target,
+        window_secs: config.window,
+        tracking_since,
+        last_proof_at,
+        stale: now.saturating_sub(last_proof_at.unwrap_or(tracking_since).max(tracking_since))
+            > config.max_proof_interval,
+        budget_secs: budget,
+        spent_secs: spent,
+        remaining_secs: remaining,
+        exhausted: spent > 0 && remaining == 0,
+    }
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System clock is before the unix epoch")?
+        .as_secs())
+}
+
+/// Evaluates the SLO of every proof chain in the database and alerts once when a budget
+/// is exhausted and once when it recovers, forever
+pub async fn run_slo_monitor(db: DbPool, config: SloConfig) -> Result<()> {
+    let client = crate::egress::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let alert_webhook = config.alert_url.clone().map(|url| WebhookConfig {
+        url,
+        template: serde_json::Value::Null,
+        chain_ids: None,
+        headers: BTreeMap::new(),
+    });
+    tracing::info!(
+        "🎯 Tracking a new proof at most every {}s, {}% of a {}s window",
+        config.max_proof_interval,
+        config.target * 100.0,
+        config.window
+    );
+
+    loop {
+        let now = now()?;
+        let chain_ids = StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?.chain_ids()?;
+        for chain_id in chain_ids {
+            let state_manager = StateManager::from_pool(&db, &chain_id)?;
+            let (_, alerted) = state_manager.load_slo_tracking(now)?;
+            let status = SloStatus::load(&state_manager, &config, now)?;
+            if status.exhausted == alerted {
+                continue;
+            }
+
+            if status.exhausted {
+                tracing::error!(
+                    "🚨 Error budget of {} exhausted: {}s without a fresh proof in the window",
+                    chain_id,
+                    status.spent_secs
+                );
+            } else {
+                tracing::info!("🎯 Error budget of {} recovered", chain_id);
+            }
+            if let Some(webhook) = &alert_webhook {
+                let payload = json!({ "chain_id": chain_id, "slo": status });
+                deliver(&client, webhook, &payload).await;
+            }
+            state_manager.set_slo_alerted(status.exhausted)?;
+        }
+        tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_accounting() {
+        // A proof at most every 10s, 90% of a 100s window leaves a 10s budget
+        let config = SloConfig {
+            max_proof_interval: 10,
+            target: 0.9,
+            window: 100,
+            alert_url: None,
+        };
+
+        // Tracking starts at 0, proofs every 10s up to 50, then 15s stale by 75
+        let commits = [10, 20, 30, 40, 50];
+        let status = evaluate(&config, 0, &commits, 75);
+        assert_eq!(status.spent_secs, 15);
+        assert_eq!(status.remaining_secs, 0);
+        assert!(status.stale);
+        assert!(status.exhausted);
+
+        // A gap of 15s at 30..45 is 5s stale, then fresh again
+        let status = evaluate(&config, 0, &[10, 20, 30, 45, 50], 55);
+        assert_eq!(status.spent_secs, 5);
+        assert_eq!(status.remaining_secs, 5);
+        assert!(!status.stale);
+        assert!(!status.exhausted);
+
+        // Staleness older than the window is no longer accounted
+        let mut commits = vec![10];
+        commits.extend((40..=140).step_by(10));
+        let status = evaluate(&config, 0, &commits, 145);
+        assert_eq!(status.spent_secs, 0);
+        assert_eq!(status.last_proof_at, Some(140));
+    }
+}
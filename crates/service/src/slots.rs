@@ -0,0 +1,381 @@
+// Fair-share scheduling of the proofs of concurrent proof chains.
+//
+// All proof chains of the process prove through a fixed number of proving slots set by
+// `PROVING_SLOTS`: 1 by default, the local prover proves one circuit at a time, and 4 with
+// `SP1_PROVER=network`, where proofs are outsourced. A chain holds a slot for one proving step
+// (setup, a base, recursive, wrapper or anchored proof). A freed slot goes to the waiting chain
+// that held slots for the shortest total time, so one slow chain doesn't starve the others.
+// With `GPU_DEVICES` every configured GPU is a slot, and the proving step holding it proves on
+// that GPU. Free GPUs are handed out in turn, and a GPU excluded for failing its proofs is held
+// back until its exclusion ends, unless it is the last GPU in service. With `PROVER_WORKERS`
+// every remote worker is a slot by default.
+
+use anyhow::{Result, anyhow};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+use crate::{
+    gpu::{GpuDevice, GpuHealth},
+    workers::WorkerPool,
+};
+
+/// Default number of slots when the proofs are outsourced to the prover network
+const NETWORK_SLOTS: usize = 4;
+
+/// The proving slots shared by the proof chains of the process
+#[derive(Clone)]
+pub struct ProvingSlots {
+    inner: Arc<Mutex<Slots>>,
+}
+
+struct Slots {
+    /// The free slots, with their GPU if devices are configured, the least recently used first
+    free: VecDeque<Option<GpuDevice>>,
+    /// Configured GPUs
+    devices: usize,
+    /// GPUs held back until their exclusion ends
+    excluded: usize,
+    /// Total time each chain held a slot
+    usage: HashMap<String, Duration>,
+    waiting: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    chain_id: String,
+    grant: oneshot::Sender<SlotGuard>,
+}
+
+impl ProvingSlots {
+    pub fn new(slots: usize) -> Self {
+        Self::with_free(vec![None; slots])
+    }
+
+    /// One slot per GPU
+    pub fn with_devices(devices: Vec<GpuDevice>) -> Self {
+        Self::with_free(devices.into_iter().map(Some).collect())
+    }
+
+    fn with_free(free: Vec<Option<GpuDevice>>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Slots {
+                devices: free.iter().flatten().count(),
+                excluded: 0,
+                free: free.into(),
+                usage: HashMap::new(),
+                waiting: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Creates one slot per GPU of `GPU_DEVICES`, or `PROVING_SLOTS` slots, 4 on the prover
+    /// network, one per worker of `PROVER_WORKERS` and 1 for local provers by default. Without
+    /// configured GPUs the local CUDA prover runs in a single container and supports only 1 slot
+    pub fn from_env() -> Result<Self> {
+        let devices = GpuDevice::from_env()?;
+        if !devices.is_empty() {
+            if let Ok(slots) = std::env::var("PROVING_SLOTS") {
+                if slots != devices.len().to_string() {
+                    return Err(anyhow!(
+                        "PROVING_SLOTS={} but GPU_DEVICES configures {} GPUs, unset PROVING_SLOTS",
+                        slots,
+                        devices.len()
+                    ));
+                }
+            }
+            // Fail on an invalid health configuration before the first proof
+            GpuHealth::global()?;
+            return Ok(Self::with_devices(devices));
+        }
+
+        let prover = std::env::var("SP1_PROVER").unwrap_or_default();
+        let slots = match std::env::var("PROVING_SLOTS") {
+            Ok(slots) => slots
+                .parse()
+                .ok()
+                .filter(|slots| *slots > 0)
+                .ok_or_else(|| anyhow!("Invalid PROVING_SLOTS {}, expected at least 1", slots))?,
+            Err(_) if prover == "network" => NETWORK_SLOTS,
+            // Every remote worker proves one circuit at a time
+            Err(_) => WorkerPool::global()?.map_or(1, WorkerPool::len),
+        };
+        if prover == "cuda" && slots > 1 && WorkerPool::global()?.is_none() {
+            return Err(anyhow!(
+                "PROVING_SLOTS={} but the CUDA prover proves one circuit at a time, configure \
+                 GPU_DEVICES to prove on several GPUs",
+                slots
+            ));
+        }
+        Ok(Self::new(slots))
+    }
+
+    /// The handle a proof chain acquires its slots with
+    pub fn for_chain(&self, chain_id: &str) -> ChainSlots {
+        ChainSlots {
+            slots: self.clone(),
+            chain_id: chain_id.to_string(),
+        }
+    }
+
+    fn guard(&self, chain_id: &str, device: Option<GpuDevice>) -> SlotGuard {
+        SlotGuard {
+            slots: Some(self.clone()),
+            chain_id: chain_id.to_string(),
+            device,
+            acquired_at: Instant::now(),
+        }
+    }
+
+    fn release(&self, chain_id: &str, held: Duration, device: Option<GpuDevice>) {
+        let mut slots = self.inner.lock().unwrap();
+        *slots.usage.entry(chain_id.to_string()).or_default() += held;
+
+        // Hold a failing GPU back until its exclusion ends, the last GPU in service keeps
+        // proving. The readmission needs the runtime the proving steps run on
+        let exclusion = device
+            .as_ref()
+            .zip(GpuHealth::global().ok())
+            .and_then(|(device, health)| health.exclusion(device));
+        if let (Some(exclusion), Some(device), Ok(runtime)) =
+            (exclusion, &device, tokio::runtime::Handle::try_current())
+        {
+            if slots.excluded + 1 < slots.devices {
+                slots.excluded += 1;
+                tracing::warn!(
+                    "⚠️  Excluding GPU {} from proving for {} seconds",
+                    device.name,
+                    exclusion.as_secs()
+                );
+                let (this, device) = (self.clone(), device.clone());
+                runtime.spawn(async move {
+                    tokio::time::sleep(exclusion).await;
+                    tracing::info!("🔁 GPU {} proves again", device.name);
+                    let mut slots = this.inner.lock().unwrap();
+                    slots.excluded -= 1;
+                    this.hand_over(&mut slots, Some(device));
+                });
+                return;
+            }
+        }
+        self.hand_over(&mut slots, device);
+    }
+
+    /// Hands the slot to the waiting chain with the least usage, in arrival order on ties, or
+    /// frees it
+    fn hand_over(&self, slots: &mut Slots, mut device: Option<GpuDevice>) {
+        loop {
+            let next = slots
+                .waiting
+                .iter()
+                .enumerate()
+                .min_by_key(|(index, waiter)| (slots.usage.get(&waiter.chain_id).copied(), *index))
+                .map(|(index, _)| index);
+            let Some(index) = next else {
+                slots.free.push_back(device);
+                return;
+            };
+            let waiter = slots.waiting.remove(index).unwrap();
+            match waiter.grant.send(self.guard(&waiter.chain_id, device)) {
+                Ok(()) => return,
+                // The chain stopped waiting, the guard must not release the slot again
+                Err(mut guard) => {
+                    guard.slots = None;
+                    device = guard.device.take();
+                }
+            }
+        }
+    }
+}
+
+/// A proof chain's handle on the shared proving slots
+#[derive(Clone)]
+pub struct ChainSlots {
+    slots: ProvingSlots,
+    chain_id: String,
+}
+
+impl ChainSlots {
+    /// The proof chain holding this handle
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    /// Waits for a free slot, held until the guard is dropped
+    pub async fn acquire(&self) -> SlotGuard {
+        let grant = {
+            let mut slots = self.slots.inner.lock().unwrap();
+            if slots.waiting.is_empty() {
+                if let Some(device) = slots.free.pop_front() {
+                    return self.slots.guard(&self.chain_id, device);
+                }
+            }
+            let (grant, granted) = oneshot::channel();
+            slots.waiting.push_back(Waiter {
+                chain_id: self.chain_id.clone(),
+                grant,
+            });
+            granted
+        };
+        // Waiters are only removed by handing them a slot
+        grant.await.expect("proving slot waiter dropped")
+    }
+
+    /// Takes a slot only if one is free and no chain is waiting for it, for work that must
+    /// never delay the proof chains
+    pub fn try_acquire(&self) -> Option<SlotGuard> {
+        let mut slots = self.slots.inner.lock().unwrap();
+        if !slots.waiting.is_empty() {
+            return None;
+        }
+        let device = slots.free.pop_front()?;
+        Some(self.slots.guard(&self.chain_id, device))
+    }
+}
+
+/// A held proving slot, released on drop
+pub struct SlotGuard {
+    slots: Option<ProvingSlots>,
+    chain_id: String,
+    /// The GPU the slot proves on, if devices are configured
+    device: Option<GpuDevice>,
+    acquired_at: Instant,
+}
+
+impl SlotGuard {
+    pub fn device(&self) -> Option<&GpuDevice> {
+        self.device.as_ref()
+    }
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.take() {
+            slots.release(
+                &self.chain_id,
+                self.acquired_at.elapsed(),
+                self.device.take(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_freed_slot_goes_to_least_served_chain() {
+        let slots = ProvingSlots::new(1);
+        let (slow, fast) = (slots.for_chain("slow"), slots.for_chain("fast"));
+
+        // The slow chain held the slot for a while
+        let guard = slow.acquire().await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(guard);
+
+        // Both chains wait for the busy slot, the slow chain first
+        let guard = fast.acquire().await;
+        let slow_waiting = tokio::spawn({
+            let slow = slow.clone();
+            async move { slow.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        let fast_waiting = tokio::spawn({
+            let fast = fast.clone();
+            async move { fast.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        drop(guard);
+
+        // The fast chain is served before the slow chain that arrived earlier
+        let fast_guard = tokio::time::timeout(Duration::from_secs(1), fast_waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!slow_waiting.is_finished());
+        drop(fast_guard);
+        tokio::time::timeout(Duration::from_secs(1), slow_waiting)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_steps_prove_on_different_gpus() {
+        let device = |name: &str| GpuDevice {
+            name: name.to_string(),
+            endpoint: format!("http://{}:3000", name),
+        };
+        let slots = ProvingSlots::with_devices(vec![device("gpu0"), device("gpu1")]);
+        let chain = slots.for_chain("chain");
+
+        let first = chain.acquire().await;
+        let second = chain.acquire().await;
+        assert_ne!(first.device(), second.device());
+
+        // A released GPU goes to the next step
+        let released = first.device().cloned();
+        drop(first);
+        assert_eq!(chain.acquire().await.device().cloned(), released);
+    }
+
+    #[tokio::test]
+    async fn test_gpus_are_handed_out_in_turn_and_failing_ones_held_back() {
+        let device = |name: &str| GpuDevice {
+            name: name.to_string(),
+            endpoint: format!("http://{}:3000", name),
+        };
+        let (healthy, failing) = (device("slots-gpu0"), device("slots-gpu1"));
+        let slots = ProvingSlots::with_devices(vec![healthy.clone(), failing.clone()]);
+        let chain = slots.for_chain("chain");
+
+        // The GPU that just proved goes last
+        drop(chain.acquire().await);
+        let guard = chain.acquire().await;
+        assert_eq!(guard.device(), Some(&failing));
+
+        // A GPU failing its proofs is held back, the other one keeps proving
+        let health = GpuHealth::global().unwrap();
+        while health.exclusion(&failing).is_none() {
+            health.record(&failing, false);
+        }
+        drop(guard);
+        let guard = chain.try_acquire().unwrap();
+        assert_eq!(guard.device(), Some(&healthy));
+        assert!(chain.try_acquire().is_none());
+
+        // The last GPU in service is never held back
+        while health.exclusion(&healthy).is_none() {
+            health.record(&healthy, false);
+        }
+        drop(guard);
+        assert_eq!(chain.try_acquire().unwrap().device(), Some(&healthy));
+    }
+
+    #[tokio::test]
+    async fn test_spare_slot_is_only_taken_when_free() {
+        let slots = ProvingSlots::new(1);
+        let (chain, shadow) = (
+            slots.for_chain("chain"),
+            slots.for_chain("shadow:candidate"),
+        );
+
+        let guard = chain.acquire().await;
+        assert!(shadow.try_acquire().is_none());
+        let waiting = tokio::spawn({
+            let chain = chain.clone();
+            async move { chain.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        drop(guard);
+
+        // The freed slot went to the waiting chain
+        let guard = waiting.await.unwrap();
+        assert!(shadow.try_acquire().is_none());
+        drop(guard);
+        assert!(shadow.try_acquire().is_some());
+    }
+}
@@ -0,0 +1,143 @@
+// Generation of the Solidity verifier of a proof chain, behind `generate-verifier`.
+//
+// Writes a foundry project with a `LightwaveVerifier` contract, rendered from the blueprint in
+// `contracts/` like the circuits are rendered from theirs. The contract pins the wrapper vk of
+// the chain, verifies wrapper proofs through the SP1 verifier gateway it is deployed with,
+// decodes the outputs in the encoding the wrapper circuit commits (`WRAPPER_ENCODING`) and
+// stores the proven height and root with `updateState(bytes proof, bytes publicValues)`.
+
+use anyhow::{Context, Result};
+use sp1_sdk::{HashableKey, ProverClient};
+use std::path::Path;
+
+use crate::{
+    chains::tendermint_chain, commands::wrapper_elf, networks::EthereumNetwork,
+    prover::WrapperEncoding, state::StateManager,
+};
+
+const VERIFIER_BLUEPRINT: &str = include_str!("../contracts/LightwaveVerifier.sol");
+const SP1_VERIFIER_INTERFACE: &str = include_str!("../contracts/ISP1Verifier.sol");
+const FOUNDRY_CONFIG: &str = include_str!("../contracts/foundry.toml");
+
+/// Renders the verifier contract of the chain `caip2`, proven by a `mode` backend, from the
+/// blueprint
+pub fn render_verifier(
+    wrapper_vk: &str,
+    caip2: &str,
+    mode: &str,
+    encoding: WrapperEncoding,
+) -> String {
+    // Only the Tendermint wrapper outputs commit the `transition` flag
+    let (outputs_version, transition) = match mode {
+        "HELIOS" => (helios_recursion_types::OUTPUTS_VERSION, false),
+        "GRANDPA" => (grandpa_recursion_types::OUTPUTS_VERSION, false),
+        "BITCOIN" => (bitcoin_recursion_types::OUTPUTS_VERSION, false),
+        _ => (tendermint_recursion_types::OUTPUTS_VERSION, true),
+    };
+    VERIFIER_BLUEPRINT
+        .replace("{ wrapper_vk }", wrapper_vk)
+        .replace("{ outputs_version }", &outputs_version.to_string())
+        .replace("{ abi_outputs }", &encoding.is_abi().to_string())
+        .replace("{ transition_outputs }", &transition.to_string())
+        .replace("{ chain_id }", caip2)
+}
+
+/// The CAIP-2 chain id the wrapper proofs of a chain commit, read from its latest wrapper
+/// proof or, before its first commit, from the configuration of its backend
+fn committed_caip2(db_path: &Path, chain_id: &str, mode: &str) -> Result<String> {
+    let state = StateManager::for_chain(db_path, chain_id)?.load_state()?;
+    if let Some(proof) = state.and_then(|state| state.most_recent_wrapper_proof) {
+        let public_values = proof.public_values.to_vec();
+        let caip2 = match mode {
+            "HELIOS" => helios_recursion_types::WrapperCircuitOutputs::decode(&public_values)
+                .map(|outputs| outputs.chain_id)
+                .ok(),
+            "GRANDPA" => grandpa_recursion_types::WrapperCircuitOutputs::decode(&public_values)
+                .map(|outputs| outputs.chain_id)
+                .ok(),
+            "BITCOIN" => bitcoin_recursion_types::WrapperCircuitOutputs::decode(&public_values)
+                .map(|outputs| outputs.chain_id)
+                .ok(),
+            _ => tendermint_recursion_types::WrapperCircuitOutputs::decode(&public_values)
+                .map(|outputs| outputs.chain_id)
+                .ok(),
+        };
+        return caip2.context(format!(
+            "The latest wrapper proof of {} commits no {} wrapper outputs",
+            chain_id, mode
+        ));
+    }
+    match mode {
+        "HELIOS" => Ok(EthereumNetwork::from_env()?.caip2()),
+        "TENDERMINT" => tendermint_chain(chain_id)?.caip2(),
+        _ => Err(anyhow::anyhow!(
+            "The {} chain id is read from the chain when the circuits are generated, commit a \
+             proof of {} first",
+            mode,
+            chain_id
+        )),
+    }
+}
+
+/// Writes the foundry project of the verifier contract of a chain proven by a `mode` backend to
+/// `out`
+pub fn generate_verifier(db_path: &Path, chain_id: &str, mode: &str, out: &Path) -> Result<()> {
+    if !matches!(mode, "HELIOS" | "TENDERMINT" | "GRANDPA" | "BITCOIN") {
+        return Err(anyhow::anyhow!(
+            "Only the light client backends commit wrapper outputs, not {}",
+            mode
+        ));
+    }
+    let caip2 = committed_caip2(db_path, chain_id, mode)?;
+    let client = ProverClient::builder().cpu().build();
+    let (_, wrapper_vk) = client.setup(wrapper_elf(chain_id, mode));
+    let encoding = WrapperEncoding::from_env()?;
+
+    std::fs::create_dir_all(out.join("src"))
+        .context(format!("Failed to create {}", out.display()))?;
+    for (path, contents) in [
+        ("foundry.toml", FOUNDRY_CONFIG.to_string()),
+        ("src/ISP1Verifier.sol", SP1_VERIFIER_INTERFACE.to_string()),
+        (
+            "src/LightwaveVerifier.sol",
+            render_verifier(&wrapper_vk.bytes32(), &caip2, mode, encoding),
+        ),
+    ] {
+        let path = out.join(path);
+        std::fs::write(&path, contents).context(format!("Failed to write {}", path.display()))?;
+    }
+    tracing::info!(
+        "Verifier of {} ({}, wrapper vk {}, {} outputs) written to {}",
+        chain_id,
+        caip2,
+        wrapper_vk.bytes32(),
+        encoding,
+        out.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_verifier() {
+        let vk = format!("0x{}", "ab".repeat(32));
+        let contract = render_verifier(&vk, "eip155:1", "HELIOS", WrapperEncoding::Abi);
+        assert!(!contract.contains("{ "));
+        assert!(contract.contains(&format!("bytes32 public constant WRAPPER_VKEY = {};", vk)));
+        assert!(contract.contains("uint8 public constant OUTPUTS_VERSION = 1;"));
+        assert!(contract.contains("bool public constant ABI_OUTPUTS = true;"));
+        assert!(contract.contains("bool public constant TRANSITION_OUTPUTS = false;"));
+        assert!(contract.contains("string public constant CHAIN_ID = \"eip155:1\";"));
+        assert!(contract.contains("function updateState(bytes calldata proof, bytes calldata"));
+
+        // The Tendermint wrapper outputs commit the transition flag
+        let contract = render_verifier(&vk, "cosmos:pion-1", "TENDERMINT", WrapperEncoding::Borsh);
+        assert!(!contract.contains("{ "));
+        assert!(contract.contains("uint8 public constant OUTPUTS_VERSION = 2;"));
+        assert!(contract.contains("bool public constant ABI_OUTPUTS = false;"));
+        assert!(contract.contains("bool public constant TRANSITION_OUTPUTS = true;"));
+    }
+}
@@ -1,10 +1,26 @@
-use anyhow::Result;
-use rusqlite::{Connection, OptionalExtension, params};
+use anyhow::{Context, Result};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, TransactionBehavior, params};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sp1_sdk::SP1ProofWithPublicValues;
-use std::path::Path;
+use std::{
+    io::{Read, Write},
+    ops::Deref,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{
+    control::ControlState,
+    encoding::to_hex,
+    rounds::{RoundMetadata, RoundTimings},
+    state_diff::StateDiffCheck,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceState {
     pub most_recent_recursive_proof: Option<SP1ProofWithPublicValues>,
     pub most_recent_wrapper_proof: Option<SP1ProofWithPublicValues>,
@@ -12,56 +28,312 @@ pub struct ServiceState {
     pub trusted_height: u64,
     pub trusted_root: [u8; 32],
     pub update_counter: u64,
+    /// Unix timestamp (seconds) at which the most recent proof was committed
+    pub generated_at: Option<u64>,
+}
+
+/// The trusted head of a proof chain, a [`ServiceState`] without its proofs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedHead {
+    pub trusted_slot: u64,
+    pub trusted_height: u64,
+    pub trusted_root: [u8; 32],
+    pub update_counter: u64,
+    pub generated_at: Option<u64>,
+}
+
+/// A round with a recursive proof that is not committed yet. It is persisted so a service
+/// restarted before the wrapper proof completes the round instead of proving it again
+#[derive(Debug, Clone)]
+pub struct PendingRound {
+    /// The update the round commits
+    pub update_counter: u64,
+    /// Trusted height and root of the state the round was proven from
+    pub parent_height: u64,
+    pub parent_root: [u8; 32],
+    /// The base outputs, serialized by the backend
+    pub base_outputs: Vec<u8>,
+    pub recursive_proof: SP1ProofWithPublicValues,
+    pub recursion_inputs_sha256: String,
+    pub base_ms: u64,
+    pub recursive_ms: u64,
+}
+
+/// A restart of a proof chain from its latest wrapper proof after the recursive circuit was
+/// regenerated. A proof chain is identified by its recursive circuit vk: the head the wrapper
+/// proof of the anchor update committed to is the trusted head of the next proof chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LineageRecord {
+    /// The update whose wrapper proof anchors the next proof chain
+    pub anchor_update: u64,
+    pub anchor_height: u64,
+    pub anchor_root: [u8; 32],
+    /// Hash of the anchor wrapper proof, see [`proof_hash`]
+    pub anchor_proof_hash: [u8; 32],
+    /// Recursive circuit vk of the previous proof chain
+    pub previous_vk: String,
+    /// Recursive circuit vk of the proof chain started from the anchor
+    pub vk: String,
+    pub restarted_at: u64,
+}
+
+/// The sync committee a round attested to for a beacon period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitteePeriod {
+    pub period: u64,
+    pub committee_hash: [u8; 32],
+}
+
+/// An entry of the committee index of a proof chain, the committee a period maps to and the
+/// first update that committed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeRecord {
+    pub period: u64,
+    pub committee_hash: [u8; 32],
+    pub update_counter: u64,
+}
+
+/// A note of an operator on a proof chain, about one of its updates or, without one, about
+/// an incident of the chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Annotation {
+    pub id: u64,
+    /// The annotated update, `None` for an incident
+    pub update_counter: Option<u64>,
+    pub text: String,
+    pub created_at: u64,
+}
+
+/// A proven update held back from publication until `publish_at`, see
+/// [`crate::publication`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HeldUpdate {
+    pub update_counter: u64,
+    pub height: u64,
+    pub root: [u8; 32],
+    pub proven_at: u64,
+    pub publish_at: u64,
+    /// Whether an operator aborted the publication
+    pub aborted: bool,
+}
+
+/// The outputs of a candidate recursive circuit of a shadow chain for the recursion inputs of
+/// a production round, see [`crate::shadow`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShadowRound {
+    /// The name of the shadow chain
+    pub name: String,
+    /// The production update whose recursion inputs the candidate circuit ran on
+    pub update_counter: u64,
+    pub production_public_values: Vec<u8>,
+    /// The public values of the candidate circuit, `None` if it failed
+    pub shadow_public_values: Option<Vec<u8>>,
+    /// Cycles of the candidate circuit, when executed
+    pub cycles: Option<u64>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    pub recorded_at: u64,
+}
+
+impl ShadowRound {
+    /// Whether the candidate circuit committed the same public values as production
+    pub fn matches(&self) -> bool {
+        self.shadow_public_values.as_ref() == Some(&self.production_public_values)
+    }
+}
+
+/// The globally unique id of update `update_counter` of the proof chain `chain_id` proven by
+/// `backend`, e.g. `helios:default:42`. Update counters restart with every proof chain, the
+/// id correlates an update across the database, the API, webhooks, relays and logs
+pub fn update_id(backend: &str, chain_id: &str, update_counter: u64) -> String {
+    format!(
+        "{}:{}:{}",
+        backend.to_ascii_lowercase(),
+        chain_id,
+        update_counter
+    )
+}
+
+/// Metadata of a committed update, hash-chained to the previous update.
+///
+/// `hash = sha256(update_counter || slot || height || root || proof_hash || prev_hash)`
+/// with integers encoded as big-endian u64. Observers that keep the latest hash can
+/// detect if history was ever rewritten, e.g. a different root re-proven at a served height.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpdateRecord {
+    pub update_counter: u64,
+    /// See [`update_id`], not covered by the hash
+    #[serde(default)]
+    pub update_id: String,
+    pub slot: u64,
+    pub height: u64,
+    pub root: [u8; 32],
+    /// sha256 over the wrapper proof bytes followed by its public values
+    pub proof_hash: [u8; 32],
+    /// Hash of the previous record, zero for the first update
+    pub prev_hash: [u8; 32],
+    pub hash: [u8; 32],
+    pub committed_at: Option<u64>,
+}
+
+impl UpdateRecord {
+    /// Computes the chained hash of a record
+    pub fn compute_hash(
+        update_counter: u64,
+        slot: u64,
+        height: u64,
+        root: &[u8; 32],
+        proof_hash: &[u8; 32],
+        prev_hash: &[u8; 32],
+    ) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(update_counter.to_be_bytes());
+        hasher.update(slot.to_be_bytes());
+        hasher.update(height.to_be_bytes());
+        hasher.update(root);
+        hasher.update(proof_hash);
+        hasher.update(prev_hash);
+        hasher.finalize().into()
+    }
+
+    /// Returns true if the record's hash matches its contents and links to `prev_hash`
+    pub fn verify(&self, prev_hash: &[u8; 32]) -> bool {
+        &self.prev_hash == prev_hash
+            && self.hash
+                == Self::compute_hash(
+                    self.update_counter,
+                    self.slot,
+                    self.height,
+                    &self.root,
+                    &self.proof_hash,
+                    &self.prev_hash,
+                )
+    }
+}
+
+/// Hashes a wrapper proof and its public values for inclusion in the update history
+pub fn proof_hash(proof: &SP1ProofWithPublicValues) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(proof.bytes());
+    hasher.update(proof.public_values.as_slice());
+    hasher.finalize().into()
+}
+
+/// Runs the SQLite integrity check on a database, without opening it as a state database
+/// that would be migrated
+pub fn integrity_check(db_path: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let errors = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if errors != ["ok"] {
+        return Err(anyhow::anyhow!("{}", errors.join("; ")));
+    }
+    Ok(())
+}
+
+/// The namespace of the state row used by Helios and single-chain Tendermint deployments
+pub const DEFAULT_CHAIN_ID: &str = "default";
+
+/// Writers wait this long for a lock held by another connection before failing with
+/// `SQLITE_BUSY`
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections to the state database shared by the API and the prover loops
+pub type DbPool = r2d2::Pool<SqliteConnectionManager>;
+
+/// Opens a pool of `DB_POOL_SIZE` (16 by default) connections to the state database and
+/// migrates it. Every prover loop holds one connection for its lifetime, the API borrows
+/// the others per request
+pub fn open_pool(db_path: &Path) -> Result<DbPool> {
+    let size = match std::env::var("DB_POOL_SIZE") {
+        Ok(size) => size.parse().context("Invalid DB_POOL_SIZE")?,
+        Err(_) => 16,
+    };
+    let manager = SqliteConnectionManager::file(db_path).with_init(configure);
+    let pool = r2d2::Pool::builder()
+        .max_size(size)
+        .build(manager)
+        .context(format!("Failed to open {}", db_path.display()))?;
+    migrate(&pool.get()?)?;
+    Ok(pool)
 }
 
+/// Lets readers proceed while a proof is committed and makes writers wait for each other
+fn configure(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+}
+
+/// A connection owned by its manager or borrowed from a [`DbPool`]
+enum Conn {
+    Owned(Connection),
+    Pooled(PooledConnection<SqliteConnectionManager>),
+}
+
+impl Deref for Conn {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            Conn::Owned(conn) => conn,
+            Conn::Pooled(conn) => conn,
+        }
+    }
+}
+
+/// Persists the state of one proof chain. Several chains can share a database,
+/// every row is namespaced by the chain id the manager was opened for.
 pub struct StateManager {
-    conn: Connection,
+    conn: Conn,
+    chain_id: String,
 }
 
 impl StateManager {
     pub fn new(db_path: &Path) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
+        Self::for_chain(db_path, DEFAULT_CHAIN_ID)
+    }
 
-        // Create the state table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS service_state (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                most_recent_recursive_proof BLOB,
-                most_recent_wrapper_proof BLOB,
-                trusted_slot INTEGER NOT NULL,
-                trusted_height INTEGER NOT NULL,
-                trusted_root BLOB NOT NULL,
-                update_counter INTEGER NOT NULL
-            )",
-            [],
-        )?;
+    pub fn for_chain(db_path: &Path, chain_id: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)?;
+        configure(&mut conn)?;
+        migrate(&conn)?;
+        Ok(Self {
+            conn: Conn::Owned(conn),
+            chain_id: chain_id.to_string(),
+        })
+    }
 
-        Ok(Self { conn })
+    /// A manager of a chain on a connection of `pool`, which it holds until it is dropped
+    pub fn from_pool(pool: &DbPool, chain_id: &str) -> Result<Self> {
+        Ok(Self {
+            conn: Conn::Pooled(pool.get()?),
+            chain_id: chain_id.to_string(),
+        })
     }
 
-    pub fn from_env() -> Result<Self> {
+    pub fn from_env_for_chain(chain_id: &str) -> Result<Self> {
         let db_path = std::env::var("SERVICE_STATE_DB_PATH")
             .unwrap_or_else(|_| "service_state.db".to_string());
-        let conn = Connection::open(db_path)?;
+        Self::for_chain(Path::new(&db_path), chain_id)
+    }
 
-        // Create the state table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS service_state (
-                        id INTEGER PRIMARY KEY CHECK (id = 1),
-                        most_recent_recursive_proof BLOB,
-                        most_recent_wrapper_proof BLOB,
-                        trusted_slot INTEGER NOT NULL,
-                        trusted_height INTEGER NOT NULL,
-                        trusted_root BLOB NOT NULL,
-                        update_counter INTEGER NOT NULL
-                    )",
-            [],
-        )?;
+    /// The chain this manager reads and writes state for
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
 
-        Ok(Self { conn })
+    /// The schema version of the database, see [`MIGRATIONS`]
+    pub fn schema_version(&self) -> Result<u32> {
+        schema_version(&self.conn)
     }
 
     pub fn save_state(&self, state: &ServiceState) -> Result<()> {
+        self.write_state(&self.conn, state)
+    }
+
+    fn write_state(&self, conn: &Connection, state: &ServiceState) -> Result<()> {
         let recursive_proof_bytes = state
             .most_recent_recursive_proof
             .as_ref()
@@ -74,18 +346,29 @@ impl StateManager {
             .map(|proof| serde_json::to_vec(proof))
             .transpose()?;
 
-        self.conn.execute(
-            "INSERT OR REPLACE INTO service_state (
-                id, most_recent_recursive_proof, most_recent_wrapper_proof,
-                trusted_slot, trusted_height, trusted_root, update_counter
-            ) VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+        // The backend and vk the row is bound to are kept
+        conn.execute(
+            "INSERT INTO service_state (
+                chain_id, most_recent_recursive_proof, most_recent_wrapper_proof,
+                trusted_slot, trusted_height, trusted_root, update_counter, generated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (chain_id) DO UPDATE SET
+                most_recent_recursive_proof = excluded.most_recent_recursive_proof,
+                most_recent_wrapper_proof = excluded.most_recent_wrapper_proof,
+                trusted_slot = excluded.trusted_slot,
+                trusted_height = excluded.trusted_height,
+                trusted_root = excluded.trusted_root,
+                update_counter = excluded.update_counter,
+                generated_at = excluded.generated_at",
             params![
+                self.chain_id,
                 recursive_proof_bytes,
                 wrapper_proof_bytes,
                 state.trusted_slot,
                 state.trusted_height,
                 state.trusted_root,
                 state.update_counter,
+                state.generated_at,
             ],
         )?;
 
@@ -95,12 +378,12 @@ impl StateManager {
     pub fn load_state(&self) -> Result<Option<ServiceState>> {
         let mut stmt = self.conn.prepare(
             "SELECT most_recent_recursive_proof,  most_recent_wrapper_proof,
-                    trusted_slot, trusted_height, trusted_root, update_counter 
-             FROM service_state WHERE id = 1",
+                    trusted_slot, trusted_height, trusted_root, update_counter, generated_at
+             FROM service_state WHERE chain_id = ?1",
         )?;
 
         let state = stmt
-            .query_row([], |row| {
+            .query_row(params![self.chain_id], |row| {
                 let recursive_proof_bytes: Option<Vec<u8>> = row.get(0)?;
                 let most_recent_recursive_proof = recursive_proof_bytes
                     .map(|bytes| serde_json::from_slice(&bytes))
@@ -120,6 +403,7 @@ impl StateManager {
                     trusted_height: row.get(3)?,
                     trusted_root: row.get(4)?,
                     update_counter: row.get(5)?,
+                    generated_at: row.get(6)?,
                 })
             })
             .optional()?;
@@ -127,6 +411,760 @@ impl StateManager {
         Ok(state)
     }
 
+    /// Loads the trusted head of the chain without reading its proofs
+    pub fn load_head(&self) -> Result<Option<TrustedHead>> {
+        let head = self
+            .conn
+            .query_row(
+                "SELECT trusted_slot, trusted_height, trusted_root, update_counter, generated_at
+                 FROM service_state WHERE chain_id = ?1",
+                params![self.chain_id],
+                |row| {
+                    Ok(TrustedHead {
+                        trusted_slot: row.get(0)?,
+                        trusted_height: row.get(1)?,
+                        trusted_root: row.get(2)?,
+                        update_counter: row.get(3)?,
+                        generated_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(head)
+    }
+
+    /// Saves the state of a newly committed update and appends it to the update history,
+    /// together with the serialized inputs of its base proof if the backend provides them
+    /// and the metadata of the round that proved it.
+    ///
+    /// All writes happen in one transaction so the history never diverges from the state.
+    pub fn commit_update(
+        &self,
+        mode: &str,
+        state: &ServiceState,
+        base_inputs: Option<&[u8]>,
+        round: Option<&RoundMetadata>,
+        committees: &[CommitteePeriod],
+    ) -> Result<UpdateRecord> {
+        let wrapper_proof = state
+            .most_recent_wrapper_proof
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cannot commit an update without a wrapper proof"))?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let prev_hash: [u8; 32] = tx
+            .query_row(
+                "SELECT hash FROM update_history WHERE chain_id = ?1
+                 ORDER BY update_counter DESC LIMIT 1",
+                params![self.chain_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or([0; 32]);
+
+        // Never serve two different roots for the same height
+        let conflicting_root: Option<[u8; 32]> = tx
+            .query_row(
+                "SELECT root FROM update_history
+                 WHERE chain_id = ?1 AND height = ?2 AND root != ?3 LIMIT 1",
+                params![self.chain_id, state.trusted_height, state.trusted_root],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(conflicting_root) = conflicting_root {
+            return Err(anyhow::anyhow!(
+                "Height {} was already committed with root {}, refusing to commit {}",
+                state.trusted_height,
+                to_hex(conflicting_root),
+                to_hex(state.trusted_root)
+            ));
+        }
+        // Never map a period to two different committees
+        check_committees(&tx, &self.chain_id, committees)?;
+
+        let proof_hash = proof_hash(wrapper_proof);
+        let record = UpdateRecord {
+            update_counter: state.update_counter,
+            update_id: update_id(mode, &self.chain_id, state.update_counter),
+            slot: state.trusted_slot,
+            height: state.trusted_height,
+            root: state.trusted_root,
+            proof_hash,
+            prev_hash,
+            hash: UpdateRecord::compute_hash(
+                state.update_counter,
+                state.trusted_slot,
+                state.trusted_height,
+                &state.trusted_root,
+                &proof_hash,
+                &prev_hash,
+            ),
+            committed_at: state.generated_at,
+        };
+
+        // A plain INSERT fails if the counter was already committed, history is append-only
+        tx.execute(
+            "INSERT INTO update_history (
+                chain_id, update_counter, slot, height, root, proof_hash, prev_hash, hash,
+                committed_at, update_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                self.chain_id,
+                record.update_counter,
+                record.slot,
+                record.height,
+                record.root,
+                record.proof_hash,
+                record.prev_hash,
+                record.hash,
+                record.committed_at,
+                record.update_id,
+            ],
+        )?;
+        if let Some(base_inputs) = base_inputs {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(base_inputs)?;
+            tx.execute(
+                "INSERT INTO base_inputs (chain_id, update_counter, inputs) VALUES (?1, ?2, ?3)",
+                params![self.chain_id, record.update_counter, encoder.finish()?],
+            )?;
+        }
+        if let Some(round) = round {
+            tx.execute(
+                "INSERT INTO round_metadata (chain_id, update_counter, metadata)
+                 VALUES (?1, ?2, ?3)",
+                params![
+                    self.chain_id,
+                    record.update_counter,
+                    serde_json::to_string(round)?
+                ],
+            )?;
+        }
+        for committee in committees {
+            tx.execute(
+                "INSERT OR IGNORE INTO committee_periods (
+                    chain_id, period, committee_hash, update_counter
+                ) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    self.chain_id,
+                    committee.period,
+                    committee.committee_hash,
+                    record.update_counter
+                ],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM pending_rounds WHERE chain_id = ?1 AND update_counter <= ?2",
+            params![self.chain_id, record.update_counter],
+        )?;
+        self.write_state(&tx, state)?;
+        tx.commit()?;
+
+        Ok(record)
+    }
+
+    /// Persists a round awaiting its wrapper proof, replacing an earlier attempt at the
+    /// same update
+    pub fn save_pending_round(&self, round: &PendingRound) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO pending_rounds (
+                chain_id, update_counter, parent_height, parent_root, base_outputs,
+                recursive_proof, recursion_inputs_sha256, base_ms, recursive_ms
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                self.chain_id,
+                round.update_counter,
+                round.parent_height,
+                round.parent_root,
+                round.base_outputs,
+                serde_json::to_vec(&round.recursive_proof)?,
+                round.recursion_inputs_sha256,
+                round.base_ms,
+                round.recursive_ms,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Loads the persisted round committing update `update_counter`, if any
+    pub fn load_pending_round(&self, update_counter: u64) -> Result<Option<PendingRound>> {
+        let round = self
+            .conn
+            .query_row(
+                "SELECT parent_height, parent_root, base_outputs, recursive_proof,
+                        recursion_inputs_sha256, base_ms, recursive_ms
+                 FROM pending_rounds WHERE chain_id = ?1 AND update_counter = ?2",
+                params![self.chain_id, update_counter],
+                |row| {
+                    let recursive_proof_bytes: Vec<u8> = row.get(3)?;
+                    Ok(PendingRound {
+                        update_counter,
+                        parent_height: row.get(0)?,
+                        parent_root: row.get(1)?,
+                        base_outputs: row.get(2)?,
+                        recursive_proof: serde_json::from_slice(&recursive_proof_bytes)
+                            .map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?,
+                        recursion_inputs_sha256: row.get(4)?,
+                        base_ms: row.get(5)?,
+                        recursive_ms: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(round)
+    }
+
+    /// Restarts the proof chain from its committed head: the recursive proof of the previous
+    /// circuit and its pending rounds are dropped, the wrapper proof keeps being served until
+    /// the next proof chain commits, and the restart is recorded in the lineage
+    pub fn restart_proof_chain(&self, record: &LineageRecord) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE service_state SET most_recent_recursive_proof = NULL WHERE chain_id = ?1",
+            params![self.chain_id],
+        )?;
+        tx.execute(
+            "DELETE FROM pending_rounds WHERE chain_id = ?1",
+            params![self.chain_id],
+        )?;
+        self.insert_lineage(&tx, record)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Re-anchors the proof chain at a new trusted checkpoint when it can't be restarted from
+    /// its wrapper proof, see [`crate::recover`]: both proofs and the pending rounds are
+    /// dropped, the trusted slot and height move to the checkpoint and the restart is recorded
+    /// in the lineage. No proof is served until the next proof chain commits
+    pub fn reanchor_proof_chain(
+        &self,
+        record: &LineageRecord,
+        trusted_slot: u64,
+        trusted_height: u64,
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE service_state SET most_recent_recursive_proof = NULL,
+                most_recent_wrapper_proof = NULL, trusted_slot = ?2, trusted_height = ?3
+             WHERE chain_id = ?1",
+            params![self.chain_id, trusted_slot, trusted_height],
+        )?;
+        tx.execute(
+            "DELETE FROM pending_rounds WHERE chain_id = ?1",
+            params![self.chain_id],
+        )?;
+        self.insert_lineage(&tx, record)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert_lineage(&self, conn: &Connection, record: &LineageRecord) -> Result<()> {
+        conn.execute(
+            "INSERT INTO lineage (
+                chain_id, anchor_update, anchor_height, anchor_root, anchor_proof_hash,
+                previous_vk, vk, restarted_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                self.chain_id,
+                record.anchor_update,
+                record.anchor_height,
+                record.anchor_root,
+                record.anchor_proof_hash,
+                record.previous_vk,
+                record.vk,
+                record.restarted_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Initializes a chain that committed nothing yet from the latest update of another
+    /// deployment, see [`crate::warm_start`]. The update becomes the committed head bound to
+    /// `mode` and the vk of `lineage`, its record the first of the history, and the warm start
+    /// is recorded in the lineage
+    pub fn warm_start(
+        &self,
+        state: &ServiceState,
+        record: &UpdateRecord,
+        lineage: &LineageRecord,
+        mode: &str,
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let committed: Option<u64> = tx.query_row(
+            "SELECT MAX(update_counter) FROM update_history WHERE chain_id = ?1",
+            params![self.chain_id],
+            |row| row.get(0),
+        )?;
+        if let Some(committed) = committed {
+            return Err(anyhow::anyhow!(
+                "Chain {} already committed update {}, refusing to warm start it",
+                self.chain_id,
+                committed
+            ));
+        }
+        // Rounds proven from the trusted checkpoint are dropped
+        for table in ["pending_rounds", "publication_holds"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE chain_id = ?1", table),
+                params![self.chain_id],
+            )?;
+        }
+        self.write_state(&tx, state)?;
+        tx.execute(
+            "UPDATE service_state SET mode = ?2, recursive_vk = ?3 WHERE chain_id = ?1",
+            params![self.chain_id, mode, lineage.vk],
+        )?;
+        tx.execute(
+            "INSERT INTO update_history (
+                chain_id, update_counter, slot, height, root, proof_hash, prev_hash, hash,
+                committed_at, update_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                self.chain_id,
+                record.update_counter,
+                record.slot,
+                record.height,
+                record.root,
+                record.proof_hash,
+                record.prev_hash,
+                record.hash,
+                record.committed_at,
+                record.update_id,
+            ],
+        )?;
+        self.insert_lineage(&tx, lineage)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Loads the restarts of the proof chain, oldest first
+    pub fn load_lineage(&self) -> Result<Vec<LineageRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT anchor_update, anchor_height, anchor_root, anchor_proof_hash, previous_vk,
+                    vk, restarted_at
+             FROM lineage WHERE chain_id = ?1 ORDER BY anchor_update ASC",
+        )?;
+        let records = stmt
+            .query_map(params![self.chain_id], |row| {
+                Ok(LineageRecord {
+                    anchor_update: row.get(0)?,
+                    anchor_height: row.get(1)?,
+                    anchor_root: row.get(2)?,
+                    anchor_proof_hash: row.get(3)?,
+                    previous_vk: row.get(4)?,
+                    vk: row.get(5)?,
+                    restarted_at: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// Fails if a period of `committees` is indexed with another committee, a round attesting
+    /// to them must not be committed
+    pub fn check_committees(&self, committees: &[CommitteePeriod]) -> Result<()> {
+        check_committees(&self.conn, &self.chain_id, committees)
+    }
+
+    /// Loads up to `limit` entries of the committee index starting at period `from`
+    pub fn load_committees(&self, from: u64, limit: u64) -> Result<Vec<CommitteeRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT period, committee_hash, update_counter FROM committee_periods
+             WHERE chain_id = ?1 AND period >= ?2 ORDER BY period ASC LIMIT ?3",
+        )?;
+        let records = stmt
+            .query_map(params![self.chain_id, from, limit], |row| {
+                Ok(CommitteeRecord {
+                    period: row.get(0)?,
+                    committee_hash: row.get(1)?,
+                    update_counter: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// Annotates the update `update_counter` of the proof chain, or the chain itself
+    pub fn annotate(&self, update_counter: Option<u64>, text: &str) -> Result<Annotation> {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.conn.execute(
+            "INSERT INTO annotations (chain_id, update_counter, text, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![self.chain_id, update_counter, text, created_at],
+        )?;
+        Ok(Annotation {
+            id: self.conn.last_insert_rowid() as u64,
+            update_counter,
+            text: text.to_string(),
+            created_at,
+        })
+    }
+
+    /// The annotations of the updates `from..=to` of the proof chain, oldest first
+    pub fn load_update_annotations(&self, from: u64, to: u64) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, update_counter, text, created_at FROM annotations
+             WHERE chain_id = ?1 AND update_counter BETWEEN ?2 AND ?3 ORDER BY id ASC",
+        )?;
+        let annotations = stmt
+            .query_map(params![self.chain_id, from, to], read_annotation)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(annotations)
+    }
+
+    /// The `limit` latest annotations of the proof chain and its updates, latest first
+    pub fn load_recent_annotations(&self, limit: u64) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, update_counter, text, created_at FROM annotations
+             WHERE chain_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let annotations = stmt
+            .query_map(params![self.chain_id, limit], read_annotation)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(annotations)
+    }
+
+    /// Holds a proven update back from publication, replacing the hold of an earlier attempt
+    pub fn hold_update(&self, held: &HeldUpdate) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO publication_holds (
+                chain_id, update_counter, height, root, proven_at, publish_at, aborted
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                self.chain_id,
+                held.update_counter,
+                held.height,
+                held.root,
+                held.proven_at,
+                held.publish_at,
+                held.aborted
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The update of the proof chain held back from publication, if any
+    pub fn load_hold(&self) -> Result<Option<HeldUpdate>> {
+        let held = self
+            .conn
+            .query_row(
+                "SELECT update_counter, height, root, proven_at, publish_at, aborted
+                 FROM publication_holds WHERE chain_id = ?1",
+                params![self.chain_id],
+                |row| {
+                    Ok(HeldUpdate {
+                        update_counter: row.get(0)?,
+                        height: row.get(1)?,
+                        root: row.get(2)?,
+                        proven_at: row.get(3)?,
+                        publish_at: row.get(4)?,
+                        aborted: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(held)
+    }
+
+    /// Aborts the publication of the held update `update_counter`. Returns whether it was held
+    pub fn abort_hold(&self, update_counter: u64) -> Result<bool> {
+        let aborted = self.conn.execute(
+            "UPDATE publication_holds SET aborted = 1 WHERE chain_id = ?1 AND update_counter = ?2",
+            params![self.chain_id, update_counter],
+        )?;
+        Ok(aborted > 0)
+    }
+
+    /// Ends the hold of the proof chain. The pending rounds of an aborted hold are dropped, so
+    /// the aborted round is proven again instead of being resumed
+    pub fn release_hold(&self, aborted: bool) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM publication_holds WHERE chain_id = ?1",
+            params![self.chain_id],
+        )?;
+        if aborted {
+            tx.execute(
+                "DELETE FROM pending_rounds WHERE chain_id = ?1",
+                params![self.chain_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Records the outputs of a shadow chain for a production update, replacing an earlier
+    /// attempt at the same update
+    pub fn save_shadow_round(&self, round: &ShadowRound) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO shadow_rounds (
+                chain_id, name, update_counter, production_public_values, shadow_public_values,
+                cycles, error, elapsed_ms, recorded_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                self.chain_id,
+                round.name,
+                round.update_counter,
+                round.production_public_values,
+                round.shadow_public_values,
+                round.cycles,
+                round.error,
+                round.elapsed_ms,
+                round.recorded_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Loads up to `limit` rounds of the shadow chain `name` starting at update `from`
+    pub fn load_shadow_rounds(
+        &self,
+        name: &str,
+        from: u64,
+        limit: u64,
+    ) -> Result<Vec<ShadowRound>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT update_counter, production_public_values, shadow_public_values, cycles,
+                    error, elapsed_ms, recorded_at
+             FROM shadow_rounds
+             WHERE chain_id = ?1 AND name = ?2 AND update_counter >= ?3
+             ORDER BY update_counter ASC LIMIT ?4",
+        )?;
+        let rounds = stmt
+            .query_map(params![self.chain_id, name, from, limit], |row| {
+                Ok(ShadowRound {
+                    name: name.to_string(),
+                    update_counter: row.get(0)?,
+                    production_public_values: row.get(1)?,
+                    shadow_public_values: row.get(2)?,
+                    cycles: row.get(3)?,
+                    error: row.get(4)?,
+                    elapsed_ms: row.get(5)?,
+                    recorded_at: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rounds)
+    }
+
+    /// Loads the serialized inputs of the base proof of update `update_counter`, if stored
+    pub fn load_base_inputs(&self, update_counter: u64) -> Result<Option<Vec<u8>>> {
+        let compressed: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT inputs FROM base_inputs WHERE chain_id = ?1 AND update_counter = ?2",
+                params![self.chain_id, update_counter],
+                |row| row.get(0),
+            )
+            .optional()?;
+        compressed
+            .map(|compressed| {
+                let mut inputs = Vec::new();
+                GzDecoder::new(compressed.as_slice()).read_to_end(&mut inputs)?;
+                Ok(inputs)
+            })
+            .transpose()
+    }
+
+    /// Loads the metadata recorded when update `update_counter` was committed, if any
+    pub fn load_round_metadata(&self, update_counter: u64) -> Result<Option<RoundMetadata>> {
+        let metadata: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT metadata FROM round_metadata WHERE chain_id = ?1 AND update_counter = ?2",
+                params![self.chain_id, update_counter],
+                |row| row.get(0),
+            )
+            .optional()?;
+        metadata
+            .map(|metadata| Ok(serde_json::from_str(&metadata)?))
+            .transpose()
+    }
+
+    /// Loads the proving step durations of the latest `limit` committed rounds, latest first
+    pub fn load_recent_round_timings(&self, limit: u64) -> Result<Vec<RoundTimings>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT metadata FROM round_metadata WHERE chain_id = ?1
+             ORDER BY update_counter DESC LIMIT ?2",
+        )?;
+        let metadata = stmt
+            .query_map(params![self.chain_id, limit], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        metadata
+            .iter()
+            .map(|metadata| {
+                let metadata: RoundMetadata = serde_json::from_str(metadata)?;
+                Ok(metadata.timings)
+            })
+            .collect()
+    }
+
+    /// Loads up to `limit` history records starting at update `from`, in commit order
+    pub fn load_history(&self, from: u64, limit: u64) -> Result<Vec<UpdateRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT update_counter, slot, height, root, proof_hash, prev_hash, hash, committed_at,
+                    update_id
+             FROM update_history WHERE chain_id = ?1 AND update_counter >= ?2
+             ORDER BY update_counter ASC LIMIT ?3",
+        )?;
+
+        let records = stmt
+            .query_map(params![self.chain_id, from, limit], read_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(records)
+    }
+
+    /// Checks that every record of the update history chains to its predecessor and returns
+    /// the tip, `None` if nothing was committed yet
+    pub fn verify_history(&self) -> Result<Option<UpdateRecord>> {
+        // The history of a warm-started chain starts at the update it was warm started from,
+        // whose predecessors are in the history of the deployment it joined
+        let mut prev_hash = match self.load_history(0, 1)?.first() {
+            Some(first) if self.is_warm_start(first)? => first.prev_hash,
+            _ => [0; 32],
+        };
+        let mut tip = None;
+        let mut from = 0;
+        loop {
+            let records = self.load_history(from, 1000)?;
+            let Some(last) = records.last() else {
+                break;
+            };
+            from = last.update_counter + 1;
+            for record in records {
+                if !record.verify(&prev_hash) {
+                    return Err(anyhow::anyhow!(
+                        "Update {} does not chain to its predecessor",
+                        record.update_counter
+                    ));
+                }
+                prev_hash = record.hash;
+                tip = Some(record);
+            }
+        }
+        Ok(tip)
+    }
+
+    /// Whether the chain was warm started from the update of `record`, a warm start is a
+    /// lineage record anchored at the update that keeps its vk
+    fn is_warm_start(&self, record: &UpdateRecord) -> Result<bool> {
+        Ok(self.load_lineage()?.iter().any(|lineage| {
+            lineage.anchor_update == record.update_counter
+                && lineage.anchor_proof_hash == record.proof_hash
+                && lineage.previous_vk == lineage.vk
+        }))
+    }
+
+    /// Loads the record of the update that committed `height`, if any
+    pub fn load_record_at_height(&self, height: u64) -> Result<Option<UpdateRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                "SELECT update_counter, slot, height, root, proof_hash, prev_hash, hash,
+                        committed_at, update_id
+                 FROM update_history WHERE chain_id = ?1 AND height = ?2
+                 ORDER BY update_counter DESC LIMIT 1",
+                params![self.chain_id, height],
+                read_record,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    /// Commit timestamps of the updates committed since `since`, preceded by the last commit
+    /// before it, in commit order
+    pub fn load_commit_times(&self, since: u64) -> Result<Vec<u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT committed_at FROM update_history
+             WHERE chain_id = ?1 AND committed_at >= (
+                SELECT IFNULL(MAX(committed_at), 0) FROM update_history
+                WHERE chain_id = ?1 AND committed_at < ?2
+             )
+             ORDER BY committed_at ASC",
+        )?;
+        let times = stmt
+            .query_map(params![self.chain_id, since], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(times)
+    }
+
+    /// The chains that have a state row in the database, without the parked chains of
+    /// inactive backends
+    pub fn chain_ids(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id FROM service_state WHERE chain_id NOT LIKE 'parked:%'
+             ORDER BY chain_id",
+        )?;
+        let chain_ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(chain_ids)
+    }
+
+    /// The time the SLO of this chain is tracked since and whether its exhausted error budget
+    /// was alerted. Tracking starts at `now` the first time the SLO is evaluated
+    pub fn load_slo_tracking(&self, now: u64) -> Result<(u64, bool)> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO slo_state (chain_id, tracking_since, alerted) VALUES (?1, ?2, 0)",
+            params![self.chain_id, now],
+        )?;
+        Ok(self.conn.query_row(
+            "SELECT tracking_since, alerted FROM slo_state WHERE chain_id = ?1",
+            params![self.chain_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
+
+    /// Records whether the exhausted error budget of this chain was alerted
+    pub fn set_slo_alerted(&self, alerted: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE slo_state SET alerted = ?2 WHERE chain_id = ?1",
+            params![self.chain_id, alerted],
+        )?;
+        Ok(())
+    }
+
+    /// The last update whose root was checked against the canonical chain, if any
+    pub fn last_state_diff_check(&self) -> Result<Option<u64>> {
+        Ok(self.conn.query_row(
+            "SELECT MAX(update_counter) FROM state_diff_checks WHERE chain_id = ?1",
+            params![self.chain_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Records the check of the root of an update against the canonical chain
+    pub fn save_state_diff_check(&self, check: &StateDiffCheck) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO state_diff_checks
+                (chain_id, update_counter, orphaned, check_json, checked_at)
+             VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))",
+            params![
+                self.chain_id,
+                check.update_counter,
+                check.is_orphaned(),
+                serde_json::to_string(check)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The checks that found a committed root orphaned, most recent first
+    pub fn load_orphaned_roots(&self, limit: u64) -> Result<Vec<StateDiffCheck>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT check_json FROM state_diff_checks WHERE chain_id = ?1 AND orphaned = 1
+             ORDER BY update_counter DESC LIMIT ?2",
+        )?;
+        let checks = stmt
+            .query_map(params![self.chain_id, limit], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        checks
+            .iter()
+            .map(|check| Ok(serde_json::from_str(check)?))
+            .collect()
+    }
+
     pub fn initialize_state(&self, initial_slot: u64, initial_height: u64) -> Result<ServiceState> {
         let state = ServiceState {
             most_recent_recursive_proof: None,
@@ -135,12 +1173,109 @@ impl StateManager {
             trusted_height: initial_height,
             trusted_root: [0; 32],
             update_counter: 0,
+            generated_at: None,
         };
 
         self.save_state(&state)?;
         Ok(state)
     }
 
+    /// The recursive circuit vk the state row was last proven with, if recorded
+    pub fn bound_vk(&self) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT recursive_vk FROM service_state WHERE chain_id = ?1",
+                params![self.chain_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Binds the state row to the backend proving it and its recursive circuit vk. Fails if
+    /// the row was proven by another backend, whose heights and roots mean something else
+    pub fn bind(&self, mode: &str, recursive_vk: &str) -> Result<()> {
+        let bound_mode: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT mode FROM service_state WHERE chain_id = ?1",
+                params![self.chain_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        if let Some(bound_mode) = bound_mode.filter(|bound_mode| bound_mode != mode) {
+            return Err(anyhow::anyhow!(
+                "The state of chain {} was proven by the {} backend, refusing to resume it \
+                 with {}. Set CLIENT_BACKEND={}, switch backends with the admin API or run \
+                 `make delete-state`",
+                self.chain_id,
+                bound_mode,
+                mode,
+                bound_mode
+            ));
+        }
+        self.conn.execute(
+            "UPDATE service_state SET mode = ?2, recursive_vk = ?3 WHERE chain_id = ?1",
+            params![self.chain_id, mode, recursive_vk],
+        )?;
+        Ok(())
+    }
+
+    /// The persisted configuration of the prover loops, if one was recorded
+    pub fn load_control(&self) -> Result<Option<ControlState>> {
+        let row: Option<(String, String)> = self
+            .conn
+            .query_row(
+                "SELECT backend, disabled_chains FROM service_control WHERE id = 0",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        row.map(|(backend, disabled_chains)| {
+            Ok(ControlState {
+                backend,
+                disabled_chains: serde_json::from_str(&disabled_chains)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub fn save_control(&self, control: &ControlState) -> Result<()> {
+        write_control(&self.conn, control)
+    }
+
+    /// Hands the `default` chain over from the backend `from` to the backend of `control`:
+    /// the rows of `from` are parked and the parked rows of the new backend, if any, are
+    /// restored. Also persists `control`, in the same transaction
+    pub fn switch_backend(&self, from: &str, control: &ControlState) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for table in [
+            "service_state",
+            "update_history",
+            "base_inputs",
+            "round_metadata",
+            "pending_rounds",
+            "lineage",
+            "shadow_rounds",
+            "slo_state",
+            "annotations",
+            "publication_holds",
+            "state_diff_checks",
+        ] {
+            let sql = format!("UPDATE {} SET chain_id = ?2 WHERE chain_id = ?1", table);
+            tx.execute(&sql, params![DEFAULT_CHAIN_ID, parked_chain_id(from)])?;
+            tx.execute(
+                &sql,
+                params![parked_chain_id(&control.backend), DEFAULT_CHAIN_ID],
+            )?;
+        }
+        write_control(&tx, control)?;
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Deletes the entire state file.
     /// Note: This will close the current connection and delete the database file.
     /// The StateManager instance will be consumed by this operation.
@@ -160,3 +1295,503 @@ impl StateManager {
         Ok(())
     }
 }
+
+/// The namespace the `default` chain of a backend is parked under while another backend runs
+pub fn parked_chain_id(backend: &str) -> String {
+    format!("parked:{}", backend)
+}
+
+fn read_record(row: &rusqlite::Row) -> rusqlite::Result<UpdateRecord> {
+    Ok(UpdateRecord {
+        update_counter: row.get(0)?,
+        slot: row.get(1)?,
+        height: row.get(2)?,
+        root: row.get(3)?,
+        proof_hash: row.get(4)?,
+        prev_hash: row.get(5)?,
+        hash: row.get(6)?,
+        committed_at: row.get(7)?,
+        update_id: row.get(8)?,
+    })
+}
+
+fn read_annotation(row: &rusqlite::Row) -> rusqlite::Result<Annotation> {
+    Ok(Annotation {
+        id: row.get(0)?,
+        update_counter: row.get(1)?,
+        text: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+fn check_committees(
+    conn: &Connection,
+    chain_id: &str,
+    committees: &[CommitteePeriod],
+) -> Result<()> {
+    for committee in committees {
+        let conflicting: Option<([u8; 32], u64)> = conn
+            .query_row(
+                "SELECT committee_hash, update_counter FROM committee_periods
+                 WHERE chain_id = ?1 AND period = ?2 AND committee_hash != ?3",
+                params![chain_id, committee.period, committee.committee_hash],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some((committee_hash, update_counter)) = conflicting {
+            return Err(anyhow::anyhow!(
+                "Period {} maps to committee {} since update {}, refusing committee {}",
+                committee.period,
+                to_hex(committee_hash),
+                update_counter,
+                to_hex(committee.committee_hash)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn write_control(conn: &Connection, control: &ControlState) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO service_control (id, backend, disabled_chains)
+         VALUES (0, ?1, ?2)",
+        params![
+            control.backend,
+            serde_json::to_string(&control.disabled_chains)?
+        ],
+    )?;
+    Ok(())
+}
+
+/// A step of the schema of the state database. Migrations are embedded in the binary and
+/// applied once, in order, when a database is opened; released migrations are never edited,
+/// schema changes append a new one
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        apply: baseline_schema,
+    },
+    Migration {
+        version: 2,
+        description: "committee index",
+        apply: create_committee_periods_table,
+    },
+    Migration {
+        version: 3,
+        description: "backend and recursive vk of every state row",
+        apply: bind_state_rows,
+    },
+    Migration {
+        version: 4,
+        description: "operator annotations",
+        apply: create_annotations_table,
+    },
+    Migration {
+        version: 5,
+        description: "publication holds",
+        apply: create_publication_holds_table,
+    },
+    Migration {
+        version: 6,
+        description: "canonical update ids",
+        apply: add_update_ids,
+    },
+    Migration {
+        version: 7,
+        description: "state-diff checks",
+        apply: create_state_diff_checks_table,
+    },
+];
+
+/// Applies the migrations a database lacks. Databases of releases before versioning are at
+/// version 0, the baseline brings them to the schema they were last created with
+fn migrate(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    let latest = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    let current = schema_version(conn)?;
+    if current > latest {
+        return Err(anyhow::anyhow!(
+            "The state database has schema version {}, this release only knows version {}. \
+             Upgrade the service or restore a backup of the database",
+            current,
+            latest
+        ));
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+    {
+        // Another process opening the database may have applied it meanwhile
+        let tx = rusqlite::Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+        if schema_version(&tx)? >= migration.version {
+            continue;
+        }
+        (migration.apply)(&tx).context(format!(
+            "Failed to migrate the state database to version {} ({})",
+            migration.version, migration.description
+        ))?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, description, applied_at)
+             VALUES (?1, ?2, strftime('%s', 'now'))",
+            params![migration.version, migration.description],
+        )?;
+        tx.commit()?;
+        tracing::info!(
+            "🗃️  Migrated the state database to version {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+    Ok(())
+}
+
+/// The version of the last migration applied to a database
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// The tables of the last release before versioned migrations. Moves the unnamespaced state
+/// and history of single-chain databases to the default chain
+fn baseline_schema(conn: &Connection) -> Result<()> {
+    // Databases created before multi-chain support hold a single unnamespaced
+    // state row, move it to the default chain
+    if table_exists(conn, "service_state")? && !has_column(conn, "service_state", "chain_id")? {
+        if !has_column(conn, "service_state", "generated_at")? {
+            conn.execute(
+                "ALTER TABLE service_state ADD COLUMN generated_at INTEGER",
+                [],
+            )?;
+        }
+        conn.execute(
+            "ALTER TABLE service_state RENAME TO service_state_legacy",
+            [],
+        )?;
+        create_state_table(conn)?;
+        conn.execute(
+            "INSERT INTO service_state (
+                chain_id, most_recent_recursive_proof, most_recent_wrapper_proof,
+                trusted_slot, trusted_height, trusted_root, update_counter, generated_at
+            )
+            SELECT ?1, most_recent_recursive_proof, most_recent_wrapper_proof,
+                trusted_slot, trusted_height, trusted_root, update_counter, generated_at
+            FROM service_state_legacy",
+            params![DEFAULT_CHAIN_ID],
+        )?;
+        conn.execute("DROP TABLE service_state_legacy", [])?;
+    }
+    if table_exists(conn, "update_history")? && !has_column(conn, "update_history", "chain_id")? {
+        conn.execute(
+            "ALTER TABLE update_history RENAME TO update_history_legacy",
+            [],
+        )?;
+        create_history_table(conn)?;
+        conn.execute(
+            "INSERT INTO update_history
+            SELECT ?1, update_counter, slot, height, root, proof_hash, prev_hash, hash,
+                committed_at
+            FROM update_history_legacy",
+            params![DEFAULT_CHAIN_ID],
+        )?;
+        conn.execute("DROP TABLE update_history_legacy", [])?;
+    }
+
+    create_state_table(conn)?;
+    create_history_table(conn)?;
+    create_base_inputs_table(conn)?;
+    create_round_metadata_table(conn)?;
+    create_pending_rounds_table(conn)?;
+    create_lineage_table(conn)?;
+    create_shadow_rounds_table(conn)?;
+    create_slo_table(conn)?;
+    create_control_table(conn)?;
+    Ok(())
+}
+
+/// Records the backend and recursive circuit vk a state row was proven with. The backend of
+/// existing rows follows from their chain id, their vk is recorded by the next start
+fn bind_state_rows(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE service_state ADD COLUMN mode TEXT", [])?;
+    conn.execute("ALTER TABLE service_state ADD COLUMN recursive_vk TEXT", [])?;
+    conn.execute(
+        "UPDATE service_state SET mode = CASE
+            WHEN chain_id = ?1 THEN (SELECT backend FROM service_control WHERE id = 0)
+            WHEN chain_id LIKE 'parked:%' THEN substr(chain_id, 8)
+            WHEN chain_id IN ('opstack', 'storage', 'ics23') THEN upper(chain_id)
+            ELSE 'TENDERMINT'
+        END",
+        params![DEFAULT_CHAIN_ID],
+    )?;
+    Ok(())
+}
+
+/// Records the id of every committed update, see [`update_id`]. The backend of existing
+/// records is the one of their state row, parked rows keep the chain id they were proven as
+fn add_update_ids(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE update_history ADD COLUMN update_id TEXT", [])?;
+    conn.execute(
+        "UPDATE update_history SET update_id =
+            lower(IFNULL(
+                (SELECT mode FROM service_state
+                 WHERE service_state.chain_id = update_history.chain_id),
+                'unknown'
+            ))
+            || ':' || CASE WHEN chain_id LIKE 'parked:%' THEN ?1 ELSE chain_id END
+            || ':' || update_counter",
+        params![DEFAULT_CHAIN_ID],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS update_history_update_id ON update_history (update_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn create_state_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS service_state (
+            chain_id TEXT PRIMARY KEY,
+            most_recent_recursive_proof BLOB,
+            most_recent_wrapper_proof BLOB,
+            trusted_slot INTEGER NOT NULL,
+            trusted_height INTEGER NOT NULL,
+            trusted_root BLOB NOT NULL,
+            update_counter INTEGER NOT NULL,
+            generated_at INTEGER
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Hash-chained metadata of every committed update
+fn create_history_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS update_history (
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            slot INTEGER NOT NULL,
+            height INTEGER NOT NULL,
+            root BLOB NOT NULL,
+            proof_hash BLOB NOT NULL,
+            prev_hash BLOB NOT NULL,
+            hash BLOB NOT NULL,
+            committed_at INTEGER,
+            PRIMARY KEY (chain_id, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Gzip compressed inputs of the base proof of every committed update, for audits
+fn create_base_inputs_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS base_inputs (
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            inputs BLOB NOT NULL,
+            PRIMARY KEY (chain_id, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// What each committed round ran against, JSON encoded [`RoundMetadata`]
+fn create_round_metadata_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS round_metadata (
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            metadata TEXT NOT NULL,
+            PRIMARY KEY (chain_id, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Rounds awaiting their wrapper proof, see [`PendingRound`]
+fn create_pending_rounds_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pending_rounds (
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            parent_height INTEGER NOT NULL,
+            parent_root BLOB NOT NULL,
+            base_outputs BLOB NOT NULL,
+            recursive_proof BLOB NOT NULL,
+            recursion_inputs_sha256 TEXT NOT NULL,
+            base_ms INTEGER NOT NULL,
+            recursive_ms INTEGER NOT NULL,
+            PRIMARY KEY (chain_id, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Restarts of the proof chains from their wrapper proofs, see [`LineageRecord`]
+fn create_lineage_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS lineage (
+            chain_id TEXT NOT NULL,
+            anchor_update INTEGER NOT NULL,
+            anchor_height INTEGER NOT NULL,
+            anchor_root BLOB NOT NULL,
+            anchor_proof_hash BLOB NOT NULL,
+            previous_vk TEXT NOT NULL,
+            vk TEXT NOT NULL,
+            restarted_at INTEGER NOT NULL,
+            PRIMARY KEY (chain_id, anchor_update)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Notes of the operators on proof chains and their updates
+fn create_annotations_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER,
+            text TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS annotations_by_update
+         ON annotations (chain_id, update_counter)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// The proven update of every proof chain held back from publication
+fn create_publication_holds_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS publication_holds (
+            chain_id TEXT PRIMARY KEY,
+            update_counter INTEGER NOT NULL,
+            height INTEGER NOT NULL,
+            root BLOB NOT NULL,
+            proven_at INTEGER NOT NULL,
+            publish_at INTEGER NOT NULL,
+            aborted INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// The checks of the committed execution roots against the canonical chain, keyed by the
+/// update whose root was checked, see [`crate::state_diff`]
+fn create_state_diff_checks_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS state_diff_checks (
+            chain_id TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            orphaned INTEGER NOT NULL,
+            check_json TEXT NOT NULL,
+            checked_at INTEGER NOT NULL,
+            PRIMARY KEY (chain_id, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// The committee index, the sync committee every beacon period maps to
+fn create_committee_periods_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS committee_periods (
+            chain_id TEXT NOT NULL,
+            period INTEGER NOT NULL,
+            committee_hash BLOB NOT NULL,
+            update_counter INTEGER NOT NULL,
+            PRIMARY KEY (chain_id, period)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Outputs of the candidate circuits of shadow chains, see [`ShadowRound`]
+fn create_shadow_rounds_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS shadow_rounds (
+            chain_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            update_counter INTEGER NOT NULL,
+            production_public_values BLOB NOT NULL,
+            shadow_public_values BLOB,
+            cycles INTEGER,
+            error TEXT,
+            elapsed_ms INTEGER NOT NULL,
+            recorded_at INTEGER NOT NULL,
+            PRIMARY KEY (chain_id, name, update_counter)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Error budget accounting of the freshness SLO, kept across restarts
+fn create_slo_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS slo_state (
+            chain_id TEXT PRIMARY KEY,
+            tracking_since INTEGER NOT NULL,
+            alerted INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// The backend and disabled chains set through the admin API, a single row
+fn create_control_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS service_control (
+            id INTEGER PRIMARY KEY CHECK (id = 0),
+            backend TEXT NOT NULL,
+            disabled_chains TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    Ok(conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?
+        .exists(params![table])?)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(conn
+        .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+        .exists(params![table, column])?)
+}
@@ -0,0 +1,277 @@
+// State-diff verification of the Helios proof chain: an analytics task checking the committed
+// execution roots against the canonical chain of an execution RPC.
+//
+// For every two consecutive committed updates, the headers of the blocks between their
+// execution heights are fetched from `ETHEREUM_URL`. The segment must link block by block, and
+// the state roots of its endpoints must be the committed roots. A committed root that is not
+// the state root of the canonical block at its height is flagged as orphaned: logged, recorded
+// and alerted to `STATE_DIFF_ALERT_URL`. The checks never hold up the prover.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    control::active_backend,
+    encoding::to_hex,
+    preprocessor::opstack::{ExecutionHeader, ExecutionRpcClient},
+    state::{DEFAULT_CHAIN_ID, DbPool, StateManager, UpdateRecord},
+    webhooks::{WebhookConfig, deliver},
+};
+
+/// Seconds between two checks of the newly committed updates
+const CHECK_INTERVAL: u64 = 60;
+
+/// Updates checked per round
+const BATCH_SIZE: u64 = 100;
+
+/// The state-diff verification of `STATE_DIFF_VERIFICATION`
+#[derive(Debug, Clone)]
+pub struct StateDiffConfig {
+    pub execution_rpc: String,
+    /// Longest segment fetched block by block, only the endpoints of longer segments are
+    /// checked
+    pub max_blocks: u64,
+    /// Receives a POST for every orphaned root
+    pub alert_url: Option<String>,
+}
+
+impl StateDiffConfig {
+    /// Reads `STATE_DIFF_VERIFICATION`, `STATE_DIFF_MAX_BLOCKS` (defaults to 1024),
+    /// `STATE_DIFF_ALERT_URL` and `ETHEREUM_URL`. Returns `None` unless the verification is
+    /// enabled
+    pub fn from_env() -> Result<Option<Self>> {
+        if !std::env::var("STATE_DIFF_VERIFICATION").is_ok_and(|enabled| enabled == "true") {
+            return Ok(None);
+        }
+        let execution_rpc = std::env::var("ETHEREUM_URL")
+            .context("ETHEREUM_URL must be set to verify the committed roots")?;
+        let max_blocks = match std::env::var("STATE_DIFF_MAX_BLOCKS") {
+            Ok(blocks) => blocks
+                .parse()
+                .context(format!("Invalid STATE_DIFF_MAX_BLOCKS {}", blocks))?,
+            Err(_) => 1024,
+        };
+        Ok(Some(Self {
+            execution_rpc,
+            max_blocks,
+            alert_url: std::env::var("STATE_DIFF_ALERT_URL").ok(),
+        }))
+    }
+}
+
+/// How a committed root compares to the canonical chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    /// The root is the state root of the canonical block, reached from the previous
+    /// committed block by a linked segment unless the segment was too long to fetch
+    Consistent { linked: bool },
+    /// The root is not the state root of the canonical block at its height
+    Orphaned { canonical_root: String },
+}
+
+/// The check of the root committed by an update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiffCheck {
+    pub update_counter: u64,
+    pub update_id: String,
+    /// Execution height of the previous committed update, the start of the segment
+    pub from_height: Option<u64>,
+    pub height: u64,
+    pub root: String,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+impl StateDiffCheck {
+    pub fn is_orphaned(&self) -> bool {
+        matches!(self.verdict, Verdict::Orphaned { .. })
+    }
+}
+
+/// Checks the root committed by `to` against the canonical headers from the height of `from`
+/// to the height of `to`, in height order. Only the endpoints may be given for a segment too
+/// long to fetch. Fails if the given headers don't link, the RPC changed its head meanwhile
+pub fn verify_segment(
+    from: Option<&UpdateRecord>,
+    to: &UpdateRecord,
+    headers: &[ExecutionHeader],
+) -> Result<StateDiffCheck> {
+    let endpoint = headers
+        .iter()
+        .find(|header| header.number == to.height)
+        .context(format!("Missing the header of block {}", to.height))?;
+    let start = from.map_or(to.height, |from| from.height.min(to.height));
+    let contiguous = headers.len() as u64 == to.height.saturating_sub(start) + 1;
+    if contiguous {
+        for (parent, child) in headers.iter().zip(headers.iter().skip(1)) {
+            if child.parent_hash != parent.hash {
+                return Err(anyhow::anyhow!(
+                    "Block {} doesn't extend block {}",
+                    child.number,
+                    parent.number
+                ));
+            }
+        }
+    }
+
+    // The previous root was checked as the endpoint of its own segment
+    let verdict = if endpoint.state_root == to.root {
+        Verdict::Consistent { linked: contiguous }
+    } else {
+        Verdict::Orphaned {
+            canonical_root: to_hex(endpoint.state_root),
+        }
+    };
+    Ok(StateDiffCheck {
+        update_counter: to.update_counter,
+        update_id: to.update_id.clone(),
+        from_height: from.map(|from| from.height),
+        height: to.height,
+        root: to_hex(to.root),
+        verdict,
+    })
+}
+
+/// Fetches the canonical headers of the segment from `from` to `to`, the endpoints only if it
+/// is longer than `max_blocks`
+async fn fetch_segment(
+    rpc: &ExecutionRpcClient,
+    from: Option<&UpdateRecord>,
+    to: &UpdateRecord,
+    max_blocks: u64,
+) -> Result<Vec<ExecutionHeader>> {
+    let start = from.map_or(to.height, |from| from.height.min(to.height));
+    let heights: Vec<u64> = if to.height - start < max_blocks {
+        (start..=to.height).collect()
+    } else {
+        vec![start, to.height]
+    };
+    let mut headers = Vec::with_capacity(heights.len());
+    for height in heights {
+        headers.push(rpc.header(height).await?);
+    }
+    Ok(headers)
+}
+
+/// Checks the roots of the updates committed since the last check into `checks`
+async fn check_new_updates(
+    state_manager: &StateManager,
+    rpc: &ExecutionRpcClient,
+    config: &StateDiffConfig,
+    checks: &mut Vec<StateDiffCheck>,
+) -> Result<()> {
+    // The last checked update starts the next segment
+    let last = state_manager.last_state_diff_check()?;
+    let records = state_manager.load_history(last.unwrap_or(0), BATCH_SIZE)?;
+    for (i, to) in records.iter().enumerate() {
+        let from = match (i, last) {
+            (0, Some(_)) => continue,
+            (0, None) => None,
+            _ => Some(&records[i - 1]),
+        };
+        let headers = fetch_segment(rpc, from, to, config.max_blocks).await?;
+        let check = verify_segment(from, to, &headers)?;
+        state_manager.save_state_diff_check(&check)?;
+        checks.push(check);
+    }
+    Ok(())
+}
+
+/// Checks the roots committed by the Helios backend as they are committed, forever. Other
+/// backends don't commit execution roots and are skipped
+pub async fn run_state_diff_verifier(db: DbPool, config: StateDiffConfig) -> Result<()> {
+    let client = crate::egress::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let alert_webhook = config.alert_url.clone().map(|url| WebhookConfig {
+        url,
+        template: serde_json::Value::Null,
+        chain_ids: None,
+        headers: BTreeMap::new(),
+    });
+    let rpc = ExecutionRpcClient::new(config.execution_rpc.clone());
+    tracing::info!(
+        "🔎 Verifying the committed roots against the canonical chain, segments up to {} blocks",
+        config.max_blocks
+    );
+
+    loop {
+        let state_manager = StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?;
+        if active_backend(&state_manager)? == "HELIOS" {
+            let mut checks = Vec::new();
+            if let Err(e) = check_new_updates(&state_manager, &rpc, &config, &mut checks).await {
+                // Retried from the last checked update
+                tracing::warn!("⚠️  State-diff verification failed: {:#}", e);
+            }
+            for check in checks.iter().filter(|check| check.is_orphaned()) {
+                tracing::error!(
+                    "🚨 Committed root {} of update {} at height {} is orphaned: {:?}",
+                    check.root,
+                    check.update_id,
+                    check.height,
+                    check.verdict
+                );
+                if let Some(webhook) = &alert_webhook {
+                    let payload = json!({ "chain_id": DEFAULT_CHAIN_ID, "state_diff": check });
+                    deliver(&client, webhook, &payload).await;
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(number: u64, parent: u8, state_root: u8) -> ExecutionHeader {
+        ExecutionHeader {
+            number,
+            hash: [number as u8; 32],
+            parent_hash: [parent; 32],
+            state_root: [state_root; 32],
+        }
+    }
+
+    fn record(update_counter: u64, height: u64, root: u8) -> UpdateRecord {
+        UpdateRecord {
+            update_counter,
+            update_id: crate::state::update_id("HELIOS", DEFAULT_CHAIN_ID, update_counter),
+            slot: height,
+            height,
+            root: [root; 32],
+            proof_hash: [0; 32],
+            prev_hash: [0; 32],
+            hash: [0; 32],
+            committed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_segment() {
+        let from = record(1, 10, 0xa);
+        let to = record(2, 12, 0xc);
+        let segment = [header(10, 9, 0xa), header(11, 10, 0xb), header(12, 11, 0xc)];
+        let check = verify_segment(Some(&from), &to, &segment).unwrap();
+        assert_eq!(check.verdict, Verdict::Consistent { linked: true });
+        assert_eq!(check.from_height, Some(10));
+
+        // The canonical block at the committed height has another state root
+        let reorged = [header(10, 9, 0xa), header(11, 10, 0xb), header(12, 11, 0xd)];
+        let check = verify_segment(Some(&from), &to, &reorged).unwrap();
+        assert!(check.is_orphaned());
+
+        // Headers that don't link are refetched rather than flagged
+        let unlinked = [header(10, 9, 0xa), header(11, 7, 0xb), header(12, 11, 0xc)];
+        assert!(verify_segment(Some(&from), &to, &unlinked).is_err());
+
+        // Only the endpoints of long segments are compared
+        let endpoints = [header(10, 9, 0xa), header(12, 11, 0xc)];
+        let check = verify_segment(Some(&from), &to, &endpoints).unwrap();
+        assert_eq!(check.verdict, Verdict::Consistent { linked: false });
+    }
+}
@@ -0,0 +1,174 @@
+// Storage slots of an Ethereum account anchored to the Helios proof chain.
+//
+// After every Helios update, the storage stage fetches the account and storage proofs of the
+// configured slots at the proven execution state root and proves them with the storage proof
+// circuit. The proofs are committed to their own state row, `STORAGE_CHAIN_ID`.
+
+use alloy_primitives::U256;
+use anyhow::{Context, Result};
+use sp1_sdk::SP1ProvingKey;
+use std::time::{SystemTime, UNIX_EPOCH};
+use storage_proof_types::{StorageCircuitInputs, verify_storage_slots};
+
+use crate::{
+    archive::ProofArchive,
+    control::Stop,
+    encoding::{parse_hex_array, to_hex},
+    preprocessor::opstack::ExecutionRpcClient,
+    prover::prove_anchored,
+    relayers::Relayers,
+    slots::ChainSlots,
+    state::{DEFAULT_CHAIN_ID, DbPool, StateManager},
+    webhooks::Webhooks,
+};
+
+/// The namespace of the state row holding the storage proofs
+pub const STORAGE_CHAIN_ID: &str = "storage";
+
+/// Seconds between two checks for a new Helios update
+const POLL_INTERVAL: u64 = 60;
+
+/// The account and storage slots that are proven
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub address: [u8; 20],
+    pub slots: Vec<[u8; 32]>,
+    /// Execution RPC the storage proofs are fetched from
+    pub execution_rpc: String,
+}
+
+impl StorageConfig {
+    /// Reads `STORAGE_PROOF_ADDRESS` and the comma separated `STORAGE_PROOF_SLOTS` together
+    /// with `ETHEREUM_URL`. Returns `None` if no account is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(address) = std::env::var("STORAGE_PROOF_ADDRESS") else {
+            return Ok(None);
+        };
+        let address = parse_hex_array(&address)
+            .context(format!("Invalid STORAGE_PROOF_ADDRESS {}", address))?;
+        let slots = std::env::var("STORAGE_PROOF_SLOTS")
+            .context("STORAGE_PROOF_SLOTS must be set to prove the storage of an account")?
+            .split(',')
+            .map(|slot| {
+                let slot = slot.trim();
+                slot.parse::<U256>()
+                    .map(|slot| slot.to_be_bytes())
+                    .context(format!("Invalid storage slot {}", slot))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let execution_rpc = std::env::var("ETHEREUM_URL")
+            .context("ETHEREUM_URL must be set to prove the storage of an account")?;
+        Ok(Some(Self {
+            address,
+            slots,
+            execution_rpc,
+        }))
+    }
+}
+
+/// Proves the configured storage slots at every new Helios update, until `stop` is requested
+pub async fn run_storage_loop(
+    db: DbPool,
+    config: StorageConfig,
+    elf: Vec<u8>,
+    slots: ChainSlots,
+    mut stop: Stop,
+) -> Result<()> {
+    let rpc = ExecutionRpcClient::new(config.execution_rpc.clone());
+    let mut pk: Option<SP1ProvingKey> = None;
+    let webhooks = Webhooks::from_env()?;
+    let relayers = Relayers::from_env()?;
+    let archive = ProofArchive::from_env()?;
+    tracing::info!("🚀 Starting storage proof service loop...");
+
+    loop {
+        if stop.requested() {
+            tracing::info!("⏸️  Stopping the storage proof loop");
+            return Ok(());
+        }
+        let helios_state = StateManager::from_pool(&db, DEFAULT_CHAIN_ID)?.load_state()?;
+        let Some((helios_proof, height, state_root)) = helios_state.and_then(|state| {
+            let proof = state.most_recent_wrapper_proof?;
+            Some((proof, state.trusted_height, state.trusted_root))
+        }) else {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        };
+
+        let state_manager = StateManager::from_pool(&db, STORAGE_CHAIN_ID)?;
+        let mut service_state = match state_manager.load_state()? {
+            Some(state) => state,
+            None => state_manager.initialize_state(0, 0)?,
+        };
+        if height <= service_state.trusted_height {
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        // Fetch the storage proofs at the proven block
+        tracing::info!(
+            "💾 Fetching storage proofs of {} at height {}...",
+            to_hex(config.address),
+            height
+        );
+        let (account, storage) = match rpc.get_proof(&config.address, &config.slots, height).await {
+            Ok(proofs) => proofs,
+            Err(e) => {
+                tracing::warn!("⚠️  Failed to fetch storage proofs: {}", e);
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        // Check the proofs before proving, a mismatch means the RPC serves another chain
+        if let Err(e) = verify_storage_slots(&state_root, &account, &storage) {
+            tracing::warn!(
+                "⚠️  Storage proofs do not verify against the proven state root: {:?}",
+                e
+            );
+            stop.sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        let storage_root = account.storage_root;
+        let inputs = StorageCircuitInputs {
+            helios_proof: helios_proof.bytes(),
+            helios_public_values: helios_proof.public_values.to_vec(),
+            account,
+            storage,
+        };
+        tracing::info!("⚡ Generating storage proof at height {}...", height);
+        let proof = match prove_anchored("Storage", &elf, &mut pk, &slots, &inputs).await {
+            Ok(proof) => proof,
+            Err(e) => {
+                tracing::error!("❌ {}", e);
+                stop.sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        service_state.trusted_slot = height;
+        service_state.trusted_height = height;
+        service_state.trusted_root = storage_root;
+        service_state.most_recent_wrapper_proof = Some(proof);
+        service_state.update_counter += 1;
+        service_state.generated_at = Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("System clock is before the unix epoch")?
+                .as_secs(),
+        );
+        let record = state_manager.commit_update("STORAGE", &service_state, None, None, &[])?;
+        tracing::info!(
+            "✅ Update {}: Storage of {} at height {} committed - Storage root: {}",
+            record.update_id,
+            to_hex(config.address),
+            height,
+            to_hex(storage_root)
+        );
+        tracing::info!("🔗 Update history hash: {}", to_hex(record.hash));
+        webhooks.notify(STORAGE_CHAIN_ID, "STORAGE", &record);
+        relayers.relay(STORAGE_CHAIN_ID, "STORAGE", &record, &service_state);
+        archive.export_latest(&state_manager, "STORAGE");
+    }
+}
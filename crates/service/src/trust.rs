@@ -0,0 +1,299 @@
+// The trust assumptions of a deployment, served by `GET /trust`.
+//
+// A consumer of the proofs trusts the checkpoint the proof chain starts from, the circuits the
+// vks pin, the SP1 proof system and the prover configuration, and the RPC endpoints the prover
+// reads heads from for liveness. The response is rendered from the configuration and state of
+// the running deployment, so it stays true when the documentation drifts from what is deployed.
+
+use anyhow::Result;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    api::VkResponse,
+    chains::tendermint_chain,
+    checkpoints::{BITCOIN_TRUSTED_HEIGHT, CheckpointManifest, GRANDPA_TRUSTED_HEIGHT},
+    egress::EgressAllowList,
+    encoding::to_hex,
+    networks::EthereumNetwork,
+    prover::{Aggregation, ProverMode, WrapperEncoding},
+    state::StateManager,
+    state_diff::StateDiffConfig,
+};
+
+/// Seconds per slot of the networks with the mainnet preset, the only ones Helios follows
+const SECONDS_PER_SLOT: u64 = 12;
+
+/// The checkpoint a proof chain starts from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedCheckpoint {
+    /// Where the checkpoint is configured, e.g. `checkpoints.json` or `HELIOS_TRUSTED_SLOT`
+    pub source: String,
+    /// Slot of the checkpoint, Helios only
+    pub slot: Option<u64>,
+    pub height: Option<u64>,
+    /// The trusted sync committee hash (Helios) or header hash (Tendermint), 0x-prefixed hex.
+    /// Absent for backends reading it from the chain when the circuits are generated
+    pub root: Option<String>,
+    /// Unix timestamp (seconds) of the checkpoint, if known without an RPC
+    pub timestamp: Option<u64>,
+    pub age_secs: Option<u64>,
+    /// Slots (Helios) or blocks the trusted head is past the checkpoint
+    pub heads_since: Option<u64>,
+    /// Times the proof chain was restarted or re-anchored, see `GET /lineage`
+    pub restarts: usize,
+}
+
+/// How the proofs are produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofSystem {
+    /// `PROVER_MODE`
+    pub prover_mode: String,
+    /// Whether the recursion and wrapper proofs are mock proofs, which prove nothing
+    pub mock_proofs: bool,
+    /// `SP1_PROVER`, unset defaults to the CPU prover
+    pub sp1_prover: Option<String>,
+    /// `RECURSION_AGGREGATION`: `groth16` or `compressed`
+    pub recursion_aggregation: String,
+    /// `WRAPPER_ENCODING`: `borsh` or `abi`
+    pub wrapper_encoding: String,
+}
+
+/// What the prover trusts its RPC endpoints with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcPolicy {
+    /// Hosts of the endpoints the latest round read its base proof inputs from. The URLs may
+    /// carry credentials and are not exposed
+    pub endpoints: Vec<String>,
+    /// Checks of the committed heads against other sources than the proven endpoints
+    pub cross_checks: Vec<String>,
+    /// Whether `EGRESS_ALLOW_LIST` restricts the hosts the service talks to
+    pub egress_restricted: bool,
+}
+
+/// The trust assumptions of a proof chain
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustResponse {
+    pub chain_id: String,
+    pub mode: String,
+    /// Absent for proof chains derived from another proof chain, which inherit its checkpoint
+    pub checkpoint: Option<TrustedCheckpoint>,
+    /// Absent until the circuits of the chain are set up by this process
+    pub vks: Option<VkResponse>,
+    pub proof_system: ProofSystem,
+    pub rpc: RpcPolicy,
+    /// The assumptions above, one sentence each
+    pub assumptions: Vec<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+/// The host of an endpoint URL, without its credentials, path and query
+fn host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "<invalid url>".to_string())
+}
+
+/// The checkpoint of the chain of `state_manager` proven by a `mode` backend
+fn checkpoint(state_manager: &StateManager, mode: &str) -> Result<Option<TrustedCheckpoint>> {
+    let mut checkpoint = match mode {
+        "HELIOS" => {
+            let network = EthereumNetwork::from_env()?;
+            let slot = network.trusted_slot()?;
+            let manifest = &CheckpointManifest::built_in().helios;
+            let from_manifest = std::env::var("HELIOS_TRUSTED_SLOT").is_err();
+            // The genesis of the network of the slot, unknown if helios doesn't know the network
+            let timestamp = network
+                .helios_config("")
+                .ok()
+                .map(|config| config.chain.genesis_time + slot * SECONDS_PER_SLOT);
+            TrustedCheckpoint {
+                source: if from_manifest {
+                    "checkpoints.json".to_string()
+                } else {
+                    "HELIOS_TRUSTED_SLOT".to_string()
+                },
+                slot: Some(slot),
+                height: None,
+                root: from_manifest.then(|| manifest.trusted_sync_committee_hash.clone()),
+                timestamp,
+                age_secs: None,
+                heads_since: None,
+                restarts: 0,
+            }
+        }
+        "TENDERMINT" => {
+            let chain = tendermint_chain(state_manager.chain_id())?;
+            TrustedCheckpoint {
+                source: if std::env::var("TENDERMINT_CHAINS_CONFIG").is_ok() {
+                    "TENDERMINT_CHAINS_CONFIG".to_string()
+                } else {
+                    "checkpoints.json".to_string()
+                },
+                slot: None,
+                height: Some(chain.trusted_height),
+                root: Some(to_hex(chain.trusted_root)),
+                timestamp: None,
+                age_secs: None,
+                heads_since: None,
+                restarts: 0,
+            }
+        }
+        "GRANDPA" | "BITCOIN" => TrustedCheckpoint {
+            source: "circuit constants".to_string(),
+            slot: None,
+            height: Some(if mode == "GRANDPA" {
+                GRANDPA_TRUSTED_HEIGHT
+            } else {
+                BITCOIN_TRUSTED_HEIGHT
+            }),
+            root: None,
+            timestamp: None,
+            age_secs: None,
+            heads_since: None,
+            restarts: 0,
+        },
+        _ => return Ok(None),
+    };
+
+    checkpoint.age_secs = checkpoint
+        .timestamp
+        .map(|timestamp| now().saturating_sub(timestamp));
+    if let Some(state) = state_manager.load_state()? {
+        checkpoint.heads_since = match (checkpoint.slot, checkpoint.height) {
+            (Some(slot), _) => Some(state.trusted_slot.saturating_sub(slot)),
+            (None, Some(height)) => Some(state.trusted_height.saturating_sub(height)),
+            _ => None,
+        };
+    }
+    checkpoint.restarts = state_manager.load_lineage()?.len();
+    Ok(Some(checkpoint))
+}
+
+impl TrustResponse {
+    /// The trust assumptions of the chain of `state_manager` proven by a `mode` backend
+    pub fn load(state_manager: &StateManager, mode: &str) -> Result<Self> {
+        let chain_id = state_manager.chain_id().to_string();
+        let checkpoint = checkpoint(state_manager, mode)?;
+
+        let prover_mode = ProverMode::from_env()?;
+        let proof_system = ProofSystem {
+            prover_mode: prover_mode.to_string(),
+            mock_proofs: prover_mode.mocks_circuits(),
+            sp1_prover: std::env::var("SP1_PROVER").ok(),
+            recursion_aggregation: match Aggregation::from_env()? {
+                Aggregation::Groth16 => "groth16".to_string(),
+                Aggregation::Compressed => "compressed".to_string(),
+            },
+            wrapper_encoding: WrapperEncoding::from_env()?.to_string(),
+        };
+
+        let latest_round = match state_manager.load_state()? {
+            Some(state) => state_manager.load_round_metadata(state.update_counter)?,
+            None => None,
+        };
+        let mut cross_checks = Vec::new();
+        if mode == "HELIOS" {
+            if let Some(config) = StateDiffConfig::from_env()? {
+                cross_checks.push(format!(
+                    "state_diff: committed execution roots are checked against the canonical \
+                     chain of {}",
+                    host(&config.execution_rpc)
+                ));
+            }
+        }
+        let rpc = RpcPolicy {
+            endpoints: latest_round
+                .map(|metadata| metadata.rpc_endpoints.iter().map(|url| host(url)).collect())
+                .unwrap_or_default(),
+            cross_checks,
+            egress_restricted: EgressAllowList::global()?.is_some(),
+        };
+
+        let vks = VkResponse::load(&chain_id);
+        let assumptions = assumptions(mode, checkpoint.as_ref(), vks.as_ref(), &proof_system, &rpc);
+        Ok(Self {
+            chain_id,
+            mode: mode.to_string(),
+            checkpoint,
+            vks,
+            proof_system,
+            rpc,
+            assumptions,
+        })
+    }
+}
+
+/// Renders the assumptions of a proof chain as sentences
+fn assumptions(
+    mode: &str,
+    checkpoint: Option<&TrustedCheckpoint>,
+    vks: Option<&VkResponse>,
+    proof_system: &ProofSystem,
+    rpc: &RpcPolicy,
+) -> Vec<String> {
+    let mut assumptions = Vec::new();
+    match checkpoint {
+        Some(checkpoint) => {
+            let at = match (checkpoint.slot, checkpoint.height) {
+                (Some(slot), _) => format!("slot {}", slot),
+                (None, Some(height)) => format!("height {}", height),
+                _ => "an unknown head".to_string(),
+            };
+            let root = checkpoint
+                .root
+                .as_ref()
+                .map_or(String::new(), |root| format!(" with root {}", root));
+            let age = checkpoint
+                .age_secs
+                .map_or(String::new(), |age| format!(", {} seconds old", age));
+            assumptions.push(format!(
+                "The {} checkpoint at {}{} from {} is trusted{}",
+                mode, at, root, checkpoint.source, age
+            ));
+        }
+        None => assumptions.push(format!(
+            "The {} proofs are derived from another proof chain and inherit its checkpoint",
+            mode
+        )),
+    }
+    match vks {
+        Some(vks) => assumptions.push(format!(
+            "The circuits are those of wrapper vk {} and recursive vk {}",
+            vks.wrapper_vk, vks.recursive_vk
+        )),
+        None => assumptions.push("The circuits are not set up yet".to_string()),
+    }
+    if proof_system.mock_proofs {
+        assumptions.push(format!(
+            "PROVER_MODE={} commits mock proofs, which prove nothing",
+            proof_system.prover_mode
+        ));
+    } else {
+        assumptions.push(format!(
+            "The proofs are sound under the SP1 {} proof system and its Groth16 setup",
+            sp1_sdk::SP1_CIRCUIT_VERSION
+        ));
+    }
+    let endpoints = if rpc.endpoints.is_empty() {
+        "its RPC endpoints".to_string()
+    } else {
+        rpc.endpoints.join(", ")
+    };
+    assumptions.push(format!(
+        "The heads are read from {}, which can withhold heads but not forge proven ones",
+        endpoints
+    ));
+    if rpc.cross_checks.is_empty() {
+        assumptions.push("No other source cross-checks the committed heads".to_string());
+    }
+    assumptions
+}
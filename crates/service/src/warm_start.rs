@@ -0,0 +1,247 @@
+// Warm start of a fresh deployment from the latest update of a running one.
+//
+// A new deployment proves its chain from the trusted checkpoint the circuits were generated
+// with. To join an existing proof chain instead, it is initialized from a snapshot of the
+// latest committed update of another deployment (`GET /snapshot`): its history record, its
+// wrapper proof and the recursive proof the wrapper proof verified. The snapshot is imported
+// with `warm-start --snapshot <file>` while the service is stopped, or with
+// `POST /admin/warm-start` while the chain is disabled, and only into a chain that has
+// committed nothing yet.
+//
+// The update becomes the committed head: its wrapper proof is served right away, the next
+// round verifies its recursive proof, and update counters and the history hash chain carry
+// on from it. A snapshot without a recursive proof, taken right after its proof chain was
+// restarted from a wrapper proof, is resumed like such a restart: the circuits must be
+// generated with the committed head as their trusted checkpoint. Every warm start is recorded
+// in the lineage of the chain.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{HashableKey, ProverClient, SP1ProofWithPublicValues};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    api::{HistoryRecord, committed_vk},
+    commands::wrapper_elf,
+    control::active_backend,
+    encoding::{parse_hex_array, to_hex},
+    state::{LineageRecord, ServiceState, StateManager, UpdateRecord, proof_hash, update_id},
+};
+
+/// The snapshot can't initialize the chain, the request is at fault
+#[derive(Debug)]
+pub struct InvalidSnapshot(pub String);
+
+impl std::fmt::Display for InvalidSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidSnapshot {}
+
+/// The latest committed update of a chain with its proofs, everything another deployment
+/// needs to continue its proof chain
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub chain_id: String,
+    pub mode: String,
+    pub record: HistoryRecord,
+    /// The recursive circuit vk the proof chain is bound to
+    pub recursive_vk: String,
+    pub wrapper_proof: SP1ProofWithPublicValues,
+    /// Absent if the proof chain was restarted from its wrapper proof since its last commit
+    pub recursive_proof: Option<SP1ProofWithPublicValues>,
+}
+
+impl Snapshot {
+    /// The snapshot of the latest committed update of the chain of `state_manager`, none
+    /// before its first commit
+    pub fn load(state_manager: &StateManager, mode: &str) -> Result<Option<Self>> {
+        let Some(state) = state_manager.load_state()? else {
+            return Ok(None);
+        };
+        let Some(wrapper_proof) = state.most_recent_wrapper_proof else {
+            return Ok(None);
+        };
+        let record = state_manager
+            .load_history(state.update_counter, 1)?
+            .into_iter()
+            .find(|record| record.update_counter == state.update_counter)
+            .context(format!(
+                "No history record for update {}",
+                state.update_counter
+            ))?;
+        let recursive_vk = match &state.most_recent_recursive_proof {
+            Some(proof) => committed_vk(mode, proof.public_values.as_slice()),
+            None => state_manager.bound_vk()?,
+        }
+        .context("The recursive circuit vk of the proof chain is unknown")?;
+        Ok(Some(Self {
+            chain_id: state_manager.chain_id().to_string(),
+            mode: mode.to_string(),
+            record: record.into(),
+            recursive_vk,
+            wrapper_proof,
+            recursive_proof: state.most_recent_recursive_proof,
+        }))
+    }
+}
+
+/// Imports the snapshot at `path` into the state database at `db_path`, with the service
+/// stopped
+pub fn import_file(db_path: &Path, path: &Path, verify: bool) -> Result<UpdateRecord> {
+    let snapshot =
+        std::fs::read(path).context(format!("Failed to read snapshot {}", path.display()))?;
+    let snapshot: Snapshot = serde_json::from_slice(&snapshot)
+        .context(format!("Invalid snapshot {}", path.display()))?;
+    // The backend may have been switched at runtime
+    let mode = active_backend(&StateManager::new(db_path)?)?;
+    let state_manager = StateManager::for_chain(db_path, &snapshot.chain_id)?;
+    warm_start(&state_manager, &mode, snapshot, verify)
+}
+
+/// Initializes the chain of `state_manager`, proven by the `mode` backend, from `snapshot`.
+/// With `verify` the wrapper proof is verified against the wrapper circuit built into this
+/// binary, which mock proofs don't pass. Fails with [`InvalidSnapshot`] if the snapshot
+/// doesn't match the chain or is inconsistent, and if the chain already committed an update
+pub fn warm_start(
+    state_manager: &StateManager,
+    mode: &str,
+    snapshot: Snapshot,
+    verify: bool,
+) -> Result<UpdateRecord> {
+    let chain_id = state_manager.chain_id();
+    if snapshot.chain_id != chain_id || snapshot.mode != mode {
+        return Err(InvalidSnapshot(format!(
+            "The snapshot of chain {} ({}) can't warm start chain {} ({})",
+            snapshot.chain_id, snapshot.mode, chain_id, mode
+        ))
+        .into());
+    }
+    if let Some(state) = state_manager.load_state()? {
+        if state.update_counter > 0 || state.most_recent_wrapper_proof.is_some() {
+            return Err(InvalidSnapshot(format!(
+                "Chain {} already committed update {}, delete its state to warm start it",
+                chain_id, state.update_counter
+            ))
+            .into());
+        }
+    }
+
+    let mut record = parse_record(&snapshot.record)?;
+    // Snapshots of earlier releases have no update id
+    record.update_id = update_id(mode, chain_id, record.update_counter);
+    let (height, root) = wrapper_outputs(mode, snapshot.wrapper_proof.public_values.as_slice())?;
+    if (height, root) != (record.height, record.root) {
+        return Err(InvalidSnapshot(format!(
+            "The wrapper proof commits height {} and root {}, the record height {} and root {}",
+            height,
+            to_hex(root),
+            record.height,
+            to_hex(record.root)
+        ))
+        .into());
+    }
+    if proof_hash(&snapshot.wrapper_proof) != record.proof_hash {
+        return Err(InvalidSnapshot(format!(
+            "The wrapper proof is not the proof of update {}",
+            record.update_counter
+        ))
+        .into());
+    }
+    if let Some(recursive_proof) = &snapshot.recursive_proof {
+        let vk = committed_vk(mode, recursive_proof.public_values.as_slice());
+        if vk.as_deref() != Some(snapshot.recursive_vk.as_str()) {
+            return Err(InvalidSnapshot(format!(
+                "The recursive proof is not bound to vk {}",
+                snapshot.recursive_vk
+            ))
+            .into());
+        }
+    }
+    if verify {
+        let client = ProverClient::builder().cpu().build();
+        let (_, vk) = client.setup(wrapper_elf(chain_id, mode));
+        client.verify(&snapshot.wrapper_proof, &vk).map_err(|e| {
+            InvalidSnapshot(format!(
+                "The wrapper proof does not verify against {}: {}",
+                vk.bytes32(),
+                e
+            ))
+        })?;
+    }
+
+    let state = ServiceState {
+        most_recent_recursive_proof: snapshot.recursive_proof,
+        most_recent_wrapper_proof: Some(snapshot.wrapper_proof),
+        trusted_slot: record.slot,
+        trusted_height: record.height,
+        trusted_root: record.root,
+        update_counter: record.update_counter,
+        generated_at: record.committed_at,
+    };
+    let lineage = LineageRecord {
+        anchor_update: record.update_counter,
+        anchor_height: record.height,
+        anchor_root: record.root,
+        anchor_proof_hash: record.proof_hash,
+        previous_vk: snapshot.recursive_vk.clone(),
+        vk: snapshot.recursive_vk,
+        restarted_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    state_manager.warm_start(&state, &record, &lineage, mode)?;
+    tracing::info!(
+        "🔥 Warm started chain {} from update {} at height {} (root {})",
+        chain_id,
+        record.update_counter,
+        record.height,
+        to_hex(record.root)
+    );
+    Ok(record)
+}
+
+/// Parses a history record, which must hash to its own hash
+fn parse_record(record: &HistoryRecord) -> Result<UpdateRecord> {
+    let invalid =
+        |field: &str| InvalidSnapshot(format!("Invalid {} in the snapshot record", field));
+    let record = UpdateRecord {
+        update_counter: record.update_counter,
+        update_id: record.update_id.clone(),
+        slot: record.slot,
+        height: record.height,
+        root: parse_hex_array(&record.root).map_err(|_| invalid("root"))?,
+        proof_hash: parse_hex_array(&record.proof_hash).map_err(|_| invalid("proof_hash"))?,
+        prev_hash: parse_hex_array(&record.prev_hash).map_err(|_| invalid("prev_hash"))?,
+        hash: parse_hex_array(&record.hash).map_err(|_| invalid("hash"))?,
+        committed_at: record.committed_at,
+    };
+    if record.update_counter == 0 || !record.verify(&record.prev_hash) {
+        return Err(invalid("hash").into());
+    }
+    Ok(record)
+}
+
+/// The height and root committed by the wrapper circuit of the `mode` backend
+fn wrapper_outputs(mode: &str, public_values: &[u8]) -> Result<(u64, [u8; 32])> {
+    let outputs = match mode {
+        "HELIOS" => helios_recursion_types::WrapperCircuitOutputs::decode(public_values)
+            .map(|outputs| (outputs.height, outputs.root))
+            .ok(),
+        "GRANDPA" => grandpa_recursion_types::WrapperCircuitOutputs::decode(public_values)
+            .map(|outputs| (outputs.height, outputs.root))
+            .ok(),
+        "BITCOIN" => bitcoin_recursion_types::WrapperCircuitOutputs::decode(public_values)
+            .map(|outputs| (outputs.height, outputs.root))
+            .ok(),
+        _ => tendermint_recursion_types::WrapperCircuitOutputs::decode(public_values)
+            .map(|outputs| (outputs.height, outputs.root))
+            .ok(),
+    };
+    outputs.ok_or_else(|| {
+        InvalidSnapshot(format!("The public values are no {} wrapper outputs", mode)).into()
+    })
+}
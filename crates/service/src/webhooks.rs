@@ -0,0 +1,229 @@
+// Webhooks fired on every committed update, for integrations that only need to be told
+// about new roots (serverless relayers, notification bots).
+//
+// Webhooks are configured with a JSON file referenced by `WEBHOOKS_CONFIG`. Every webhook
+// receives a POST with a JSON payload rendered from its template, deliveries run in the
+// background and never hold up the prover.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use crate::{encoding::to_hex, state::UpdateRecord};
+
+/// Deliveries are attempted this many times before the update is dropped
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A webhook and the payload it receives
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// JSON payload, `{{placeholder}}`s in strings are replaced with the values of the update
+    #[serde(default = "default_template")]
+    pub template: Value,
+    /// Only fire for updates of these chains, defaults to all chains
+    #[serde(default)]
+    pub chain_ids: Option<Vec<String>>,
+    /// Additional request headers, e.g. an authorization token
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn default_template() -> Value {
+    json!({
+        "chain_id": "{{chain_id}}",
+        "mode": "{{mode}}",
+        "update_id": "{{update_id}}",
+        "update_counter": "{{update_counter}}",
+        "height": "{{height}}",
+        "root": "{{root}}",
+        "hash": "{{hash}}",
+        "generated_at": "{{generated_at}}",
+        "proof_url": "{{proof_url}}",
+    })
+}
+
+/// The configured webhooks, cheap to clone into the prover loops
+#[derive(Debug, Clone, Default)]
+pub struct Webhooks {
+    webhooks: Arc<Vec<WebhookConfig>>,
+    /// Public base URL of the API, used to render `{{proof_url}}`
+    public_url: String,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    /// Loads the webhooks from the file referenced by `WEBHOOKS_CONFIG`, none if it is unset.
+    /// `API_PUBLIC_URL` is the base of the rendered proof URLs
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var("WEBHOOKS_CONFIG") else {
+            return Ok(Self::default());
+        };
+        let contents = std::fs::read_to_string(&path)
+            .context(format!("Failed to read webhooks config {}", path))?;
+        let webhooks: Vec<WebhookConfig> = serde_json::from_str(&contents)
+            .context(format!("Failed to parse webhooks config {}", path))?;
+        Ok(Self {
+            webhooks: Arc::new(webhooks),
+            public_url: std::env::var("API_PUBLIC_URL")
+                .unwrap_or_default()
+                .trim_end_matches('/')
+                .to_string(),
+            client: crate::egress::client_builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+        })
+    }
+
+    /// Fires the webhooks subscribed to `chain_id` for a committed update
+    pub fn notify(&self, chain_id: &str, mode: &str, record: &UpdateRecord) {
+        let values = self.values(chain_id, mode, record);
+        for webhook in self.webhooks.iter() {
+            if webhook
+                .chain_ids
+                .as_ref()
+                .is_some_and(|chain_ids| !chain_ids.iter().any(|id| id == chain_id))
+            {
+                continue;
+            }
+            let payload = render(&webhook.template, &values);
+            let webhook = webhook.clone();
+            let client = self.client.clone();
+            tokio::spawn(async move { deliver(&client, &webhook, &payload).await });
+        }
+    }
+
+    fn values(
+        &self,
+        chain_id: &str,
+        mode: &str,
+        record: &UpdateRecord,
+    ) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("chain_id", json!(chain_id)),
+            ("mode", json!(mode)),
+            ("update_counter", json!(record.update_counter)),
+            ("update_id", json!(record.update_id)),
+            ("slot", json!(record.slot)),
+            ("height", json!(record.height)),
+            ("root", json!(to_hex(record.root))),
+            ("hash", json!(to_hex(record.hash))),
+            ("generated_at", json!(record.committed_at)),
+            (
+                "proof_url",
+                json!(format!("{}/?chain_id={}", self.public_url, chain_id)),
+            ),
+        ])
+    }
+}
+
+/// Renders a template. A string that is exactly one placeholder takes the value with its
+/// JSON type, placeholders inside longer strings are replaced with their text
+fn render(template: &Value, values: &BTreeMap<&'static str, Value>) -> Value {
+    match template {
+        Value::String(text) => {
+            if let Some(value) = text
+                .strip_prefix("{{")
+                .and_then(|text| text.strip_suffix("}}"))
+                .and_then(|name| values.get(name.trim()))
+            {
+                return value.clone();
+            }
+            let mut rendered = text.clone();
+            for (name, value) in values {
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
+                rendered = rendered.replace(&format!("{{{{{}}}}}", name), &text);
+            }
+            Value::String(rendered)
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| render(item, values)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, values)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+pub(crate) async fn deliver(client: &reqwest::Client, webhook: &WebhookConfig, payload: &Value) {
+    for attempt in 1..=MAX_ATTEMPTS {
+        let mut request = client.post(&webhook.url).json(payload);
+        for (name, value) in &webhook.headers {
+            request = request.header(name, value);
+        }
+        match request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => {
+                tracing::info!("📣 Webhook {} notified", webhook.url);
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "⚠️  Webhook {} failed (attempt {}/{}): {}",
+                    webhook.url,
+                    attempt,
+                    MAX_ATTEMPTS,
+                    e
+                );
+                if attempt < MAX_ATTEMPTS {
+                    tokio::time::sleep(Duration::from_secs(5 * attempt as u64)).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let record = UpdateRecord {
+            update_counter: 3,
+            update_id: "tendermint:pion-1:3".to_string(),
+            slot: 100,
+            height: 42,
+            root: [0xab; 32],
+            proof_hash: [0; 32],
+            prev_hash: [0; 32],
+            hash: [1; 32],
+            committed_at: None,
+        };
+        let webhooks = Webhooks {
+            public_url: "https://lightwave.example".to_string(),
+            ..Default::default()
+        };
+        let values = webhooks.values("pion-1", "TENDERMINT", &record);
+        let template = json!({
+            "text": "{{chain_id}} reached height {{height}}",
+            "height": "{{ height }}",
+            "root": "{{root}}",
+            "links": ["{{proof_url}}"],
+            "at": "{{generated_at}}",
+            "static": true,
+        });
+        assert_eq!(
+            render(&template, &values),
+            json!({
+                "text": "pion-1 reached height 42",
+                "height": 42,
+                "root": format!("0x{}", "ab".repeat(32)),
+                "links": ["https://lightwave.example/?chain_id=pion-1"],
+                "at": null,
+                "static": true,
+            })
+        );
+    }
+}
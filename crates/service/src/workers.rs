@@ -0,0 +1,316 @@
+// Proving on remote prover workers.
+//
+// A coordinator (`service run` with `PROVER_WORKERS` set) keeps running the preprocessors and
+// the proof chains, but instead of proving locally it sends the program inputs of every proof
+// to a worker (`service worker`) that proves them on its own hardware and returns the proof.
+// Adding workers scales proof throughput horizontally: each worker is a proving slot, and a
+// proof goes to the worker with the fewest proofs in flight.
+//
+// Workers serve HTTP on `API_BIND_ADDRESS`/`API_PORT`, guarded by `WORKER_AUTH_TOKEN`:
+// - `POST /prove`: JSON `{ "elf_sha256", "stdin", "compressed" }`, returns the Groth16 proof
+//   (a compressed proof if `compressed` is set) as JSON, or `404` if the worker doesn't have
+//   the ELF yet
+// - `PUT /elfs/{sha256}`: uploads an ELF, the worker sets up and keeps its proving key
+// - `GET /health`: whether the worker is proving and the number of ELFs it holds
+
+use anyhow::{Context, Result, anyhow};
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, State},
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{ProverClient, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    gpu::{LocalProver, cleanup_gpu_containers},
+    middleware::require_bearer_token,
+    rounds::sha256_hex,
+    server,
+};
+
+static POOL: OnceCell<Option<WorkerPool>> = OnceCell::new();
+
+/// The body of `POST /prove`
+#[derive(Serialize, Deserialize)]
+struct ProveRequest {
+    elf_sha256: String,
+    stdin: SP1Stdin,
+    /// A compressed proof instead of a Groth16 one, for compressed recursive proofs
+    #[serde(default)]
+    compressed: bool,
+}
+
+/// The body of `GET /health`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerHealth {
+    pub proving: bool,
+    pub elfs: usize,
+}
+
+/// The prover workers of a coordinator
+pub struct WorkerPool {
+    urls: Vec<String>,
+    /// Proofs in flight per worker
+    in_flight: Mutex<Vec<usize>>,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+/// Releases a worker when its proof is done
+struct InFlight<'a> {
+    pool: &'a WorkerPool,
+    index: usize,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.pool.in_flight.lock().unwrap()[self.index] -= 1;
+    }
+}
+
+impl WorkerPool {
+    /// The workers of `PROVER_WORKERS`, comma separated URLs, `None` to prove locally
+    pub fn global() -> Result<Option<&'static Self>> {
+        Ok(POOL.get_or_try_init(Self::from_env)?.as_ref())
+    }
+
+    fn from_env() -> Result<Option<Self>> {
+        let Ok(workers) = std::env::var("PROVER_WORKERS") else {
+            return Ok(None);
+        };
+        let urls: Vec<String> = workers
+            .split(',')
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        if urls.is_empty() {
+            return Err(anyhow!("PROVER_WORKERS lists no workers"));
+        }
+        Ok(Some(Self {
+            in_flight: Mutex::new(vec![0; urls.len()]),
+            urls,
+            token: std::env::var("WORKER_AUTH_TOKEN").ok(),
+            client: crate::egress::client(),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    /// Proves `stdin` on the least busy worker, uploading the ELF of `pk` if it lacks it
+    pub async fn prove(
+        &self,
+        name: &str,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+    ) -> Result<SP1ProofWithPublicValues> {
+        self.prove_with_mode(name, pk, stdin, SP1ProofMode::Groth16)
+            .await
+    }
+
+    /// Proves `stdin` in `mode` on the least busy worker, Groth16 or compressed
+    pub async fn prove_with_mode(
+        &self,
+        name: &str,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<SP1ProofWithPublicValues> {
+        if !matches!(mode, SP1ProofMode::Groth16 | SP1ProofMode::Compressed) {
+            return Err(anyhow!("Workers don't prove {:?} proofs", mode));
+        }
+        let worker = self.acquire();
+        let url = &self.urls[worker.index];
+        let elf_sha256 = sha256_hex(&pk.elf);
+        let request = ProveRequest {
+            elf_sha256: elf_sha256.clone(),
+            stdin: stdin.clone(),
+            compressed: mode == SP1ProofMode::Compressed,
+        };
+        tracing::info!("🛰️  Sending {} proof to worker {}", name, url);
+
+        let mut response = self
+            .send(self.client.post(format!("{}/prove", url)).json(&request))
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            tracing::info!("🛰️  Uploading the {} ELF to worker {}", name, url);
+            self.send(
+                self.client
+                    .put(format!("{}/elfs/{}", url, elf_sha256))
+                    .body(pk.elf.to_vec()),
+            )
+            .await?
+            .error_for_status()
+            .context(format!("Worker {} rejected the {} ELF", url, name))?;
+            response = self
+                .send(self.client.post(format!("{}/prove", url)).json(&request))
+                .await?;
+        }
+        let response = response
+            .error_for_status()
+            .context(format!("Worker {} failed the {} proof", url, name))?;
+        let proof = response
+            .json()
+            .await
+            .context(format!("Invalid {} proof from worker {}", name, url))?;
+        tracing::info!("✅ Worker {} returned the {} proof", url, name);
+        Ok(proof)
+    }
+
+    fn acquire(&self) -> InFlight<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let index = (0..in_flight.len())
+            .min_by_key(|index| in_flight[*index])
+            .unwrap();
+        in_flight[index] += 1;
+        InFlight { pool: self, index }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        Ok(request.send().await?)
+    }
+}
+
+/// A worker's proving keys by ELF sha256, proofs are generated one at a time
+#[derive(Default)]
+struct Worker {
+    keys: Mutex<HashMap<String, SP1ProvingKey>>,
+    proving: tokio::sync::Mutex<()>,
+}
+
+/// Serves proofs to coordinators until the process is stopped
+pub async fn run_worker() -> Result<()> {
+    let worker = Arc::new(Worker::default());
+    let mut app = Router::new()
+        .route("/prove", post(prove))
+        .route("/elfs/{sha256}", put(upload_elf))
+        .route("/health", get(health))
+        .with_state(worker)
+        // ELFs and program inputs exceed the default limit
+        .layer(DefaultBodyLimit::disable());
+    match std::env::var("WORKER_AUTH_TOKEN") {
+        Ok(token) => {
+            app = app.layer(from_fn_with_state(Arc::new(token), require_bearer_token));
+        }
+        Err(_) => tracing::warn!("⚠️  WORKER_AUTH_TOKEN is unset, any client can use this worker"),
+    }
+
+    let bind = server::ApiBind::from_env()?;
+    tracing::info!("🛰️  Prover worker ready");
+    server::serve(bind, app, async {
+        tokio::signal::ctrl_c().await.ok();
+    })
+    .await?;
+    Ok(())
+}
+
+async fn upload_elf(
+    State(worker): State<Arc<Worker>>,
+    Path(sha256): Path<String>,
+    elf: Bytes,
+) -> Response {
+    if sha256_hex(&elf) != sha256 {
+        return (StatusCode::BAD_REQUEST, "ELF does not match its sha256").into_response();
+    }
+    if worker.keys.lock().unwrap().contains_key(&sha256) {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+    let setup = tokio::task::spawn_blocking(move || {
+        let (pk, _) = ProverClient::from_env().setup(&elf);
+        pk
+    });
+    match setup.await {
+        Ok(pk) => {
+            tracing::info!("🔑 Set up the proving key of ELF {}", sha256);
+            worker.keys.lock().unwrap().insert(sha256, pk);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to set up ELF {}: {}", sha256, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn prove(State(worker): State<Arc<Worker>>, Json(request): Json<ProveRequest>) -> Response {
+    let Some(pk) = worker
+        .keys
+        .lock()
+        .unwrap()
+        .get(&request.elf_sha256)
+        .cloned()
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let _proving = worker.proving.lock().await;
+    if let Err(e) = cleanup_gpu_containers().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+    tracing::info!("⚡ Proving ELF {}", request.elf_sha256);
+    let stdin = request.stdin;
+    let mode = match request.compressed {
+        true => SP1ProofMode::Compressed,
+        false => SP1ProofMode::Groth16,
+    };
+    let proof =
+        tokio::task::spawn_blocking(move || LocalProver::new(None).prove(&pk, &stdin, mode)).await;
+    match proof {
+        Ok(Ok(proof)) => {
+            tracing::info!("✅ Proved ELF {}", request.elf_sha256);
+            Json(proof).into_response()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Proof of ELF {} failed: {}", request.elf_sha256, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Proof task of ELF {} failed: {}", request.elf_sha256, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn health(State(worker): State<Arc<Worker>>) -> Json<WorkerHealth> {
+    Json(WorkerHealth {
+        proving: worker.proving.try_lock().is_err(),
+        elfs: worker.keys.lock().unwrap().len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proofs_go_to_least_busy_worker() {
+        let pool = WorkerPool {
+            urls: vec!["http://a".to_string(), "http://b".to_string()],
+            in_flight: Mutex::new(vec![0, 0]),
+            token: None,
+            client: crate::egress::client(),
+        };
+        let first = pool.acquire();
+        let second = pool.acquire();
+        assert_ne!(first.index, second.index);
+
+        // The freed worker takes the next proof
+        let index = first.index;
+        drop(first);
+        assert_eq!(pool.acquire().index, index);
+    }
+}
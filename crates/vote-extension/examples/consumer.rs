@@ -0,0 +1,84 @@
+// A minimal oracle consumer of light client vote extensions.
+//
+// Mimics what a Cosmos SDK chain does with the payloads: every vote extension is
+// checked as in `VerifyVoteExtension`, then the verified (height, root) pairs are
+// aggregated as in `PreBlocker` and a root is accepted once more than two thirds of
+// the voting power attest to it.
+//
+// Usage: cargo run -p vote-extension --example consumer -- <wrapper_vk> <hex payload>...
+// Payloads can be fetched from the service with `GET /?format=vote_extension`.
+
+use std::collections::BTreeMap;
+use vote_extension::LightClientVoteExtension;
+
+/// `VerifyVoteExtension`: reject payloads that don't carry a valid wrapper proof
+fn verify_vote_extension(
+    wrapper_vk: &str,
+    bytes: &[u8],
+) -> Result<LightClientVoteExtension, vote_extension::Error> {
+    let extension = LightClientVoteExtension::decode(bytes)?;
+    extension.verify(wrapper_vk)?;
+    Ok(extension)
+}
+
+/// `PreBlocker`: returns the highest (height, root) attested by more than 2/3 of the power
+fn aggregate(
+    votes: &[(u64, LightClientVoteExtension)],
+    total_power: u64,
+) -> Option<(u64, [u8; 32])> {
+    let mut power_by_state: BTreeMap<(u64, [u8; 32]), u64> = BTreeMap::new();
+    for (power, extension) in votes {
+        *power_by_state
+            .entry((extension.height, extension.root))
+            .or_default() += power;
+    }
+    power_by_state
+        .into_iter()
+        .rev()
+        .find(|(_, power)| *power * 3 > total_power * 2)
+        .map(|(state, _)| state)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let wrapper_vk = args
+        .next()
+        .expect("Usage: consumer <wrapper_vk> <hex payload>...");
+
+    // Every validator has the same voting power in this example
+    let payloads: Vec<String> = args.collect();
+    let total_power = payloads.len() as u64;
+    let mut votes = Vec::new();
+    for (validator, payload) in payloads.iter().enumerate() {
+        let bytes = match hex::decode(payload.trim_start_matches("0x")) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                println!("validator {}: rejected, invalid hex: {}", validator, e);
+                continue;
+            }
+        };
+        match verify_vote_extension(&wrapper_vk, &bytes) {
+            Ok(extension) => {
+                println!(
+                    "validator {}: accepted height {} root 0x{}",
+                    validator,
+                    extension.height,
+                    hex::encode(extension.root)
+                );
+                votes.push((1, extension));
+            }
+            Err(e) => println!("validator {}: rejected, {}", validator, e),
+        }
+    }
+
+    match aggregate(&votes, total_power) {
+        Some((height, root)) => {
+            println!(
+                "oracle: trusted height {} root 0x{}",
+                height,
+                hex::encode(root)
+            )
+        }
+        None => println!("oracle: no root reached a 2/3 majority"),
+    }
+}
@@ -0,0 +1,216 @@
+// Encoding of wrapper proofs as CometBFT vote extensions.
+//
+// Validators of a Cosmos SDK chain attach the latest wrapper proof to their precommit
+// in `ExtendVote`, peers check it in `VerifyVoteExtension` and an oracle module
+// aggregates the verified (height, root) pairs in `PrepareProposal` / `PreBlocker`.
+// The payload is a small versioned borsh structure that fits the vote extension budget.
+
+#![no_std]
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sp1_verifier::Groth16Verifier;
+
+/// Version of the payload layout, bumped on incompatible changes
+pub const VOTE_EXTENSION_VERSION: u8 = 1;
+
+/// Latest format version of the wrapper outputs the public values are decoded as, the
+/// `OUTPUTS_VERSION` of the Tendermint recursion-types crate. Version 1 outputs, committed by
+/// the other backends, are decoded as well
+pub const OUTPUTS_VERSION: u8 = 2;
+
+/// Upper bound of an encoded payload. A Groth16 wrapper proof is 260 bytes and its
+/// public values 40 bytes, the bound leaves room for future fields while keeping the
+/// extensions of a large validator set small.
+pub const MAX_VOTE_EXTENSION_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The payload exceeds [`MAX_VOTE_EXTENSION_SIZE`]
+    TooLarge(usize),
+    /// The payload is not a valid borsh encoding
+    Malformed,
+    /// The payload was produced for another layout version
+    UnsupportedVersion(u8),
+    /// The public values are of an unknown format version, newer than [`OUTPUTS_VERSION`]
+    UnsupportedOutputsVersion(u8),
+    /// The public values don't commit to the advertised height and root
+    OutputsMismatch,
+    /// The Groth16 proof doesn't verify against the wrapper circuit vk
+    InvalidProof,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::TooLarge(size) => write!(
+                f,
+                "vote extension is {} bytes, the limit is {}",
+                size, MAX_VOTE_EXTENSION_SIZE
+            ),
+            Error::Malformed => write!(f, "malformed vote extension"),
+            Error::UnsupportedVersion(version) => {
+                write!(f, "unsupported vote extension version {}", version)
+            }
+            Error::UnsupportedOutputsVersion(version) => {
+                write!(f, "unsupported wrapper outputs version {}", version)
+            }
+            Error::OutputsMismatch => write!(f, "public values don't match height and root"),
+            Error::InvalidProof => write!(f, "invalid wrapper proof"),
+        }
+    }
+}
+
+/// A wrapper proof and the trusted state it commits to
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct LightClientVoteExtension {
+    pub version: u8,
+    /// The trusted execution height (Helios) or block height (Tendermint)
+    pub height: u64,
+    /// The trusted state root / app hash
+    pub root: [u8; 32],
+    /// The Groth16 wrapper proof, as returned by `SP1ProofWithPublicValues::bytes`
+    pub proof: Vec<u8>,
+    /// The public values committed by the wrapper circuit
+    pub public_values: Vec<u8>,
+}
+
+impl LightClientVoteExtension {
+    pub fn new(height: u64, root: [u8; 32], proof: Vec<u8>, public_values: Vec<u8>) -> Self {
+        Self {
+            version: VOTE_EXTENSION_VERSION,
+            height,
+            root,
+            proof,
+            public_values,
+        }
+    }
+
+    /// Encodes the payload to be returned from `ExtendVote`
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let bytes = borsh::to_vec(self).map_err(|_| Error::Malformed)?;
+        if bytes.len() > MAX_VOTE_EXTENSION_SIZE {
+            return Err(Error::TooLarge(bytes.len()));
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a payload received in `VerifyVoteExtension`, without verifying the proof
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() > MAX_VOTE_EXTENSION_SIZE {
+            return Err(Error::TooLarge(bytes.len()));
+        }
+        let extension: Self = borsh::from_slice(bytes).map_err(|_| Error::Malformed)?;
+        if extension.version != VOTE_EXTENSION_VERSION {
+            return Err(Error::UnsupportedVersion(extension.version));
+        }
+        Ok(extension)
+    }
+
+    /// Checks that the proof verifies against the wrapper circuit vk (`0x`-prefixed
+    /// bytes32 hash) and that its public values commit to the advertised height and root
+    pub fn verify(&self, wrapper_vk: &str) -> Result<(), Error> {
+        // The wrapper circuit commits its format version followed by `WrapperCircuitOutputs {
+        // height, root, previous_height, previous_root, chain_id }`, with the `transition` flag
+        // of Tendermint before the chain id from version 2
+        let (&version, outputs) = self.public_values.split_first().ok_or(Error::Malformed)?;
+        let (height, root) = match version {
+            1 => {
+                let (height, root, _previous_height, _previous_root, _chain_id): (
+                    u64,
+                    [u8; 32],
+                    u64,
+                    [u8; 32],
+                    String,
+                ) = borsh::from_slice(outputs).map_err(|_| Error::Malformed)?;
+                (height, root)
+            }
+            OUTPUTS_VERSION => {
+                let (height, root, _previous_height, _previous_root, _transition, _chain_id): (
+                    u64,
+                    [u8; 32],
+                    u64,
+                    [u8; 32],
+                    bool,
+                    String,
+                ) = borsh::from_slice(outputs).map_err(|_| Error::Malformed)?;
+                (height, root)
+            }
+            _ => return Err(Error::UnsupportedOutputsVersion(version)),
+        };
+        if (height, root) != (self.height, self.root) {
+            return Err(Error::OutputsMismatch);
+        }
+        Groth16Verifier::verify(
+            &self.proof,
+            &self.public_values,
+            wrapper_vk,
+            *sp1_verifier::GROTH16_VK_BYTES,
+        )
+        .map_err(|_| Error::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Public values of the wrapper circuit committing `outputs`
+    fn public_values(outputs: (u64, [u8; 32], u64, [u8; 32], bool, &str)) -> Vec<u8> {
+        let mut public_values = vec![OUTPUTS_VERSION];
+        public_values.extend(borsh::to_vec(&outputs).unwrap());
+        public_values
+    }
+
+    #[test]
+    fn test_vote_extension_roundtrip() {
+        let extension = LightClientVoteExtension::new(
+            31234400,
+            [7; 32],
+            vec![1; 260],
+            public_values((31234400, [7; 32], 31234300, [6; 32], false, "cosmos:pion-1")),
+        );
+        let bytes = extension.encode().unwrap();
+        assert!(bytes.len() < MAX_VOTE_EXTENSION_SIZE);
+        assert_eq!(LightClientVoteExtension::decode(&bytes).unwrap(), extension);
+
+        let mut outdated = bytes.clone();
+        outdated[0] = 0;
+        assert_eq!(
+            LightClientVoteExtension::decode(&outdated),
+            Err(Error::UnsupportedVersion(0))
+        );
+        assert_eq!(
+            LightClientVoteExtension::decode(&bytes[..bytes.len() - 1]),
+            Err(Error::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_vote_extension_rejects_mismatched_outputs() {
+        let extension = LightClientVoteExtension::new(
+            1,
+            [7; 32],
+            vec![1; 260],
+            public_values((2, [7; 32], 1, [6; 32], false, "cosmos:pion-1")),
+        );
+        assert_eq!(extension.verify("0x00"), Err(Error::OutputsMismatch));
+
+        // Version 1 outputs, without the transition flag, are decoded too
+        let mut public_values = vec![1];
+        public_values
+            .extend(borsh::to_vec(&(2u64, [7u8; 32], 1u64, [6u8; 32], "eip155:1")).unwrap());
+        let helios = LightClientVoteExtension::new(1, [7; 32], vec![1; 260], public_values);
+        assert_eq!(helios.verify("0x00"), Err(Error::OutputsMismatch));
+
+        // Outputs of another format version are refused rather than misread
+        let mut outdated = extension.clone();
+        outdated.public_values[0] = OUTPUTS_VERSION + 1;
+        assert_eq!(
+            outdated.verify("0x00"),
+            Err(Error::UnsupportedOutputsVersion(OUTPUTS_VERSION + 1))
+        );
+    }
+}
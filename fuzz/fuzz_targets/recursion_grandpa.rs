@@ -0,0 +1,12 @@
+#![no_main]
+
+use grandpa_recursion_types::{OUTPUTS_VERSION, RecursionCircuitInputs, RecursionCircuitOutputs};
+use libfuzzer_sys::fuzz_target;
+use lightwave_fuzz::{check_recursion, load_elf};
+use std::sync::LazyLock;
+
+static ELF: LazyLock<Vec<u8>> = LazyLock::new(|| load_elf("grandpa-recursive-elf.bin"));
+
+fuzz_target!(|data: &[u8]| {
+    check_recursion::<RecursionCircuitInputs, RecursionCircuitOutputs>(&ELF, OUTPUTS_VERSION, data);
+});
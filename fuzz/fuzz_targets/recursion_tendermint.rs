@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lightwave_fuzz::{check_recursion, load_elf};
+use std::sync::LazyLock;
+use tendermint_recursion_types::{
+    OUTPUTS_VERSION, RecursionCircuitInputs, RecursionCircuitOutputs,
+};
+
+static ELF: LazyLock<Vec<u8>> = LazyLock::new(|| load_elf("tendermint-recursive-elf.bin"));
+
+fuzz_target!(|data: &[u8]| {
+    check_recursion::<RecursionCircuitInputs, RecursionCircuitOutputs>(&ELF, OUTPUTS_VERSION, data);
+});
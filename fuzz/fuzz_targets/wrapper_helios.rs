@@ -0,0 +1,23 @@
+#![no_main]
+
+use helios_recursion_types::{OUTPUTS_VERSION, RecursionCircuitOutputs, WrapperCircuitOutputs};
+use libfuzzer_sys::fuzz_target;
+use lightwave_fuzz::{check_wrapper, load_elf};
+use std::sync::LazyLock;
+
+static ELF: LazyLock<Vec<u8>> = LazyLock::new(|| load_elf("helios-wrapper-elf.bin"));
+
+fuzz_target!(|data: &[u8]| {
+    check_wrapper(
+        &ELF,
+        OUTPUTS_VERSION,
+        data,
+        |outputs: RecursionCircuitOutputs| WrapperCircuitOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            previous_height: outputs.previous_height,
+            previous_root: outputs.previous_root,
+            chain_id: outputs.chain_id,
+        },
+    );
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lightwave_fuzz::{check_wrapper, load_elf};
+use std::sync::LazyLock;
+use tendermint_recursion_types::{OUTPUTS_VERSION, RecursionCircuitOutputs, WrapperCircuitOutputs};
+
+static ELF: LazyLock<Vec<u8>> = LazyLock::new(|| load_elf("tendermint-wrapper-elf.bin"));
+
+fuzz_target!(|data: &[u8]| {
+    check_wrapper(
+        &ELF,
+        OUTPUTS_VERSION,
+        data,
+        |outputs: RecursionCircuitOutputs| WrapperCircuitOutputs {
+            height: outputs.height,
+            root: outputs.root,
+            previous_height: outputs.previous_height,
+            previous_root: outputs.previous_root,
+            transition: outputs.transition,
+            chain_id: outputs.chain_id,
+        },
+    );
+});
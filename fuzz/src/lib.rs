@@ -0,0 +1,95 @@
+// Shared harness of the circuit fuzz targets.
+//
+// Every target feeds mutated serialized inputs to a circuit executed in the SP1 executor.
+// Malformed inputs must make the circuit panic before it commits anything, so whenever an
+// execution succeeds the inputs must decode exactly (no truncated, reordered or trailing
+// fields) and the committed outputs must decode and match what was proven.
+
+use borsh::BorshDeserialize;
+use sp1_sdk::{CpuProver, Prover, ProverClient, SP1Stdin};
+use std::{fmt::Debug, sync::LazyLock};
+
+static CLIENT: LazyLock<CpuProver> = LazyLock::new(|| ProverClient::builder().cpu().build());
+
+/// The inputs shared by all wrapper circuits
+#[derive(BorshDeserialize)]
+struct WrapperCircuitInputs {
+    #[allow(dead_code)]
+    recursive_proof: Vec<u8>,
+    recursive_public_values: Vec<u8>,
+}
+
+/// Reads an ELF dumped by `make build-circuits` from `ELFS_OUT` (default `elfs/variable`)
+pub fn load_elf(name: &str) -> Vec<u8> {
+    let elfs_path = std::env::var("ELFS_OUT").unwrap_or_else(|_| "elfs/variable".to_string());
+    let path = format!("{}/{}", elfs_path, name);
+    std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read {}: {}, run `make build-circuits` first",
+            path, e
+        )
+    })
+}
+
+/// Decodes public values of format `version`, the `OUTPUTS_VERSION` of the recursion-types
+/// crate of the circuit, as the recursion-types crates do
+fn decode_outputs<O: BorshDeserialize>(version: u8, public_values: &[u8]) -> Option<O> {
+    match public_values.split_first() {
+        Some((&found, outputs)) if found == version => borsh::from_slice(outputs).ok(),
+        _ => None,
+    }
+}
+
+/// Executes the circuit, returns the committed public values if it didn't panic
+pub fn execute(elf: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write_slice(data);
+    CLIENT
+        .execute(elf, &stdin)
+        .run()
+        .ok()
+        .map(|(public_values, _)| public_values.to_vec())
+}
+
+/// Checks that a recursion circuit only commits outputs for well-formed inputs
+pub fn check_recursion<I: BorshDeserialize, O: BorshDeserialize>(
+    elf: &[u8],
+    version: u8,
+    data: &[u8],
+) {
+    let Some(public_values) = execute(elf, data) else {
+        return;
+    };
+    assert!(
+        borsh::from_slice::<I>(data).is_ok(),
+        "Recursion circuit accepted malformed inputs"
+    );
+    assert!(
+        decode_outputs::<O>(version, &public_values).is_some(),
+        "Recursion circuit committed malformed outputs"
+    );
+}
+
+/// Checks that a wrapper circuit only commits outputs for well-formed inputs, and that
+/// it commits the wrapper outputs `committed` derives from the recursive proof it verified
+pub fn check_wrapper<O: BorshDeserialize, W: BorshDeserialize + PartialEq + Debug>(
+    elf: &[u8],
+    version: u8,
+    data: &[u8],
+    committed: impl Fn(O) -> W,
+) {
+    let Some(public_values) = execute(elf, data) else {
+        return;
+    };
+    let inputs = borsh::from_slice::<WrapperCircuitInputs>(data)
+        .expect("Wrapper circuit accepted malformed inputs");
+    let recursive_outputs = decode_outputs::<O>(version, &inputs.recursive_public_values)
+        .expect("Wrapper circuit accepted malformed recursive outputs");
+    let outputs = decode_outputs::<W>(version, &public_values)
+        .expect("Wrapper circuit committed malformed outputs");
+    assert_eq!(
+        outputs,
+        committed(recursive_outputs),
+        "Wrapper circuit committed outputs that weren't proven"
+    );
+}